@@ -790,12 +790,16 @@ class AddFriendActivity : BaseActivity() {
                         addedTimestamp = System.currentTimeMillis(),
                         lastContactTimestamp = System.currentTimeMillis(),
                         trustLevel = Contact.TRUST_UNTRUSTED,
-                        friendshipStatus = Contact.FRIENDSHIP_CONFIRMED
+                        friendshipStatus = Contact.FRIENDSHIP_CONFIRMED,
+                        peerCapabilities = contactCard.capabilities,
+                        mlDsaPublicKeyBase64 = contactCard.mlDsaPublicKey?.let { android.util.Base64.encodeToString(it, android.util.Base64.NO_WRAP) }
                     )
 
                     val contactId = withContext(Dispatchers.IO) {
                         database.contactDao().insertContact(contact)
                     }
+                    com.securelegion.crypto.HybridSigning.registerContact(contact)
+                    com.securelegion.crypto.ContactKeyRegistry.pin(contactId, contact)
 
                     Log.i(TAG, "SUCCESS! Contact added with ID: $contactId")
 
@@ -961,9 +965,10 @@ class AddFriendActivity : BaseActivity() {
                 }
 
                 // Verify Ed25519 signature (defense-in-depth against .onion MitM)
+                var senderEd25519PublicKey: ByteArray? = null
                 if (phase1Obj.has("signature") && phase1Obj.has("ed25519_public_key")) {
                     val signature = Base64.decode(phase1Obj.getString("signature"), Base64.NO_WRAP)
-                    val senderEd25519PublicKey = Base64.decode(phase1Obj.getString("ed25519_public_key"), Base64.NO_WRAP)
+                    val signingKey = Base64.decode(phase1Obj.getString("ed25519_public_key"), Base64.NO_WRAP)
 
                     // Reconstruct unsigned JSON to verify signature
                     val unsignedJson = org.json.JSONObject().apply {
@@ -977,7 +982,7 @@ class AddFriendActivity : BaseActivity() {
                     val signatureValid = com.securelegion.crypto.RustBridge.verifySignature(
                         unsignedJson.toByteArray(Charsets.UTF_8),
                         signature,
-                        senderEd25519PublicKey
+                        signingKey
                     )
 
                     if (!signatureValid) {
@@ -987,6 +992,7 @@ class AddFriendActivity : BaseActivity() {
                         return@launch
                     }
                     Log.i(TAG, "✓ Phase 1 signature verified (Ed25519)")
+                    senderEd25519PublicKey = signingKey
                 } else {
                     Log.w(TAG, "⚠️  Phase 1 has no signature (legacy friend request)")
                 }
@@ -1016,10 +1022,28 @@ class AddFriendActivity : BaseActivity() {
 
                 Log.d(TAG, "Built own contact card for Phase 2")
 
-                // Generate Kyber ciphertext for quantum-resistant key chain initialization
+                // Prefer a PQXDH handshake against the sender's published prekeys (signed, rotated
+                // and one-time keys); fall back to encapsulating to their static Kyber key. The bundle
+                // must be signed by the Ed25519 key verified above, so no PQXDH for unsigned requests
                 var hybridSharedSecret: ByteArray? = null
+                val pqxdhSession = senderEd25519PublicKey?.let {
+                    com.securelegion.crypto.PrekeyManager.initiate(
+                        this@AddFriendActivity,
+                        senderFriendRequestOnion,
+                        senderX25519PublicKey,
+                        it
+                    )
+                }
+                val pqxdhInitBase64 = pqxdhSession?.let { (rootKey, initMessage) ->
+                    hybridSharedSecret = rootKey
+                    Base64.encodeToString(initMessage, Base64.NO_WRAP)
+                }
+
+                // Generate Kyber ciphertext for quantum-resistant key chain initialization
                 val kyberCiphertextBase64 = withContext(Dispatchers.IO) {
-                    if (senderKyberPublicKey != null && senderKyberPublicKey.any { it != 0.toByte() }) {
+                    if (pqxdhInitBase64 != null) {
+                        null
+                    } else if (senderKyberPublicKey != null && senderKyberPublicKey.any { it != 0.toByte() }) {
                         // Perform hybrid encapsulation using sender's keys
                         Log.d(TAG, "Generating hybrid Kyber ciphertext for quantum resistance")
                         val encapResult = RustBridge.hybridEncapsulate(
@@ -1044,6 +1068,9 @@ class AddFriendActivity : BaseActivity() {
                     if (kyberCiphertextBase64 != null) {
                         put("kyber_ciphertext", kyberCiphertextBase64)
                     }
+                    if (pqxdhInitBase64 != null) {
+                        put("pqxdh_init", pqxdhInitBase64)
+                    }
                     put("phase", 2)
                 }.toString()
 
@@ -1071,7 +1098,7 @@ class AddFriendActivity : BaseActivity() {
                     )
                 }
 
-                Log.d(TAG, "Encrypted Phase 2 payload: ${encryptedPhase2.size} bytes (quantum=${kyberCiphertextBase64 != null})")
+                Log.d(TAG, "Encrypted Phase 2 payload: ${encryptedPhase2.size} bytes (quantum=${kyberCiphertextBase64 != null || pqxdhInitBase64 != null})")
 
                 // Send Phase 2 to sender's friend-request.onion using dedicated channel
                 val success = withContext(Dispatchers.IO) {
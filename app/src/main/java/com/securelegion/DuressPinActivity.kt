@@ -9,6 +9,7 @@ import android.widget.Button
 import android.widget.EditText
 import androidx.appcompat.app.AppCompatActivity
 import androidx.appcompat.widget.SwitchCompat
+import com.securelegion.crypto.KeyManager
 import com.securelegion.utils.ThemedToast
 
 class DuressPinActivity : AppCompatActivity() {
@@ -20,10 +21,13 @@ class DuressPinActivity : AppCompatActivity() {
         private const val KEY_DURESS_PIN = "duress_pin"
         private const val KEY_DURESS_SALT = "duress_salt"
         private const val KEY_WIPE_PHONE = "wipe_phone_on_distress"
+        private const val KEY_DURESS_IN_UNLOCK_RECORD = "duress_in_unlock_record"
         private const val TAG = "DuressPinActivity"
 
         /**
-         * Verify if entered PIN matches stored duress PIN hash
+         * Verify if entered PIN matches a duress PIN saved before unlock records existed
+         * (KeyManager.unlockWithPassword checks it while the account has no unlock record;
+         * newer PINs live in the record itself)
          * @param context Application context
          * @param enteredPin PIN entered by user
          * @return true if PIN matches, false otherwise
@@ -54,7 +58,8 @@ class DuressPinActivity : AppCompatActivity() {
          */
         fun isDuressPinSet(context: Context): Boolean {
             val prefs = context.getSharedPreferences(PREFS_NAME, Context.MODE_PRIVATE)
-            return prefs.getString(KEY_DURESS_PIN, null) != null
+            return prefs.getBoolean(KEY_DURESS_IN_UNLOCK_RECORD, false) ||
+                prefs.getString(KEY_DURESS_PIN, null) != null
         }
 
         /**
@@ -99,9 +104,6 @@ class DuressPinActivity : AppCompatActivity() {
             }
 
             saveDuressPin(pin)
-            ThemedToast.show(this, "Duress PIN saved successfully!")
-            Log.i(TAG, "Duress PIN saved. Wipe on distress: ${wipePhoneSwitch.isChecked}")
-            finish()
         }
     }
 
@@ -115,31 +117,49 @@ class DuressPinActivity : AppCompatActivity() {
     }
 
     private fun setupSwitchListeners() {
+        // The wipe choice is sealed into the unlock record, so it applies when the PIN is saved
         wipePhoneSwitch.setOnCheckedChangeListener { _, isChecked ->
-            val prefs = getSharedPreferences(PREFS_NAME, Context.MODE_PRIVATE)
-            prefs.edit().putBoolean(KEY_WIPE_PHONE, isChecked).apply()
-            Log.i(TAG, "Wipe phone on distress: $isChecked")
+            Log.i(TAG, "Wipe phone on distress: $isChecked (applies when the duress PIN is saved)")
         }
     }
 
+    /**
+     * Store the duress PIN in KeyManager's unlock record
+     * The device password authorizes the change; the record keeps the lock screen check
+     * constant-time whichever password is entered
+     */
     private fun saveDuressPin(pin: String) {
-        val prefs = getSharedPreferences(PREFS_NAME, Context.MODE_PRIVATE)
-
-        // Hash the duress PIN using Argon2id before storing
-        // Generate random 32-byte salt
-        val salt = ByteArray(32)
-        java.security.SecureRandom().nextBytes(salt)
-
-        // Hash PIN with Argon2id (memory-hard, GPU-resistant)
-        val pinHash = com.securelegion.crypto.RustBridge.hashPassword(pin, salt)
-
-        // Store both hash and salt (salt is not secret, hash is)
-        prefs.edit()
-            .putString(KEY_DURESS_PIN, android.util.Base64.encodeToString(pinHash, android.util.Base64.NO_WRAP))
-            .putString(KEY_DURESS_SALT, android.util.Base64.encodeToString(salt, android.util.Base64.NO_WRAP))
-            .apply()
+        val passwordInput = EditText(this).apply {
+            inputType = android.text.InputType.TYPE_CLASS_TEXT or android.text.InputType.TYPE_TEXT_VARIATION_PASSWORD
+            hint = "Device password"
+        }
 
-        Log.i(TAG, "Duress PIN hash saved securely (Argon2id)")
+        android.app.AlertDialog.Builder(this)
+            .setTitle("Confirm Device Password")
+            .setMessage("Enter your device password to save the duress PIN.")
+            .setView(passwordInput)
+            .setPositiveButton("Save") { _, _ ->
+                val wipe = wipePhoneSwitch.isChecked
+                val keyManager = KeyManager.getInstance(this)
+                if (!keyManager.setDuressPassword(passwordInput.text.toString(), pin, wipe)) {
+                    ThemedToast.show(this, "Incorrect password, or the PIN matches your password")
+                    return@setPositiveButton
+                }
+
+                // Drop the pre-unlock-record PIN hash, if any
+                getSharedPreferences(PREFS_NAME, Context.MODE_PRIVATE).edit()
+                    .remove(KEY_DURESS_PIN)
+                    .remove(KEY_DURESS_SALT)
+                    .putBoolean(KEY_WIPE_PHONE, wipe)
+                    .putBoolean(KEY_DURESS_IN_UNLOCK_RECORD, true)
+                    .apply()
+
+                ThemedToast.show(this, "Duress PIN saved successfully!")
+                Log.i(TAG, "Duress PIN saved in unlock record. Wipe on distress: $wipe")
+                finish()
+            }
+            .setNegativeButton("Cancel", null)
+            .show()
     }
 
     private fun setupBackButton() {
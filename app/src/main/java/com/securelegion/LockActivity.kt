@@ -210,17 +210,18 @@ class LockActivity : AppCompatActivity() {
                 return@setOnClickListener
             }
 
-            // Check if entered password is the duress PIN
-            if (DuressPinActivity.verifyDuressPin(this, password)) {
-                Log.w("LockActivity", "Duress PIN detected - triggering distress protocol")
+            // The unlock record is the only check: the real password, the duress password
+            // and a wrong password take the same time
+            val keyManager = KeyManager.getInstance(this)
+            val unlock = keyManager.unlockWithPassword(password)
+            if (unlock != null && unlock.outcome != KeyManager.UnlockOutcome.REAL) {
+                Log.w("LockActivity", "Duress password detected - triggering distress protocol")
                 isProcessingDistress = true
-                handleDistressProtocol()
+                handleDuressUnlock(unlock)
                 return@setOnClickListener
             }
 
-            // Verify normal device password
-            val keyManager = KeyManager.getInstance(this)
-            if (keyManager.verifyDevicePassword(password)) {
+            if (unlock != null) {
                 Log.i("LockActivity", "Password verified")
 
                 // Reset failed attempts counter on successful login
@@ -242,11 +243,14 @@ class LockActivity : AppCompatActivity() {
                 } else {
                     Log.i("LockActivity", "Account complete, unlocking app")
 
-                    // Offer biometric enrollment first, then unlock app
-                    // If biometric dialog is shown, unlockApp() is called after user responds
+                    // Re-encrypt an upgraded password hash for biometric unlock, then offer
+                    // biometric enrollment, then unlock app
+                    // If a dialog is shown, unlockApp() is called after user responds
                     // If no dialog shown, unlockApp() is called immediately
-                    offerBiometricEnrollment(keyManager) {
-                        unlockApp()
+                    reencryptBiometricHash(keyManager) {
+                        offerBiometricEnrollment(keyManager) {
+                            unlockApp()
+                        }
                     }
                 }
             } else {
@@ -294,6 +298,39 @@ class LockActivity : AppCompatActivity() {
         }
     }
 
+    /**
+     * Re-encrypt the biometric copy of the password hash after it was upgraded at login
+     * The upgraded hash is only stored once the biometric copy holds it; if the user cancels,
+     * the old hash stays and the upgrade is retried at the next password login
+     * @param onComplete Callback to execute after the prompt is handled (or immediately if none is due)
+     */
+    private fun reencryptBiometricHash(keyManager: KeyManager, onComplete: () -> Unit) {
+        val upgradedHash = keyManager.getPendingPasswordHashUpgrade()
+        if (upgradedHash == null) {
+            onComplete()
+            return
+        }
+
+        biometricHelper.enableBiometric(
+            passwordHash = upgradedHash,
+            activity = this,
+            onSuccess = {
+                keyManager.commitPasswordHashUpgrade(upgradedHash)
+                upgradedHash.fill(0)
+                Log.i("LockActivity", "Biometric copy re-encrypted with upgraded password hash")
+                onComplete()
+            },
+            onError = { error ->
+                keyManager.discardPasswordHashUpgrade()
+                upgradedHash.fill(0)
+                Log.w("LockActivity", "Biometric re-encryption skipped: $error")
+                onComplete()
+            },
+            title = "Update Biometric Unlock",
+            subtitle = "Authenticate to keep fingerprint/face unlock working"
+        )
+    }
+
     /**
      * Offer biometric enrollment on first successful password login
      * @param onComplete Callback to execute after biometric dialog is handled (or immediately if not shown)
@@ -439,43 +476,41 @@ class LockActivity : AppCompatActivity() {
     }
 
     /**
-     * Handle distress protocol when duress PIN is entered
-     * Sends panic notifications and executes distress actions based on settings
+     * Handle the duress password from the unlock record
+     * Both outcomes alert distress contacts and open the empty decoy profile
+     * DECOY: block outgoing messages from the real profile
+     * WIPED: wipe the real profile's keys and data before opening the decoy
      */
-    private fun handleDistressProtocol() {
+    private fun handleDuressUnlock(unlock: KeyManager.PasswordUnlock) {
         lifecycleScope.launch {
             try {
-                Log.w("LockActivity", "Executing distress protocol")
-
-                // Send panic notifications to distress contacts
+                // The real database is still open here; alert contacts before switching
                 sendPanicNotifications()
 
-                // Check if phone should be wiped
-                val shouldWipe = DuressPinActivity.shouldWipePhoneOnDistress(this@LockActivity)
-
-                if (shouldWipe) {
-                    Log.w("LockActivity", "Wipe toggle ON - wiping all data")
-                    wipeAllData()
+                if (unlock.outcome == KeyManager.UnlockOutcome.WIPED) {
+                    Log.w("LockActivity", "Duress wipe - destroying real profile")
+                    try {
+                        wipeProfileData()
+                    } catch (e: Exception) {
+                        Log.e("LockActivity", "Failed to wipe data", e)
+                    }
                 } else {
-                    Log.w("LockActivity", "Wipe toggle OFF - enabling silent message blocking")
                     enableMessageBlocking()
                 }
 
-                // Show normal unlock screen to maintain cover
-                // (Don't show any error or indication that distress was triggered)
-                withContext(Dispatchers.Main) {
-                    ThemedToast.show(this@LockActivity, "Incorrect password")
-                    findViewById<EditText>(R.id.passwordInput).text.clear()
-                    isProcessingDistress = false
+                withContext(Dispatchers.IO) {
+                    SecureLegionDatabase.openDecoy(this@LockActivity, unlock.decoyDatabaseKey)
                 }
 
+                resetFailedAttempts()
+                hasAuthenticated = true
+                unlockApp()
             } catch (e: Exception) {
-                Log.e("LockActivity", "Failed to execute distress protocol", e)
-                withContext(Dispatchers.Main) {
-                    ThemedToast.show(this@LockActivity, "Incorrect password")
-                    findViewById<EditText>(R.id.passwordInput).text.clear()
-                    isProcessingDistress = false
-                }
+                Log.e("LockActivity", "Failed to open decoy profile", e)
+                unlock.decoyDatabaseKey.fill(0)
+                ThemedToast.show(this@LockActivity, "Incorrect password")
+                findViewById<EditText>(R.id.passwordInput).text.clear()
+                isProcessingDistress = false
             }
         }
     }
@@ -546,27 +581,7 @@ class LockActivity : AppCompatActivity() {
     private suspend fun wipeAllData() {
         withContext(Dispatchers.IO) {
             try {
-                Log.w("LockActivity", "WIPING ALL DATA (3-pass secure overwrite)")
-
-                // Stop TorService FIRST to prevent connection loops after wipe
-                withContext(Dispatchers.Main) {
-                    try {
-                        val torServiceIntent = Intent(this@LockActivity, com.securelegion.services.TorService::class.java)
-                        stopService(torServiceIntent)
-                        Log.w("LockActivity", "Stopped TorService before wipe")
-                    } catch (e: Exception) {
-                        Log.e("LockActivity", "Failed to stop TorService", e)
-                    }
-                }
-
-                // Wipe all cryptographic keys
-                val keyManager = KeyManager.getInstance(this@LockActivity)
-                keyManager.wipeAllKeys()
-
-                // Securely wipe all data (3-pass overwrite)
-                SecureWipe.wipeAllData(this@LockActivity)
-
-                Log.w("LockActivity", "All data securely wiped")
+                wipeProfileData()
 
                 // Redirect to CreateAccountActivity since no account exists anymore
                 withContext(Dispatchers.Main) {
@@ -581,6 +596,35 @@ class LockActivity : AppCompatActivity() {
         }
     }
 
+    /**
+     * Stop Tor and securely wipe the keys and data (no navigation)
+     */
+    private suspend fun wipeProfileData() {
+        withContext(Dispatchers.IO) {
+            Log.w("LockActivity", "WIPING ALL DATA (3-pass secure overwrite)")
+
+            // Stop TorService FIRST to prevent connection loops after wipe
+            withContext(Dispatchers.Main) {
+                try {
+                    val torServiceIntent = Intent(this@LockActivity, com.securelegion.services.TorService::class.java)
+                    stopService(torServiceIntent)
+                    Log.w("LockActivity", "Stopped TorService before wipe")
+                } catch (e: Exception) {
+                    Log.e("LockActivity", "Failed to stop TorService", e)
+                }
+            }
+
+            // Wipe all cryptographic keys
+            val keyManager = KeyManager.getInstance(this@LockActivity)
+            keyManager.wipeAllKeys()
+
+            // Securely wipe all data (3-pass overwrite)
+            SecureWipe.wipeAllData(this@LockActivity)
+
+            Log.w("LockActivity", "All data securely wiped")
+        }
+    }
+
     /**
      * Enable silent message blocking mode
      * Sets a flag in SharedPreferences to block all outgoing messages
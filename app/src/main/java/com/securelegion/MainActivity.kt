@@ -1178,7 +1178,10 @@ class MainActivity : BaseActivity() {
 
                 val encryptedPingBytes = android.util.Base64.decode(encryptedPingData, android.util.Base64.NO_WRAP)
                 Log.d("MainActivity", "Restoring Ping from ${encryptedPingBytes.size} bytes of encrypted data")
-                val restoredPingId = com.securelegion.crypto.RustBridge.decryptIncomingPing(encryptedPingBytes)
+                val restoredPingId = com.securelegion.crypto.RustBridge.decryptIncomingPing(
+                    encryptedPingBytes,
+                    com.securelegion.crypto.ContactKeyRegistry.expectedSender(this@MainActivity, contactId)
+                )
 
                 if (restoredPingId == null) {
                     Log.e("MainActivity", "Failed to decrypt/restore Ping")
@@ -11,6 +11,7 @@ import android.util.Log
 import androidx.lifecycle.DefaultLifecycleObserver
 import androidx.lifecycle.LifecycleOwner
 import androidx.lifecycle.ProcessLifecycleOwner
+import com.securelegion.crypto.RustBridge
 import com.securelegion.crypto.TorManager
 import IPtProxy.Controller
 import java.io.File
@@ -128,6 +129,14 @@ class SecureLegionApplication : Application() {
         })
         */
 
+        // Rust caches the wallet seed while in use; drop it whenever the app leaves the foreground
+        // (background work re-fetches it from KeyManager on demand)
+        ProcessLifecycleOwner.get().lifecycle.addObserver(object : DefaultLifecycleObserver {
+            override fun onStop(owner: LifecycleOwner) {
+                RustBridge.clearKeyHierarchy()
+            }
+        })
+
         // Track current foreground activity
         registerActivityLifecycleCallbacks(object : ActivityLifecycleCallbacks {
             override fun onActivityCreated(activity: Activity, savedInstanceState: Bundle?) {}
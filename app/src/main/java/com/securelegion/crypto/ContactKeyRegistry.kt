@@ -0,0 +1,94 @@
+package com.securelegion.crypto
+
+import android.content.Context
+import android.util.Base64
+import android.util.Log
+import com.securelegion.database.SecureLegionDatabase
+import com.securelegion.database.entities.Contact
+import kotlinx.coroutines.runBlocking
+import org.json.JSONObject
+import java.io.File
+
+/**
+ * Trust-on-first-use registry of contacts' identity keys (Rust key_registry)
+ *
+ * - Pins a contact's Ed25519/X25519 keys when the contact is added
+ * - Incoming Pings are checked against the keys pinned for the contact they were
+ *   resolved to; a key change blocks the contact until the user accepts it
+ * - Registry ids are Room contact ids
+ */
+object ContactKeyRegistry {
+    private const val TAG = "ContactKeyRegistry"
+    private const val REGISTRY_FILE = "contact_key_registry.bin"
+
+    @Volatile
+    private var initialized = false
+
+    /**
+     * Open the registry and pin the keys of contacts added before it existed
+     * Safe to call repeatedly; call before Pings are handled
+     */
+    @Synchronized
+    fun init(context: Context): Boolean {
+        if (initialized) return true
+        return try {
+            val path = File(context.filesDir, REGISTRY_FILE).absolutePath
+            if (!RustBridge.initKeyRegistry(path, true)) {
+                return false
+            }
+            initialized = true
+
+            val keyManager = KeyManager.getInstance(context)
+            val database = SecureLegionDatabase.getInstance(context, keyManager.getDatabasePassphrase())
+            val contacts = runBlocking { database.contactDao().getAllContacts() }
+            contacts.forEach { pin(it.id, it) }
+            Log.i(TAG, "Key registry ready (${contacts.size} contacts)")
+            true
+        } catch (e: Exception) {
+            Log.e(TAG, "Failed to open key registry", e)
+            false
+        }
+    }
+
+    /**
+     * Pin a contact's keys (call when the contact is added)
+     * @return False if the contact's keys differ from the ones already pinned
+     */
+    fun pin(contactId: Long, contact: Contact): Boolean {
+        return try {
+            val result = RustBridge.checkContactKeys(
+                contactId.toString(),
+                Base64.decode(contact.publicKeyBase64, Base64.NO_WRAP),
+                Base64.decode(contact.x25519PublicKeyBase64, Base64.NO_WRAP)
+            ) ?: return false
+            val status = JSONObject(result).getString("result")
+            if (status == "flagged" || status == "blocked") {
+                Log.w(TAG, "Keys for ${contact.displayName} differ from the pinned ones ($status)")
+            }
+            status != "blocked"
+        } catch (e: Exception) {
+            Log.e(TAG, "Failed to pin keys for ${contact.displayName}", e)
+            false
+        }
+    }
+
+    /**
+     * Check a Ping fresh off the listener against its contact's pinned keys
+     * @param contactId Contact resolved from its stored Ed25519 identity key
+     */
+    fun verifyPingSender(context: Context, pingId: String, contactId: Long): Boolean {
+        if (!init(context)) {
+            return false
+        }
+        return RustBridge.verifyPingSender(pingId, contactId.toString())
+    }
+
+    /**
+     * Registry id to pass to decryptIncomingPing for a Ping we already attributed to a contact
+     * (if the registry cannot be opened, Rust rejects the Ping)
+     */
+    fun expectedSender(context: Context, contactId: Long): String {
+        init(context)
+        return contactId.toString()
+    }
+}
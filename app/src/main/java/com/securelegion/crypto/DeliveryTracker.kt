@@ -0,0 +1,74 @@
+package com.securelegion.crypto
+
+import android.util.Log
+import com.securelegion.database.entities.Message
+
+/**
+ * Outgoing delivery tracking on top of the Rust ACK state machine
+ *
+ * - begin(): when a PING is sent (retries of the same message are no-ops)
+ * - accept(): before an ACK updates the database; out-of-order ACKs are rejected
+ * - MESSAGE_ACK finishes the exchange and stops tracking
+ *
+ * The Rust tracker lives in memory, so a delivery sent before a restart is re-tracked
+ * from the stages the database already records the first time one of its ACKs arrives.
+ */
+object DeliveryTracker {
+    private const val TAG = "DeliveryTracker"
+
+    // ACK type codes of RustBridge.validateAckOrdering
+    private const val ACK_PING = 0
+    private const val ACK_PONG = 1
+    private const val ACK_MESSAGE = 2
+
+    /**
+     * Start tracking a message whose PING was just sent
+     */
+    fun begin(message: Message) {
+        if (RustBridge.beginAckTracking(message.messageId, message.contactId.toString())) {
+            Log.d(TAG, "Tracking delivery ${message.messageId}")
+        }
+    }
+
+    /**
+     * Validate and record an ACK for an outgoing message
+     * @param ackType "PING_ACK", "PONG_ACK" or "MESSAGE_ACK"
+     * @return false if the ACK is out of order and must not update the message
+     */
+    fun accept(message: Message, ackType: String): Boolean {
+        val code = when (ackType) {
+            "PING_ACK" -> ACK_PING
+            "PONG_ACK" -> ACK_PONG
+            "MESSAGE_ACK" -> ACK_MESSAGE
+            else -> return true
+        }
+
+        if (RustBridge.beginAckTracking(message.messageId, message.contactId.toString())) {
+            // Not tracked yet (sent before a restart) - replay what the database has seen
+            if (message.pingDelivered) {
+                RustBridge.validateAckOrdering(message.messageId, ACK_PING)
+            }
+            if (message.pongDelivered || message.status == Message.STATUS_SENT) {
+                RustBridge.validateAckOrdering(message.messageId, ACK_PONG)
+            }
+        }
+
+        if (!RustBridge.validateAckOrdering(message.messageId, code)) {
+            Log.w(TAG, "Rejected out-of-order $ackType for ${message.messageId}")
+            return false
+        }
+        if (code == ACK_MESSAGE) {
+            RustBridge.resetAckState(message.messageId)
+        }
+        return true
+    }
+
+    /**
+     * Record the PONG for an outgoing message
+     * An authenticated PONG answers our PING, so it also stands in for a lost PING_ACK
+     */
+    fun acceptPong(message: Message): Boolean {
+        accept(message, "PING_ACK")
+        return accept(message, "PONG_ACK")
+    }
+}
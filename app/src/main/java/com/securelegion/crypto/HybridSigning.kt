@@ -0,0 +1,63 @@
+package com.securelegion.crypto
+
+import android.content.Context
+import android.util.Base64
+import android.util.Log
+import com.securelegion.database.SecureLegionDatabase
+import com.securelegion.database.entities.Contact
+import kotlinx.coroutines.runBlocking
+
+/**
+ * Hybrid (Ed25519 + ML-DSA-65) signatures on Pings, Pongs and delivery ACKs
+ *
+ * - Installs our signing keypair (derived from the wallet seed) in Rust
+ * - Registers each contact's ML-DSA key from their contact card, after which
+ *   their tokens must carry a valid ML-DSA signature
+ * - Registers each contact's capabilities, which pick the encoding tokens are signed over
+ */
+object HybridSigning {
+    private const val TAG = "HybridSigning"
+
+    /**
+     * Install our keypair and register the ML-DSA keys of all contacts
+     * Call before the Ping/Pong pollers start
+     * @return false if no wallet exists yet or the keypair could not be derived
+     */
+    fun init(context: Context): Boolean {
+        return try {
+            val keyManager = KeyManager.getInstance(context)
+            if (!keyManager.isInitialized()) {
+                return false
+            }
+            keyManager.initHybridSigning()
+
+            val database = SecureLegionDatabase.getInstance(context, keyManager.getDatabasePassphrase())
+            val contacts = runBlocking { database.contactDao().getAllContacts() }
+            val registered = contacts.count { registerContact(it) }
+            Log.i(TAG, "Hybrid signing ready ($registered/${contacts.size} contacts with ML-DSA keys)")
+            true
+        } catch (e: Exception) {
+            Log.e(TAG, "Failed to initialize hybrid signing", e)
+            false
+        }
+    }
+
+    /**
+     * Register a contact's capabilities and ML-DSA key (no-op for contacts on older builds)
+     * @return true if the contact has an ML-DSA key and it was accepted
+     */
+    fun registerContact(contact: Contact): Boolean {
+        val ed25519PublicKey = Base64.decode(contact.publicKeyBase64, Base64.NO_WRAP)
+        RustBridge.registerContactCapabilities(ed25519PublicKey, contact.peerCapabilities)
+
+        val mlDsaPublicKey = contact.mlDsaPublicKeyBase64 ?: return false
+        val accepted = RustBridge.registerContactMlDsaKey(
+            ed25519PublicKey,
+            Base64.decode(mlDsaPublicKey, Base64.NO_WRAP)
+        )
+        if (!accepted) {
+            Log.w(TAG, "ML-DSA key rejected for ${contact.displayName}")
+        }
+        return accepted
+    }
+}
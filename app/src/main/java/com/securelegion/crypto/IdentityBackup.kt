@@ -0,0 +1,90 @@
+package com.securelegion.crypto
+
+import android.content.Context
+import android.util.Log
+import org.json.JSONObject
+
+/**
+ * Passphrase-sealed identity backup (seed phrase, contacts, ratchet state)
+ *
+ * - export(): seal KeyManager's wallet seed phrase with the caller's contacts and ratchet state
+ * - restore(): open a blob; the result holds every secret as a ByteArray and must be wiped
+ *
+ * Keys are re-derived in Rust from the phrase's 64-byte BIP39 seed exactly as KeyManager
+ * derives them, so KeyManager.initializeFromSeed(restored.seedPhrase()) yields the same identity.
+ */
+object IdentityBackup {
+    private const val TAG = "IdentityBackup"
+
+    // Slots of RustBridge.importIdentityBackup (mirrors RESTORED_FIXED_SLOTS in android.rs)
+    const val SLOT_SEED_PHRASE = 0
+    const val SLOT_BIP39_SEED = 1
+    const val SLOT_SIGNING_PRIVATE = 2
+    const val SLOT_SIGNING_PUBLIC = 3
+    const val SLOT_X25519_PRIVATE = 4
+    const val SLOT_X25519_PUBLIC = 5
+    const val SLOT_KYBER_PUBLIC = 6
+    const val SLOT_KYBER_SECRET = 7
+    const val SLOT_HIDDEN_SERVICE_PRIVATE = 8
+    const val SLOT_HIDDEN_SERVICE_PUBLIC = 9
+    const val SLOT_FRIEND_REQUEST_PRIVATE = 10
+    const val SLOT_FRIEND_REQUEST_PUBLIC = 11
+    const val SLOT_VOICE_SERVICE_PRIVATE = 12
+    const val SLOT_VOICE_SERVICE_PUBLIC = 13
+    const val SLOT_METADATA = 14
+    private const val FIXED_SLOTS = 15
+
+    /**
+     * A restored backup; call wipe() as soon as the keys are stored
+     */
+    class Restored(private val slots: Array<ByteArray>) {
+        val metadata: JSONObject = JSONObject(String(slots[SLOT_METADATA], Charsets.UTF_8))
+
+        fun slot(index: Int): ByteArray = slots[index]
+
+        /** The seed phrase for KeyManager.initializeFromSeed */
+        fun seedPhrase(): String = String(slots[SLOT_SEED_PHRASE], Charsets.UTF_8)
+
+        /** Serialized ratchet state of each contact, keyed by contact ID */
+        fun ratchetStates(): Map<String, ByteArray> {
+            val ids = metadata.getJSONArray("ratchetStates")
+            return (0 until ids.length()).associate { ids.getString(it) to slots[FIXED_SLOTS + it] }
+        }
+
+        fun wipe() {
+            slots.forEach { it.fill(0) }
+        }
+    }
+
+    /**
+     * Seal the wallet identity with the given contacts and ratchet state
+     * @return the backup blob, or null if no wallet exists or sealing failed
+     */
+    fun export(context: Context, passphrase: String, contactsJson: String, ratchetStatesJson: String): ByteArray? {
+        val phrase = KeyManager.getInstance(context).getMainWalletSeedForZcash()?.toByteArray(Charsets.UTF_8)
+            ?: return null
+        return try {
+            RustBridge.exportIdentityBackup(phrase, passphrase, contactsJson, ratchetStatesJson)
+        } catch (e: Exception) {
+            Log.e(TAG, "Failed to export identity backup", e)
+            null
+        } finally {
+            phrase.fill(0)
+        }
+    }
+
+    /**
+     * Open a backup blob
+     * @return the restored identity, or null on a wrong passphrase or corrupted blob
+     */
+    fun restore(backup: ByteArray, passphrase: String): Restored? {
+        val slots = RustBridge.importIdentityBackup(backup, passphrase) ?: return null
+        return try {
+            Restored(slots)
+        } catch (e: Exception) {
+            slots.forEach { it.fill(0) }
+            Log.e(TAG, "Malformed identity backup result", e)
+            null
+        }
+    }
+}
@@ -170,6 +170,56 @@ object KeyChainManager {
         }
     }
 
+    /**
+     * Move a contact's key chain to a new session epoch after a SESSION_RESET
+     * Both chains are re-derived from the new root (same direction mapping as
+     * initializeKeyChain) and restart at sequence 0. The old receive chain stays
+     * usable until previousEpochExpiresAt for messages already in flight.
+     *
+     * @param rootKey New epoch root key (zeroized here)
+     * @param previousEpochExpiresAt Unix time (seconds) the old epoch stops decrypting
+     * @param healerState Serialized session reset state to store with the chain
+     */
+    suspend fun switchSessionEpoch(
+        context: Context,
+        contactId: Long,
+        epoch: Int,
+        rootKey: ByteArray,
+        ourMessagingOnion: String,
+        theirMessagingOnion: String,
+        previousEpochExpiresAt: Long,
+        healerState: ByteArray
+    ) {
+        withContext(Dispatchers.IO) {
+            val outgoingKey = deriveOutgoingChainKey(rootKey)
+            val incomingKey = deriveIncomingChainKey(rootKey)
+            val (sendChainKey, receiveChainKey) = if (ourMessagingOnion < theirMessagingOnion) {
+                Pair(outgoingKey, incomingKey)
+            } else {
+                Pair(incomingKey, outgoingKey)
+            }
+
+            val database = SecureLegionDatabase.getInstance(context, KeyManager.getInstance(context).getDatabasePassphrase())
+            database.contactKeyChainDao().switchSessionEpoch(
+                contactId = contactId,
+                epoch = epoch,
+                rootKeyBase64 = Base64.encodeToString(rootKey, Base64.NO_WRAP),
+                sendChainKeyBase64 = Base64.encodeToString(sendChainKey, Base64.NO_WRAP),
+                receiveChainKeyBase64 = Base64.encodeToString(receiveChainKey, Base64.NO_WRAP),
+                previousEpochExpiresAt = previousEpochExpiresAt,
+                healerStateBase64 = Base64.encodeToString(healerState, Base64.NO_WRAP),
+                timestamp = System.currentTimeMillis()
+            )
+            // Sequences restart at 0 - skipped keys from the old epoch would collide
+            database.skippedMessageKeyDao().deleteKeysForContact(contactId)
+            rootKey.fill(0)
+            outgoingKey.fill(0)
+            incomingKey.fill(0)
+
+            Log.i(TAG, "✓ Contact $contactId switched to session epoch $epoch")
+        }
+    }
+
     /**
      * Get key chain for a contact
      * Returns null if key chain doesn't exist yet
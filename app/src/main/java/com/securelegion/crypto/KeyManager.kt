@@ -8,6 +8,8 @@ import androidx.security.crypto.MasterKey
 import com.goterl.lazysodium.LazySodiumAndroid
 import com.goterl.lazysodium.SodiumAndroid
 import com.goterl.lazysodium.interfaces.Sign
+import com.securelegion.DuressPinActivity
+import com.securelegion.utils.BiometricAuthHelper
 import org.web3j.crypto.MnemonicUtils
 import java.io.File
 import java.math.BigInteger
@@ -48,6 +50,10 @@ class KeyManager private constructor(context: Context) {
         private const val HIDDEN_SERVICE_KEY_ALIAS = "${KEYSTORE_ALIAS_PREFIX}hidden_service_key"
         private const val DEVICE_PASSWORD_HASH_ALIAS = "${KEYSTORE_ALIAS_PREFIX}device_password_hash"
         private const val DEVICE_PASSWORD_SALT_ALIAS = "${KEYSTORE_ALIAS_PREFIX}device_password_salt"
+        private const val DEVICE_PASSWORD_KDF_ALIAS = "${KEYSTORE_ALIAS_PREFIX}device_password_kdf"
+        private const val KDF_TARGET_ALIAS = "${KEYSTORE_ALIAS_PREFIX}kdf_target"
+        private const val UNLOCK_RECORD_ALIAS = "${KEYSTORE_ALIAS_PREFIX}unlock_record"
+        private const val LEGACY_DECOY_KEY_ALIAS = "${KEYSTORE_ALIAS_PREFIX}legacy_decoy_key"
         private const val ZCASH_ADDRESS_ALIAS = "${KEYSTORE_ALIAS_PREFIX}zcash_address"
         private const val ZCASH_TRANSPARENT_ADDRESS_ALIAS = "${KEYSTORE_ALIAS_PREFIX}zcash_transparent_address"
 
@@ -58,6 +64,15 @@ class KeyManager private constructor(context: Context) {
         private const val CONTACT_PIN_ALIAS = "${KEYSTORE_ALIAS_PREFIX}contact_pin"
         private const val IPFS_CID_ALIAS = "${KEYSTORE_ALIAS_PREFIX}ipfs_cid"
         private const val SEED_PHRASE_ALIAS = "${KEYSTORE_ALIAS_PREFIX}wallet_main_seed"
+        private const val PREKEY_STORE_ALIAS = "${KEYSTORE_ALIAS_PREFIX}pqxdh_prekey_store"
+        private const val RECOVERY_SHARE_ALIAS_PREFIX = "${KEYSTORE_ALIAS_PREFIX}recovery_share_"
+
+        // Key hierarchy derivation paths (see Rust crypto::key_hierarchy)
+        private const val KEY_DERIVATION_VERSION_LEGACY = 0
+
+        // Argon2id calibration target (see Rust crypto::hashing::calibrate_kdf)
+        private const val KDF_TARGET_MILLIS = 500
+        private const val KDF_MEMORY_KIB = 64 * 1024
 
         init {
             // Register BouncyCastle provider for SHA3-256 support
@@ -90,6 +105,12 @@ class KeyManager private constructor(context: Context) {
 
     private val lazySodium = LazySodiumAndroid(SodiumAndroid())
 
+    /** Upgraded password hash held until the biometric copy is re-encrypted */
+    private class PendingPasswordUpgrade(val hash: ByteArray, val salt: ByteArray, val kdf: ByteArray)
+
+    @Volatile
+    private var pendingPasswordUpgrade: PendingPasswordUpgrade? = null
+
     /**
      * Initialize keys from seed phrase (12-word mnemonic)
      * This is called during account creation or restoration
@@ -99,6 +120,9 @@ class KeyManager private constructor(context: Context) {
         try {
             Log.d(TAG, "Initializing keys from seed phrase")
 
+            // Rust must re-derive its keys from the new seed
+            RustBridge.clearKeyHierarchy()
+
             // Derive wallet keys from seed phrase
             val seed = mnemonicToSeed(seedPhrase)
 
@@ -321,6 +345,16 @@ class KeyManager private constructor(context: Context) {
         return hexToBytes(seedHex)
     }
 
+    /**
+     * Key derivation path used for this identity
+     * 0 = legacy SHA-256(seed || label) scheme, 1 = HKDF v1
+     * Called via JNI when Rust builds its key hierarchy from the wallet seed
+     */
+    @Suppress("unused")
+    fun getKeyDerivationVersion(): Int {
+        return KEY_DERIVATION_VERSION_LEGACY
+    }
+
     /**
      * Get Zcash unified address (stored after Zcash SDK initialization)
      * Returns the shielded address for receiving ZEC
@@ -456,6 +490,19 @@ class KeyManager private constructor(context: Context) {
         return encryptedPrefs.contains(WALLET_SEED_ALIAS)
     }
 
+    /**
+     * Install our Ed25519 + ML-DSA-65 signing keypair in Rust (derived from the wallet seed)
+     * @return Our ML-DSA-65 public key, published in the contact card
+     */
+    fun initHybridSigning(): ByteArray? {
+        val seed = getWalletSeed()
+        return try {
+            RustBridge.initHybridSigning(seed)
+        } finally {
+            seed.fill(0)
+        }
+    }
+
     /**
      * Wipe all keys (for duress PIN or account deletion)
      * Clears EncryptedSharedPreferences which internally manages Android Keystore
@@ -467,6 +514,10 @@ class KeyManager private constructor(context: Context) {
         // Clear encrypted preferences (which manages Android Keystore internally)
         encryptedPrefs.edit { clear() }
 
+        // Drop the keys Rust derived from the old seed
+        RustBridge.clearKeyHierarchy()
+        RustBridge.clearHybridSigning()
+
         Log.i(TAG, "All keys wiped successfully")
     }
 
@@ -977,6 +1028,44 @@ class KeyManager private constructor(context: Context) {
         }
     }
 
+    // ==================== PQXDH PREKEY STORAGE ====================
+
+    /**
+     * Store the serialized PQXDH prekey store (holds prekey secrets)
+     */
+    fun storePrekeyStore(store: ByteArray) {
+        encryptedPrefs.edit(commit = true) {
+            putString(PREKEY_STORE_ALIAS, android.util.Base64.encodeToString(store, android.util.Base64.NO_WRAP))
+        }
+    }
+
+    /**
+     * Get the serialized PQXDH prekey store, or null if none has been generated yet
+     */
+    fun getPrekeyStore(): ByteArray? {
+        val encoded = encryptedPrefs.getString(PREKEY_STORE_ALIAS, null) ?: return null
+        return android.util.Base64.decode(encoded, android.util.Base64.NO_WRAP)
+    }
+
+    // ==================== SOCIAL RECOVERY SHARES ====================
+
+    /**
+     * Store the recovery share a contact deposited with us (we are their guardian)
+     */
+    fun storeRecoveryShare(contactId: Long, share: ByteArray) {
+        encryptedPrefs.edit(commit = true) {
+            putString("$RECOVERY_SHARE_ALIAS_PREFIX$contactId", android.util.Base64.encodeToString(share, android.util.Base64.NO_WRAP))
+        }
+    }
+
+    /**
+     * Get the recovery share held for a contact, or null if they deposited none
+     */
+    fun getRecoveryShare(contactId: Long): ByteArray? {
+        val encoded = encryptedPrefs.getString("$RECOVERY_SHARE_ALIAS_PREFIX$contactId", null) ?: return null
+        return android.util.Base64.decode(encoded, android.util.Base64.NO_WRAP)
+    }
+
     // ==================== SEED PHRASE STORAGE ====================
 
     /**
@@ -1029,7 +1118,8 @@ class KeyManager private constructor(context: Context) {
 
     /**
      * Set device password (stores Argon2id hash with random salt)
-     * Called during account creation
+     * Called during account creation and password changes; a new unlock record
+     * is created, so any duress password has to be set again
      */
     fun setDevicePassword(password: String) {
         if (password.isBlank()) {
@@ -1040,13 +1130,17 @@ class KeyManager private constructor(context: Context) {
         val salt = ByteArray(32)
         java.security.SecureRandom().nextBytes(salt)
 
-        // Hash password with Argon2id (memory-hard, GPU-resistant)
-        val passwordHash = RustBridge.hashPassword(password, salt)
+        // Hash password with Argon2id (memory-hard, GPU-resistant), calibrated for this device
+        val kdf = getKdfTarget()
+        val passwordHash = RustBridge.hashPasswordWithKdf(password, salt, kdf)
+        val unlockRecord = RustBridge.createUnlockRecord(password, kdf)
 
-        // Store both hash and salt in encrypted preferences
+        // Store hash, salt, KDF parameters and unlock record in encrypted preferences
         encryptedPrefs.edit {
             putString(DEVICE_PASSWORD_HASH_ALIAS, bytesToHex(passwordHash))
             putString(DEVICE_PASSWORD_SALT_ALIAS, bytesToHex(salt))
+            putString(DEVICE_PASSWORD_KDF_ALIAS, bytesToHex(kdf))
+            putString(UNLOCK_RECORD_ALIAS, bytesToHex(unlockRecord))
         }
 
         Log.i(TAG, "Device password set successfully (Argon2id)")
@@ -1057,18 +1151,35 @@ class KeyManager private constructor(context: Context) {
      * Returns true if password matches stored hash
      */
     fun verifyDevicePassword(password: String): Boolean {
+        val matches = matchesDevicePasswordHash(password)
+        if (matches) {
+            upgradeDevicePasswordHash(password)
+            ensureUnlockRecord(password)
+        }
+        return matches
+    }
+
+    /**
+     * Compare a password against the stored Argon2id hash (no rehash, no record)
+     */
+    private fun matchesDevicePasswordHash(password: String): Boolean {
         val storedHashHex = encryptedPrefs.getString(DEVICE_PASSWORD_HASH_ALIAS, null)
             ?: return false
         val storedSaltHex = encryptedPrefs.getString(DEVICE_PASSWORD_SALT_ALIAS, null)
             ?: return false
 
         try {
-            // Decode stored hash and salt
+            // Decode stored hash, salt and KDF parameters (none = legacy hashPassword)
             val storedHash = hexToBytes(storedHashHex)
             val salt = hexToBytes(storedSaltHex)
+            val kdf = encryptedPrefs.getString(DEVICE_PASSWORD_KDF_ALIAS, null)?.let { hexToBytes(it) }
 
-            // Hash provided password with same salt
-            val providedHash = RustBridge.hashPassword(password, salt)
+            // Hash provided password with same salt and parameters
+            val providedHash = if (kdf != null) {
+                RustBridge.hashPasswordWithKdf(password, salt, kdf)
+            } else {
+                RustBridge.hashPassword(password, salt)
+            }
 
             // Constant-time comparison to prevent timing attacks
             return java.security.MessageDigest.isEqual(storedHash, providedHash)
@@ -1078,6 +1189,243 @@ class KeyManager private constructor(context: Context) {
         }
     }
 
+    /**
+     * Rehash-on-login: re-derive the device password hash if it was made with
+     * weaker Argon2 parameters than this device's calibrated target
+     *
+     * With biometric unlock enabled the new hash is held until the biometric copy has been
+     * re-encrypted (see getPendingPasswordHashUpgrade / commitPasswordHashUpgrade).
+     */
+    private fun upgradeDevicePasswordHash(password: String) {
+        try {
+            val currentKdf = encryptedPrefs.getString(DEVICE_PASSWORD_KDF_ALIAS, null)?.let { hexToBytes(it) }
+            val target = getKdfTarget()
+            if (!RustBridge.kdfNeedsRehash(currentKdf, target)) {
+                return
+            }
+
+            val salt = ByteArray(32)
+            java.security.SecureRandom().nextBytes(salt)
+            val passwordHash = RustBridge.hashPasswordWithKdf(password, salt, target)
+            val upgrade = PendingPasswordUpgrade(passwordHash, salt, target)
+
+            // Biometric unlock holds an encrypted copy of the current hash; replacing it first would lock that out
+            if (BiometricAuthHelper(appContext).isBiometricEnabled()) {
+                pendingPasswordUpgrade = upgrade
+                Log.i(TAG, "Device password hash upgrade waiting for biometric re-encryption")
+                return
+            }
+
+            storePasswordUpgrade(upgrade)
+        } catch (e: Exception) {
+            Log.e(TAG, "Failed to upgrade device password hash (keeping existing hash)", e)
+        }
+    }
+
+    /**
+     * Upgraded password hash that the biometric copy must be re-encrypted with, if any
+     */
+    fun getPendingPasswordHashUpgrade(): ByteArray? = pendingPasswordUpgrade?.hash?.copyOf()
+
+    /**
+     * Store the pending upgraded hash once the biometric copy holds it
+     * @param passwordHash The hash that was re-encrypted (from getPendingPasswordHashUpgrade)
+     * @return true if the upgrade was stored
+     */
+    fun commitPasswordHashUpgrade(passwordHash: ByteArray): Boolean {
+        val upgrade = pendingPasswordUpgrade ?: return false
+        if (!java.security.MessageDigest.isEqual(upgrade.hash, passwordHash)) {
+            return false
+        }
+        pendingPasswordUpgrade = null
+        storePasswordUpgrade(upgrade)
+        return true
+    }
+
+    /**
+     * Drop the pending upgrade (biometric re-encryption cancelled; retried at next password login)
+     */
+    fun discardPasswordHashUpgrade() {
+        pendingPasswordUpgrade = null
+    }
+
+    // ==================== DURESS UNLOCK ====================
+
+    /** Which profile a lock screen password opened (mirrors duress::UnlockOutcome) */
+    enum class UnlockOutcome { REAL, DECOY, WIPED }
+
+    /**
+     * Result of unlockWithPassword
+     * @property decoyDatabaseKey Key of the decoy database (SecureLegionDatabase.openDecoy);
+     *   the real database key stays seed-derived (getDatabasePassphrase)
+     */
+    class PasswordUnlock(val outcome: UnlockOutcome, val decoyDatabaseKey: ByteArray)
+
+    /**
+     * Check a lock screen password; this is the lock screen's only password check
+     * The real password, the duress password and a wrong one cost the same. Accounts that
+     * predate the unlock record are migrated here (see unlockLegacy).
+     * On WIPED Rust has dropped its key material; the caller alerts distress contacts,
+     * then wipes the key store and data (wipeAllKeys, SecureWipe) and opens the decoy.
+     * @return the outcome, or null if the password is wrong
+     */
+    fun unlockWithPassword(password: String): PasswordUnlock? {
+        val record = encryptedPrefs.getString(UNLOCK_RECORD_ALIAS, null)?.let { hexToBytes(it) }
+            ?: return unlockLegacy(password)
+        val result = RustBridge.unlockWithPassword(record, password) ?: return null
+
+        return try {
+            val outcome = when (result[0].toInt()) {
+                0 -> UnlockOutcome.REAL
+                1 -> UnlockOutcome.DECOY
+                2 -> UnlockOutcome.WIPED
+                else -> return null
+            }
+            val key = result.copyOfRange(1, 33)
+
+            if (outcome != UnlockOutcome.WIPED) {
+                encryptedPrefs.edit {
+                    putString(UNLOCK_RECORD_ALIAS, bytesToHex(result.copyOfRange(33, result.size)))
+                }
+            }
+            if (outcome == UnlockOutcome.REAL) {
+                upgradeDevicePasswordHash(password)
+            }
+            PasswordUnlock(outcome, key)
+        } finally {
+            result.fill(0)
+        }
+    }
+
+    /**
+     * Unlock an account that has no unlock record yet
+     * Both legacy hashes (device password, duress PIN) are checked and a record is built and
+     * opened whatever was entered; the record is only kept for the real password.
+     * A legacy duress PIN opens a decoy keyed by a stored random key until the PIN is re-saved.
+     */
+    private fun unlockLegacy(password: String): PasswordUnlock? {
+        return try {
+            val isReal = matchesDevicePasswordHash(password)
+            val isDuress = DuressPinActivity.verifyDuressPin(appContext, password)
+            val record = RustBridge.createUnlockRecord(password, getKdfTarget())
+            RustBridge.unlockWithPassword(record, password)?.fill(0)
+
+            when {
+                isDuress -> {
+                    val outcome = if (DuressPinActivity.shouldWipePhoneOnDistress(appContext)) {
+                        UnlockOutcome.WIPED
+                    } else {
+                        UnlockOutcome.DECOY
+                    }
+                    PasswordUnlock(outcome, getLegacyDecoyKey())
+                }
+                isReal -> {
+                    encryptedPrefs.edit {
+                        putString(UNLOCK_RECORD_ALIAS, bytesToHex(record))
+                    }
+                    Log.i(TAG, "Unlock record created")
+                    upgradeDevicePasswordHash(password)
+                    PasswordUnlock(UnlockOutcome.REAL, ByteArray(32))
+                }
+                else -> null
+            }
+        } catch (e: Exception) {
+            Log.e(TAG, "Error checking legacy device password", e)
+            null
+        }
+    }
+
+    /**
+     * Decoy database key for a legacy duress PIN, created on first use
+     */
+    private fun getLegacyDecoyKey(): ByteArray {
+        encryptedPrefs.getString(LEGACY_DECOY_KEY_ALIAS, null)?.let { return hexToBytes(it) }
+
+        val key = ByteArray(32)
+        java.security.SecureRandom().nextBytes(key)
+        encryptedPrefs.edit {
+            putString(LEGACY_DECOY_KEY_ALIAS, bytesToHex(key))
+        }
+        return key
+    }
+
+    /**
+     * Set (or replace) the duress password that opens the decoy profile
+     * @param realPassword Device password, to authorize the change
+     * @param wipe Also destroy the real profile when the duress password is entered
+     * @return false if the real password is wrong or both passwords match
+     */
+    fun setDuressPassword(realPassword: String, duressPassword: String, wipe: Boolean): Boolean {
+        if (!verifyDevicePassword(realPassword)) {
+            return false
+        }
+        val record = encryptedPrefs.getString(UNLOCK_RECORD_ALIAS, null)?.let { hexToBytes(it) }
+            ?: return false
+        val updated = RustBridge.setDuressPassword(record, realPassword, duressPassword, wipe) ?: return false
+
+        encryptedPrefs.edit {
+            putString(UNLOCK_RECORD_ALIAS, bytesToHex(updated))
+        }
+        Log.i(TAG, "Duress password set")
+        return true
+    }
+
+    /**
+     * Remove the duress password
+     * @return false if the real password is wrong
+     */
+    fun clearDuressPassword(realPassword: String): Boolean {
+        val record = encryptedPrefs.getString(UNLOCK_RECORD_ALIAS, null)?.let { hexToBytes(it) }
+            ?: return false
+        val updated = RustBridge.clearDuressPassword(record, realPassword) ?: return false
+
+        encryptedPrefs.edit {
+            putString(UNLOCK_RECORD_ALIAS, bytesToHex(updated))
+        }
+        Log.i(TAG, "Duress password cleared")
+        return true
+    }
+
+    /**
+     * Create the unlock record for accounts that predate it (needs the verified password)
+     */
+    private fun ensureUnlockRecord(password: String) {
+        if (encryptedPrefs.contains(UNLOCK_RECORD_ALIAS)) {
+            return
+        }
+        try {
+            val record = RustBridge.createUnlockRecord(password, getKdfTarget())
+            encryptedPrefs.edit {
+                putString(UNLOCK_RECORD_ALIAS, bytesToHex(record))
+            }
+            Log.i(TAG, "Unlock record created")
+        } catch (e: Exception) {
+            Log.e(TAG, "Failed to create unlock record", e)
+        }
+    }
+
+    private fun storePasswordUpgrade(upgrade: PendingPasswordUpgrade) {
+        encryptedPrefs.edit {
+            putString(DEVICE_PASSWORD_HASH_ALIAS, bytesToHex(upgrade.hash))
+            putString(DEVICE_PASSWORD_SALT_ALIAS, bytesToHex(upgrade.salt))
+            putString(DEVICE_PASSWORD_KDF_ALIAS, bytesToHex(upgrade.kdf))
+        }
+        Log.i(TAG, "Device password hash upgraded to calibrated Argon2id parameters")
+    }
+
+    /**
+     * Argon2id parameters for new password hashes, calibrated once per device
+     */
+    private fun getKdfTarget(): ByteArray {
+        encryptedPrefs.getString(KDF_TARGET_ALIAS, null)?.let { return hexToBytes(it) }
+
+        val target = RustBridge.calibrateKdf(KDF_TARGET_MILLIS, KDF_MEMORY_KIB)
+        encryptedPrefs.edit {
+            putString(KDF_TARGET_ALIAS, bytesToHex(target))
+        }
+        return target
+    }
+
     /**
      * Get the stored password hash for biometric encryption
      * Used to encrypt password hash with biometric-protected key
@@ -0,0 +1,136 @@
+package com.securelegion.crypto
+
+import android.content.Context
+import android.util.Log
+import kotlinx.coroutines.Dispatchers
+import kotlinx.coroutines.sync.Mutex
+import kotlinx.coroutines.sync.withLock
+import kotlinx.coroutines.withContext
+
+/**
+ * Manages our PQXDH prekeys and the asynchronous session setup built on them
+ *
+ * - Keeps the prekey store in KeyManager's encrypted preferences
+ * - Rotates the signed and KEM prekeys weekly and tops up one-time prekeys
+ * - Publishes bundles at GET /prekey-bundle on the friend request .onion
+ * - Initiates (friend request acceptor) and responds (requester) to PQXDH handshakes
+ */
+object PrekeyManager {
+    private const val TAG = "PrekeyManager"
+    private const val ONE_TIME_PREKEY_TARGET = 20
+    private const val CONTACT_EXCHANGE_PORT = 9152
+    private const val ROOT_KEY_SIZE = 32
+
+    // Serializes every read-modify-write of the prekey store
+    private val storeLock = Mutex()
+
+    /**
+     * Load (or create) the prekey store, rotate it if due and publish its bundles
+     * Call after the contact exchange endpoint has started
+     */
+    suspend fun publish(context: Context): Boolean {
+        return withContext(Dispatchers.IO) {
+            storeLock.withLock {
+                try {
+                    val keyManager = KeyManager.getInstance(context)
+                    val signingKey = keyManager.getSigningKeyBytes()
+
+                    var store = keyManager.getPrekeyStore()
+                    if (store == null) {
+                        Log.i(TAG, "Generating PQXDH prekey store")
+                        store = RustBridge.generatePrekeyStore(
+                            signingKey,
+                            keyManager.getEncryptionPublicKey(),
+                            ONE_TIME_PREKEY_TARGET
+                        )
+                        keyManager.storePrekeyStore(store)
+                    } else {
+                        RustBridge.maintainPrekeyStore(store, signingKey, ONE_TIME_PREKEY_TARGET)?.let { updated ->
+                            Log.i(TAG, "Prekey store rotated or replenished")
+                            keyManager.storePrekeyStore(updated)
+                            store = updated
+                        }
+                    }
+                    signingKey.fill(0)
+
+                    RustBridge.publishPrekeyBundles(store!!)
+                } catch (e: Exception) {
+                    Log.e(TAG, "Failed to publish prekey bundles", e)
+                    false
+                }
+            }
+        }
+    }
+
+    /**
+     * Start a PQXDH session with a peer (we are accepting their friend request)
+     * @param friendRequestOnion Peer's friend request .onion (serves the bundle)
+     * @param theirX25519PublicKey Peer's X25519 identity key the bundle must match
+     * @param theirEd25519PublicKey Peer's verified Ed25519 identity key the bundle must be signed by
+     * @return (rootKey, initMessage), or null if the peer publishes no valid bundle
+     */
+    suspend fun initiate(
+        context: Context,
+        friendRequestOnion: String,
+        theirX25519PublicKey: ByteArray,
+        theirEd25519PublicKey: ByteArray
+    ): Pair<ByteArray, ByteArray>? {
+        return withContext(Dispatchers.IO) {
+            try {
+                val url = "http://$friendRequestOnion:$CONTACT_EXCHANGE_PORT/prekey-bundle"
+                val bundle = RustBridge.fetchPrekeyBundle(url, theirX25519PublicKey, theirEd25519PublicKey) ?: run {
+                    Log.w(TAG, "No prekey bundle from ${friendRequestOnion.take(10)}...")
+                    return@withContext null
+                }
+
+                val ourPrivateKey = KeyManager.getInstance(context).getEncryptionKeyBytes()
+                val result = RustBridge.pqxdhInitiate(ourPrivateKey, bundle, theirEd25519PublicKey)
+                ourPrivateKey.fill(0)
+                result ?: return@withContext null
+
+                val rootKey = result.copyOfRange(0, ROOT_KEY_SIZE)
+                val initMessage = result.copyOfRange(ROOT_KEY_SIZE, result.size)
+                result.fill(0)
+                Log.i(TAG, "✓ PQXDH session initiated (init message: ${initMessage.size} bytes)")
+                Pair(rootKey, initMessage)
+            } catch (e: Exception) {
+                Log.e(TAG, "PQXDH initiate failed", e)
+                null
+            }
+        }
+    }
+
+    /**
+     * Complete a PQXDH session started by a peer from one of our bundles
+     * The consumed one-time prekey is deleted from the persisted store before the key is returned
+     * @param initMessage Init message received from the peer
+     * @return Root key, or null if the message does not match our prekeys
+     */
+    suspend fun respond(context: Context, initMessage: ByteArray): ByteArray? {
+        return withContext(Dispatchers.IO) {
+            storeLock.withLock {
+                try {
+                    val keyManager = KeyManager.getInstance(context)
+                    val store = keyManager.getPrekeyStore() ?: run {
+                        Log.e(TAG, "PQXDH init message received but we have no prekey store")
+                        return@withLock null
+                    }
+
+                    val ourPrivateKey = keyManager.getEncryptionKeyBytes()
+                    val result = RustBridge.pqxdhRespond(store, ourPrivateKey, initMessage)
+                    ourPrivateKey.fill(0)
+                    result ?: return@withLock null
+
+                    val rootKey = result.copyOfRange(0, ROOT_KEY_SIZE)
+                    keyManager.storePrekeyStore(result.copyOfRange(ROOT_KEY_SIZE, result.size))
+                    result.fill(0)
+                    Log.i(TAG, "✓ PQXDH session completed as responder")
+                    rootKey
+                } catch (e: Exception) {
+                    Log.e(TAG, "PQXDH respond failed", e)
+                    null
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,87 @@
+package com.securelegion.crypto
+
+import android.content.Context
+import android.util.Log
+import com.securelegion.database.SecureLegionDatabase
+import kotlinx.coroutines.runBlocking
+import org.json.JSONArray
+import org.json.JSONObject
+import java.io.File
+
+/**
+ * Write-ahead journal for deferred send ratchet advancements
+ *
+ * - Opens the encrypted journal in Rust at startup and resolves what survived a crash
+ * - On PING_ACK: commit in Rust, persist the send chain key, then ack so Rust finalizes the journal
+ *
+ * Contacts are identified by their database ID (as a string) in the journal.
+ */
+object RatchetJournal {
+    private const val TAG = "RatchetJournal"
+    private const val JOURNAL_FILE = "ratchet_journal.bin"
+
+    /**
+     * Open the journal and finalize recovered advancements whose key is already in the database
+     * Advancements not yet persisted stay pending until their PING_ACK arrives or they expire
+     * Call before any message is sent
+     * @return false if no wallet exists yet or the journal could not be opened
+     */
+    fun init(context: Context): Boolean {
+        return try {
+            val keyManager = KeyManager.getInstance(context)
+            if (!keyManager.isInitialized()) {
+                return false
+            }
+            val path = File(context.filesDir, JOURNAL_FILE).absolutePath
+            val recovered = JSONArray(RustBridge.initRatchetJournal(path) ?: return false)
+
+            val database = SecureLegionDatabase.getInstance(context, keyManager.getDatabasePassphrase())
+            var finalized = 0
+            for (i in 0 until recovered.length()) {
+                val entry = recovered.getJSONObject(i)
+                val contactId = entry.getString("contactId")
+                val messageId = entry.getString("messageId")
+                val keyChain = contactId.toLongOrNull()?.let {
+                    runBlocking { database.contactKeyChainDao().getKeyChainByContactId(it) }
+                }
+                // The DB write happened but the process died before the ack
+                if (keyChain != null && keyChain.sendCounter >= entry.getLong("nextSequence")) {
+                    if (RustBridge.ackRatchetPersisted(contactId, messageId) > 0) finalized++
+                }
+            }
+            Log.i(TAG, "Ratchet journal ready (${recovered.length()} recovered, $finalized already persisted)")
+            true
+        } catch (e: Exception) {
+            Log.e(TAG, "Failed to open ratchet journal", e)
+            false
+        }
+    }
+
+    /**
+     * Commit the advancement for an acknowledged message and persist it
+     * No-op if the message was not sent with deferred ratcheting
+     * @return true if a new send chain key was persisted
+     */
+    fun commitOnAck(context: Context, contactId: Long, messageId: String): Boolean {
+        val json = RustBridge.commitRatchetAdvancementForMessage(contactId.toString(), messageId) ?: return false
+        val committed = JSONObject(json)
+        val nextSequence = committed.getLong("nextSequence")
+
+        val database = SecureLegionDatabase.getInstance(context, KeyManager.getInstance(context).getDatabasePassphrase())
+        runBlocking {
+            database.contactKeyChainDao().updateSendChainKey(
+                contactId = contactId,
+                newSendChainKeyBase64 = committed.getString("nextChainKey"),
+                newSendCounter = nextSequence,
+                timestamp = System.currentTimeMillis()
+            )
+        }
+
+        // Only now may Rust drop the advancement from the journal
+        if (RustBridge.ackRatchetPersisted(contactId.toString(), messageId) < 0) {
+            Log.w(TAG, "Ratchet journal ack failed for $messageId - will be resolved at next startup")
+        }
+        Log.d(TAG, "Committed send ratchet for contact $contactId through seq $nextSequence")
+        return true
+    }
+}
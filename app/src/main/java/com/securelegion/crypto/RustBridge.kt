@@ -47,6 +47,17 @@ object RustBridge {
         val evolvedChainKey: ByteArray
     )
 
+    /**
+     * Result of decryption through the skipped-key store
+     * nextExpectedSequence is unchanged when a late message was decrypted with a parked key
+     */
+    data class WindowedDecryptionResult(
+        val plaintext: String,
+        val evolvedChainKey: ByteArray,
+        val nextExpectedSequence: Long,
+        val skippedKeyStore: ByteArray
+    )
+
     // ==================== CRYPTOGRAPHY ====================
 
     /**
@@ -101,6 +112,31 @@ object RustBridge {
      */
     external fun hashPassword(password: String, salt: ByteArray): ByteArray
 
+    /**
+     * Hash a password using Argon2id with explicit parameters
+     * @param password The password to hash
+     * @param salt The salt (16 bytes minimum)
+     * @param kdfDescriptor 15-byte KDF descriptor (from calibrateKdf); store it with the hash
+     * @return Password hash (32 bytes)
+     */
+    external fun hashPasswordWithKdf(password: String, salt: ByteArray, kdfDescriptor: ByteArray): ByteArray
+
+    /**
+     * Calibrate Argon2id parameters for this device
+     * @param targetMillis Desired derivation time in milliseconds
+     * @param memoryKib Memory cost in KiB
+     * @return 15-byte KDF descriptor (never weaker than the legacy parameters)
+     */
+    external fun calibrateKdf(targetMillis: Int, memoryKib: Int): ByteArray
+
+    /**
+     * Check whether a password hash should be re-derived on next login
+     * @param currentDescriptor Stored KDF descriptor, or null for hashes from hashPassword
+     * @param targetDescriptor Parameters new hashes should use
+     * @return True if the stored hash is weaker than the target
+     */
+    external fun kdfNeedsRehash(currentDescriptor: ByteArray?, targetDescriptor: ByteArray): Boolean
+
     // ==================== POST-QUANTUM CRYPTOGRAPHY (Hybrid X25519 + ML-KEM-1024) ====================
 
     /**
@@ -131,6 +167,42 @@ object RustBridge {
      */
     external fun hybridDecapsulate(ourX25519Secret: ByteArray, ourKyberSecret: ByteArray, ciphertext: ByteArray): ByteArray?
 
+    /**
+     * Install our Ed25519 + ML-DSA-65 signing keypair derived from the wallet seed
+     * Pings, Pongs and delivery ACKs created afterwards carry an ML-DSA signature
+     * @param seed BIP39 wallet seed (64 bytes)
+     * @return Our ML-DSA-65 public key (1952 bytes), published in the contact card
+     */
+    external fun initHybridSigning(seed: ByteArray): ByteArray?
+
+    /**
+     * Drop our hybrid signing keypair (account wiped)
+     */
+    external fun clearHybridSigning()
+
+    /**
+     * Our ML-DSA-65 public key, or null if initHybridSigning has not run
+     */
+    external fun getHybridSigningPublicKey(): ByteArray?
+
+    /**
+     * Register a contact's ML-DSA-65 key from their contact card
+     * From then on their Pings, Pongs and ACKs must carry a valid ML-DSA signature
+     * @param ed25519PublicKey Contact's Ed25519 identity key (32 bytes)
+     * @param mlDsaPublicKey Contact's ML-DSA-65 public key (1952 bytes)
+     * @return false if the key is malformed or differs from the one already registered
+     */
+    external fun registerContactMlDsaKey(ed25519PublicKey: ByteArray, mlDsaPublicKey: ByteArray): Boolean
+
+    /**
+     * Register the capability flags from a contact's card
+     * If canonical signing is negotiated, their Pings, Pongs and ACKs are signed (and must be
+     * signed) over the canonical encoding from then on
+     * @param ed25519PublicKey Contact's Ed25519 identity key (32 bytes)
+     * @param peerCapabilities Flags from the contact's card (Contact.peerCapabilities)
+     */
+    external fun registerContactCapabilities(ed25519PublicKey: ByteArray, peerCapabilities: Int)
+
     // ==================== KEY EVOLUTION (Progressive Ephemeral Keys) ====================
 
     /**
@@ -259,41 +331,223 @@ object RustBridge {
      */
     external fun rollbackRatchetAdvancement(contactId: String): Boolean
 
+    /**
+     * Commit pending ratchet advancements up to and including an acknowledged message
+     * Several messages may be in flight per contact; later ones stay pending
+     *
+     * @param contactId Contact identifier
+     * @param messageId Message whose PING_ACK arrived
+     * @return JSON string: {"nextChainKey":"base64","nextSequence":123}, or null if not pending
+     */
+    external fun commitRatchetAdvancementForMessage(contactId: String, messageId: String): String?
+
+    /**
+     * Roll back a failed message and every pending advancement queued after it
+     * (later messages were encrypted with chain keys derived from the failed one)
+     *
+     * @param contactId Contact identifier
+     * @param messageId Message whose send permanently failed
+     * @return Number of advancements discarded, or -1 on error
+     */
+    external fun rollbackRatchetAdvancementForMessage(contactId: String, messageId: String): Int
+
+    /**
+     * Finalize committed advancements once the new send chain key is in the database
+     * The journal keeps them until this is called, so a crash before the write replays them
+     *
+     * @param contactId Contact identifier
+     * @param messageId Message whose committed key was persisted
+     * @return Number of advancements finalized, or -1 on error
+     */
+    external fun ackRatchetPersisted(contactId: String, messageId: String): Int
+
+    /**
+     * Open the encrypted ratchet write-ahead journal (call once at startup, before sending)
+     * Pending advancements are logged before ciphertext leaves the device and replayed here,
+     * so a process kill between encrypt and commit does not desync the chains
+     *
+     * @param path Journal file in app-private storage
+     * @return JSON array of advancements still pending from the last run:
+     *         [{"contactId":"...","messageId":"...","nextSequence":123}]
+     */
+    external fun initRatchetJournal(path: String): String?
+
+    /**
+     * Deferred encryption using the v2 wire format (authenticated header)
+     * Same as encryptMessageDeferred, but version, sequence, both identity keys and
+     * the MSG_TYPE byte are bound as AEAD associated data
+     *
+     * @param plaintext Message to encrypt
+     * @param chainKey Current chain key (NOT modified)
+     * @param sequence Current sequence number
+     * @param senderIdentity Our Ed25519 identity public key (32 bytes)
+     * @param recipientIdentity Contact's Ed25519 identity public key (32 bytes)
+     * @param msgType MSG_TYPE_* byte the message will be sent as
+     * @param epoch Session epoch of the chain key (0 until the first SESSION_RESET)
+     * @param capabilities Contact's advertised capabilities (v3 key-committing if negotiated)
+     * @param securityTier "HIGH_RISK", "NORMAL" or "BULK" - selects the padding size classes
+     * @return JSON string: {"ciphertext":"base64","nextChainKey":"base64","nextSequence":123}
+     */
+    external fun encryptMessageDeferredV2(
+        plaintext: String,
+        chainKey: ByteArray,
+        sequence: Long,
+        senderIdentity: ByteArray,
+        recipientIdentity: ByteArray,
+        msgType: Int,
+        epoch: Int,
+        capabilities: Int,
+        securityTier: String
+    ): String
+
+    /**
+     * Open or close the migration window for legacy v1 messages
+     * While open, the v2 decrypt path still accepts v1 (unauthenticated header) messages
+     *
+     * @param accepted True to keep accepting v1 messages
+     */
+    external fun setLegacyV1MessagesAccepted(accepted: Boolean)
+
+    /**
+     * Open or close the migration window for the legacy token signing encoding
+     * While open, contacts without canonical signing get (and may send) legacy-signed tokens;
+     * once closed, every token is signed and verified over the canonical encoding
+     *
+     * @param accepted True to keep the legacy encoding for those contacts
+     */
+    external fun setLegacySigningAccepted(accepted: Boolean)
+
+    /**
+     * Protocol capability flags this build supports
+     * Advertised in our contact card ("capabilities")
+     */
+    external fun getLocalCapabilities(): Int
+
+    /**
+     * Capabilities both we and a contact support
+     *
+     * @param peerCapabilities Flags from the contact's card (Contact.peerCapabilities)
+     * @return Flags to enable for this contact (0 for contacts on older builds)
+     */
+    external fun negotiateCapabilities(peerCapabilities: Int): Int
+
+    /**
+     * Encrypt bytes (e.g. an attachment) with key-committing XChaCha20-Poly1305
+     * The ciphertext decrypts under this key only
+     *
+     * @param data Plaintext bytes
+     * @param key 32-byte encryption key
+     * @return [version][nonce][commitment][ciphertext]
+     */
+    external fun encryptBytesCommitting(data: ByteArray, key: ByteArray): ByteArray?
+
+    /**
+     * Decrypt bytes produced by encryptBytesCommitting
+     *
+     * @param encrypted Output of encryptBytesCommitting
+     * @param key 32-byte encryption key
+     * @return Plaintext, or null if the key or ciphertext does not match
+     */
+    external fun decryptBytesCommitting(encrypted: ByteArray, key: ByteArray): ByteArray?
+
+    /**
+     * Encrypt a file chunk by chunk (STREAM construction), for attachments too large
+     * to encrypt in memory or send as a single frame
+     *
+     * @param inputPath Plaintext file
+     * @param outputPath Destination for [header][chunks]
+     * @param key 32-byte encryption key
+     * @return True on success
+     */
+    external fun encryptFileStream(inputPath: String, outputPath: String, key: ByteArray): Boolean
+
+    /**
+     * Decrypt a file produced by encryptFileStream
+     * Truncated, reordered or modified chunks fail and the partial output is deleted
+     *
+     * @param inputPath Encrypted file
+     * @param outputPath Destination for the plaintext
+     * @param key 32-byte encryption key
+     * @return True if the whole stream authenticated
+     */
+    external fun decryptFileStream(inputPath: String, outputPath: String, key: ByteArray): Boolean
+
+    /**
+     * Open the encrypted PING replay log (call once at startup, before checkPingReplay)
+     * Seen PINGs are remembered for the acceptance window, so replays are still
+     * rejected after a restart
+     *
+     * @param path Replay log file in app-private storage
+     * @param windowSeconds Acceptance window for PING timestamps (0 = default, 7 days)
+     * @return Number of PINGs remembered from previous runs, or -1 on failure
+     */
+    external fun initPingReplayGuard(path: String, windowSeconds: Long): Int
+
     /**
      * FIX #9: Check if PING is a replay attack
      * Uses Blake3 hash of PING wire bytes + sender pubkey for deduplication
-     * LRU cache of 10,000 entries prevents memory exhaustion
+     * The Ping's signed timestamp must fall inside the acceptance window; each sender
+     * has its own bounded history, so one noisy sender cannot evict another's entries
      *
-     * @param senderPubkey Sender's Ed25519 public key (32 bytes)
+     * @param pingId Ping ID from decryptIncomingPing (supplies sender and timestamp)
      * @param pingBytes Full PING wire bytes (will be hashed)
-     * @return True if PING is NEW (should process), False if REPLAY (should drop)
+     * @return True if PING is NEW (should process), False if REPLAY or outside the window (should drop)
      */
     external fun checkPingReplay(
-        senderPubkey: ByteArray,
+        pingId: String,
         pingBytes: ByteArray
     ): Boolean
 
+    /**
+     * FIX #7: Start tracking a message delivery
+     * Call when the PING is sent; the delivery starts in the Sent state
+     *
+     * @param messageId Message being delivered
+     * @param contactId Recipient
+     * @return True if tracking started, False if the message is already tracked
+     */
+    external fun beginAckTracking(
+        messageId: String,
+        contactId: String
+    ): Boolean
+
     /**
      * FIX #7: Validate ACK ordering
-     * Enforces state machine: PING_ACK → PONG_ACK → MESSAGE_ACK
-     * Rejects out-of-order ACKs to prevent protocol violations
+     * Enforces state machine per message: Sent → PING_ACK → PONG_ACK → MESSAGE_ACK
+     * Rejects out-of-order ACKs to prevent protocol violations; resent ACKs are accepted
      *
-     * @param contactId Contact identifier
-     * @param ackType ACK type: 0=PING_ACK, 1=PONG_ACK, 2=MESSAGE_ACK
+     * @param messageId Delivery the ACK refers to
+     * @param ackType ACK type: 0=PING_ACK, 1=PONG_ACK, 2=MESSAGE_ACK, 3=TAP_ACK
      * @return True if ACK is valid and accepted, False if rejected
      */
     external fun validateAckOrdering(
-        contactId: String,
+        messageId: String,
         ackType: Int
     ): Boolean
 
     /**
-     * FIX #7: Reset ACK state for a contact
-     * Call this after completing a message exchange to reset the state machine
+     * FIX #7: Mark a delivery as failed (e.g., retries exhausted)
      *
-     * @param contactId Contact identifier
+     * @param messageId Delivery to fail
+     * @return True if the delivery was in flight
+     */
+    external fun markDeliveryFailed(messageId: String): Boolean
+
+    /**
+     * FIX #7: List deliveries stuck in a stage past its timeout
+     * Deliveries older than the expiry are marked expired first
+     *
+     * @return JSON array of {message_id, contact_id, state, sent_at, state_since}
+     */
+    external fun getStuckDeliveries(): String
+
+    /**
+     * FIX #7: Stop tracking a delivery
+     * Call this after completing a message exchange
+     *
+     * @param messageId Message identifier
      */
-    external fun resetAckState(contactId: String)
+    external fun resetAckState(messageId: String)
 
     /**
      * Kotlin wrapper for encryptMessageDeferred with JSON parsing
@@ -359,6 +613,53 @@ object RustBridge {
         expectedSequence: Long
     ): ByteArray?
 
+    /**
+     * Native JNI function for v2 decryption with key evolution
+     * Returns: [evolved_chain_key:32][plaintext_utf8], or null if decryption fails
+     */
+    private external fun decryptMessageWithEvolutionV2JNI(
+        encryptedData: ByteArray,
+        chainKey: ByteArray,
+        expectedSequence: Long,
+        senderIdentity: ByteArray,
+        recipientIdentity: ByteArray,
+        msgType: Int,
+        epoch: Int,
+        capabilities: Int
+    ): ByteArray?
+
+    /**
+     * Native JNI function for decryption through the skipped-key store
+     * Returns: [evolved_chain_key:32][next_sequence:8][store_len:4][store][plaintext_utf8], or null
+     */
+    private external fun decryptMessageWithSkippedKeysJNI(
+        encryptedData: ByteArray,
+        chainKey: ByteArray,
+        expectedSequence: Long,
+        skippedKeyStore: ByteArray?,
+        senderIdentity: ByteArray,
+        recipientIdentity: ByteArray,
+        msgType: Int,
+        epoch: Int,
+        capabilities: Int
+    ): ByteArray?
+
+    /**
+     * Native JNI function for v2 (padded) encryption with key evolution
+     * Returns: [evolved_chain_key:32][ciphertext]
+     */
+    private external fun encryptMessageWithEvolutionV2JNI(
+        plaintext: String,
+        chainKey: ByteArray,
+        sequence: Long,
+        senderIdentity: ByteArray,
+        recipientIdentity: ByteArray,
+        msgType: Int,
+        epoch: Int,
+        capabilities: Int,
+        securityTier: String
+    ): ByteArray
+
     // ==================== PUBLIC WRAPPER FUNCTIONS ====================
 
     /**
@@ -417,6 +718,146 @@ object RustBridge {
         return DecryptionResult(plaintext, evolvedKey)
     }
 
+    /**
+     * Decrypt message with atomic key evolution, verifying the v2 authenticated header
+     * Accepts v1 messages while the legacy migration window is open
+     *
+     * @param encryptedData Encrypted message with wire format header
+     * @param chainKey Current chain key
+     * @param expectedSequence Expected sequence number (for replay protection)
+     * @param senderIdentity Contact's Ed25519 identity public key (32 bytes)
+     * @param recipientIdentity Our Ed25519 identity public key (32 bytes)
+     * @param msgType MSG_TYPE_* byte the message was received as
+     * @param epoch Session epoch of the chain key (must match the message header)
+     * @param capabilities Contact's advertised capabilities
+     * @return DecryptionResult containing both plaintext and evolved chain key, or null if decryption fails
+     */
+    fun decryptMessageWithEvolutionV2(
+        encryptedData: ByteArray,
+        chainKey: ByteArray,
+        expectedSequence: Long,
+        senderIdentity: ByteArray,
+        recipientIdentity: ByteArray,
+        msgType: Int,
+        epoch: Int = 0,
+        capabilities: Int = 0
+    ): DecryptionResult? {
+        val result = decryptMessageWithEvolutionV2JNI(
+            encryptedData, chainKey, expectedSequence,
+            senderIdentity, recipientIdentity, msgType, epoch, capabilities
+        ) ?: return null
+
+        val evolvedKey = result.copyOfRange(0, 32)
+        val plaintext = String(result.copyOfRange(32, result.size), Charsets.UTF_8)
+
+        return DecryptionResult(plaintext, evolvedKey)
+    }
+
+    /**
+     * Decrypt a chain message that may arrive early or late (v1 during migration, v2, v3)
+     * Early messages park the keys for the gap in the store; late messages decrypt with
+     * their parked key in O(1). Persist the returned store with the chain key.
+     *
+     * @param encryptedData Encrypted message with wire format header
+     * @param chainKey Current receive chain key
+     * @param expectedSequence Sequence the chain key corresponds to
+     * @param skippedKeyStore Store from the previous call (null for a new store)
+     * @param senderIdentity Contact's Ed25519 identity public key (32 bytes)
+     * @param recipientIdentity Our Ed25519 identity public key (32 bytes)
+     * @param msgType MSG_TYPE_* byte the message was received as
+     * @param epoch Session epoch of the chain key (must match the message header)
+     * @param capabilities Contact's advertised capabilities
+     * @return WindowedDecryptionResult, or null if decryption fails (nothing to persist then)
+     */
+    fun decryptMessageWithSkippedKeys(
+        encryptedData: ByteArray,
+        chainKey: ByteArray,
+        expectedSequence: Long,
+        skippedKeyStore: ByteArray?,
+        senderIdentity: ByteArray,
+        recipientIdentity: ByteArray,
+        msgType: Int,
+        epoch: Int = 0,
+        capabilities: Int = 0
+    ): WindowedDecryptionResult? {
+        val result = decryptMessageWithSkippedKeysJNI(
+            encryptedData, chainKey, expectedSequence, skippedKeyStore,
+            senderIdentity, recipientIdentity, msgType, epoch, capabilities
+        ) ?: return null
+
+        val buffer = java.nio.ByteBuffer.wrap(result)
+        val evolvedKey = ByteArray(32).also { buffer.get(it) }
+        val nextSequence = buffer.long
+        val store = ByteArray(buffer.int).also { buffer.get(it) }
+        val plaintext = String(result, buffer.position(), buffer.remaining(), Charsets.UTF_8)
+        result.fill(0)
+
+        return WindowedDecryptionResult(plaintext, evolvedKey, nextSequence, store)
+    }
+
+    /**
+     * Encrypt message with atomic key evolution in the v2 format
+     * The plaintext is padded to its size class for the security tier, and the header,
+     * both identity keys and the message type are bound as associated data.
+     * Only send v2 to contacts that negotiated the chain v2 capability.
+     *
+     * @param plaintext Message to encrypt
+     * @param chainKey Current chain key
+     * @param sequence Message sequence number
+     * @param senderIdentity Our Ed25519 identity public key (32 bytes)
+     * @param recipientIdentity Contact's Ed25519 identity public key (32 bytes)
+     * @param msgType MSG_TYPE_* byte the message will be sent as
+     * @param securityTier "HIGH_RISK", "NORMAL" or "BULK" - selects the padding size classes
+     * @param epoch Session epoch of the chain key (0 until the first SESSION_RESET)
+     * @param capabilities Contact's advertised capabilities; v3 (key-committing) is sent
+     *        when both sides support it, v2 otherwise
+     * @return EncryptionResult containing both ciphertext and evolved chain key
+     */
+    fun encryptMessageWithEvolutionV2(
+        plaintext: String,
+        chainKey: ByteArray,
+        sequence: Long,
+        senderIdentity: ByteArray,
+        recipientIdentity: ByteArray,
+        msgType: Int,
+        securityTier: String,
+        epoch: Int = 0,
+        capabilities: Int = 0
+    ): EncryptionResult {
+        val result = encryptMessageWithEvolutionV2JNI(
+            plaintext, chainKey, sequence,
+            senderIdentity, recipientIdentity, msgType, epoch, capabilities, securityTier
+        )
+
+        val evolvedKey = result.copyOfRange(0, 32)
+        val ciphertext = result.copyOfRange(32, result.size)
+
+        return EncryptionResult(ciphertext, evolvedKey)
+    }
+
+    /**
+     * Decrypt a v2/v3 message using a pre-derived (skipped) message key
+     * Checks the authenticated header and strips the padding
+     *
+     * @param ciphertext Encrypted message with wire format header
+     * @param messageKey Pre-derived 32-byte message key for this specific sequence
+     * @param senderIdentity Contact's Ed25519 identity public key (32 bytes)
+     * @param recipientIdentity Our Ed25519 identity public key (32 bytes)
+     * @param msgType MSG_TYPE_* byte the message was received as
+     * @param epoch Session epoch the message key belongs to
+     * @param capabilities Contact's advertised capabilities
+     * @return Decrypted plaintext string, or null if decryption fails
+     */
+    external fun decryptWithMessageKeyV2(
+        ciphertext: ByteArray,
+        messageKey: ByteArray,
+        senderIdentity: ByteArray,
+        recipientIdentity: ByteArray,
+        msgType: Int,
+        epoch: Int,
+        capabilities: Int
+    ): String?
+
     /**
      * Decrypt message using a pre-derived message key
      * Used for decrypting skipped messages that arrive out-of-order
@@ -538,6 +979,102 @@ object RustBridge {
      */
     external fun serveContactList(cid: String, encryptedList: ByteArray, listLength: Int)
 
+    /**
+     * Publish a PQXDH prekey bundle at GET /prekey-bundle on the contact exchange endpoint
+     * @param bundle Serialized PrekeyBundle
+     * @param oneTime True if the bundle carries a one-time prekey (served to a single requester);
+     *                false sets the fallback bundle served once one-time bundles run out
+     */
+    external fun servePrekeyBundle(bundle: ByteArray, oneTime: Boolean)
+
+    /**
+     * Generate a PQXDH prekey store (signed prekey, signed KEM prekey, one-time prekeys)
+     * @param identitySigningPrivate Our Ed25519 identity private key (signs the prekeys)
+     * @param identityDhPublic Our X25519 identity public key
+     * @param oneTimeCount Number of one-time prekeys to generate
+     * @return Serialized store (contains secrets - persist encrypted)
+     */
+    external fun generatePrekeyStore(identitySigningPrivate: ByteArray, identityDhPublic: ByteArray, oneTimeCount: Int): ByteArray
+
+    /**
+     * Rotate the signed and KEM prekeys once they are a week old and top up one-time prekeys
+     * @param store Serialized prekey store
+     * @param identitySigningPrivate Our Ed25519 identity private key
+     * @param oneTimeTarget Number of unused one-time prekeys to keep available
+     * @return Updated store to persist and republish, or null if nothing changed
+     */
+    external fun maintainPrekeyStore(store: ByteArray, identitySigningPrivate: ByteArray, oneTimeTarget: Int): ByteArray?
+
+    /**
+     * Publish every bundle of a prekey store at GET /prekey-bundle, replacing earlier bundles
+     * @param store Serialized prekey store
+     * @return True if the bundles were built and handed to the endpoint
+     */
+    external fun publishPrekeyBundles(store: ByteArray): Boolean
+
+    /**
+     * Fetch a peer's prekey bundle via Tor and verify its signatures
+     * @param url http://<friend-request-onion>:9152/prekey-bundle
+     * @param expectedIdentityDhKey Peer's X25519 identity key the bundle must be issued for
+     * @param expectedIdentitySigningKey Peer's pinned Ed25519 key the bundle must be signed by
+     * @return Serialized bundle, or null if unavailable or invalid
+     */
+    external fun fetchPrekeyBundle(url: String, expectedIdentityDhKey: ByteArray, expectedIdentitySigningKey: ByteArray): ByteArray?
+
+    /**
+     * Start a PQXDH session from a peer's prekey bundle
+     * @param ourIdentityDhPrivate Our X25519 identity private key
+     * @param bundle Serialized bundle from fetchPrekeyBundle
+     * @param expectedIdentitySigningKey Peer's pinned Ed25519 key the bundle must be signed by
+     * @return [root_key:32][init_message] or null on failure
+     */
+    external fun pqxdhInitiate(ourIdentityDhPrivate: ByteArray, bundle: ByteArray, expectedIdentitySigningKey: ByteArray): ByteArray?
+
+    /**
+     * Complete a PQXDH session as the responder (the named one-time prekey is consumed)
+     * @param store Serialized prekey store
+     * @param ourIdentityDhPrivate Our X25519 identity private key
+     * @param initMessage Init message sent by the initiator
+     * @return [root_key:32][updated_store] or null on failure
+     */
+    external fun pqxdhRespond(store: ByteArray, ourIdentityDhPrivate: ByteArray, initMessage: ByteArray): ByteArray?
+
+    /**
+     * Open the trust-on-first-use identity key registry (call once at startup)
+     * @param path File the registry is persisted to
+     * @param blockOnKeyChange True to drop traffic from contacts whose keys changed;
+     *                         false only flags it (verified contacts are always blocked)
+     * @return True if the registry was opened
+     */
+    external fun initKeyRegistry(path: String, blockOnKeyChange: Boolean): Boolean
+
+    /**
+     * Check a contact's identity keys, pinning them on first use
+     * @param contactId App-level contact identifier
+     * @param ed25519PublicKey Contact's Ed25519 signing public key (32 bytes)
+     * @param x25519PublicKey Contact's X25519 encryption public key (32 bytes)
+     * @return JSON: {"result": "first_seen" | "trusted" | "flagged" | "blocked", "verified"?, "event"?}
+     */
+    external fun checkContactKeys(contactId: String, ed25519PublicKey: ByteArray, x25519PublicKey: ByteArray): String?
+
+    /**
+     * Accept the keys from a contact's last key change (pinned again as unverified)
+     * @return True if there was a pending key change to accept
+     */
+    external fun acceptContactKeyChange(contactId: String): Boolean
+
+    /**
+     * Mark a contact's pinned keys as verified after comparing safety numbers
+     * @return False if the contact is unknown or has an unresolved key change
+     */
+    external fun markContactKeysVerified(contactId: String): Boolean
+
+    /**
+     * Drain identity key changes detected on incoming traffic
+     * @return JSON array of key change events (empty array if none)
+     */
+    external fun pollKeyChangeEvents(): String
+
     /**
      * Create voice hidden service for voice calling (v2.0)
      * Uses seed-derived voice service Ed25519 key from KeyManager
@@ -586,6 +1123,49 @@ object RustBridge {
      */
     external fun startFriendRequestListener(): Boolean
 
+    /**
+     * Start the SESSION_RESET listener channel (0x0E, separate from chain messages)
+     * @return True if the channel was initialized
+     */
+    external fun startSessionResetListener(): Boolean
+
+    /**
+     * Poll for an incoming SESSION_RESET (non-blocking)
+     * @return [sender Ed25519:32][reset message], or null if none is waiting
+     */
+    external fun pollSessionReset(): ByteArray?
+
+    /**
+     * Session epoch a chain message was sent under (selects the chain keys to decrypt with)
+     * @return 0 for messages without an epoch header, -1 if malformed
+     */
+    external fun chainMessageEpoch(encryptedData: ByteArray): Int
+
+    /**
+     * Start (or repeat, while unanswered) a SESSION_RESET with a contact
+     * @param healerState Stored session reset state, or null to start from rootKey/epoch
+     * @param rootKey Current root key (used only when healerState is null)
+     * @param epoch Current session epoch (used only when healerState is null)
+     * @return [state_len:4][healer state][wire]; send wire as MSG_TYPE_SESSION_RESET
+     */
+    external fun sessionResetInitiate(healerState: ByteArray?, rootKey: ByteArray, epoch: Int): ByteArray?
+
+    /**
+     * Handle a SESSION_RESET init or ACK from a contact
+     * Refuses forged, stale or replayed messages (outside the freshness window)
+     * @param message Reset message as polled, after the sender key
+     * @param theirEd25519 The contact's stored Ed25519 key
+     * @return [switched:1][epoch:4][grace_expires_at:8][state_len:4][healer state][root_key:32][reply],
+     *         or null if rejected (parsed by SessionHealing)
+     */
+    external fun sessionResetHandle(
+        healerState: ByteArray?,
+        rootKey: ByteArray,
+        epoch: Int,
+        theirEd25519: ByteArray,
+        message: ByteArray
+    ): ByteArray?
+
     /**
      * Start the pong listener on the specified port
      * This enables receiving incoming pong responses
@@ -866,11 +1446,24 @@ object RustBridge {
 
     /**
      * Decrypt an incoming encrypted Ping token and store it
-     * Wire format: [Sender X25519 Public Key - 32 bytes][Encrypted Ping Token]
+     * Wire format: forward-secret Ping envelope (ephemeral X25519 per Ping), or the legacy
+     * [Sender X25519 Public Key - 32 bytes][Encrypted Ping Token] from older peers
      * @param encryptedPingWire The encrypted wire message from pollIncomingPing
+     * @param contactId Contact this Ping is expected from (e.g. its ping inbox entry); the sender's
+     *                  keys must match that contact's pinned keys. Null for Pings fresh off the
+     *                  listener - check those with verifyPingSender once the contact is resolved
      * @return Ping ID (String) to pass to respondToPing, or null on failure
      */
-    external fun decryptIncomingPing(encryptedPingWire: ByteArray): String?
+    external fun decryptIncomingPing(encryptedPingWire: ByteArray, contactId: String?): String?
+
+    /**
+     * Check a decrypted Ping against the keys pinned for the contact it was resolved to
+     * A rejected Ping is dropped (respondToPing can no longer answer it)
+     * @param pingId The Ping ID from decryptIncomingPing
+     * @param contactId Contact resolved from its stored identity key
+     * @return True if the sender's keys match the pinned ones
+     */
+    external fun verifyPingSender(pingId: String, contactId: String): Boolean
 
     /**
      * Get the sender's Ed25519 public key from a stored Ping
@@ -1191,6 +1784,146 @@ object RustBridge {
      */
     external fun getNLx402Version(): String
 
+    // ==================== IDENTITY BACKUP ====================
+
+    /**
+     * Export an encrypted backup of the identity, contacts and ratchet state
+     * Trust records are taken from the key registry if it is initialized
+     *
+     * @param seedPhrase KeyManager's 12-word wallet seed phrase as UTF-8 (zero it afterwards)
+     * @param passphrase Backup passphrase (stretched with Argon2id)
+     * @param contactsJson [{"contactId": "...", "card": {ContactCard JSON}}]
+     * @param ratchetStatesJson [{"contactId": "...", "state": "base64"}]
+     * @return Encrypted backup blob
+     */
+    external fun exportIdentityBackup(
+        seedPhrase: ByteArray,
+        passphrase: String,
+        contactsJson: String,
+        ratchetStatesJson: String
+    ): ByteArray
+
+    /**
+     * Restore a backup and re-derive every identity key from its seed phrase
+     * Keys are derived from the 64-byte BIP39 seed exactly as KeyManager does
+     *
+     * @param backup Blob from exportIdentityBackup
+     * @param passphrase Backup passphrase
+     * @return Slots as in IdentityBackup (seed phrase, BIP39 seed, keypairs, metadata JSON,
+     *         ratchet states); every entry must be zeroed after use. Null on a wrong passphrase
+     */
+    external fun importIdentityBackup(backup: ByteArray, passphrase: String): Array<ByteArray>?
+
+    // ==================== SOCIAL RECOVERY ====================
+
+    /**
+     * Start the RECOVERY_SHARE listener channel (0x0F, separate from chain messages)
+     * @return True if the channel was initialized
+     */
+    external fun startRecoveryListener(): Boolean
+
+    /**
+     * Poll for an incoming RECOVERY_SHARE (non-blocking)
+     * @return [sender X25519:32][recovery message], or null if none is waiting
+     */
+    external fun pollRecoveryMessage(): ByteArray?
+
+    /**
+     * Split the wallet seed phrase into k-of-n Shamir shares, one per guardian
+     * Each share is sealed to the guardian's X25519 + Kyber key
+     *
+     * @param seedPhrase KeyManager's 12-word seed phrase as UTF-8 (zero it afterwards)
+     * @param threshold Shares needed to recover (k)
+     * @param guardiansJson [{"contactId": "...", "x25519PublicKey": "base64", "kyberPublicKey": "base64"}]
+     * @return JSON [{"contactId": "...", "message": "base64"}]; send each as MSG_TYPE_RECOVERY_SHARE
+     */
+    external fun createRecoveryShares(seedPhrase: ByteArray, threshold: Int, guardiansJson: String): String
+
+    /**
+     * Guardian: open a share deposited by a contact
+     *
+     * @param message RECOVERY_SHARE message body
+     * @return Share bytes to store encrypted, or null if invalid
+     */
+    external fun openRecoveryDeposit(message: ByteArray): ByteArray?
+
+    /**
+     * Guardian: answer a recovery request with the held share
+     * Only call after confirming the request with the owner out of band
+     *
+     * @param shareBytes Share from openRecoveryDeposit
+     * @param requestMessage RECOVERY_SHARE request body
+     * @return Release message to send back, or null if invalid
+     */
+    external fun releaseRecoveryShare(shareBytes: ByteArray, requestMessage: ByteArray): ByteArray?
+
+    /**
+     * New device: start social recovery
+     *
+     * @return Request message to send to every guardian
+     */
+    external fun startSocialRecovery(): ByteArray
+
+    /**
+     * New device: add a guardian's release
+     *
+     * @param senderX25519 X25519 key the release came from (a few releases per sender are kept)
+     * @param message RECOVERY_SHARE release body
+     * @return Seed phrase (UTF-8) once enough shares arrived, otherwise null; zero it after use
+     */
+    external fun addRecoveryRelease(senderX25519: ByteArray, message: ByteArray): ByteArray?
+
+    // ==================== KEY HIERARCHY ====================
+
+    /**
+     * Forget the keys Rust derived from the wallet seed this session
+     * The seed is fetched again from KeyManager on next use; call after wipe or re-init
+     */
+    external fun clearKeyHierarchy()
+
+    // ==================== DURESS UNLOCK ====================
+
+    /**
+     * Create an unlock record for the real password (no duress password yet)
+     * @param password Real device password
+     * @param kdfDescriptor 15-byte KDF descriptor (from calibrateKdf)
+     * @return Record bytes to store
+     */
+    external fun createUnlockRecord(password: String, kdfDescriptor: ByteArray): ByteArray
+
+    /**
+     * Set or replace the duress password
+     * @param record Stored unlock record
+     * @param realPassword Real password (authorizes the change)
+     * @param duressPassword Password that opens the empty decoy profile
+     * @param wipe Also destroy the real profile when the duress password is used
+     * @return Updated record, or null if the real password is wrong or both passwords match
+     */
+    external fun setDuressPassword(
+        record: ByteArray,
+        realPassword: String,
+        duressPassword: String,
+        wipe: Boolean
+    ): ByteArray?
+
+    /**
+     * Remove the duress password
+     * @param record Stored unlock record
+     * @param realPassword Real password (authorizes the change)
+     * @return Updated record, or null if the real password is wrong
+     */
+    external fun clearDuressPassword(record: ByteArray, realPassword: String): ByteArray?
+
+    /**
+     * Unlock with the real or duress password (constant time whichever is entered)
+     * On Wiped, Rust has already destroyed its key material and ratchet journal;
+     * the caller clears KeyManager's key store and deletes the real database (see KeyManager.unlockWithPassword).
+     * @param record Stored unlock record
+     * @param password Password entered at the lock screen
+     * @return [outcome:1 (0=Real, 1=Decoy, 2=Wiped)][decoyKey:32 (zeros on Real)][record], or null if wrong
+     */
+    external fun unlockWithPassword(record: ByteArray, password: String): ByteArray?
+
     // ==================== HELPER FUNCTIONS ====================
 
     /**
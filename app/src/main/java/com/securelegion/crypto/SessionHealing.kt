@@ -0,0 +1,153 @@
+package com.securelegion.crypto
+
+import android.content.Context
+import android.util.Base64
+import android.util.Log
+import com.securelegion.database.SecureLegionDatabase
+import com.securelegion.database.entities.Contact
+import com.securelegion.database.entities.ed25519PublicKeyBytes
+import com.securelegion.database.entities.rootKeyBytes
+import kotlinx.coroutines.runBlocking
+import java.nio.ByteBuffer
+
+/**
+ * SESSION_RESET handling for broken key chains
+ *
+ * - initiate(): when a chain can no longer decrypt, start (or repeat) a signed reset
+ * - handleIncoming(): answer an init or complete on an ACK, then move the chain to the new epoch
+ *
+ * Only contacts that advertise CAPABILITY_SESSION_RESET are reset; the healer state
+ * (pending init, last accepted epoch) is stored with the contact's key chain.
+ */
+object SessionHealing {
+    private const val TAG = "SessionHealing"
+    const val CAPABILITY_SESSION_RESET = 0x08
+    const val MSG_TYPE_SESSION_RESET: Byte = 0x0E
+
+    /**
+     * Send a SESSION_RESET init to a contact
+     * @return false if the contact does not support resets or nothing was sent
+     */
+    fun initiate(context: Context, contact: Contact): Boolean {
+        return try {
+            if (RustBridge.negotiateCapabilities(contact.peerCapabilities) and CAPABILITY_SESSION_RESET == 0) {
+                return false
+            }
+            val onion = contact.messagingOnion ?: return false
+            val database = SecureLegionDatabase.getInstance(context, KeyManager.getInstance(context).getDatabasePassphrase())
+            val keyChain = runBlocking { database.contactKeyChainDao().getKeyChainByContactId(contact.id) }
+                ?: return false
+
+            val healerState = keyChain.sessionHealerStateBase64?.let { Base64.decode(it, Base64.NO_WRAP) }
+            val rootKey = keyChain.rootKeyBytes
+            val result = RustBridge.sessionResetInitiate(healerState, rootKey, keyChain.sessionEpoch)
+            rootKey.fill(0)
+            healerState?.fill(0)
+            if (result == null) {
+                return false
+            }
+
+            val buffer = ByteBuffer.wrap(result)
+            val state = ByteArray(buffer.int).also { buffer.get(it) }
+            val wire = ByteArray(buffer.remaining()).also { buffer.get(it) }
+            runBlocking {
+                database.contactKeyChainDao().updateSessionHealerState(
+                    contactId = contact.id,
+                    healerStateBase64 = Base64.encodeToString(state, Base64.NO_WRAP)
+                )
+            }
+            state.fill(0)
+            result.fill(0)
+
+            val sent = RustBridge.sendMessageBlob(onion, wire, MSG_TYPE_SESSION_RESET)
+            Log.i(TAG, "SESSION_RESET init for contact ${contact.id} (epoch ${keyChain.sessionEpoch + 1}) sent=$sent")
+            sent
+        } catch (e: Exception) {
+            Log.e(TAG, "Failed to initiate session reset for contact ${contact.id}", e)
+            false
+        }
+    }
+
+    /**
+     * Handle a polled SESSION_RESET ([sender_ed25519:32][message])
+     * Switches the contact's key chain to the new epoch once the reset completes
+     */
+    fun handleIncoming(context: Context, bytes: ByteArray) {
+        try {
+            if (bytes.size <= 32) {
+                Log.w(TAG, "Dropping truncated SESSION_RESET")
+                return
+            }
+            val keyManager = KeyManager.getInstance(context)
+            val database = SecureLegionDatabase.getInstance(context, keyManager.getDatabasePassphrase())
+            val senderKey = Base64.encodeToString(bytes.copyOfRange(0, 32), Base64.NO_WRAP)
+            val contact = database.contactDao().getContactByPublicKey(senderKey)
+            if (contact == null) {
+                Log.w(TAG, "SESSION_RESET from unknown sender - dropping")
+                return
+            }
+            val keyChain = runBlocking { database.contactKeyChainDao().getKeyChainByContactId(contact.id) }
+            if (keyChain == null) {
+                Log.w(TAG, "SESSION_RESET from contact ${contact.id} without key chain - dropping")
+                return
+            }
+
+            val healerState = keyChain.sessionHealerStateBase64?.let { Base64.decode(it, Base64.NO_WRAP) }
+            val currentRoot = keyChain.rootKeyBytes
+            val result = RustBridge.sessionResetHandle(
+                healerState,
+                currentRoot,
+                keyChain.sessionEpoch,
+                contact.ed25519PublicKeyBytes,
+                bytes.copyOfRange(32, bytes.size)
+            )
+            currentRoot.fill(0)
+            healerState?.fill(0)
+            if (result == null) {
+                Log.w(TAG, "SESSION_RESET from contact ${contact.id} rejected (forged, stale or replayed)")
+                return
+            }
+
+            val buffer = ByteBuffer.wrap(result)
+            val switched = buffer.get() != 0.toByte()
+            val epoch = buffer.int
+            val graceExpiresAt = buffer.long
+            val state = ByteArray(buffer.int).also { buffer.get(it) }
+            val rootKey = ByteArray(32).also { buffer.get(it) }
+            val reply = ByteArray(buffer.remaining()).also { buffer.get(it) }
+            result.fill(0)
+
+            val ourOnion = keyManager.getMessagingOnion()
+            val theirOnion = contact.messagingOnion
+            if (switched && ourOnion != null && theirOnion != null) {
+                runBlocking {
+                    KeyChainManager.switchSessionEpoch(
+                        context = context,
+                        contactId = contact.id,
+                        epoch = epoch,
+                        rootKey = rootKey,
+                        ourMessagingOnion = ourOnion,
+                        theirMessagingOnion = theirOnion,
+                        previousEpochExpiresAt = graceExpiresAt,
+                        healerState = state
+                    )
+                }
+            } else {
+                runBlocking {
+                    database.contactKeyChainDao().updateSessionHealerState(
+                        contactId = contact.id,
+                        healerStateBase64 = Base64.encodeToString(state, Base64.NO_WRAP)
+                    )
+                }
+            }
+            rootKey.fill(0)
+            state.fill(0)
+
+            if (reply.isNotEmpty() && theirOnion != null) {
+                RustBridge.sendMessageBlob(theirOnion, reply, MSG_TYPE_SESSION_RESET)
+            }
+        } catch (e: Exception) {
+            Log.e(TAG, "Failed to handle SESSION_RESET", e)
+        }
+    }
+}
@@ -0,0 +1,165 @@
+package com.securelegion.crypto
+
+import android.content.Context
+import android.util.Base64
+import android.util.Log
+import com.securelegion.database.SecureLegionDatabase
+import com.securelegion.database.entities.Contact
+import org.json.JSONArray
+import org.json.JSONObject
+
+/**
+ * Social recovery of the wallet seed phrase through guardian contacts
+ *
+ * - deposit(): split our seed phrase k-of-n and send each guardian its sealed share
+ * - handleIncoming(): store deposits, queue requests for the user, collect releases
+ * - release(): answer a request the user confirmed with the owner out of band
+ *
+ * RECOVERY_SHARE messages have their own channel and are never decrypted as chain messages.
+ * A recovered phrase is held until the owner confirms the restored identity (a full set of
+ * forged releases would also combine), then passed to KeyManager.initializeFromSeed.
+ *
+ * Only the receive side is wired (TorService's recovery poller). There are no UI entry
+ * points yet for deposit(), release(), RustBridge.startSocialRecovery or
+ * takeRecoveredPhrase(); those screens are out of scope here.
+ */
+object SocialRecovery {
+    private const val TAG = "SocialRecovery"
+    const val MSG_TYPE_RECOVERY_SHARE: Byte = 0x0F
+
+    private const val KIND_DEPOSIT: Byte = 0x01
+    private const val KIND_REQUEST: Byte = 0x02
+    private const val KIND_RELEASE: Byte = 0x03
+
+    /** Requests held at once, one per sender; further senders are dropped until some expire */
+    private const val MAX_PENDING_REQUESTS = 16
+    private const val PENDING_REQUEST_TTL_MS = 24 * 60 * 60 * 1000L
+
+    /** Recovery request awaiting the guardian's confirmation */
+    data class PendingRequest(val senderX25519Base64: String, val message: ByteArray, val receivedAt: Long)
+
+    private val pendingRequests = LinkedHashMap<String, PendingRequest>()
+    private var recoveredPhrase: ByteArray? = null
+
+    /**
+     * Split our seed phrase among guardians
+     * @return number of deposits sent
+     */
+    fun deposit(context: Context, threshold: Int, guardians: List<Contact>): Int {
+        val phrase = KeyManager.getInstance(context).getMainWalletSeedForZcash()?.toByteArray(Charsets.UTF_8)
+            ?: return 0
+        return try {
+            val guardiansJson = JSONArray()
+            for (guardian in guardians) {
+                val kyber = guardian.kyberPublicKeyBase64 ?: throw IllegalArgumentException("Guardian ${guardian.id} has no Kyber key")
+                guardiansJson.put(JSONObject().apply {
+                    put("contactId", guardian.id.toString())
+                    put("x25519PublicKey", guardian.x25519PublicKeyBase64)
+                    put("kyberPublicKey", kyber)
+                })
+            }
+            val deposits = JSONArray(RustBridge.createRecoveryShares(phrase, threshold, guardiansJson.toString()))
+
+            var sent = 0
+            for (i in 0 until deposits.length()) {
+                val deposit = deposits.getJSONObject(i)
+                val guardian = guardians.first { it.id.toString() == deposit.getString("contactId") }
+                val onion = guardian.messagingOnion ?: continue
+                val message = Base64.decode(deposit.getString("message"), Base64.NO_WRAP)
+                if (RustBridge.sendMessageBlob(onion, message, MSG_TYPE_RECOVERY_SHARE)) {
+                    sent++
+                }
+            }
+            Log.i(TAG, "Sent $sent of ${guardians.size} recovery deposits (threshold $threshold)")
+            sent
+        } catch (e: Exception) {
+            Log.e(TAG, "Failed to deposit recovery shares", e)
+            0
+        } finally {
+            phrase.fill(0)
+        }
+    }
+
+    /**
+     * Handle a polled RECOVERY_SHARE ([sender_x25519:32][kind][body])
+     */
+    fun handleIncoming(context: Context, bytes: ByteArray) {
+        try {
+            if (bytes.size <= 33) {
+                Log.w(TAG, "Dropping truncated RECOVERY_SHARE")
+                return
+            }
+            val sender = Base64.encodeToString(bytes.copyOfRange(0, 32), Base64.NO_WRAP)
+            val message = bytes.copyOfRange(32, bytes.size)
+
+            when (message[0]) {
+                KIND_DEPOSIT -> {
+                    val keyManager = KeyManager.getInstance(context)
+                    val database = SecureLegionDatabase.getInstance(context, keyManager.getDatabasePassphrase())
+                    val contact = database.contactDao().getContactByX25519PublicKey(sender)
+                    if (contact == null) {
+                        Log.w(TAG, "Recovery deposit from unknown sender - dropping")
+                        return
+                    }
+                    val share = RustBridge.openRecoveryDeposit(message) ?: return
+                    keyManager.storeRecoveryShare(contact.id, share)
+                    share.fill(0)
+                    Log.i(TAG, "Holding recovery share for contact ${contact.id}")
+                }
+                KIND_REQUEST -> synchronized(pendingRequests) {
+                    val now = System.currentTimeMillis()
+                    pendingRequests.values.removeAll { now - it.receivedAt > PENDING_REQUEST_TTL_MS }
+                    // A sender's newer request replaces its older one
+                    if (sender !in pendingRequests && pendingRequests.size >= MAX_PENDING_REQUESTS) {
+                        Log.w(TAG, "Too many pending recovery requests - dropping")
+                        return
+                    }
+                    pendingRequests[sender] = PendingRequest(sender, message, now)
+                    Log.i(TAG, "Recovery request queued for confirmation")
+                }
+                KIND_RELEASE -> {
+                    val phrase = RustBridge.addRecoveryRelease(bytes.copyOfRange(0, 32), message) ?: return
+                    synchronized(this) {
+                        recoveredPhrase?.fill(0)
+                        recoveredPhrase = phrase
+                    }
+                    Log.i(TAG, "Seed phrase recovered - awaiting owner confirmation")
+                }
+                else -> Log.w(TAG, "Unknown recovery message kind ${message[0]}")
+            }
+        } catch (e: Exception) {
+            Log.e(TAG, "Failed to handle RECOVERY_SHARE", e)
+        }
+    }
+
+    /**
+     * Requests waiting for the user to confirm with the owner
+     */
+    fun pendingRequests(): List<PendingRequest> = synchronized(pendingRequests) { pendingRequests.values.toList() }
+
+    /**
+     * Release the share held for a contact to a confirmed request
+     * @param replyOnion messaging .onion of the owner's new device, confirmed out of band
+     */
+    fun release(context: Context, contactId: Long, request: PendingRequest, replyOnion: String): Boolean {
+        val share = KeyManager.getInstance(context).getRecoveryShare(contactId) ?: return false
+        return try {
+            val release = RustBridge.releaseRecoveryShare(share, request.message) ?: return false
+            val sent = RustBridge.sendMessageBlob(replyOnion, release, MSG_TYPE_RECOVERY_SHARE)
+            if (sent) {
+                synchronized(pendingRequests) { pendingRequests.remove(request.senderX25519Base64, request) }
+            }
+            sent
+        } finally {
+            share.fill(0)
+        }
+    }
+
+    /**
+     * Take the recovered seed phrase (UTF-8) once; the caller zeroes it after
+     * the owner confirms the identity and KeyManager.initializeFromSeed runs
+     */
+    fun takeRecoveredPhrase(): ByteArray? = synchronized(this) {
+        recoveredPhrase.also { recoveredPhrase = null }
+    }
+}
@@ -50,7 +50,7 @@ import net.zetetic.database.sqlcipher.SupportOpenHelperFactory
  */
 @Database(
     entities = [Contact::class, Message::class, Wallet::class, ReceivedId::class, UsedSignature::class, Group::class, GroupMember::class, GroupMessage::class, CallHistory::class, CallQualityLog::class, PingInbox::class, ContactKeyChain::class, SkippedMessageKey::class],
-    version = 31,
+    version = 35,
     exportSchema = false
 )
 abstract class SecureLegionDatabase : RoomDatabase() {
@@ -72,6 +72,7 @@ abstract class SecureLegionDatabase : RoomDatabase() {
     companion object {
         private const val TAG = "SecureLegionDatabase"
         private const val DATABASE_NAME = "secure_legion.db"
+        private const val DECOY_DATABASE_NAME = "secure_legion_decoy.db"
 
         @Volatile
         private var INSTANCE: SecureLegionDatabase? = null
@@ -589,6 +590,64 @@ abstract class SecureLegionDatabase : RoomDatabase() {
             }
         }
 
+        /**
+         * Migration from version 31 to 32: Add peerCapabilities to contacts
+         * Capability flags advertised in the contact card (0 = older build, nothing negotiated)
+         */
+        private val MIGRATION_31_32 = object : Migration(31, 32) {
+            override fun migrate(database: SupportSQLiteDatabase) {
+                Log.i(TAG, "Migrating database from version 31 to 32")
+
+                database.execSQL("ALTER TABLE contacts ADD COLUMN peerCapabilities INTEGER NOT NULL DEFAULT 0")
+
+                Log.i(TAG, "Migration completed: Added peerCapabilities column")
+            }
+        }
+
+        /**
+         * Migration from version 32 to 33: Add mlDsaPublicKeyBase64 to contacts
+         * ML-DSA-65 key from the contact card (null = older build, Ed25519-only tokens)
+         */
+        private val MIGRATION_32_33 = object : Migration(32, 33) {
+            override fun migrate(database: SupportSQLiteDatabase) {
+                Log.i(TAG, "Migrating database from version 32 to 33")
+
+                database.execSQL("ALTER TABLE contacts ADD COLUMN mlDsaPublicKeyBase64 TEXT")
+
+                Log.i(TAG, "Migration completed: Added mlDsaPublicKeyBase64 column")
+            }
+        }
+
+        /**
+         * Migration from version 33 to 34: Session epochs for SESSION_RESET healing
+         */
+        private val MIGRATION_33_34 = object : Migration(33, 34) {
+            override fun migrate(database: SupportSQLiteDatabase) {
+                Log.i(TAG, "Migrating database from version 33 to 34")
+
+                database.execSQL("ALTER TABLE contact_key_chains ADD COLUMN sessionEpoch INTEGER NOT NULL DEFAULT 0")
+                database.execSQL("ALTER TABLE contact_key_chains ADD COLUMN previousReceiveChainKeyBase64 TEXT")
+                database.execSQL("ALTER TABLE contact_key_chains ADD COLUMN previousReceiveCounter INTEGER NOT NULL DEFAULT 0")
+                database.execSQL("ALTER TABLE contact_key_chains ADD COLUMN previousEpochExpiresAt INTEGER NOT NULL DEFAULT 0")
+                database.execSQL("ALTER TABLE contact_key_chains ADD COLUMN sessionHealerStateBase64 TEXT")
+
+                Log.i(TAG, "Migration completed: Added session epoch columns to contact_key_chains")
+            }
+        }
+
+        /**
+         * Migration from version 34 to 35: Rust skipped-key store per receive chain
+         */
+        private val MIGRATION_34_35 = object : Migration(34, 35) {
+            override fun migrate(database: SupportSQLiteDatabase) {
+                Log.i(TAG, "Migrating database from version 34 to 35")
+
+                database.execSQL("ALTER TABLE contact_key_chains ADD COLUMN skippedKeyStoreBase64 TEXT")
+
+                Log.i(TAG, "Migration completed: Added skippedKeyStoreBase64 column")
+            }
+        }
+
         /**
          * Migration from version 20 to 21: Add group messaging tables
          */
@@ -710,10 +769,30 @@ abstract class SecureLegionDatabase : RoomDatabase() {
             }
         }
 
+        /**
+         * Open the decoy profile's database in place of the real one (duress unlock)
+         * getInstance() returns the decoy until the process restarts
+         * @param passphrase Decoy database key from KeyManager.unlockWithPassword (zeroized)
+         */
+        fun openDecoy(context: Context, passphrase: ByteArray): SecureLegionDatabase {
+            return synchronized(this) {
+                INSTANCE?.close()
+                try {
+                    val instance = buildDatabase(context, passphrase, DECOY_DATABASE_NAME)
+                    INSTANCE = instance
+                    Log.i(TAG, "Decoy database opened")
+                    instance
+                } finally {
+                    java.util.Arrays.fill(passphrase, 0.toByte())
+                }
+            }
+        }
+
         /**
          * Build encrypted database with SQLCipher
+         * @param name Database file (the real profile's unless opening the decoy)
          */
-        private fun buildDatabase(context: Context, passphrase: ByteArray): SecureLegionDatabase {
+        private fun buildDatabase(context: Context, passphrase: ByteArray, name: String = DATABASE_NAME): SecureLegionDatabase {
             Log.i(TAG, "Building encrypted database with SQLCipher")
 
             // Initialize SQLCipher native library
@@ -726,10 +805,10 @@ abstract class SecureLegionDatabase : RoomDatabase() {
                 Room.databaseBuilder(
                     context.applicationContext,
                     SecureLegionDatabase::class.java,
-                    DATABASE_NAME
+                    name
                 )
                     .openHelperFactory(factory)
-                    .addMigrations(MIGRATION_1_2, MIGRATION_2_3, MIGRATION_3_4, MIGRATION_4_5, MIGRATION_5_6, MIGRATION_6_7, MIGRATION_7_8, MIGRATION_8_9, MIGRATION_9_10, MIGRATION_10_11, MIGRATION_11_12, MIGRATION_12_13, MIGRATION_13_14, MIGRATION_14_15, MIGRATION_15_16, MIGRATION_16_17, MIGRATION_17_18, MIGRATION_19_20, MIGRATION_20_21, MIGRATION_21_22, MIGRATION_22_23, MIGRATION_23_24, MIGRATION_24_25, MIGRATION_25_26, MIGRATION_26_27, MIGRATION_27_28, MIGRATION_28_29, MIGRATION_29_30, MIGRATION_30_31)
+                    .addMigrations(MIGRATION_1_2, MIGRATION_2_3, MIGRATION_3_4, MIGRATION_4_5, MIGRATION_5_6, MIGRATION_6_7, MIGRATION_7_8, MIGRATION_8_9, MIGRATION_9_10, MIGRATION_10_11, MIGRATION_11_12, MIGRATION_12_13, MIGRATION_13_14, MIGRATION_14_15, MIGRATION_15_16, MIGRATION_16_17, MIGRATION_17_18, MIGRATION_19_20, MIGRATION_20_21, MIGRATION_21_22, MIGRATION_22_23, MIGRATION_23_24, MIGRATION_24_25, MIGRATION_25_26, MIGRATION_26_27, MIGRATION_27_28, MIGRATION_28_29, MIGRATION_29_30, MIGRATION_30_31, MIGRATION_31_32, MIGRATION_32_33, MIGRATION_33_34, MIGRATION_34_35)
                     .addCallback(object : RoomDatabase.Callback() {
                         override fun onCreate(db: SupportSQLiteDatabase) {
                             super.onCreate(db)
@@ -782,34 +861,34 @@ abstract class SecureLegionDatabase : RoomDatabase() {
 
                     // Securely delete corrupted database files
                     try {
-                        val dbPath = context.getDatabasePath(DATABASE_NAME)
+                        val dbPath = context.getDatabasePath(name)
                         if (dbPath.exists()) {
                             com.securelegion.utils.SecureWipe.secureDeleteFile(dbPath)
                             Log.i(TAG, "Securely deleted corrupted database file")
                         }
 
                         // Securely delete associated files
-                        val dbJournal = context.getDatabasePath("$DATABASE_NAME-journal")
+                        val dbJournal = context.getDatabasePath("$name-journal")
                         if (dbJournal.exists()) {
                             com.securelegion.utils.SecureWipe.secureDeleteFile(dbJournal)
                         }
 
-                        val dbWal = context.getDatabasePath("$DATABASE_NAME-wal")
+                        val dbWal = context.getDatabasePath("$name-wal")
                         if (dbWal.exists()) {
                             com.securelegion.utils.SecureWipe.secureDeleteFile(dbWal)
                         }
 
-                        val dbShm = context.getDatabasePath("$DATABASE_NAME-shm")
+                        val dbShm = context.getDatabasePath("$name-shm")
                         if (dbShm.exists()) {
                             com.securelegion.utils.SecureWipe.secureDeleteFile(dbShm)
                         }
                     } catch (e: Exception) {
                         Log.w(TAG, "Secure deletion failed, using simple delete as fallback", e)
                         // Fallback to simple delete if secure wipe fails
-                        context.getDatabasePath(DATABASE_NAME).delete()
-                        context.getDatabasePath("$DATABASE_NAME-journal").delete()
-                        context.getDatabasePath("$DATABASE_NAME-wal").delete()
-                        context.getDatabasePath("$DATABASE_NAME-shm").delete()
+                        context.getDatabasePath(name).delete()
+                        context.getDatabasePath("$name-journal").delete()
+                        context.getDatabasePath("$name-wal").delete()
+                        context.getDatabasePath("$name-shm").delete()
                     }
 
                     // Try creating database again
@@ -817,10 +896,10 @@ abstract class SecureLegionDatabase : RoomDatabase() {
                     return Room.databaseBuilder(
                         context.applicationContext,
                         SecureLegionDatabase::class.java,
-                        DATABASE_NAME
+                        name
                     )
                         .openHelperFactory(SupportOpenHelperFactory(passphrase))
-                        .addMigrations(MIGRATION_1_2, MIGRATION_2_3, MIGRATION_3_4, MIGRATION_4_5, MIGRATION_5_6, MIGRATION_6_7, MIGRATION_7_8, MIGRATION_8_9, MIGRATION_9_10, MIGRATION_10_11, MIGRATION_11_12, MIGRATION_12_13, MIGRATION_13_14, MIGRATION_14_15, MIGRATION_15_16, MIGRATION_16_17, MIGRATION_17_18, MIGRATION_19_20, MIGRATION_20_21, MIGRATION_21_22, MIGRATION_22_23, MIGRATION_23_24, MIGRATION_24_25, MIGRATION_25_26, MIGRATION_26_27, MIGRATION_27_28, MIGRATION_28_29, MIGRATION_29_30, MIGRATION_30_31)
+                        .addMigrations(MIGRATION_1_2, MIGRATION_2_3, MIGRATION_3_4, MIGRATION_4_5, MIGRATION_5_6, MIGRATION_6_7, MIGRATION_7_8, MIGRATION_8_9, MIGRATION_9_10, MIGRATION_10_11, MIGRATION_11_12, MIGRATION_12_13, MIGRATION_13_14, MIGRATION_14_15, MIGRATION_15_16, MIGRATION_16_17, MIGRATION_17_18, MIGRATION_19_20, MIGRATION_20_21, MIGRATION_21_22, MIGRATION_22_23, MIGRATION_23_24, MIGRATION_24_25, MIGRATION_25_26, MIGRATION_26_27, MIGRATION_27_28, MIGRATION_28_29, MIGRATION_29_30, MIGRATION_30_31, MIGRATION_31_32, MIGRATION_32_33, MIGRATION_33_34, MIGRATION_34_35)
                         .addCallback(object : RoomDatabase.Callback() {
                             override fun onCreate(db: SupportSQLiteDatabase) {
                                 super.onCreate(db)
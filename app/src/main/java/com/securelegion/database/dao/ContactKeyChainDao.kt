@@ -90,6 +90,25 @@ interface ContactKeyChainDao {
         timestamp: Long
     )
 
+    /**
+     * Update the receive chain and its skipped-key store after a message decrypted
+     */
+    @Query("""
+        UPDATE contact_key_chains
+        SET receiveChainKeyBase64 = :newReceiveChainKeyBase64,
+            receiveCounter = :newReceiveCounter,
+            skippedKeyStoreBase64 = :skippedKeyStoreBase64,
+            lastEvolutionTimestamp = :timestamp
+        WHERE contactId = :contactId
+    """)
+    suspend fun updateReceiveState(
+        contactId: Long,
+        newReceiveChainKeyBase64: String,
+        newReceiveCounter: Long,
+        skippedKeyStoreBase64: String,
+        timestamp: Long
+    )
+
     /**
      * Get all key chains (for debugging/diagnostics)
      */
@@ -126,8 +145,62 @@ interface ContactKeyChainDao {
         UPDATE contact_key_chains
         SET sendCounter = 0,
             receiveCounter = 0,
+            skippedKeyStoreBase64 = NULL,
             lastEvolutionTimestamp = :timestamp
         WHERE contactId = :contactId
     """)
     suspend fun resetCounters(contactId: Long, timestamp: Long)
+
+    /**
+     * Store the session reset state machine (e.g. after starting a reset)
+     */
+    @Query("UPDATE contact_key_chains SET sessionHealerStateBase64 = :healerStateBase64 WHERE contactId = :contactId")
+    suspend fun updateSessionHealerState(contactId: Long, healerStateBase64: String)
+
+    /**
+     * Switch a contact to a new session epoch after a SESSION_RESET
+     * The current receive chain moves to the previous-epoch slot for the grace period;
+     * new chains start at sequence 0
+     */
+    @Query("""
+        UPDATE contact_key_chains
+        SET previousReceiveChainKeyBase64 = receiveChainKeyBase64,
+            previousReceiveCounter = receiveCounter,
+            previousEpochExpiresAt = :previousEpochExpiresAt,
+            sessionEpoch = :epoch,
+            rootKeyBase64 = :rootKeyBase64,
+            sendChainKeyBase64 = :sendChainKeyBase64,
+            receiveChainKeyBase64 = :receiveChainKeyBase64,
+            sendCounter = 0,
+            receiveCounter = 0,
+            skippedKeyStoreBase64 = NULL,
+            sessionHealerStateBase64 = :healerStateBase64,
+            lastEvolutionTimestamp = :timestamp
+        WHERE contactId = :contactId
+    """)
+    suspend fun switchSessionEpoch(
+        contactId: Long,
+        epoch: Int,
+        rootKeyBase64: String,
+        sendChainKeyBase64: String,
+        receiveChainKeyBase64: String,
+        previousEpochExpiresAt: Long,
+        healerStateBase64: String,
+        timestamp: Long
+    )
+
+    /**
+     * Update the previous epoch's receive chain after decrypting an in-flight message
+     */
+    @Query("""
+        UPDATE contact_key_chains
+        SET previousReceiveChainKeyBase64 = :previousReceiveChainKeyBase64,
+            previousReceiveCounter = :previousReceiveCounter
+        WHERE contactId = :contactId
+    """)
+    suspend fun updatePreviousReceiveChainKey(
+        contactId: Long,
+        previousReceiveChainKeyBase64: String,
+        previousReceiveCounter: Long
+    )
 }
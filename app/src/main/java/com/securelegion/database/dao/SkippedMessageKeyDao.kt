@@ -46,6 +46,13 @@ interface SkippedMessageKeyDao {
     @Query("DELETE FROM skipped_message_keys WHERE timestamp < :cutoffTime")
     suspend fun deleteOldKeys(cutoffTime: Long): Int
 
+    /**
+     * Delete all skipped keys for a contact
+     * Used when a session reset restarts the chain sequences at 0
+     */
+    @Query("DELETE FROM skipped_message_keys WHERE contactId = :contactId")
+    suspend fun deleteKeysForContact(contactId: Long)
+
     /**
      * Get count of stored skipped keys for a contact
      * Useful for monitoring and debugging
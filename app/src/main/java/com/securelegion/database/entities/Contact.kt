@@ -134,7 +134,19 @@ data class Contact(
      * PENDING_SENT: You added them, waiting for them to add you back
      * CONFIRMED: Mutual friends - both have added each other, can message
      */
-    val friendshipStatus: String = FRIENDSHIP_PENDING_SENT
+    val friendshipStatus: String = FRIENDSHIP_PENDING_SENT,
+
+    /**
+     * Protocol capability flags from the contact's card
+     * 0 for contacts on builds that predate capability negotiation
+     */
+    val peerCapabilities: Int = 0,
+
+    /**
+     * Contact's ML-DSA-65 signing key from their card (Base64)
+     * Null for contacts on builds without hybrid signatures
+     */
+    val mlDsaPublicKeyBase64: String? = null
 ) {
     companion object {
         // Trust levels
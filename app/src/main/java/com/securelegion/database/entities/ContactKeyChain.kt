@@ -91,7 +91,42 @@ data class ContactKeyChain(
      * Unix timestamp (milliseconds) when keys were last evolved
      * Updated on every message send/receive
      */
-    val lastEvolutionTimestamp: Long = System.currentTimeMillis()
+    val lastEvolutionTimestamp: Long = System.currentTimeMillis(),
+
+    /**
+     * Session epoch the root and chain keys belong to
+     * 0 from the initial key exchange, incremented by each SESSION_RESET
+     * Chain messages of epoch > 0 carry it in their header
+     */
+    val sessionEpoch: Int = 0,
+
+    /**
+     * Receive chain key of the previous epoch, kept for in-flight messages during the
+     * grace period after a SESSION_RESET (null if there is none)
+     */
+    val previousReceiveChainKeyBase64: String? = null,
+
+    /**
+     * Receive counter of the previous epoch's chain
+     */
+    val previousReceiveCounter: Long = 0,
+
+    /**
+     * Unix timestamp (seconds) after which the previous epoch stops decrypting
+     */
+    val previousEpochExpiresAt: Long = 0,
+
+    /**
+     * Serialized session reset state machine (pending reset and epoch roots)
+     * Null until the first SESSION_RESET with this contact
+     */
+    val sessionHealerStateBase64: String? = null,
+
+    /**
+     * Serialized Rust skipped-key store of the current receive chain (see
+     * RustBridge.decryptMessageWithSkippedKeys); null until a message arrives early
+     */
+    val skippedKeyStoreBase64: String? = null
 )
 
 /**
@@ -1,5 +1,7 @@
 package com.securelegion.models
 
+import android.util.Base64
+import com.securelegion.crypto.RustBridge
 import org.json.JSONArray
 import org.json.JSONObject
 
@@ -34,6 +36,12 @@ data class ContactCard(
 
     val timestamp: Long,                // Creation timestamp (Unix seconds)
 
+    // Protocol capability flags (v2.2) - 0 for cards from older builds
+    val capabilities: Int = RustBridge.getLocalCapabilities(),
+
+    // ML-DSA-65 signing key (v2.3) - null for cards from older builds
+    val mlDsaPublicKey: ByteArray? = RustBridge.getHybridSigningPublicKey(),
+
     // DEPRECATED - Keep for backward compatibility
     @Deprecated("Use messagingOnion instead", ReplaceWith("messagingOnion"))
     val torOnionAddress: String = messagingOnion
@@ -78,6 +86,10 @@ data class ContactCard(
         }
 
         json.put("timestamp", timestamp)
+        json.put("capabilities", capabilities)
+        if (mlDsaPublicKey != null) {
+            json.put("ml_dsa_public_key", Base64.encodeToString(mlDsaPublicKey, Base64.NO_WRAP))
+        }
 
         // DEPRECATED - For backward compatibility with v1.0 clients
         json.put("onion_address", messagingOnion)
@@ -147,6 +159,10 @@ data class ContactCard(
                 json.getString("profile_picture")
             } else null
 
+            val mlDsaPublicKey = if (json.has("ml_dsa_public_key")) {
+                Base64.decode(json.getString("ml_dsa_public_key"), Base64.NO_WRAP)
+            } else null
+
             return ContactCard(
                 displayName = json.getString("handle"),
                 solanaPublicKey = publicKey,
@@ -159,7 +175,9 @@ data class ContactCard(
                 contactPin = contactPin,
                 ipfsCid = ipfsCid,
                 profilePictureBase64 = profilePictureBase64,
-                timestamp = json.getLong("timestamp")
+                timestamp = json.getLong("timestamp"),
+                capabilities = json.optInt("capabilities", 0),
+                mlDsaPublicKey = mlDsaPublicKey
             )
         }
     }
@@ -180,6 +198,8 @@ data class ContactCard(
         if (voiceOnion != other.voiceOnion) return false
         if (contactPin != other.contactPin) return false
         if (timestamp != other.timestamp) return false
+        if (capabilities != other.capabilities) return false
+        if (!(mlDsaPublicKey ?: ByteArray(0)).contentEquals(other.mlDsaPublicKey ?: ByteArray(0))) return false
 
         return true
     }
@@ -195,6 +215,8 @@ data class ContactCard(
         result = 31 * result + voiceOnion.hashCode()
         result = 31 * result + contactPin.hashCode()
         result = 31 * result + timestamp.hashCode()
+        result = 31 * result + capabilities
+        result = 31 * result + (mlDsaPublicKey?.contentHashCode() ?: 0)
         return result
     }
 }
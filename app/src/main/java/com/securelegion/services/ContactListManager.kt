@@ -104,6 +104,8 @@ class ContactListManager private constructor(private val context: Context) {
                         put("ipfsCid", contact.ipfsCid ?: "")
                         put("contactPin", contact.contactPin ?: "")
                         put("addedTimestamp", contact.addedTimestamp)
+                        put("peerCapabilities", contact.peerCapabilities)
+                        put("mlDsaPublicKeyBase64", contact.mlDsaPublicKeyBase64 ?: "")
                     }
                     contactsArray.put(contactJson)
                 }
@@ -166,7 +168,9 @@ class ContactListManager private constructor(private val context: Context) {
                     isBlocked = contactJson.optBoolean("isBlocked", false),
                     ipfsCid = contactJson.optString("ipfsCid", null),
                     contactPin = contactJson.optString("contactPin", null),
-                    addedTimestamp = contactJson.getLong("addedTimestamp")
+                    addedTimestamp = contactJson.getLong("addedTimestamp"),
+                    peerCapabilities = contactJson.optInt("peerCapabilities", 0),
+                    mlDsaPublicKeyBase64 = contactJson.optString("mlDsaPublicKeyBase64", "").ifEmpty { null }
                 )
                 contacts.add(contact)
             }
@@ -301,7 +301,10 @@ class DownloadMessageService : Service() {
             val encryptedPingWire = android.util.Base64.decode(pingWireBytes, android.util.Base64.NO_WRAP)
 
             withContext(Dispatchers.IO) {
-                com.securelegion.crypto.RustBridge.decryptIncomingPing(encryptedPingWire)
+                com.securelegion.crypto.RustBridge.decryptIncomingPing(
+                    encryptedPingWire,
+                    com.securelegion.crypto.ContactKeyRegistry.expectedSender(this@DownloadMessageService, contactId)
+                )
             }
         } catch (e: Exception) {
             Log.e(TAG, "Failed to restore Ping - Ping session is unrecoverable", e)
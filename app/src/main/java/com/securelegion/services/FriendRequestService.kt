@@ -12,6 +12,7 @@ import kotlinx.coroutines.*
  * Manages the contact exchange endpoint on friend request .onion:
  * - Starts P2P listener on friend request .onion address
  * - Serves encrypted contact card at GET /contact-card
+ * - Serves PQXDH prekey bundles at GET /prekey-bundle
  *
  * Friend requests are handled by v1.0 wire protocol (0x07/0x08) on messaging .onion.
  * See TorService.handleFriendRequest() for friend request processing.
@@ -93,6 +94,11 @@ class FriendRequestService(private val context: Context) {
                 // Store encrypted card in endpoint
                 RustBridge.serveContactCard(encryptedCardResult, encryptedCardResult.size, cid)
 
+                // Publish PQXDH prekey bundles (rotated weekly) at GET /prekey-bundle
+                if (!com.securelegion.crypto.PrekeyManager.publish(context)) {
+                    Log.w(TAG, "Prekey bundles not published - new contacts fall back to the static Kyber key")
+                }
+
                 serverStarted = true
                 Log.i(TAG, "Contact exchange endpoint started on port $SERVER_PORT (CID: $cid)")
 
@@ -4,11 +4,14 @@ import android.content.Context
 import android.content.Intent
 import android.util.Base64
 import android.util.Log
+import com.securelegion.crypto.DeliveryTracker
 import com.securelegion.crypto.KeyChainManager
 import com.securelegion.crypto.KeyManager
 import com.securelegion.crypto.NLx402Manager
 import com.securelegion.crypto.RustBridge
 import com.securelegion.database.SecureLegionDatabase
+import com.securelegion.database.entities.Contact
+import com.securelegion.database.entities.ContactKeyChain
 import com.securelegion.database.entities.Message
 import com.securelegion.database.entities.ed25519PublicKeyBytes
 import com.securelegion.database.entities.x25519PublicKeyBytes
@@ -62,6 +65,197 @@ class MessageService(private val context: Context) {
          */
         private val messageBuffer = java.util.concurrent.ConcurrentHashMap<Long, java.util.concurrent.ConcurrentHashMap<Long, BufferedMessage>>()
 
+        /**
+         * Capability flag (Rust CAPABILITY_CHAIN_V2): contact decrypts padded v2 chain messages
+         */
+        const val CAPABILITY_CHAIN_V2 = 0x04
+
+        /**
+         * Wire version byte of legacy v1 chain messages (unpadded, unauthenticated header)
+         */
+        private const val CHAIN_WIRE_VERSION_V1: Byte = 0x01
+
+        /**
+         * Padding tier for outgoing chain messages ("HIGH_RISK", "NORMAL", "BULK")
+         * Stored in the "security" preferences
+         */
+        const val PREF_SECURITY_TIER = "message_security_tier"
+        private const val DEFAULT_SECURITY_TIER = "NORMAL"
+
+        /**
+         * Convert a message type to its wire protocol type byte (MSG_TYPE_*)
+         * The same byte is bound into v2 chain messages as associated data
+         */
+        fun wireTypeByte(messageType: String): Byte = when (messageType) {
+            Message.MESSAGE_TYPE_VOICE -> 0x04.toByte()           // VOICE
+            Message.MESSAGE_TYPE_IMAGE -> 0x09.toByte()           // IMAGE
+            Message.MESSAGE_TYPE_PAYMENT_REQUEST -> 0x0A.toByte() // PAYMENT_REQUEST
+            Message.MESSAGE_TYPE_PAYMENT_SENT -> 0x0B.toByte()    // PAYMENT_SENT
+            Message.MESSAGE_TYPE_PAYMENT_ACCEPTED -> 0x0C.toByte() // PAYMENT_ACCEPTED
+            else -> 0x03.toByte()                                  // TEXT (default)
+        }
+
+        /**
+         * Whether chain messages to this contact use the padded v2 format
+         */
+        private fun usesChainV2(contact: Contact): Boolean {
+            return RustBridge.negotiateCapabilities(contact.peerCapabilities) and CAPABILITY_CHAIN_V2 != 0
+        }
+
+        /**
+         * Encrypt a chain message of any type (text, voice, image, payment) for a contact
+         * Padded to the configured security tier when the contact supports v2; legacy v1 otherwise
+         * Sent as key-committing v3 when the contact also negotiated it (decided in Rust)
+         * Chains of epoch > 0 (after a SESSION_RESET) always use v2, which carries the epoch
+         */
+        fun encryptChainMessage(
+            context: Context,
+            contact: Contact,
+            plaintext: String,
+            chainKey: ByteArray,
+            sequence: Long,
+            messageType: String,
+            epoch: Int = 0
+        ): RustBridge.EncryptionResult {
+            if (!usesChainV2(contact) && epoch == 0) {
+                return RustBridge.encryptMessageWithEvolution(plaintext, chainKey, sequence)
+            }
+
+            val tier = context.getSharedPreferences("security", Context.MODE_PRIVATE)
+                .getString(PREF_SECURITY_TIER, DEFAULT_SECURITY_TIER) ?: DEFAULT_SECURITY_TIER
+            return RustBridge.encryptMessageWithEvolutionV2(
+                plaintext,
+                chainKey,
+                sequence,
+                KeyManager.getInstance(context).getSigningPublicKey(),
+                contact.ed25519PublicKeyBytes,
+                wireTypeByte(messageType).toInt() and 0xFF,
+                tier,
+                epoch,
+                contact.peerCapabilities
+            )
+        }
+
+        /**
+         * Whether a chain message belongs to the previous session epoch while its grace period runs
+         * (messages already in flight when the contact switched epochs, see SessionHealing)
+         */
+        fun inPreviousEpochGrace(keyChain: ContactKeyChain, messageEpoch: Int): Boolean {
+            return keyChain.previousReceiveChainKeyBase64 != null &&
+                messageEpoch == keyChain.sessionEpoch - 1 &&
+                System.currentTimeMillis() / 1000 < keyChain.previousEpochExpiresAt
+        }
+
+        /**
+         * Decrypt a chain message from a contact with atomic key evolution
+         * v1 messages take the legacy path; v2/v3 are checked against the header binding and unpadded
+         */
+        fun decryptChainMessage(
+            context: Context,
+            contact: Contact,
+            encryptedData: ByteArray,
+            chainKey: ByteArray,
+            expectedSequence: Long,
+            messageType: String,
+            epoch: Int = 0
+        ): RustBridge.DecryptionResult? {
+            if (encryptedData.isEmpty() || encryptedData[0] == CHAIN_WIRE_VERSION_V1) {
+                return RustBridge.decryptMessageWithEvolution(encryptedData, chainKey, expectedSequence)
+            }
+
+            return RustBridge.decryptMessageWithEvolutionV2(
+                encryptedData,
+                chainKey,
+                expectedSequence,
+                contact.ed25519PublicKeyBytes,
+                KeyManager.getInstance(context).getSigningPublicKey(),
+                wireTypeByte(messageType).toInt() and 0xFF,
+                epoch,
+                contact.peerCapabilities
+            )
+        }
+
+        /**
+         * Decrypt a chain message from a contact with a stored (skipped) message key
+         */
+        fun decryptChainMessageWithKey(
+            context: Context,
+            contact: Contact,
+            encryptedData: ByteArray,
+            messageKey: ByteArray,
+            messageType: String,
+            epoch: Int = 0
+        ): String? {
+            if (encryptedData.isEmpty() || encryptedData[0] == CHAIN_WIRE_VERSION_V1) {
+                return RustBridge.decryptWithMessageKey(encryptedData, messageKey)
+            }
+
+            return RustBridge.decryptWithMessageKeyV2(
+                encryptedData,
+                messageKey,
+                contact.ed25519PublicKeyBytes,
+                KeyManager.getInstance(context).getSigningPublicKey(),
+                wireTypeByte(messageType).toInt() and 0xFF,
+                epoch,
+                contact.peerCapabilities
+            )
+        }
+
+        /**
+         * Decrypt a chain message of the current session epoch through the contact's Rust
+         * skipped-key store, persisting the chain key and store on success
+         * Keys parked in skipped_message_keys by earlier builds are still used for late messages
+         * @return plaintext, or null if the message cannot be decrypted (state unchanged)
+         */
+        suspend fun decryptCurrentEpochMessage(
+            context: Context,
+            database: SecureLegionDatabase,
+            contact: Contact,
+            keyChain: ContactKeyChain,
+            encryptedData: ByteArray,
+            messageSequence: Long,
+            messageType: String,
+            epoch: Int
+        ): String? {
+            val result = RustBridge.decryptMessageWithSkippedKeys(
+                encryptedData,
+                keyChain.receiveChainKeyBytes,
+                keyChain.receiveCounter,
+                keyChain.skippedKeyStoreBase64?.let { Base64.decode(it, Base64.NO_WRAP) },
+                contact.ed25519PublicKeyBytes,
+                KeyManager.getInstance(context).getSigningPublicKey(),
+                wireTypeByte(messageType).toInt() and 0xFF,
+                epoch,
+                contact.peerCapabilities
+            )
+            if (result != null) {
+                database.contactKeyChainDao().updateReceiveState(
+                    contactId = contact.id,
+                    newReceiveChainKeyBase64 = Base64.encodeToString(result.evolvedChainKey, Base64.NO_WRAP),
+                    newReceiveCounter = result.nextExpectedSequence,
+                    skippedKeyStoreBase64 = Base64.encodeToString(result.skippedKeyStore, Base64.NO_WRAP),
+                    timestamp = System.currentTimeMillis()
+                )
+                Log.d(TAG, "✓ Decrypted seq=$messageSequence, receiveCounter=${result.nextExpectedSequence}")
+                return result.plaintext
+            }
+
+            if (messageSequence >= keyChain.receiveCounter) {
+                Log.e(TAG, "❌ DECRYPTION FAILED: seq=$messageSequence (expecting ${keyChain.receiveCounter})")
+                return null
+            }
+            val legacyKey = database.skippedMessageKeyDao().getKey(contact.id, messageSequence)
+            val plaintext = legacyKey?.let {
+                decryptChainMessageWithKey(context, contact, encryptedData, it.messageKey, messageType, epoch)
+            }
+            if (plaintext == null) {
+                Log.e(TAG, "❌ DECRYPTION FAILED: no usable key for late seq=$messageSequence")
+                return null
+            }
+            database.skippedMessageKeyDao().deleteKey(contact.id, messageSequence)
+            return plaintext
+        }
+
         /**
          * Generate a cryptographically random 24-byte nonce for ping ID
          * Returns hex-encoded string (48 characters)
@@ -155,133 +349,1185 @@ class MessageService(private val context: Context) {
                 .digest(plaintext.toByteArray())
                 .joinToString("") { "%02x".format(it) }
 
-            // PHASE 1.2: SORTED PUBKEYS (MANDATORY)
-            // Sort keys lexicographically to ensure Alice and Bob derive the same conversationId
-            // Alice: sort([AlicePubKey, BobPubKey]) → "AlicePubKey|BobPubKey"
-            // Bob:   sort([AlicePubKey, BobPubKey]) → "AlicePubKey|BobPubKey" (SAME!)
-            // Without sorting: Alice gets "A|B", Bob gets "B|A" → TWO SEPARATE THREADS
-            // DO NOT REMOVE SORTING or 1-to-1 chats will break
-            val sortedKeys = listOf(senderEd25519Base64, recipientEd25519Base64).sorted()
-            val conversationId = sortedKeys.joinToString("|")
+            // PHASE 1.2: SORTED PUBKEYS (MANDATORY)
+            // Sort keys lexicographically to ensure Alice and Bob derive the same conversationId
+            // Alice: sort([AlicePubKey, BobPubKey]) → "AlicePubKey|BobPubKey"
+            // Bob:   sort([AlicePubKey, BobPubKey]) → "AlicePubKey|BobPubKey" (SAME!)
+            // Without sorting: Alice gets "A|B", Bob gets "B|A" → TWO SEPARATE THREADS
+            // DO NOT REMOVE SORTING or 1-to-1 chats will break
+            val sortedKeys = listOf(senderEd25519Base64, recipientEd25519Base64).sorted()
+            val conversationId = sortedKeys.joinToString("|")
+
+            // Hash input: v1 || conversationId || senderEd25519 || recipientEd25519 || plaintextHash || messageNonce
+            val hashInput = "v1||$conversationId||$senderEd25519Base64||$recipientEd25519Base64||$plaintextHash||$messageNonce"
+
+            // Compute SHA-256(hashInput)
+            val messageIdHash = MessageDigest.getInstance("SHA-256")
+                .digest(hashInput.toByteArray())
+
+            // Base64-encode and take first 32 characters (256 bits / 8 * 4 = 128 chars, so 32 is reasonable)
+            return android.util.Base64.encodeToString(messageIdHash, android.util.Base64.NO_WRAP).take(32)
+        }
+    }
+
+    private val keyManager = KeyManager.getInstance(context)
+
+    /**
+     * Send a voice message to a contact via Tor
+     * @param contactId Database ID of the recipient contact
+     * @param audioBytes The recorded audio as ByteArray
+     * @param durationSeconds Duration of the voice clip in seconds
+     * @param enableSelfDestruct Whether to enable self-destruct (24h)
+     * @param onMessageSaved Callback when message is saved to DB (before sending)
+     * @return Result with Message entity if successful
+     */
+    suspend fun sendVoiceMessage(
+        contactId: Long,
+        audioBytes: ByteArray,
+        durationSeconds: Int,
+        selfDestructDurationMs: Long? = null,
+        onMessageSaved: ((Message) -> Unit)? = null
+    ): Result<Message> = withContext(Dispatchers.IO) {
+        try {
+            Log.d(TAG, "Sending voice message to contact ID: $contactId (${audioBytes.size} bytes, ${durationSeconds}s)")
+
+            // Get database instance
+            val dbPassphrase = keyManager.getDatabasePassphrase()
+            val database = SecureLegionDatabase.getInstance(context, dbPassphrase)
+
+            // Get contact details
+            val contact = database.contactDao().getContactById(contactId)
+                ?: return@withContext Result.failure(Exception("Contact not found"))
+
+            Log.d(TAG, "Sending voice to: ${contact.displayName} (${contact.torOnionAddress})")
+
+            // Get our keypair for signing (needed for messageId determinism)
+            val ourPublicKey = keyManager.getSigningPublicKey()
+            val ourPrivateKey = keyManager.getSigningKeyBytes()
+            val ourPublicKeyBase64 = android.util.Base64.encodeToString(ourPublicKey, android.util.Base64.NO_WRAP)
+
+            // PHASE 1.1: STABLE IDENTITY
+            // Generate message nonce ONCE (never regenerated on retry)
+            val messageNonce = generateMessageNonce()
+
+            // Generate STABLE messageId for voice message
+            // Convert audio bytes to string for hashing (same as encryption input)
+            val audioAsString = String(audioBytes, Charsets.ISO_8859_1)
+            val messageId = generateDeterministicMessageId(
+                plaintext = audioAsString,
+                senderEd25519Base64 = ourPublicKeyBase64,
+                recipientEd25519Base64 = contact.publicKeyBase64,
+                messageNonce = messageNonce
+            )
+            Log.d(TAG, "Generated deterministic messageId for voice: $messageId (from nonce: $messageNonce)")
+
+            // Get key chain for this contact (for progressive ephemeral key evolution)
+            Log.d(TAG, "SEND KEY CHAIN LOAD (VOICE): Loading key chain from database...")
+            Log.d(TAG, "  contactId=$contactId (${contact.displayName})")
+            Log.d(TAG, "  Audio bytes: ${audioBytes.size} bytes")
+            Log.d(TAG, "  Duration: ${durationSeconds}s")
+
+            val keyChain = KeyChainManager.getKeyChain(context, contactId)
+                ?: throw Exception("Key chain not found for contact ${contact.displayName} (ID: $contactId)")
+            Log.d(TAG, "SEND KEY CHAIN LOAD (VOICE): Loaded from database successfully")
+            Log.d(TAG, "  sendCounter=${keyChain.sendCounter} <- will use this for encryption")
+
+            // Encrypt using current send chain key and counter (ATOMIC key evolution)
+            // Wire format: [version:1][sequence:8][nonce:24][ciphertext][tag:16]
+            Log.d(TAG, "SEND KEY EVOLUTION (VOICE): Encrypting with sequence ${keyChain.sendCounter}")
+            val result = encryptChainMessage(
+                context,
+                contact,
+                String(audioBytes, Charsets.ISO_8859_1), // Convert bytes to string for encryption
+                keyChain.sendChainKeyBytes,
+                keyChain.sendCounter,
+                Message.MESSAGE_TYPE_VOICE,
+                keyChain.sessionEpoch
+            )
+            val encryptedBytes = result.ciphertext
+            Log.d(TAG, "SEND KEY EVOLUTION (VOICE): Encryption complete, encrypted ${encryptedBytes.size} bytes")
+
+            // Save evolved key to database (key evolution happened atomically in Rust)
+            Log.d(TAG, "SEND KEY EVOLUTION (VOICE): Updating database with new sendCounter=${keyChain.sendCounter + 1}")
+            database.contactKeyChainDao().updateSendChainKey(
+                contactId = contactId,
+                newSendChainKeyBase64 = android.util.Base64.encodeToString(result.evolvedChainKey, android.util.Base64.NO_WRAP),
+                newSendCounter = keyChain.sendCounter + 1,
+                timestamp = System.currentTimeMillis()
+            )
+            // VERIFY the update actually persisted
+            val verifyKeyChain = database.contactKeyChainDao().getKeyChainByContactId(contactId)
+            Log.d(TAG, "SEND VERIFICATION (VOICE): After update, database shows sendCounter=${verifyKeyChain?.sendCounter}")
+            if (verifyKeyChain?.sendCounter != keyChain.sendCounter + 1) {
+                Log.e(TAG, "SEND ERROR (VOICE): Counter update did NOT persist! Expected ${keyChain.sendCounter + 1}, got ${verifyKeyChain?.sendCounter}")
+            } else {
+                Log.d(TAG, "SEND (VOICE): Counter update verified successfully")
+            }
+
+            // Prepend message type byte: 0x01 for VOICE, followed by duration (4 bytes, big-endian)
+            val durationBytes = ByteArray(4)
+            durationBytes[0] = (durationSeconds shr 24).toByte()
+            durationBytes[1] = (durationSeconds shr 16).toByte()
+            durationBytes[2] = (durationSeconds shr 8).toByte()
+            durationBytes[3] = durationSeconds.toByte()
+
+            // Get our X25519 public key for sender identification
+            val ourX25519PublicKey = keyManager.getEncryptionPublicKey()
+
+            // Wire format: [0x01][Duration:4][X25519:32][Encrypted Audio]
+            val encryptedWithMetadata = byteArrayOf(0x01) + durationBytes + ourX25519PublicKey + encryptedBytes
+            val encryptedBase64 = Base64.encodeToString(encryptedWithMetadata, Base64.NO_WRAP)
+
+            Log.d(TAG, "  Metadata: 1 byte type + 4 bytes duration + 32 bytes X25519")
+            Log.d(TAG, "  Total payload: ${encryptedWithMetadata.size} bytes (${encryptedBase64.length} Base64 chars)")
+
+            // Extract nonce from wire format (bytes 9-32, after version and sequence)
+            val nonce = encryptedBytes.sliceArray(9 until 33)
+            val nonceBase64 = Base64.encodeToString(nonce, Base64.NO_WRAP)
+
+            // Sign the message
+            Log.d(TAG, "Signing voice message...")
+            val messageData = (messageId + durationSeconds.toString() + System.currentTimeMillis()).toByteArray()
+            val signature = RustBridge.signData(messageData, ourPrivateKey)
+            val signatureBase64 = Base64.encodeToString(signature, Base64.NO_WRAP)
+
+            // Save audio to local storage
+            val voiceRecorder = com.securelegion.utils.VoiceRecorder(context)
+            val voiceFilePath = voiceRecorder.saveVoiceMessage(audioBytes, durationSeconds)
+
+            // Calculate self-destruct timestamp if custom duration provided
+            val currentTime = System.currentTimeMillis()
+            val selfDestructAt = selfDestructDurationMs?.let { currentTime + it }
+
+            // Generate Ping ID and timestamp ONCE (never changes, prevents ghost pings)
+            val pingId = generatePingId()  // 24-byte nonce as hex string
+            val pingTimestamp = currentTime
+            Log.d(TAG, "Generated Ping ID: ${pingId.take(16)}... (timestamp: $pingTimestamp)")
+
+            // Create message entity with VOICE type
+            val message = Message(
+                contactId = contactId,
+                messageId = messageId,
+                encryptedContent = "", // Empty for voice messages
+                messageType = Message.MESSAGE_TYPE_VOICE,
+                voiceDuration = durationSeconds,
+                voiceFilePath = voiceFilePath,
+                isSentByMe = true,
+                timestamp = currentTime,
+                status = Message.STATUS_PENDING,
+                signatureBase64 = signatureBase64,
+                nonceBase64 = nonceBase64,
+                messageNonce = messageNonce,        // CRITICAL: Stored once, reused on all retries
+                selfDestructAt = selfDestructAt,
+                requiresReadReceipt = false, // Voice messages don't need read receipts
+                pingId = pingId,
+                pingTimestamp = pingTimestamp,
+                encryptedPayload = encryptedBase64,
+                retryCount = 0,
+                lastRetryTimestamp = currentTime
+            )
+
+            Log.d(TAG, "Voice message queued for persistent delivery (PING_SENT)")
+
+            // Save to database
+            Log.d(TAG, "Saving voice message to database...")
+            val savedMessageId = database.messageDao().insertMessage(message)
+            val savedMessage = message.copy(id = savedMessageId)
+
+            // Notify that message is saved (allows UI to update immediately)
+            onMessageSaved?.invoke(savedMessage)
+
+            // Broadcast to MainActivity to refresh chat list preview
+            val intent = android.content.Intent("com.securelegion.NEW_PING")
+            intent.setPackage(context.packageName)
+            intent.putExtra("CONTACT_ID", contactId)
+            context.sendBroadcast(intent)
+            Log.d(TAG, "Sent explicit NEW_PING broadcast to refresh MainActivity chat list")
+
+            // Immediately attempt to send Ping
+            Log.i(TAG, "Voice message queued successfully: $messageId (Ping ID: $pingId)")
+            Log.d(TAG, "Attempting immediate Ping send...")
+
+            try {
+                sendPingForMessage(savedMessage)
+                Log.d(TAG, "Ping sent immediately, will poll for Pong later")
+            } catch (e: Exception) {
+                Log.w(TAG, "Immediate Ping send failed, retry worker will retry: ${e.message}")
+            }
+
+            // Schedule fast retry worker for this message (5s intervals)
+            ImmediateRetryWorker.scheduleForMessage(context, messageId)
+            Log.d(TAG, "Scheduled immediate retry worker for message $messageId")
+
+            Result.success(savedMessage)
+
+        } catch (e: Exception) {
+            Log.e(TAG, "Failed to send voice message", e)
+            Result.failure(e)
+        }
+    }
+
+    /**
+     * Send an image message to a contact via Tor
+     * @param contactId Database ID of the recipient contact
+     * @param imageBase64 The compressed image as Base64 string
+     * @param selfDestructDurationMs Custom self-destruct duration in milliseconds (null = disabled)
+     * @param onMessageSaved Callback when message is saved to DB (before sending)
+     * @return Result with Message entity if successful
+     */
+    suspend fun sendImageMessage(
+        contactId: Long,
+        imageBase64: String,
+        selfDestructDurationMs: Long? = null,
+        onMessageSaved: ((Message) -> Unit)? = null
+    ): Result<Message> = withContext(Dispatchers.IO) {
+        try {
+            Log.d(TAG, "Sending image message to contact ID: $contactId (${imageBase64.length} Base64 chars)")
+
+            // Get database instance
+            val dbPassphrase = keyManager.getDatabasePassphrase()
+            val database = SecureLegionDatabase.getInstance(context, dbPassphrase)
+
+            // Get contact details
+            val contact = database.contactDao().getContactById(contactId)
+                ?: return@withContext Result.failure(Exception("Contact not found"))
+
+            Log.d(TAG, "Sending image to: ${contact.displayName} (${contact.torOnionAddress})")
+
+            // Get our keypair for signing (needed for messageId determinism)
+            val ourPublicKey = keyManager.getSigningPublicKey()
+            val ourPrivateKey = keyManager.getSigningKeyBytes()
+            val ourPublicKeyBase64 = android.util.Base64.encodeToString(ourPublicKey, android.util.Base64.NO_WRAP)
+
+            // PHASE 1.1: STABLE IDENTITY
+            // Generate message nonce ONCE (never regenerated on retry)
+            val messageNonce = generateMessageNonce()
+
+            // Generate STABLE messageId for image message
+            // Use the original image Base64 for hashing (before encryption)
+            val messageId = generateDeterministicMessageId(
+                plaintext = imageBase64,
+                senderEd25519Base64 = ourPublicKeyBase64,
+                recipientEd25519Base64 = contact.publicKeyBase64,
+                messageNonce = messageNonce
+            )
+            Log.d(TAG, "Generated deterministic messageId for image: $messageId (from nonce: $messageNonce)")
+
+            // Get key chain for this contact (for progressive ephemeral key evolution)
+            Log.d(TAG, "Encrypting image message with key evolution...")
+            val imageBytes = Base64.decode(imageBase64, Base64.NO_WRAP)
+            Log.d(TAG, "  Image bytes: ${imageBytes.size} bytes")
+
+            val keyChain = KeyChainManager.getKeyChain(context, contactId)
+                ?: throw Exception("Key chain not found for contact ${contact.displayName} (ID: $contactId)")
+
+            // Encrypt using current send chain key and counter (ATOMIC key evolution)
+            // Wire format: [version:1][sequence:8][nonce:24][ciphertext][tag:16]
+            val result = encryptChainMessage(
+                context,
+                contact,
+                String(imageBytes, Charsets.ISO_8859_1), // Convert bytes to string for encryption
+                keyChain.sendChainKeyBytes,
+                keyChain.sendCounter,
+                Message.MESSAGE_TYPE_IMAGE,
+                keyChain.sessionEpoch
+            )
+            val encryptedBytes = result.ciphertext
+            Log.d(TAG, "  Encrypted: ${encryptedBytes.size} bytes (sequence ${keyChain.sendCounter})")
+
+            // Save evolved key to database (key evolution happened atomically in Rust)
+            database.contactKeyChainDao().updateSendChainKey(
+                contactId = contactId,
+                newSendChainKeyBase64 = android.util.Base64.encodeToString(result.evolvedChainKey, android.util.Base64.NO_WRAP),
+                newSendCounter = keyChain.sendCounter + 1,
+                timestamp = System.currentTimeMillis()
+            )
+
+            // Get our X25519 public key for sender identification
+            val ourX25519PublicKey = keyManager.getEncryptionPublicKey()
+
+            // Wire format: [X25519:32][version:1][sequence:8][nonce:24][ciphertext][tag:16]
+            // NOTE: Wire protocol type byte (0x09) is added by android.rs sendPing()
+            val encryptedWithMetadata = ourX25519PublicKey + encryptedBytes
+            Log.d(TAG, "  Total with metadata: ${encryptedWithMetadata.size} bytes (X25519 + encrypted)")
+
+            val encryptedBase64 = Base64.encodeToString(encryptedWithMetadata, Base64.NO_WRAP)
+
+            Log.d(TAG, "  Total payload: ${encryptedBytes.size} bytes (${encryptedBase64.length} Base64 chars)")
+
+            // Extract nonce from wire format (bytes 9-32, after version and sequence)
+            val nonce = encryptedBytes.sliceArray(9 until 33)
+            val nonceBase64 = Base64.encodeToString(nonce, Base64.NO_WRAP)
+
+            // Sign the message
+            Log.d(TAG, "Signing image message...")
+            val messageData = (messageId + System.currentTimeMillis()).toByteArray()
+            val signature = RustBridge.signData(messageData, ourPrivateKey)
+            val signatureBase64 = Base64.encodeToString(signature, Base64.NO_WRAP)
+
+            // Calculate self-destruct timestamp if custom duration provided
+            val currentTime = System.currentTimeMillis()
+            val selfDestructAt = selfDestructDurationMs?.let { currentTime + it }
+
+            // Generate Ping ID for persistent messaging
+            val pingId = generatePingId()  // 24-byte nonce as hex string
+            val pingTimestamp = currentTime
+            Log.d(TAG, "Generated Ping ID: $pingId")
+
+            // Create message entity with IMAGE type
+            val message = Message(
+                contactId = contactId,
+                messageId = messageId,
+                encryptedContent = "", // Empty for image messages
+                messageType = Message.MESSAGE_TYPE_IMAGE,
+                attachmentType = "image",
+                attachmentData = imageBase64, // Store original for display
+                isSentByMe = true,
+                timestamp = currentTime,
+                status = Message.STATUS_PENDING,
+                signatureBase64 = signatureBase64,
+                nonceBase64 = nonceBase64,
+                messageNonce = messageNonce,        // CRITICAL: Stored once, reused on all retries
+                selfDestructAt = selfDestructAt,
+                requiresReadReceipt = false, // Image messages don't need read receipts
+                pingId = pingId,
+                pingTimestamp = pingTimestamp,
+                encryptedPayload = encryptedBase64,
+                retryCount = 0,
+                lastRetryTimestamp = currentTime
+            )
+
+            Log.d(TAG, "Image message queued for persistent delivery (PING_SENT)")
+
+            // Save to database
+            Log.d(TAG, "Saving image message to database...")
+            val savedMessageId = database.messageDao().insertMessage(message)
+            val savedMessage = message.copy(id = savedMessageId)
+
+            // Notify that message is saved (allows UI to update immediately)
+            onMessageSaved?.invoke(savedMessage)
+
+            // Broadcast to MainActivity to refresh chat list preview
+            val intent = android.content.Intent("com.securelegion.NEW_PING")
+            intent.setPackage(context.packageName)
+            intent.putExtra("CONTACT_ID", contactId)
+            context.sendBroadcast(intent)
+            Log.d(TAG, "Sent explicit NEW_PING broadcast to refresh MainActivity chat list")
+
+            // Immediately attempt to send Ping
+            Log.i(TAG, "Image message queued successfully: $messageId (Ping ID: $pingId)")
+            Log.d(TAG, "Attempting immediate Ping send...")
+
+            try {
+                sendPingForMessage(savedMessage)
+                Log.d(TAG, "Ping sent immediately, will poll for Pong later")
+            } catch (e: Exception) {
+                Log.w(TAG, "Immediate Ping send failed, retry worker will retry: ${e.message}")
+            }
+
+            // Schedule fast retry worker for this message (5s intervals)
+            ImmediateRetryWorker.scheduleForMessage(context, messageId)
+            Log.d(TAG, "Scheduled immediate retry worker for message $messageId")
+
+            Result.success(savedMessage)
+
+        } catch (e: Exception) {
+            Log.e(TAG, "Failed to send image message", e)
+            Result.failure(e)
+        }
+    }
+
+    /**
+     * Send an encrypted message to a contact via Tor
+     * @param contactId Database ID of the recipient contact
+     * @param plaintext The message content
+     * @param selfDestructDurationMs Custom self-destruct duration in milliseconds (null = disabled)
+     * @param enableReadReceipt Whether to enable read receipts
+     * @param onMessageSaved Callback when message is saved to DB (before sending)
+     * @return Result with Message entity if successful
+     */
+    suspend fun sendMessage(
+        contactId: Long,
+        plaintext: String,
+        selfDestructDurationMs: Long? = null,
+        enableReadReceipt: Boolean = true,
+        onMessageSaved: ((Message) -> Unit)? = null
+    ): Result<Message> = withContext(Dispatchers.IO) {
+        try {
+            Log.d(TAG, "Sending message to contact ID: $contactId")
+
+            // Get database instance
+            val dbPassphrase = keyManager.getDatabasePassphrase()
+            val database = SecureLegionDatabase.getInstance(context, dbPassphrase)
+
+            // Get contact details
+            val contact = database.contactDao().getContactById(contactId)
+                ?: return@withContext Result.failure(Exception("Contact not found"))
+
+            Log.d(TAG, "Sending to: ${contact.displayName} (${contact.torOnionAddress})")
+
+            // Get our keypair for signing (needed for messageId determinism)
+            val ourPublicKey = keyManager.getSigningPublicKey()
+            val ourPrivateKey = keyManager.getSigningKeyBytes()
+            val ourPublicKeyBase64 = android.util.Base64.encodeToString(ourPublicKey, android.util.Base64.NO_WRAP)
+
+            // PHASE 1.1: STABLE IDENTITY
+            // Generate message nonce ONCE (never regenerated on retry)
+            // This nonce is stored in DB and reused for all retries/resends
+            val messageNonce = generateMessageNonce()
+
+            // Generate STABLE messageId using deterministic hash
+            // Formula: SHA-256(v1 || conversationId || senderEd25519 || recipientEd25519 || plaintextHash || messageNonce)
+            // Returns same ID if plaintext + contacts + nonce are same (guarantees dedup on retry)
+            val messageId = generateDeterministicMessageId(
+                plaintext = plaintext,
+                senderEd25519Base64 = ourPublicKeyBase64,
+                recipientEd25519Base64 = contact.publicKeyBase64,
+                messageNonce = messageNonce
+            )
+            Log.d(TAG, "Generated deterministic messageId: $messageId (from nonce: $messageNonce)")
+
+            // Get key chain for this contact (for progressive ephemeral key evolution)
+            Log.d(TAG, "SEND KEY CHAIN LOAD: Loading key chain from database...")
+            Log.d(TAG, "  contactId=$contactId (${contact.displayName})")
+            val keyChain = KeyChainManager.getKeyChain(context, contactId)
+                ?: throw Exception("Key chain not found for contact ${contact.displayName} (ID: $contactId)")
+            Log.d(TAG, "SEND KEY CHAIN LOAD: Loaded from database successfully")
+            Log.d(TAG, "  sendCounter=${keyChain.sendCounter} <- will use this for encryption")
+            Log.d(TAG, "  receiveCounter=${keyChain.receiveCounter}")
+
+            // ATOMIC ENCRYPTION + KEY EVOLUTION
+            // Encrypts and evolves key in one indivisible operation to prevent desync
+            // Wire format: [version:1][sequence:8][nonce:24][ciphertext][tag:16]
+            Log.d(TAG, "SEND KEY EVOLUTION: About to encrypt and evolve send chain key")
+            Log.d(TAG, "  contactId=$contactId (${contact.displayName})")
+            Log.d(TAG, "  Current sendCounter=${keyChain.sendCounter}")
+            Log.d(TAG, "  Will encrypt with sequence ${keyChain.sendCounter}")
+            val result = encryptChainMessage(
+                context,
+                contact,
+                plaintext,
+                keyChain.sendChainKeyBytes,
+                keyChain.sendCounter,
+                Message.MESSAGE_TYPE_TEXT,
+                keyChain.sessionEpoch
+            )
+            val encryptedBytes = result.ciphertext
+            Log.d(TAG, "SEND KEY EVOLUTION: Encryption complete, encrypted ${encryptedBytes.size} bytes")
+
+            // Save evolved key to database (key evolution happened atomically in Rust)
+            Log.d(TAG, "SEND KEY EVOLUTION: Updating database with new sendCounter=${keyChain.sendCounter + 1}")
+            database.contactKeyChainDao().updateSendChainKey(
+                contactId = contactId,
+                newSendChainKeyBase64 = android.util.Base64.encodeToString(result.evolvedChainKey, android.util.Base64.NO_WRAP),
+                newSendCounter = keyChain.sendCounter + 1,
+                timestamp = System.currentTimeMillis()
+            )
+            // VERIFY the update actually persisted
+            val verifyKeyChain = database.contactKeyChainDao().getKeyChainByContactId(contactId)
+            Log.d(TAG, "SEND VERIFICATION: After update, database shows sendCounter=${verifyKeyChain?.sendCounter}")
+            if (verifyKeyChain?.sendCounter != keyChain.sendCounter + 1) {
+                Log.e(TAG, "SEND ERROR: Counter update did NOT persist! Expected ${keyChain.sendCounter + 1}, got ${verifyKeyChain?.sendCounter}")
+            } else {
+                Log.d(TAG, "SEND: Counter update verified successfully")
+            }
+            Log.d(TAG, "Message encrypted with sequence ${keyChain.sendCounter}")
+
+            // Get our X25519 public key for sender identification
+            val ourX25519PublicKey = keyManager.getEncryptionPublicKey()
+
+            // Wire format: [X25519:32][version:1][sequence:8][nonce:24][ciphertext][tag:16]
+            // NOTE: Wire protocol type byte (0x03) is added by android.rs sendPing()
+            val encryptedWithMetadata = ourX25519PublicKey + encryptedBytes
+            Log.d(TAG, "  Total with metadata: ${encryptedWithMetadata.size} bytes (X25519 + encrypted)")
+
+            val encryptedBase64 = Base64.encodeToString(encryptedWithMetadata, Base64.NO_WRAP)
+
+            // Extract nonce from wire format (bytes 9-32, after version and sequence)
+            val nonce = encryptedBytes.sliceArray(9 until 33)
+            val nonceBase64 = Base64.encodeToString(nonce, Base64.NO_WRAP)
+
+            // Sign the message
+            Log.d(TAG, "Signing message...")
+            val messageData = (messageId + plaintext + System.currentTimeMillis()).toByteArray()
+            val signature = RustBridge.signData(messageData, ourPrivateKey)
+            val signatureBase64 = Base64.encodeToString(signature, Base64.NO_WRAP)
+
+            // Calculate self-destruct timestamp if custom duration provided
+            val currentTime = System.currentTimeMillis()
+            val selfDestructAt = selfDestructDurationMs?.let { currentTime + it }
+
+            // Generate Ping ID and timestamp ONCE (never changes, prevents ghost pings)
+            val pingId = generatePingId()  // 24-byte nonce as hex string
+            val pingTimestamp = currentTime
+            Log.d(TAG, "Generated Ping ID: ${pingId.take(16)}... (timestamp: $pingTimestamp)")
+
+            // Create message entity with PING_SENT status for persistent queue
+            val message = Message(
+                contactId = contactId,
+                messageId = messageId,
+                encryptedContent = plaintext, // Store plaintext for now (will encrypt in future)
+                isSentByMe = true,
+                timestamp = currentTime,
+                status = Message.STATUS_PING_SENT, // Start as PING_SENT so pollForPongsAndSendMessages() can find it
+                signatureBase64 = signatureBase64,
+                nonceBase64 = nonceBase64,
+                messageNonce = messageNonce,        // CRITICAL: Stored once, reused on all retries
+                selfDestructAt = selfDestructAt,
+                requiresReadReceipt = enableReadReceipt,
+                pingId = pingId,                    // Generated ONCE, used for all retries
+                pingTimestamp = pingTimestamp,      // Generated ONCE with pingId
+                encryptedPayload = encryptedBase64, // Store encrypted payload to send after Pong
+                retryCount = 0,                     // Initialize retry counter
+                lastRetryTimestamp = currentTime   // Track when we last attempted
+            )
+
+            val durationText = selfDestructDurationMs?.let { duration ->
+                when {
+                    duration < 60000 -> "${duration / 1000}s"
+                    duration < 3600000 -> "${duration / 60000}min"
+                    else -> "${duration / 3600000}h"
+                }
+            } ?: "disabled"
+            Log.d(TAG, "Self-destruct: $durationText")
+            Log.d(TAG, "Read receipt: ${if (enableReadReceipt) "enabled" else "disabled"}")
+            Log.d(TAG, "Message queued for persistent delivery (PING_SENT)")
+
+            // Save to database
+            Log.d(TAG, "Saving message to database...")
+            val savedMessageId = database.messageDao().insertMessage(message)
+            val savedMessage = message.copy(id = savedMessageId)
+
+            // Notify that message is saved (allows UI to update immediately)
+            onMessageSaved?.invoke(savedMessage)
+
+            // Broadcast to MainActivity to refresh chat list preview (explicit broadcast)
+            val intent = android.content.Intent("com.securelegion.NEW_PING")
+            intent.setPackage(context.packageName) // Make it explicit
+            intent.putExtra("CONTACT_ID", contactId)
+            context.sendBroadcast(intent)
+            Log.d(TAG, "Sent explicit NEW_PING broadcast to refresh MainActivity chat list")
+
+            // Immediately attempt to send Ping (but don't wait for Pong)
+            // This provides instant send attempt for online recipients
+            // Retry worker will handle retries and Pong polling
+            Log.i(TAG, "Message queued successfully: $messageId (Ping ID: $pingId)")
+            Log.d(TAG, "Attempting immediate Ping send...")
+
+            try {
+                sendPingForMessage(savedMessage)
+                Log.d(TAG, "Ping sent immediately, will poll for Pong later")
+            } catch (e: Exception) {
+                // Silent failure - retry worker will handle it
+                Log.w(TAG, "Immediate Ping send failed, retry worker will retry: ${e.message}")
+            }
+
+            // Schedule fast retry worker for this message (5s intervals)
+            ImmediateRetryWorker.scheduleForMessage(context, messageId)
+            Log.d(TAG, "Scheduled immediate retry worker for message $messageId")
+
+            Result.success(savedMessage)
+
+        } catch (e: Exception) {
+            Log.e(TAG, "Failed to send message", e)
+            Result.failure(e)
+        }
+    }
+
+    /**
+     * Send a payment request message (NLx402 quote) to a contact via Tor
+     * @param contactId Database ID of the recipient contact
+     * @param quote The NLx402 payment quote
+     * @param onMessageSaved Callback when message is saved to DB (before sending)
+     * @return Result with Message entity if successful
+     */
+    suspend fun sendPaymentRequest(
+        contactId: Long,
+        quote: NLx402Manager.PaymentQuote,
+        onMessageSaved: ((Message) -> Unit)? = null
+    ): Result<Message> = withContext(Dispatchers.IO) {
+        try {
+            Log.i(TAG, "╔════════════════════════════════════════")
+            Log.i(TAG, "║ MessageService.sendPaymentRequest()")
+            Log.i(TAG, "║ Contact ID: $contactId")
+            Log.i(TAG, "║ Quote ID: ${quote.quoteId}")
+            Log.i(TAG, "║ Amount: ${quote.formattedAmount}")
+            Log.i(TAG, "╚════════════════════════════════════════")
+
+            // Get database instance
+            val dbPassphrase = keyManager.getDatabasePassphrase()
+            val database = SecureLegionDatabase.getInstance(context, dbPassphrase)
+
+            // Get contact details
+            val contact = database.contactDao().getContactById(contactId)
+                ?: return@withContext Result.failure(Exception("Contact not found"))
+
+            Log.d(TAG, "Sending payment request to: ${contact.displayName} (${contact.torOnionAddress})")
+
+            // Generate unique message ID using quote ID
+            val messageId = "pay_req_${quote.quoteId}"
+
+            // Get our keypair for signing
+            val ourPrivateKey = keyManager.getSigningKeyBytes()
+
+            // Create the payment request payload (JSON with quote)
+            val paymentRequestPayload = """{"type":"PAYMENT_REQUEST","quote":${quote.rawJson}}"""
+
+            // Get key chain for this contact (for progressive ephemeral key evolution)
+            Log.d(TAG, "SEND KEY CHAIN LOAD: Loading key chain from database...")
+            Log.d(TAG, "  contactId=$contactId (${contact.displayName})")
+            val keyChain = KeyChainManager.getKeyChain(context, contactId)
+                ?: throw Exception("Key chain not found for contact ${contact.displayName} (ID: $contactId)")
+            Log.d(TAG, "SEND KEY CHAIN LOAD: Loaded from database successfully")
+            Log.d(TAG, "  sendCounter=${keyChain.sendCounter} <- will use this for encryption")
+
+            // ATOMIC ENCRYPTION + KEY EVOLUTION
+            // Encrypts and evolves key in one indivisible operation to prevent desync
+            // Wire format: [version:1][sequence:8][nonce:24][ciphertext][tag:16]
+            Log.d(TAG, "SEND KEY EVOLUTION: About to encrypt and evolve send chain key")
+            Log.d(TAG, "  contactId=$contactId (${contact.displayName})")
+            Log.d(TAG, "  Current sendCounter=${keyChain.sendCounter}")
+            Log.d(TAG, "  Will encrypt with sequence ${keyChain.sendCounter}")
+            val result = encryptChainMessage(
+                context,
+                contact,
+                paymentRequestPayload,
+                keyChain.sendChainKeyBytes,
+                keyChain.sendCounter,
+                Message.MESSAGE_TYPE_PAYMENT_REQUEST,
+                keyChain.sessionEpoch
+            )
+            val encryptedBytes = result.ciphertext
+            Log.d(TAG, "SEND KEY EVOLUTION: Encryption complete, encrypted ${encryptedBytes.size} bytes")
+
+            // Save evolved key to database (key evolution happened atomically in Rust)
+            Log.d(TAG, "SEND KEY EVOLUTION: Updating database with new sendCounter=${keyChain.sendCounter + 1}")
+            database.contactKeyChainDao().updateSendChainKey(
+                contactId = contactId,
+                newSendChainKeyBase64 = android.util.Base64.encodeToString(result.evolvedChainKey, android.util.Base64.NO_WRAP),
+                newSendCounter = keyChain.sendCounter + 1,
+                timestamp = System.currentTimeMillis()
+            )
+            // VERIFY the update actually persisted
+            val verifyKeyChain = database.contactKeyChainDao().getKeyChainByContactId(contactId)
+            Log.d(TAG, "SEND VERIFICATION: After update, database shows sendCounter=${verifyKeyChain?.sendCounter}")
+            if (verifyKeyChain?.sendCounter != keyChain.sendCounter + 1) {
+                Log.e(TAG, "SEND ERROR: Counter update did NOT persist! Expected ${keyChain.sendCounter + 1}, got ${verifyKeyChain?.sendCounter}")
+            } else {
+                Log.d(TAG, "SEND: Counter update verified successfully")
+            }
+            Log.d(TAG, "Payment request encrypted with sequence ${keyChain.sendCounter}")
+
+            // Get our X25519 public key for sender identification
+            val ourX25519PublicKey = keyManager.getEncryptionPublicKey()
+
+            // Wire format: [X25519:32][version:1][sequence:8][nonce:24][ciphertext][tag:16]
+            // NOTE: Wire protocol type byte (0x0A) is added by android.rs sendPing()
+            val encryptedWithMetadata = ourX25519PublicKey + encryptedBytes
+            Log.d(TAG, "  Total with metadata: ${encryptedWithMetadata.size} bytes (X25519 + encrypted)")
+
+            val encryptedBase64 = Base64.encodeToString(encryptedWithMetadata, Base64.NO_WRAP)
+
+            // Extract nonce from wire format (bytes 9-32, after version and sequence)
+            val nonce = encryptedBytes.sliceArray(9 until 33)
+            val nonceBase64 = Base64.encodeToString(nonce, Base64.NO_WRAP)
+
+            // Sign the message
+            Log.d(TAG, "Signing payment request...")
+            val messageData = (messageId + quote.rawJson + System.currentTimeMillis()).toByteArray()
+            val signature = RustBridge.signData(messageData, ourPrivateKey)
+            val signatureBase64 = Base64.encodeToString(signature, Base64.NO_WRAP)
+
+            val currentTime = System.currentTimeMillis()
+
+            // Generate Ping ID for persistent messaging
+            val pingId = generatePingId()  // 24-byte nonce as hex string
+            val pingTimestamp = currentTime
+            Log.d(TAG, "Generated Ping ID for payment request: $pingId")
+
+            // PHASE 1.1: STABLE IDENTITY
+            // Generate message nonce ONCE (never regenerated on retry)
+            // For payment messages, messageId is stable (pay_req_${quoteId}), but we still need nonce for retries
+            val messageNonce = generateMessageNonce()
+
+            // Create message entity with PAYMENT_REQUEST type
+            val message = Message(
+                contactId = contactId,
+                messageId = messageId,
+                encryptedContent = "Payment Request: ${quote.formattedAmount}", // Display text
+                messageType = Message.MESSAGE_TYPE_PAYMENT_REQUEST,
+                isSentByMe = true,
+                timestamp = currentTime,
+                status = Message.STATUS_PENDING,
+                signatureBase64 = signatureBase64,
+                nonceBase64 = nonceBase64,
+                messageNonce = messageNonce,        // CRITICAL: Stored once, reused on all retries
+                pingId = pingId,
+                pingTimestamp = pingTimestamp,
+                encryptedPayload = encryptedBase64,
+                retryCount = 0,
+                lastRetryTimestamp = currentTime,
+                // Payment-specific fields
+                paymentQuoteJson = quote.rawJson,
+                paymentStatus = Message.PAYMENT_STATUS_PENDING,
+                paymentToken = quote.token,
+                paymentAmount = quote.amount
+            )
+
+            Log.d(TAG, "Payment request queued for persistent delivery (PING_SENT)")
+
+            // Save to database
+            Log.d(TAG, "Saving payment request to database...")
+            val savedMessageId = database.messageDao().insertMessage(message)
+            val savedMessage = message.copy(id = savedMessageId)
+
+            // Notify that message is saved (allows UI to update immediately)
+            onMessageSaved?.invoke(savedMessage)
+
+            // Broadcast to ChatActivity to refresh messages (so payment request shows immediately)
+            val chatIntent = android.content.Intent("com.securelegion.MESSAGE_RECEIVED")
+            chatIntent.setPackage(context.packageName)
+            chatIntent.putExtra("CONTACT_ID", contactId)
+            context.sendBroadcast(chatIntent)
+            Log.d(TAG, "Sent explicit MESSAGE_RECEIVED broadcast to refresh ChatActivity")
+
+            // Broadcast to MainActivity to refresh chat list preview
+            val intent = android.content.Intent("com.securelegion.NEW_PING")
+            intent.setPackage(context.packageName)
+            intent.putExtra("CONTACT_ID", contactId)
+            context.sendBroadcast(intent)
+            Log.d(TAG, "Sent explicit NEW_PING broadcast to refresh MainActivity chat list")
+
+            // Immediately attempt to send Ping
+            Log.i(TAG, "Payment request queued successfully: $messageId (Ping ID: $pingId)")
+            Log.d(TAG, "Attempting immediate Ping send...")
+
+            try {
+                sendPingForMessage(savedMessage)
+                Log.d(TAG, "Ping sent immediately, will poll for Pong later")
+            } catch (e: Exception) {
+                Log.w(TAG, "Immediate Ping send failed, retry worker will retry: ${e.message}")
+            }
+
+            // Schedule fast retry worker for this message (5s intervals)
+            ImmediateRetryWorker.scheduleForMessage(context, messageId)
+            Log.d(TAG, "Scheduled immediate retry worker for payment request $messageId")
+
+            Result.success(savedMessage)
+
+        } catch (e: Exception) {
+            Log.e(TAG, "Failed to send payment request", e)
+            Result.failure(e)
+        }
+    }
+
+    /**
+     * Send a payment confirmation message (after paying someone's request)
+     * @param contactId Database ID of the recipient contact
+     * @param originalQuote The original payment quote that was paid
+     * @param txSignature The transaction signature
+     * @param onMessageSaved Callback when message is saved to DB (before sending)
+     * @return Result with Message entity if successful
+     */
+    suspend fun sendPaymentConfirmation(
+        contactId: Long,
+        originalQuote: NLx402Manager.PaymentQuote,
+        txSignature: String,
+        onMessageSaved: ((Message) -> Unit)? = null
+    ): Result<Message> = withContext(Dispatchers.IO) {
+        try {
+            Log.d(TAG, "Sending payment confirmation to contact ID: $contactId")
+            Log.d(TAG, "Paid quote: ${originalQuote.quoteId} (${originalQuote.formattedAmount})")
+            Log.d(TAG, "TX: $txSignature")
+
+            // Get database instance
+            val dbPassphrase = keyManager.getDatabasePassphrase()
+            val database = SecureLegionDatabase.getInstance(context, dbPassphrase)
+
+            // Get contact details
+            val contact = database.contactDao().getContactById(contactId)
+                ?: return@withContext Result.failure(Exception("Contact not found"))
+
+            Log.d(TAG, "Sending payment confirmation to: ${contact.displayName}")
+
+            // Generate unique message ID using quote ID and tx signature
+            val messageId = "pay_sent_${originalQuote.quoteId}_${txSignature.take(8)}"
+
+            // Get our keypair for signing
+            val ourPrivateKey = keyManager.getSigningKeyBytes()
+
+            // Create the payment confirmation payload
+            val paymentConfirmPayload = """{"type":"PAYMENT_SENT","quote_id":"${originalQuote.quoteId}","tx_signature":"$txSignature","amount":${originalQuote.amount},"token":"${originalQuote.token}"}"""
+
+            // Get key chain for this contact
+            Log.d(TAG, "SEND KEY CHAIN LOAD: Loading key chain from database for payment confirmation...")
+            Log.d(TAG, "  contactId=$contactId (${contact.displayName})")
+            val keyChain = KeyChainManager.getKeyChain(context, contactId)
+                ?: throw Exception("Key chain not found for contact ${contact.displayName}")
+            Log.d(TAG, "  sendCounter=${keyChain.sendCounter}")
+
+            // ATOMIC ENCRYPTION + KEY EVOLUTION
+            Log.d(TAG, "SEND KEY EVOLUTION: Encrypting payment confirmation with sequence ${keyChain.sendCounter}")
+            val result = encryptChainMessage(
+                context,
+                contact,
+                paymentConfirmPayload,
+                keyChain.sendChainKeyBytes,
+                keyChain.sendCounter,
+                Message.MESSAGE_TYPE_PAYMENT_SENT,
+                keyChain.sessionEpoch
+            )
+            val encryptedBytes = result.ciphertext
+            Log.d(TAG, "SEND KEY EVOLUTION: Payment confirmation encrypted: ${encryptedBytes.size} bytes")
+
+            // Save evolved key to database
+            database.contactKeyChainDao().updateSendChainKey(
+                contactId = contactId,
+                newSendChainKeyBase64 = android.util.Base64.encodeToString(result.evolvedChainKey, android.util.Base64.NO_WRAP),
+                newSendCounter = keyChain.sendCounter + 1,
+                timestamp = System.currentTimeMillis()
+            )
+            Log.d(TAG, "SEND: Updated sendCounter to ${keyChain.sendCounter + 1}")
+
+            // Get our X25519 public key for sender identification
+            val ourX25519PublicKey = keyManager.getEncryptionPublicKey()
+
+            // Wire format: [X25519:32][version:1][sequence:8][nonce:24][ciphertext][tag:16]
+            // NOTE: Wire protocol type byte (0x0B) is added by android.rs sendPing()
+            val encryptedWithMetadata = ourX25519PublicKey + encryptedBytes
+            val encryptedBase64 = Base64.encodeToString(encryptedWithMetadata, Base64.NO_WRAP)
+
+            // Extract nonce from wire format (bytes 9-32, after version and sequence)
+            val nonce = encryptedBytes.sliceArray(9 until 33)
+            val nonceBase64 = Base64.encodeToString(nonce, Base64.NO_WRAP)
+
+            // Sign the message
+            val messageData = (messageId + txSignature + System.currentTimeMillis()).toByteArray()
+            val signature = RustBridge.signData(messageData, ourPrivateKey)
+            val signatureBase64 = Base64.encodeToString(signature, Base64.NO_WRAP)
+
+            val currentTime = System.currentTimeMillis()
+            val pingId = generatePingId()  // 24-byte nonce as hex string
+            val pingTimestamp = currentTime
+
+            // PHASE 1.1: STABLE IDENTITY
+            // Generate message nonce ONCE (never regenerated on retry)
+            val messageNonce = generateMessageNonce()
+
+            // Create message entity with PAYMENT_SENT type
+            val message = Message(
+                contactId = contactId,
+                messageId = messageId,
+                encryptedContent = "Paid: ${originalQuote.formattedAmount}", // Display text
+                messageType = Message.MESSAGE_TYPE_PAYMENT_SENT,
+                isSentByMe = true,
+                timestamp = currentTime,
+                status = Message.STATUS_PENDING,
+                signatureBase64 = signatureBase64,
+                nonceBase64 = nonceBase64,
+                messageNonce = messageNonce,        // CRITICAL: Stored once, reused on all retries
+                pingId = pingId,
+                pingTimestamp = pingTimestamp,
+                encryptedPayload = encryptedBase64,
+                retryCount = 0,
+                lastRetryTimestamp = currentTime,
+                // Payment-specific fields
+                paymentQuoteJson = originalQuote.rawJson,
+                paymentStatus = Message.PAYMENT_STATUS_PAID,
+                paymentToken = originalQuote.token,
+                paymentAmount = originalQuote.amount,
+                txSignature = txSignature
+            )
+
+            // Save to database
+            val savedMessageId = database.messageDao().insertMessage(message)
+            val savedMessage = message.copy(id = savedMessageId)
+
+            // Notify that message is saved
+            onMessageSaved?.invoke(savedMessage)
+
+            // Broadcast to ChatActivity to refresh messages
+            val chatIntent = android.content.Intent("com.securelegion.MESSAGE_RECEIVED")
+            chatIntent.setPackage(context.packageName)
+            chatIntent.putExtra("CONTACT_ID", contactId)
+            context.sendBroadcast(chatIntent)
+            Log.d(TAG, "Sent MESSAGE_RECEIVED broadcast for payment confirmation")
+
+            // Broadcast to MainActivity
+            val intent = android.content.Intent("com.securelegion.NEW_PING")
+            intent.setPackage(context.packageName)
+            intent.putExtra("CONTACT_ID", contactId)
+            context.sendBroadcast(intent)
+
+            // Attempt to send Ping
+            try {
+                sendPingForMessage(savedMessage)
+            } catch (e: Exception) {
+                Log.w(TAG, "Immediate Ping send failed for payment confirmation: ${e.message}")
+            }
+
+            // Schedule retry worker
+            ImmediateRetryWorker.scheduleForMessage(context, messageId)
+
+            Log.i(TAG, "Payment confirmation sent: $messageId")
+            Result.success(savedMessage)
+
+        } catch (e: Exception) {
+            Log.e(TAG, "Failed to send payment confirmation", e)
+            Result.failure(e)
+        }
+    }
+
+    /**
+     * Send a payment acceptance message
+     * When someone sends you money, you accept by providing your receive address
+     * This triggers the sender's app to execute the blockchain transfer
+     */
+    suspend fun sendPaymentAcceptance(
+        contactId: Long,
+        originalQuote: NLx402Manager.PaymentQuote,
+        receiveAddress: String,
+        onMessageSaved: ((Message) -> Unit)? = null
+    ): Result<Message> = withContext(Dispatchers.IO) {
+        try {
+            Log.d(TAG, "Sending payment acceptance to contact ID: $contactId")
+            Log.d(TAG, "Quote: ${originalQuote.quoteId}, receive to: $receiveAddress")
+
+            val dbPassphrase = keyManager.getDatabasePassphrase()
+            val database = SecureLegionDatabase.getInstance(context, dbPassphrase)
+
+            val contact = database.contactDao().getContactById(contactId)
+                ?: return@withContext Result.failure(Exception("Contact not found"))
+
+            Log.d(TAG, "Sending payment acceptance to: ${contact.displayName}")
+
+            // PHASE 1.1: STABLE IDENTITY
+            // Generate message nonce ONCE (never regenerated on retry)
+            val messageNonce = generateMessageNonce()
+
+            // Fixed: Remove timestamp from messageId for determinism
+            // Use only quote.quoteId + nonce to ensure same message ID across retries
+            val messageId = "pay_accept_${originalQuote.quoteId}"
+
+            val ourPrivateKey = keyManager.getSigningKeyBytes()
+
+            // Create the payment acceptance payload with receive address
+            val paymentAcceptPayload = """{"type":"PAYMENT_ACCEPTED","quote_id":"${originalQuote.quoteId}","receive_address":"$receiveAddress","amount":${originalQuote.amount},"token":"${originalQuote.token}"}"""
+
+            // Get key chain for this contact
+            Log.d(TAG, "SEND KEY CHAIN LOAD: Loading key chain from database for payment acceptance...")
+            Log.d(TAG, "  contactId=$contactId (${contact.displayName})")
+            val keyChain = KeyChainManager.getKeyChain(context, contactId)
+                ?: throw Exception("Key chain not found for contact ${contact.displayName}")
+            Log.d(TAG, "  sendCounter=${keyChain.sendCounter}")
+
+            // ATOMIC ENCRYPTION + KEY EVOLUTION
+            Log.d(TAG, "SEND KEY EVOLUTION: Encrypting payment acceptance with sequence ${keyChain.sendCounter}")
+            val result = encryptChainMessage(
+                context,
+                contact,
+                paymentAcceptPayload,
+                keyChain.sendChainKeyBytes,
+                keyChain.sendCounter,
+                Message.MESSAGE_TYPE_PAYMENT_ACCEPTED,
+                keyChain.sessionEpoch
+            )
+            val encryptedBytes = result.ciphertext
+            Log.d(TAG, "SEND KEY EVOLUTION: Payment acceptance encrypted: ${encryptedBytes.size} bytes")
+
+            // Save evolved key to database
+            database.contactKeyChainDao().updateSendChainKey(
+                contactId = contactId,
+                newSendChainKeyBase64 = android.util.Base64.encodeToString(result.evolvedChainKey, android.util.Base64.NO_WRAP),
+                newSendCounter = keyChain.sendCounter + 1,
+                timestamp = System.currentTimeMillis()
+            )
+            Log.d(TAG, "SEND: Updated sendCounter to ${keyChain.sendCounter + 1}")
+
+            // Get our X25519 public key for sender identification
+            val ourX25519PublicKey = keyManager.getEncryptionPublicKey()
+
+            // Wire format: [X25519:32][version:1][sequence:8][nonce:24][ciphertext][tag:16]
+            // NOTE: Wire protocol type byte (0x0C) is added by android.rs sendPing()
+            val encryptedWithMetadata = ourX25519PublicKey + encryptedBytes
+            val encryptedBase64 = Base64.encodeToString(encryptedWithMetadata, Base64.NO_WRAP)
+
+            // Extract nonce from wire format (bytes 9-32, after version and sequence)
+            val nonce = encryptedBytes.sliceArray(9 until 33)
+            val nonceBase64 = Base64.encodeToString(nonce, Base64.NO_WRAP)
+
+            val messageData = (messageId + receiveAddress + System.currentTimeMillis()).toByteArray()
+            val signature = RustBridge.signData(messageData, ourPrivateKey)
+            val signatureBase64 = Base64.encodeToString(signature, Base64.NO_WRAP)
 
-            // Hash input: v1 || conversationId || senderEd25519 || recipientEd25519 || plaintextHash || messageNonce
-            val hashInput = "v1||$conversationId||$senderEd25519Base64||$recipientEd25519Base64||$plaintextHash||$messageNonce"
+            val currentTime = System.currentTimeMillis()
+            val pingId = generatePingId()  // 24-byte nonce as hex string
+            val pingTimestamp = currentTime
 
-            // Compute SHA-256(hashInput)
-            val messageIdHash = MessageDigest.getInstance("SHA-256")
-                .digest(hashInput.toByteArray())
+            val message = Message(
+                contactId = contactId,
+                messageId = messageId,
+                encryptedContent = "Payment accepted: ${originalQuote.formattedAmount}",
+                messageType = Message.MESSAGE_TYPE_PAYMENT_ACCEPTED,
+                isSentByMe = true,
+                timestamp = currentTime,
+                status = Message.STATUS_PENDING,
+                signatureBase64 = signatureBase64,
+                nonceBase64 = nonceBase64,
+                messageNonce = messageNonce,        // CRITICAL: Stored once, reused on all retries
+                pingId = pingId,
+                pingTimestamp = pingTimestamp,
+                encryptedPayload = encryptedBase64,
+                retryCount = 0,
+                lastRetryTimestamp = currentTime,
+                paymentQuoteJson = originalQuote.rawJson,
+                paymentToken = originalQuote.token,
+                paymentAmount = originalQuote.amount,
+                paymentStatus = Message.PAYMENT_STATUS_PENDING
+            )
 
-            // Base64-encode and take first 32 characters (256 bits / 8 * 4 = 128 chars, so 32 is reasonable)
-            return android.util.Base64.encodeToString(messageIdHash, android.util.Base64.NO_WRAP).take(32)
+            val savedMessageId = database.messageDao().insertMessage(message)
+            val savedMessage = message.copy(id = savedMessageId)
+
+            onMessageSaved?.invoke(savedMessage)
+
+            // Broadcast to ChatActivity to refresh messages
+            val chatIntent = android.content.Intent("com.securelegion.MESSAGE_RECEIVED")
+            chatIntent.setPackage(context.packageName)
+            chatIntent.putExtra("CONTACT_ID", contactId)
+            context.sendBroadcast(chatIntent)
+            Log.d(TAG, "Sent MESSAGE_RECEIVED broadcast for payment acceptance")
+
+            // Broadcast to MainActivity
+            val intent = android.content.Intent("com.securelegion.NEW_PING")
+            intent.setPackage(context.packageName)
+            intent.putExtra("CONTACT_ID", contactId)
+            context.sendBroadcast(intent)
+
+            // Try immediate send via Ping
+            try {
+                sendPingForMessage(savedMessage)
+                Log.d(TAG, "Sent Ping for payment acceptance")
+            } catch (e: Exception) {
+                Log.w(TAG, "Immediate Ping send failed for payment acceptance: ${e.message}")
+            }
+
+            ImmediateRetryWorker.scheduleForMessage(context, messageId)
+
+            Log.i(TAG, "Payment acceptance sent: $messageId")
+            Result.success(savedMessage)
+
+        } catch (e: Exception) {
+            Log.e(TAG, "Failed to send payment acceptance", e)
+            Result.failure(e)
         }
     }
 
-    private val keyManager = KeyManager.getInstance(context)
-
     /**
-     * Send a voice message to a contact via Tor
-     * @param contactId Database ID of the recipient contact
-     * @param audioBytes The recorded audio as ByteArray
-     * @param durationSeconds Duration of the voice clip in seconds
-     * @param enableSelfDestruct Whether to enable self-destruct (24h)
-     * @param onMessageSaved Callback when message is saved to DB (before sending)
+     * Receive and decrypt an incoming message
+     * @param encryptedData The encrypted message data
+     * @param senderPublicKey The sender's public key
+     * @param senderOnionAddress The sender's .onion address
+     * @param messageType Message type ("TEXT" or "VOICE")
+     * @param voiceDuration Duration in seconds (for voice messages)
+     * @param selfDestructAt Self-destruct timestamp (or null)
+     * @param requiresReadReceipt Whether sender wants read receipt
      * @return Result with Message entity if successful
      */
-    suspend fun sendVoiceMessage(
-        contactId: Long,
-        audioBytes: ByteArray,
-        durationSeconds: Int,
-        selfDestructDurationMs: Long? = null,
-        onMessageSaved: ((Message) -> Unit)? = null
+    suspend fun receiveMessage(
+        encryptedData: String,
+        senderPublicKey: ByteArray,
+        senderOnionAddress: String,
+        messageType: String = Message.MESSAGE_TYPE_TEXT,
+        voiceDuration: Int? = null,
+        selfDestructAt: Long? = null,
+        requiresReadReceipt: Boolean = true,
+        pingId: String? = null
     ): Result<Message> = withContext(Dispatchers.IO) {
         try {
-            Log.d(TAG, "Sending voice message to contact ID: $contactId (${audioBytes.size} bytes, ${durationSeconds}s)")
+            Log.d(TAG, "Receiving $messageType message from: $senderOnionAddress")
+
+            // PHASE 1.2: Get our public key for deterministic messageId calculation
+            // Both sender and receiver must compute same ID using SORTED pubkeys
+            val ourPublicKeyBase64 = android.util.Base64.encodeToString(keyManager.getSigningPublicKey(), android.util.Base64.NO_WRAP)
+            val senderPublicKeyBase64 = android.util.Base64.encodeToString(senderPublicKey, android.util.Base64.NO_WRAP)
 
             // Get database instance
             val dbPassphrase = keyManager.getDatabasePassphrase()
             val database = SecureLegionDatabase.getInstance(context, dbPassphrase)
 
-            // Get contact details
-            val contact = database.contactDao().getContactById(contactId)
-                ?: return@withContext Result.failure(Exception("Contact not found"))
-
-            Log.d(TAG, "Sending voice to: ${contact.displayName} (${contact.torOnionAddress})")
-
-            // Get our keypair for signing (needed for messageId determinism)
-            val ourPublicKey = keyManager.getSigningPublicKey()
-            val ourPrivateKey = keyManager.getSigningKeyBytes()
-            val ourPublicKeyBase64 = android.util.Base64.encodeToString(ourPublicKey, android.util.Base64.NO_WRAP)
-
-            // PHASE 1.1: STABLE IDENTITY
-            // Generate message nonce ONCE (never regenerated on retry)
-            val messageNonce = generateMessageNonce()
+            // Find contact by Tor address
+            val contact = database.contactDao().getContactByOnionAddress(senderOnionAddress)
+                ?: return@withContext Result.failure(Exception("Unknown sender: $senderOnionAddress"))
 
-            // Generate STABLE messageId for voice message
-            // Convert audio bytes to string for hashing (same as encryption input)
-            val audioAsString = String(audioBytes, Charsets.ISO_8859_1)
-            val messageId = generateDeterministicMessageId(
-                plaintext = audioAsString,
-                senderEd25519Base64 = ourPublicKeyBase64,
-                recipientEd25519Base64 = contact.publicKeyBase64,
-                messageNonce = messageNonce
-            )
-            Log.d(TAG, "Generated deterministic messageId for voice: $messageId (from nonce: $messageNonce)")
+            Log.d(TAG, "Message from: ${contact.displayName}")
 
             // Get key chain for this contact (for progressive ephemeral key evolution)
-            Log.d(TAG, "SEND KEY CHAIN LOAD (VOICE): Loading key chain from database...")
-            Log.d(TAG, "  contactId=$contactId (${contact.displayName})")
-            Log.d(TAG, "  Audio bytes: ${audioBytes.size} bytes")
-            Log.d(TAG, "  Duration: ${durationSeconds}s")
+            Log.d(TAG, "Decrypting message with skipped key support...")
+            val keyChain = KeyChainManager.getKeyChain(context, contact.id)
+            if (keyChain == null) {
+                Log.e(TAG, "❌ DECRYPTION FAILED: Key chain not found for contact ${contact.id} (${contact.displayName})")
+                Log.e(TAG, "   This contact was probably added before key chain support was implemented.")
+                Log.e(TAG, "   Fix: Remove and re-add this contact to initialize a new key chain.")
+                throw Exception("Key chain not found for contact ${contact.id}")
+            }
 
-            val keyChain = KeyChainManager.getKeyChain(context, contactId)
-                ?: throw Exception("Key chain not found for contact ${contact.displayName} (ID: $contactId)")
-            Log.d(TAG, "SEND KEY CHAIN LOAD (VOICE): Loaded from database successfully")
-            Log.d(TAG, "  sendCounter=${keyChain.sendCounter} <- will use this for encryption")
+            // Log current key chain state for debugging
+            Log.d(TAG, "📊 Key chain state: sendCounter=${keyChain.sendCounter}, receiveCounter=${keyChain.receiveCounter}")
 
-            // Encrypt using current send chain key and counter (ATOMIC key evolution)
             // Wire format: [version:1][sequence:8][nonce:24][ciphertext][tag:16]
-            Log.d(TAG, "SEND KEY EVOLUTION (VOICE): Encrypting with sequence ${keyChain.sendCounter}")
-            val result = RustBridge.encryptMessageWithEvolution(
-                String(audioBytes, Charsets.ISO_8859_1), // Convert bytes to string for encryption
-                keyChain.sendChainKeyBytes,
-                keyChain.sendCounter
-            )
-            val encryptedBytes = result.ciphertext
-            Log.d(TAG, "SEND KEY EVOLUTION (VOICE): Encryption complete, encrypted ${encryptedBytes.size} bytes")
+            val encryptedBytes = Base64.decode(encryptedData, Base64.NO_WRAP)
 
-            // Save evolved key to database (key evolution happened atomically in Rust)
-            Log.d(TAG, "SEND KEY EVOLUTION (VOICE): Updating database with new sendCounter=${keyChain.sendCounter + 1}")
-            database.contactKeyChainDao().updateSendChainKey(
-                contactId = contactId,
-                newSendChainKeyBase64 = android.util.Base64.encodeToString(result.evolvedChainKey, android.util.Base64.NO_WRAP),
-                newSendCounter = keyChain.sendCounter + 1,
-                timestamp = System.currentTimeMillis()
-            )
-            // VERIFY the update actually persisted
-            val verifyKeyChain = database.contactKeyChainDao().getKeyChainByContactId(contactId)
-            Log.d(TAG, "SEND VERIFICATION (VOICE): After update, database shows sendCounter=${verifyKeyChain?.sendCounter}")
-            if (verifyKeyChain?.sendCounter != keyChain.sendCounter + 1) {
-                Log.e(TAG, "SEND ERROR (VOICE): Counter update did NOT persist! Expected ${keyChain.sendCounter + 1}, got ${verifyKeyChain?.sendCounter}")
+            // Extract sequence from wire format
+            val messageSequence = if (encryptedBytes.size >= 9) {
+                java.nio.ByteBuffer.wrap(encryptedBytes.sliceArray(1..8)).long
             } else {
-                Log.d(TAG, "SEND (VOICE): Counter update verified successfully")
+                Log.e(TAG, "❌ DECRYPTION FAILED: Ciphertext too short (${encryptedBytes.size} bytes)")
+                return@withContext Result.failure(Exception("Ciphertext too short"))
             }
 
-            // Prepend message type byte: 0x01 for VOICE, followed by duration (4 bytes, big-endian)
-            val durationBytes = ByteArray(4)
-            durationBytes[0] = (durationSeconds shr 24).toByte()
-            durationBytes[1] = (durationSeconds shr 16).toByte()
-            durationBytes[2] = (durationSeconds shr 8).toByte()
-            durationBytes[3] = durationSeconds.toByte()
+            val receiveCounter = keyChain.receiveCounter
+            val messageEpoch = RustBridge.chainMessageEpoch(encryptedBytes)
+            Log.d(TAG, "📨 Message sequence: $messageSequence (expecting: $receiveCounter), epoch $messageEpoch")
 
-            // Get our X25519 public key for sender identification
-            val ourX25519PublicKey = keyManager.getEncryptionPublicKey()
+            // Skipped message key algorithm: previous epoch, or current epoch through the Rust store
+            val decryptedData: String
 
-            // Wire format: [0x01][Duration:4][X25519:32][Encrypted Audio]
-            val encryptedWithMetadata = byteArrayOf(0x01) + durationBytes + ourX25519PublicKey + encryptedBytes
-            val encryptedBase64 = Base64.encodeToString(encryptedWithMetadata, Base64.NO_WRAP)
+            when {
+                // PATH 0: In-flight message from the previous session epoch (grace period after SESSION_RESET)
+                messageEpoch != keyChain.sessionEpoch -> {
+                    val previousChainKey = keyChain.previousReceiveChainKeyBase64
+                    if (previousChainKey == null || !inPreviousEpochGrace(keyChain, messageEpoch)) {
+                        Log.e(TAG, "❌ Message for session epoch $messageEpoch, current ${keyChain.sessionEpoch} (no grace)")
+                        return@withContext Result.failure(Exception("Message for an unknown or expired session epoch"))
+                    }
 
-            Log.d(TAG, "  Metadata: 1 byte type + 4 bytes duration + 32 bytes X25519")
-            Log.d(TAG, "  Total payload: ${encryptedWithMetadata.size} bytes (${encryptedBase64.length} Base64 chars)")
+                    val result = decryptChainMessage(
+                        context,
+                        contact,
+                        encryptedBytes,
+                        Base64.decode(previousChainKey, Base64.NO_WRAP),
+                        keyChain.previousReceiveCounter,
+                        messageType,
+                        messageEpoch
+                    ) ?: return@withContext Result.failure(Exception("Failed to decrypt previous-epoch message"))
+                    database.contactKeyChainDao().updatePreviousReceiveChainKey(
+                        contactId = contact.id,
+                        previousReceiveChainKeyBase64 = Base64.encodeToString(result.evolvedChainKey, Base64.NO_WRAP),
+                        previousReceiveCounter = keyChain.previousReceiveCounter + 1
+                    )
+
+                    // Current epoch's chain is untouched
+                    decryptedData = result.plaintext
+                }
+
+                // PATH 1: Current epoch - the Rust skipped-key store takes in-order, early (gap keys
+                // parked) and late (parked key used once, in O(1)) messages
+                else -> {
+                    decryptedData = decryptCurrentEpochMessage(context, database, contact, keyChain, encryptedBytes, messageSequence, messageType, messageEpoch)
+                        ?: return@withContext Result.failure(Exception("Failed to decrypt message"))
+                }
+            }
 
             // Extract nonce from wire format (bytes 9-32, after version and sequence)
             val nonce = encryptedBytes.sliceArray(9 until 33)
@@ -425,10 +1671,14 @@ class MessageService(private val context: Context) {
 
             // Encrypt using current send chain key and counter (ATOMIC key evolution)
             // Wire format: [version:1][sequence:8][nonce:24][ciphertext][tag:16]
-            val result = RustBridge.encryptMessageWithEvolution(
+            val result = encryptChainMessage(
+                context,
+                contact,
                 String(imageBytes, Charsets.ISO_8859_1), // Convert bytes to string for encryption
                 keyChain.sendChainKeyBytes,
-                keyChain.sendCounter
+                keyChain.sendCounter,
+                Message.MESSAGE_TYPE_IMAGE,
+                keyChain.sessionEpoch
             )
             val encryptedBytes = result.ciphertext
             Log.d(TAG, "  Encrypted: ${encryptedBytes.size} bytes (sequence ${keyChain.sendCounter})")
@@ -601,10 +1851,14 @@ class MessageService(private val context: Context) {
             Log.d(TAG, "  contactId=$contactId (${contact.displayName})")
             Log.d(TAG, "  Current sendCounter=${keyChain.sendCounter}")
             Log.d(TAG, "  Will encrypt with sequence ${keyChain.sendCounter}")
-            val result = RustBridge.encryptMessageWithEvolution(
+            val result = encryptChainMessage(
+                context,
+                contact,
                 plaintext,
                 keyChain.sendChainKeyBytes,
-                keyChain.sendCounter
+                keyChain.sendCounter,
+                Message.MESSAGE_TYPE_TEXT,
+                keyChain.sessionEpoch
             )
             val encryptedBytes = result.ciphertext
             Log.d(TAG, "SEND KEY EVOLUTION: Encryption complete, encrypted ${encryptedBytes.size} bytes")
@@ -782,10 +2036,14 @@ class MessageService(private val context: Context) {
             Log.d(TAG, "  contactId=$contactId (${contact.displayName})")
             Log.d(TAG, "  Current sendCounter=${keyChain.sendCounter}")
             Log.d(TAG, "  Will encrypt with sequence ${keyChain.sendCounter}")
-            val result = RustBridge.encryptMessageWithEvolution(
+            val result = encryptChainMessage(
+                context,
+                contact,
                 paymentRequestPayload,
                 keyChain.sendChainKeyBytes,
-                keyChain.sendCounter
+                keyChain.sendCounter,
+                Message.MESSAGE_TYPE_PAYMENT_REQUEST,
+                keyChain.sessionEpoch
             )
             val encryptedBytes = result.ciphertext
             Log.d(TAG, "SEND KEY EVOLUTION: Encryption complete, encrypted ${encryptedBytes.size} bytes")
@@ -958,10 +2216,14 @@ class MessageService(private val context: Context) {
 
             // ATOMIC ENCRYPTION + KEY EVOLUTION
             Log.d(TAG, "SEND KEY EVOLUTION: Encrypting payment confirmation with sequence ${keyChain.sendCounter}")
-            val result = RustBridge.encryptMessageWithEvolution(
+            val result = encryptChainMessage(
+                context,
+                contact,
                 paymentConfirmPayload,
                 keyChain.sendChainKeyBytes,
-                keyChain.sendCounter
+                keyChain.sendCounter,
+                Message.MESSAGE_TYPE_PAYMENT_SENT,
+                keyChain.sessionEpoch
             )
             val encryptedBytes = result.ciphertext
             Log.d(TAG, "SEND KEY EVOLUTION: Payment confirmation encrypted: ${encryptedBytes.size} bytes")
@@ -1109,10 +2371,14 @@ class MessageService(private val context: Context) {
 
             // ATOMIC ENCRYPTION + KEY EVOLUTION
             Log.d(TAG, "SEND KEY EVOLUTION: Encrypting payment acceptance with sequence ${keyChain.sendCounter}")
-            val result = RustBridge.encryptMessageWithEvolution(
+            val result = encryptChainMessage(
+                context,
+                contact,
                 paymentAcceptPayload,
                 keyChain.sendChainKeyBytes,
-                keyChain.sendCounter
+                keyChain.sendCounter,
+                Message.MESSAGE_TYPE_PAYMENT_ACCEPTED,
+                keyChain.sessionEpoch
             )
             val encryptedBytes = result.ciphertext
             Log.d(TAG, "SEND KEY EVOLUTION: Payment acceptance encrypted: ${encryptedBytes.size} bytes")
@@ -1269,146 +2535,46 @@ class MessageService(private val context: Context) {
             }
 
             val receiveCounter = keyChain.receiveCounter
-            Log.d(TAG, "📨 Message sequence: $messageSequence (expecting: $receiveCounter)")
-
-            // MAX_SKIP security limit to prevent memory exhaustion attacks
-            val MAX_SKIP = 100L
+            val messageEpoch = RustBridge.chainMessageEpoch(encryptedBytes)
+            Log.d(TAG, "📨 Message sequence: $messageSequence (expecting: $receiveCounter), epoch $messageEpoch")
 
-            // Skipped message key algorithm: Four distinct paths
+            // Skipped message key algorithm: previous epoch, or current epoch through the Rust store
             val decryptedData: String
-            val newReceiveCounter: Long
-            val newReceiveChainKey: ByteArray
 
             when {
-                // PATH 1: Past message (n < Nr) - check skipped keys table
-                messageSequence < receiveCounter -> {
-                    Log.d(TAG, "📥 PAST MESSAGE: seq=$messageSequence < receiveCounter=$receiveCounter")
-                    Log.d(TAG, "   Checking skipped_message_keys table...")
-
-                    val skippedKey = database.skippedMessageKeyDao().getKey(contact.id, messageSequence)
-                    if (skippedKey == null) {
-                        Log.e(TAG, "❌ SKIPPED KEY NOT FOUND: seq=$messageSequence (too old or never skipped)")
-                        return@withContext Result.failure(Exception("Message key not found - message too old"))
-                    }
-
-                    Log.d(TAG, "✓ Found skipped key for seq=$messageSequence, attempting decryption...")
-
-                    // Decrypt using stored message key (no chain key evolution)
-                    val plaintext = RustBridge.decryptWithMessageKey(encryptedBytes, skippedKey.messageKey)
-                    if (plaintext == null) {
-                        Log.e(TAG, "❌ DECRYPTION FAILED: Invalid message key for seq=$messageSequence")
-                        return@withContext Result.failure(Exception("Decryption failed with skipped key"))
-                    }
-
-                    decryptedData = plaintext
-
-                    // Delete used key immediately (forward secrecy)
-                    database.skippedMessageKeyDao().deleteKey(contact.id, messageSequence)
-                    Log.d(TAG, "✓ Deleted used skipped key for seq=$messageSequence")
-
-                    // Chain key state unchanged (past message doesn't affect current state)
-                    newReceiveCounter = receiveCounter
-                    newReceiveChainKey = keyChain.receiveChainKeyBytes
-                }
-
-                // PATH 2: Gap too large (n - Nr > MAX_SKIP) - reject
-                messageSequence - receiveCounter > MAX_SKIP -> {
-                    Log.e(TAG, "⚠️ SEQUENCE TOO FAR: seq=$messageSequence, receiveCounter=$receiveCounter, gap=${messageSequence - receiveCounter} > MAX_SKIP=$MAX_SKIP")
-                    return@withContext Result.failure(Exception("Sequence too far ahead - possible desync or attack"))
-                }
-
-                // PATH 3: Future message (n > Nr) - derive and store skipped keys
-                messageSequence > receiveCounter -> {
-                    Log.d(TAG, "⏭️ FUTURE MESSAGE: seq=$messageSequence > receiveCounter=$receiveCounter")
-                    Log.d(TAG, "   Deriving ${messageSequence - receiveCounter} skipped message keys for gap [${receiveCounter}..${messageSequence - 1}]")
-
-                    var currentChainKey = keyChain.receiveChainKeyBytes
-                    val skippedKeys = mutableListOf<com.securelegion.database.entities.SkippedMessageKey>()
-
-                    // Derive and store message keys for skipped sequences [Nr..n-1]
-                    for (seq in receiveCounter until messageSequence) {
-                        // Derive message key from current chain key
-                        val messageKey = RustBridge.deriveMessageKey(currentChainKey)
-                            ?: throw Exception("Failed to derive message key for sequence $seq")
-
-                        // Store skipped key for this sequence
-                        skippedKeys.add(
-                            com.securelegion.database.entities.SkippedMessageKey(
-                                id = "${contact.id}_$seq",
-                                contactId = contact.id,
-                                sequence = seq,
-                                messageKey = messageKey,
-                                timestamp = System.currentTimeMillis()
-                            )
-                        )
-
-                        // Evolve chain key to next state
-                        currentChainKey = RustBridge.evolveChainKey(currentChainKey)
-                            ?: throw Exception("Failed to evolve chain key at sequence $seq")
-                    }
-
-                    // Bulk insert skipped keys
-                    database.skippedMessageKeyDao().insertAll(skippedKeys)
-                    Log.d(TAG, "✓ Stored ${skippedKeys.size} skipped message keys")
-
-                    // currentChainKey is now at sequence n (current message)
-                    // Derive message key for current message
-                    val currentMessageKey = RustBridge.deriveMessageKey(currentChainKey)
-                        ?: throw Exception("Failed to derive message key for sequence $messageSequence")
-
-                    // Decrypt current message using derived key
-                    val plaintext = RustBridge.decryptWithMessageKey(encryptedBytes, currentMessageKey)
-                    if (plaintext == null) {
-                        Log.e(TAG, "❌ DECRYPTION FAILED: Message key invalid for seq=$messageSequence")
-                        return@withContext Result.failure(Exception("Decryption failed after skipping"))
+                // PATH 0: In-flight message from the previous session epoch (grace period after SESSION_RESET)
+                messageEpoch != keyChain.sessionEpoch -> {
+                    val previousChainKey = keyChain.previousReceiveChainKeyBase64
+                    if (previousChainKey == null || !inPreviousEpochGrace(keyChain, messageEpoch)) {
+                        Log.e(TAG, "❌ Message for session epoch $messageEpoch, current ${keyChain.sessionEpoch} (no grace)")
+                        return@withContext Result.failure(Exception("Message for an unknown or expired session epoch"))
                     }
 
-                    decryptedData = plaintext
-
-                    // Evolve chain key once more for next expected message
-                    val evolvedChainKey = RustBridge.evolveChainKey(currentChainKey)
-                        ?: throw Exception("Failed to evolve chain key after decrypting sequence $messageSequence")
-
-                    // Update state: receiveCounter = n + 1
-                    newReceiveCounter = messageSequence + 1
-                    newReceiveChainKey = evolvedChainKey
-                    Log.d(TAG, "✓ Decrypted message seq=$messageSequence, updated receiveCounter=$newReceiveCounter")
-                }
-
-                // PATH 4: In-order message (n == Nr) - normal flow
-                else -> {
-                    Log.d(TAG, "✅ IN-ORDER MESSAGE: seq=$messageSequence == receiveCounter=$receiveCounter")
-
-                    // Normal decryption with atomic key evolution
-                    val result = RustBridge.decryptMessageWithEvolution(
+                    val result = decryptChainMessage(
+                        context,
+                        contact,
                         encryptedBytes,
-                        keyChain.receiveChainKeyBytes,
-                        receiveCounter
+                        Base64.decode(previousChainKey, Base64.NO_WRAP),
+                        keyChain.previousReceiveCounter,
+                        messageType,
+                        messageEpoch
+                    ) ?: return@withContext Result.failure(Exception("Failed to decrypt previous-epoch message"))
+                    database.contactKeyChainDao().updatePreviousReceiveChainKey(
+                        contactId = contact.id,
+                        previousReceiveChainKeyBase64 = Base64.encodeToString(result.evolvedChainKey, Base64.NO_WRAP),
+                        previousReceiveCounter = keyChain.previousReceiveCounter + 1
                     )
 
-                    if (result == null) {
-                        Log.e(TAG, "❌ DECRYPTION FAILED: RustBridge returned null")
-                        return@withContext Result.failure(Exception("Failed to decrypt message"))
-                    }
-
+                    // Current epoch's chain is untouched
                     decryptedData = result.plaintext
-
-                    // Update state: receiveCounter = n + 1
-                    newReceiveCounter = receiveCounter + 1
-                    newReceiveChainKey = result.evolvedChainKey
-                    Log.d(TAG, "✓ Decrypted in-order message, updated receiveCounter=$newReceiveCounter")
                 }
-            }
 
-            // Save updated chain key state to database (only if changed)
-            if (newReceiveCounter != receiveCounter) {
-                database.contactKeyChainDao().updateReceiveChainKey(
-                    contactId = contact.id,
-                    newReceiveChainKeyBase64 = android.util.Base64.encodeToString(newReceiveChainKey, android.util.Base64.NO_WRAP),
-                    newReceiveCounter = newReceiveCounter,
-                    timestamp = System.currentTimeMillis()
-                )
-                Log.d(TAG, "💾 Saved updated key chain state: receiveCounter=$newReceiveCounter")
+                // PATH 1: Current epoch - the Rust skipped-key store takes in-order, early (gap keys
+                // parked) and late (parked key used once, in O(1)) messages
+                else -> {
+                    decryptedData = decryptCurrentEpochMessage(context, database, contact, keyChain, encryptedBytes, messageSequence, messageType, messageEpoch)
+                        ?: return@withContext Result.failure(Exception("Failed to decrypt message"))
+                }
             }
 
             // Extract nonce from wire format (bytes 9-32, after version and sequence)
@@ -1969,14 +3135,7 @@ class MessageService(private val context: Context) {
             Log.d(TAG, "Encrypted message size: ${encryptedBytes.size} bytes (Base64 encoded size: ${message.encryptedPayload.length})")
 
             // Convert message type to wire protocol type byte
-            val messageTypeByte: Byte = when (message.messageType) {
-                Message.MESSAGE_TYPE_VOICE -> 0x04.toByte()           // VOICE
-                Message.MESSAGE_TYPE_IMAGE -> 0x09.toByte()           // IMAGE
-                Message.MESSAGE_TYPE_PAYMENT_REQUEST -> 0x0A.toByte() // PAYMENT_REQUEST
-                Message.MESSAGE_TYPE_PAYMENT_SENT -> 0x0B.toByte()    // PAYMENT_SENT
-                Message.MESSAGE_TYPE_PAYMENT_ACCEPTED -> 0x0C.toByte() // PAYMENT_ACCEPTED
-                else -> 0x03.toByte()                                  // TEXT (default)
-            }
+            val messageTypeByte: Byte = wireTypeByte(message.messageType)
             Log.d(TAG, "Message type: ${message.messageType} → wire byte: 0x${messageTypeByte.toString(16).padStart(2, '0')}")
 
             // Send Ping via Rust bridge (with message for instant mode)
@@ -2054,6 +3213,8 @@ class MessageService(private val context: Context) {
                 Log.i(TAG, "✓ Saved outgoing Ping to SharedPreferences (both formats): contact_id=${contact.id}, ping_id=$pingId")
             }
 
+            DeliveryTracker.begin(message)
+
             Log.i(TAG, "✓ Ping sent successfully: $pingId (waiting for PING_ACK from receiver)")
             Result.success(pingId)
 
@@ -2102,6 +3263,10 @@ class MessageService(private val context: Context) {
                 Log.d(TAG, "pollForPong($pingId) returned: $pongReceived")
 
                 if (pongReceived) {
+                    if (!DeliveryTracker.acceptPong(message)) {
+                        Log.w(TAG, "Pong for ${message.messageId} rejected by delivery tracking - not sending blob")
+                        continue
+                    }
                     Log.i(TAG, "✓ Pong received for Ping ID $pingId! Sending message blob...")
 
                     // Get contact for .onion address
@@ -2122,14 +3287,7 @@ class MessageService(private val context: Context) {
                     val encryptedBytes = Base64.decode(encryptedPayload, Base64.NO_WRAP)
 
                     // Convert message type to wire protocol type byte
-                    val messageTypeByte: Byte = when (message.messageType) {
-                        Message.MESSAGE_TYPE_VOICE -> 0x04.toByte()           // VOICE
-                        Message.MESSAGE_TYPE_IMAGE -> 0x09.toByte()           // IMAGE
-                        Message.MESSAGE_TYPE_PAYMENT_REQUEST -> 0x0A.toByte() // PAYMENT_REQUEST
-                        Message.MESSAGE_TYPE_PAYMENT_SENT -> 0x0B.toByte()    // PAYMENT_SENT
-                        Message.MESSAGE_TYPE_PAYMENT_ACCEPTED -> 0x0C.toByte() // PAYMENT_ACCEPTED
-                        else -> 0x03.toByte()                                  // TEXT (default)
-                    }
+                    val messageTypeByte: Byte = wireTypeByte(message.messageType)
 
                     // Send message blob
                     val success = RustBridge.sendMessageBlob(
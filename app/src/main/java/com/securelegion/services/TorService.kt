@@ -66,6 +66,8 @@ class TorService : Service() {
     private var isMessagePollerRunning = false
     private var isVoicePollerRunning = false
     private var isFriendRequestPollerRunning = false
+    private var isSessionResetPollerRunning = false
+    private var isRecoveryPollerRunning = false
     private var isPongPollerRunning = false
     private var isSessionCleanupRunning = false
     private var isAckPollerRunning = false
@@ -502,6 +504,27 @@ class TorService : Service() {
     private fun startIncomingListener() {
         Log.i(TAG, "===== startIncomingListener() CALLED =====")
         try {
+            // Hybrid (Ed25519 + ML-DSA) token signing must be ready before any Ping/Pong/ACK is handled
+            if (!com.securelegion.crypto.HybridSigning.init(this)) {
+                Log.w(TAG, "Hybrid signing unavailable - Pings/Pongs/ACKs are Ed25519-only")
+            }
+            if (!com.securelegion.crypto.ContactKeyRegistry.init(this)) {
+                Log.e(TAG, "Key registry unavailable - incoming Pings will be rejected")
+            }
+            if (!com.securelegion.crypto.RatchetJournal.init(this)) {
+                Log.w(TAG, "Ratchet journal unavailable - deferred send ratchets are not crash-safe")
+            }
+            // Seen PINGs are remembered across restarts so a recorded PING cannot be replayed later
+            val replayGuardReady = try {
+                RustBridge.initPingReplayGuard(File(filesDir, "ping_replay.log").absolutePath, 0L) >= 0
+            } catch (e: Exception) {
+                Log.e(TAG, "Failed to open PING replay log", e)
+                false
+            }
+            if (!replayGuardReady) {
+                Log.w(TAG, "PING replay log unavailable - replays are only caught until restart")
+            }
+
             // PHASE 1: Start ACK listener FIRST (port 9153)
             // CRITICAL: Must ALWAYS start ACK listener, even if main listener is already running
             // This initializes ACK_TX channel so any ACKs arriving on port 8080 can be routed
@@ -564,6 +587,18 @@ class TorService : Service() {
             // Both share port 9151, routed by message type in Rust
             startFriendRequestPoller()
 
+            // SESSION_RESET (0x0E) has its own channel so resets are never handled as chain messages
+            if (!RustBridge.startSessionResetListener()) {
+                Log.w(TAG, "Session reset channel already initialized")
+            }
+            startSessionResetPoller()
+
+            // RECOVERY_SHARE (0x0F) likewise has its own channel: shares are not chain messages
+            if (!RustBridge.startRecoveryListener()) {
+                Log.w(TAG, "Recovery channel already initialized")
+            }
+            startRecoveryPoller()
+
             // PONGs arrive at main listener (port 8080) and are routed by message type
             // Start polling for incoming pongs from main listener queue
             startPongPoller()
@@ -823,6 +858,76 @@ class TorService : Service() {
         }.start()
     }
 
+    /**
+     * Start session reset poller
+     * Polls for incoming SESSION_RESET inits/ACKs that re-key desynchronized chains
+     */
+    private fun startSessionResetPoller() {
+        if (isSessionResetPollerRunning) {
+            Log.d(TAG, "Session reset poller already running, skipping")
+            return
+        }
+
+        isSessionResetPollerRunning = true
+
+        Thread {
+            Log.d(TAG, "Session reset poller thread started")
+            while (isServiceRunning) {
+                try {
+                    val resetBytes = RustBridge.pollSessionReset()
+                    if (resetBytes != null) {
+                        Log.i(TAG, "Received session reset: ${resetBytes.size} bytes")
+                        com.securelegion.crypto.SessionHealing.handleIncoming(this, resetBytes)
+                    }
+
+                    // Poll every 2 seconds (resets are rare)
+                    Thread.sleep(2000)
+                } catch (e: InterruptedException) {
+                    Log.d(TAG, "Session reset poller interrupted")
+                    break
+                } catch (e: Exception) {
+                    Log.e(TAG, "Error polling for session resets", e)
+                }
+            }
+            Log.d(TAG, "Session reset poller thread stopped")
+        }.start()
+    }
+
+    /**
+     * Start recovery poller
+     * Polls for incoming social recovery deposits, requests and releases
+     */
+    private fun startRecoveryPoller() {
+        if (isRecoveryPollerRunning) {
+            Log.d(TAG, "Recovery poller already running, skipping")
+            return
+        }
+
+        isRecoveryPollerRunning = true
+
+        Thread {
+            Log.d(TAG, "Recovery poller thread started")
+            while (isServiceRunning) {
+                try {
+                    val recoveryBytes = RustBridge.pollRecoveryMessage()
+                    if (recoveryBytes != null) {
+                        Log.i(TAG, "Received recovery message: ${recoveryBytes.size} bytes")
+                        com.securelegion.crypto.SocialRecovery.handleIncoming(this, recoveryBytes)
+                    }
+
+                    // Poll every 2 seconds (recovery messages are rare)
+                    Thread.sleep(2000)
+                } catch (e: InterruptedException) {
+                    Log.d(TAG, "Recovery poller interrupted")
+                    break
+                } catch (e: Exception) {
+                    Log.e(TAG, "Error polling for recovery messages", e)
+                }
+            }
+            Log.d(TAG, "Recovery poller thread stopped")
+        }.start()
+    }
+
     /**
      * PHASE 8: Start pong poller
      * Polls for incoming PONGs from contacts who are downloading messages
@@ -1247,6 +1352,7 @@ class TorService : Service() {
             // Try to parse as NEW Phase 2 format with signature (v2.1+)
             var contactCard: com.securelegion.models.ContactCard? = null
             var kyberCiphertext: ByteArray? = null
+            var pqxdhInit: ByteArray? = null
 
             try {
                 val phase2Obj = org.json.JSONObject(decryptedJson)
@@ -1265,6 +1371,9 @@ class TorService : Service() {
                             if (phase2Obj.has("kyber_ciphertext")) {
                                 put("kyber_ciphertext", phase2Obj.getString("kyber_ciphertext"))
                             }
+                            if (phase2Obj.has("pqxdh_init")) {
+                                put("pqxdh_init", phase2Obj.getString("pqxdh_init"))
+                            }
                             put("phase", 2)
                         }.toString()
 
@@ -1290,6 +1399,10 @@ class TorService : Service() {
                         kyberCiphertext = android.util.Base64.decode(ciphertextBase64, android.util.Base64.NO_WRAP)
                         Log.i(TAG, "✓ Phase 2 with Kyber ciphertext (${kyberCiphertext.size} bytes)")
                     }
+                    if (phase2Obj.has("pqxdh_init")) {
+                        pqxdhInit = android.util.Base64.decode(phase2Obj.getString("pqxdh_init"), android.util.Base64.NO_WRAP)
+                        Log.i(TAG, "✓ Phase 2 with PQXDH init message (${pqxdhInit.size} bytes)")
+                    }
                 } else {
                     // OLD Phase 2 format (plain ContactCard)
                     contactCard = com.securelegion.models.ContactCard.fromJson(decryptedJson)
@@ -1324,12 +1437,16 @@ class TorService : Service() {
                 contactPin = contactCard.contactPin,
                 ipfsCid = contactCard.ipfsCid,
                 addedTimestamp = System.currentTimeMillis(),
-                friendshipStatus = com.securelegion.database.entities.Contact.FRIENDSHIP_CONFIRMED
+                friendshipStatus = com.securelegion.database.entities.Contact.FRIENDSHIP_CONFIRMED,
+                peerCapabilities = contactCard.capabilities,
+                mlDsaPublicKeyBase64 = contactCard.mlDsaPublicKey?.let { android.util.Base64.encodeToString(it, android.util.Base64.NO_WRAP) }
             )
 
             val contactId = kotlinx.coroutines.runBlocking {
                 database.contactDao().insertContact(contact)
             }
+            com.securelegion.crypto.HybridSigning.registerContact(contact)
+            com.securelegion.crypto.ContactKeyRegistry.pin(contactId, contact)
 
             Log.i(TAG, "Contact added to database: ${contactCard.displayName} (ID: $contactId)")
 
@@ -1340,6 +1457,20 @@ class TorService : Service() {
                     val theirMessagingOnion = contactCard.messagingOnion
                     if (ourMessagingOnion.isNullOrEmpty() || theirMessagingOnion.isNullOrEmpty()) {
                         Log.e(TAG, "Cannot initialize key chain: missing onion address")
+                    } else if (pqxdhInit != null) {
+                        // Device A path: Complete the PQXDH handshake against our published prekeys
+                        val rootKey = pqxdhInit?.let { com.securelegion.crypto.PrekeyManager.respond(this@TorService, it) }
+                            ?: throw IllegalStateException("PQXDH init message does not match our prekeys")
+                        com.securelegion.crypto.KeyChainManager.initializeKeyChain(
+                            context = this@TorService,
+                            contactId = contactId,
+                            theirX25519PublicKey = contactCard.x25519PublicKey,
+                            theirKyberPublicKey = contactCard.kyberPublicKey,
+                            ourMessagingOnion = ourMessagingOnion,
+                            theirMessagingOnion = theirMessagingOnion,
+                            precomputedSharedSecret = rootKey
+                        )
+                        Log.i(TAG, "✓ Key chain initialized for ${contactCard.displayName} (quantum - PQXDH)")
                     } else if (kyberCiphertext != null) {
                         // Device A path: Decapsulate the ciphertext from Phase 2
                         com.securelegion.crypto.KeyChainManager.initializeKeyChain(
@@ -1595,10 +1726,14 @@ class TorService : Service() {
                     contactPin = contactCard.contactPin,
                     ipfsCid = contactCard.ipfsCid,
                     addedTimestamp = System.currentTimeMillis(),
-                    friendshipStatus = com.securelegion.database.entities.Contact.FRIENDSHIP_CONFIRMED
+                    friendshipStatus = com.securelegion.database.entities.Contact.FRIENDSHIP_CONFIRMED,
+                    peerCapabilities = contactCard.capabilities,
+                    mlDsaPublicKeyBase64 = contactCard.mlDsaPublicKey?.let { android.util.Base64.encodeToString(it, android.util.Base64.NO_WRAP) }
                 )
 
                 val contactId = database.contactDao().insertContact(contact)
+                com.securelegion.crypto.HybridSigning.registerContact(contact)
+                com.securelegion.crypto.ContactKeyRegistry.pin(contactId, contact)
                 Log.i(TAG, "Contact added to database: ${contact.displayName} (ID: $contactId)")
 
                 // NOTE: Key chain initialization is handled by the caller (Phase 2/3 handlers)
@@ -1874,10 +2009,17 @@ class TorService : Service() {
                     val database = com.securelegion.database.SecureLegionDatabase.getInstance(this@TorService, dbPassphrase)
 
                     if (message != null) {
+                        // Out-of-order PING_ACK / MESSAGE_ACK must not advance the message
+                        if (!com.securelegion.crypto.DeliveryTracker.accept(message, ackType)) {
+                            Log.w(TAG, "$ackType for ${message.messageId} rejected by delivery tracking")
+                            return@launch
+                        }
+
                         // Update appropriate field based on ACK type
                         val updatedMessage = when (ackType) {
                             "PING_ACK" -> {
                                 Log.i(TAG, "✓ Received PING_ACK for message ${message.messageId} (pingId: $itemId) after $retryCount retries")
+                                com.securelegion.crypto.RatchetJournal.commitOnAck(this@TorService, message.contactId, message.messageId)
                                 // Broadcast to update sender's UI (show single checkmark)
                                 val intent = Intent("com.securelegion.MESSAGE_RECEIVED")
                                 intent.setPackage(packageName)
@@ -2148,7 +2290,7 @@ class TorService : Service() {
             // Try to decrypt as Ping first (may throw exception if it's actually a Pong)
             var pingId: String? = null
             try {
-                pingId = RustBridge.decryptIncomingPing(encryptedPingWire)
+                pingId = RustBridge.decryptIncomingPing(encryptedPingWire, null)
             } catch (e: Exception) {
                 Log.w(TAG, "⚠️  decryptIncomingPing threw exception: ${e.message}")
             }
@@ -2306,6 +2448,12 @@ class TorService : Service() {
                 return
             }
 
+            // TOFU: the sender's X25519 key must also match the keys pinned for this contact
+            if (!com.securelegion.crypto.ContactKeyRegistry.verifyPingSender(this, pingId, contactId)) {
+                Log.w(TAG, "Ping from $senderName rejected - identity keys differ from the pinned ones")
+                return
+            }
+
             // PING INBOX STATE TRACKING: Check current state
             Log.d(TAG, "═══ PING INBOX CHECK: pingId=$pingId ═══")
             val existingPing = withContext(Dispatchers.IO) {
@@ -2336,6 +2484,12 @@ class TorService : Service() {
                     // Don't notify again
                 }
 
+                !RustBridge.checkPingReplay(pingId, encryptedPingWire) -> {
+                    // Not in the inbox, but the replay guard has seen it (or it is outside the
+                    // acceptance window): never surface it again, just ACK so retries stop
+                    Log.w(TAG, "PING $pingId rejected by the replay guard - not notifying")
+                }
+
                 else -> {
                     // New PING - insert into ping_inbox as PING_SEEN
                     Log.i(TAG, "✓ NEW PING $pingId - inserting as PING_SEEN")
@@ -2833,15 +2987,38 @@ class TorService : Service() {
             Log.d(TAG, "KEY CHAIN LOAD: Loaded from database successfully")
             Log.d(TAG, "  sendCounter=${keyChain.sendCounter}")
             Log.d(TAG, "  receiveCounter=${keyChain.receiveCounter} <- will use this for decryption")
-            Log.d(TAG, "Attempting to decrypt ${actualEncryptedMessage.size} bytes with sequence ${keyChain.receiveCounter}...")
-            var result = RustBridge.decryptMessageWithEvolution(
+            // Messages still in flight from the previous session epoch use its receive chain (grace period)
+            val messageEpoch = RustBridge.chainMessageEpoch(actualEncryptedMessage)
+            val previousEpoch = messageEpoch != keyChain.sessionEpoch
+            if (previousEpoch && !MessageService.inPreviousEpochGrace(keyChain, messageEpoch)) {
+                Log.e(TAG, "✗ Message for session epoch $messageEpoch, current ${keyChain.sessionEpoch} - dropping")
+                return
+            }
+            val receiveChainKey = if (previousEpoch) {
+                android.util.Base64.decode(keyChain.previousReceiveChainKeyBase64!!, android.util.Base64.NO_WRAP)
+            } else {
+                keyChain.receiveChainKeyBytes
+            }
+            val receiveCounter = if (previousEpoch) keyChain.previousReceiveCounter else keyChain.receiveCounter
+
+            Log.d(TAG, "Attempting to decrypt ${actualEncryptedMessage.size} bytes with sequence $receiveCounter (epoch $messageEpoch)...")
+            var result = MessageService.decryptChainMessage(
+                this@TorService,
+                contact,
                 actualEncryptedMessage,
-                keyChain.receiveChainKeyBytes,
-                keyChain.receiveCounter
+                receiveChainKey,
+                receiveCounter,
+                messageType,
+                messageEpoch
             )
 
             var plaintext: String
-            var finalReceiveCounter: Long = keyChain.receiveCounter
+            var finalReceiveCounter: Long = receiveCounter
+
+            if (result == null && previousEpoch) {
+                Log.e(TAG, "✗ Previous-epoch message out of order or forged - dropping")
+                return
+            }
 
             if (result == null) {
                 // Decryption failed with expected sequence - try out-of-order decryption
@@ -2891,14 +3068,20 @@ class TorService : Service() {
                 Log.i(TAG, "🔑 Successfully derived key at sender's sequence $senderSequence, retrying decryption...")
 
                 // Try decrypting with the derived key
-                result = RustBridge.decryptMessageWithEvolution(
+                result = MessageService.decryptChainMessage(
+                    this@TorService,
+                    contact,
                     actualEncryptedMessage,
                     derivedKey,
-                    senderSequence
+                    senderSequence,
+                    messageType,
+                    messageEpoch
                 )
 
                 if (result == null) {
                     Log.e(TAG, "✗ Out-of-order decryption also failed for sequence $senderSequence")
+                    // Chains have desynchronized: re-key with a SESSION_RESET (no-op if the contact can't)
+                    com.securelegion.crypto.SessionHealing.initiate(this@TorService, contact)
                     return
                 }
 
@@ -2919,6 +3102,14 @@ class TorService : Service() {
                 val keyManager = com.securelegion.crypto.KeyManager.getInstance(this@TorService)
                 val dbPassphrase = keyManager.getDatabasePassphrase()
                 val database = com.securelegion.database.SecureLegionDatabase.getInstance(this@TorService, dbPassphrase)
+                if (previousEpoch) {
+                    database.contactKeyChainDao().updatePreviousReceiveChainKey(
+                        contactId = contact.id,
+                        previousReceiveChainKeyBase64 = android.util.Base64.encodeToString(result.evolvedChainKey, android.util.Base64.NO_WRAP),
+                        previousReceiveCounter = finalReceiveCounter + 1
+                    )
+                    return@runBlocking
+                }
                 database.contactKeyChainDao().updateReceiveChainKey(
                     contactId = contact.id,
                     newReceiveChainKeyBase64 = android.util.Base64.encodeToString(result.evolvedChainKey, android.util.Base64.NO_WRAP),
@@ -3304,6 +3495,7 @@ class TorService : Service() {
             // Try to parse as NEW Phase 2 format with Kyber ciphertext (v2.1)
             var contactCard: com.securelegion.models.ContactCard? = null
             var kyberCiphertext: ByteArray? = null
+            var pqxdhInit: ByteArray? = null
             var isPhase2 = false
 
             try {
@@ -3322,6 +3514,10 @@ class TorService : Service() {
                     } else {
                         Log.i(TAG, "✓ Phase 2 (legacy): Received ContactCard from: ${contactCard.displayName}")
                     }
+                    if (phase2Obj.has("pqxdh_init")) {
+                        pqxdhInit = android.util.Base64.decode(phase2Obj.getString("pqxdh_init"), android.util.Base64.NO_WRAP)
+                        Log.i(TAG, "✓ Phase 2 (quantum): Received PQXDH init message (${pqxdhInit.size} bytes)")
+                    }
 
                     // Verify Ed25519 signature (defense-in-depth against .onion MitM)
                     if (phase2Obj.has("signature") && phase2Obj.has("ed25519_public_key")) {
@@ -3334,6 +3530,9 @@ class TorService : Service() {
                             if (phase2Obj.has("kyber_ciphertext")) {
                                 put("kyber_ciphertext", phase2Obj.getString("kyber_ciphertext"))
                             }
+                            if (phase2Obj.has("pqxdh_init")) {
+                                put("pqxdh_init", phase2Obj.getString("pqxdh_init"))
+                            }
                             put("phase", 2)
                         }.toString()
 
@@ -3396,12 +3595,16 @@ class TorService : Service() {
                         addedTimestamp = System.currentTimeMillis(),
                         lastContactTimestamp = System.currentTimeMillis(),
                         trustLevel = com.securelegion.database.entities.Contact.TRUST_UNTRUSTED,
-                        friendshipStatus = com.securelegion.database.entities.Contact.FRIENDSHIP_CONFIRMED
+                        friendshipStatus = com.securelegion.database.entities.Contact.FRIENDSHIP_CONFIRMED,
+                        peerCapabilities = contactCard.capabilities,
+                        mlDsaPublicKeyBase64 = contactCard.mlDsaPublicKey?.let { android.util.Base64.encodeToString(it, android.util.Base64.NO_WRAP) }
                     )
 
                     val contactId = kotlinx.coroutines.runBlocking {
                         database.contactDao().insertContact(contact)
                     }
+                    com.securelegion.crypto.HybridSigning.registerContact(contact)
+                    com.securelegion.crypto.ContactKeyRegistry.pin(contactId, contact)
 
                     Log.i(TAG, "✓ Phase 2: Added ${contactCard.displayName} to Contacts (ID: $contactId)")
 
@@ -3452,6 +3655,20 @@ class TorService : Service() {
                                         precomputedSharedSecret = precomputedSharedSecret
                                     )
                                     Log.i(TAG, "✓ Key chain initialized for ${contact.displayName} (quantum - precomputed secret)")
+                                } else if (pqxdhInit != null) {
+                                    // Device A (initiator) path: Complete the PQXDH handshake against our published prekeys
+                                    val rootKey = pqxdhInit?.let { com.securelegion.crypto.PrekeyManager.respond(this@TorService, it) }
+                                        ?: throw IllegalStateException("PQXDH init message does not match our prekeys")
+                                    com.securelegion.crypto.KeyChainManager.initializeKeyChain(
+                                        context = this@TorService,
+                                        contactId = contactId,
+                                        theirX25519PublicKey = contactCard.x25519PublicKey,
+                                        theirKyberPublicKey = contactCard.kyberPublicKey,
+                                        ourMessagingOnion = ourMessagingOnion,
+                                        theirMessagingOnion = theirMessagingOnion,
+                                        precomputedSharedSecret = rootKey
+                                    )
+                                    Log.i(TAG, "✓ Key chain initialized for ${contact.displayName} (quantum - PQXDH)")
                                 } else if (kyberCiphertext != null) {
                                     // Device A (initiator) path: Decapsulate ciphertext from Phase 2
                                     com.securelegion.crypto.KeyChainManager.initializeKeyChain(
@@ -3622,12 +3839,16 @@ class TorService : Service() {
                     addedTimestamp = System.currentTimeMillis(),
                     lastContactTimestamp = System.currentTimeMillis(),
                     trustLevel = com.securelegion.database.entities.Contact.TRUST_UNTRUSTED,
-                    friendshipStatus = com.securelegion.database.entities.Contact.FRIENDSHIP_CONFIRMED
+                    friendshipStatus = com.securelegion.database.entities.Contact.FRIENDSHIP_CONFIRMED,
+                    peerCapabilities = contactCard.capabilities,
+                    mlDsaPublicKeyBase64 = contactCard.mlDsaPublicKey?.let { android.util.Base64.encodeToString(it, android.util.Base64.NO_WRAP) }
                 )
 
                 val contactId = kotlinx.coroutines.runBlocking {
                     database.contactDao().insertContact(contact)
                 }
+                com.securelegion.crypto.HybridSigning.registerContact(contact)
+                com.securelegion.crypto.ContactKeyRegistry.pin(contactId, contact)
 
                 Log.i(TAG, "✓ Added ${contactCard.displayName} to Contacts with CONFIRMED status (ID: $contactId)")
 
@@ -108,16 +108,22 @@ class BiometricAuthHelper(private val context: Context) {
     /**
      * Enable biometric authentication by encrypting the password hash
      *
+     * Also used to re-encrypt the hash after it was upgraded at password login
+     *
      * @param passwordHash The Argon2id hash of the user's password
      * @param activity The activity to show biometric prompt
      * @param onSuccess Callback when encryption succeeds
      * @param onError Callback when encryption fails
+     * @param title Prompt title
+     * @param subtitle Prompt subtitle
      */
     fun enableBiometric(
         passwordHash: ByteArray,
         activity: FragmentActivity,
         onSuccess: () -> Unit,
-        onError: (String) -> Unit
+        onError: (String) -> Unit,
+        title: String = "Enable Biometric Unlock",
+        subtitle: String = "Authenticate to enable fingerprint/face unlock"
     ) {
         try {
             val cipher = getCipher()
@@ -166,8 +172,8 @@ class BiometricAuthHelper(private val context: Context) {
             )
 
             val promptInfo = BiometricPrompt.PromptInfo.Builder()
-                .setTitle("Enable Biometric Unlock")
-                .setSubtitle("Authenticate to enable fingerprint/face unlock")
+                .setTitle(title)
+                .setSubtitle(subtitle)
                 .setNegativeButtonText("Cancel")
                 .setAllowedAuthenticators(BiometricManager.Authenticators.BIOMETRIC_STRONG)
                 .build()
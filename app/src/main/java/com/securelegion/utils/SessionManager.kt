@@ -33,11 +33,13 @@ object SessionManager {
 
     /**
      * Mark the app as locked (user on lock screen or auto-lock triggered)
+     * Also drops the wallet seed Rust cached for this session
      */
     fun setLocked(context: Context) {
         getPrefs(context).edit()
             .putBoolean(KEY_IS_UNLOCKED, false)
             .apply()
+        com.securelegion.crypto.RustBridge.clearKeyHierarchy()
     }
 
     /**
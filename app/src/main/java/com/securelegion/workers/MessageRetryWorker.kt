@@ -3,6 +3,7 @@ package com.securelegion.workers
 import android.content.Context
 import android.util.Log
 import androidx.work.*
+import com.securelegion.crypto.DeliveryTracker
 import com.securelegion.crypto.KeyManager
 import com.securelegion.database.SecureLegionDatabase
 import com.securelegion.database.entities.Message
@@ -202,6 +203,8 @@ class MessageRetryWorker(
                     }
 
                     if (retrySuccess) {
+                        DeliveryTracker.begin(message)
+
                         // Update retry counter and timestamp
                         val updatedMessage = message.copy(
                             retryCount = message.retryCount + 1,
@@ -1,113 +1,551 @@
+//! Delivery ACK State Machine
+//! Tracks each message exchange by `message_id`
+//!
+//! A delivery moves through the protocol stages in order:
+//!
+//! ```text
+//! Sent → PingAcked → PongAcked → MessageAcked
+//!   └──────────┴───────────┴──→ Failed / Expired
+//! ```
+//!
+//! Every delivery has its own state and timestamps, so several exchanges with the same
+//! contact can be in flight at once. ACKs that skip a stage are rejected; an ACK for a
+//! stage already reached is accepted as a duplicate (peers resend ACKs on retry) and
+//! leaves the state unchanged. TAP_ACK is a contact-level liveness signal and never
+//! moves a delivery.
+//!
+//! Each non-terminal state has a timeout. Deliveries that overstay it are reported by
+//! `stuck`, and deliveries older than the overall expiry are moved to `Expired`.
+
+use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use std::sync::Mutex;
-use once_cell::sync::Lazy;
+use thiserror::Error;
+
+/// ACK type constants (wire values used over JNI)
+pub const ACK_TYPE_PING: u8 = 0;
+pub const ACK_TYPE_PONG: u8 = 1;
+pub const ACK_TYPE_MESSAGE: u8 = 2;
+pub const ACK_TYPE_TAP: u8 = 3;
+
+/// Default time to wait for a PING_ACK after sending
+pub const DEFAULT_PING_ACK_TIMEOUT_SECS: i64 = 5 * 60;
+
+/// Default time to wait for the PONG exchange after a PING_ACK
+pub const DEFAULT_PONG_ACK_TIMEOUT_SECS: i64 = 10 * 60;
+
+/// Default time to wait for a MESSAGE_ACK after the PONG exchange
+pub const DEFAULT_MESSAGE_ACK_TIMEOUT_SECS: i64 = 10 * 60;
+
+/// Default age after which an unfinished delivery expires (7 days, matching the PING window)
+pub const DEFAULT_EXPIRY_SECS: i64 = 7 * 24 * 60 * 60;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum AckStateError {
+    #[error("Unknown ACK type: {0}")]
+    UnknownAckType(String),
+    #[error("Delivery already tracked: {0}")]
+    AlreadyTracked(String),
+    #[error("Unknown delivery: {0}")]
+    UnknownDelivery(String),
+    #[error("{ack:?} out of order for delivery in state {state:?}")]
+    OutOfOrder { state: DeliveryState, ack: AckKind },
+    #[error("Delivery already finished in state {0:?}")]
+    Finished(DeliveryState),
+}
+
+pub type Result<T> = std::result::Result<T, AckStateError>;
+
+/// Kind of delivery ACK, shared by the JNI byte codes and `DeliveryAck` strings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AckKind {
+    Ping,
+    Pong,
+    Message,
+    Tap,
+}
+
+impl AckKind {
+    /// Parse a JNI ACK type code (`ACK_TYPE_*`)
+    pub fn from_u8(value: u8) -> Result<Self> {
+        match value {
+            ACK_TYPE_PING => Ok(Self::Ping),
+            ACK_TYPE_PONG => Ok(Self::Pong),
+            ACK_TYPE_MESSAGE => Ok(Self::Message),
+            ACK_TYPE_TAP => Ok(Self::Tap),
+            other => Err(AckStateError::UnknownAckType(other.to_string())),
+        }
+    }
+
+    /// Parse a `DeliveryAck::ACK_TYPE_*` string
+    pub fn from_ack_type(value: &str) -> Result<Self> {
+        use crate::network::pingpong::DeliveryAck;
+
+        match value {
+            DeliveryAck::ACK_TYPE_PING => Ok(Self::Ping),
+            DeliveryAck::ACK_TYPE_PONG => Ok(Self::Pong),
+            DeliveryAck::ACK_TYPE_MESSAGE => Ok(Self::Message),
+            DeliveryAck::ACK_TYPE_TAP => Ok(Self::Tap),
+            other => Err(AckStateError::UnknownAckType(other.to_string())),
+        }
+    }
+
+    /// JNI ACK type code
+    pub fn as_u8(self) -> u8 {
+        match self {
+            Self::Ping => ACK_TYPE_PING,
+            Self::Pong => ACK_TYPE_PONG,
+            Self::Message => ACK_TYPE_MESSAGE,
+            Self::Tap => ACK_TYPE_TAP,
+        }
+    }
+
+    /// `DeliveryAck` wire string
+    pub fn as_ack_type(self) -> &'static str {
+        use crate::network::pingpong::DeliveryAck;
+
+        match self {
+            Self::Ping => DeliveryAck::ACK_TYPE_PING,
+            Self::Pong => DeliveryAck::ACK_TYPE_PONG,
+            Self::Message => DeliveryAck::ACK_TYPE_MESSAGE,
+            Self::Tap => DeliveryAck::ACK_TYPE_TAP,
+        }
+    }
+
+    /// State this ACK moves a delivery into (TAP_ACK moves nothing)
+    fn target_state(self) -> Option<DeliveryState> {
+        match self {
+            Self::Ping => Some(DeliveryState::PingAcked),
+            Self::Pong => Some(DeliveryState::PongAcked),
+            Self::Message => Some(DeliveryState::MessageAcked),
+            Self::Tap => None,
+        }
+    }
+}
+
+/// Stage of a single message delivery
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DeliveryState {
+    /// PING sent, waiting for PING_ACK
+    Sent,
+    /// PING_ACK received, waiting for the PONG exchange
+    PingAcked,
+    /// PONG_ACK exchanged, waiting for MESSAGE_ACK
+    PongAcked,
+    /// MESSAGE_ACK received (delivered)
+    MessageAcked,
+    /// Given up by the caller
+    Failed,
+    /// Unfinished when the expiry elapsed
+    Expired,
+}
+
+impl DeliveryState {
+    /// True once no further ACKs can change the state
+    pub fn is_terminal(self) -> bool {
+        matches!(self, Self::MessageAcked | Self::Failed | Self::Expired)
+    }
+
+    /// Lowercase name, as reported over JNI
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Sent => "sent",
+            Self::PingAcked => "ping_acked",
+            Self::PongAcked => "pong_acked",
+            Self::MessageAcked => "message_acked",
+            Self::Failed => "failed",
+            Self::Expired => "expired",
+        }
+    }
+}
+
+/// How long a delivery may stay in each stage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AckTimeouts {
+    pub ping_ack_secs: i64,
+    pub pong_ack_secs: i64,
+    pub message_ack_secs: i64,
+    pub expiry_secs: i64,
+}
+
+impl Default for AckTimeouts {
+    fn default() -> Self {
+        Self {
+            ping_ack_secs: DEFAULT_PING_ACK_TIMEOUT_SECS,
+            pong_ack_secs: DEFAULT_PONG_ACK_TIMEOUT_SECS,
+            message_ack_secs: DEFAULT_MESSAGE_ACK_TIMEOUT_SECS,
+            expiry_secs: DEFAULT_EXPIRY_SECS,
+        }
+    }
+}
+
+impl AckTimeouts {
+    /// Timeout for a state (None for terminal states)
+    fn for_state(&self, state: DeliveryState) -> Option<i64> {
+        match state {
+            DeliveryState::Sent => Some(self.ping_ack_secs),
+            DeliveryState::PingAcked => Some(self.pong_ack_secs),
+            DeliveryState::PongAcked => Some(self.message_ack_secs),
+            _ => None,
+        }
+    }
+}
+
+/// One tracked message exchange
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delivery {
+    pub message_id: String,
+    pub contact_id: String,
+    pub state: DeliveryState,
+    /// When the PING was sent (Unix seconds)
+    pub sent_at: i64,
+    /// When the delivery entered its current state (Unix seconds)
+    pub state_since: i64,
+}
+
+/// Delivery state machines, keyed by `message_id`
+pub struct AckTracker {
+    timeouts: AckTimeouts,
+    deliveries: HashMap<String, Delivery>,
+}
+
+impl AckTracker {
+    pub fn new(timeouts: AckTimeouts) -> Self {
+        Self {
+            timeouts,
+            deliveries: HashMap::new(),
+        }
+    }
+
+    /// Start tracking a delivery in the `Sent` state
+    ///
+    /// # Arguments
+    /// * `message_id` - Message being delivered
+    /// * `contact_id` - Recipient
+    /// * `now` - Current Unix time (seconds)
+    pub fn begin(&mut self, message_id: &str, contact_id: &str, now: i64) -> Result<()> {
+        if self.deliveries.contains_key(message_id) {
+            return Err(AckStateError::AlreadyTracked(message_id.to_string()));
+        }
+
+        self.deliveries.insert(message_id.to_string(), Delivery {
+            message_id: message_id.to_string(),
+            contact_id: contact_id.to_string(),
+            state: DeliveryState::Sent,
+            sent_at: now,
+            state_since: now,
+        });
+        Ok(())
+    }
+
+    /// Apply an ACK to a delivery
+    ///
+    /// # Arguments
+    /// * `message_id` - Delivery the ACK refers to
+    /// * `ack` - Kind of ACK received
+    /// * `now` - Current Unix time (seconds)
+    ///
+    /// # Returns
+    /// The delivery's state after the ACK (unchanged for duplicates and TAP_ACK)
+    pub fn record_ack(&mut self, message_id: &str, ack: AckKind, now: i64) -> Result<DeliveryState> {
+        let delivery = self.deliveries.get_mut(message_id)
+            .ok_or_else(|| AckStateError::UnknownDelivery(message_id.to_string()))?;
+
+        let target = match ack.target_state() {
+            Some(target) => target,
+            None => return Ok(delivery.state),
+        };
+
+        match delivery.state {
+            DeliveryState::Failed | DeliveryState::Expired => {
+                Err(AckStateError::Finished(delivery.state))
+            }
+            state if state >= target => Ok(state),
+            state if next_state(state) == Some(target) => {
+                delivery.state = target;
+                delivery.state_since = now;
+                Ok(target)
+            }
+            state => Err(AckStateError::OutOfOrder { state, ack }),
+        }
+    }
+
+    /// Mark a delivery as failed
+    pub fn fail(&mut self, message_id: &str, now: i64) -> Result<()> {
+        let delivery = self.deliveries.get_mut(message_id)
+            .ok_or_else(|| AckStateError::UnknownDelivery(message_id.to_string()))?;
+
+        if delivery.state.is_terminal() {
+            return Err(AckStateError::Finished(delivery.state));
+        }
+        delivery.state = DeliveryState::Failed;
+        delivery.state_since = now;
+        Ok(())
+    }
+
+    /// Look up a delivery
+    pub fn get(&self, message_id: &str) -> Option<&Delivery> {
+        self.deliveries.get(message_id)
+    }
+
+    /// Stop tracking a delivery
+    pub fn remove(&mut self, message_id: &str) -> Option<Delivery> {
+        self.deliveries.remove(message_id)
+    }
+
+    /// All deliveries to one contact
+    pub fn for_contact(&self, contact_id: &str) -> Vec<&Delivery> {
+        self.deliveries.values()
+            .filter(|d| d.contact_id == contact_id)
+            .collect()
+    }
+
+    /// Unfinished deliveries that have overstayed their current stage, oldest first
+    pub fn stuck(&self, now: i64) -> Vec<&Delivery> {
+        let mut stuck: Vec<&Delivery> = self.deliveries.values()
+            .filter(|d| match self.timeouts.for_state(d.state) {
+                Some(timeout) => now.saturating_sub(d.state_since) > timeout,
+                None => false,
+            })
+            .collect();
+        stuck.sort_by_key(|d| d.sent_at);
+        stuck
+    }
+
+    /// Expire unfinished deliveries past the overall expiry and forget finished ones
+    ///
+    /// # Returns
+    /// Message IDs that were moved to `Expired`
+    pub fn expire(&mut self, now: i64) -> Vec<String> {
+        let expiry = self.timeouts.expiry_secs;
+        let mut expired = Vec::new();
+
+        self.deliveries.retain(|id, d| {
+            let overdue = now.saturating_sub(d.sent_at) > expiry;
+            if !overdue {
+                return true;
+            }
+            if d.state.is_terminal() {
+                return false;
+            }
+            d.state = DeliveryState::Expired;
+            d.state_since = now;
+            expired.push(id.clone());
+            true
+        });
+
+        expired
+    }
+
+    pub fn len(&self) -> usize {
+        self.deliveries.len()
+    }
 
-/// ACK state for a single contact
-#[derive(Debug, Clone, Default)]
-pub struct AckState {
-    pub ping_ack_received: bool,
-    pub pong_ack_received: bool,
-    pub message_ack_received: bool,
+    pub fn is_empty(&self) -> bool {
+        self.deliveries.is_empty()
+    }
 }
 
-/// Global ACK state manager
-static ACK_STATES: Lazy<Mutex<HashMap<String, AckState>>> = Lazy::new(|| {
-    Mutex::new(HashMap::new())
+fn next_state(state: DeliveryState) -> Option<DeliveryState> {
+    match state {
+        DeliveryState::Sent => Some(DeliveryState::PingAcked),
+        DeliveryState::PingAcked => Some(DeliveryState::PongAcked),
+        DeliveryState::PongAcked => Some(DeliveryState::MessageAcked),
+        _ => None,
+    }
+}
+
+/// Global delivery tracker
+static ACK_TRACKER: Lazy<Mutex<AckTracker>> = Lazy::new(|| {
+    Mutex::new(AckTracker::new(AckTimeouts::default()))
 });
 
-/// ACK type constants
-pub const ACK_TYPE_PING: u8 = 0;
-pub const ACK_TYPE_PONG: u8 = 1;
-pub const ACK_TYPE_MESSAGE: u8 = 2;
+/// Start tracking a delivery (call when the PING is sent)
+///
+/// Returns false if the message is already tracked
+///
+/// # Arguments
+/// * `message_id` - Message being delivered
+/// * `contact_id` - Recipient
+pub fn begin_delivery(message_id: &str, contact_id: &str) -> bool {
+    match ACK_TRACKER.lock().unwrap().begin(message_id, contact_id, unix_now()) {
+        Ok(()) => {
+            log::debug!("✓ Tracking delivery {}", message_id);
+            true
+        }
+        Err(e) => {
+            log::warn!("⚠️  {}", e);
+            false
+        }
+    }
+}
 
 /// Validate ACK ordering and update state
 ///
 /// Returns true if ACK is valid according to protocol ordering
-/// Returns false if ACK violates ordering (should be rejected)
+/// Returns false if ACK violates ordering or the delivery is unknown (should be rejected)
 ///
 /// # Arguments
-/// * `contact_id` - Contact identifier
-/// * `ack_type` - Type of ACK (0=PING_ACK, 1=PONG_ACK, 2=MESSAGE_ACK)
-pub fn validate_and_record_ack(contact_id: &str, ack_type: u8) -> bool {
-    let mut states = ACK_STATES.lock().unwrap();
-    let state = states.entry(contact_id.to_string()).or_default();
-
-    match ack_type {
-        ACK_TYPE_PING => {
-            // PING_ACK always valid (first ACK in sequence)
-            state.ping_ack_received = true;
-            log::info!("✓ PING_ACK accepted for contact {}", contact_id);
-            true
+/// * `message_id` - Delivery the ACK refers to
+/// * `ack_type` - Type of ACK (0=PING_ACK, 1=PONG_ACK, 2=MESSAGE_ACK, 3=TAP_ACK)
+pub fn validate_and_record_ack(message_id: &str, ack_type: u8) -> bool {
+    let ack = match AckKind::from_u8(ack_type) {
+        Ok(ack) => ack,
+        Err(e) => {
+            log::error!("✗ {}", e);
+            return false;
         }
-        ACK_TYPE_PONG => {
-            // PONG_ACK requires PING_ACK first
-            if !state.ping_ack_received {
-                log::warn!("⚠️  PONG_ACK rejected for contact {} - PING_ACK not received", contact_id);
-                return false;
-            }
-            state.pong_ack_received = true;
-            log::info!("✓ PONG_ACK accepted for contact {}", contact_id);
-            true
-        }
-        ACK_TYPE_MESSAGE => {
-            // MESSAGE_ACK requires PONG_ACK first
-            if !state.pong_ack_received {
-                log::warn!("⚠️  MESSAGE_ACK rejected for contact {} - PONG_ACK not received", contact_id);
-                return false;
-            }
-            state.message_ack_received = true;
-            log::info!("✓ MESSAGE_ACK accepted for contact {}", contact_id);
+    };
+
+    match ACK_TRACKER.lock().unwrap().record_ack(message_id, ack, unix_now()) {
+        Ok(state) => {
+            log::info!("✓ {} accepted for delivery {} ({:?})", ack.as_ack_type(), message_id, state);
             true
         }
-        _ => {
-            log::error!("✗ Invalid ACK type: {}", ack_type);
+        Err(e) => {
+            log::warn!("⚠️  {} rejected for delivery {}: {}", ack.as_ack_type(), message_id, e);
             false
         }
     }
 }
 
-/// Reset ACK state for a contact (e.g., after completing a message exchange)
-pub fn reset_ack_state(contact_id: &str) {
-    let mut states = ACK_STATES.lock().unwrap();
-    states.remove(contact_id);
-    log::debug!("✓ ACK state reset for contact {}", contact_id);
+/// Mark a delivery as failed (e.g., retries exhausted)
+pub fn mark_delivery_failed(message_id: &str) -> bool {
+    ACK_TRACKER.lock().unwrap().fail(message_id, unix_now()).is_ok()
 }
 
-/// Clear all ACK states (for testing)
-#[cfg(test)]
-pub fn clear_ack_states() {
-    let mut states = ACK_STATES.lock().unwrap();
-    states.clear();
+/// Current state of a delivery, if tracked
+pub fn delivery_state(message_id: &str) -> Option<DeliveryState> {
+    ACK_TRACKER.lock().unwrap().get(message_id).map(|d| d.state)
+}
+
+/// Expire overdue deliveries, then list the ones stuck in a stage
+///
+/// # Returns
+/// Snapshot of stuck deliveries, oldest first
+pub fn stuck_deliveries() -> Vec<Delivery> {
+    let now = unix_now();
+    let mut tracker = ACK_TRACKER.lock().unwrap();
+
+    for message_id in tracker.expire(now) {
+        log::warn!("Delivery {} expired before completing", message_id);
+    }
+    tracker.stuck(now).into_iter().cloned().collect()
+}
+
+/// Stop tracking a delivery (e.g., after completing a message exchange)
+pub fn reset_ack_state(message_id: &str) {
+    ACK_TRACKER.lock().unwrap().remove(message_id);
+    log::debug!("✓ ACK state reset for delivery {}", message_id);
+}
+
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const NOW: i64 = 1_700_000_000;
+
     #[test]
     fn test_ack_ordering() {
-        clear_ack_states();
-        let contact = "test_contact";
+        let mut tracker = AckTracker::new(AckTimeouts::default());
+        let message = "test_message";
+        tracker.begin(message, "test_contact", NOW).unwrap();
 
-        // PING_ACK should always be valid
-        assert!(validate_and_record_ack(contact, ACK_TYPE_PING));
+        // PING_ACK should be valid after sending
+        assert_eq!(tracker.record_ack(message, AckKind::Ping, NOW + 1), Ok(DeliveryState::PingAcked));
 
         // PONG_ACK should be valid after PING_ACK
-        assert!(validate_and_record_ack(contact, ACK_TYPE_PONG));
+        assert_eq!(tracker.record_ack(message, AckKind::Pong, NOW + 2), Ok(DeliveryState::PongAcked));
 
         // MESSAGE_ACK should be valid after PONG_ACK
-        assert!(validate_and_record_ack(contact, ACK_TYPE_MESSAGE));
+        assert_eq!(tracker.record_ack(message, AckKind::Message, NOW + 3), Ok(DeliveryState::MessageAcked));
+
+        // Resent ACKs are accepted without moving the state
+        assert_eq!(tracker.record_ack(message, AckKind::Ping, NOW + 4), Ok(DeliveryState::MessageAcked));
+        assert_eq!(tracker.get(message).unwrap().state_since, NOW + 3);
     }
 
     #[test]
     fn test_ack_ordering_violation() {
-        clear_ack_states();
-        let contact = "test_contact2";
+        let mut tracker = AckTracker::new(AckTimeouts::default());
+        let message = "test_message2";
+
+        // ACKs for untracked deliveries are rejected
+        assert_eq!(tracker.record_ack(message, AckKind::Ping, NOW),
+            Err(AckStateError::UnknownDelivery(message.into())));
+
+        tracker.begin(message, "test_contact", NOW).unwrap();
 
         // MESSAGE_ACK should be rejected without PONG_ACK
-        assert!(!validate_and_record_ack(contact, ACK_TYPE_MESSAGE));
+        assert_eq!(tracker.record_ack(message, AckKind::Message, NOW + 1),
+            Err(AckStateError::OutOfOrder { state: DeliveryState::Sent, ack: AckKind::Message }));
 
         // PONG_ACK should be rejected without PING_ACK
-        assert!(!validate_and_record_ack(contact, ACK_TYPE_PONG));
+        assert_eq!(tracker.record_ack(message, AckKind::Pong, NOW + 1),
+            Err(AckStateError::OutOfOrder { state: DeliveryState::Sent, ack: AckKind::Pong }));
+        assert_eq!(tracker.get(message).unwrap().state, DeliveryState::Sent);
+    }
+
+    #[test]
+    fn test_concurrent_deliveries_to_one_contact() {
+        let mut tracker = AckTracker::new(AckTimeouts::default());
+        tracker.begin("m1", "alice", NOW).unwrap();
+        tracker.begin("m2", "alice", NOW).unwrap();
+        assert_eq!(tracker.begin("m1", "alice", NOW), Err(AckStateError::AlreadyTracked("m1".into())));
+
+        tracker.record_ack("m1", AckKind::Ping, NOW + 1).unwrap();
+        tracker.record_ack("m1", AckKind::Pong, NOW + 2).unwrap();
+        assert_eq!(tracker.record_ack("m2", AckKind::Message, NOW + 3),
+            Err(AckStateError::OutOfOrder { state: DeliveryState::Sent, ack: AckKind::Message }));
+        assert_eq!(tracker.record_ack("m2", AckKind::Tap, NOW + 3), Ok(DeliveryState::Sent));
+
+        tracker.fail("m2", NOW + 4).unwrap();
+        assert_eq!(tracker.record_ack("m2", AckKind::Ping, NOW + 5),
+            Err(AckStateError::Finished(DeliveryState::Failed)));
+        assert_eq!(tracker.get("m1").unwrap().state, DeliveryState::PongAcked);
+        assert_eq!(tracker.for_contact("alice").len(), 2);
+    }
+
+    #[test]
+    fn test_stuck_and_expired_deliveries() {
+        let timeouts = AckTimeouts::default();
+        let mut tracker = AckTracker::new(timeouts);
+        tracker.begin("old", "alice", NOW).unwrap();
+        tracker.begin("new", "bob", NOW + 100).unwrap();
+        tracker.begin("done", "bob", NOW).unwrap();
+        for ack in [AckKind::Ping, AckKind::Pong, AckKind::Message] {
+            tracker.record_ack("done", ack, NOW).unwrap();
+        }
+
+        let later = NOW + timeouts.ping_ack_secs + 50;
+        let stuck: Vec<&str> = tracker.stuck(later).iter().map(|d| d.message_id.as_str()).collect();
+        assert_eq!(stuck, vec!["old"]);
+
+        // Acking restarts the stage timer
+        tracker.record_ack("old", AckKind::Ping, later).unwrap();
+        assert!(tracker.stuck(later + 1).is_empty());
+
+        let expired = tracker.expire(NOW + timeouts.expiry_secs + 1);
+        assert_eq!(expired, vec!["old".to_string()]);
+        assert_eq!(tracker.get("old").unwrap().state, DeliveryState::Expired);
+        assert!(tracker.get("done").is_none());
+        assert_eq!(tracker.get("new").unwrap().state, DeliveryState::Sent);
+    }
+
+    #[test]
+    fn test_ack_kind_codes_match_delivery_ack_strings() {
+        for kind in [AckKind::Ping, AckKind::Pong, AckKind::Message, AckKind::Tap] {
+            assert_eq!(AckKind::from_u8(kind.as_u8()), Ok(kind));
+            assert_eq!(AckKind::from_ack_type(kind.as_ack_type()), Ok(kind));
+        }
+        assert!(AckKind::from_ack_type("BOGUS_ACK").is_err());
+        assert!(AckKind::from_u8(9).is_err());
     }
 }
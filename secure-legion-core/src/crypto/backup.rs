@@ -0,0 +1,447 @@
+//! Identity Backup
+//! Passphrase-sealed export of the wallet seed phrase, contacts and ratchet state
+//!
+//! The identity is KeyManager's 12-word BIP39 seed phrase. The blob stores its entropy
+//! (the checksum is recomputed on restore), and every identity key is re-derived from the
+//! 64-byte BIP39 seed (PBKDF2 of the phrase, empty passphrase) exactly as KeyManager does:
+//! Ed25519 = seed[0..32], every other key = SHA-256(seed || label). A restored device
+//! therefore gets the same keys and .onion addresses.
+//!
+//! Blob format (v3): [magic: 4][version: 1][kdf: 15][salt: 16][nonce: 24][ciphertext][tag: 16]
+//! The header (magic, version, KDF descriptor and salt) is bound as associated data,
+//! and the ciphertext is sealed with XChaCha20-Poly1305 under an Argon2id key.
+//! Blobs before v3 carried a 32-byte seed KeyManager never derived from; they cannot
+//! restore an identity and are refused.
+
+use crate::crypto::hashing::{derive_key, generate_salt, HashingError, KdfParams, KDF_DESCRIPTOR_LEN};
+use crate::crypto::key_hierarchy::{DerivationVersion, KeyHierarchy};
+use crate::protocol::contact::ContactCard;
+use crate::protocol::key_registry::TrustRecord;
+use bip39::Mnemonic;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, OsRng, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use ed25519_dalek::SigningKey;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use x25519_dalek::{PublicKey, StaticSecret};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// Identifies a Secure Legion backup blob
+pub const BACKUP_MAGIC: &[u8; 4] = b"SLBK";
+
+/// Current backup blob version
+pub const BACKUP_VERSION: u8 = 3;
+
+/// Words in KeyManager's seed phrase (128 bits of entropy + 4-bit checksum)
+pub const MNEMONIC_WORDS: usize = 12;
+
+/// Length of the BIP39 seed the identity keys derive from
+pub const BIP39_SEED_LEN: usize = 64;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const HEADER_LEN: usize = 4 + 1 + KDF_DESCRIPTOR_LEN + SALT_LEN;
+
+#[derive(Error, Debug)]
+pub enum BackupError {
+    #[error("Invalid mnemonic: {0}")]
+    InvalidMnemonic(String),
+    #[error("Not a backup file")]
+    InvalidFormat,
+    #[error("Unsupported backup version: {0}")]
+    UnsupportedVersion(u8),
+    #[error("Wrong passphrase or corrupted backup")]
+    DecryptionFailed,
+    #[error("Encryption failed")]
+    EncryptionFailed,
+    #[error("Key derivation failed: {0}")]
+    KeyDerivation(String),
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+    #[error("Passphrase hashing failed: {0}")]
+    Hashing(#[from] HashingError),
+}
+
+pub type Result<T> = std::result::Result<T, BackupError>;
+
+/// A contact as stored in a backup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupContact {
+    pub contact_id: String,
+    /// `ContactCard::serialize` output (keeps the PQ signature trailer)
+    card: Vec<u8>,
+    /// TOFU trust state for the contact's keys, if any
+    pub trust: Option<TrustRecord>,
+}
+
+/// Ratchet state for one contact
+#[derive(Clone, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub struct BackupRatchetState {
+    #[zeroize(skip)]
+    pub contact_id: String,
+    /// Serialized session (`RatchetSession::to_bytes`, or the app's linear chain record)
+    pub state: Vec<u8>,
+}
+
+/// Everything sealed in a backup blob
+#[derive(Serialize, Deserialize)]
+pub struct BackupContents {
+    /// BIP39 entropy of the seed phrase
+    seed_entropy: Vec<u8>,
+    pub contacts: Vec<BackupContact>,
+    pub ratchet_states: Vec<BackupRatchetState>,
+    /// Unix seconds when the backup was made
+    pub created_at: u64,
+}
+
+/// An Ed25519 or X25519 keypair derived from the BIP39 seed
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct DerivedKeyPair {
+    pub private_key: [u8; 32],
+    pub public_key: [u8; 32],
+}
+
+/// Every identity key, re-derived from the BIP39 seed
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct IdentityKeyMaterial {
+    /// Ed25519 identity/signing key
+    pub signing: DerivedKeyPair,
+    /// X25519 encryption key
+    pub x25519: DerivedKeyPair,
+    pub kyber_public: Vec<u8>,
+    pub kyber_secret: Vec<u8>,
+    /// Ed25519 key of the messaging .onion
+    pub hidden_service: DerivedKeyPair,
+    /// Ed25519 key of the friend request .onion
+    pub friend_request: DerivedKeyPair,
+    /// Ed25519 key of the voice .onion
+    pub voice_service: DerivedKeyPair,
+}
+
+/// Result of restoring a backup blob
+pub struct RestoredBackup {
+    pub keys: IdentityKeyMaterial,
+    pub contents: BackupContents,
+}
+
+impl BackupContact {
+    pub fn new(contact_id: &str, card: &ContactCard, trust: Option<TrustRecord>) -> Result<Self> {
+        let card = card.serialize().map_err(|e| BackupError::Serialization(e.to_string()))?;
+        Ok(Self { contact_id: contact_id.to_string(), card, trust })
+    }
+
+    pub fn card(&self) -> Result<ContactCard> {
+        ContactCard::deserialize(&self.card).map_err(|e| BackupError::Serialization(e.to_string()))
+    }
+}
+
+impl BackupContents {
+    /// # Arguments
+    /// * `seed_phrase` - KeyManager's wallet seed phrase
+    pub fn new(seed_phrase: &str) -> Result<Self> {
+        let (mut entropy, len) = parse_seed_phrase(seed_phrase)?.to_entropy_array();
+        let seed_entropy = entropy[..len].to_vec();
+        entropy.zeroize();
+
+        Ok(Self {
+            seed_entropy,
+            contacts: Vec::new(),
+            ratchet_states: Vec::new(),
+            created_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        })
+    }
+
+    /// The seed phrase, as KeyManager.initializeFromSeed takes it
+    pub fn seed_phrase(&self) -> Result<Zeroizing<String>> {
+        Mnemonic::from_entropy(&self.seed_entropy)
+            .map(|mnemonic| Zeroizing::new(mnemonic.to_string()))
+            .map_err(|e| BackupError::InvalidMnemonic(e.to_string()))
+    }
+
+    /// The 64-byte BIP39 seed every identity key derives from
+    pub fn bip39_seed(&self) -> Result<Zeroizing<[u8; BIP39_SEED_LEN]>> {
+        seed_phrase_to_seed(&self.seed_phrase()?)
+    }
+}
+
+impl Drop for BackupContents {
+    fn drop(&mut self) {
+        self.seed_entropy.zeroize();
+    }
+}
+
+impl DerivedKeyPair {
+    fn ed25519(signing_key: &SigningKey) -> Self {
+        Self {
+            private_key: signing_key.to_bytes(),
+            public_key: signing_key.verifying_key().to_bytes(),
+        }
+    }
+
+    fn x25519(secret: &StaticSecret) -> Self {
+        Self {
+            public_key: PublicKey::from(secret).to_bytes(),
+            private_key: secret.to_bytes(),
+        }
+    }
+}
+
+impl IdentityKeyMaterial {
+    /// Derive every identity key from the BIP39 seed
+    ///
+    /// Uses the `Legacy` path of `KeyHierarchy`: signing uses seed[0..32] and the
+    /// other keys use SHA-256(seed || label), exactly as KeyManager does.
+    ///
+    /// # Arguments
+    /// * `bip39_seed` - 64-byte BIP39 seed of the wallet seed phrase
+    pub fn from_bip39_seed(bip39_seed: &[u8; BIP39_SEED_LEN]) -> Result<Self> {
+        let hierarchy = KeyHierarchy::new(bip39_seed, DerivationVersion::Legacy)
+            .map_err(|e| BackupError::KeyDerivation(e.to_string()))?;
+        let hybrid = hierarchy
+            .hybrid_keypair()
+            .map_err(|e| BackupError::KeyDerivation(e.to_string()))?;
+
+        Ok(Self {
+            signing: DerivedKeyPair::ed25519(&hierarchy.signing_key()),
+            x25519: DerivedKeyPair::x25519(&hierarchy.encryption_secret()),
+            kyber_public: hybrid.kyber_public.to_vec(),
+            kyber_secret: hybrid.kyber_secret.to_vec(),
+            hidden_service: DerivedKeyPair::ed25519(&hierarchy.hidden_service_key()),
+            friend_request: DerivedKeyPair::ed25519(&hierarchy.friend_request_key()),
+            voice_service: DerivedKeyPair::ed25519(&hierarchy.voice_service_key()),
+        })
+    }
+}
+
+/// Parse a seed phrase, checking word list, word count and checksum
+///
+/// # Arguments
+/// * `seed_phrase` - 12 words separated by whitespace (case-insensitive)
+pub fn parse_seed_phrase(seed_phrase: &str) -> Result<Mnemonic> {
+    let mut normalized = seed_phrase.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    let parsed = Mnemonic::parse_normalized(&normalized)
+        .map_err(|e| BackupError::InvalidMnemonic(e.to_string()));
+    normalized.zeroize();
+    let parsed = parsed?;
+    if parsed.word_count() != MNEMONIC_WORDS {
+        return Err(BackupError::InvalidMnemonic(format!(
+            "expected {} words, got {}",
+            MNEMONIC_WORDS,
+            parsed.word_count()
+        )));
+    }
+    Ok(parsed)
+}
+
+/// The 64-byte BIP39 seed of a seed phrase (PBKDF2-HMAC-SHA512, empty passphrase)
+///
+/// Matches KeyManager's `MnemonicUtils.generateSeed(phrase, "")`.
+pub fn seed_phrase_to_seed(seed_phrase: &str) -> Result<Zeroizing<[u8; BIP39_SEED_LEN]>> {
+    Ok(Zeroizing::new(parse_seed_phrase(seed_phrase)?.to_seed("")))
+}
+
+/// Seal backup contents under a passphrase
+///
+/// # Arguments
+/// * `contents` - Seed phrase, contacts and ratchet state
+/// * `passphrase` - User passphrase (stretched with Argon2id)
+///
+/// # Returns
+/// Versioned backup blob
+pub fn create_backup(contents: &BackupContents, passphrase: &str) -> Result<Vec<u8>> {
+    create_backup_with_params(contents, passphrase, &KdfParams::default())
+}
+
+/// Seal backup contents under a passphrase with explicit KDF parameters
+///
+/// # Arguments
+/// * `contents` - Seed phrase, contacts and ratchet state
+/// * `passphrase` - User passphrase
+/// * `kdf` - Argon2 parameters (recorded in the header)
+///
+/// # Returns
+/// Versioned backup blob
+pub fn create_backup_with_params(contents: &BackupContents, passphrase: &str, kdf: &KdfParams) -> Result<Vec<u8>> {
+    let salt = generate_salt();
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(BACKUP_MAGIC);
+    header.push(BACKUP_VERSION);
+    header.extend_from_slice(&kdf.to_bytes());
+    header.extend_from_slice(&salt);
+
+    let mut plaintext = bincode::serialize(contents).map_err(|e| BackupError::Serialization(e.to_string()))?;
+    let mut key = derive_key(passphrase, &salt, kdf)?;
+    let cipher = XChaCha20Poly1305::new_from_slice(&key).map_err(|_| BackupError::EncryptionFailed);
+    key.zeroize();
+    let ciphertext = cipher?
+        .encrypt(XNonce::from_slice(&nonce), Payload { msg: &plaintext, aad: &header })
+        .map_err(|_| BackupError::EncryptionFailed);
+    plaintext.zeroize();
+
+    let mut blob = header;
+    blob.extend_from_slice(&nonce);
+    blob.extend_from_slice(&ciphertext?);
+    Ok(blob)
+}
+
+/// Open a backup blob and re-derive every identity key from its seed phrase
+///
+/// # Arguments
+/// * `blob` - Output of `create_backup`
+/// * `passphrase` - Passphrase the backup was made with
+pub fn restore_backup(blob: &[u8], passphrase: &str) -> Result<RestoredBackup> {
+    if blob.len() < 5 || &blob[..4] != BACKUP_MAGIC {
+        return Err(BackupError::InvalidFormat);
+    }
+    if blob[4] != BACKUP_VERSION {
+        return Err(BackupError::UnsupportedVersion(blob[4]));
+    }
+    if blob.len() < HEADER_LEN + NONCE_LEN + 16 {
+        return Err(BackupError::InvalidFormat);
+    }
+
+    let (header, rest) = blob.split_at(HEADER_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let kdf = KdfParams::from_bytes(&header[5..5 + KDF_DESCRIPTOR_LEN])?;
+    let salt = &header[5 + KDF_DESCRIPTOR_LEN..];
+
+    let mut key = derive_key(passphrase, salt, &kdf)?;
+    let cipher = XChaCha20Poly1305::new_from_slice(&key).map_err(|_| BackupError::DecryptionFailed);
+    key.zeroize();
+    let mut plaintext = cipher?
+        .decrypt(XNonce::from_slice(nonce), Payload { msg: ciphertext, aad: header })
+        .map_err(|_| BackupError::DecryptionFailed)?;
+
+    let contents = bincode::deserialize::<BackupContents>(&plaintext)
+        .map_err(|e| BackupError::Serialization(e.to_string()));
+    plaintext.zeroize();
+    let contents = contents?;
+
+    let keys = IdentityKeyMaterial::from_bip39_seed(&*contents.bip39_seed()?)?;
+    Ok(RestoredBackup { keys, contents })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::contact::RelayPreferences;
+
+    fn sample_card() -> ContactCard {
+        ContactCard {
+            public_key: vec![1; 32],
+            solana_address: "addr".to_string(),
+            handle: "alice".to_string(),
+            onion_address: Some("alice.onion".to_string()),
+            relay_preferences: RelayPreferences { accepts_relay_messages: false, preferred_relays: vec![] },
+            timestamp: 1,
+            signature: vec![2; 64],
+            capabilities: 0,
+            pq_signature: None,
+        }
+    }
+
+    /// BIP39 test vector: 11 x "abandon" + "about"
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn test_derivation_matches_key_manager_vectors() {
+        // BIP39 seed of PHRASE with an empty passphrase (published vector)
+        let seed = seed_phrase_to_seed(&PHRASE.to_uppercase()).unwrap();
+        assert_eq!(
+            hex::encode(*seed),
+            "5eb00bbddcf069084889a8ab9155568165f5c453ccb85e70811aaed6f6da5fc1\
+             9a5ac40b389cd370d086206dec8aa6c43daea6690f20ad3d8d48b2d2ce9e38e4"
+        );
+
+        // KeyManager: Ed25519 from seed[0..32], the rest from SHA-256(seed || label)
+        // (computed independently of this crate)
+        let keys = IdentityKeyMaterial::from_bip39_seed(&seed).unwrap();
+        assert_eq!(keys.signing.private_key, seed[..32]);
+        assert_eq!(hex::encode(keys.signing.public_key), "c5785e1865b708938aff8161d573006496663b1aa10834e396dc566869a2c66a");
+        assert_eq!(hex::encode(keys.x25519.private_key), "d8c7ab9b1a3234fc0796fb6a65662ad10e3b6b1f3895330fc5d17e6d5ed9cfda");
+        assert_eq!(hex::encode(keys.x25519.public_key), "6e6015770d8fe02fd16d5f0fcabf928a99d2039d6cca6e74fb6431a48195ae41");
+        assert_eq!(hex::encode(keys.hidden_service.public_key), "991cfa54bc10d2e6d1a3bfdc2e01629e406fd1e77f908bc611399e7d9314236a");
+        assert_eq!(hex::encode(keys.friend_request.public_key), "1fe492587e80dea9eac2fead0f09aec65be93bd359f846c3c00587cb528190fc");
+        assert_eq!(hex::encode(keys.voice_service.public_key), "6d0b617e466d6d7d8571b85d82ddff177d309ba082041d08c4ffb5706d1c1016");
+
+        // Word count and checksum are enforced
+        assert!(parse_seed_phrase(&PHRASE.replace("about", "abandon")).is_err());
+        assert!(parse_seed_phrase(&format!("{} {}", PHRASE, PHRASE)).is_err());
+    }
+
+    #[test]
+    fn test_backup_roundtrip_rederives_keys() {
+        let mut contents = BackupContents::new(PHRASE).unwrap();
+        contents.contacts.push(BackupContact::new("alice", &sample_card(), None).unwrap());
+        contents.ratchet_states.push(BackupRatchetState { contact_id: "alice".into(), state: vec![9; 40] });
+
+        let blob = create_backup(&contents, "correct horse").unwrap();
+        assert_eq!(&blob[..4], BACKUP_MAGIC);
+
+        let restored = restore_backup(&blob, "correct horse").unwrap();
+        assert_eq!(restored.contents.seed_phrase().unwrap().as_str(), PHRASE);
+        assert_eq!(restored.contents.contacts[0].card().unwrap().handle, "alice");
+        assert_eq!(restored.contents.ratchet_states[0].state, vec![9; 40]);
+
+        let expected = IdentityKeyMaterial::from_bip39_seed(&seed_phrase_to_seed(PHRASE).unwrap()).unwrap();
+        let keys = &restored.keys;
+        assert_eq!(keys.signing.public_key, expected.signing.public_key);
+        assert_eq!(keys.x25519.public_key, expected.x25519.public_key);
+        assert_eq!(keys.kyber_public, expected.kyber_public);
+        assert_eq!(keys.voice_service.public_key, expected.voice_service.public_key);
+
+        // Every key is independent
+        let publics = [
+            keys.signing.public_key,
+            keys.x25519.public_key,
+            keys.hidden_service.public_key,
+            keys.friend_request.public_key,
+            keys.voice_service.public_key,
+        ];
+        for (i, a) in publics.iter().enumerate() {
+            assert!(publics[i + 1..].iter().all(|b| a != b));
+        }
+    }
+
+    #[test]
+    fn test_backup_rejects_wrong_passphrase_and_tampering() {
+        let contents = BackupContents::new(PHRASE).unwrap();
+        let blob = create_backup(&contents, "passphrase").unwrap();
+
+        assert!(matches!(restore_backup(&blob, "wrong"), Err(BackupError::DecryptionFailed)));
+
+        let mut tampered = blob.clone();
+        tampered[HEADER_LEN - 1] ^= 1; // salt
+        assert!(matches!(restore_backup(&tampered, "passphrase"), Err(BackupError::DecryptionFailed)));
+
+        let mut future = blob.clone();
+        future[4] = BACKUP_VERSION + 1;
+        assert!(matches!(restore_backup(&future, "passphrase"), Err(BackupError::UnsupportedVersion(_))));
+        assert!(matches!(restore_backup(&blob[..10], "passphrase"), Err(BackupError::InvalidFormat)));
+    }
+
+    #[test]
+    fn test_backup_records_kdf_and_refuses_pre_v3_blobs() {
+        let contents = BackupContents::new(PHRASE).unwrap();
+        let kdf = KdfParams { m_cost_kib: 8 * 1024, t_cost: 1, ..KdfParams::RECOMMENDED };
+        let blob = create_backup_with_params(&contents, "pass", &kdf).unwrap();
+        assert_eq!(KdfParams::from_bytes(&blob[5..5 + KDF_DESCRIPTOR_LEN]).unwrap(), kdf);
+        assert_eq!(restore_backup(&blob, "pass").unwrap().contents.seed_phrase().unwrap().as_str(), PHRASE);
+
+        // v1/v2 held a 32-byte seed no identity key was derived from
+        for version in [1u8, 2] {
+            let mut old = blob.clone();
+            old[4] = version;
+            assert!(matches!(restore_backup(&old, "pass"), Err(BackupError::UnsupportedVersion(v)) if v == version));
+        }
+    }
+}
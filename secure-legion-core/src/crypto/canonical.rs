@@ -0,0 +1,136 @@
+//! Canonical Signing Encoding
+//! Length-delimited TLV bytes covered by every token signature
+//!
+//! Layout: [version u8][domain len u8][domain][field]* where each field is
+//! [tag u8][len u32 BE][value]. The domain names the token type (its `PQ_CONTEXT`),
+//! tags are per type and strictly increasing, and absent optional fields are omitted.
+//! Every field carries its length, so no two distinct tokens of any type share
+//! signed bytes. Integers are big-endian; booleans are one byte.
+//!
+//! Tokens signed before this encoding covered a bare concatenation of their fields.
+//! Builds that verify the canonical encoding advertise `CAPABILITY_CANONICAL_SIGNING`;
+//! tokens for a peer without it are still signed over the legacy encoding, and legacy
+//! signatures are accepted from such peers while `legacy_signing_accepted()` holds.
+//! A peer that negotiated canonical signing never gets or has legacy bytes accepted.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Canonical encoding version (first byte of the signed bytes)
+pub const CANONICAL_SIGNING_VERSION: u8 = 1;
+
+/// Whether the pre-canonical concatenated encoding is still in use (migration window)
+static ACCEPT_LEGACY_SIGNING: AtomicBool = AtomicBool::new(true);
+
+/// Enable or disable the legacy signing encoding for peers without canonical signing
+///
+/// Leave enabled until every contact advertises `CAPABILITY_CANONICAL_SIGNING`;
+/// disabling it closes the migration window: tokens are then always signed canonically
+/// and legacy signatures are rejected from every peer.
+pub fn set_legacy_signing_accepted(accepted: bool) {
+    ACCEPT_LEGACY_SIGNING.store(accepted, Ordering::SeqCst);
+    log::info!("✓ Legacy signing encoding: {}", accepted);
+}
+
+/// Whether the legacy signing encoding is still used for peers without canonical signing
+pub fn legacy_signing_accepted() -> bool {
+    ACCEPT_LEGACY_SIGNING.load(Ordering::SeqCst)
+}
+
+/// Builds the canonical signing bytes for one token
+pub struct CanonicalEncoder {
+    bytes: Vec<u8>,
+    last_tag: u8,
+}
+
+impl CanonicalEncoder {
+    /// Start an encoding for a token type
+    ///
+    /// # Arguments
+    /// * `domain` - Domain separation tag (at most 255 bytes)
+    pub fn new(domain: &[u8]) -> Self {
+        assert!(domain.len() <= u8::MAX as usize, "signing domain too long");
+
+        let mut bytes = Vec::with_capacity(2 + domain.len() + 64);
+        bytes.push(CANONICAL_SIGNING_VERSION);
+        bytes.push(domain.len() as u8);
+        bytes.extend_from_slice(domain);
+        Self { bytes, last_tag: 0 }
+    }
+
+    /// Append a raw byte field
+    ///
+    /// # Arguments
+    /// * `tag` - Field tag (non-zero, greater than the previous tag)
+    /// * `value` - Field value
+    pub fn bytes(mut self, tag: u8, value: &[u8]) -> Self {
+        assert!(tag > self.last_tag, "field tags must be strictly increasing");
+        assert!(value.len() <= u32::MAX as usize, "field too long");
+
+        self.last_tag = tag;
+        self.bytes.push(tag);
+        self.bytes.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        self.bytes.extend_from_slice(value);
+        self
+    }
+
+    /// Append a UTF-8 string field
+    pub fn str(self, tag: u8, value: &str) -> Self {
+        self.bytes(tag, value.as_bytes())
+    }
+
+    /// Append a signed integer field
+    pub fn i64(self, tag: u8, value: i64) -> Self {
+        self.bytes(tag, &value.to_be_bytes())
+    }
+
+    /// Append a boolean field
+    pub fn bool(self, tag: u8, value: bool) -> Self {
+        self.bytes(tag, &[value as u8])
+    }
+
+    /// Append an optional string field (omitted when None)
+    pub fn opt_str(self, tag: u8, value: Option<&str>) -> Self {
+        match value {
+            Some(value) => self.str(tag, value),
+            None => self,
+        }
+    }
+
+    /// Finish the encoding
+    pub fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        let encoded = CanonicalEncoder::new(b"T").str(1, "ab").bool(2, true).finish();
+        assert_eq!(encoded, vec![CANONICAL_SIGNING_VERSION, 1, b'T', 1, 0, 0, 0, 2, b'a', b'b', 2, 0, 0, 0, 1, 1]);
+    }
+
+    #[test]
+    fn test_field_splits_and_domains_are_distinct() {
+        // "ab" + "c" and "a" + "bc" concatenate identically but encode differently
+        let a = CanonicalEncoder::new(b"Card").str(1, "ab").str(2, "c").finish();
+        let b = CanonicalEncoder::new(b"Card").str(1, "a").str(2, "bc").finish();
+        assert_ne!(a, b);
+
+        let other_type = CanonicalEncoder::new(b"Ping").str(1, "ab").str(2, "c").finish();
+        assert_ne!(a, other_type);
+
+        // An absent optional field differs from an empty one
+        let absent = CanonicalEncoder::new(b"Card").opt_str(1, None).finish();
+        let empty = CanonicalEncoder::new(b"Card").opt_str(1, Some("")).finish();
+        assert_ne!(absent, empty);
+    }
+
+    #[test]
+    #[should_panic(expected = "strictly increasing")]
+    fn test_rejects_out_of_order_tags() {
+        let _ = CanonicalEncoder::new(b"T").i64(2, 0).i64(1, 0);
+    }
+}
@@ -0,0 +1,344 @@
+//! Duress Unlock
+//! One record, two passwords: the real one opens the profile, the duress one opens a decoy
+//!
+//! An `UnlockRecord` holds two slots under the same Argon2id parameters: the real
+//! password's and the duress password's. Each slot is [salt 16][verifier 32][flags 1];
+//! the password is stretched with `hashing::derive_key` and HKDF splits the result into
+//! the verifier, a database key and a mask that seals the slot's flags. Only the duress
+//! slot's database key is handed out: it keys the decoy profile's database. The real
+//! profile's database key is derived from the wallet seed by the app, not from here. Without a
+//! duress password the second slot is random filler, so the record does not reveal
+//! whether one is configured, or whether it wipes.
+//!
+//! `unlock` always derives both slots and compares both verifiers in constant time
+//! before choosing an outcome, so the real password, the duress password and a wrong
+//! password all cost the same. A duress slot flagged to wipe returns `Wiped` and
+//! replaces the real slot with filler; the caller persists the record and destroys the
+//! real key material and ratchet state.
+
+use crate::crypto::hashing::{derive_key, HashingError, KdfParams, KDF_DESCRIPTOR_LEN};
+use hkdf::Hkdf;
+use rand::{rngs::OsRng, RngCore};
+use sha2::Sha256;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+/// Current record layout version
+pub const UNLOCK_RECORD_VERSION: u8 = 1;
+
+/// Encoded size of an `UnlockRecord`
+pub const UNLOCK_RECORD_LEN: usize = 1 + KDF_DESCRIPTOR_LEN + 2 * SLOT_LEN;
+
+const SALT_LEN: usize = 16;
+const SLOT_LEN: usize = SALT_LEN + 32 + 1;
+const HKDF_SALT: &[u8] = b"SecureLegion-Unlock";
+
+/// Slot flag: wipe the real profile when this slot unlocks
+const FLAG_WIPE: u8 = 0x01;
+
+#[derive(Error, Debug)]
+pub enum DuressError {
+    #[error("Wrong password")]
+    WrongPassword,
+    #[error("Duress password must differ from the real password")]
+    SamePassword,
+    #[error("Invalid unlock record")]
+    InvalidRecord,
+    #[error("Password hashing failed: {0}")]
+    Hashing(#[from] HashingError),
+}
+
+pub type Result<T> = std::result::Result<T, DuressError>;
+
+/// Which profile a password unlocked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnlockOutcome {
+    /// Real password: open the real profile
+    Real,
+    /// Duress password: open the empty decoy profile
+    Decoy,
+    /// Duress password with wipe: destroy the real profile, then open the decoy
+    Wiped,
+}
+
+impl UnlockOutcome {
+    /// Wire value used over JNI
+    pub fn as_u8(self) -> u8 {
+        match self {
+            Self::Real => 0,
+            Self::Decoy => 1,
+            Self::Wiped => 2,
+        }
+    }
+}
+
+/// Result of a successful unlock
+pub struct Unlocked {
+    pub outcome: UnlockOutcome,
+    /// Key of the decoy profile's database on `Decoy` and `Wiped`; all zeros on `Real`
+    pub decoy_key: Zeroizing<[u8; 32]>,
+}
+
+/// Keys derived from one password for one slot
+struct SlotKeys {
+    verifier: [u8; 32],
+    database_key: Zeroizing<[u8; 32]>,
+    flags_mask: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Slot {
+    salt: [u8; SALT_LEN],
+    verifier: [u8; 32],
+    sealed_flags: u8,
+}
+
+impl Slot {
+    /// Random filler, indistinguishable from a configured slot
+    fn filler() -> Self {
+        let mut slot = Self { salt: [0u8; SALT_LEN], verifier: [0u8; 32], sealed_flags: 0 };
+        OsRng.fill_bytes(&mut slot.salt);
+        OsRng.fill_bytes(&mut slot.verifier);
+        slot.sealed_flags = OsRng.next_u32() as u8;
+        slot
+    }
+
+    fn create(password: &str, flags: u8, kdf: &KdfParams) -> Result<Self> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let keys = derive_slot_keys(password, &salt, kdf)?;
+
+        Ok(Self {
+            salt,
+            verifier: keys.verifier,
+            sealed_flags: flags ^ keys.flags_mask,
+        })
+    }
+
+    /// Derive this slot's keys for `password` and check the verifier
+    fn open(&self, password: &str, kdf: &KdfParams) -> Result<(Choice, SlotKeys)> {
+        let keys = derive_slot_keys(password, &self.salt, kdf)?;
+        let matches = keys.verifier.ct_eq(&self.verifier);
+        Ok((matches, keys))
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.salt);
+        out.extend_from_slice(&self.verifier);
+        out.push(self.sealed_flags);
+    }
+
+    fn read(bytes: &[u8]) -> Self {
+        Self {
+            salt: bytes[..SALT_LEN].try_into().unwrap(),
+            verifier: bytes[SALT_LEN..SALT_LEN + 32].try_into().unwrap(),
+            sealed_flags: bytes[SLOT_LEN - 1],
+        }
+    }
+}
+
+/// Stored unlock state: KDF parameters plus the real and duress slots
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnlockRecord {
+    kdf: KdfParams,
+    real: Slot,
+    duress: Slot,
+}
+
+impl UnlockRecord {
+    /// Create a record for the real password (no duress password yet)
+    ///
+    /// # Arguments
+    /// * `password` - Real password
+    /// * `kdf` - Argon2 parameters for both slots
+    pub fn new(password: &str, kdf: KdfParams) -> Result<Self> {
+        Ok(Self {
+            real: Slot::create(password, 0, &kdf)?,
+            duress: Slot::filler(),
+            kdf,
+        })
+    }
+
+    /// Argon2 parameters used by both slots
+    pub fn kdf(&self) -> &KdfParams {
+        &self.kdf
+    }
+
+    /// Set (or replace) the duress password
+    ///
+    /// # Arguments
+    /// * `real_password` - Real password, to authorize the change
+    /// * `duress_password` - Password that opens the decoy profile
+    /// * `wipe` - Also destroy the real profile when the duress password is used
+    pub fn set_duress_password(&mut self, real_password: &str, duress_password: &str, wipe: bool) -> Result<()> {
+        self.authorize(real_password)?;
+
+        let (same, _) = self.real.open(duress_password, &self.kdf)?;
+        if bool::from(same) {
+            return Err(DuressError::SamePassword);
+        }
+
+        let flags = if wipe { FLAG_WIPE } else { 0 };
+        self.duress = Slot::create(duress_password, flags, &self.kdf)?;
+        Ok(())
+    }
+
+    /// Remove the duress password (its slot becomes filler again)
+    pub fn clear_duress_password(&mut self, real_password: &str) -> Result<()> {
+        self.authorize(real_password)?;
+        self.duress = Slot::filler();
+        Ok(())
+    }
+
+    /// Unlock with a password
+    ///
+    /// Both slots are always derived and compared, so timing does not depend on which
+    /// password was entered. On `Wiped` the real slot is replaced with filler; persist
+    /// `to_bytes()` before reporting the unlock.
+    ///
+    /// # Arguments
+    /// * `password` - Password entered at the lock screen
+    ///
+    /// # Returns
+    /// The outcome and, unless it is `Real`, the decoy database key
+    pub fn unlock(&mut self, password: &str) -> Result<Unlocked> {
+        let (is_real, _) = self.real.open(password, &self.kdf)?;
+        let (is_duress, duress) = self.duress.open(password, &self.kdf)?;
+
+        let mut decoy_key = Zeroizing::new([0u8; 32]);
+        for (out, d) in decoy_key.iter_mut().zip(duress.database_key.iter()) {
+            *out = u8::conditional_select(d, &0, is_real);
+        }
+        let flags = self.duress.sealed_flags ^ duress.flags_mask;
+        let wipe = is_duress & !is_real & Choice::from(flags & FLAG_WIPE);
+
+        let outcome = if bool::from(is_real) {
+            UnlockOutcome::Real
+        } else if bool::from(wipe) {
+            self.real = Slot::filler();
+            UnlockOutcome::Wiped
+        } else if bool::from(is_duress) {
+            UnlockOutcome::Decoy
+        } else {
+            return Err(DuressError::WrongPassword);
+        };
+
+        Ok(Unlocked { outcome, decoy_key })
+    }
+
+    /// Encode for storage
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(UNLOCK_RECORD_LEN);
+        out.push(UNLOCK_RECORD_VERSION);
+        out.extend_from_slice(&self.kdf.to_bytes());
+        self.real.write(&mut out);
+        self.duress.write(&mut out);
+        out
+    }
+
+    /// Decode a stored record
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != UNLOCK_RECORD_LEN || bytes[0] != UNLOCK_RECORD_VERSION {
+            return Err(DuressError::InvalidRecord);
+        }
+
+        let kdf_end = 1 + KDF_DESCRIPTOR_LEN;
+        let kdf = KdfParams::from_bytes(&bytes[1..kdf_end]).map_err(|_| DuressError::InvalidRecord)?;
+        Ok(Self {
+            kdf,
+            real: Slot::read(&bytes[kdf_end..kdf_end + SLOT_LEN]),
+            duress: Slot::read(&bytes[kdf_end + SLOT_LEN..]),
+        })
+    }
+
+    fn authorize(&self, real_password: &str) -> Result<()> {
+        let (matches, _) = self.real.open(real_password, &self.kdf)?;
+        if bool::from(matches) {
+            Ok(())
+        } else {
+            Err(DuressError::WrongPassword)
+        }
+    }
+}
+
+fn derive_slot_keys(password: &str, salt: &[u8], kdf: &KdfParams) -> Result<SlotKeys> {
+    let stretched = Zeroizing::new(derive_key(password, salt, kdf)?);
+    let hk = Hkdf::<Sha256>::new(Some(HKDF_SALT), stretched.as_slice());
+
+    let mut verifier = [0u8; 32];
+    let mut database_key = Zeroizing::new([0u8; 32]);
+    let mut flags_mask = [0u8; 1];
+    hk.expand(b"SecureLegion-Unlock-v1/verifier", &mut verifier)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    hk.expand(b"SecureLegion-Unlock-v1/database", database_key.as_mut())
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    hk.expand(b"SecureLegion-Unlock-v1/flags", &mut flags_mask)
+        .expect("1 byte is a valid HKDF-SHA256 output length");
+
+    Ok(SlotKeys { verifier, database_key, flags_mask: flags_mask[0] })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_kdf() -> KdfParams {
+        KdfParams { m_cost_kib: 8 * 1024, t_cost: 1, ..KdfParams::RECOMMENDED }
+    }
+
+    #[test]
+    fn test_real_decoy_and_wrong_password() {
+        let mut record = UnlockRecord::new("real pass", test_kdf()).unwrap();
+        assert!(matches!(record.unlock("1234"), Err(DuressError::WrongPassword)));
+
+        record.set_duress_password("real pass", "1234", false).unwrap();
+        let real = record.unlock("real pass").unwrap();
+        let decoy = record.unlock("1234").unwrap();
+
+        assert_eq!(real.outcome, UnlockOutcome::Real);
+        assert_eq!(decoy.outcome, UnlockOutcome::Decoy);
+        assert_eq!(*real.decoy_key, [0u8; 32]);
+        assert_ne!(*decoy.decoy_key, [0u8; 32]);
+        assert_eq!(*record.unlock("1234").unwrap().decoy_key, *decoy.decoy_key);
+        assert!(matches!(record.unlock("wrong"), Err(DuressError::WrongPassword)));
+
+        assert!(matches!(record.set_duress_password("wrong", "5678", false), Err(DuressError::WrongPassword)));
+        assert!(matches!(record.set_duress_password("real pass", "real pass", false), Err(DuressError::SamePassword)));
+        record.clear_duress_password("real pass").unwrap();
+        assert!(matches!(record.unlock("1234"), Err(DuressError::WrongPassword)));
+    }
+
+    #[test]
+    fn test_wipe_destroys_real_slot() {
+        let mut record = UnlockRecord::new("real pass", test_kdf()).unwrap();
+        record.set_duress_password("real pass", "1234", true).unwrap();
+
+        let decoy_key = record.unlock("1234").unwrap();
+        assert_eq!(decoy_key.outcome, UnlockOutcome::Wiped);
+
+        // Persisted record no longer opens the real profile; the decoy still opens
+        let mut reloaded = UnlockRecord::from_bytes(&record.to_bytes()).unwrap();
+        assert!(matches!(reloaded.unlock("real pass"), Err(DuressError::WrongPassword)));
+        let again = reloaded.unlock("1234").unwrap();
+        assert_eq!(*again.decoy_key, *decoy_key.decoy_key);
+    }
+
+    #[test]
+    fn test_record_encoding_hides_duress_configuration() {
+        let plain = UnlockRecord::new("real pass", test_kdf()).unwrap();
+        let mut with_duress = plain.clone();
+        with_duress.set_duress_password("real pass", "1234", true).unwrap();
+
+        let a = plain.to_bytes();
+        let b = with_duress.to_bytes();
+        assert_eq!(a.len(), UNLOCK_RECORD_LEN);
+        assert_eq!(b.len(), UNLOCK_RECORD_LEN);
+        assert_eq!(UnlockRecord::from_bytes(&b).unwrap(), with_duress);
+
+        assert!(UnlockRecord::from_bytes(&a[..UNLOCK_RECORD_LEN - 1]).is_err());
+        let mut bad_version = a.clone();
+        bad_version[0] = UNLOCK_RECORD_VERSION + 1;
+        assert!(UnlockRecord::from_bytes(&bad_version).is_err());
+    }
+}
@@ -1,31 +1,154 @@
 use chacha20poly1305::{
-    aead::{Aead, KeyInit, OsRng},
+    aead::{Aead, KeyInit, OsRng, Payload},
     XChaCha20Poly1305, XNonce,
 };
 use hkdf::Hkdf;
 use hmac::{Hmac, Mac};
 use rand::RngCore;
+use serde::{Deserialize, Serialize};
 use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use super::padding::{pad, unpad, PaddingScheme};
+use super::skipped_keys::{SkippedKeyStore, LINEAR_CHAIN_ID};
+use std::sync::atomic::{AtomicBool, Ordering};
 use thiserror::Error;
-use zeroize::Zeroize;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 /// Maximum number of sequence numbers ahead that will be accepted
 /// Messages with sequence >= expected + WINDOW_SIZE will be rejected
 /// This prevents desync from packet loss while still protecting against replay attacks
 const SEQUENCE_WINDOW_SIZE: u64 = 100;
 
+/// Linear chain wire version without header authentication
+pub const WIRE_VERSION_V1: u8 = 0x01;
+
+/// Linear chain wire version with the header bound as AEAD associated data
+/// v2 plaintexts are always padded (see `padding`)
+pub const WIRE_VERSION_V2: u8 = 0x02;
+
+/// Key-committing wire version: v2 with a key-commitment tag ahead of the ciphertext
+/// Also the version byte of the standalone `encrypt_message_committing` format
+pub const WIRE_VERSION_V3: u8 = 0x03;
+
+/// Header flag on the v2/v3 version byte: a 4-byte session epoch follows the sequence
+/// Only set for epochs > 0, i.e. sessions re-keyed by SESSION_RESET (see `session_reset`)
+pub const WIRE_FLAG_EPOCH: u8 = 0x80;
+
+/// Whether v1 messages are still accepted by the v2 decrypt paths (migration window)
+static ACCEPT_LEGACY_V1: AtomicBool = AtomicBool::new(true);
+
+/// HKDF info for the key commitment and the AEAD subkey it commits to
+const KEY_COMMITMENT_INFO: &[u8] = b"SecureLegion-KeyCommitment-v1";
+
+/// Length of the key-commitment tag
+pub const KEY_COMMITMENT_BYTES: usize = 32;
+
+/// Context bound into every v2 message as AEAD associated data
+///
+/// The receiver builds the same binding from its own view: `sender_identity` is the
+/// peer's identity key and `recipient_identity` is ours. `msg_type` is the
+/// `MSG_TYPE_*` byte the transport routed the message on. `epoch` is the session
+/// epoch the chain keys belong to (0 until the first SESSION_RESET).
+///
+/// `accept_legacy_v1` is receive policy, not associated data: whether a v1 message
+/// is still accepted under this binding. It defaults to the app-wide migration
+/// setting (`set_legacy_v1_accepted`) when the binding is built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageBinding {
+    pub sender_identity: [u8; 32],
+    pub recipient_identity: [u8; 32],
+    pub msg_type: u8,
+    pub epoch: u32,
+    pub key_committing: bool,
+    pub accept_legacy_v1: bool,
+}
+
+impl MessageBinding {
+    pub fn new(sender_identity: [u8; 32], recipient_identity: [u8; 32], msg_type: u8) -> Self {
+        Self {
+            sender_identity,
+            recipient_identity,
+            msg_type,
+            epoch: 0,
+            key_committing: false,
+            accept_legacy_v1: legacy_v1_accepted(),
+        }
+    }
+
+    /// Bind to a session epoch other than 0 (after a SESSION_RESET)
+    pub fn with_epoch(mut self, epoch: u32) -> Self {
+        self.epoch = epoch;
+        self
+    }
+
+    /// Send v3 (key-committing) instead of v2; only once the peer negotiated
+    /// CAPABILITY_KEY_COMMITTING. Receiving accepts v2 and v3 either way.
+    pub fn with_key_committing(mut self, key_committing: bool) -> Self {
+        self.key_committing = key_committing;
+        self
+    }
+
+    /// Version new linear chain messages are sent with under this binding
+    pub fn send_version(&self) -> u8 {
+        if self.key_committing {
+            WIRE_VERSION_V3
+        } else {
+            WIRE_VERSION_V2
+        }
+    }
+
+    /// Override whether v1 messages are accepted under this binding
+    pub fn with_legacy_v1(mut self, accepted: bool) -> Self {
+        self.accept_legacy_v1 = accepted;
+        self
+    }
+
+    /// Wire header: [version:1][sequence:8], plus [epoch:4] with `WIRE_FLAG_EPOCH` set for epoch > 0
+    pub fn header(&self, version: u8, sequence: u64) -> Vec<u8> {
+        let mut header = Vec::with_capacity(1 + 8 + 4);
+        if self.epoch > 0 {
+            header.push(version | WIRE_FLAG_EPOCH);
+            header.extend_from_slice(&sequence.to_be_bytes());
+            header.extend_from_slice(&self.epoch.to_be_bytes());
+        } else {
+            header.push(version);
+            header.extend_from_slice(&sequence.to_be_bytes());
+        }
+        header
+    }
+
+    /// AEAD associated data: [header][sender_identity:32][recipient_identity:32][msg_type:1]
+    pub fn associated_data(&self, version: u8, sequence: u64) -> Vec<u8> {
+        let mut aad = self.header(version, sequence);
+        aad.reserve(32 + 32 + 1);
+        aad.extend_from_slice(&self.sender_identity);
+        aad.extend_from_slice(&self.recipient_identity);
+        aad.push(self.msg_type);
+        aad
+    }
+}
+
 /// Result of encryption with atomic key evolution
 #[derive(Debug, Clone)]
 pub struct EncryptionResult {
     pub ciphertext: Vec<u8>,
-    pub evolved_chain_key: [u8; 32],
+    pub evolved_chain_key: ChainKey,
 }
 
 /// Result of decryption with atomic key evolution
 #[derive(Debug, Clone)]
 pub struct DecryptionResult {
     pub plaintext: Vec<u8>,
-    pub evolved_chain_key: [u8; 32],
+    pub evolved_chain_key: ChainKey,
+}
+
+/// Result of windowed decryption (late and early messages accepted)
+#[derive(Debug, Clone)]
+pub struct WindowedDecryptionResult {
+    pub plaintext: Vec<u8>,
+    pub evolved_chain_key: ChainKey,
+    /// Sequence expected next; unchanged when a late message was decrypted from the skipped-key store
+    pub next_expected_sequence: u64,
 }
 
 #[derive(Error, Debug)]
@@ -44,6 +167,16 @@ pub enum EncryptionError {
     SequenceTooFar { received: u64, expected: u64, max: u64 },
     #[error("Out of order message: received {received}, expected {expected}")]
     OutOfOrder { received: u64, expected: u64 },
+    #[error("Ratchet session cannot send yet")]
+    SessionNotReady,
+    #[error("Invalid ratchet session state")]
+    InvalidSessionState,
+    #[error("Unsupported wire version: {0:#04x}")]
+    UnsupportedVersion(u8),
+    #[error("Message is for session epoch {received}, chain keys are for epoch {expected}")]
+    EpochMismatch { received: u32, expected: u32 },
+    #[error("Ratchet journal error: {0}")]
+    Journal(#[from] RatchetJournalError),
 }
 
 pub type Result<T> = std::result::Result<T, EncryptionError>;
@@ -126,25 +259,412 @@ pub fn generate_key() -> [u8; 32] {
     key
 }
 
-/// Derive root key from X25519 shared secret using HKDF-SHA256
+/// Encrypt a message after padding it to its size class
 ///
 /// # Arguments
-/// * `shared_secret` - 32-byte X25519 shared secret OR 64-byte hybrid X25519+Kyber combined secret
-/// * `info` - Context information (e.g., "SecureLegion-RootKey-v1")
+/// * `plaintext` - The message to encrypt
+/// * `key` - 32-byte encryption key
+/// * `padding` - Padding scheme (see `PaddingScheme::for_tier`)
 ///
 /// # Returns
-/// 32-byte root key for initializing key chains
-pub fn derive_root_key(shared_secret: &[u8], info: &[u8]) -> Result<[u8; 32]> {
-    if shared_secret.len() != 32 && shared_secret.len() != 64 {
+/// Encrypted message with prepended nonce (24 bytes + ciphertext)
+pub fn encrypt_message_padded(plaintext: &[u8], key: &[u8], padding: PaddingScheme) -> Result<Vec<u8>> {
+    let mut padded = pad(plaintext, padding);
+    let result = encrypt_message(&padded, key);
+    padded.zeroize();
+    result
+}
+
+/// Decrypt a message produced by `encrypt_message_padded` and strip its padding
+pub fn decrypt_message_padded(encrypted_data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+    let mut padded = decrypt_message(encrypted_data, key)?;
+    let result = unpad(&padded).map_err(|_| EncryptionError::DecryptionFailed);
+    padded.zeroize();
+    result
+}
+
+// ==================== KEY-COMMITTING AEAD ====================
+
+/// Encrypt a message with a key-committing XChaCha20-Poly1305 construction
+///
+/// Poly1305 alone does not commit to the key: a crafted ciphertext can decrypt under
+/// two different keys. Here the key and nonce are expanded with HKDF into a commitment
+/// tag and the AEAD subkey; the tag travels with the ciphertext and is checked before
+/// decryption, so a ciphertext opens under one key only. Use for attachments and any
+/// payload encrypted under a key the peer may choose.
+///
+/// # Arguments
+/// * `plaintext` - The message to encrypt
+/// * `key` - 32-byte encryption key
+///
+/// # Returns
+/// Wire format: [version: 1][nonce: 24][commitment: 32][ciphertext][tag: 16]
+pub fn encrypt_message_committing(plaintext: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+    let mut nonce = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce);
+    seal_committing_message(plaintext, key, &nonce)
+}
+
+/// Decrypt a message produced by `encrypt_message_committing`
+///
+/// # Arguments
+/// * `encrypted_data` - [version][nonce][commitment][ciphertext]
+/// * `key` - 32-byte encryption key
+///
+/// # Returns
+/// Decrypted plaintext; fails if the commitment does not match `key`
+pub fn decrypt_message_committing(encrypted_data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+    if encrypted_data.len() < 1 + 24 + KEY_COMMITMENT_BYTES + 16 {
+        return Err(EncryptionError::DecryptionFailed);
+    }
+    if encrypted_data[0] != WIRE_VERSION_V3 {
+        return Err(EncryptionError::UnsupportedVersion(encrypted_data[0]));
+    }
+
+    let nonce: [u8; 24] = encrypted_data[1..25].try_into()
+        .map_err(|_| EncryptionError::InvalidNonceLength)?;
+    open_committing(key, &nonce, &encrypted_data[25..], &encrypted_data[..1])
+}
+
+/// Standalone committing format with a caller-chosen nonce
+fn seal_committing_message(plaintext: &[u8], key: &[u8], nonce: &[u8; 24]) -> Result<Vec<u8>> {
+    let sealed = seal_committing(key, nonce, plaintext, &[WIRE_VERSION_V3])?;
+
+    let mut result = Vec::with_capacity(1 + 24 + sealed.len());
+    result.push(WIRE_VERSION_V3);
+    result.extend_from_slice(nonce);
+    result.extend_from_slice(&sealed);
+    Ok(result)
+}
+
+/// Derive the key-commitment tag and the AEAD subkey for one key and nonce
+///
+/// HKDF-SHA256(salt = nonce, ikm = key) expands to 64 bytes: the commitment, then the
+/// subkey. Two keys with the same commitment would be an HKDF-SHA256 collision.
+fn derive_key_commitment(key: &[u8], nonce: &[u8; 24]) -> Result<([u8; KEY_COMMITMENT_BYTES], [u8; 32])> {
+    if key.len() != 32 {
         return Err(EncryptionError::InvalidKeyLength);
     }
 
-    let hkdf = Hkdf::<Sha256>::new(None, shared_secret);
-    let mut root_key = [0u8; 32];
-    hkdf.expand(info, &mut root_key)
+    let hkdf = Hkdf::<Sha256>::new(Some(nonce), key);
+    let mut okm = [0u8; KEY_COMMITMENT_BYTES + 32];
+    hkdf.expand(KEY_COMMITMENT_INFO, &mut okm)
         .map_err(|_| EncryptionError::EncryptionFailed)?;
 
-    Ok(root_key)
+    let mut commitment = [0u8; KEY_COMMITMENT_BYTES];
+    let mut subkey = [0u8; 32];
+    commitment.copy_from_slice(&okm[..KEY_COMMITMENT_BYTES]);
+    subkey.copy_from_slice(&okm[KEY_COMMITMENT_BYTES..]);
+    okm.zeroize();
+    Ok((commitment, subkey))
+}
+
+/// Encrypt under the committed subkey; returns [commitment: 32][ciphertext][tag: 16]
+fn seal_committing(key: &[u8], nonce: &[u8; 24], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    let (commitment, mut subkey) = derive_key_commitment(key, nonce)?;
+    let cipher = XChaCha20Poly1305::new_from_slice(&subkey)
+        .map_err(|_| EncryptionError::InvalidKeyLength);
+    subkey.zeroize();
+
+    let ciphertext = cipher?
+        .encrypt(XNonce::from_slice(nonce), Payload { msg: plaintext, aad })
+        .map_err(|_| EncryptionError::EncryptionFailed)?;
+
+    let mut sealed = Vec::with_capacity(KEY_COMMITMENT_BYTES + ciphertext.len());
+    sealed.extend_from_slice(&commitment);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+/// Check the commitment in constant time, then decrypt under the committed subkey
+fn open_committing(key: &[u8], nonce: &[u8; 24], sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    if sealed.len() < KEY_COMMITMENT_BYTES + 16 {
+        return Err(EncryptionError::DecryptionFailed);
+    }
+
+    let (commitment, mut subkey) = derive_key_commitment(key, nonce)?;
+    if !bool::from(commitment.ct_eq(&sealed[..KEY_COMMITMENT_BYTES])) {
+        subkey.zeroize();
+        return Err(EncryptionError::DecryptionFailed);
+    }
+
+    let cipher = XChaCha20Poly1305::new_from_slice(&subkey)
+        .map_err(|_| EncryptionError::InvalidKeyLength);
+    subkey.zeroize();
+
+    cipher?
+        .decrypt(XNonce::from_slice(nonce), Payload { msg: &sealed[KEY_COMMITMENT_BYTES..], aad })
+        .map_err(|_| EncryptionError::DecryptionFailed)
+}
+
+/// Root key both parties derive from the key agreement, wiped on drop
+///
+/// Only chain key derivation is exposed; `Debug` never prints the key.
+/// Serializes as its raw 32 bytes, so persisted session layouts are unchanged.
+#[derive(Clone, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub struct RootKey([u8; 32]);
+
+/// Symmetric chain key for one direction, wiped on drop
+///
+/// A chain key only ever moves forward: it yields the current message key and then
+/// advances, wiping its previous value.
+#[derive(Clone, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub struct ChainKey([u8; 32]);
+
+/// Single-use message key derived from a chain key, wiped on drop
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub struct MessageKey([u8; 32]);
+
+impl RootKey {
+    /// Derive root key from a key agreement using HKDF-SHA256
+    ///
+    /// # Arguments
+    /// * `shared_secret` - 32-byte X25519 shared secret OR 64-byte hybrid X25519+Kyber combined secret
+    /// * `info` - Context information (e.g., "SecureLegion-RootKey-v1")
+    pub fn derive(shared_secret: &[u8], info: &[u8]) -> Result<Self> {
+        if shared_secret.len() != 32 && shared_secret.len() != 64 {
+            return Err(EncryptionError::InvalidKeyLength);
+        }
+
+        let hkdf = Hkdf::<Sha256>::new(None, shared_secret);
+        let mut root_key = [0u8; 32];
+        hkdf.expand(info, &mut root_key)
+            .map_err(|_| EncryptionError::EncryptionFailed)?;
+
+        Ok(Self(root_key))
+    }
+
+    /// Load a stored root key
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let key: [u8; 32] = bytes.try_into().map_err(|_| EncryptionError::InvalidKeyLength)?;
+        Ok(Self(key))
+    }
+
+    /// Chain key for the outgoing direction, HMAC(root_key, 0x03)
+    pub fn outgoing_chain_key(&self) -> ChainKey {
+        ChainKey(chain_hmac(&self.0, 0x03))
+    }
+
+    /// Chain key for the incoming direction, HMAC(root_key, 0x04)
+    pub fn incoming_chain_key(&self) -> ChainKey {
+        ChainKey(chain_hmac(&self.0, 0x04))
+    }
+
+    /// Receive chain key at a specific sender sequence (out-of-order decryption)
+    ///
+    /// Takes the sender's direction key (the direction is fixed by lexicographic comparison
+    /// of .onion addresses) and advances it `sender_sequence` times. Cost is
+    /// O(sender_sequence) HMACs per call; for live traffic prefer
+    /// `decrypt_message_with_skipped_keys`, which parks gap keys and decrypts late messages in O(1).
+    ///
+    /// # Arguments
+    /// * `sender_sequence` - The sequence number the sender used to encrypt
+    /// * `our_onion` - Our .onion address (for direction mapping)
+    /// * `their_onion` - Their .onion address (for direction mapping)
+    pub fn receive_chain_key_at(&self, sender_sequence: u64, our_onion: &str, their_onion: &str) -> Result<ChainKey> {
+        // Max sequence gap protection (prevent DoS)
+        const MAX_SEQUENCE_GAP: u64 = 10_000;
+        if sender_sequence > MAX_SEQUENCE_GAP {
+            return Err(EncryptionError::SequenceTooFar {
+                received: sender_sequence,
+                expected: 0,
+                max: MAX_SEQUENCE_GAP,
+            });
+        }
+
+        // Sender used their SEND chain, which is our RECEIVE chain
+        let mut chain_key = if our_onion < their_onion {
+            self.outgoing_chain_key()
+        } else {
+            self.incoming_chain_key()
+        };
+        for _ in 0..sender_sequence {
+            chain_key.advance();
+        }
+        Ok(chain_key)
+    }
+
+    /// Raw key bytes, for persisting session state only
+    pub fn expose_secret(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl ChainKey {
+    /// Load a stored chain key
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let key: [u8; 32] = bytes.try_into().map_err(|_| EncryptionError::InvalidKeyLength)?;
+        Ok(Self(key))
+    }
+
+    /// Message key for the current position, HMAC(chain_key, 0x02)
+    pub fn message_key(&self) -> MessageKey {
+        MessageKey(chain_hmac(&self.0, 0x02))
+    }
+
+    /// Move to the next chain key, HMAC(chain_key, 0x01), wiping the current one
+    pub fn advance(&mut self) {
+        let next = chain_hmac(&self.0, 0x01);
+        self.0.zeroize();
+        self.0 = next;
+    }
+
+    /// Encrypt the next message as v1 and advance the chain
+    ///
+    /// # Arguments
+    /// * `plaintext` - Message to encrypt
+    /// * `sequence` - Sequence number of this chain position
+    ///
+    /// # Returns
+    /// Wire format: [version: 1][sequence: 8][nonce: 24][ciphertext][tag: 16]
+    pub fn encrypt_next(&mut self, plaintext: &[u8], sequence: u64) -> Result<Vec<u8>> {
+        let sealed = self.message_key().encrypt(plaintext)?;
+        self.advance();
+
+        let mut encrypted_message = Vec::with_capacity(1 + 8 + sealed.len());
+        encrypted_message.push(WIRE_VERSION_V1);
+        encrypted_message.extend_from_slice(&sequence.to_be_bytes());
+        encrypted_message.extend_from_slice(&sealed);
+        Ok(encrypted_message)
+    }
+
+    /// Decrypt the next in-order v1 message and advance the chain
+    ///
+    /// The chain only advances if decryption succeeds.
+    ///
+    /// # Arguments
+    /// * `encrypted_data` - Encrypted message with v1 header
+    /// * `expected_sequence` - Sequence number of this chain position
+    pub fn decrypt_next(&mut self, encrypted_data: &[u8], expected_sequence: u64) -> Result<Vec<u8>> {
+        // Validate minimum length: version(1) + sequence(8) + nonce(24) + tag(16)
+        if encrypted_data.len() < 1 + 8 + 24 + 16 {
+            return Err(EncryptionError::DecryptionFailed);
+        }
+
+        // Parse wire format
+        let version = encrypted_data[0];
+        if version != WIRE_VERSION_V1 {
+            return Err(EncryptionError::DecryptionFailed);
+        }
+
+        let sequence = u64::from_be_bytes(
+            encrypted_data[1..9].try_into()
+                .map_err(|_| EncryptionError::DecryptionFailed)?
+        );
+
+        // Windowed sequence acceptance (prevents replay while allowing out-of-order delivery)
+
+        // Reject replays (sequence < expected)
+        if sequence < expected_sequence {
+            return Err(EncryptionError::ReplayAttack {
+                received: sequence,
+                expected: expected_sequence,
+            });
+        }
+
+        // Reject sequences too far in future (prevents desync attacks)
+        if sequence >= expected_sequence + SEQUENCE_WINDOW_SIZE {
+            return Err(EncryptionError::SequenceTooFar {
+                received: sequence,
+                expected: expected_sequence,
+                max: expected_sequence + SEQUENCE_WINDOW_SIZE - 1,
+            });
+        }
+
+        // Accept out-of-order messages within window [expected, expected+100)
+        // Note: this function only decrypts in order; use decrypt_message_with_skipped_keys
+        // to decrypt early and late messages without buffering in Kotlin
+        if sequence != expected_sequence {
+            return Err(EncryptionError::OutOfOrder {
+                received: sequence,
+                expected: expected_sequence,
+            });
+        }
+
+        let plaintext = self.message_key().decrypt(&encrypted_data[9..])?;
+
+        // Evolve chain key forward (must match sender's evolution)
+        self.advance();
+        Ok(plaintext)
+    }
+
+    /// Raw key bytes, for persisting session state only
+    pub fn expose_secret(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl MessageKey {
+    /// Encrypt with XChaCha20-Poly1305
+    ///
+    /// # Returns
+    /// Encrypted message with prepended nonce (24 bytes + ciphertext)
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        encrypt_message(plaintext, &self.0)
+    }
+
+    /// Decrypt a message produced by `encrypt`
+    pub fn decrypt(&self, encrypted_data: &[u8]) -> Result<Vec<u8>> {
+        decrypt_message(encrypted_data, &self.0)
+    }
+
+    /// Raw key bytes, for AEAD with caller-built associated data only
+    pub fn expose_secret(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl From<[u8; 32]> for RootKey {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<[u8; 32]> for ChainKey {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl std::fmt::Debug for RootKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RootKey([REDACTED])")
+    }
+}
+
+impl std::fmt::Debug for ChainKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ChainKey([REDACTED])")
+    }
+}
+
+impl std::fmt::Debug for MessageKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MessageKey([REDACTED])")
+    }
+}
+
+/// HMAC-SHA256(key, label) used for every chain step
+fn chain_hmac(key: &[u8; 32], label: u8) -> [u8; 32] {
+    type HmacSha256 = Hmac<Sha256>;
+
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(key)
+        .expect("HMAC-SHA256 accepts 32-byte keys");
+    mac.update(&[label]);
+    mac.finalize().into_bytes().into()
+}
+
+/// Derive root key from X25519 shared secret using HKDF-SHA256
+///
+/// # Arguments
+/// * `shared_secret` - 32-byte X25519 shared secret OR 64-byte hybrid X25519+Kyber combined secret
+/// * `info` - Context information (e.g., "SecureLegion-RootKey-v1")
+///
+/// # Returns
+/// 32-byte root key for initializing key chains
+#[deprecated(note = "use RootKey::derive")]
+pub fn derive_root_key(shared_secret: &[u8], info: &[u8]) -> Result<[u8; 32]> {
+    Ok(*RootKey::derive(shared_secret, info)?.expose_secret())
 }
 
 /// Evolve chain key forward using HMAC-SHA256 (one-way function)
@@ -152,25 +672,19 @@ pub fn derive_root_key(shared_secret: &[u8], info: &[u8]) -> Result<[u8; 32]> {
 /// This provides forward secrecy - old chain keys cannot be recovered from new ones.
 ///
 /// # Arguments
-/// * `chain_key` - Current 32-byte chain key
+/// * `chain_key` - Current 32-byte chain key (zeroized)
 ///
 /// # Returns
 /// Next chain key (32 bytes)
+#[deprecated(note = "use ChainKey::advance")]
 pub fn evolve_chain_key(chain_key: &mut [u8; 32]) -> Result<[u8; 32]> {
-    type HmacSha256 = Hmac<Sha256>;
-
-    let mut mac = <HmacSha256 as Mac>::new_from_slice(chain_key)
-        .map_err(|_| EncryptionError::InvalidKeyLength)?;
-
-    // HMAC(chain_key, 0x01) for chain key evolution
-    mac.update(&[0x01]);
-    let result = mac.finalize();
-    let new_chain_key: [u8; 32] = result.into_bytes().into();
+    let mut typed = ChainKey::from(*chain_key);
+    typed.advance();
 
     // Zero out the old chain key for forward secrecy
     chain_key.zeroize();
 
-    Ok(new_chain_key)
+    Ok(*typed.expose_secret())
 }
 
 /// Derive ephemeral message key from chain key
@@ -180,76 +694,37 @@ pub fn evolve_chain_key(chain_key: &mut [u8; 32]) -> Result<[u8; 32]> {
 ///
 /// # Returns
 /// 32-byte message key for encrypting/decrypting this message
+#[deprecated(note = "use ChainKey::message_key")]
 pub fn derive_message_key(chain_key: &[u8; 32]) -> Result<[u8; 32]> {
-    type HmacSha256 = Hmac<Sha256>;
-
-    let mut mac = <HmacSha256 as Mac>::new_from_slice(chain_key)
-        .map_err(|_| EncryptionError::InvalidKeyLength)?;
-
-    // HMAC(chain_key, 0x02) for message key derivation
-    mac.update(&[0x02]);
-    let result = mac.finalize();
-    let message_key: [u8; 32] = result.into_bytes().into();
-
-    Ok(message_key)
+    Ok(*ChainKey::from(*chain_key).message_key().expose_secret())
 }
 
 /// Derive outgoing direction chain key from root key
 ///
-/// Both parties use this for ONE direction of communication.
-/// The direction is determined by lexicographic comparison of .onion addresses.
-///
 /// # Arguments
 /// * `root_key` - 32-byte root key derived from X25519 shared secret
 ///
 /// # Returns
 /// 32-byte chain key for outgoing direction
+#[deprecated(note = "use RootKey::outgoing_chain_key")]
 pub fn derive_outgoing_chain_key(root_key: &[u8; 32]) -> Result<[u8; 32]> {
-    type HmacSha256 = Hmac<Sha256>;
-
-    let mut mac = <HmacSha256 as Mac>::new_from_slice(root_key)
-        .map_err(|_| EncryptionError::InvalidKeyLength)?;
-
-    // HMAC(root_key, 0x03) for outgoing direction chain key
-    mac.update(&[0x03]);
-    let result = mac.finalize();
-    let chain_key: [u8; 32] = result.into_bytes().into();
-
-    Ok(chain_key)
+    Ok(*RootKey::from(*root_key).outgoing_chain_key().expose_secret())
 }
 
 /// Derive incoming direction chain key from root key
 ///
-/// Both parties use this for the OTHER direction of communication.
-/// The direction is determined by lexicographic comparison of .onion addresses.
-///
 /// # Arguments
 /// * `root_key` - 32-byte root key derived from X25519 shared secret
 ///
 /// # Returns
 /// 32-byte chain key for incoming direction
+#[deprecated(note = "use RootKey::incoming_chain_key")]
 pub fn derive_incoming_chain_key(root_key: &[u8; 32]) -> Result<[u8; 32]> {
-    type HmacSha256 = Hmac<Sha256>;
-
-    let mut mac = <HmacSha256 as Mac>::new_from_slice(root_key)
-        .map_err(|_| EncryptionError::InvalidKeyLength)?;
-
-    // HMAC(root_key, 0x04) for incoming direction chain key
-    mac.update(&[0x04]);
-    let result = mac.finalize();
-    let chain_key: [u8; 32] = result.into_bytes().into();
-
-    Ok(chain_key)
+    Ok(*RootKey::from(*root_key).incoming_chain_key().expose_secret())
 }
 
 /// Derive receive chain key for a specific sender sequence (out-of-order decryption)
 ///
-/// This implements two-stage derivation:
-/// 1. Derive direction key from root (sender's perspective)
-/// 2. Evolve N times to reach sender's sequence
-///
-/// This allows decrypting messages from ANY past sequence, fixing out-of-order delivery.
-///
 /// # Arguments
 /// * `root_key` - 32-byte root key (same for both parties)
 /// * `sender_sequence` - The sequence number the sender used to encrypt
@@ -258,58 +733,15 @@ pub fn derive_incoming_chain_key(root_key: &[u8; 32]) -> Result<[u8; 32]> {
 ///
 /// # Returns
 /// 32-byte chain key at sender's sequence (for decrypting their message)
-///
-/// # Example
-/// ```
-/// // They encrypted with sequence 10, we're at sequence 15
-/// // We need to derive the key they used at sequence 10
-/// let key = derive_receive_key_at_sequence(&root_key, 10, our_onion, their_onion)?;
-/// let plaintext = decrypt_message(ciphertext, &key)?;
-/// ```
+#[deprecated(note = "use RootKey::receive_chain_key_at")]
 pub fn derive_receive_key_at_sequence(
     root_key: &[u8; 32],
     sender_sequence: u64,
     our_onion: &str,
     their_onion: &str,
 ) -> Result<[u8; 32]> {
-    // Max sequence gap protection (prevent DoS)
-    const MAX_SEQUENCE_GAP: u64 = 10_000;
-    if sender_sequence > MAX_SEQUENCE_GAP {
-        return Err(EncryptionError::SequenceTooFar {
-            received: sender_sequence,
-            expected: 0,
-            max: MAX_SEQUENCE_GAP,
-        });
-    }
-
-    // STAGE 1: Derive direction key (sender's perspective)
-    // Sender used their SEND chain, which is our RECEIVE chain
-    let direction_key = if our_onion < their_onion {
-        // They used outgoing (0x03) to send to us
-        derive_outgoing_chain_key(root_key)?
-    } else {
-        // They used incoming (0x04) to send to us
-        derive_incoming_chain_key(root_key)?
-    };
-
-    // STAGE 2: Evolve to sender's sequence
-    // Start from direction key and evolve N times
-    let mut key = direction_key;
-    for _ in 0..sender_sequence {
-        // Each evolution: HMAC(key, 0x01)
-        let new_key = {
-            type HmacSha256 = Hmac<Sha256>;
-            let mut mac = <HmacSha256 as Mac>::new_from_slice(&key)
-                .map_err(|_| EncryptionError::InvalidKeyLength)?;
-            mac.update(&[0x01]);
-            let result = mac.finalize();
-            let evolved: [u8; 32] = result.into_bytes().into();
-            evolved
-        };
-        key = new_key;
-    }
-
-    Ok(key)
+    let chain_key = RootKey::from(*root_key).receive_chain_key_at(sender_sequence, our_onion, their_onion)?;
+    Ok(*chain_key.expose_secret())
 }
 
 /// Encrypt message with key evolution (for messaging)
@@ -325,43 +757,20 @@ pub fn derive_receive_key_at_sequence(
 /// # Returns
 /// EncryptionResult containing both encrypted message and evolved chain key
 /// Wire format: [version: 1][sequence: 8][nonce: 24][ciphertext][tag: 16]
+#[deprecated(note = "use ChainKey::encrypt_next")]
 pub fn encrypt_message_with_evolution(
     plaintext: &[u8],
     chain_key: &mut [u8; 32],
     sequence: u64,
 ) -> Result<EncryptionResult> {
-    // Derive message key from current chain key
-    let message_key = derive_message_key(chain_key)?;
-
-    // Evolve chain key forward (provides forward secrecy)
-    let new_chain_key = evolve_chain_key(chain_key)?;
-    *chain_key = new_chain_key;
-
-    // Encrypt with derived message key
-    let cipher = XChaCha20Poly1305::new_from_slice(&message_key)
-        .map_err(|_| EncryptionError::InvalidKeyLength)?;
-
-    // Generate random nonce
-    let mut nonce_bytes = [0u8; 24];
-    OsRng.fill_bytes(&mut nonce_bytes);
-    let nonce = XNonce::from_slice(&nonce_bytes);
-
-    // Encrypt
-    let ciphertext = cipher
-        .encrypt(nonce, plaintext)
-        .map_err(|_| EncryptionError::EncryptionFailed)?;
-
-    // Build wire format: [version][sequence][nonce][ciphertext]
-    let mut encrypted_message = Vec::with_capacity(1 + 8 + 24 + ciphertext.len());
-    encrypted_message.push(0x01); // Version 1
-    encrypted_message.extend_from_slice(&sequence.to_be_bytes());
-    encrypted_message.extend_from_slice(&nonce_bytes);
-    encrypted_message.extend_from_slice(&ciphertext);
+    let mut typed = ChainKey::from(*chain_key);
+    let ciphertext = typed.encrypt_next(plaintext, sequence)?;
+    chain_key.copy_from_slice(typed.expose_secret());
 
     // Return both encrypted message AND evolved key atomically
     Ok(EncryptionResult {
-        ciphertext: encrypted_message,
-        evolved_chain_key: new_chain_key,
+        ciphertext,
+        evolved_chain_key: typed,
     })
 }
 
@@ -377,34 +786,68 @@ pub fn encrypt_message_with_evolution(
 ///
 /// # Returns
 /// DecryptionResult containing both decrypted plaintext and evolved chain key
+#[deprecated(note = "use ChainKey::decrypt_next")]
 pub fn decrypt_message_with_evolution(
     encrypted_data: &[u8],
     chain_key: &mut [u8; 32],
     expected_sequence: u64,
 ) -> Result<DecryptionResult> {
-    // Validate minimum length: version(1) + sequence(8) + nonce(24) + tag(16)
-    if encrypted_data.len() < 1 + 8 + 24 + 16 {
-        return Err(EncryptionError::DecryptionFailed);
-    }
+    let mut typed = ChainKey::from(*chain_key);
+    let plaintext = typed.decrypt_next(encrypted_data, expected_sequence)?;
+    chain_key.copy_from_slice(typed.expose_secret());
 
-    // Parse wire format
-    let version = encrypted_data[0];
-    if version != 0x01 {
-        return Err(EncryptionError::DecryptionFailed);
-    }
-
-    let sequence = u64::from_be_bytes(
-        encrypted_data[1..9].try_into()
-            .map_err(|_| EncryptionError::DecryptionFailed)?
-    );
+    // Return both plaintext AND evolved key atomically
+    Ok(DecryptionResult {
+        plaintext,
+        evolved_chain_key: typed,
+    })
+}
 
-    // Windowed sequence acceptance (prevents replay while allowing out-of-order delivery)
+/// Decrypt message with key evolution, accepting out-of-order delivery
+///
+/// ATOMIC OPERATION: chain key and skipped-key store are only updated on success
+///
+/// A message ahead of `expected_sequence` (within SEQUENCE_WINDOW_SIZE) advances the
+/// chain to its sequence and parks the keys for the gap in `skipped_keys`. A message
+/// behind `expected_sequence` decrypts with its parked key in O(1), without touching
+/// the chain. Each parked key is used at most once, so replays are still rejected.
+///
+/// # Arguments
+/// * `encrypted_data` - Encrypted message with v1 or v2 header
+/// * `chain_key` - Current receive chain key (evolved if the message is not late)
+/// * `expected_sequence` - Next sequence the chain key corresponds to
+/// * `skipped_keys` - Per-session skipped-key store (persist alongside the chain key)
+/// * `binding` - v2 header binding; `None` accepts only v1 messages
+///
+/// # Returns
+/// WindowedDecryptionResult with plaintext, chain key and next expected sequence
+pub fn decrypt_message_with_skipped_keys(
+    encrypted_data: &[u8],
+    chain_key: &mut [u8; 32],
+    expected_sequence: u64,
+    skipped_keys: &mut SkippedKeyStore,
+    binding: Option<&MessageBinding>,
+) -> Result<WindowedDecryptionResult> {
+    let (sequence, aad, header_len) = parse_chain_header(encrypted_data, binding)?;
 
-    // Reject replays (sequence < expected)
+    // Late message: only decryptable if its key was parked (and not used yet)
     if sequence < expected_sequence {
-        return Err(EncryptionError::ReplayAttack {
-            received: sequence,
-            expected: expected_sequence,
+        let message_key = skipped_keys.peek(&LINEAR_CHAIN_ID, sequence)
+            .ok_or(EncryptionError::ReplayAttack {
+                received: sequence,
+                expected: expected_sequence,
+            })?;
+
+        let result = open_chain_payload(&message_key, encrypted_data, header_len, aad.as_deref());
+
+        // Forged message: the parked key stays, with its original age, for the real one
+        let plaintext = result.map_err(|_| EncryptionError::DecryptionFailed)?;
+        skipped_keys.remove(&LINEAR_CHAIN_ID, sequence);
+
+        return Ok(WindowedDecryptionResult {
+            plaintext,
+            evolved_chain_key: ChainKey::from(*chain_key),
+            next_expected_sequence: expected_sequence,
         });
     }
 
@@ -417,46 +860,304 @@ pub fn decrypt_message_with_evolution(
         });
     }
 
-    // Accept out-of-order messages within window [expected, expected+100)
-    // Note: Kotlin layer must buffer and process in order to maintain key chain sync
-    if sequence != expected_sequence {
-        return Err(EncryptionError::OutOfOrder {
+    // Advance a copy of the chain, collecting keys for the gap (all wiped on drop)
+    let mut next_chain_key = ChainKey::from(*chain_key);
+    let mut gap_keys = Vec::with_capacity((sequence - expected_sequence) as usize);
+    for _ in expected_sequence..sequence {
+        gap_keys.push(next_chain_key.message_key());
+        next_chain_key.advance();
+    }
+
+    let result = open_chain_payload(next_chain_key.message_key().expose_secret(), encrypted_data, header_len, aad.as_deref());
+    let plaintext = result.map_err(|_| EncryptionError::DecryptionFailed)?;
+
+    // Commit: park gap keys, then move the chain past this message
+    for (offset, key) in gap_keys.iter().enumerate() {
+        skipped_keys.insert(LINEAR_CHAIN_ID, expected_sequence + offset as u64, key.expose_secret());
+    }
+    next_chain_key.advance();
+    chain_key.copy_from_slice(next_chain_key.expose_secret());
+
+    Ok(WindowedDecryptionResult {
+        plaintext,
+        evolved_chain_key: next_chain_key,
+        next_expected_sequence: sequence + 1,
+    })
+}
+
+/// Encrypt message with key evolution using the v2 (authenticated header) format
+///
+/// ATOMIC OPERATION: Encrypts and evolves key in one indivisible operation
+///
+/// Same chain semantics as `encrypt_message_with_evolution`, but version, sequence,
+/// both identity keys and the message type are bound as AEAD associated data, so
+/// any change to the header or routing byte makes decryption fail.
+///
+/// # Arguments
+/// * `plaintext` - Message to encrypt
+/// * `chain_key` - Current chain key (will be evolved)
+/// * `sequence` - Message sequence number
+/// * `binding` - Sender/recipient identity keys and message type
+/// * `padding` - Padding scheme applied before encryption (see `PaddingScheme::for_tier`)
+///
+/// # Returns
+/// EncryptionResult containing both encrypted message and evolved chain key
+/// Wire format: [version: 1][sequence: 8][nonce: 24][ciphertext][tag: 16]
+pub fn encrypt_message_with_evolution_v2(
+    plaintext: &[u8],
+    chain_key: &mut [u8; 32],
+    sequence: u64,
+    binding: &MessageBinding,
+    padding: PaddingScheme,
+) -> Result<EncryptionResult> {
+    let mut typed = ChainKey::from(*chain_key);
+    let encrypted_message = seal_chain_message(
+        typed.message_key().expose_secret(),
+        plaintext,
+        sequence,
+        binding,
+        padding,
+        binding.send_version(),
+    )?;
+
+    // Evolve only after encryption succeeded so the chain never skips ahead on error
+    typed.advance();
+    chain_key.copy_from_slice(typed.expose_secret());
+
+    Ok(EncryptionResult {
+        ciphertext: encrypted_message,
+        evolved_chain_key: typed,
+    })
+}
+
+/// Decrypt message with key evolution, accepting v2, v3 and (during migration) v1
+///
+/// ATOMIC OPERATION: Decrypts and evolves key in one indivisible operation
+///
+/// # Arguments
+/// * `encrypted_data` - Encrypted message with header
+/// * `chain_key` - Current chain key (will be evolved)
+/// * `expected_sequence` - Expected sequence number
+/// * `binding` - Binding from the receiver's view (sender = peer, recipient = us)
+///
+/// # Returns
+/// DecryptionResult containing both decrypted plaintext and evolved chain key
+pub fn decrypt_message_with_evolution_v2(
+    encrypted_data: &[u8],
+    chain_key: &mut [u8; 32],
+    expected_sequence: u64,
+    binding: &MessageBinding,
+) -> Result<DecryptionResult> {
+    let (sequence, aad, header_len) = parse_chain_header(encrypted_data, Some(binding))?;
+
+    if sequence < expected_sequence {
+        return Err(EncryptionError::ReplayAttack {
             received: sequence,
             expected: expected_sequence,
         });
     }
 
-    let nonce_bytes = &encrypted_data[9..33];
-    let ciphertext = &encrypted_data[33..];
-
-    // Derive message key from current chain key
-    let message_key = derive_message_key(chain_key)?;
+    if sequence >= expected_sequence + SEQUENCE_WINDOW_SIZE {
+        return Err(EncryptionError::SequenceTooFar {
+            received: sequence,
+            expected: expected_sequence,
+            max: expected_sequence + SEQUENCE_WINDOW_SIZE - 1,
+        });
+    }
 
-    // Decrypt
-    let cipher = XChaCha20Poly1305::new_from_slice(&message_key)
-        .map_err(|_| EncryptionError::InvalidKeyLength)?;
+    if sequence != expected_sequence {
+        return Err(EncryptionError::OutOfOrder {
+            received: sequence,
+            expected: expected_sequence,
+        });
+    }
 
-    let nonce = XNonce::from_slice(nonce_bytes);
-    let plaintext = cipher
-        .decrypt(nonce, ciphertext)
-        .map_err(|_| EncryptionError::DecryptionFailed)?;
+    let mut typed = ChainKey::from(*chain_key);
+    let plaintext = open_chain_payload(typed.message_key().expose_secret(), encrypted_data, header_len, aad.as_deref())?;
 
-    // Evolve chain key forward (must match sender's evolution)
-    let new_chain_key = evolve_chain_key(chain_key)?;
-    *chain_key = new_chain_key;
+    typed.advance();
+    chain_key.copy_from_slice(typed.expose_secret());
 
-    // Return both plaintext AND evolved key atomically
     Ok(DecryptionResult {
         plaintext,
-        evolved_chain_key: new_chain_key,
+        evolved_chain_key: typed,
     })
 }
 
+/// Enable or disable acceptance of v1 messages by the v2 decrypt paths
+///
+/// Leave enabled until every contact has upgraded; disabling it closes the
+/// migration window and makes unauthenticated headers a hard error. Applies to
+/// bindings built afterwards; see `MessageBinding::with_legacy_v1`.
+pub fn set_legacy_v1_accepted(accepted: bool) {
+    ACCEPT_LEGACY_V1.store(accepted, Ordering::SeqCst);
+    log::info!("✓ Legacy v1 message acceptance: {}", accepted);
+}
+
+/// Whether v1 messages are currently accepted by the v2 decrypt paths
+pub fn legacy_v1_accepted() -> bool {
+    ACCEPT_LEGACY_V1.load(Ordering::SeqCst)
+}
+
+/// Session epoch a linear chain message was sent under (0 without `WIRE_FLAG_EPOCH`)
+///
+/// Lets the receiver pick the chain keys of that epoch before decrypting; the epoch is
+/// authenticated as part of the header when the message is opened.
+pub fn chain_message_epoch(encrypted_data: &[u8]) -> Result<u32> {
+    match encrypted_data.first() {
+        Some(version) if version & WIRE_FLAG_EPOCH != 0 => encrypted_data
+            .get(9..13)
+            .and_then(|epoch| epoch.try_into().ok())
+            .map(u32::from_be_bytes)
+            .ok_or(EncryptionError::DecryptionFailed),
+        Some(_) => Ok(0),
+        None => Err(EncryptionError::DecryptionFailed),
+    }
+}
+
+/// Parse a linear chain header and build the AEAD associated data for its version
+///
+/// Returns (sequence, associated data, header length). v1 has no associated data and
+/// is only accepted without a binding, or with one whose `accept_legacy_v1` is set.
+/// The header's epoch must match the binding's.
+fn parse_chain_header(
+    encrypted_data: &[u8],
+    binding: Option<&MessageBinding>,
+) -> Result<(u64, Option<Vec<u8>>, usize)> {
+    let flagged = encrypted_data.first().is_some_and(|version| version & WIRE_FLAG_EPOCH != 0);
+    let header_len = if flagged { 1 + 8 + 4 } else { 1 + 8 };
+
+    // Validate minimum length: header + nonce(24) + tag(16)
+    if encrypted_data.len() < header_len + 24 + 16 {
+        return Err(EncryptionError::DecryptionFailed);
+    }
+
+    let version = encrypted_data[0] & !WIRE_FLAG_EPOCH;
+    let sequence = u64::from_be_bytes(
+        encrypted_data[1..9].try_into()
+            .map_err(|_| EncryptionError::DecryptionFailed)?
+    );
+    let epoch = chain_message_epoch(encrypted_data)?;
+
+    match (version, binding) {
+        (WIRE_VERSION_V1, None) if !flagged => Ok((sequence, None, header_len)),
+        (WIRE_VERSION_V1, Some(binding)) if !flagged && binding.accept_legacy_v1 && binding.epoch == 0 => {
+            log::warn!("⚠️  Accepting legacy v1 message (unauthenticated header)");
+            Ok((sequence, None, header_len))
+        }
+        (WIRE_VERSION_V2 | WIRE_VERSION_V3, Some(binding)) => {
+            if epoch != binding.epoch || (flagged && epoch == 0) {
+                return Err(EncryptionError::EpochMismatch { received: epoch, expected: binding.epoch });
+            }
+            Ok((sequence, Some(binding.associated_data(version, sequence)), header_len))
+        }
+        _ => Err(EncryptionError::UnsupportedVersion(encrypted_data[0])),
+    }
+}
+
+/// Pad and encrypt into the v2 or v3 wire format: [header][nonce][commitment (v3)][ciphertext]
+fn seal_chain_message(
+    message_key: &[u8; 32],
+    plaintext: &[u8],
+    sequence: u64,
+    binding: &MessageBinding,
+    padding: PaddingScheme,
+    version: u8,
+) -> Result<Vec<u8>> {
+    let mut nonce_bytes = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce_bytes);
+
+    let header = binding.header(version, sequence);
+    let aad = binding.associated_data(version, sequence);
+    let mut padded = pad(plaintext, padding);
+    let ciphertext = match version {
+        WIRE_VERSION_V2 => XChaCha20Poly1305::new_from_slice(message_key)
+            .map_err(|_| EncryptionError::InvalidKeyLength)
+            .and_then(|cipher| {
+                cipher
+                    .encrypt(XNonce::from_slice(&nonce_bytes), Payload { msg: &padded, aad: &aad })
+                    .map_err(|_| EncryptionError::EncryptionFailed)
+            }),
+        WIRE_VERSION_V3 => seal_committing(message_key, &nonce_bytes, &padded, &aad),
+        _ => Err(EncryptionError::UnsupportedVersion(version)),
+    };
+    padded.zeroize();
+    let ciphertext = ciphertext?;
+
+    let mut encrypted_message = Vec::with_capacity(header.len() + 24 + ciphertext.len());
+    encrypted_message.extend_from_slice(&header);
+    encrypted_message.extend_from_slice(&nonce_bytes);
+    encrypted_message.extend_from_slice(&ciphertext);
+
+    Ok(encrypted_message)
+}
+
+/// Decrypt a linear chain message with an already-derived message key
+///
+/// For skipped messages whose keys the caller parked itself. The header is checked
+/// against `binding` exactly as in `decrypt_message_with_evolution_v2` and v2/v3
+/// padding is stripped; no chain state is touched.
+///
+/// # Arguments
+/// * `encrypted_data` - Encrypted message with header
+/// * `message_key` - Message key for the message's sequence
+/// * `binding` - Binding from the receiver's view (sender = peer, recipient = us)
+pub fn decrypt_with_message_key_v2(
+    encrypted_data: &[u8],
+    message_key: &[u8; 32],
+    binding: &MessageBinding,
+) -> Result<Vec<u8>> {
+    let (_, aad, header_len) = parse_chain_header(encrypted_data, Some(binding))?;
+    open_chain_payload(message_key, encrypted_data, header_len, aad.as_deref())
+}
+
+/// Decrypt the payload of a parsed linear chain message
+///
+/// v2/v3 payloads (those with associated data) are padded; the padding is stripped here.
+/// v3 payloads carry a key commitment that is checked before decryption.
+fn open_chain_payload(
+    message_key: &[u8; 32],
+    encrypted_data: &[u8],
+    header_len: usize,
+    aad: Option<&[u8]>,
+) -> Result<Vec<u8>> {
+    let nonce_end = header_len + 24;
+    let plaintext = if encrypted_data[0] & !WIRE_FLAG_EPOCH == WIRE_VERSION_V3 {
+        let nonce: [u8; 24] = encrypted_data[header_len..nonce_end].try_into()
+            .map_err(|_| EncryptionError::InvalidNonceLength)?;
+        open_committing(message_key, &nonce, &encrypted_data[nonce_end..], aad.unwrap_or(&[]))?
+    } else {
+        let cipher = XChaCha20Poly1305::new_from_slice(message_key)
+            .map_err(|_| EncryptionError::InvalidKeyLength)?;
+
+        let nonce = XNonce::from_slice(&encrypted_data[header_len..nonce_end]);
+        let payload = Payload {
+            msg: &encrypted_data[nonce_end..],
+            aad: aad.unwrap_or(&[]),
+        };
+
+        cipher
+            .decrypt(nonce, payload)
+            .map_err(|_| EncryptionError::DecryptionFailed)?
+    };
+
+    match aad {
+        Some(_) => {
+            let mut padded = plaintext;
+            let result = unpad(&padded).map_err(|_| EncryptionError::DecryptionFailed);
+            padded.zeroize();
+            result
+        }
+        None => Ok(plaintext),
+    }
+}
+
 // ==================== TWO-PHASE RATCHET COMMIT (Fix #6) ====================
 
 use std::sync::Mutex;
 use once_cell::sync::Lazy;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use super::ratchet_journal::{JournaledAdvancement, RatchetJournal, RatchetJournalError};
 
 /// Result of deferred encryption (ratchet not yet committed)
 #[derive(Debug, Clone)]
@@ -485,16 +1186,16 @@ pub fn encrypt_message_deferred(
     sequence: u64,
 ) -> Result<DeferredEncryptionResult> {
     // Derive message key from current chain key
-    let message_key = derive_message_key(chain_key)?;
+    let mut typed = ChainKey::from(*chain_key);
+    let message_key = typed.message_key();
 
-    // Evolve chain key forward (provides forward secrecy)
-    // Create mutable copy for evolution
-    let mut chain_key_copy = *chain_key;
-    let next_chain_key = evolve_chain_key(&mut chain_key_copy)?;
+    // Evolve chain key forward (provides forward secrecy) on a copy
+    typed.advance();
+    let next_chain_key = *typed.expose_secret();
     let next_sequence = sequence + 1;
 
     // Encrypt with derived message key
-    let cipher = XChaCha20Poly1305::new_from_slice(&message_key)
+    let cipher = XChaCha20Poly1305::new_from_slice(message_key.expose_secret())
         .map_err(|_| EncryptionError::InvalidKeyLength)?;
 
     // Generate random nonce
@@ -522,6 +1223,47 @@ pub fn encrypt_message_deferred(
     })
 }
 
+/// Encrypt message WITHOUT committing ratchet advancement, using the v2 format
+///
+/// Phase 1 of the two-phase commit, like `encrypt_message_deferred`, with the
+/// header bound as AEAD associated data (see `MessageBinding`).
+///
+/// # Arguments
+/// * `plaintext` - Message to encrypt
+/// * `chain_key` - Current chain key (NOT mutated)
+/// * `sequence` - Current sequence number
+/// * `binding` - Sender/recipient identity keys and message type
+/// * `padding` - Padding scheme applied before encryption (see `PaddingScheme::for_tier`)
+///
+/// # Returns
+/// DeferredEncryptionResult with ciphertext and next state (uncommitted)
+pub fn encrypt_message_deferred_v2(
+    plaintext: &[u8],
+    chain_key: &[u8; 32],
+    sequence: u64,
+    binding: &MessageBinding,
+    padding: PaddingScheme,
+) -> Result<DeferredEncryptionResult> {
+    let mut typed = ChainKey::from(*chain_key);
+    let encrypted_message = seal_chain_message(
+        typed.message_key().expose_secret(),
+        plaintext,
+        sequence,
+        binding,
+        padding,
+        binding.send_version(),
+    );
+
+    typed.advance();
+    let next_chain_key = *typed.expose_secret();
+
+    Ok(DeferredEncryptionResult {
+        ciphertext: encrypted_message?,
+        next_chain_key,
+        next_sequence: sequence + 1,
+    })
+}
+
 /// Pending ratchet advancement waiting for PING_ACK
 #[derive(Clone)]
 struct PendingRatchetAdvancement {
@@ -530,14 +1272,75 @@ struct PendingRatchetAdvancement {
     next_chain_key: [u8; 32],      // Next chain key (uncommitted)
     next_sequence: u64,            // Next sequence number (uncommitted)
     created_at: std::time::SystemTime,
+    acknowledged: bool,            // Handed to the app after PING_ACK, awaiting ack_ratchet_persisted
 }
 
-/// Global storage for pending ratchet advancements
-static PENDING_RATCHETS: Lazy<Mutex<HashMap<String, PendingRatchetAdvancement>>> =
+/// Global storage for pending ratchet advancements: contact_id -> queue in sequence order
+static PENDING_RATCHETS: Lazy<Mutex<HashMap<String, VecDeque<PendingRatchetAdvancement>>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
+/// Write-ahead journal backing PENDING_RATCHETS (None until init_ratchet_journal)
+static RATCHET_JOURNAL: Lazy<Mutex<Option<RatchetJournal>>> = Lazy::new(|| Mutex::new(None));
+
+impl PendingRatchetAdvancement {
+    fn from_journal(advancement: &JournaledAdvancement) -> Self {
+        Self {
+            contact_id: advancement.contact_id.clone(),
+            message_id: advancement.message_id.clone(),
+            next_chain_key: advancement.next_chain_key,
+            next_sequence: advancement.next_sequence,
+            created_at: std::time::UNIX_EPOCH + std::time::Duration::from_secs(advancement.created_at.max(0) as u64),
+            acknowledged: false,
+        }
+    }
+
+    fn to_journal(&self) -> JournaledAdvancement {
+        JournaledAdvancement {
+            contact_id: self.contact_id.clone(),
+            message_id: self.message_id.clone(),
+            next_chain_key: self.next_chain_key,
+            next_sequence: self.next_sequence,
+            created_at: self.created_at
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0),
+        }
+    }
+}
+
+/// Open the ratchet journal and restore the advancements that were pending at the last shutdown/crash
+///
+/// # Arguments
+/// * `path` - Journal file
+/// * `key` - 32-byte journal encryption key
+///
+/// # Returns
+/// Advancements recovered from the journal (still uncommitted)
+pub fn init_ratchet_journal(path: &std::path::Path, key: &[u8; 32]) -> Result<Vec<JournaledAdvancement>> {
+    let journal = RatchetJournal::open(path, key)?;
+    let recovered: Vec<JournaledAdvancement> = journal.pending().cloned().collect();
+
+    let mut pending = PENDING_RATCHETS.lock()
+        .map_err(|_| EncryptionError::EncryptionFailed)?;
+    pending.clear();
+    for advancement in &recovered {
+        pending.entry(advancement.contact_id.clone())
+            .or_default()
+            .push_back(PendingRatchetAdvancement::from_journal(advancement));
+    }
+    *RATCHET_JOURNAL.lock().map_err(|_| EncryptionError::EncryptionFailed)? = Some(journal);
+
+    log::info!("Recovered {} pending ratchet advancements from journal", recovered.len());
+    Ok(recovered)
+}
+
 /// Store a pending ratchet advancement (waiting for PING_ACK)
 ///
+/// Advancements queue up per contact, so several messages can be in flight before
+/// the first PING_ACK. Each must continue the previous one's sequence.
+/// With a journal open, the advancement is durably logged before this returns,
+/// so the caller must not send the ciphertext if this fails.
+///
 /// # Arguments
 /// * `contact_id` - Unique contact identifier (pubkey or onion address)
 /// * `message_id` - Message ID (for tracking which message this ratchet belongs to)
@@ -551,6 +1354,17 @@ pub fn store_pending_ratchet_advancement(
 ) -> Result<()> {
     let mut pending = PENDING_RATCHETS.lock()
         .map_err(|_| EncryptionError::EncryptionFailed)?;
+    let queue = pending.entry(contact_id.to_string()).or_default();
+
+    if let Some(last) = queue.back() {
+        if next_sequence <= last.next_sequence {
+            return Err(EncryptionError::OutOfOrder { received: next_sequence, expected: last.next_sequence + 1 });
+        }
+    }
+    if queue.iter().any(|a| a.message_id == message_id) {
+        log::warn!("⚠️  Message {} already has a pending ratchet advancement", message_id);
+        return Err(EncryptionError::InvalidSessionState);
+    }
 
     let advancement = PendingRatchetAdvancement {
         contact_id: contact_id.to_string(),
@@ -558,17 +1372,96 @@ pub fn store_pending_ratchet_advancement(
         next_chain_key,
         next_sequence,
         created_at: std::time::SystemTime::now(),
+        acknowledged: false,
     };
 
-    pending.insert(contact_id.to_string(), advancement);
+    if let Some(journal) = RATCHET_JOURNAL.lock().map_err(|_| EncryptionError::EncryptionFailed)?.as_mut() {
+        journal.record_pending(advancement.to_journal())?;
+    }
+    queue.push_back(advancement);
 
-    log::info!("✓ Stored pending ratchet advancement for contact {}, message {}",
-        contact_id, message_id);
+    log::info!("✓ Stored pending ratchet advancement for contact {}, message {} ({} in flight)",
+        contact_id, message_id, queue.len());
 
     Ok(())
 }
 
-/// Commit ratchet advancement after PING_ACK received (Phase 2)
+/// Commit ratchet advancements through `message_id` after its PING_ACK (Phase 2)
+///
+/// Everything queued before the acknowledged message is committed with it, in order;
+/// later messages stay pending. The advancements are only marked acknowledged here:
+/// they stay in the journal until the app has persisted the returned key and called
+/// `ack_ratchet_persisted`, so a crash in between replays them instead of losing the key.
+///
+/// # Arguments
+/// * `contact_id` - Contact identifier
+/// * `message_id` - Acknowledged message
+///
+/// # Returns
+/// (next_chain_key, next_sequence) of the acknowledged message to persist,
+/// or None if it is not pending
+pub fn commit_ratchet_advancement_through(
+    contact_id: &str,
+    message_id: &str,
+) -> Result<Option<([u8; 32], u64)>> {
+    let mut pending = PENDING_RATCHETS.lock()
+        .map_err(|_| EncryptionError::EncryptionFailed)?;
+
+    let Some(queue) = pending.get_mut(contact_id) else {
+        log::warn!("⚠️  No pending ratchet advancement found for contact {}", contact_id);
+        return Ok(None);
+    };
+    let Some(position) = queue.iter().position(|a| a.message_id == message_id && !a.acknowledged) else {
+        log::warn!("⚠️  No pending ratchet advancement for contact {}, message {}", contact_id, message_id);
+        return Ok(None);
+    };
+
+    for advancement in queue.iter_mut().take(position + 1) {
+        advancement.acknowledged = true;
+    }
+    let committed = &queue[position];
+
+    log::info!("✓ Committed ratchet advancements for contact {} through message {} ({} committed)",
+        contact_id, message_id, position + 1);
+    Ok(Some((committed.next_chain_key, committed.next_sequence)))
+}
+
+/// Finalize advancements through `message_id` once the app has persisted the committed key
+///
+/// Appends the journal Commit record and drops the advancements from memory.
+/// Also resolves advancements recovered by `init_ratchet_journal` whose key the
+/// app had already persisted before the crash.
+///
+/// # Arguments
+/// * `contact_id` - Contact identifier
+/// * `message_id` - Message whose committed key was persisted
+///
+/// # Returns
+/// Number of advancements finalized (0 if the message is not pending)
+pub fn ack_ratchet_persisted(contact_id: &str, message_id: &str) -> Result<usize> {
+    let mut pending = PENDING_RATCHETS.lock()
+        .map_err(|_| EncryptionError::EncryptionFailed)?;
+
+    let Some(queue) = pending.get_mut(contact_id) else { return Ok(0) };
+    let Some(position) = queue.iter().position(|a| a.message_id == message_id) else { return Ok(0) };
+
+    if let Some(journal) = RATCHET_JOURNAL.lock().map_err(|_| EncryptionError::EncryptionFailed)?.as_mut() {
+        journal.commit(contact_id, message_id)?;
+    }
+
+    for mut advancement in queue.drain(..=position) {
+        advancement.next_chain_key.zeroize();
+    }
+    if queue.is_empty() {
+        pending.remove(contact_id);
+    }
+
+    log::info!("✓ Ratchet advancements for contact {} through message {} persisted ({} finalized)",
+        contact_id, message_id, position + 1);
+    Ok(position + 1)
+}
+
+/// Commit every pending advancement for a contact (Phase 2, without a message ID)
 ///
 /// This is called by Kotlin after PING_ACK arrives.
 /// Returns the next chain key and sequence to persist in database.
@@ -577,65 +1470,137 @@ pub fn store_pending_ratchet_advancement(
 /// * `contact_id` - Contact identifier (must match store_pending_ratchet_advancement)
 ///
 /// # Returns
-/// (next_chain_key, next_sequence) to persist, or None if no pending advancement
+/// (next_chain_key, next_sequence) of the newest advancement, or None if none is pending
 pub fn commit_ratchet_advancement(
     contact_id: &str,
 ) -> Result<Option<([u8; 32], u64)>> {
+    let newest = PENDING_RATCHETS.lock()
+        .map_err(|_| EncryptionError::EncryptionFailed)?
+        .get(contact_id)
+        .and_then(|queue| queue.back())
+        .map(|advancement| advancement.message_id.clone());
+
+    match newest {
+        Some(message_id) => commit_ratchet_advancement_through(contact_id, &message_id),
+        None => {
+            log::warn!("⚠️  No pending ratchet advancement found for contact {}", contact_id);
+            Ok(None)
+        }
+    }
+}
+
+/// Roll back `message_id` and every advancement queued after it (if its send permanently fails)
+///
+/// Later messages were encrypted with chain keys derived from this one, so they
+/// cannot be committed without it. Acknowledged advancements are never rolled back;
+/// the peer already has them.
+///
+/// # Arguments
+/// * `contact_id` - Contact identifier
+/// * `message_id` - Failed message
+///
+/// # Returns
+/// Number of advancements discarded
+pub fn rollback_ratchet_advancement_from(contact_id: &str, message_id: &str) -> Result<usize> {
     let mut pending = PENDING_RATCHETS.lock()
         .map_err(|_| EncryptionError::EncryptionFailed)?;
 
-    if let Some(advancement) = pending.remove(contact_id) {
-        log::info!("✓ Committed ratchet advancement for contact {}, message {}",
-            contact_id, advancement.message_id);
+    let Some(queue) = pending.get_mut(contact_id) else { return Ok(0) };
+    let Some(position) = queue.iter().position(|a| a.message_id == message_id && !a.acknowledged) else { return Ok(0) };
 
-        Ok(Some((advancement.next_chain_key, advancement.next_sequence)))
-    } else {
-        log::warn!("⚠️  No pending ratchet advancement found for contact {}", contact_id);
-        Ok(None)
+    if let Some(journal) = RATCHET_JOURNAL.lock().map_err(|_| EncryptionError::EncryptionFailed)?.as_mut() {
+        journal.rollback(contact_id, message_id)?;
     }
+
+    let discarded = queue.split_off(position).len();
+    if queue.is_empty() {
+        pending.remove(contact_id);
+    }
+
+    log::info!("✓ Rolled back {} ratchet advancements for contact {} from message {}",
+        discarded, contact_id, message_id);
+
+    Ok(discarded)
 }
 
-/// Rollback/discard pending ratchet advancement (if send permanently fails)
+/// Rollback/discard all pending ratchet advancements for a contact
 ///
 /// Call this if message is deleted before receiving PING_ACK.
 ///
 /// # Arguments
 /// * `contact_id` - Contact identifier
 pub fn rollback_ratchet_advancement(contact_id: &str) -> Result<()> {
-    let mut pending = PENDING_RATCHETS.lock()
-        .map_err(|_| EncryptionError::EncryptionFailed)?;
-
-    if let Some(advancement) = pending.remove(contact_id) {
-        log::info!("✓ Rolled back ratchet advancement for contact {}, message {}",
-            contact_id, advancement.message_id);
+    let oldest = PENDING_RATCHETS.lock()
+        .map_err(|_| EncryptionError::EncryptionFailed)?
+        .get(contact_id)
+        .and_then(|queue| queue.iter().find(|advancement| !advancement.acknowledged))
+        .map(|advancement| advancement.message_id.clone());
+
+    if let Some(message_id) = oldest {
+        rollback_ratchet_advancement_from(contact_id, &message_id)?;
     }
 
     Ok(())
 }
 
 /// Clean up expired pending ratchet advancements (older than 5 minutes)
+///
+/// An expired advancement is rolled back together with everything queued after it.
+/// Acknowledged advancements are waiting on the app's database write and do not expire.
 pub fn cleanup_expired_pending_ratchets() -> Result<()> {
-    let mut pending = PENDING_RATCHETS.lock()
-        .map_err(|_| EncryptionError::EncryptionFailed)?;
-
     let now = std::time::SystemTime::now();
     const MAX_AGE: std::time::Duration = std::time::Duration::from_secs(300); // 5 minutes
 
-    pending.retain(|contact_id, advancement| {
-        if let Ok(age) = now.duration_since(advancement.created_at) {
-            if age > MAX_AGE {
-                log::warn!("⚠️  Expired pending ratchet for contact {} (age: {:?})",
-                    contact_id, age);
-                return false;
+    let expired: Vec<(String, String)> = PENDING_RATCHETS.lock()
+        .map_err(|_| EncryptionError::EncryptionFailed)?
+        .iter()
+        .filter_map(|(contact_id, queue)| {
+            queue.iter()
+                .find(|advancement| {
+                    !advancement.acknowledged
+                        && now.duration_since(advancement.created_at).is_ok_and(|age| age > MAX_AGE)
+                })
+                .map(|advancement| (contact_id.clone(), advancement.message_id.clone()))
+        })
+        .collect();
+
+    for (contact_id, message_id) in expired {
+        log::warn!("⚠️  Expired pending ratchet for contact {} (message {})", contact_id, message_id);
+        rollback_ratchet_advancement_from(&contact_id, &message_id)?;
+    }
+
+    Ok(())
+}
+
+/// Destroy all pending ratchet state and the journal (duress wipe)
+///
+/// Pending chain keys are zeroized and the journal is overwritten and deleted.
+/// Committed ratchet state lives in the app database, which the caller deletes.
+pub fn wipe_ratchet_state() -> Result<()> {
+    {
+        let mut pending = PENDING_RATCHETS.lock()
+            .map_err(|_| EncryptionError::EncryptionFailed)?;
+        for queue in pending.values_mut() {
+            for advancement in queue.iter_mut() {
+                advancement.next_chain_key.zeroize();
             }
         }
-        true
-    });
+        pending.clear();
+    }
 
+    let journal = RATCHET_JOURNAL.lock()
+        .map_err(|_| EncryptionError::EncryptionFailed)?
+        .take();
+    if let Some(journal) = journal {
+        journal.destroy()?;
+    }
+
+    log::warn!("Pending ratchet state and journal wiped");
     Ok(())
 }
 
 #[cfg(test)]
+#[allow(deprecated)]
 mod tests {
     use super::*;
 
@@ -671,4 +1636,387 @@ mod tests {
         let result = encrypt_message(plaintext, &short_key);
         assert!(result.is_err());
     }
+    #[test]
+    fn test_typed_chain_keys_match_byte_api() {
+        let root = RootKey::derive(&[9u8; 32], b"SecureLegion-RootKey-v1").unwrap();
+        assert_eq!(root.expose_secret(), &derive_root_key(&[9u8; 32], b"SecureLegion-RootKey-v1").unwrap());
+        assert_eq!(root.outgoing_chain_key().expose_secret(), &derive_outgoing_chain_key(root.expose_secret()).unwrap());
+
+        let mut send = root.outgoing_chain_key();
+        let mut recv = ChainKey::from(*send.expose_secret());
+        let mut legacy_recv = *send.expose_secret();
+
+        let first = send.encrypt_next(b"typed", 0).unwrap();
+        let second = send.encrypt_next(b"api", 1).unwrap();
+        assert_eq!(decrypt_message_with_evolution(&first, &mut legacy_recv, 0).unwrap().plaintext, b"typed");
+
+        // A failed decrypt leaves the chain where it was
+        assert!(recv.decrypt_next(&second, 0).is_err());
+        assert_eq!(recv.decrypt_next(&first, 0).unwrap(), b"typed");
+        assert_eq!(recv.expose_secret(), &legacy_recv);
+        assert_eq!(recv.decrypt_next(&second, 1).unwrap(), b"api");
+        assert_eq!(recv.expose_secret(), send.expose_secret());
+
+        assert!(ChainKey::from_bytes(&[0u8; 16]).is_err());
+        assert_eq!(format!("{:?}", recv.message_key()), "MessageKey([REDACTED])");
+    }
+
+    #[test]
+    fn test_out_of_order_with_skipped_keys() {
+        let mut send_key = [9u8; 32];
+        let mut recv_key = send_key;
+        let messages: Vec<Vec<u8>> = (0..4u64)
+            .map(|seq| encrypt_message_with_evolution(&[seq as u8; 8], &mut send_key, seq).unwrap().ciphertext)
+            .collect();
+
+        let mut skipped = SkippedKeyStore::default();
+        let mut expected = 0;
+
+        // 2 arrives first: keys for 0 and 1 are parked
+        let result = decrypt_message_with_skipped_keys(&messages[2], &mut recv_key, expected, &mut skipped, None).unwrap();
+        assert_eq!(result.plaintext, vec![2u8; 8]);
+        expected = result.next_expected_sequence;
+        assert_eq!(expected, 3);
+        assert_eq!(skipped.len(), 2);
+
+        // Late messages decrypt without moving the chain
+        for seq in [0usize, 1] {
+            let result = decrypt_message_with_skipped_keys(&messages[seq], &mut recv_key, expected, &mut skipped, None).unwrap();
+            assert_eq!(result.plaintext, vec![seq as u8; 8]);
+            assert_eq!(result.next_expected_sequence, 3);
+        }
+
+        let result = decrypt_message_with_skipped_keys(&messages[3], &mut recv_key, expected, &mut skipped, None).unwrap();
+        assert_eq!(result.plaintext, vec![3u8; 8]);
+        expected = result.next_expected_sequence;
+
+        // Replays are rejected once the parked key is used
+        let replay = decrypt_message_with_skipped_keys(&messages[0], &mut recv_key, expected, &mut skipped, None);
+        assert!(matches!(replay, Err(EncryptionError::ReplayAttack { .. })));
+    }
+
+    #[test]
+    fn test_skipped_keys_untouched_by_forgery() {
+        let mut send_key = [5u8; 32];
+        let mut recv_key = send_key;
+        let mut forged = encrypt_message_with_evolution(b"m0", &mut send_key, 0).unwrap().ciphertext;
+        let late = forged.clone();
+        let ahead = encrypt_message_with_evolution(b"m1", &mut send_key, 1).unwrap().ciphertext;
+
+        let mut skipped = SkippedKeyStore::default();
+        decrypt_message_with_skipped_keys(&ahead, &mut recv_key, 0, &mut skipped, None).unwrap();
+
+        let last = forged.len() - 1;
+        forged[last] ^= 0x01;
+        assert!(decrypt_message_with_skipped_keys(&forged, &mut recv_key, 2, &mut skipped, None).is_err());
+
+        let result = decrypt_message_with_skipped_keys(&late, &mut recv_key, 2, &mut skipped, None).unwrap();
+        assert_eq!(result.plaintext, b"m0");
+    }
+
+    fn alice_to_bob(msg_type: u8) -> MessageBinding {
+        MessageBinding::new([0xA1; 32], [0xB0; 32], msg_type)
+    }
+
+    #[test]
+    fn test_v2_roundtrip() {
+        let mut send_key = [4u8; 32];
+        let mut recv_key = send_key;
+        let binding = alice_to_bob(0x03);
+
+        let deferred = encrypt_message_deferred_v2(b"first", &send_key, 0, &binding, PaddingScheme::Padme).unwrap();
+        assert_eq!(deferred.ciphertext[0], WIRE_VERSION_V2);
+        send_key = deferred.next_chain_key;
+        let second = encrypt_message_with_evolution_v2(b"second", &mut send_key, 1, &binding, PaddingScheme::Padme).unwrap();
+
+        let result = decrypt_message_with_evolution_v2(&deferred.ciphertext, &mut recv_key, 0, &binding).unwrap();
+        assert_eq!(result.plaintext, b"first");
+        let result = decrypt_message_with_evolution_v2(&second.ciphertext, &mut recv_key, 1, &binding).unwrap();
+        assert_eq!(result.plaintext, b"second");
+    }
+
+    #[test]
+    fn test_epoch_carried_and_authenticated_in_header() {
+        let mut send_key = [5u8; 32];
+        let binding = alice_to_bob(0x03).with_epoch(2);
+
+        let sent = encrypt_message_with_evolution_v2(b"healed", &mut send_key, 0, &binding, PaddingScheme::Padme).unwrap();
+        assert_eq!(sent.ciphertext[0], WIRE_VERSION_V2 | WIRE_FLAG_EPOCH);
+        assert_eq!(chain_message_epoch(&sent.ciphertext).unwrap(), 2);
+
+        // Chain keys of another epoch are refused before any key is used
+        let mut recv_key = [5u8; 32];
+        assert!(matches!(
+            decrypt_message_with_evolution_v2(&sent.ciphertext, &mut recv_key, 0, &alice_to_bob(0x03)),
+            Err(EncryptionError::EpochMismatch { received: 2, expected: 0 })
+        ));
+        assert_eq!(recv_key, [5u8; 32]);
+
+        // Rewriting the epoch breaks the AEAD
+        let mut relabeled = sent.ciphertext.clone();
+        relabeled[12] = 3;
+        assert!(decrypt_message_with_evolution_v2(&relabeled, &mut recv_key, 0, &alice_to_bob(0x03).with_epoch(3)).is_err());
+
+        let result = decrypt_message_with_evolution_v2(&sent.ciphertext, &mut recv_key, 0, &binding).unwrap();
+        assert_eq!(result.plaintext, b"healed");
+
+        // Epoch 0 keeps the pre-reset header byte for byte
+        let plain = encrypt_message_with_evolution_v2(b"x", &mut [5u8; 32], 0, &alice_to_bob(0x03), PaddingScheme::Padme).unwrap();
+        assert_eq!(plain.ciphertext[0], WIRE_VERSION_V2);
+        assert_eq!(chain_message_epoch(&plain.ciphertext).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_v2_rejects_header_tampering() {
+        let mut send_key = [6u8; 32];
+        let binding = alice_to_bob(0x03);
+        let ciphertext = encrypt_message_with_evolution_v2(b"hi", &mut send_key, 0, &binding, PaddingScheme::Padme).unwrap().ciphertext;
+
+        let try_decrypt = |data: &[u8], binding: &MessageBinding| {
+            let mut recv_key = [6u8; 32];
+            decrypt_message_with_evolution_v2(data, &mut recv_key, 0, binding)
+        };
+        assert!(try_decrypt(&ciphertext, &binding).is_ok());
+
+        // Re-routed as another message type
+        assert!(try_decrypt(&ciphertext, &alice_to_bob(0x09)).is_err());
+
+        // Reflected back to the sender (identities swapped)
+        let reflected = MessageBinding::new(binding.recipient_identity, binding.sender_identity, 0x03);
+        assert!(try_decrypt(&ciphertext, &reflected).is_err());
+
+        // Downgraded to v1: the tag was computed over the v2 associated data
+        let mut downgraded = ciphertext.clone();
+        downgraded[0] = WIRE_VERSION_V1;
+        assert!(try_decrypt(&downgraded, &binding).is_err());
+
+        // Sequence rewritten (stays within the window via skipped keys)
+        let mut resequenced = ciphertext.clone();
+        resequenced[8] = 1;
+        let mut recv_key = [6u8; 32];
+        let mut skipped = SkippedKeyStore::default();
+        assert!(decrypt_message_with_skipped_keys(&resequenced, &mut recv_key, 0, &mut skipped, Some(&binding)).is_err());
+        assert_eq!(recv_key, [6u8; 32]);
+    }
+
+    #[test]
+    fn test_v2_decrypt_with_parked_message_key() {
+        let chain_key = [12u8; 32];
+        let mut send_key = chain_key;
+        let binding = alice_to_bob(0x09);
+        let image = encrypt_message_with_evolution_v2(&[0xFF; 3000], &mut send_key, 0, &binding, PaddingScheme::Padme)
+            .unwrap()
+            .ciphertext;
+
+        let message_key = *ChainKey::from(chain_key).message_key().expose_secret();
+        assert_eq!(decrypt_with_message_key_v2(&image, &message_key, &binding).unwrap(), vec![0xFF; 3000]);
+
+        // Bound to the message type it was sent as
+        assert!(decrypt_with_message_key_v2(&image, &message_key, &alice_to_bob(0x03)).is_err());
+    }
+
+    #[test]
+    fn test_v1_accepted_during_migration() {
+        let mut send_key = [8u8; 32];
+        let binding = alice_to_bob(0x03);
+        let legacy = encrypt_message_with_evolution(b"old", &mut send_key, 0).unwrap().ciphertext;
+
+        let mut recv_key = [8u8; 32];
+        let result = decrypt_message_with_evolution_v2(&legacy, &mut recv_key, 0, &binding.with_legacy_v1(true)).unwrap();
+        assert_eq!(result.plaintext, b"old");
+
+        let mut recv_key = [8u8; 32];
+        let rejected = decrypt_message_with_evolution_v2(&legacy, &mut recv_key, 0, &binding.with_legacy_v1(false));
+        assert!(matches!(rejected, Err(EncryptionError::UnsupportedVersion(WIRE_VERSION_V1))));
+    }
+
+    #[test]
+    fn test_padding_hides_length_within_size_class() {
+        let key = generate_key();
+        let high_risk = PaddingScheme::for_tier(crate::protocol::security_mode::SecurityTier::HighRisk);
+
+        let short = encrypt_message_padded(b"ok", &key, high_risk).unwrap();
+        let longer = encrypt_message_padded(&[0x41; 300], &key, high_risk).unwrap();
+        assert_eq!(short.len(), longer.len());
+        assert_eq!(decrypt_message_padded(&short, &key).unwrap(), b"ok");
+
+        let mut send_key = [3u8; 32];
+        let binding = alice_to_bob(0x03);
+        let a = encrypt_message_with_evolution_v2(b"a", &mut send_key, 0, &binding, high_risk).unwrap().ciphertext;
+        let b = encrypt_message_with_evolution_v2(&[0x42; 400], &mut send_key, 1, &binding, high_risk).unwrap().ciphertext;
+        assert_eq!(a.len(), b.len());
+        assert_eq!(a.len(), 1 + 8 + 24 + 512 + 16);
+    }
+
+    #[test]
+    fn test_key_commitment_known_answer() {
+        // HKDF-SHA256(salt = nonce, ikm = key, info = KEY_COMMITMENT_INFO), computed independently
+        let nonce = [0x5au8; 24];
+        let (commitment, _) = derive_key_commitment(&[0x11u8; 32], &nonce).unwrap();
+        assert_eq!(hex::encode(commitment), "4a09be0df16dd111c851247a77f0e26eede939befb9cacd25592c7986b632a42");
+        let (commitment, _) = derive_key_commitment(&[0x22u8; 32], &nonce).unwrap();
+        assert_eq!(hex::encode(commitment), "0cb04a7d6754de0c13451c60f2ca32c0d3a93620250122144a406f0825cb3c52");
+    }
+
+    /// Invisible-salamander ciphertexts: Poly1305 tags forced equal under two keys by solving
+    /// for one ciphertext block (generated outside this crate, keys 0x11.. and 0x22.., nonce 0x5a..)
+    const SALAMANDER_V2: &str = "0ea57b73a8d03d7225aa973188416e2767f4844eb1e4a64b4d45a1a793164658\
+                                 a59ebc1590ae05e28f44f75ec3b7efe36f44f57fe952bad0930fa808295a12e5\
+                                 4a961f4183afac7a0e4eccb5ee969409";
+    /// The same attack aimed at v3: colliding under both committed subkeys
+    const SALAMANDER_V3: &str = "8adde71fb2796fba20ca15e71606418d53ff9ea9a03d53249f49a2f632594aff\
+                                 ab772ef6ca5bcca28b0680bfefbf3f1294629b493ac95612e9a9c99575816a58\
+                                 2e19a59ffc387e5d96166524540b8a24";
+
+    fn salamander(vector: &str) -> Vec<u8> {
+        hex::decode(vector.split_whitespace().collect::<String>()).unwrap()
+    }
+
+    #[test]
+    fn test_v2_opens_under_two_keys_v3_under_one() {
+        let keys = [[0x11u8; 32], [0x22u8; 32]];
+        let nonce = [0x5au8; 24];
+        let binding = alice_to_bob(0x03);
+
+        // v2: one ciphertext, two valid keys, two different plaintexts
+        let v2 = salamander(SALAMANDER_V2);
+        let aad = binding.associated_data(WIRE_VERSION_V2, 0);
+        let plaintexts: Vec<Vec<u8>> = keys
+            .iter()
+            .map(|key| {
+                XChaCha20Poly1305::new_from_slice(key)
+                    .unwrap()
+                    .decrypt(XNonce::from_slice(&nonce), Payload { msg: &v2, aad: &aad })
+                    .expect("v2 AEAD is not key-committing")
+            })
+            .collect();
+        assert_ne!(plaintexts[0], plaintexts[1]);
+
+        // v3: even a ciphertext colliding under both subkeys opens only under the committed key
+        let v3 = salamander(SALAMANDER_V3);
+        let aad = binding.associated_data(WIRE_VERSION_V3, 0);
+        for (committed, key) in keys.iter().enumerate() {
+            let (commitment, subkey) = derive_key_commitment(key, &nonce).unwrap();
+            assert!(XChaCha20Poly1305::new_from_slice(&subkey)
+                .unwrap()
+                .decrypt(XNonce::from_slice(&nonce), Payload { msg: &v3, aad: &aad })
+                .is_ok());
+
+            let mut sealed = commitment.to_vec();
+            sealed.extend_from_slice(&v3);
+            let opened: Vec<usize> = (0..keys.len())
+                .filter(|&i| open_committing(&keys[i], &nonce, &sealed, &aad).is_ok())
+                .collect();
+            assert_eq!(opened, vec![committed]);
+        }
+    }
+
+    #[test]
+    fn test_committing_message_rejects_tampering() {
+        let key = generate_key();
+        let encrypted = encrypt_message_committing(b"payload", &key).unwrap();
+        assert_eq!(encrypted[0], WIRE_VERSION_V3);
+
+        let mut bad_commitment = encrypted.clone();
+        bad_commitment[25] ^= 1;
+        assert!(decrypt_message_committing(&bad_commitment, &key).is_err());
+
+        let mut bad_version = encrypted.clone();
+        bad_version[0] = WIRE_VERSION_V2;
+        assert!(matches!(
+            decrypt_message_committing(&bad_version, &key),
+            Err(EncryptionError::UnsupportedVersion(WIRE_VERSION_V2))
+        ));
+
+        // Legacy format has no commitment and is not accepted here
+        let legacy = encrypt_message(b"payload", &key).unwrap();
+        assert!(decrypt_message_committing(&legacy, &key).is_err());
+    }
+
+    #[test]
+    fn test_v3_chain_message_is_key_committing() {
+        let padding = PaddingScheme::for_tier(crate::protocol::security_mode::SecurityTier::HighRisk);
+        let mut send_key = [4u8; 32];
+        let plain = encrypt_message_with_evolution_v2(b"v2", &mut send_key, 0, &alice_to_bob(0x03), padding).unwrap();
+        assert_eq!(plain.ciphertext[0], WIRE_VERSION_V2);
+
+        // Negotiated per contact: only this binding sends v3
+        let binding = alice_to_bob(0x03).with_key_committing(true);
+        let mut send_key = [4u8; 32];
+        let ciphertext = encrypt_message_with_evolution_v2(b"committed", &mut send_key, 0, &binding, padding)
+            .unwrap()
+            .ciphertext;
+        assert_eq!(ciphertext[0], WIRE_VERSION_V3);
+        assert_eq!(ciphertext.len(), 1 + 8 + 24 + KEY_COMMITMENT_BYTES + 512 + 16);
+
+        let mut recv_key = [4u8; 32];
+        let result = decrypt_message_with_evolution_v2(&ciphertext, &mut recv_key, 0, &binding).unwrap();
+        assert_eq!(result.plaintext, b"committed");
+
+        // Downgrading the version byte to v2 breaks the header binding
+        let mut downgraded = ciphertext.clone();
+        downgraded[0] = WIRE_VERSION_V2;
+        let mut recv_key = [4u8; 32];
+        assert!(decrypt_message_with_evolution_v2(&downgraded, &mut recv_key, 0, &binding).is_err());
+        assert_eq!(recv_key, [4u8; 32]);
+    }
+
+    /// PENDING_RATCHETS and RATCHET_JOURNAL are process-global
+    static RATCHET_STATE_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_pending_ratchets_queue_per_contact() {
+        let _guard = RATCHET_STATE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let contact = "queue-test-contact";
+        for seq in 1..=4u64 {
+            store_pending_ratchet_advancement(contact, &format!("m{}", seq), [seq as u8; 32], seq).unwrap();
+        }
+        // A second message no longer overwrites the first; sequences must keep increasing
+        assert!(store_pending_ratchet_advancement(contact, "stale", [0u8; 32], 2).is_err());
+
+        // ACK for m2 commits m1..m2 only
+        assert_eq!(commit_ratchet_advancement_through(contact, "m2").unwrap(), Some(([2u8; 32], 2)));
+        assert_eq!(commit_ratchet_advancement_through(contact, "m1").unwrap(), None);
+
+        // Rolling back m3 drops m4 too
+        assert_eq!(rollback_ratchet_advancement_from(contact, "m3").unwrap(), 2);
+        assert_eq!(commit_ratchet_advancement(contact).unwrap(), None);
+
+        // Acknowledged advancements can no longer be rolled back, only finalized
+        assert_eq!(rollback_ratchet_advancement_from(contact, "m1").unwrap(), 0);
+        assert_eq!(ack_ratchet_persisted(contact, "m2").unwrap(), 2);
+        assert_eq!(ack_ratchet_persisted(contact, "m2").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_journal_keeps_commit_until_app_persists() {
+        let _guard = RATCHET_STATE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let path = std::env::temp_dir().join(format!("encryption_journal_{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let key = [9u8; 32];
+        let contact = "journal-test-contact";
+
+        assert!(init_ratchet_journal(&path, &key).unwrap().is_empty());
+        store_pending_ratchet_advancement(contact, "m1", [1u8; 32], 1).unwrap();
+        store_pending_ratchet_advancement(contact, "m2", [2u8; 32], 2).unwrap();
+
+        // PING_ACK for m2 hands out the key, then the process dies before the app persists it
+        assert_eq!(commit_ratchet_advancement_through(contact, "m2").unwrap(), Some(([2u8; 32], 2)));
+        let recovered = init_ratchet_journal(&path, &key).unwrap();
+        assert_eq!(recovered.iter().map(|a| a.next_sequence).collect::<Vec<_>>(), vec![1, 2]);
+
+        // After restart the key is still available; once persisted and acked it is gone for good
+        assert_eq!(commit_ratchet_advancement_through(contact, "m2").unwrap(), Some(([2u8; 32], 2)));
+        assert_eq!(ack_ratchet_persisted(contact, "m2").unwrap(), 2);
+        assert!(init_ratchet_journal(&path, &key).unwrap().is_empty());
+
+        // A recovered advancement the app had already persisted is resolved by ack alone
+        store_pending_ratchet_advancement(contact, "m3", [3u8; 32], 3).unwrap();
+        assert_eq!(init_ratchet_journal(&path, &key).unwrap().len(), 1);
+        assert_eq!(ack_ratchet_persisted(contact, "m3").unwrap(), 1);
+        assert!(init_ratchet_journal(&path, &key).unwrap().is_empty());
+
+        wipe_ratchet_state().unwrap();
+        assert!(!path.exists());
+    }
 }
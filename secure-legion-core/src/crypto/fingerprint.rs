@@ -0,0 +1,205 @@
+//! Safety Numbers
+//! Out-of-band verification of a contact's identity keys
+//!
+//! Each party's fingerprint is an iterated SHA-512 over the format version and its
+//! Ed25519, X25519 and ML-KEM (Kyber) public keys. The safety number is both fingerprints
+//! in sorted order, so the two users see the same digits and the same QR payload.
+//! Replacing any key changes the number; the version byte lets the format evolve.
+
+use crate::crypto::pqc::KYBER_PUBLIC_KEY_BYTES;
+use sha2::{Digest, Sha512};
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+
+/// Current safety number format version
+pub const FINGERPRINT_VERSION: u8 = 1;
+
+/// SHA-512 iterations per fingerprint (slows down brute-forcing a colliding key)
+pub const FINGERPRINT_ITERATIONS: usize = 5200;
+
+/// Bytes of each party's fingerprint carried in the QR payload
+pub const FINGERPRINT_BYTES: usize = 32;
+
+/// Decimal digits shown per party (6 groups of 5)
+pub const FINGERPRINT_DIGITS: usize = 30;
+
+/// QR payload: [version][lower fingerprint 32][higher fingerprint 32]
+pub const QR_PAYLOAD_LEN: usize = 1 + 2 * FINGERPRINT_BYTES;
+
+const FINGERPRINT_DOMAIN: &[u8] = b"SecureLegion-SafetyNumber";
+
+#[derive(Error, Debug)]
+pub enum FingerprintError {
+    #[error("Invalid public key length")]
+    InvalidKeyLength,
+    #[error("Malformed QR payload")]
+    InvalidPayload,
+    #[error("Unsupported safety number version: {0}")]
+    UnsupportedVersion(u8),
+}
+
+pub type Result<T> = std::result::Result<T, FingerprintError>;
+
+/// Public identity keys of one party
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdentityKeys {
+    pub ed25519: [u8; 32],
+    pub x25519: [u8; 32],
+    pub kyber: Vec<u8>,
+}
+
+/// Safety number shared by two contacts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SafetyNumber {
+    version: u8,
+    /// Both fingerprints, lower digits first
+    fingerprints: [[u8; FINGERPRINT_BYTES]; 2],
+}
+
+impl IdentityKeys {
+    pub fn new(ed25519: [u8; 32], x25519: [u8; 32], kyber: &[u8]) -> Result<Self> {
+        if kyber.len() != KYBER_PUBLIC_KEY_BYTES {
+            return Err(FingerprintError::InvalidKeyLength);
+        }
+        Ok(Self { ed25519, x25519, kyber: kyber.to_vec() })
+    }
+
+    /// Iterated hash of this party's keys
+    fn fingerprint(&self, version: u8) -> [u8; FINGERPRINT_BYTES] {
+        let mut keys = Vec::with_capacity(64 + self.kyber.len());
+        keys.extend_from_slice(&self.ed25519);
+        keys.extend_from_slice(&self.x25519);
+        keys.extend_from_slice(&self.kyber);
+
+        let mut hash = Sha512::new()
+            .chain_update(FINGERPRINT_DOMAIN)
+            .chain_update([version])
+            .chain_update(&keys)
+            .finalize();
+        for _ in 1..FINGERPRINT_ITERATIONS {
+            hash = Sha512::new().chain_update(hash).chain_update(&keys).finalize();
+        }
+
+        let mut fingerprint = [0u8; FINGERPRINT_BYTES];
+        fingerprint.copy_from_slice(&hash[..FINGERPRINT_BYTES]);
+        fingerprint
+    }
+}
+
+impl SafetyNumber {
+    /// Compute the safety number for a pair of contacts (argument order does not matter)
+    ///
+    /// # Arguments
+    /// * `local` - Our identity keys
+    /// * `remote` - The contact's identity keys
+    pub fn new(local: &IdentityKeys, remote: &IdentityKeys) -> Self {
+        let mut fingerprints = [local.fingerprint(FINGERPRINT_VERSION), remote.fingerprint(FINGERPRINT_VERSION)];
+        fingerprints.sort_by_key(fingerprint_digits);
+        Self { version: FINGERPRINT_VERSION, fingerprints }
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// 60 digits, lower party first
+    pub fn digits(&self) -> String {
+        self.fingerprints.iter().map(fingerprint_digits).collect()
+    }
+
+    /// Digits in groups of 5 separated by spaces, as shown to users
+    pub fn display_string(&self) -> String {
+        let digits = self.digits();
+        digits
+            .as_bytes()
+            .chunks(5)
+            .map(|group| std::str::from_utf8(group).unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Compact payload to render as a QR code
+    pub fn to_qr_payload(&self) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(QR_PAYLOAD_LEN);
+        payload.push(self.version);
+        payload.extend_from_slice(&self.fingerprints[0]);
+        payload.extend_from_slice(&self.fingerprints[1]);
+        payload
+    }
+
+    /// Compare a payload scanned from the contact's screen with our own safety number
+    ///
+    /// # Returns
+    /// true if both sides see the same keys
+    pub fn verify_qr_payload(&self, scanned: &[u8]) -> Result<bool> {
+        if scanned.len() != QR_PAYLOAD_LEN {
+            return Err(FingerprintError::InvalidPayload);
+        }
+        if scanned[0] != self.version {
+            return Err(FingerprintError::UnsupportedVersion(scanned[0]));
+        }
+        Ok(bool::from(self.to_qr_payload().ct_eq(scanned)))
+    }
+}
+
+/// Six 5-digit groups, each from 5 fingerprint bytes mod 100000
+fn fingerprint_digits(fingerprint: &[u8; FINGERPRINT_BYTES]) -> String {
+    fingerprint[..30]
+        .chunks(5)
+        .map(|chunk| {
+            let value = chunk.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+            format!("{:05}", value % 100_000)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(tag: u8) -> IdentityKeys {
+        IdentityKeys::new([tag; 32], [tag.wrapping_add(1); 32], &vec![tag; KYBER_PUBLIC_KEY_BYTES]).unwrap()
+    }
+
+    #[test]
+    fn test_safety_number_is_symmetric() {
+        let alice = identity(1);
+        let bob = identity(2);
+
+        let ours = SafetyNumber::new(&alice, &bob);
+        let theirs = SafetyNumber::new(&bob, &alice);
+        assert_eq!(ours.digits(), theirs.digits());
+        assert_eq!(ours.digits().len(), 2 * FINGERPRINT_DIGITS);
+        assert_eq!(ours.display_string().split(' ').count(), 12);
+        assert!(ours.verify_qr_payload(&theirs.to_qr_payload()).unwrap());
+    }
+
+    #[test]
+    fn test_any_key_change_changes_number() {
+        let alice = identity(1);
+        let bob = identity(2);
+        let original = SafetyNumber::new(&alice, &bob);
+
+        let mut changed = [bob.clone(), bob.clone(), bob];
+        changed[0].ed25519[0] ^= 1;
+        changed[1].x25519[0] ^= 1;
+        changed[2].kyber[0] ^= 1;
+        for bob in &changed {
+            let number = SafetyNumber::new(&alice, bob);
+            assert_ne!(number.digits(), original.digits());
+            assert!(!original.verify_qr_payload(&number.to_qr_payload()).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_qr_payload_rejects_malformed_input() {
+        let number = SafetyNumber::new(&identity(1), &identity(2));
+        let mut payload = number.to_qr_payload();
+        assert_eq!(payload.len(), QR_PAYLOAD_LEN);
+
+        assert!(matches!(number.verify_qr_payload(&payload[1..]), Err(FingerprintError::InvalidPayload)));
+        payload[0] = FINGERPRINT_VERSION + 1;
+        assert!(matches!(number.verify_qr_payload(&payload), Err(FingerprintError::UnsupportedVersion(_))));
+        assert!(IdentityKeys::new([0; 32], [0; 32], &[0u8; 16]).is_err());
+    }
+}
@@ -1,8 +1,9 @@
 use argon2::{
     password_hash::{PasswordHasher, SaltString},
-    Argon2, PasswordHash, PasswordVerifier,
+    Algorithm, Argon2, Params, PasswordHash, PasswordVerifier, Version,
 };
 use rand::rngs::OsRng;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -13,20 +14,251 @@ pub enum HashingError {
     VerificationFailed,
     #[error("Invalid salt")]
     InvalidSalt,
+    #[error("Invalid KDF parameters: {0}")]
+    InvalidParams(String),
 }
 
 pub type Result<T> = std::result::Result<T, HashingError>;
 
+/// Encoded size of a `KdfParams` descriptor
+pub const KDF_DESCRIPTOR_LEN: usize = 15;
+
+/// Descriptor layout version (first byte of the encoding)
+const KDF_DESCRIPTOR_FORMAT: u8 = 1;
+
+/// Highest pass count calibration will pick (and a stored descriptor may ask for)
+pub const MAX_CALIBRATED_T_COST: u32 = 10;
+
+/// Highest memory cost a stored descriptor may ask for (1 GiB)
+pub const MAX_M_COST_KIB: u32 = 1024 * 1024;
+
+/// Argon2 variant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KdfAlgorithm {
+    Argon2d = 0,
+    Argon2i = 1,
+    Argon2id = 2,
+}
+
+impl KdfAlgorithm {
+    fn from_u8(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(Self::Argon2d),
+            1 => Ok(Self::Argon2i),
+            2 => Ok(Self::Argon2id),
+            other => Err(HashingError::InvalidParams(format!("unknown algorithm {}", other))),
+        }
+    }
+
+    fn to_argon2(self) -> Algorithm {
+        match self {
+            Self::Argon2d => Algorithm::Argon2d,
+            Self::Argon2i => Algorithm::Argon2i,
+            Self::Argon2id => Algorithm::Argon2id,
+        }
+    }
+
+    fn from_argon2(algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::Argon2d => Self::Argon2d,
+            Algorithm::Argon2i => Self::Argon2i,
+            Algorithm::Argon2id => Self::Argon2id,
+        }
+    }
+}
+
+/// KDF descriptor, stored next to every key or hash derived from a password
+///
+/// Encoded as [format u8][algorithm u8][version u8][m_cost u32 BE][t_cost u32 BE][p_cost u32 BE]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    pub algorithm: KdfAlgorithm,
+    /// Argon2 version (0x10 or 0x13)
+    pub version: u8,
+    /// Memory cost in KiB
+    pub m_cost_kib: u32,
+    /// Number of passes
+    pub t_cost: u32,
+    /// Degree of parallelism
+    pub p_cost: u32,
+}
+
+impl KdfParams {
+    /// `Argon2::default()`, which every key derived before descriptors existed used
+    pub const LEGACY: KdfParams = KdfParams {
+        algorithm: KdfAlgorithm::Argon2id,
+        version: 0x13,
+        m_cost_kib: Params::DEFAULT_M_COST,
+        t_cost: Params::DEFAULT_T_COST,
+        p_cost: Params::DEFAULT_P_COST,
+    };
+
+    /// Floor for calibrated parameters (never derive more cheaply than legacy keys)
+    pub const MINIMUM: KdfParams = Self::LEGACY;
+
+    /// Parameters for new hashes when no calibration result is available
+    pub const RECOMMENDED: KdfParams = KdfParams {
+        algorithm: KdfAlgorithm::Argon2id,
+        version: 0x13,
+        m_cost_kib: 64 * 1024,
+        t_cost: 3,
+        p_cost: 1,
+    };
+
+    /// Encode the descriptor for storage
+    pub fn to_bytes(&self) -> [u8; KDF_DESCRIPTOR_LEN] {
+        let mut out = [0u8; KDF_DESCRIPTOR_LEN];
+        out[0] = KDF_DESCRIPTOR_FORMAT;
+        out[1] = self.algorithm as u8;
+        out[2] = self.version;
+        out[3..7].copy_from_slice(&self.m_cost_kib.to_be_bytes());
+        out[7..11].copy_from_slice(&self.t_cost.to_be_bytes());
+        out[11..15].copy_from_slice(&self.p_cost.to_be_bytes());
+        out
+    }
+
+    /// Decode and validate a stored descriptor
+    ///
+    /// Only Argon2id v0x13 within `MAX_M_COST_KIB` and `MAX_CALIBRATED_T_COST` is
+    /// accepted, so a tampered descriptor cannot downgrade the KDF or exhaust the device.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != KDF_DESCRIPTOR_LEN {
+            return Err(HashingError::InvalidParams(format!("descriptor is {} bytes", bytes.len())));
+        }
+        if bytes[0] != KDF_DESCRIPTOR_FORMAT {
+            return Err(HashingError::InvalidParams(format!("unknown descriptor format {}", bytes[0])));
+        }
+
+        let params = Self {
+            algorithm: KdfAlgorithm::from_u8(bytes[1])?,
+            version: bytes[2],
+            m_cost_kib: u32::from_be_bytes(bytes[3..7].try_into().unwrap()),
+            t_cost: u32::from_be_bytes(bytes[7..11].try_into().unwrap()),
+            p_cost: u32::from_be_bytes(bytes[11..15].try_into().unwrap()),
+        };
+        params.check_bounds()?;
+        params.argon2()?;
+        Ok(params)
+    }
+
+    /// Parameters recorded in a PHC hash string (held to the same bounds as descriptors)
+    pub fn from_phc(hash: &str) -> Result<Self> {
+        let parsed = PasswordHash::new(hash)
+            .map_err(|e| HashingError::InvalidParams(e.to_string()))?;
+        let algorithm = Algorithm::try_from(parsed.algorithm)
+            .map_err(|e| HashingError::InvalidParams(e.to_string()))?;
+        let params = Params::try_from(&parsed)
+            .map_err(|e| HashingError::InvalidParams(e.to_string()))?;
+        let version = parsed.version.unwrap_or(Version::V0x13 as u32);
+
+        let params = Self {
+            algorithm: KdfAlgorithm::from_argon2(algorithm),
+            version: u8::try_from(version)
+                .map_err(|_| HashingError::InvalidParams(format!("unknown version {}", version)))?,
+            m_cost_kib: params.m_cost(),
+            t_cost: params.t_cost(),
+            p_cost: params.p_cost(),
+        };
+        params.check_bounds()?;
+        Ok(params)
+    }
+
+    /// True if keys derived with these parameters should be re-derived with `target`
+    ///
+    /// Parallelism is a device choice and does not count as weaker.
+    pub fn needs_rehash(&self, target: &KdfParams) -> bool {
+        self.algorithm != target.algorithm
+            || self.version < target.version
+            || self.m_cost_kib < target.m_cost_kib
+            || self.t_cost < target.t_cost
+    }
+
+    fn check_bounds(&self) -> Result<()> {
+        if self.algorithm != KdfAlgorithm::Argon2id {
+            return Err(HashingError::InvalidParams(format!("{:?} is not accepted", self.algorithm)));
+        }
+        if self.version != Version::V0x13 as u8 {
+            return Err(HashingError::InvalidParams(format!("version {:#x} is not accepted", self.version)));
+        }
+        if self.m_cost_kib > MAX_M_COST_KIB {
+            return Err(HashingError::InvalidParams(format!("m_cost {} KiB exceeds {}", self.m_cost_kib, MAX_M_COST_KIB)));
+        }
+        if self.t_cost > MAX_CALIBRATED_T_COST {
+            return Err(HashingError::InvalidParams(format!("t_cost {} exceeds {}", self.t_cost, MAX_CALIBRATED_T_COST)));
+        }
+        Ok(())
+    }
+
+    fn argon2(&self) -> Result<Argon2<'static>> {
+        let version = Version::try_from(self.version as u32)
+            .map_err(|e| HashingError::InvalidParams(e.to_string()))?;
+        let params = Params::new(self.m_cost_kib, self.t_cost, self.p_cost, Some(32))
+            .map_err(|e| HashingError::InvalidParams(e.to_string()))?;
+        Ok(Argon2::new(self.algorithm.to_argon2(), version, params))
+    }
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self::RECOMMENDED
+    }
+}
+
+/// Pick parameters that take roughly `target` on this device
+///
+/// Memory is fixed by the caller (it depends on how much RAM the app may use); one
+/// single-pass probe is timed and the pass count scaled to fill the target. The result
+/// is never weaker than `KdfParams::MINIMUM`.
+///
+/// # Arguments
+/// * `target` - Desired derivation time
+/// * `memory_kib` - Memory cost to use
+///
+/// # Returns
+/// Calibrated parameters
+pub fn calibrate_kdf(target: Duration, memory_kib: u32) -> Result<KdfParams> {
+    let m_cost_kib = memory_kib.clamp(KdfParams::MINIMUM.m_cost_kib, MAX_M_COST_KIB);
+    let probe = KdfParams { m_cost_kib, t_cost: 1, ..KdfParams::RECOMMENDED };
+
+    let start = Instant::now();
+    derive_key("calibration", &[0u8; 16], &probe)?;
+    let per_pass = start.elapsed().as_nanos().max(1);
+
+    let passes = (target.as_nanos() / per_pass).min(MAX_CALIBRATED_T_COST as u128) as u32;
+    let params = KdfParams {
+        t_cost: passes.clamp(KdfParams::MINIMUM.t_cost, MAX_CALIBRATED_T_COST),
+        ..probe
+    };
+
+    log::info!("Calibrated Argon2id: m={} KiB, t={} ({} ns/pass)", params.m_cost_kib, params.t_cost, per_pass);
+    Ok(params)
+}
+
 /// Hash a password using Argon2id
 ///
+/// The PHC string records its own parameters, so `verify_password` keeps working
+/// when the recommended parameters change.
+///
 /// # Arguments
 /// * `password` - Password to hash
 ///
 /// # Returns
 /// Password hash string (PHC format)
 pub fn hash_password(password: &str) -> Result<String> {
+    hash_password_with_params(password, &KdfParams::default())
+}
+
+/// Hash a password into a PHC string with explicit parameters
+///
+/// # Arguments
+/// * `password` - Password to hash
+/// * `params` - KDF parameters
+///
+/// # Returns
+/// Password hash string (PHC format)
+pub fn hash_password_with_params(password: &str, params: &KdfParams) -> Result<String> {
     let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
+    let argon2 = params.argon2()?;
 
     let password_hash = argon2
         .hash_password(password.as_bytes(), &salt)
@@ -38,6 +270,10 @@ pub fn hash_password(password: &str) -> Result<String> {
 
 /// Hash a password with a specific salt
 ///
+/// Always uses `KdfParams::LEGACY`, since the raw output carries no parameters and
+/// callers compare it against keys derived before descriptors existed. New keys
+/// should use `derive_key` and store the descriptor.
+///
 /// # Arguments
 /// * `password` - Password to hash
 /// * `salt` - Salt bytes (16 bytes recommended)
@@ -45,11 +281,24 @@ pub fn hash_password(password: &str) -> Result<String> {
 /// # Returns
 /// 32-byte hash
 pub fn hash_password_with_salt(password: &str, salt: &[u8]) -> Result<[u8; 32]> {
+    derive_key(password, salt, &KdfParams::LEGACY)
+}
+
+/// Derive a 32-byte key from a password with explicit parameters
+///
+/// # Arguments
+/// * `password` - Password to stretch
+/// * `salt` - Salt bytes (at least 16)
+/// * `params` - KDF parameters (store `params.to_bytes()` with the key)
+///
+/// # Returns
+/// 32-byte key
+pub fn derive_key(password: &str, salt: &[u8], params: &KdfParams) -> Result<[u8; 32]> {
     if salt.len() < 16 {
         return Err(HashingError::InvalidSalt);
     }
 
-    let argon2 = Argon2::default();
+    let argon2 = params.argon2()?;
 
     let mut output = [0u8; 32];
     argon2
@@ -79,8 +328,42 @@ pub fn verify_password(password: &str, hash: &str) -> Result<bool> {
     }
 }
 
+/// Result of a login-time password check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasswordCheck {
+    /// Password does not match
+    Mismatch,
+    /// Password matches and the hash already meets the target parameters
+    Valid,
+    /// Password matches; store this replacement hash (made with the target parameters)
+    Rehashed(String),
+}
+
+/// Verify a password and upgrade its hash if it was made with weaker parameters
+///
+/// # Arguments
+/// * `password` - Password to verify
+/// * `hash` - Stored password hash (PHC format)
+/// * `target` - Parameters new hashes should use
+///
+/// # Returns
+/// Whether the password matched, and a replacement hash if one is due
+pub fn verify_password_and_rehash(password: &str, hash: &str, target: &KdfParams) -> Result<PasswordCheck> {
+    if !verify_password(password, hash)? {
+        return Ok(PasswordCheck::Mismatch);
+    }
+
+    if !KdfParams::from_phc(hash)?.needs_rehash(target) {
+        return Ok(PasswordCheck::Valid);
+    }
+
+    Ok(PasswordCheck::Rehashed(hash_password_with_params(password, target)?))
+}
+
 /// Hash a handle (username) for privacy-preserving lookup
 ///
+/// Pinned to `KdfParams::LEGACY` so every device computes the same lookup hash.
+///
 /// # Arguments
 /// * `handle` - User handle/username
 /// * `salt` - Global salt (should be consistent across app)
@@ -150,6 +433,77 @@ mod tests {
         assert_eq!(hash1, hash2);
     }
 
+    #[test]
+    fn test_kdf_descriptor_roundtrip() {
+        let params = KdfParams { m_cost_kib: 32 * 1024, t_cost: 4, ..KdfParams::RECOMMENDED };
+        let bytes = params.to_bytes();
+        assert_eq!(KdfParams::from_bytes(&bytes).unwrap(), params);
+
+        // Legacy descriptor reproduces the raw-bytes hash
+        let salt = generate_salt();
+        assert_eq!(
+            derive_key("pw", &salt, &KdfParams::LEGACY).unwrap(),
+            hash_password_with_salt("pw", &salt).unwrap()
+        );
+        assert_ne!(
+            derive_key("pw", &salt, &params).unwrap(),
+            hash_password_with_salt("pw", &salt).unwrap()
+        );
+
+        let mut bad = bytes;
+        bad[1] = 7;
+        assert!(KdfParams::from_bytes(&bad).is_err());
+        assert!(KdfParams::from_bytes(&bytes[..10]).is_err());
+    }
+
+    #[test]
+    fn test_kdf_descriptor_bounds() {
+        assert!(KdfParams::from_bytes(&KdfParams::LEGACY.to_bytes()).is_ok());
+
+        let rejected = [
+            KdfParams { algorithm: KdfAlgorithm::Argon2i, ..KdfParams::RECOMMENDED },
+            KdfParams { algorithm: KdfAlgorithm::Argon2d, ..KdfParams::RECOMMENDED },
+            KdfParams { version: 0x10, ..KdfParams::RECOMMENDED },
+            KdfParams { m_cost_kib: MAX_M_COST_KIB + 1, ..KdfParams::RECOMMENDED },
+            KdfParams { t_cost: MAX_CALIBRATED_T_COST + 1, ..KdfParams::RECOMMENDED },
+            KdfParams { m_cost_kib: u32::MAX, t_cost: u32::MAX, ..KdfParams::RECOMMENDED },
+        ];
+        for params in rejected {
+            assert!(KdfParams::from_bytes(&params.to_bytes()).is_err(), "{:?}", params);
+        }
+
+        let max = KdfParams { m_cost_kib: MAX_M_COST_KIB, t_cost: MAX_CALIBRATED_T_COST, ..KdfParams::RECOMMENDED };
+        assert_eq!(KdfParams::from_bytes(&max.to_bytes()).unwrap(), max);
+    }
+
+    #[test]
+    fn test_verify_password_and_rehash() {
+        let cheap = KdfParams { m_cost_kib: 8 * 1024, t_cost: 1, ..KdfParams::RECOMMENDED };
+        let target = KdfParams { m_cost_kib: 8 * 1024, t_cost: 2, ..KdfParams::RECOMMENDED };
+        let old_hash = hash_password_with_params("hunter2", &cheap).unwrap();
+        assert_eq!(KdfParams::from_phc(&old_hash).unwrap(), cheap);
+
+        assert_eq!(verify_password_and_rehash("wrong", &old_hash, &target).unwrap(), PasswordCheck::Mismatch);
+
+        let new_hash = match verify_password_and_rehash("hunter2", &old_hash, &target).unwrap() {
+            PasswordCheck::Rehashed(hash) => hash,
+            other => panic!("expected rehash, got {:?}", other),
+        };
+        assert_eq!(KdfParams::from_phc(&new_hash).unwrap(), target);
+        assert!(verify_password("hunter2", &new_hash).unwrap());
+        assert_eq!(verify_password_and_rehash("hunter2", &new_hash, &target).unwrap(), PasswordCheck::Valid);
+    }
+
+    #[test]
+    fn test_calibrate_kdf_respects_bounds() {
+        let quick = calibrate_kdf(Duration::from_nanos(1), 1024).unwrap();
+        assert_eq!(quick, KdfParams { m_cost_kib: KdfParams::MINIMUM.m_cost_kib, t_cost: KdfParams::MINIMUM.t_cost, ..KdfParams::RECOMMENDED });
+        assert!(!quick.needs_rehash(&KdfParams::MINIMUM));
+
+        let slow = calibrate_kdf(Duration::from_secs(3600), 0).unwrap();
+        assert_eq!(slow.t_cost, MAX_CALIBRATED_T_COST);
+    }
+
     #[test]
     fn test_generate_salt() {
         let salt1 = generate_salt();
@@ -1,6 +1,8 @@
 use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 #[derive(Error, Debug)]
 pub enum KeyExchangeError {
@@ -12,6 +14,65 @@ pub enum KeyExchangeError {
 
 pub type Result<T> = std::result::Result<T, KeyExchangeError>;
 
+/// X25519 static private key, wiped on drop
+///
+/// Only Diffie-Hellman and public key derivation are exposed; `Debug` never prints the key.
+#[derive(Clone, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub struct X25519Secret([u8; 32]);
+
+impl X25519Secret {
+    /// Generate a random static secret
+    pub fn generate() -> Self {
+        Self(StaticSecret::random_from_rng(OsRng).to_bytes())
+    }
+
+    /// Load a secret from untrusted bytes
+    ///
+    /// # Arguments
+    /// * `bytes` - 32-byte X25519 private key
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let key: [u8; 32] = bytes.try_into().map_err(|_| KeyExchangeError::InvalidKeyLength)?;
+        Ok(Self(key))
+    }
+
+    /// Perform X25519 Diffie-Hellman
+    ///
+    /// # Arguments
+    /// * `their_public_key` - Their 32-byte X25519 public key
+    ///
+    /// # Returns
+    /// 32-byte shared secret, wiped on drop
+    pub fn diffie_hellman(&self, their_public_key: &[u8]) -> Result<Zeroizing<[u8; 32]>> {
+        let public: [u8; 32] = their_public_key
+            .try_into()
+            .map_err(|_| KeyExchangeError::InvalidKeyLength)?;
+        let shared = StaticSecret::from(self.0).diffie_hellman(&PublicKey::from(public));
+        Ok(Zeroizing::new(shared.to_bytes()))
+    }
+
+    /// 32-byte X25519 public key
+    pub fn public_key(&self) -> [u8; 32] {
+        PublicKey::from(&StaticSecret::from(self.0)).to_bytes()
+    }
+
+    /// Raw key bytes, for handing back to KeyManager storage only
+    pub fn expose_secret(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl From<[u8; 32]> for X25519Secret {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl std::fmt::Debug for X25519Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("X25519Secret([REDACTED])")
+    }
+}
+
 /// Generate an ephemeral X25519 keypair
 ///
 /// # Returns
@@ -42,32 +103,13 @@ pub fn generate_static_keypair() -> ([u8; 32], [u8; 32]) {
 ///
 /// # Returns
 /// 32-byte shared secret
+#[deprecated(note = "use X25519Secret::diffie_hellman")]
 pub fn derive_shared_secret(
     our_private_key: &[u8],
     their_public_key: &[u8],
 ) -> Result<[u8; 32]> {
-    if our_private_key.len() != 32 {
-        return Err(KeyExchangeError::InvalidKeyLength);
-    }
-
-    if their_public_key.len() != 32 {
-        return Err(KeyExchangeError::InvalidKeyLength);
-    }
-
-    // Create our secret key
-    let mut secret_bytes = [0u8; 32];
-    secret_bytes.copy_from_slice(our_private_key);
-    let secret = StaticSecret::from(secret_bytes);
-
-    // Create their public key
-    let mut public_bytes = [0u8; 32];
-    public_bytes.copy_from_slice(their_public_key);
-    let public = PublicKey::from(public_bytes);
-
-    // Perform Diffie-Hellman
-    let shared_secret = secret.diffie_hellman(&public);
-
-    Ok(shared_secret.to_bytes())
+    let shared_secret = X25519Secret::from_bytes(our_private_key)?.diffie_hellman(their_public_key)?;
+    Ok(*shared_secret)
 }
 
 /// Derive shared secret using ephemeral private key
@@ -117,6 +159,7 @@ pub fn derive_public_key(private_key: &[u8]) -> Result<[u8; 32]> {
 }
 
 #[cfg(test)]
+#[allow(deprecated)]
 mod tests {
     use super::*;
 
@@ -166,4 +209,18 @@ mod tests {
 
         assert_eq!(expected_public, derived_public);
     }
+
+    #[test]
+    fn test_x25519_secret() {
+        let alice = X25519Secret::generate();
+        let bob = X25519Secret::generate();
+
+        let ab = alice.diffie_hellman(&bob.public_key()).unwrap();
+        let ba = bob.diffie_hellman(&alice.public_key()).unwrap();
+        assert_eq!(*ab, *ba);
+        assert_eq!(derive_shared_secret(alice.expose_secret(), &bob.public_key()).unwrap(), *ab);
+
+        assert!(alice.diffie_hellman(&[0u8; 31]).is_err());
+        assert_eq!(format!("{:?}", alice), "X25519Secret([REDACTED])");
+    }
 }
@@ -0,0 +1,277 @@
+//! Deterministic Key Hierarchy
+//! Derives every purpose key from a single master seed
+//!
+//! Each key purpose (identity signing, X25519 encryption, Kyber, Tor hidden service,
+//! friend request and voice service) has its own domain-separated label, so one
+//! master seed is enough to rebuild the whole identity. Derivation paths are
+//! versioned:
+//!
+//! - `Legacy` reproduces KeyManager's scheme byte for byte: signing uses the first
+//!   32 bytes of the seed, the other keys use SHA-256(seed || label).
+//! - `V1` uses HKDF-SHA256 with a fixed salt and a per-purpose info string
+//!   "SecureLegion-KeyHierarchy-v1/<purpose>".
+//!
+//! Kyber always derives from the encryption secret (see
+//! `generate_hybrid_keypair_from_seed`), so the hybrid keypair's X25519 half matches
+//! the encryption key. The master seed and every derived secret are zeroized on drop.
+
+use crate::crypto::pqc::{generate_hybrid_keypair_from_seed, HybridKEMKeypair};
+use ed25519_dalek::SigningKey;
+use hkdf::Hkdf;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use x25519_dalek::StaticSecret;
+use zeroize::Zeroizing;
+
+/// Shortest accepted master seed (a raw 256-bit seed)
+pub const MIN_MASTER_SEED_BYTES: usize = 32;
+
+/// Longest accepted master seed (a 512-bit BIP39 seed)
+pub const MAX_MASTER_SEED_BYTES: usize = 64;
+
+/// Seed length of the `Legacy` path: KeyManager only ever derives from a 64-byte BIP39 seed
+pub const LEGACY_SEED_BYTES: usize = 64;
+
+const V1_SALT: &[u8] = b"SecureLegion-KeyHierarchy";
+
+#[derive(Error, Debug)]
+pub enum KeyHierarchyError {
+    #[error("Invalid master seed length: {0} bytes")]
+    InvalidSeedLength(usize),
+    #[error("Unknown derivation version: {0}")]
+    UnknownVersion(u8),
+    #[error("Key derivation failed: {0}")]
+    DerivationFailed(String),
+}
+
+pub type Result<T> = std::result::Result<T, KeyHierarchyError>;
+
+/// Derivation path version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DerivationVersion {
+    /// SHA-256(seed || label), as used by KeyManager since v1.0
+    Legacy = 0,
+    /// HKDF-SHA256 with versioned info strings
+    V1 = 1,
+}
+
+impl DerivationVersion {
+    /// Parse a version from its wire/storage byte
+    pub fn from_u8(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(Self::Legacy),
+            1 => Ok(Self::V1),
+            other => Err(KeyHierarchyError::UnknownVersion(other)),
+        }
+    }
+}
+
+/// What a derived key is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyPurpose {
+    /// Ed25519 identity signing key
+    Signing,
+    /// X25519 encryption key (also seeds the Kyber keypair)
+    Encryption,
+    /// Ed25519 key for the messaging .onion address
+    HiddenService,
+    /// Ed25519 key for the friend request .onion address
+    FriendRequest,
+    /// Ed25519 key for the voice calling .onion address
+    VoiceService,
+}
+
+impl KeyPurpose {
+    /// Every purpose, in a stable order
+    pub const ALL: [KeyPurpose; 5] = [
+        KeyPurpose::Signing,
+        KeyPurpose::Encryption,
+        KeyPurpose::HiddenService,
+        KeyPurpose::FriendRequest,
+        KeyPurpose::VoiceService,
+    ];
+
+    /// KeyManager's SHA-256 label (signing has none; it uses the seed directly)
+    fn legacy_label(self) -> Option<&'static [u8]> {
+        match self {
+            KeyPurpose::Signing => None,
+            KeyPurpose::Encryption => Some(b"x25519"),
+            KeyPurpose::HiddenService => Some(b"tor_hs"),
+            KeyPurpose::FriendRequest => Some(b"friend_req"),
+            KeyPurpose::VoiceService => Some(b"tor_voice"),
+        }
+    }
+
+    /// HKDF info string for the V1 derivation path
+    fn v1_info(self) -> &'static [u8] {
+        match self {
+            KeyPurpose::Signing => b"SecureLegion-KeyHierarchy-v1/signing",
+            KeyPurpose::Encryption => b"SecureLegion-KeyHierarchy-v1/encryption",
+            KeyPurpose::HiddenService => b"SecureLegion-KeyHierarchy-v1/hidden_service",
+            KeyPurpose::FriendRequest => b"SecureLegion-KeyHierarchy-v1/friend_request",
+            KeyPurpose::VoiceService => b"SecureLegion-KeyHierarchy-v1/voice_service",
+        }
+    }
+}
+
+/// All purpose keys, derived on demand from one master seed
+pub struct KeyHierarchy {
+    master_seed: Zeroizing<Vec<u8>>,
+    version: DerivationVersion,
+}
+
+impl KeyHierarchy {
+    /// Create a hierarchy from a master seed
+    ///
+    /// # Arguments
+    /// * `master_seed` - The 64-byte BIP39 seed for `Legacy`; 32 to 64 bytes for `V1`
+    /// * `version` - Derivation path to use
+    pub fn new(master_seed: &[u8], version: DerivationVersion) -> Result<Self> {
+        let valid = match version {
+            DerivationVersion::Legacy => master_seed.len() == LEGACY_SEED_BYTES,
+            DerivationVersion::V1 => (MIN_MASTER_SEED_BYTES..=MAX_MASTER_SEED_BYTES).contains(&master_seed.len()),
+        };
+        if !valid {
+            return Err(KeyHierarchyError::InvalidSeedLength(master_seed.len()));
+        }
+
+        Ok(Self {
+            master_seed: Zeroizing::new(master_seed.to_vec()),
+            version,
+        })
+    }
+
+    /// Derivation path this hierarchy uses
+    pub fn version(&self) -> DerivationVersion {
+        self.version
+    }
+
+    /// Derive the 32-byte secret seed for one purpose
+    ///
+    /// # Arguments
+    /// * `purpose` - Which key to derive
+    ///
+    /// # Returns
+    /// The purpose seed, zeroized when dropped
+    pub fn purpose_seed(&self, purpose: KeyPurpose) -> Zeroizing<[u8; 32]> {
+        let mut out = Zeroizing::new([0u8; 32]);
+        match self.version {
+            DerivationVersion::Legacy => match purpose.legacy_label() {
+                None => out.copy_from_slice(&self.master_seed[..32]),
+                Some(label) => {
+                    let digest = Sha256::new()
+                        .chain_update(self.master_seed.as_slice())
+                        .chain_update(label)
+                        .finalize();
+                    out.copy_from_slice(&digest);
+                }
+            },
+            DerivationVersion::V1 => {
+                let hk = Hkdf::<Sha256>::new(Some(V1_SALT), &self.master_seed);
+                hk.expand(purpose.v1_info(), out.as_mut())
+                    .expect("32 bytes is a valid HKDF-SHA256 output length");
+            }
+        }
+        out
+    }
+
+    /// Ed25519 identity signing key
+    pub fn signing_key(&self) -> SigningKey {
+        self.ed25519(KeyPurpose::Signing)
+    }
+
+    /// X25519 encryption secret
+    pub fn encryption_secret(&self) -> StaticSecret {
+        StaticSecret::from(*self.purpose_seed(KeyPurpose::Encryption))
+    }
+
+    /// Hybrid X25519 + Kyber-1024 keypair, seeded from the encryption secret
+    pub fn hybrid_keypair(&self) -> Result<HybridKEMKeypair> {
+        generate_hybrid_keypair_from_seed(&self.purpose_seed(KeyPurpose::Encryption))
+            .map_err(|e| KeyHierarchyError::DerivationFailed(e.to_string()))
+    }
+
+    /// Ed25519 key for the messaging hidden service
+    pub fn hidden_service_key(&self) -> SigningKey {
+        self.ed25519(KeyPurpose::HiddenService)
+    }
+
+    /// Ed25519 key for the friend request hidden service
+    pub fn friend_request_key(&self) -> SigningKey {
+        self.ed25519(KeyPurpose::FriendRequest)
+    }
+
+    /// Ed25519 key for the voice calling hidden service
+    pub fn voice_service_key(&self) -> SigningKey {
+        self.ed25519(KeyPurpose::VoiceService)
+    }
+
+    fn ed25519(&self, purpose: KeyPurpose) -> SigningKey {
+        SigningKey::from_bytes(&self.purpose_seed(purpose))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use x25519_dalek::PublicKey;
+
+    #[test]
+    fn test_legacy_matches_key_manager() {
+        // KeyManager stores a 64-byte BIP39 seed and hashes all of it
+        let seed: Vec<u8> = (0u8..64).collect();
+        let hierarchy = KeyHierarchy::new(&seed, DerivationVersion::Legacy).unwrap();
+
+        let signing = SigningKey::from_bytes(&seed[..32].try_into().unwrap());
+        assert_eq!(hierarchy.signing_key().to_bytes(), signing.to_bytes());
+
+        let x25519: [u8; 32] = Sha256::new().chain_update(&seed).chain_update(b"x25519").finalize().into();
+        assert_eq!(hierarchy.encryption_secret().to_bytes(), x25519);
+
+        let tor_hs: [u8; 32] = Sha256::new().chain_update(&seed).chain_update(b"tor_hs").finalize().into();
+        assert_eq!(hierarchy.hidden_service_key().to_bytes(), tor_hs);
+
+        let hybrid = hierarchy.hybrid_keypair().unwrap();
+        assert_eq!(hybrid.x25519_public, PublicKey::from(&hierarchy.encryption_secret()).to_bytes());
+    }
+
+    #[test]
+    fn test_v1_is_deterministic_and_domain_separated() {
+        let seed = [7u8; 64];
+        let a = KeyHierarchy::new(&seed, DerivationVersion::V1).unwrap();
+        let b = KeyHierarchy::new(&seed, DerivationVersion::V1).unwrap();
+        let legacy = KeyHierarchy::new(&seed, DerivationVersion::Legacy).unwrap();
+
+        let mut seen = Vec::new();
+        for purpose in KeyPurpose::ALL {
+            let derived = a.purpose_seed(purpose);
+            assert_eq!(*derived, *b.purpose_seed(purpose));
+            assert_ne!(*derived, *legacy.purpose_seed(purpose));
+            assert!(!seen.contains(&*derived), "{:?} collides with another purpose", purpose);
+            seen.push(*derived);
+        }
+
+        assert_eq!(a.friend_request_key().to_bytes(), *a.purpose_seed(KeyPurpose::FriendRequest));
+        assert_eq!(a.voice_service_key().to_bytes(), *a.purpose_seed(KeyPurpose::VoiceService));
+    }
+
+    #[test]
+    fn test_rejects_invalid_inputs() {
+        assert!(matches!(
+            KeyHierarchy::new(&[0u8; 31], DerivationVersion::V1),
+            Err(KeyHierarchyError::InvalidSeedLength(31))
+        ));
+        assert!(matches!(
+            KeyHierarchy::new(&[0u8; 65], DerivationVersion::Legacy),
+            Err(KeyHierarchyError::InvalidSeedLength(65))
+        ));
+        // Legacy derives from KeyManager's 64-byte BIP39 seed only
+        assert!(matches!(
+            KeyHierarchy::new(&[0u8; 32], DerivationVersion::Legacy),
+            Err(KeyHierarchyError::InvalidSeedLength(32))
+        ));
+        assert!(KeyHierarchy::new(&[0u8; 32], DerivationVersion::V1).is_ok());
+        assert_eq!(DerivationVersion::from_u8(1).unwrap(), DerivationVersion::V1);
+        assert!(matches!(DerivationVersion::from_u8(9), Err(KeyHierarchyError::UnknownVersion(9))));
+    }
+}
@@ -5,19 +5,61 @@ pub mod hashing;
 pub mod pqc;
 pub mod replay_cache;
 pub mod ack_state;
+pub mod ratchet;
+pub mod skipped_keys;
+pub mod padding;
+pub mod pqxdh;
+pub mod fingerprint;
+pub mod ratchet_journal;
+pub mod session_reset;
+pub mod stream;
+pub mod backup;
+pub mod social_recovery;
+pub mod key_hierarchy;
+pub mod duress;
+pub mod canonical;
 
 pub use encryption::{
     encrypt_message,
     decrypt_message,
-    derive_root_key,
-    evolve_chain_key,
-    derive_message_key,
-    encrypt_message_with_evolution,
-    decrypt_message_with_evolution,
-    derive_receive_key_at_sequence,
+    encrypt_message_committing,
+    decrypt_message_committing,
+    decrypt_message_with_skipped_keys,
+    encrypt_message_with_evolution_v2,
+    decrypt_message_with_evolution_v2,
+    chain_message_epoch,
+    MessageBinding,
+    RootKey,
+    ChainKey,
+    MessageKey,
 };
-pub use signing::{sign_data, verify_signature, generate_keypair};
-pub use key_exchange::{derive_shared_secret, generate_ephemeral_key};
+#[allow(deprecated)]
+pub use encryption::{derive_root_key, encrypt_message_with_evolution, decrypt_message_with_evolution};
+#[allow(deprecated)]
+pub use encryption::{evolve_chain_key, derive_message_key, derive_receive_key_at_sequence};
+pub use ratchet::{
+    RatchetSession, RatchetHeader, RATCHET_WIRE_VERSION, RATCHET_PQ_WIRE_VERSION, RATCHET_HE_WIRE_VERSION,
+    CAPABILITY_PQ_RATCHET, CAPABILITY_HEADER_ENCRYPTION, LOCAL_CAPABILITIES, negotiate_capabilities,
+};
+pub use skipped_keys::SkippedKeyStore;
+pub use padding::PaddingScheme;
+pub use pqxdh::{PrekeyBundle, PrekeyStore, PqxdhInitMessage, PqxdhOutput};
+pub use fingerprint::{IdentityKeys, SafetyNumber, FINGERPRINT_VERSION};
+pub use session_reset::{SessionHealer, SessionResetMessage, SessionResetPhase};
+pub use stream::{
+    StreamDecryptor, StreamEncryptor, StreamOpener, StreamSealer, StreamHeader,
+    encrypt_stream_async, decrypt_stream_async,
+};
+pub use backup::{parse_seed_phrase, seed_phrase_to_seed, create_backup, restore_backup, BackupContents, IdentityKeyMaterial};
+pub use social_recovery::{split_secret, combine_shares, RecoveryMessage, RecoverySession};
+pub use key_hierarchy::{DerivationVersion, KeyHierarchy, KeyPurpose};
+pub use duress::{UnlockOutcome, UnlockRecord};
+pub use signing::{verify_signature, generate_keypair, SigningSecret};
+pub use key_exchange::{generate_ephemeral_key, X25519Secret};
+#[allow(deprecated)]
+pub use signing::sign_data;
+#[allow(deprecated)]
+pub use key_exchange::derive_shared_secret;
 pub use hashing::{hash_password, hash_handle};
 pub use pqc::{
     generate_hybrid_keypair_from_seed,
@@ -0,0 +1,158 @@
+//! Length-Hiding Padding
+//! Plaintext padding applied inside the AEAD so ciphertext sizes only reveal a size class
+//!
+//! Padding is ISO/IEC 7816-4 style: plaintext || 0x80 || 0x00.., so it can be stripped
+//! without knowing which scheme the sender used. The scheme only decides the target size:
+//! - `PowerOfTwo`: next power of two above a floor (few, coarse classes; up to 2x overhead)
+//! - `Padme`: Padmé (at most ~12% overhead, O(log log n) bits leaked)
+//! - `Block`: round up to a fixed block (minimal overhead, hides only small differences)
+
+use crate::protocol::security_mode::SecurityTier;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Smallest padded size for `SecurityTier::HighRisk` (covers most text messages)
+pub const HIGH_RISK_MIN_PADDED_LEN: usize = 512;
+
+/// Block size for `SecurityTier::Bulk`
+pub const BULK_BLOCK_LEN: usize = 256;
+
+/// Marker byte separating plaintext from zero padding
+const PADDING_MARKER: u8 = 0x80;
+
+#[derive(Error, Debug)]
+pub enum PaddingError {
+    #[error("Invalid padding")]
+    InvalidPadding,
+}
+
+pub type Result<T> = std::result::Result<T, PaddingError>;
+
+/// How far a plaintext is padded before encryption
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaddingScheme {
+    /// Next power of two, but never below `min_len`
+    PowerOfTwo { min_len: usize },
+    /// Padmé: keep the top bits of the length, zero the rest
+    Padme,
+    /// Next multiple of `block_len`
+    Block { block_len: usize },
+}
+
+impl PaddingScheme {
+    /// Scheme for a security tier: HighRisk pads aggressively, Bulk minimally
+    pub fn for_tier(tier: SecurityTier) -> Self {
+        match tier {
+            SecurityTier::HighRisk => PaddingScheme::PowerOfTwo { min_len: HIGH_RISK_MIN_PADDED_LEN },
+            SecurityTier::Normal => PaddingScheme::Padme,
+            SecurityTier::Bulk => PaddingScheme::Block { block_len: BULK_BLOCK_LEN },
+        }
+    }
+
+    /// Padded length for a plaintext of `plaintext_len` bytes (includes the marker byte)
+    pub fn padded_len(&self, plaintext_len: usize) -> usize {
+        let len = plaintext_len + 1;
+        match *self {
+            PaddingScheme::PowerOfTwo { min_len } => len.max(min_len).next_power_of_two(),
+            PaddingScheme::Padme => padme(len),
+            PaddingScheme::Block { block_len } => {
+                let block_len = block_len.max(1);
+                len.div_ceil(block_len) * block_len
+            }
+        }
+    }
+}
+
+impl Default for PaddingScheme {
+    fn default() -> Self {
+        PaddingScheme::for_tier(SecurityTier::default())
+    }
+}
+
+/// Pad a plaintext to its size class
+///
+/// # Returns
+/// plaintext || 0x80 || 0x00.. with length `scheme.padded_len(plaintext.len())`
+pub fn pad(plaintext: &[u8], scheme: PaddingScheme) -> Vec<u8> {
+    let padded_len = scheme.padded_len(plaintext.len());
+    let mut padded = Vec::with_capacity(padded_len);
+    padded.extend_from_slice(plaintext);
+    padded.push(PADDING_MARKER);
+    padded.resize(padded_len, 0x00);
+    padded
+}
+
+/// Strip padding added by `pad` (any scheme)
+pub fn unpad(padded: &[u8]) -> Result<Vec<u8>> {
+    let marker = padded
+        .iter()
+        .rposition(|&b| b != 0x00)
+        .ok_or(PaddingError::InvalidPadding)?;
+    if padded[marker] != PADDING_MARKER {
+        return Err(PaddingError::InvalidPadding);
+    }
+    Ok(padded[..marker].to_vec())
+}
+
+/// Padmé length: round `len` up so only the top ~log2(log2(len)) bits are significant
+fn padme(len: usize) -> usize {
+    if len < 2 {
+        return len;
+    }
+    let exponent = usize::BITS - 1 - len.leading_zeros(); // floor(log2 len)
+    let exponent_bits = u32::BITS - exponent.leading_zeros(); // floor(log2 exponent) + 1
+    let last_bits = exponent - exponent_bits;
+    let mask = (1usize << last_bits) - 1;
+    (len + mask) & !mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pad_unpad_roundtrip() {
+        for tier in [SecurityTier::HighRisk, SecurityTier::Normal, SecurityTier::Bulk] {
+            let scheme = PaddingScheme::for_tier(tier);
+            for len in [0usize, 1, 15, 511, 512, 4000, 70_000] {
+                let plaintext: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+                let padded = pad(&plaintext, scheme);
+                assert_eq!(padded.len(), scheme.padded_len(len));
+                assert_eq!(unpad(&padded).unwrap(), plaintext);
+            }
+        }
+    }
+
+    #[test]
+    fn test_size_classes_are_stable() {
+        let high_risk = PaddingScheme::for_tier(SecurityTier::HighRisk);
+        assert!((0..HIGH_RISK_MIN_PADDED_LEN).all(|len| high_risk.padded_len(len) == 512));
+        assert_eq!(high_risk.padded_len(600), 1024);
+        assert_eq!(high_risk.padded_len(1023), 1024);
+
+        let bulk = PaddingScheme::for_tier(SecurityTier::Bulk);
+        assert_eq!(bulk.padded_len(0), 256);
+        assert_eq!(bulk.padded_len(300), 512);
+        assert_eq!(bulk.padded_len(511), 512);
+
+        // Padmé: monotonic, bounded overhead, and far fewer classes than lengths
+        let normal = PaddingScheme::for_tier(SecurityTier::Normal);
+        let mut classes = std::collections::BTreeSet::new();
+        let mut previous = 0;
+        for len in 1_000..2_000 {
+            let padded = normal.padded_len(len);
+            assert!(padded >= previous);
+            assert!(padded as f64 <= (len + 1) as f64 * 1.12);
+            classes.insert(padded);
+            previous = padded;
+        }
+        assert!(classes.len() <= 32);
+    }
+
+    #[test]
+    fn test_unpad_rejects_bad_padding() {
+        assert!(unpad(&[]).is_err());
+        assert!(unpad(&[0x00, 0x00]).is_err());
+        assert!(unpad(&[0x41, 0x42]).is_err());
+    }
+}
@@ -0,0 +1,471 @@
+//! Hybrid Ed25519 + ML-DSA-65 Signatures
+//! Composite signatures for identity-bearing tokens
+//!
+//! A hybrid signature is valid only if BOTH the Ed25519 and the ML-DSA-65 signature
+//! verify, so it stays unforgeable as long as either scheme is unbroken.
+//!
+//! Keys come from the existing identity seed: the Ed25519 key is the first 32 bytes of
+//! the seed (as derived by KeyManager today), the ML-DSA-65 key is generated from
+//! HKDF-SHA256(seed, "SecureLegion-ML-DSA-65-v1").
+//!
+//! Tokens keep their Ed25519 `signature` field unchanged and carry the ML-DSA half as an
+//! optional trailer after their bincode body: [version:1][len:2 BE][ml_dsa_signature].
+//! Builds that predate hybrid signatures ignore the trailer (bincode allows trailing
+//! bytes), so hybrid tokens still verify there with Ed25519 alone. A verifier that knows
+//! the signer's ML-DSA key requires the trailer, which prevents stripping it.
+//!
+//! Both halves sign the token's canonical encoding (see `crypto::canonical`) for peers
+//! registered as canonical signers, and its legacy encoding for other peers while the
+//! migration window is open. Legacy signatures are never accepted from canonical signers.
+//!
+//! In the app, our keypair is installed from the identity seed after unlock and every
+//! Ping/Pong/DeliveryAck we create gets the trailer. Contacts publish their ML-DSA key in
+//! their contact card; once it is registered here, their tokens must carry a valid trailer.
+
+use super::ml_dsa::{
+    ml_dsa_keypair_from_seed, ml_dsa_sign, ml_dsa_verify, ML_DSA_65_PUBLIC_KEY_BYTES, ML_DSA_65_SIGNATURE_BYTES,
+};
+use crate::crypto::canonical::legacy_signing_accepted;
+use crate::crypto::signing::{verify_signature, SigningSecret};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use hkdf::Hkdf;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_big_array::BigArray;
+use once_cell::sync::Lazy;
+use sha2::Sha256;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use thiserror::Error;
+use zeroize::Zeroize;
+
+/// Token signature version: Ed25519 only (no trailer)
+pub const SIGNATURE_VERSION_ED25519: u8 = 0x01;
+
+/// Token signature version: Ed25519 + ML-DSA-65 trailer
+pub const SIGNATURE_VERSION_HYBRID: u8 = 0x02;
+
+/// HKDF info for deriving the ML-DSA key generation seed from the identity seed
+const ML_DSA_SEED_INFO: &[u8] = b"SecureLegion-ML-DSA-65-v1";
+
+#[derive(Error, Debug)]
+pub enum HybridSignatureError {
+    #[error("Seed must be at least 32 bytes")]
+    InvalidSeed,
+    #[error("Signing failed")]
+    SigningFailed,
+    #[error("Malformed PQ signature trailer")]
+    MalformedTrailer,
+    #[error("Unsupported signature version: {0}")]
+    UnsupportedVersion(u8),
+    #[error("Invalid ML-DSA public key")]
+    InvalidPublicKey,
+    #[error("ML-DSA key already registered for this identity")]
+    PublicKeyMismatch,
+}
+
+pub type Result<T> = std::result::Result<T, HybridSignatureError>;
+
+/// Ed25519 + ML-DSA-65 signing keypair
+#[derive(Clone, Zeroize)]
+#[zeroize(drop)]
+pub struct HybridSigningKeypair {
+    pub ed25519_secret: [u8; 32],
+    pub ed25519_public: [u8; 32],
+    /// ML-DSA-65 key generation seed (the expanded key is rebuilt per signature)
+    pub ml_dsa_secret: Vec<u8>,
+    pub ml_dsa_public: Vec<u8>,
+}
+
+/// Public half of a hybrid signing keypair, as published to contacts
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HybridSigningPublicKey {
+    pub ed25519: [u8; 32],
+    pub ml_dsa: Vec<u8>,
+}
+
+/// Composite signature (both halves required)
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HybridSignature {
+    #[serde(with = "BigArray")]
+    pub ed25519: [u8; 64],
+    pub ml_dsa: Vec<u8>,
+}
+
+/// Our hybrid keypair, installed from the identity seed while the app is unlocked
+static LOCAL_KEYPAIR: Lazy<Mutex<Option<HybridSigningKeypair>>> = Lazy::new(|| Mutex::new(None));
+
+/// Contacts' ML-DSA public keys, indexed by their Ed25519 identity key
+static PEER_ML_DSA_KEYS: Lazy<Mutex<HashMap<[u8; 32], Vec<u8>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Ed25519 identities of contacts that negotiated `CAPABILITY_CANONICAL_SIGNING`
+static PEER_CANONICAL_SIGNERS: Lazy<Mutex<HashSet<[u8; 32]>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// ML-DSA half of a token signature, carried as a trailer after the token body
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PqSignature {
+    /// Signature version flag (SIGNATURE_VERSION_HYBRID)
+    pub version: u8,
+    /// ML-DSA-65 signature
+    pub signature: Vec<u8>,
+}
+
+impl HybridSigningKeypair {
+    /// Derive both keys from the identity seed
+    ///
+    /// # Arguments
+    /// * `seed` - Identity seed (BIP39 seed); only the first 32 bytes feed Ed25519
+    pub fn from_seed(seed: &[u8]) -> Result<Self> {
+        if seed.len() < 32 {
+            return Err(HybridSignatureError::InvalidSeed);
+        }
+
+        let mut ed25519_secret = [0u8; 32];
+        ed25519_secret.copy_from_slice(&seed[..32]);
+        let ed25519_public = crate::crypto::signing::derive_public_key(&ed25519_secret)
+            .map_err(|_| HybridSignatureError::InvalidSeed)?;
+
+        let mut ml_dsa_seed = [0u8; 32];
+        Hkdf::<Sha256>::new(None, seed)
+            .expand(ML_DSA_SEED_INFO, &mut ml_dsa_seed)
+            .map_err(|_| HybridSignatureError::InvalidSeed)?;
+        let (ml_dsa_public, ml_dsa_secret) = ml_dsa_keypair_from_seed(&ml_dsa_seed);
+        ml_dsa_seed.zeroize();
+
+        Ok(Self { ed25519_secret, ed25519_public, ml_dsa_secret, ml_dsa_public })
+    }
+
+    /// Public keys to publish
+    pub fn public_key(&self) -> HybridSigningPublicKey {
+        HybridSigningPublicKey {
+            ed25519: self.ed25519_public,
+            ml_dsa: self.ml_dsa_public.clone(),
+        }
+    }
+
+    /// Ed25519 signing key (for the existing token constructors)
+    pub fn ed25519_signing_key(&self) -> ed25519_dalek::SigningKey {
+        ed25519_dalek::SigningKey::from_bytes(&self.ed25519_secret)
+    }
+
+    /// Produce only the ML-DSA half for a token
+    ///
+    /// # Arguments
+    /// * `message` - Token's signing bytes (same bytes the Ed25519 signature covers)
+    /// * `context` - Token type label, so one token's signature never verifies as another's
+    pub fn sign_pq(&self, message: &[u8], context: &[u8]) -> Result<PqSignature> {
+        let signature = ml_dsa_sign(&self.ml_dsa_secret, message, context)
+            .map_err(|_| HybridSignatureError::SigningFailed)?;
+        Ok(PqSignature { version: SIGNATURE_VERSION_HYBRID, signature })
+    }
+}
+
+/// Install our hybrid keypair (replaces any previous one)
+///
+/// # Returns
+/// Our ML-DSA public key, to publish in the contact card
+pub fn install_local_keypair(keypair: HybridSigningKeypair) -> Vec<u8> {
+    let ml_dsa_public = keypair.ml_dsa_public.clone();
+    *LOCAL_KEYPAIR.lock().unwrap() = Some(keypair);
+    ml_dsa_public
+}
+
+/// Drop our hybrid keypair (app locked); tokens are Ed25519-only until it is reinstalled
+pub fn clear_local_keypair() {
+    LOCAL_KEYPAIR.lock().unwrap().take();
+}
+
+/// Our ML-DSA public key, if the keypair is installed
+pub fn local_ml_dsa_public_key() -> Option<Vec<u8>> {
+    LOCAL_KEYPAIR.lock().unwrap().as_ref().map(|keypair| keypair.ml_dsa_public.clone())
+}
+
+/// Attach the ML-DSA half to a freshly signed token
+///
+/// No-op when no keypair is installed or the installed one belongs to a different
+/// Ed25519 identity than `signer`. `peer` is the recipient's Ed25519 identity.
+pub fn attach_local_pq_signature<T: HybridSignedToken>(token: &mut T, signer: &VerifyingKey, peer: &[u8; 32]) -> Result<()> {
+    match LOCAL_KEYPAIR.lock().unwrap().as_ref() {
+        Some(keypair) if keypair.ed25519_public == signer.to_bytes() => token.sign_pq(keypair, peer),
+        _ => Ok(()),
+    }
+}
+
+/// Register a contact's ML-DSA key (from their contact card)
+///
+/// A different key for an already registered identity is rejected.
+pub fn register_peer_ml_dsa_key(ed25519: [u8; 32], ml_dsa_public: Vec<u8>) -> Result<()> {
+    if ml_dsa_public.len() != ML_DSA_65_PUBLIC_KEY_BYTES {
+        return Err(HybridSignatureError::InvalidPublicKey);
+    }
+    let mut keys = PEER_ML_DSA_KEYS.lock().unwrap();
+    match keys.get(&ed25519) {
+        Some(existing) if *existing != ml_dsa_public => Err(HybridSignatureError::PublicKeyMismatch),
+        _ => {
+            keys.insert(ed25519, ml_dsa_public);
+            Ok(())
+        }
+    }
+}
+
+/// ML-DSA key registered for an Ed25519 identity
+pub fn peer_ml_dsa_key(ed25519: &[u8; 32]) -> Option<Vec<u8>> {
+    PEER_ML_DSA_KEYS.lock().unwrap().get(ed25519).cloned()
+}
+
+/// Register a contact that negotiated `CAPABILITY_CANONICAL_SIGNING`
+///
+/// From then on their tokens are signed canonically and their legacy signatures rejected.
+pub fn register_peer_canonical_signing(ed25519: [u8; 32]) {
+    PEER_CANONICAL_SIGNERS.lock().unwrap().insert(ed25519);
+}
+
+/// Whether an Ed25519 identity is registered as a canonical signer
+pub fn peer_signs_canonically(ed25519: &[u8; 32]) -> bool {
+    PEER_CANONICAL_SIGNERS.lock().unwrap().contains(ed25519)
+}
+
+/// Sign with both keys
+pub fn hybrid_sign(keypair: &HybridSigningKeypair, message: &[u8], context: &[u8]) -> Result<HybridSignature> {
+    let ed25519 = SigningSecret::from(keypair.ed25519_secret).sign(message);
+    let ml_dsa = keypair.sign_pq(message, context)?.signature;
+    Ok(HybridSignature { ed25519, ml_dsa })
+}
+
+/// Composite verify: true only if both signatures are valid
+pub fn hybrid_verify(
+    public_key: &HybridSigningPublicKey,
+    message: &[u8],
+    context: &[u8],
+    signature: &HybridSignature,
+) -> bool {
+    let ed25519_ok = matches!(verify_signature(message, &signature.ed25519, &public_key.ed25519), Ok(true));
+    let ml_dsa_ok = ml_dsa_verify(&public_key.ml_dsa, message, context, &signature.ml_dsa);
+    ed25519_ok & ml_dsa_ok
+}
+
+/// Check the PQ half of a token signature against the signer's policy
+///
+/// # Arguments
+/// * `pq_signature` - Trailer carried by the token, if any
+/// * `ml_dsa_public` - Signer's ML-DSA key if known; when Some, the trailer is mandatory
+/// * `message` / `context` - Same inputs used by `sign_pq`
+pub fn verify_pq_half(
+    pq_signature: Option<&PqSignature>,
+    ml_dsa_public: Option<&[u8]>,
+    message: &[u8],
+    context: &[u8],
+) -> bool {
+    match (ml_dsa_public, pq_signature) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(public_key), Some(pq)) => {
+            pq.version == SIGNATURE_VERSION_HYBRID && ml_dsa_verify(public_key, message, context, &pq.signature)
+        }
+    }
+}
+
+/// Append the PQ signature trailer (if any) to a token's bincode body
+pub fn append_pq_trailer(body: &mut Vec<u8>, pq_signature: Option<&PqSignature>) {
+    if let Some(pq) = pq_signature {
+        body.push(pq.version);
+        body.extend_from_slice(&(pq.signature.len() as u16).to_be_bytes());
+        body.extend_from_slice(&pq.signature);
+    }
+}
+
+/// Parse the bytes following a token's bincode body
+///
+/// # Returns
+/// None for Ed25519-only tokens (no trailer)
+pub fn parse_pq_trailer(trailer: &[u8]) -> Result<Option<PqSignature>> {
+    if trailer.is_empty() {
+        return Ok(None);
+    }
+    if trailer[0] != SIGNATURE_VERSION_HYBRID {
+        return Err(HybridSignatureError::UnsupportedVersion(trailer[0]));
+    }
+    if trailer.len() < 3 {
+        return Err(HybridSignatureError::MalformedTrailer);
+    }
+    let len = u16::from_be_bytes([trailer[1], trailer[2]]) as usize;
+    if len != ML_DSA_65_SIGNATURE_BYTES || trailer.len() != 3 + len {
+        return Err(HybridSignatureError::MalformedTrailer);
+    }
+    Ok(Some(PqSignature { version: trailer[0], signature: trailer[3..].to_vec() }))
+}
+
+/// Token that carries an Ed25519 signature plus an optional ML-DSA trailer
+pub trait HybridSignedToken {
+    /// ML-DSA context string, unique per token type
+    const PQ_CONTEXT: &'static [u8];
+
+    /// Bytes covered by both signatures
+    fn signing_bytes(&self) -> Vec<u8>;
+
+    /// Pre-canonical signing bytes, used with peers that do not sign canonically
+    fn legacy_signing_bytes(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Bytes to sign for a peer: canonical unless the peer is still on the legacy encoding
+    fn signing_bytes_for(&self, peer: &[u8; 32]) -> Vec<u8> {
+        if legacy_signing_accepted() && !peer_signs_canonically(peer) {
+            if let Some(legacy) = self.legacy_signing_bytes() {
+                return legacy;
+            }
+        }
+        self.signing_bytes()
+    }
+
+    fn pq_signature(&self) -> Option<&PqSignature>;
+
+    fn set_pq_signature(&mut self, pq_signature: Option<PqSignature>);
+
+    /// SIGNATURE_VERSION_HYBRID if the token carries an ML-DSA signature
+    fn signature_version(&self) -> u8 {
+        match self.pq_signature() {
+            Some(pq) => pq.version,
+            None => SIGNATURE_VERSION_ED25519,
+        }
+    }
+
+    /// Attach the ML-DSA half over the same bytes as the Ed25519 half (call after it is set)
+    fn sign_pq(&mut self, keypair: &HybridSigningKeypair, peer: &[u8; 32]) -> Result<()> {
+        let pq_signature = keypair.sign_pq(&self.signing_bytes_for(peer), Self::PQ_CONTEXT)?;
+        self.set_pq_signature(Some(pq_signature));
+        Ok(())
+    }
+
+    /// Verify the Ed25519 half over the canonical (or, for a legacy signer, legacy) encoding
+    fn verify_ed25519(&self, signer: &VerifyingKey, signature: &[u8; 64]) -> bool {
+        let signature = Signature::from_bytes(signature);
+        signer.verify(&self.signing_bytes(), &signature).is_ok()
+            || self.accepted_legacy_bytes(signer.as_bytes()).is_some_and(|legacy| signer.verify(&legacy, &signature).is_ok())
+    }
+
+    /// Verify the ML-DSA half; mandatory when the signer's ML-DSA key is known
+    fn verify_pq(&self, signer: &[u8; 32], ml_dsa_public: Option<&[u8]>) -> bool {
+        verify_pq_half(self.pq_signature(), ml_dsa_public, &self.signing_bytes(), Self::PQ_CONTEXT)
+            || self.accepted_legacy_bytes(signer).is_some_and(|legacy| {
+                verify_pq_half(self.pq_signature(), ml_dsa_public, &legacy, Self::PQ_CONTEXT)
+            })
+    }
+
+    /// Legacy signing bytes, if they are still accepted from this signer
+    fn accepted_legacy_bytes(&self, signer: &[u8; 32]) -> Option<Vec<u8>> {
+        if legacy_signing_accepted() && !peer_signs_canonically(signer) {
+            self.legacy_signing_bytes()
+        } else {
+            None
+        }
+    }
+}
+
+/// Implement `HybridSignedToken` for a token type
+///
+/// The token must have a `serialize_for_signing()` method (its canonical encoding) and a
+/// `pq_signature: Option<PqSignature>` field. `legacy(t => [...])` lists, in order, the
+/// parts the pre-canonical encoding concatenated; those bytes are signed for and accepted
+/// from peers that have not negotiated canonical signing (see `crypto::canonical`).
+macro_rules! impl_hybrid_signed_token {
+    ($token:ty, $context:expr, legacy($this:ident => [$($part:expr),+ $(,)?])) => {
+        impl $crate::crypto::pqc::hybrid_signature::HybridSignedToken for $token {
+            const PQ_CONTEXT: &'static [u8] = $context;
+
+            fn signing_bytes(&self) -> Vec<u8> {
+                self.serialize_for_signing()
+            }
+
+            fn legacy_signing_bytes(&self) -> Option<Vec<u8>> {
+                let $this = self;
+                let mut data = Vec::new();
+                $(data.extend_from_slice(&$part);)+
+                Some(data)
+            }
+
+            fn pq_signature(&self) -> Option<&$crate::crypto::pqc::hybrid_signature::PqSignature> {
+                self.pq_signature.as_ref()
+            }
+
+            fn set_pq_signature(&mut self, pq_signature: Option<$crate::crypto::pqc::hybrid_signature::PqSignature>) {
+                self.pq_signature = pq_signature;
+            }
+        }
+    };
+}
+pub(crate) use impl_hybrid_signed_token;
+
+/// bincode body followed by the PQ signature trailer
+pub fn serialize_with_pq_trailer<T: Serialize>(token: &T, pq_signature: Option<&PqSignature>) -> bincode::Result<Vec<u8>> {
+    let mut bytes = bincode::serialize(token)?;
+    append_pq_trailer(&mut bytes, pq_signature);
+    Ok(bytes)
+}
+
+/// Parse a bincode body and the PQ signature trailer that may follow it
+pub fn deserialize_with_pq_trailer<T: Serialize + DeserializeOwned>(
+    bytes: &[u8],
+) -> bincode::Result<(T, Option<PqSignature>)> {
+    let token: T = bincode::deserialize(bytes)?;
+    let body_len = bincode::serialized_size(&token)? as usize;
+    let pq_signature = parse_pq_trailer(bytes.get(body_len..).unwrap_or_default())
+        .map_err(|e| Box::new(bincode::ErrorKind::Custom(e.to_string())))?;
+    Ok((token, pq_signature))
+}
+
+impl HybridSigningPublicKey {
+    /// Whether the ML-DSA key has the expected size
+    pub fn is_well_formed(&self) -> bool {
+        self.ml_dsa.len() == ML_DSA_65_PUBLIC_KEY_BYTES
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_composite_verify_requires_both() {
+        let keypair = HybridSigningKeypair::from_seed(&[5u8; 64]).unwrap();
+        let public_key = keypair.public_key();
+        assert!(public_key.is_well_formed());
+
+        let signature = hybrid_sign(&keypair, b"token", b"Test").unwrap();
+        assert!(hybrid_verify(&public_key, b"token", b"Test", &signature));
+
+        let mut bad_ed = signature.clone();
+        bad_ed.ed25519[0] ^= 0x01;
+        assert!(!hybrid_verify(&public_key, b"token", b"Test", &bad_ed));
+
+        let mut bad_pq = signature.clone();
+        bad_pq.ml_dsa[0] ^= 0x01;
+        assert!(!hybrid_verify(&public_key, b"token", b"Test", &bad_pq));
+    }
+
+    #[test]
+    fn test_seed_derivation_keeps_existing_ed25519_key() {
+        let seed = [9u8; 64];
+        let keypair = HybridSigningKeypair::from_seed(&seed).unwrap();
+        let expected = ed25519_dalek::SigningKey::from_bytes(&[9u8; 32]).verifying_key().to_bytes();
+        assert_eq!(keypair.ed25519_public, expected);
+        assert_eq!(keypair.ml_dsa_public, HybridSigningKeypair::from_seed(&seed).unwrap().ml_dsa_public);
+    }
+
+    #[test]
+    fn test_trailer_roundtrip_and_downgrade() {
+        let keypair = HybridSigningKeypair::from_seed(&[1u8; 32]).unwrap();
+        let pq = keypair.sign_pq(b"body", b"Test").unwrap();
+
+        let mut bytes = b"body".to_vec();
+        append_pq_trailer(&mut bytes, Some(&pq));
+        let parsed = parse_pq_trailer(&bytes[4..]).unwrap();
+        assert_eq!(parsed.as_ref(), Some(&pq));
+        assert!(parse_pq_trailer(&bytes[4..bytes.len() - 1]).is_err());
+
+        let ml_dsa_public = keypair.ml_dsa_public.as_slice();
+        assert!(verify_pq_half(parsed.as_ref(), Some(ml_dsa_public), b"body", b"Test"));
+        // Stripped trailer is rejected once the signer's ML-DSA key is known
+        assert!(!verify_pq_half(None, Some(ml_dsa_public), b"body", b"Test"));
+        assert!(verify_pq_half(None, None, b"body", b"Test"));
+    }
+}
@@ -0,0 +1,152 @@
+//! ML-DSA-65 Signatures (NIST FIPS 204)
+//! Module-lattice signatures at security category 3
+//!
+//! Thin wrapper around the RustCrypto `ml-dsa` crate. Key generation is deterministic
+//! from a 32-byte seed ξ, and the secret key is kept in that seed form (the expanded key
+//! is rebuilt for each signature). Signing is the hedged variant (fresh `rnd` per
+//! signature) with a context string, i.e. ML-DSA.Sign / ML-DSA.Verify from FIPS 204 §5.2.
+//!
+//! Sizes: public key 1952 bytes, seed 32 bytes, signature 3309 bytes.
+
+use ml_dsa::common::getrandom::SysRng;
+use ml_dsa::{EncodedVerifyingKey, ExpandedSigningKey, MlDsa65, Seed, Signature, SigningKey, VerifyingKey};
+use thiserror::Error;
+use zeroize::Zeroize;
+
+pub const ML_DSA_65_SEED_BYTES: usize = 32;
+pub const ML_DSA_65_PUBLIC_KEY_BYTES: usize = 1952;
+pub const ML_DSA_65_SIGNATURE_BYTES: usize = 3309;
+
+/// Maximum context string length (FIPS 204 §5.2)
+pub const ML_DSA_MAX_CONTEXT_BYTES: usize = 255;
+
+#[derive(Error, Debug)]
+pub enum MlDsaError {
+    #[error("Invalid key length")]
+    InvalidKeyLength,
+    #[error("Context string too long")]
+    ContextTooLong,
+    #[error("Signing failed")]
+    SigningFailed,
+}
+
+pub type Result<T> = std::result::Result<T, MlDsaError>;
+
+/// Generate a key pair deterministically from a 32-byte seed (ML-DSA.KeyGen_internal)
+///
+/// # Returns
+/// (public_key, secret_seed) - the secret key is the seed itself
+pub fn ml_dsa_keypair_from_seed(seed: &[u8; ML_DSA_65_SEED_BYTES]) -> (Vec<u8>, Vec<u8>) {
+    let signing_key = SigningKey::<MlDsa65>::from_seed(&Seed::from(*seed));
+    let public_key = signing_key.as_ref().encode().to_vec();
+    (public_key, seed.to_vec())
+}
+
+/// Sign a message (ML-DSA.Sign, hedged)
+///
+/// # Arguments
+/// * `secret_seed` - 32-byte seed from `ml_dsa_keypair_from_seed`
+/// * `message` - Message to sign
+/// * `context` - Domain-separation context string (at most 255 bytes)
+pub fn ml_dsa_sign(secret_seed: &[u8], message: &[u8], context: &[u8]) -> Result<Vec<u8>> {
+    if secret_seed.len() != ML_DSA_65_SEED_BYTES {
+        return Err(MlDsaError::InvalidKeyLength);
+    }
+    if context.len() > ML_DSA_MAX_CONTEXT_BYTES {
+        return Err(MlDsaError::ContextTooLong);
+    }
+
+    let mut seed = Seed::try_from(secret_seed).map_err(|_| MlDsaError::InvalidKeyLength)?;
+    let signing_key = ExpandedSigningKey::<MlDsa65>::from_seed(&seed);
+    seed.zeroize();
+
+    let signature = signing_key
+        .sign_randomized(message, context, &mut SysRng)
+        .map_err(|_| MlDsaError::SigningFailed)?;
+    Ok(signature.encode().to_vec())
+}
+
+/// Verify a signature (ML-DSA.Verify)
+///
+/// # Returns
+/// True only if the signature is well-formed and valid for (public_key, message, context)
+pub fn ml_dsa_verify(public_key: &[u8], message: &[u8], context: &[u8], signature: &[u8]) -> bool {
+    let (Some(verifying_key), Ok(signature)) = (decode_public_key(public_key), Signature::<MlDsa65>::try_from(signature))
+    else {
+        return false;
+    };
+    verifying_key.verify_with_context(message, context, &signature)
+}
+
+fn decode_public_key(public_key: &[u8]) -> Option<VerifyingKey<MlDsa65>> {
+    let encoded = EncodedVerifyingKey::<MlDsa65>::try_from(public_key).ok()?;
+    Some(VerifyingKey::decode(&encoded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ml_dsa::B32;
+
+    /// NIST ACVP FIPS 204 vectors (ML-DSA-65 internal projection)
+    const ACVP_VECTORS: &str = include_str!("testdata/ml_dsa_65_acvp.json");
+
+    fn acvp() -> serde_json::Value {
+        serde_json::from_str(ACVP_VECTORS).unwrap()
+    }
+
+    fn hex_field(value: &serde_json::Value, field: &str) -> Vec<u8> {
+        hex::decode(value[field].as_str().unwrap()).unwrap()
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_acvp_keygen() {
+        for case in acvp()["keyGen"].as_array().unwrap() {
+            let seed: [u8; 32] = hex_field(case, "seed").try_into().unwrap();
+            let (public_key, secret_seed) = ml_dsa_keypair_from_seed(&seed);
+            assert_eq!(public_key, hex_field(case, "pk"), "tcId {}", case["tcId"]);
+
+            let expanded = ExpandedSigningKey::<MlDsa65>::from_seed(&Seed::try_from(secret_seed.as_slice()).unwrap());
+            assert_eq!(expanded.to_expanded().to_vec(), hex_field(case, "sk"), "tcId {}", case["tcId"]);
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_acvp_sign_and_verify() {
+        let vectors = acvp();
+        for case in vectors["sigGen"].as_array().unwrap() {
+            let sk = hex_field(case, "sk");
+            let signing_key = ExpandedSigningKey::<MlDsa65>::from_expanded(sk.as_slice().try_into().unwrap());
+            let rnd = B32::try_from(hex_field(case, "rnd").as_slice()).unwrap();
+            let signature = signing_key.sign_internal(&[&hex_field(case, "message")], &rnd);
+            assert_eq!(signature.encode().to_vec(), hex_field(case, "signature"), "tcId {}", case["tcId"]);
+        }
+
+        let sig_ver = &vectors["sigVer"];
+        let verifying_key = decode_public_key(&hex_field(sig_ver, "pk")).unwrap();
+        for case in sig_ver["tests"].as_array().unwrap() {
+            let valid = Signature::<MlDsa65>::try_from(hex_field(case, "signature").as_slice())
+                .is_ok_and(|signature| verifying_key.verify_internal(&hex_field(case, "message"), &signature));
+            assert_eq!(valid, case["testPassed"].as_bool().unwrap(), "tcId {} ({})", case["tcId"], case["reason"]);
+        }
+    }
+
+    #[test]
+    fn test_context_encoding_matches_fips204() {
+        let (public_key, secret_seed) = ml_dsa_keypair_from_seed(&[7u8; 32]);
+        let signature = ml_dsa_sign(&secret_seed, b"message", b"ctx").unwrap();
+        assert_eq!(signature.len(), ML_DSA_65_SIGNATURE_BYTES);
+        assert!(ml_dsa_verify(&public_key, b"message", b"ctx", &signature));
+        assert!(!ml_dsa_verify(&public_key, b"message", b"other", &signature));
+
+        // Pure ML-DSA signs M' = 0 || |ctx| || ctx || M (Algorithm 2)
+        let expanded = ExpandedSigningKey::<MlDsa65>::from_seed(&Seed::from([7u8; 32]));
+        let internal = expanded.sign_internal(&[&[0u8, 3], b"ctx", b"message"], &B32::default());
+        assert!(ml_dsa_verify(&public_key, b"message", b"ctx", &internal.encode()));
+
+        assert!(matches!(ml_dsa_sign(&secret_seed, b"m", &[0u8; 256]), Err(MlDsaError::ContextTooLong)));
+        assert!(!ml_dsa_verify(&public_key[1..], b"message", b"ctx", &signature));
+    }
+}
@@ -1,18 +1,28 @@
 /// Post-Quantum Cryptography Module
 /// Implements hybrid X25519 + ML-KEM-1024 key encapsulation
+/// and hybrid Ed25519 + ML-DSA-65 signatures
 ///
 /// Security: Protected against both classical and quantum attacks
-/// Standards: NIST FIPS 203 (ML-KEM) + RFC 7748 (X25519)
+/// Standards: NIST FIPS 203 (ML-KEM) + FIPS 204 (ML-DSA) + RFC 7748 (X25519) + RFC 8032 (Ed25519)
 
 pub mod hybrid_kem;
+pub mod hybrid_signature;
+pub mod ml_dsa;
+pub mod pq_ratchet;
 pub mod types;
 
 // Re-export main types and functions
 pub use hybrid_kem::{
     generate_hybrid_keypair_from_seed, hybrid_decapsulate, hybrid_encapsulate, HybridKEMError,
 };
+pub use hybrid_signature::{
+    hybrid_sign, hybrid_verify, HybridSignature, HybridSignatureError, HybridSignedToken, HybridSigningKeypair,
+    HybridSigningPublicKey, PqSignature, SIGNATURE_VERSION_ED25519, SIGNATURE_VERSION_HYBRID,
+};
+pub use ml_dsa::{ML_DSA_65_PUBLIC_KEY_BYTES, ML_DSA_65_SEED_BYTES, ML_DSA_65_SIGNATURE_BYTES};
+pub use pq_ratchet::{PqRatchetConfig, PqRatchetState};
 pub use types::{
-    HybridCiphertext, HybridKEMKeypair, HYBRID_SHARED_SECRET_BYTES, KYBER_CIPHERTEXT_BYTES,
+    HybridCiphertext, HybridKEMKeypair, HybridKEMPublicKey, HYBRID_SHARED_SECRET_BYTES, KYBER_CIPHERTEXT_BYTES,
     KYBER_PUBLIC_KEY_BYTES, KYBER_SECRET_KEY_BYTES, KYBER_SHARED_SECRET_BYTES,
     X25519_PUBLIC_KEY_BYTES, X25519_SECRET_KEY_BYTES,
 };
@@ -0,0 +1,475 @@
+//! Sparse Post-Quantum Ratchet
+//! Periodic hybrid X25519 + ML-KEM-1024 re-encapsulation mixed into the Double Ratchet root key
+//!
+//! Every `interval_messages` sent messages or `interval_secs` seconds, the next DH ratchet step
+//! that starts a new sending chain also encapsulates to the peer's current KEM public key.
+//! The 64-byte hybrid secret is mixed into the root key (through `derive_root_key`) right
+//! before the root KDF step for that chain, and the ciphertext is attached to every message of
+//! the chain, so the receiver can apply it from whichever message arrives first.
+//!
+//! After decapsulating, the receiver rotates its KEM key pair and advertises the new public key
+//! on its next sending chain. The previous KEM key pair is kept so a late encapsulation to it
+//! can still be opened.
+//!
+//! Runs inside `ratchet::RatchetSession`, which the app does not use yet (see `ratchet`).
+
+use super::hybrid_kem::{generate_hybrid_keypair_from_seed, hybrid_decapsulate, hybrid_encapsulate};
+use super::types::*;
+use crate::crypto::encryption::{EncryptionError, Result, RootKey};
+use rand::RngCore;
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Default number of sent messages between PQ ratchet steps
+pub const DEFAULT_PQ_INTERVAL_MESSAGES: u64 = 50;
+
+/// Default number of seconds between PQ ratchet steps (1 hour)
+pub const DEFAULT_PQ_INTERVAL_SECS: i64 = 3600;
+
+/// Length of the KEM key identifier carried with each encapsulation
+pub const PQ_KEY_ID_BYTES: usize = 8;
+
+const PQ_KEM_INFO: &[u8] = b"SecureLegion-PQRatchet-KEM-v1";
+const PQ_ROOT_INFO: &[u8] = b"SecureLegion-PQRatchet-Root-v1";
+
+const FLAG_KEM_PUBLIC: u8 = 0x01;
+const FLAG_CIPHERTEXT: u8 = 0x02;
+
+/// How often a PQ ratchet step is taken (whichever comes first)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PqRatchetConfig {
+    pub interval_messages: u64,
+    pub interval_secs: i64,
+}
+
+impl Default for PqRatchetConfig {
+    fn default() -> Self {
+        Self {
+            interval_messages: DEFAULT_PQ_INTERVAL_MESSAGES,
+            interval_secs: DEFAULT_PQ_INTERVAL_SECS,
+        }
+    }
+}
+
+/// Hybrid encapsulation to a specific KEM key of the peer
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PqCiphertext {
+    pub key_id: [u8; PQ_KEY_ID_BYTES],
+    pub ciphertext: HybridCiphertext,
+}
+
+/// PQ ratchet data attached to a Double Ratchet header
+///
+/// Format: [flags:1][kem_public:1600 if flag 0x01][key_id:8 + ciphertext:1600 if flag 0x02]
+#[derive(Clone, Default)]
+pub struct PqHeaderExtension {
+    pub kem_public: Option<HybridKEMPublicKey>,
+    pub ciphertext: Option<PqCiphertext>,
+}
+
+impl PqHeaderExtension {
+    /// Serialize extension
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut flags = 0u8;
+        let mut body = Vec::new();
+
+        if let Some(ref kem_public) = self.kem_public {
+            flags |= FLAG_KEM_PUBLIC;
+            body.extend_from_slice(&kem_public.to_bytes());
+        }
+        if let Some(ref ciphertext) = self.ciphertext {
+            flags |= FLAG_CIPHERTEXT;
+            body.extend_from_slice(&ciphertext.key_id);
+            body.extend_from_slice(&ciphertext.ciphertext.to_bytes());
+        }
+
+        let mut result = Vec::with_capacity(1 + body.len());
+        result.push(flags);
+        result.extend_from_slice(&body);
+        result
+    }
+
+    /// Parse extension from the start of `bytes`
+    ///
+    /// # Returns
+    /// (extension, number of bytes consumed)
+    pub fn from_bytes(bytes: &[u8]) -> Result<(Self, usize)> {
+        let flags = *bytes.first().ok_or(EncryptionError::DecryptionFailed)?;
+        if flags & !(FLAG_KEM_PUBLIC | FLAG_CIPHERTEXT) != 0 {
+            return Err(EncryptionError::DecryptionFailed);
+        }
+
+        let mut offset = 1;
+        let mut extension = Self::default();
+
+        if flags & FLAG_KEM_PUBLIC != 0 {
+            let end = offset + HybridKEMPublicKey::size();
+            let slice = bytes.get(offset..end).ok_or(EncryptionError::DecryptionFailed)?;
+            extension.kem_public = Some(
+                HybridKEMPublicKey::from_bytes(slice)
+                    .map_err(|_| EncryptionError::DecryptionFailed)?
+            );
+            offset = end;
+        }
+
+        if flags & FLAG_CIPHERTEXT != 0 {
+            let end = offset + PQ_KEY_ID_BYTES + HybridCiphertext::size();
+            let slice = bytes.get(offset..end).ok_or(EncryptionError::DecryptionFailed)?;
+            let mut key_id = [0u8; PQ_KEY_ID_BYTES];
+            key_id.copy_from_slice(&slice[..PQ_KEY_ID_BYTES]);
+            let ciphertext = HybridCiphertext::from_bytes(&slice[PQ_KEY_ID_BYTES..])
+                .map_err(|_| EncryptionError::DecryptionFailed)?;
+            extension.ciphertext = Some(PqCiphertext { key_id, ciphertext });
+            offset = end;
+        }
+
+        Ok((extension, offset))
+    }
+}
+
+/// Post-quantum ratchet state for one contact
+///
+/// Owned by a `RatchetSession`; both parties must enable it for the session to converge.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PqRatchetState {
+    config: PqRatchetConfig,
+    our_kem: HybridKEMKeypair,
+    our_previous_kem: Option<HybridKEMKeypair>,
+    their_kem_public: Option<HybridKEMPublicKey>,
+    /// Encapsulation attached to every message of the current sending chain
+    outgoing_ciphertext: Option<PqCiphertext>,
+    /// Whether the current sending chain advertises our KEM public key
+    advertise_kem_public: bool,
+    /// Our KEM key changed since the current sending chain started
+    kem_rotated: bool,
+    messages_since_step: u64,
+    last_step_at: i64,
+    epoch: u64,
+}
+
+impl PqRatchetState {
+    /// Create PQ ratchet state
+    ///
+    /// # Arguments
+    /// * `our_kem` - Our initial KEM key pair (e.g. from `generate_hybrid_keypair_from_seed`)
+    /// * `their_kem_public` - Peer's initial KEM public key, if already known from contact exchange
+    /// * `config` - Step interval
+    pub fn new(
+        our_kem: HybridKEMKeypair,
+        their_kem_public: Option<HybridKEMPublicKey>,
+        config: PqRatchetConfig,
+    ) -> Self {
+        Self {
+            config,
+            our_kem,
+            our_previous_kem: None,
+            their_kem_public,
+            outgoing_ciphertext: None,
+            advertise_kem_public: true,
+            kem_rotated: true,
+            messages_since_step: 0,
+            last_step_at: unix_now(),
+            epoch: 0,
+        }
+    }
+
+    /// Number of PQ secrets mixed into the root so far (sent or received)
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Count an outgoing message towards the step interval
+    pub fn record_message_sent(&mut self) {
+        self.messages_since_step += 1;
+    }
+
+    /// Extension to attach to outgoing messages of the current sending chain
+    pub fn outgoing_extension(&self) -> PqHeaderExtension {
+        PqHeaderExtension {
+            kem_public: if self.advertise_kem_public {
+                Some(self.our_kem.public_key())
+            } else {
+                None
+            },
+            ciphertext: self.outgoing_ciphertext.clone(),
+        }
+    }
+
+    fn step_due(&self, now: i64) -> bool {
+        self.messages_since_step >= self.config.interval_messages
+            || now - self.last_step_at >= self.config.interval_secs
+    }
+
+    /// Sender side: called when the session starts a new sending chain
+    ///
+    /// # Returns
+    /// Root key to use for the sending chain KDF step (mixed if a PQ step was taken)
+    pub fn begin_sending_chain(&mut self, root_key: &RootKey) -> Result<RootKey> {
+        self.advertise_kem_public = self.kem_rotated;
+        self.kem_rotated = false;
+        self.outgoing_ciphertext = None;
+
+        let now = unix_now();
+        let their_kem_public = match self.their_kem_public {
+            Some(ref public) if self.step_due(now) => public,
+            _ => return Ok(root_key.clone()),
+        };
+
+        let (mut hybrid_secret, ciphertext) = hybrid_encapsulate(
+            &their_kem_public.x25519_public,
+            &their_kem_public.kyber_public,
+        )
+        .map_err(|_| EncryptionError::EncryptionFailed)?;
+
+        let new_root_key = mix_into_root(root_key, &hybrid_secret);
+        hybrid_secret.zeroize();
+
+        self.outgoing_ciphertext = Some(PqCiphertext {
+            key_id: kem_key_id(their_kem_public),
+            ciphertext,
+        });
+        self.messages_since_step = 0;
+        self.last_step_at = now;
+        self.epoch += 1;
+
+        new_root_key
+    }
+
+    /// Receiver side: called before the root KDF step for a new receiving chain
+    ///
+    /// # Returns
+    /// Root key to use for the receiving chain KDF step (mixed if the chain carries a PQ step)
+    pub fn apply_incoming(
+        &mut self,
+        root_key: &RootKey,
+        extension: &PqHeaderExtension,
+    ) -> Result<RootKey> {
+        if let Some(ref kem_public) = extension.kem_public {
+            self.their_kem_public = Some(kem_public.clone());
+        }
+
+        let pq_ciphertext = match extension.ciphertext {
+            Some(ref ciphertext) => ciphertext,
+            None => return Ok(root_key.clone()),
+        };
+
+        let keypair = if pq_ciphertext.key_id == kem_key_id(&self.our_kem.public_key()) {
+            &self.our_kem
+        } else {
+            match self.our_previous_kem {
+                Some(ref previous) if pq_ciphertext.key_id == kem_key_id(&previous.public_key()) => previous,
+                _ => return Err(EncryptionError::DecryptionFailed),
+            }
+        };
+
+        let mut hybrid_secret = hybrid_decapsulate(
+            &keypair.x25519_secret,
+            &keypair.kyber_secret,
+            &pq_ciphertext.ciphertext,
+        )
+        .map_err(|_| EncryptionError::DecryptionFailed)?;
+
+        let new_root_key = mix_into_root(root_key, &hybrid_secret);
+        hybrid_secret.zeroize();
+
+        // Rotate our KEM key so the next encapsulation targets a fresh key
+        let rotated = generate_random_kem_keypair()?;
+        self.our_previous_kem = Some(std::mem::replace(&mut self.our_kem, rotated));
+        self.kem_rotated = true;
+        self.last_step_at = unix_now();
+        self.epoch += 1;
+
+        new_root_key
+    }
+
+    /// Serialize state for persistence
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self).map_err(|_| EncryptionError::InvalidSessionState)
+    }
+
+    /// Restore state from `to_bytes` output
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        bincode::deserialize(bytes).map_err(|_| EncryptionError::InvalidSessionState)
+    }
+}
+
+/// Mix a 64-byte hybrid KEM secret into a root key
+///
+/// Formula:
+///   pq_key = RootKey::derive(hybrid_secret, "SecureLegion-PQRatchet-KEM-v1")
+///   new_root = RootKey::derive(root_key || pq_key, "SecureLegion-PQRatchet-Root-v1")
+pub fn mix_into_root(
+    root_key: &RootKey,
+    hybrid_secret: &[u8; HYBRID_SHARED_SECRET_BYTES],
+) -> Result<RootKey> {
+    let pq_key = RootKey::derive(hybrid_secret, PQ_KEM_INFO)?;
+
+    let mut ikm = Zeroizing::new([0u8; 64]);
+    ikm[..32].copy_from_slice(root_key.expose_secret());
+    ikm[32..].copy_from_slice(pq_key.expose_secret());
+    RootKey::derive(&*ikm, PQ_ROOT_INFO)
+}
+
+/// Short identifier of a KEM public key: SHA-256(x25519 || kyber)[..8]
+pub fn kem_key_id(public: &HybridKEMPublicKey) -> [u8; PQ_KEY_ID_BYTES] {
+    let mut hasher = Sha256::new();
+    hasher.update(public.x25519_public);
+    hasher.update(public.kyber_public);
+    let hash = hasher.finalize();
+
+    let mut key_id = [0u8; PQ_KEY_ID_BYTES];
+    key_id.copy_from_slice(&hash[..PQ_KEY_ID_BYTES]);
+    key_id
+}
+
+/// Generate a fresh KEM key pair from a random seed
+pub fn generate_random_kem_keypair() -> Result<HybridKEMKeypair> {
+    let mut seed = [0u8; 32];
+    OsRng.fill_bytes(&mut seed);
+    let keypair = generate_hybrid_keypair_from_seed(&seed)
+        .map_err(|_| EncryptionError::EncryptionFailed);
+    seed.zeroize();
+    keypair
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::key_exchange::X25519Secret;
+    use crate::crypto::ratchet::RatchetSession;
+
+    fn every_chain() -> PqRatchetConfig {
+        PqRatchetConfig {
+            interval_messages: 1,
+            interval_secs: DEFAULT_PQ_INTERVAL_SECS,
+        }
+    }
+
+    fn pq_session_pair() -> (RatchetSession, RatchetSession) {
+        let root_key = RootKey::from([3u8; 32]);
+        let bob_secret = X25519Secret::generate();
+        let bob_public = bob_secret.public_key();
+        let alice_kem = generate_random_kem_keypair().unwrap();
+        let bob_kem = generate_random_kem_keypair().unwrap();
+
+        let alice_pq = PqRatchetState::new(alice_kem.clone(), Some(bob_kem.public_key()), every_chain());
+        let bob_pq = PqRatchetState::new(bob_kem, Some(alice_kem.public_key()), every_chain());
+
+        let alice = RatchetSession::new_initiator(&root_key, &bob_public, b"ad")
+            .unwrap()
+            .with_pq_ratchet(alice_pq);
+        let bob = RatchetSession::new_responder(&root_key, &bob_secret, b"ad")
+            .unwrap()
+            .with_pq_ratchet(bob_pq);
+        (alice, bob)
+    }
+
+    #[test]
+    fn test_mix_into_root() {
+        let root_key = RootKey::from([1u8; 32]);
+        let secret_a = [2u8; HYBRID_SHARED_SECRET_BYTES];
+        let secret_b = [3u8; HYBRID_SHARED_SECRET_BYTES];
+
+        let mixed_a = mix_into_root(&root_key, &secret_a).unwrap();
+        let mixed_b = mix_into_root(&root_key, &secret_b).unwrap();
+        assert_eq!(mixed_a.expose_secret(), mix_into_root(&root_key, &secret_a).unwrap().expose_secret());
+        assert_ne!(mixed_a.expose_secret(), root_key.expose_secret());
+        assert_ne!(mixed_a.expose_secret(), mixed_b.expose_secret());
+    }
+
+    #[test]
+    fn test_pq_ratchet_converges_out_of_order() {
+        let (mut alice, mut bob) = pq_session_pair();
+
+        for round in 0..4u8 {
+            let alice_msgs: Vec<Vec<u8>> = (0..3u8)
+                .map(|i| alice.encrypt(&[round, i]).unwrap())
+                .collect();
+            // Deliver in reverse: the PQ ciphertext must be usable from any message of the chain
+            for i in (0..3).rev() {
+                assert_eq!(bob.decrypt(&alice_msgs[i]).unwrap(), vec![round, i as u8]);
+            }
+
+            let bob_msgs: Vec<Vec<u8>> = (0..3u8)
+                .map(|i| bob.encrypt(&[round, 10 + i]).unwrap())
+                .collect();
+            for &i in &[2usize, 0, 1] {
+                assert_eq!(alice.decrypt(&bob_msgs[i]).unwrap(), vec![round, 10 + i as u8]);
+            }
+        }
+
+        // Several PQ secrets were mixed into both roots
+        assert!(alice.pq_epoch().unwrap() >= 4);
+        assert!(bob.pq_epoch().unwrap() >= 4);
+    }
+
+    #[test]
+    fn test_pq_secret_is_mixed_into_root() {
+        // Bob runs without the PQ ratchet, so he never mixes Alice's PQ secret in
+        let root_key = RootKey::from([3u8; 32]);
+        let bob_secret = X25519Secret::generate();
+        let bob_public = bob_secret.public_key();
+        let alice_kem = generate_random_kem_keypair().unwrap();
+        let bob_kem = generate_random_kem_keypair().unwrap();
+        let alice_pq = PqRatchetState::new(alice_kem, Some(bob_kem.public_key()), every_chain());
+
+        let mut alice = RatchetSession::new_initiator(&root_key, &bob_public, b"ad")
+            .unwrap()
+            .with_pq_ratchet(alice_pq);
+        let mut bob = RatchetSession::new_responder(&root_key, &bob_secret, b"ad").unwrap();
+
+        let m0 = alice.encrypt(b"first").unwrap();
+        assert_eq!(bob.decrypt(&m0).unwrap(), b"first");
+        let r0 = bob.encrypt(b"reply").unwrap();
+        assert_eq!(alice.decrypt(&r0).unwrap(), b"reply");
+
+        // Alice's next chain is PQ-stepped: without the PQ secret Bob cannot decrypt it
+        let m1 = alice.encrypt(b"pq step").unwrap();
+        assert!(bob.decrypt(&m1).is_err());
+    }
+
+    #[test]
+    fn test_pq_state_serialization_roundtrip() {
+        let ours = generate_random_kem_keypair().unwrap();
+        let theirs = generate_random_kem_keypair().unwrap();
+        let state = PqRatchetState::new(ours, Some(theirs.public_key()), PqRatchetConfig::default());
+
+        let bytes = state.to_bytes().unwrap();
+        let restored = PqRatchetState::from_bytes(&bytes).unwrap();
+
+        assert_eq!(restored.epoch(), state.epoch());
+        assert_eq!(restored.config, state.config);
+        assert!(restored.their_kem_public == state.their_kem_public);
+        assert_eq!(restored.our_kem.kyber_secret, state.our_kem.kyber_secret);
+    }
+
+    #[test]
+    fn test_header_extension_roundtrip() {
+        let kem = generate_random_kem_keypair().unwrap();
+        let (_, ciphertext) = hybrid_encapsulate(&kem.x25519_public, &kem.kyber_public).unwrap();
+        let extension = PqHeaderExtension {
+            kem_public: Some(kem.public_key()),
+            ciphertext: Some(PqCiphertext {
+                key_id: kem_key_id(&kem.public_key()),
+                ciphertext,
+            }),
+        };
+
+        let bytes = extension.to_bytes();
+        let (parsed, consumed) = PqHeaderExtension::from_bytes(&bytes).unwrap();
+
+        assert_eq!(consumed, bytes.len());
+        assert!(parsed.kem_public == extension.kem_public);
+        assert_eq!(parsed.to_bytes(), bytes);
+    }
+}
@@ -19,15 +19,25 @@ pub const X25519_SECRET_KEY_BYTES: usize = 32;
 pub const HYBRID_SHARED_SECRET_BYTES: usize = 64;
 
 /// Hybrid KEM keypair (X25519 + Kyber-1024)
-#[derive(Clone, Zeroize)]
+#[derive(Clone, Zeroize, Serialize, Deserialize)]
 #[zeroize(drop)]
 pub struct HybridKEMKeypair {
     pub x25519_public: [u8; X25519_PUBLIC_KEY_BYTES],
     pub x25519_secret: [u8; X25519_SECRET_KEY_BYTES],
+    #[serde(with = "BigArray")]
     pub kyber_public: [u8; KYBER_PUBLIC_KEY_BYTES],
+    #[serde(with = "BigArray")]
     pub kyber_secret: [u8; KYBER_SECRET_KEY_BYTES],
 }
 
+/// Hybrid KEM public key (X25519 + Kyber-1024), as advertised to peers
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HybridKEMPublicKey {
+    pub x25519_public: [u8; X25519_PUBLIC_KEY_BYTES],
+    #[serde(with = "BigArray")]
+    pub kyber_public: [u8; KYBER_PUBLIC_KEY_BYTES],
+}
+
 /// Hybrid KEM ciphertext (X25519 ephemeral + Kyber ciphertext)
 #[derive(Clone, Serialize, Deserialize)]
 pub struct HybridCiphertext {
@@ -86,6 +96,48 @@ impl HybridKEMKeypair {
             kyber_secret,
         })
     }
+
+    /// Public half of the keypair
+    pub fn public_key(&self) -> HybridKEMPublicKey {
+        HybridKEMPublicKey {
+            x25519_public: self.x25519_public,
+            kyber_public: self.kyber_public,
+        }
+    }
+}
+
+impl HybridKEMPublicKey {
+    /// Get total size of public key
+    pub fn size() -> usize {
+        X25519_PUBLIC_KEY_BYTES + KYBER_PUBLIC_KEY_BYTES
+    }
+
+    /// Serialize public key to bytes
+    /// Format: [x25519_pub][kyber_pub]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut result = Vec::with_capacity(Self::size());
+        result.extend_from_slice(&self.x25519_public);
+        result.extend_from_slice(&self.kyber_public);
+        result
+    }
+
+    /// Deserialize public key from bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+        if bytes.len() != Self::size() {
+            return Err("Invalid public key size");
+        }
+
+        let mut x25519_public = [0u8; X25519_PUBLIC_KEY_BYTES];
+        let mut kyber_public = [0u8; KYBER_PUBLIC_KEY_BYTES];
+
+        x25519_public.copy_from_slice(&bytes[0..X25519_PUBLIC_KEY_BYTES]);
+        kyber_public.copy_from_slice(&bytes[X25519_PUBLIC_KEY_BYTES..]);
+
+        Ok(Self {
+            x25519_public,
+            kyber_public,
+        })
+    }
 }
 
 impl HybridCiphertext {
@@ -0,0 +1,710 @@
+//! PQXDH-Style Asynchronous Session Setup
+//! Signed prekeys so the first message can be sent while the recipient is offline
+//!
+//! The responder publishes a `PrekeyBundle` through the friend-request .onion endpoint:
+//! - a signed X25519 prekey (SPK, rotated periodically)
+//! - an optional one-time X25519 prekey (OPK, used for at most one session)
+//! - a signed hybrid X25519 + ML-KEM-1024 prekey (PQSPK)
+//!
+//! The initiator (identity key IK_A, ephemeral key EK_A) verifies the bundle and computes
+//!   DH1 = DH(IK_A, SPK_B), DH2 = DH(EK_A, IK_B), DH3 = DH(EK_A, SPK_B), DH4 = DH(EK_A, OPK_B)
+//!   SS  = KEM encapsulation to PQSPK_B
+//!   root_key = HKDF(0xFF * 32 || DH1 || DH2 || DH3 [|| DH4] || SS)
+//! and sends a `PqxdhInitMessage` alongside its first message. The responder recomputes the
+//! root key and deletes the one-time prekey. The root key seeds `RatchetSession`, with the
+//! signed prekey as the responder's initial ratchet key.
+//!
+//! The signed prekey and the KEM prekey are rotated every `PREKEY_ROTATION_SECS`; the previous
+//! one of each is kept for one more period so init messages built from a cached bundle still
+//! complete.
+
+use super::encryption::RootKey;
+use super::key_exchange::X25519Secret;
+use super::pqc::pq_ratchet::generate_random_kem_keypair;
+use super::pqc::{hybrid_decapsulate, hybrid_encapsulate, HybridCiphertext, HybridKEMKeypair, HybridKEMPublicKey};
+use super::signing::{verify_signature, SigningSecret};
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use serde_big_array::BigArray;
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use thiserror::Error;
+use zeroize::{Zeroize, Zeroizing};
+
+/// Prekey bundle / init message format version
+pub const PREKEY_BUNDLE_VERSION: u8 = 0x01;
+
+/// Maximum age of the signed prekey and the KEM prekey before `rotate_if_due` replaces them
+pub const PREKEY_ROTATION_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// HKDF info for the initial root key
+const PQXDH_KDF_INFO: &[u8] = b"SecureLegion-PQXDH-v1";
+
+/// Signature domains (a prekey signature can never be replayed as another kind of signature)
+const SIGNED_PREKEY_DOMAIN: &[u8] = b"SecureLegion-SignedPrekey-v1";
+const KEM_PREKEY_DOMAIN: &[u8] = b"SecureLegion-KemPrekey-v1";
+
+#[derive(Error, Debug)]
+pub enum PqxdhError {
+    #[error("Invalid prekey signature")]
+    InvalidSignature,
+    #[error("Prekey bundle is signed by an unexpected identity key")]
+    UnexpectedIdentityKey,
+    #[error("Unsupported prekey format version: {0}")]
+    UnsupportedVersion(u8),
+    #[error("Unknown signed prekey: {0}")]
+    UnknownSignedPrekey(u32),
+    #[error("Unknown or already used one-time prekey: {0}")]
+    UnknownOneTimePrekey(u32),
+    #[error("Unknown KEM prekey: {0}")]
+    UnknownKemPrekey(u32),
+    #[error("Key generation failed")]
+    KeyGenerationFailed,
+    #[error("Key agreement failed")]
+    KeyAgreementFailed,
+    #[error("Serialization error: {0}")]
+    SerializationError(String),
+}
+
+pub type Result<T> = std::result::Result<T, PqxdhError>;
+
+/// Signed X25519 prekey
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SignedPrekey {
+    pub id: u32,
+    pub public_key: [u8; 32],
+    #[serde(with = "BigArray")]
+    pub signature: [u8; 64],
+}
+
+/// Signed hybrid X25519 + ML-KEM-1024 prekey
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SignedKemPrekey {
+    pub id: u32,
+    pub public_key: HybridKEMPublicKey,
+    #[serde(with = "BigArray")]
+    pub signature: [u8; 64],
+}
+
+/// One-time X25519 prekey (unsigned; authenticated by the session's DH terms)
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OneTimePrekey {
+    pub id: u32,
+    pub public_key: [u8; 32],
+}
+
+/// Public prekey bundle served at GET /prekey-bundle
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PrekeyBundle {
+    pub version: u8,
+    /// Ed25519 identity key that signs the prekeys
+    pub identity_signing_key: [u8; 32],
+    /// X25519 identity key (IK_B)
+    pub identity_dh_key: [u8; 32],
+    pub signed_prekey: SignedPrekey,
+    pub kem_prekey: SignedKemPrekey,
+    pub one_time_prekey: Option<OneTimePrekey>,
+}
+
+/// First-contact message sent by the initiator alongside its first ratchet message
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PqxdhInitMessage {
+    pub version: u8,
+    /// Initiator's X25519 identity key (IK_A)
+    pub identity_dh_key: [u8; 32],
+    /// Initiator's ephemeral X25519 key (EK_A)
+    pub ephemeral_key: [u8; 32],
+    pub signed_prekey_id: u32,
+    pub one_time_prekey_id: Option<u32>,
+    pub kem_prekey_id: u32,
+    pub kem_ciphertext: HybridCiphertext,
+}
+
+/// Result of the handshake, identical on both sides
+pub struct PqxdhOutput {
+    /// Initial root key for `RatchetSession`
+    pub root_key: RootKey,
+    /// IK_A || IK_B, to bind into every ratchet message
+    pub associated_data: Vec<u8>,
+}
+
+impl PrekeyBundle {
+    /// Verify that the bundle belongs to `expected_signing_key` and that both prekey
+    /// signatures are valid under it
+    ///
+    /// # Arguments
+    /// * `expected_signing_key` - The peer's pinned Ed25519 identity key (a bundle is otherwise
+    ///   only self-consistent, and anyone can sign their own prekeys)
+    pub fn verify(&self, expected_signing_key: &[u8; 32]) -> Result<()> {
+        if self.version != PREKEY_BUNDLE_VERSION {
+            return Err(PqxdhError::UnsupportedVersion(self.version));
+        }
+        if self.identity_signing_key != *expected_signing_key {
+            return Err(PqxdhError::UnexpectedIdentityKey);
+        }
+
+        let spk_message = signed_prekey_message(
+            &self.identity_dh_key,
+            self.signed_prekey.id,
+            &self.signed_prekey.public_key,
+        );
+        let kem_message = kem_prekey_message(&self.identity_dh_key, self.kem_prekey.id, &self.kem_prekey.public_key);
+
+        for (message, signature) in [
+            (spk_message, &self.signed_prekey.signature),
+            (kem_message, &self.kem_prekey.signature),
+        ] {
+            match verify_signature(&message, signature, &self.identity_signing_key) {
+                Ok(true) => {}
+                _ => return Err(PqxdhError::InvalidSignature),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Serialize for publishing
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self).map_err(|e| PqxdhError::SerializationError(e.to_string()))
+    }
+
+    /// Deserialize a fetched bundle (call `verify` with the peer's pinned key before use)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        bincode::deserialize(bytes).map_err(|e| PqxdhError::SerializationError(e.to_string()))
+    }
+}
+
+impl PqxdhInitMessage {
+    /// Serialize for transmission
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self).map_err(|e| PqxdhError::SerializationError(e.to_string()))
+    }
+
+    /// Deserialize a received init message
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        bincode::deserialize(bytes).map_err(|e| PqxdhError::SerializationError(e.to_string()))
+    }
+}
+
+/// Private half of the published prekeys (persisted by the responder)
+///
+/// Every secret is held in a type that wipes itself on drop.
+#[derive(Serialize, Deserialize)]
+pub struct PrekeyStore {
+    identity_signing_key: [u8; 32],
+    identity_dh_key: [u8; 32],
+    signed_prekey: SignedPrekey,
+    signed_prekey_secret: X25519Secret,
+    /// Previous signed prekey, kept after rotation for init messages still in flight
+    previous_signed_prekey: Option<(u32, X25519Secret)>,
+    /// Unix time the current signed prekey was generated
+    signed_prekey_created: u64,
+    kem_prekey: SignedKemPrekey,
+    kem_keypair: HybridKEMKeypair,
+    /// Previous KEM prekey, kept after rotation for init messages still in flight
+    previous_kem_keypair: Option<(u32, HybridKEMKeypair)>,
+    /// Unix time the current KEM prekey was generated
+    kem_prekey_created: u64,
+    /// Unused one-time prekeys: id -> X25519 secret
+    one_time_prekeys: BTreeMap<u32, X25519Secret>,
+    next_prekey_id: u32,
+}
+
+impl PrekeyStore {
+    /// Generate a signed prekey, a signed KEM prekey and `one_time_count` one-time prekeys
+    ///
+    /// # Arguments
+    /// * `identity_signing_private` - Our Ed25519 identity private key (signs the prekeys)
+    /// * `identity_dh_public` - Our X25519 identity public key (IK_B)
+    /// * `one_time_count` - Number of one-time prekeys to generate
+    /// * `now` - Current Unix time in seconds
+    pub fn generate(
+        identity_signing_private: &[u8; 32],
+        identity_dh_public: &[u8; 32],
+        one_time_count: usize,
+        now: u64,
+    ) -> Result<Self> {
+        let identity_signing_key = super::signing::derive_public_key(identity_signing_private)
+            .map_err(|_| PqxdhError::KeyGenerationFailed)?;
+
+        let (signed_prekey, signed_prekey_secret) =
+            generate_signed_prekey(1, identity_signing_private, identity_dh_public)?;
+        let (kem_prekey, kem_keypair) = generate_kem_prekey(2, identity_signing_private, identity_dh_public)?;
+
+        let mut store = Self {
+            identity_signing_key,
+            identity_dh_key: *identity_dh_public,
+            signed_prekey,
+            signed_prekey_secret,
+            previous_signed_prekey: None,
+            signed_prekey_created: now,
+            kem_prekey,
+            kem_keypair,
+            previous_kem_keypair: None,
+            kem_prekey_created: now,
+            one_time_prekeys: BTreeMap::new(),
+            next_prekey_id: 3,
+        };
+        store.generate_one_time_prekeys(one_time_count);
+
+        Ok(store)
+    }
+
+    /// Add `count` fresh one-time prekeys
+    ///
+    /// # Returns
+    /// Ids of the new prekeys
+    pub fn generate_one_time_prekeys(&mut self, count: usize) -> Vec<u32> {
+        (0..count)
+            .map(|_| {
+                let id = self.allocate_id();
+                self.one_time_prekeys.insert(id, X25519Secret::generate());
+                id
+            })
+            .collect()
+    }
+
+    /// Ids of one-time prekeys that have not been used yet
+    pub fn one_time_prekey_ids(&self) -> Vec<u32> {
+        self.one_time_prekeys.keys().copied().collect()
+    }
+
+    /// Top the one-time prekeys back up to `target`
+    ///
+    /// # Returns
+    /// Ids of the new prekeys
+    pub fn replenish_one_time_prekeys(&mut self, target: usize) -> Vec<u32> {
+        let missing = target.saturating_sub(self.one_time_prekeys.len());
+        self.generate_one_time_prekeys(missing)
+    }
+
+    /// Replace the signed prekey, keeping the previous one for in-flight init messages
+    pub fn rotate_signed_prekey(&mut self, identity_signing_private: &[u8; 32], now: u64) -> Result<()> {
+        let id = self.allocate_id();
+        let (signed_prekey, secret) = generate_signed_prekey(id, identity_signing_private, &self.identity_dh_key)?;
+
+        // Dropping the replaced previous secret zeroizes it
+        let current = std::mem::replace(&mut self.signed_prekey_secret, secret);
+        self.previous_signed_prekey = Some((self.signed_prekey.id, current));
+        self.signed_prekey = signed_prekey;
+        self.signed_prekey_created = now;
+        Ok(())
+    }
+
+    /// Replace the KEM prekey, keeping the previous one for in-flight init messages
+    pub fn rotate_kem_prekey(&mut self, identity_signing_private: &[u8; 32], now: u64) -> Result<()> {
+        let id = self.allocate_id();
+        let (kem_prekey, kem_keypair) = generate_kem_prekey(id, identity_signing_private, &self.identity_dh_key)?;
+
+        // Dropping the replaced keypair zeroizes it
+        let previous = std::mem::replace(&mut self.kem_keypair, kem_keypair);
+        self.previous_kem_keypair = Some((self.kem_prekey.id, previous));
+        self.kem_prekey = kem_prekey;
+        self.kem_prekey_created = now;
+        Ok(())
+    }
+
+    /// Rotate the signed prekey and the KEM prekey once they are `PREKEY_ROTATION_SECS` old
+    ///
+    /// # Returns
+    /// True if anything was rotated (the published bundles must be replaced)
+    pub fn rotate_if_due(&mut self, identity_signing_private: &[u8; 32], now: u64) -> Result<bool> {
+        let mut rotated = false;
+        if now.saturating_sub(self.signed_prekey_created) >= PREKEY_ROTATION_SECS {
+            self.rotate_signed_prekey(identity_signing_private, now)?;
+            rotated = true;
+        }
+        if now.saturating_sub(self.kem_prekey_created) >= PREKEY_ROTATION_SECS {
+            self.rotate_kem_prekey(identity_signing_private, now)?;
+            rotated = true;
+        }
+        Ok(rotated)
+    }
+
+    /// Bundles to publish: one per unused one-time prekey, plus the fallback bundle
+    ///
+    /// # Returns
+    /// (one_time_bundles, fallback_bundle)
+    pub fn publishable_bundles(&self) -> Result<(Vec<PrekeyBundle>, PrekeyBundle)> {
+        let one_time = self
+            .one_time_prekeys
+            .keys()
+            .map(|id| self.bundle(Some(*id)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok((one_time, self.bundle(None)?))
+    }
+
+    /// Build a public bundle
+    ///
+    /// # Arguments
+    /// * `one_time_prekey_id` - One-time prekey to include, or None for a fallback bundle
+    pub fn bundle(&self, one_time_prekey_id: Option<u32>) -> Result<PrekeyBundle> {
+        let one_time_prekey = match one_time_prekey_id {
+            Some(id) => {
+                let secret = self
+                    .one_time_prekeys
+                    .get(&id)
+                    .ok_or(PqxdhError::UnknownOneTimePrekey(id))?;
+                Some(OneTimePrekey { id, public_key: secret.public_key() })
+            }
+            None => None,
+        };
+
+        Ok(PrekeyBundle {
+            version: PREKEY_BUNDLE_VERSION,
+            identity_signing_key: self.identity_signing_key,
+            identity_dh_key: self.identity_dh_key,
+            signed_prekey: self.signed_prekey.clone(),
+            kem_prekey: self.kem_prekey.clone(),
+            one_time_prekey,
+        })
+    }
+
+    /// X25519 secret of a signed prekey (the responder's initial ratchet key)
+    pub fn signed_prekey_secret(&self, id: u32) -> Result<&X25519Secret> {
+        if id == self.signed_prekey.id {
+            return Ok(&self.signed_prekey_secret);
+        }
+        match self.previous_signed_prekey {
+            Some((previous_id, ref secret)) if previous_id == id => Ok(secret),
+            _ => Err(PqxdhError::UnknownSignedPrekey(id)),
+        }
+    }
+
+    /// Complete the handshake as the responder
+    ///
+    /// The one-time prekey named in the message is deleted, so replaying the same init
+    /// message fails.
+    ///
+    /// # Arguments
+    /// * `our_identity_dh_private` - Our X25519 identity private key
+    /// * `message` - Init message from the initiator
+    pub fn respond(&mut self, our_identity_dh_private: &X25519Secret, message: &PqxdhInitMessage) -> Result<PqxdhOutput> {
+        if message.version != PREKEY_BUNDLE_VERSION {
+            return Err(PqxdhError::UnsupportedVersion(message.version));
+        }
+        let kem_keypair = match &self.previous_kem_keypair {
+            _ if message.kem_prekey_id == self.kem_prekey.id => &self.kem_keypair,
+            Some((previous_id, keypair)) if *previous_id == message.kem_prekey_id => keypair,
+            _ => return Err(PqxdhError::UnknownKemPrekey(message.kem_prekey_id)),
+        };
+        let signed_prekey_secret = self.signed_prekey_secret(message.signed_prekey_id)?;
+        if let Some(id) = message.one_time_prekey_id {
+            if !self.one_time_prekeys.contains_key(&id) {
+                return Err(PqxdhError::UnknownOneTimePrekey(id));
+            }
+        }
+
+        let dh1 = dh(signed_prekey_secret, &message.identity_dh_key)?;
+        let dh2 = dh(our_identity_dh_private, &message.ephemeral_key)?;
+        let dh3 = dh(signed_prekey_secret, &message.ephemeral_key)?;
+        let dh4 = match message.one_time_prekey_id {
+            Some(id) => Some(dh(&self.one_time_prekeys[&id], &message.ephemeral_key)?),
+            None => None,
+        };
+        let mut kem_secret = hybrid_decapsulate(
+            &kem_keypair.x25519_secret,
+            &kem_keypair.kyber_secret,
+            &message.kem_ciphertext,
+        )
+        .map_err(|_| PqxdhError::KeyAgreementFailed)?;
+
+        let root_key = derive_pqxdh_root_key(&dh1, &dh2, &dh3, dh4.as_deref(), &kem_secret);
+        kem_secret.zeroize();
+        let root_key = root_key?;
+
+        // Dropping the one-time prekey zeroizes it
+        if let Some(id) = message.one_time_prekey_id {
+            self.one_time_prekeys.remove(&id);
+        }
+
+        Ok(PqxdhOutput {
+            root_key,
+            associated_data: associated_data(&message.identity_dh_key, &self.identity_dh_key),
+        })
+    }
+
+    /// Serialize for persistence (contains secrets; store encrypted)
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self).map_err(|e| PqxdhError::SerializationError(e.to_string()))
+    }
+
+    /// Deserialize a persisted store
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        bincode::deserialize(bytes).map_err(|e| PqxdhError::SerializationError(e.to_string()))
+    }
+
+    fn allocate_id(&mut self) -> u32 {
+        let id = self.next_prekey_id;
+        self.next_prekey_id = self.next_prekey_id.wrapping_add(1);
+        id
+    }
+}
+
+/// Start a session from a peer's prekey bundle
+///
+/// # Arguments
+/// * `our_identity_dh_private` - Our X25519 identity private key (IK_A)
+/// * `bundle` - Peer's prekey bundle (signatures are verified here)
+/// * `expected_signing_key` - Peer's pinned Ed25519 identity key the bundle must be signed by
+///
+/// # Returns
+/// (output, init_message) - Use `output.root_key` with `RatchetSession::new_initiator`
+/// and `bundle.signed_prekey.public_key` as the peer's initial ratchet key
+pub fn initiate(
+    our_identity_dh_private: &X25519Secret,
+    bundle: &PrekeyBundle,
+    expected_signing_key: &[u8; 32],
+) -> Result<(PqxdhOutput, PqxdhInitMessage)> {
+    bundle.verify(expected_signing_key)?;
+
+    let our_identity_dh_public = our_identity_dh_private.public_key();
+    let ephemeral_secret = X25519Secret::generate();
+    let ephemeral_public = ephemeral_secret.public_key();
+
+    let spk = &bundle.signed_prekey.public_key;
+    let dh1 = dh(our_identity_dh_private, spk)?;
+    let dh2 = dh(&ephemeral_secret, &bundle.identity_dh_key)?;
+    let dh3 = dh(&ephemeral_secret, spk)?;
+    let dh4 = match &bundle.one_time_prekey {
+        Some(opk) => Some(dh(&ephemeral_secret, &opk.public_key)?),
+        None => None,
+    };
+    drop(ephemeral_secret);
+
+    let kem_public = &bundle.kem_prekey.public_key;
+    let (mut kem_secret, kem_ciphertext) = hybrid_encapsulate(&kem_public.x25519_public, &kem_public.kyber_public)
+        .map_err(|_| PqxdhError::KeyAgreementFailed)?;
+
+    let root_key = derive_pqxdh_root_key(&dh1, &dh2, &dh3, dh4.as_deref(), &kem_secret);
+    kem_secret.zeroize();
+
+    let output = PqxdhOutput {
+        root_key: root_key?,
+        associated_data: associated_data(&our_identity_dh_public, &bundle.identity_dh_key),
+    };
+    let message = PqxdhInitMessage {
+        version: PREKEY_BUNDLE_VERSION,
+        identity_dh_key: our_identity_dh_public,
+        ephemeral_key: ephemeral_public,
+        signed_prekey_id: bundle.signed_prekey.id,
+        one_time_prekey_id: bundle.one_time_prekey.as_ref().map(|opk| opk.id),
+        kem_prekey_id: bundle.kem_prekey.id,
+        kem_ciphertext,
+    };
+
+    Ok((output, message))
+}
+
+fn generate_signed_prekey(
+    id: u32,
+    identity_signing_private: &[u8; 32],
+    identity_dh_public: &[u8; 32],
+) -> Result<(SignedPrekey, X25519Secret)> {
+    let secret = X25519Secret::generate();
+    let public_key = secret.public_key();
+    let signature = SigningSecret::from(*identity_signing_private)
+        .sign(&signed_prekey_message(identity_dh_public, id, &public_key));
+    Ok((SignedPrekey { id, public_key, signature }, secret))
+}
+
+fn generate_kem_prekey(
+    id: u32,
+    identity_signing_private: &[u8; 32],
+    identity_dh_public: &[u8; 32],
+) -> Result<(SignedKemPrekey, HybridKEMKeypair)> {
+    let keypair = generate_random_kem_keypair().map_err(|_| PqxdhError::KeyGenerationFailed)?;
+    let public_key = keypair.public_key();
+    let signature = SigningSecret::from(*identity_signing_private)
+        .sign(&kem_prekey_message(identity_dh_public, id, &public_key));
+    Ok((SignedKemPrekey { id, public_key, signature }, keypair))
+}
+
+/// domain || IK_B || id || SPK
+fn signed_prekey_message(identity_dh_key: &[u8; 32], id: u32, public_key: &[u8; 32]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(SIGNED_PREKEY_DOMAIN.len() + 32 + 4 + 32);
+    message.extend_from_slice(SIGNED_PREKEY_DOMAIN);
+    message.extend_from_slice(identity_dh_key);
+    message.extend_from_slice(&id.to_be_bytes());
+    message.extend_from_slice(public_key);
+    message
+}
+
+/// domain || IK_B || id || PQSPK (x25519 || kyber)
+fn kem_prekey_message(identity_dh_key: &[u8; 32], id: u32, public_key: &HybridKEMPublicKey) -> Vec<u8> {
+    let mut message = Vec::new();
+    message.extend_from_slice(KEM_PREKEY_DOMAIN);
+    message.extend_from_slice(identity_dh_key);
+    message.extend_from_slice(&id.to_be_bytes());
+    message.extend_from_slice(&public_key.x25519_public);
+    message.extend_from_slice(&public_key.kyber_public);
+    message
+}
+
+fn associated_data(initiator_identity: &[u8; 32], responder_identity: &[u8; 32]) -> Vec<u8> {
+    let mut ad = Vec::with_capacity(64);
+    ad.extend_from_slice(initiator_identity);
+    ad.extend_from_slice(responder_identity);
+    ad
+}
+
+fn dh(our_private: &X25519Secret, their_public: &[u8; 32]) -> Result<Zeroizing<[u8; 32]>> {
+    our_private
+        .diffie_hellman(their_public)
+        .map_err(|_| PqxdhError::KeyAgreementFailed)
+}
+
+fn derive_pqxdh_root_key(
+    dh1: &[u8; 32],
+    dh2: &[u8; 32],
+    dh3: &[u8; 32],
+    dh4: Option<&[u8; 32]>,
+    kem_secret: &[u8],
+) -> Result<RootKey> {
+    let mut ikm = Zeroizing::new(Vec::with_capacity(32 * 5 + kem_secret.len()));
+    ikm.extend_from_slice(&[0xFF; 32]);
+    ikm.extend_from_slice(dh1);
+    ikm.extend_from_slice(dh2);
+    ikm.extend_from_slice(dh3);
+    if let Some(dh4) = dh4 {
+        ikm.extend_from_slice(dh4);
+    }
+    ikm.extend_from_slice(kem_secret);
+
+    let hkdf = Hkdf::<Sha256>::new(Some(&[0u8; 32]), &ikm);
+    let mut root_key = Zeroizing::new([0u8; 32]);
+    hkdf.expand(PQXDH_KDF_INFO, &mut *root_key)
+        .map_err(|_| PqxdhError::KeyAgreementFailed)?;
+    RootKey::from_bytes(&*root_key).map_err(|_| PqxdhError::KeyAgreementFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::ratchet::RatchetSession;
+    use crate::crypto::signing::generate_keypair;
+
+    struct Identity {
+        signing_public: [u8; 32],
+        signing_private: [u8; 32],
+        dh_public: [u8; 32],
+        dh_private: X25519Secret,
+    }
+
+    fn identity() -> Identity {
+        let (signing_public, signing_private) = generate_keypair();
+        let dh_private = X25519Secret::generate();
+        Identity { signing_public, signing_private, dh_public: dh_private.public_key(), dh_private }
+    }
+
+    #[test]
+    fn test_handshake_seeds_matching_ratchets() {
+        let alice = identity();
+        let bob = identity();
+        let mut bob_store = PrekeyStore::generate(&bob.signing_private, &bob.dh_public, 2, 0).unwrap();
+        let opk_id = bob_store.one_time_prekey_ids()[0];
+        let bundle = PrekeyBundle::from_bytes(&bob_store.bundle(Some(opk_id)).unwrap().to_bytes().unwrap()).unwrap();
+
+        let (alice_out, init) = initiate(&alice.dh_private, &bundle, &bob.signing_public).unwrap();
+        let init = PqxdhInitMessage::from_bytes(&init.to_bytes().unwrap()).unwrap();
+        let bob_out = bob_store.respond(&bob.dh_private, &init).unwrap();
+
+        assert_eq!(alice_out.root_key.expose_secret(), bob_out.root_key.expose_secret());
+        assert_eq!(alice_out.associated_data, bob_out.associated_data);
+        assert!(!bob_store.one_time_prekey_ids().contains(&opk_id));
+
+        let mut alice_session = RatchetSession::new_initiator(
+            &alice_out.root_key,
+            &bundle.signed_prekey.public_key,
+            &alice_out.associated_data,
+        ).unwrap();
+        let spk_secret = bob_store.signed_prekey_secret(init.signed_prekey_id).unwrap();
+        let mut bob_session = RatchetSession::new_responder(&bob_out.root_key, spk_secret, &bob_out.associated_data).unwrap();
+
+        let frame = alice_session.encrypt(b"first contact").unwrap();
+        assert_eq!(bob_session.decrypt(&frame).unwrap(), b"first contact");
+    }
+
+    #[test]
+    fn test_one_time_prekey_is_single_use() {
+        let alice = identity();
+        let bob = identity();
+        let mut bob_store = PrekeyStore::generate(&bob.signing_private, &bob.dh_public, 1, 0).unwrap();
+        let bundle = bob_store.bundle(Some(bob_store.one_time_prekey_ids()[0])).unwrap();
+
+        let (_, init) = initiate(&alice.dh_private, &bundle, &bob.signing_public).unwrap();
+        assert!(bob_store.respond(&bob.dh_private, &init).is_ok());
+        assert!(matches!(
+            bob_store.respond(&bob.dh_private, &init),
+            Err(PqxdhError::UnknownOneTimePrekey(_))
+        ));
+
+        // Fallback bundle (no one-time prekey) still works, and survives a rotation
+        let fallback = bob_store.bundle(None).unwrap();
+        bob_store.rotate_signed_prekey(&bob.signing_private, 0).unwrap();
+        let (alice_out, init) = initiate(&alice.dh_private, &fallback, &bob.signing_public).unwrap();
+        assert_eq!(bob_store.respond(&bob.dh_private, &init).unwrap().root_key.expose_secret(), alice_out.root_key.expose_secret());
+    }
+
+    #[test]
+    fn test_kem_prekey_rotation() {
+        let alice = identity();
+        let bob = identity();
+        let mut bob_store = PrekeyStore::generate(&bob.signing_private, &bob.dh_public, 0, 1_000).unwrap();
+        let cached = bob_store.bundle(None).unwrap();
+
+        assert!(!bob_store.rotate_if_due(&bob.signing_private, 1_000 + PREKEY_ROTATION_SECS - 1).unwrap());
+        assert!(bob_store.rotate_if_due(&bob.signing_private, 1_000 + PREKEY_ROTATION_SECS).unwrap());
+        let (_, fresh) = bob_store.publishable_bundles().unwrap();
+        assert_ne!(fresh.kem_prekey.id, cached.kem_prekey.id);
+        assert_ne!(fresh.signed_prekey.id, cached.signed_prekey.id);
+        fresh.verify(&bob.signing_public).unwrap();
+
+        // A bundle cached before the rotation still completes for one period
+        let (alice_out, init) = initiate(&alice.dh_private, &cached, &bob.signing_public).unwrap();
+        assert_eq!(bob_store.respond(&bob.dh_private, &init).unwrap().root_key.expose_secret(), alice_out.root_key.expose_secret());
+
+        // ...but not after a second rotation
+        assert!(bob_store.rotate_if_due(&bob.signing_private, 1_000 + 2 * PREKEY_ROTATION_SECS).unwrap());
+        let (_, init) = initiate(&alice.dh_private, &cached, &bob.signing_public).unwrap();
+        assert!(matches!(
+            bob_store.respond(&bob.dh_private, &init),
+            Err(PqxdhError::UnknownKemPrekey(_))
+        ));
+    }
+
+    #[test]
+    fn test_forged_bundle_rejected() {
+        let alice = identity();
+        let bob = identity();
+        let mallory = identity();
+        let bob_store = PrekeyStore::generate(&bob.signing_private, &bob.dh_public, 0, 0).unwrap();
+        let mallory_store = PrekeyStore::generate(&mallory.signing_private, &mallory.dh_public, 0, 0).unwrap();
+
+        // Mallory swaps in her signed prekey under Bob's identity
+        let mut forged = bob_store.bundle(None).unwrap();
+        forged.signed_prekey = mallory_store.bundle(None).unwrap().signed_prekey;
+        assert!(matches!(initiate(&alice.dh_private, &forged, &bob.signing_public), Err(PqxdhError::InvalidSignature)));
+
+        let mut forged = bob_store.bundle(None).unwrap();
+        forged.kem_prekey.public_key.kyber_public[0] ^= 0x01;
+        assert!(matches!(initiate(&alice.dh_private, &forged, &bob.signing_public), Err(PqxdhError::InvalidSignature)));
+    }
+
+    #[test]
+    fn test_self_signed_bundle_for_pinned_identity_rejected() {
+        let alice = identity();
+        let bob = identity();
+        let mallory = identity();
+        let mallory_store = PrekeyStore::generate(&mallory.signing_private, &bob.dh_public, 0, 0).unwrap();
+
+        // Mallory serves a correctly self-signed bundle claiming Bob's X25519 identity key
+        let forged = mallory_store.bundle(None).unwrap();
+        assert!(forged.verify(&mallory.signing_public).is_ok());
+        assert!(matches!(forged.verify(&bob.signing_public), Err(PqxdhError::UnexpectedIdentityKey)));
+        assert!(matches!(
+            initiate(&alice.dh_private, &forged, &bob.signing_public),
+            Err(PqxdhError::UnexpectedIdentityKey)
+        ));
+    }
+}
@@ -0,0 +1,938 @@
+//! Double Ratchet Session
+//! X25519 DH ratchet on top of the HMAC symmetric chain from `encryption`
+//!
+//! Every turn of the conversation carries a fresh X25519 ratchet public key in the
+//! message header. When a new ratchet key is seen, both parties mix a new DH output
+//! into the root key, so a leaked chain key only exposes messages until the next turn
+//! (post-compromise security). Message keys inside a chain still come from
+//! `ChainKey::message_key`/`ChainKey::advance`. Root, chain and ratchet keys are held in
+//! their zeroizing types and never copied out as bare arrays.
+//!
+//! Wire format: [version:1][ratchet_pub:32][prev_chain_len:8][sequence:8][nonce:24][ciphertext][tag:16]
+//! PQ format:   [version:1][ratchet_pub:32][prev_chain_len:8][sequence:8][pq_extension][nonce:24][ciphertext][tag:16]
+//! HE format:   [version:1][header_len:2][header_nonce:24][encrypted_header][nonce:24][ciphertext][tag:16]
+//! The header (everything before the nonce) is authenticated as AEAD associated data.
+//! Plaintexts are padded to their size class (see `padding`) before encryption.
+//!
+//! With header encryption the plain header above is sealed under a header key, so the
+//! ratchet key and sequence numbers are hidden from relays. Header keys form their own
+//! chain: each root KDF step derives the header key for the next chain, and the receiver
+//! trial-decrypts with its current and next header keys (Signal's header-encrypted ratchet).
+//!
+//! Not yet used by the app: messages still go over the linear chain in `encryption`, so
+//! 0x10-0x12 frames are never produced and this build does not advertise
+//! `CAPABILITY_PQ_RATCHET` or `CAPABILITY_HEADER_ENCRYPTION`. Wiring sessions through JNI
+//! and the message path is out of scope for now.
+
+use super::encryption::{ChainKey, EncryptionError, MessageKey, Result, RootKey};
+use super::key_exchange::X25519Secret;
+use super::padding::{pad, unpad, PaddingScheme};
+use super::pqc::pq_ratchet::{PqHeaderExtension, PqRatchetState};
+use super::skipped_keys::SkippedKeyStore;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, OsRng, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use hkdf::Hkdf;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::VecDeque;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// Wire version byte for Double Ratchet frames
+/// Kept apart from the linear-chain versions (0x01, ...) so receivers can route by first byte
+pub const RATCHET_WIRE_VERSION: u8 = 0x10;
+
+/// Wire version byte for Double Ratchet frames carrying a PQ ratchet extension
+pub const RATCHET_PQ_WIRE_VERSION: u8 = 0x11;
+
+/// Wire version byte for Double Ratchet frames with an encrypted header
+pub const RATCHET_HE_WIRE_VERSION: u8 = 0x12;
+
+/// Capability flag: peer supports the sparse post-quantum ratchet
+pub const CAPABILITY_PQ_RATCHET: u8 = 0x01;
+
+/// Capability flag: peer can decrypt header-encrypted frames
+pub const CAPABILITY_HEADER_ENCRYPTION: u8 = 0x02;
+
+/// Capability flag: peer decrypts padded v2 linear chain messages (`encryption::WIRE_VERSION_V2`)
+pub const CAPABILITY_CHAIN_V2: u8 = 0x04;
+
+/// Capability flag: peer handles SESSION_RESET and epoch-tagged chain messages (`session_reset`)
+pub const CAPABILITY_SESSION_RESET: u8 = 0x08;
+
+/// Capability flag: peer decrypts key-committing v3 linear chain messages (`encryption::WIRE_VERSION_V3`)
+pub const CAPABILITY_KEY_COMMITTING: u8 = 0x10;
+
+/// Capability flag: peer signs and verifies tokens over their canonical encoding (`canonical`)
+pub const CAPABILITY_CANONICAL_SIGNING: u8 = 0x20;
+
+/// Capabilities this build supports (advertised to peers)
+///
+/// Ratchet sessions are not wired into the app yet, so their flags are not advertised.
+pub const LOCAL_CAPABILITIES: u8 = CAPABILITY_CHAIN_V2
+    | CAPABILITY_SESSION_RESET
+    | CAPABILITY_KEY_COMMITTING
+    | CAPABILITY_CANONICAL_SIGNING;
+
+/// Base header length: version(1) + ratchet_pub(32) + prev_chain_len(8) + sequence(8)
+pub const RATCHET_HEADER_LEN: usize = 1 + 32 + 8 + 8;
+
+/// Maximum number of message keys that may be skipped within a single receiving chain
+const MAX_SKIP: u64 = 1000;
+
+/// HKDF info for root key ratchet steps
+const ROOT_KDF_INFO: &[u8] = b"SecureLegion-Ratchet-RootKDF-v1";
+
+/// HKDF info for the header key chain
+const HEADER_KDF_INFO: &[u8] = b"SecureLegion-Ratchet-HeaderKDF-v1";
+
+/// HKDF info for the initial header keys (initiator -> responder, responder -> initiator)
+const INITIAL_HEADER_KEY_INFO_A: &[u8] = b"SecureLegion-Ratchet-HeaderKey-A-v1";
+const INITIAL_HEADER_KEY_INFO_B: &[u8] = b"SecureLegion-Ratchet-HeaderKey-B-v1";
+
+/// Old receiving header keys kept for late messages from earlier chains
+const MAX_PREVIOUS_HEADER_KEYS: usize = 8;
+
+/// Encrypted-header prefix: version(1) + header_len(2) + header_nonce(24)
+const HE_PREFIX_LEN: usize = 1 + 2 + 24;
+
+/// Capabilities both sides support
+///
+/// `LOCAL_CAPABILITIES` is advertised in the signed contact card
+/// (`ContactCard::capabilities`) and stored per contact; enable optional features
+/// (e.g. `with_header_encryption`) only when the negotiated flags include them.
+/// Cards from older builds carry no capabilities and negotiate to 0.
+pub fn negotiate_capabilities(ours: u8, theirs: u8) -> u8 {
+    ours & theirs
+}
+
+/// Parsed Double Ratchet message header
+#[derive(Clone)]
+pub struct RatchetHeader {
+    pub ratchet_public: [u8; 32],
+    pub previous_chain_length: u64,
+    pub sequence: u64,
+    pub pq_extension: Option<PqHeaderExtension>,
+}
+
+impl RatchetHeader {
+    /// Serialize header: [version][ratchet_pub][prev_chain_len][sequence][pq_extension?]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(RATCHET_HEADER_LEN);
+        bytes.push(if self.pq_extension.is_some() {
+            RATCHET_PQ_WIRE_VERSION
+        } else {
+            RATCHET_WIRE_VERSION
+        });
+        bytes.extend_from_slice(&self.ratchet_public);
+        bytes.extend_from_slice(&self.previous_chain_length.to_be_bytes());
+        bytes.extend_from_slice(&self.sequence.to_be_bytes());
+        if let Some(ref extension) = self.pq_extension {
+            bytes.extend_from_slice(&extension.to_bytes());
+        }
+        bytes
+    }
+
+    /// Parse header from the start of a Double Ratchet frame
+    ///
+    /// # Returns
+    /// (header, header length in bytes)
+    pub fn from_bytes(bytes: &[u8]) -> Result<(Self, usize)> {
+        if bytes.len() < RATCHET_HEADER_LEN {
+            return Err(EncryptionError::DecryptionFailed);
+        }
+        if bytes[0] != RATCHET_WIRE_VERSION && bytes[0] != RATCHET_PQ_WIRE_VERSION {
+            return Err(EncryptionError::DecryptionFailed);
+        }
+
+        let mut ratchet_public = [0u8; 32];
+        ratchet_public.copy_from_slice(&bytes[1..33]);
+
+        let previous_chain_length = u64::from_be_bytes(
+            bytes[33..41].try_into()
+                .map_err(|_| EncryptionError::DecryptionFailed)?
+        );
+        let sequence = u64::from_be_bytes(
+            bytes[41..49].try_into()
+                .map_err(|_| EncryptionError::DecryptionFailed)?
+        );
+
+        let (pq_extension, header_len) = if bytes[0] == RATCHET_PQ_WIRE_VERSION {
+            let (extension, extension_len) = PqHeaderExtension::from_bytes(&bytes[RATCHET_HEADER_LEN..])?;
+            (Some(extension), RATCHET_HEADER_LEN + extension_len)
+        } else {
+            (None, RATCHET_HEADER_LEN)
+        };
+
+        Ok((
+            Self {
+                ratchet_public,
+                previous_chain_length,
+                sequence,
+                pq_extension,
+            },
+            header_len,
+        ))
+    }
+}
+
+/// Header encryption key for one chain, wiped on drop
+///
+/// Serializes as its raw 32 bytes, so persisted session layouts are unchanged.
+#[derive(Clone, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+struct HeaderKey([u8; 32]);
+
+impl std::fmt::Debug for HeaderKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("HeaderKey([REDACTED])")
+    }
+}
+
+/// Header key chain for header encryption
+///
+/// Tracked for every session so it stays in step with the root key; headers are only
+/// encrypted on send when header encryption was negotiated.
+#[derive(Clone, Serialize, Deserialize)]
+struct HeaderKeys {
+    send: Option<HeaderKey>,
+    next_send: HeaderKey,
+    recv: Option<HeaderKey>,
+    next_recv: HeaderKey,
+    previous_recv: VecDeque<HeaderKey>,
+}
+
+/// Double Ratchet session state for one contact
+///
+/// Owns the root key, both symmetric chains and the current DH ratchet key pair.
+/// All mutating operations are atomic: on error the session is left unchanged.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RatchetSession {
+    root_key: RootKey,
+    dh_self_secret: X25519Secret,
+    dh_self_public: [u8; 32],
+    dh_remote: Option<[u8; 32]>,
+    send_chain_key: Option<ChainKey>,
+    recv_chain_key: Option<ChainKey>,
+    send_sequence: u64,
+    recv_sequence: u64,
+    previous_send_count: u64,
+    /// Message keys for messages not yet received, keyed by (ratchet_pub, sequence)
+    skipped_keys: SkippedKeyStore,
+    /// Extra data bound into every message (e.g. both parties' identity keys)
+    associated_data: Vec<u8>,
+    /// Optional sparse post-quantum ratchet
+    pq: Option<PqRatchetState>,
+    header_keys: HeaderKeys,
+    /// Encrypt outgoing headers (negotiated via CAPABILITY_HEADER_ENCRYPTION)
+    header_encryption: bool,
+    /// Padding applied to outgoing plaintexts
+    padding: PaddingScheme,
+}
+
+impl RatchetSession {
+    /// Start a session as the initiator (first party to send)
+    ///
+    /// # Arguments
+    /// * `root_key` - Root key from the key agreement (e.g. `PqxdhOutput::root_key`)
+    /// * `their_ratchet_public` - Responder's initial ratchet public key (their X25519 public key)
+    /// * `associated_data` - Data bound into every message (e.g. both identity keys)
+    pub fn new_initiator(
+        root_key: &RootKey,
+        their_ratchet_public: &[u8; 32],
+        associated_data: &[u8],
+    ) -> Result<Self> {
+        let dh_self_secret = X25519Secret::generate();
+        let dh_self_public = dh_self_secret.public_key();
+        let dh_output = x25519(&dh_self_secret, their_ratchet_public)?;
+        let (header_key_a, header_key_b) = initial_header_keys(root_key)?;
+        let (root_key, send_chain_key) = kdf_root(root_key, &dh_output)?;
+        let header_keys = HeaderKeys {
+            send: Some(header_key_a),
+            next_send: kdf_header(&root_key)?,
+            recv: None,
+            next_recv: header_key_b,
+            previous_recv: VecDeque::new(),
+        };
+
+        Ok(Self {
+            root_key,
+            dh_self_secret,
+            dh_self_public,
+            dh_remote: Some(*their_ratchet_public),
+            send_chain_key: Some(send_chain_key),
+            recv_chain_key: None,
+            send_sequence: 0,
+            recv_sequence: 0,
+            previous_send_count: 0,
+            skipped_keys: SkippedKeyStore::default(),
+            associated_data: associated_data.to_vec(),
+            pq: None,
+            header_keys,
+            header_encryption: false,
+            padding: PaddingScheme::default(),
+        })
+    }
+
+    /// Start a session as the responder
+    ///
+    /// The responder cannot send until it has received the initiator's first message.
+    ///
+    /// # Arguments
+    /// * `root_key` - Root key from the key agreement (same as initiator)
+    /// * `our_ratchet_secret` - Our X25519 secret matching the public key the initiator used
+    /// * `associated_data` - Data bound into every message (must match initiator)
+    pub fn new_responder(
+        root_key: &RootKey,
+        our_ratchet_secret: &X25519Secret,
+        associated_data: &[u8],
+    ) -> Result<Self> {
+        let dh_self_public = our_ratchet_secret.public_key();
+        let (header_key_a, header_key_b) = initial_header_keys(root_key)?;
+        let header_keys = HeaderKeys {
+            send: None,
+            next_send: header_key_b,
+            recv: None,
+            next_recv: header_key_a,
+            previous_recv: VecDeque::new(),
+        };
+
+        Ok(Self {
+            root_key: root_key.clone(),
+            dh_self_secret: our_ratchet_secret.clone(),
+            dh_self_public,
+            dh_remote: None,
+            send_chain_key: None,
+            recv_chain_key: None,
+            send_sequence: 0,
+            recv_sequence: 0,
+            previous_send_count: 0,
+            skipped_keys: SkippedKeyStore::default(),
+            associated_data: associated_data.to_vec(),
+            pq: None,
+            header_keys,
+            header_encryption: false,
+            padding: PaddingScheme::default(),
+        })
+    }
+
+    /// Enable the sparse post-quantum ratchet (both parties must enable it)
+    pub fn with_pq_ratchet(mut self, pq: PqRatchetState) -> Self {
+        self.pq = Some(pq);
+        self
+    }
+
+    /// Encrypt outgoing headers (only if the peer advertised CAPABILITY_HEADER_ENCRYPTION)
+    ///
+    /// Incoming header-encrypted frames are accepted either way.
+    pub fn with_header_encryption(mut self, enabled: bool) -> Self {
+        self.header_encryption = enabled;
+        self
+    }
+
+    /// Padding scheme for outgoing messages (see `PaddingScheme::for_tier`)
+    pub fn with_padding(mut self, padding: PaddingScheme) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Whether outgoing headers are encrypted
+    pub fn header_encryption(&self) -> bool {
+        self.header_encryption
+    }
+
+    /// Number of PQ ratchet steps applied, or None if the PQ ratchet is disabled
+    pub fn pq_epoch(&self) -> Option<u64> {
+        self.pq.as_ref().map(|pq| pq.epoch())
+    }
+
+    /// Our current ratchet public key (sent in every outgoing header)
+    pub fn ratchet_public_key(&self) -> [u8; 32] {
+        self.dh_self_public
+    }
+
+    /// Whether this session can encrypt (responder must receive first)
+    pub fn can_send(&self) -> bool {
+        self.send_chain_key.is_some()
+    }
+
+    /// Encrypt a message and advance the sending chain
+    ///
+    /// # Returns
+    /// Wire bytes: [header][nonce:24][ciphertext][tag:16], with the header encrypted if enabled
+    pub fn encrypt(&mut self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let chain_key = self.send_chain_key.as_mut()
+            .ok_or(EncryptionError::SessionNotReady)?;
+
+        let message_key = chain_key.message_key();
+        chain_key.advance();
+
+        let header = RatchetHeader {
+            ratchet_public: self.dh_self_public,
+            previous_chain_length: self.previous_send_count,
+            sequence: self.send_sequence,
+            pq_extension: self.pq.as_ref().map(|pq| pq.outgoing_extension()),
+        };
+        self.send_sequence += 1;
+        if let Some(ref mut pq) = self.pq {
+            pq.record_message_sent();
+        }
+
+        let header_bytes = if self.header_encryption {
+            let header_key = self.header_keys.send.as_ref()
+                .ok_or(EncryptionError::SessionNotReady)?;
+            encrypt_header(header_key, &header.to_bytes(), &self.associated_data)?
+        } else {
+            header.to_bytes()
+        };
+
+        let mut padded = pad(plaintext, self.padding);
+        let result = seal(&message_key, &header_bytes, &self.associated_data, &padded);
+        padded.zeroize();
+        result
+    }
+
+    /// Decrypt a message, performing a DH ratchet step if the sender's ratchet key changed
+    ///
+    /// ATOMIC OPERATION: state is only updated if authentication succeeds.
+    pub fn decrypt(&mut self, encrypted_data: &[u8]) -> Result<Vec<u8>> {
+        if encrypted_data.len() < RATCHET_HEADER_LEN + 24 + 16 {
+            return Err(EncryptionError::DecryptionFailed);
+        }
+        let (header, header_len) = if encrypted_data[0] == RATCHET_HE_WIRE_VERSION {
+            self.decrypt_header(encrypted_data)?
+        } else {
+            RatchetHeader::from_bytes(encrypted_data)?
+        };
+        if encrypted_data.len() < header_len + 24 + 16 {
+            return Err(EncryptionError::DecryptionFailed);
+        }
+
+        // Late message: its key is parked and only consumed once the message authenticates
+        if let Some(message_key) = self.skipped_keys.peek(&header.ratchet_public, header.sequence) {
+            let plaintext = open_padded(&message_key, encrypted_data, header_len, &self.associated_data)?;
+            self.skipped_keys.remove(&header.ratchet_public, header.sequence);
+            return Ok(plaintext);
+        }
+
+        // Stage the chain and root changes so a forged message cannot corrupt the session
+        let mut stage = self.stage_receive();
+        let message_key = stage.advance_to(&header)?;
+        let plaintext = open_padded(message_key.expose_secret(), encrypted_data, header_len, &self.associated_data)?;
+        self.commit_receive(stage);
+
+        Ok(plaintext)
+    }
+
+    /// Trial-decrypt an encrypted header with the current, next and previous receiving header keys
+    ///
+    /// # Returns
+    /// (header, length of the encrypted header section in bytes)
+    fn decrypt_header(&self, encrypted_data: &[u8]) -> Result<(RatchetHeader, usize)> {
+        let header_keys = &self.header_keys;
+        let candidates = header_keys.recv.iter()
+            .chain(std::iter::once(&header_keys.next_recv))
+            .chain(header_keys.previous_recv.iter());
+
+        for header_key in candidates {
+            if let Ok((mut header_bytes, section_len)) =
+                open_header(header_key, encrypted_data, &self.associated_data)
+            {
+                let parsed = RatchetHeader::from_bytes(&header_bytes);
+                header_bytes.zeroize();
+                let (header, _) = parsed?;
+                return Ok((header, section_len));
+            }
+        }
+
+        Err(EncryptionError::DecryptionFailed)
+    }
+
+    /// Copy of the receiving-side state a decrypt may change (the skipped-key store is not copied)
+    fn stage_receive(&self) -> ReceiveStage {
+        ReceiveStage {
+            root_key: self.root_key.clone(),
+            dh_self_secret: self.dh_self_secret.clone(),
+            dh_self_public: self.dh_self_public,
+            dh_remote: self.dh_remote,
+            send_chain_key: self.send_chain_key.clone(),
+            recv_chain_key: self.recv_chain_key.clone(),
+            send_sequence: self.send_sequence,
+            recv_sequence: self.recv_sequence,
+            previous_send_count: self.previous_send_count,
+            pq: self.pq.clone(),
+            header_keys: self.header_keys.clone(),
+            skipped: Vec::new(),
+        }
+    }
+
+    /// Apply a staged receive after its message authenticated
+    fn commit_receive(&mut self, stage: ReceiveStage) {
+        let ReceiveStage {
+            root_key,
+            dh_self_secret,
+            dh_self_public,
+            dh_remote,
+            send_chain_key,
+            recv_chain_key,
+            send_sequence,
+            recv_sequence,
+            previous_send_count,
+            pq,
+            header_keys,
+            skipped,
+        } = stage;
+
+        self.root_key = root_key;
+        self.dh_self_secret = dh_self_secret;
+        self.dh_self_public = dh_self_public;
+        self.dh_remote = dh_remote;
+        self.send_chain_key = send_chain_key;
+        self.recv_chain_key = recv_chain_key;
+        self.send_sequence = send_sequence;
+        self.recv_sequence = recv_sequence;
+        self.previous_send_count = previous_send_count;
+        self.pq = pq;
+        self.header_keys = header_keys;
+        for (chain_id, sequence, message_key) in skipped {
+            self.skipped_keys.insert(chain_id, sequence, message_key.expose_secret());
+        }
+    }
+
+    /// Serialize session state for persistence
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self).map_err(|_| EncryptionError::InvalidSessionState)
+    }
+
+    /// Restore session state from `to_bytes` output
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        bincode::deserialize(bytes).map_err(|_| EncryptionError::InvalidSessionState)
+    }
+}
+
+/// Receiving-side session state staged by `decrypt` until the message authenticates
+struct ReceiveStage {
+    root_key: RootKey,
+    dh_self_secret: X25519Secret,
+    dh_self_public: [u8; 32],
+    dh_remote: Option<[u8; 32]>,
+    send_chain_key: Option<ChainKey>,
+    recv_chain_key: Option<ChainKey>,
+    send_sequence: u64,
+    recv_sequence: u64,
+    previous_send_count: u64,
+    pq: Option<PqRatchetState>,
+    header_keys: HeaderKeys,
+    /// Keys to park in the skipped-key store on commit: (ratchet_pub, sequence, key)
+    skipped: Vec<([u8; 32], u64, MessageKey)>,
+}
+
+impl ReceiveStage {
+    /// Ratchet and advance the receiving chain to the header's position
+    ///
+    /// # Returns
+    /// The message key for the header's sequence
+    fn advance_to(&mut self, header: &RatchetHeader) -> Result<MessageKey> {
+        if self.dh_remote != Some(header.ratchet_public) {
+            self.skip_message_keys(header.previous_chain_length)?;
+            self.dh_ratchet(header)?;
+        }
+
+        if header.sequence < self.recv_sequence {
+            return Err(EncryptionError::ReplayAttack {
+                received: header.sequence,
+                expected: self.recv_sequence,
+            });
+        }
+
+        self.skip_message_keys(header.sequence)?;
+
+        let chain_key = self.recv_chain_key.as_mut()
+            .ok_or(EncryptionError::SessionNotReady)?;
+        let message_key = chain_key.message_key();
+        chain_key.advance();
+        self.recv_sequence += 1;
+
+        Ok(message_key)
+    }
+
+    /// Stage message keys for the current receiving chain up to (not including) `until`
+    fn skip_message_keys(&mut self, until: u64) -> Result<()> {
+        let remote = match (self.recv_chain_key.as_ref(), self.dh_remote) {
+            (Some(_), Some(remote)) => remote,
+            _ => return Ok(()),
+        };
+
+        if until > self.recv_sequence + MAX_SKIP {
+            return Err(EncryptionError::SequenceTooFar {
+                received: until,
+                expected: self.recv_sequence,
+                max: self.recv_sequence + MAX_SKIP,
+            });
+        }
+
+        while self.recv_sequence < until {
+            let chain_key = self.recv_chain_key.as_mut()
+                .ok_or(EncryptionError::SessionNotReady)?;
+            let message_key = chain_key.message_key();
+            chain_key.advance();
+
+            self.skipped.push((remote, self.recv_sequence, message_key));
+            self.recv_sequence += 1;
+        }
+
+        Ok(())
+    }
+
+    /// DH ratchet step: new receiving chain from their key, then new sending chain from a fresh key pair
+    fn dh_ratchet(&mut self, header: &RatchetHeader) -> Result<()> {
+        let their_ratchet_public = &header.ratchet_public;
+        self.previous_send_count = self.send_sequence;
+        self.send_sequence = 0;
+        self.recv_sequence = 0;
+        self.dh_remote = Some(*their_ratchet_public);
+
+        // PQ secret carried by the new receiving chain is mixed in before its root KDF step
+        if let (Some(pq), Some(extension)) = (self.pq.as_mut(), header.pq_extension.as_ref()) {
+            self.root_key = pq.apply_incoming(&self.root_key, extension)?;
+        }
+
+        // Header keys move one chain forward; the old receiving key is kept for late messages
+        let header_keys = &mut self.header_keys;
+        if let Some(old_recv) = header_keys.recv.replace(header_keys.next_recv.clone()) {
+            header_keys.previous_recv.push_front(old_recv);
+            // The evicted key is wiped on drop
+            header_keys.previous_recv.truncate(MAX_PREVIOUS_HEADER_KEYS);
+        }
+        header_keys.send = Some(header_keys.next_send.clone());
+
+        let dh_output = x25519(&self.dh_self_secret, their_ratchet_public)?;
+        let (root_key, recv_chain_key) = kdf_root(&self.root_key, &dh_output)?;
+        self.root_key = root_key;
+        self.recv_chain_key = Some(recv_chain_key);
+        self.header_keys.next_recv = kdf_header(&self.root_key)?;
+
+        // The replaced secret is wiped on drop
+        self.dh_self_secret = X25519Secret::generate();
+        self.dh_self_public = self.dh_self_secret.public_key();
+
+        if let Some(ref mut pq) = self.pq {
+            self.root_key = pq.begin_sending_chain(&self.root_key)?;
+        }
+
+        let dh_output = x25519(&self.dh_self_secret, their_ratchet_public)?;
+        let (root_key, send_chain_key) = kdf_root(&self.root_key, &dh_output)?;
+        self.root_key = root_key;
+        self.send_chain_key = Some(send_chain_key);
+        self.header_keys.next_send = kdf_header(&self.root_key)?;
+
+        Ok(())
+    }
+}
+
+/// Root key KDF: HKDF-SHA256(salt = root_key, ikm = dh_output) -> (new_root_key, chain_key)
+fn kdf_root(root_key: &RootKey, dh_output: &[u8; 32]) -> Result<(RootKey, ChainKey)> {
+    let hkdf = Hkdf::<Sha256>::new(Some(root_key.expose_secret()), dh_output);
+    let mut okm = Zeroizing::new([0u8; 64]);
+    hkdf.expand(ROOT_KDF_INFO, &mut *okm)
+        .map_err(|_| EncryptionError::EncryptionFailed)?;
+
+    Ok((RootKey::from_bytes(&okm[..32])?, ChainKey::from_bytes(&okm[32..])?))
+}
+
+/// Header key for the chain that follows a root KDF step: HKDF-SHA256(ikm = root_key)
+fn kdf_header(root_key: &RootKey) -> Result<HeaderKey> {
+    let hkdf = Hkdf::<Sha256>::new(None, root_key.expose_secret());
+    let mut header_key = HeaderKey([0u8; 32]);
+    hkdf.expand(HEADER_KDF_INFO, &mut header_key.0)
+        .map_err(|_| EncryptionError::EncryptionFailed)?;
+    Ok(header_key)
+}
+
+/// Initial header keys from the shared root: (initiator's first sending key, responder's first sending key)
+fn initial_header_keys(root_key: &RootKey) -> Result<(HeaderKey, HeaderKey)> {
+    let hkdf = Hkdf::<Sha256>::new(None, root_key.expose_secret());
+    let mut header_key_a = HeaderKey([0u8; 32]);
+    let mut header_key_b = HeaderKey([0u8; 32]);
+    hkdf.expand(INITIAL_HEADER_KEY_INFO_A, &mut header_key_a.0)
+        .map_err(|_| EncryptionError::EncryptionFailed)?;
+    hkdf.expand(INITIAL_HEADER_KEY_INFO_B, &mut header_key_b.0)
+        .map_err(|_| EncryptionError::EncryptionFailed)?;
+    Ok((header_key_a, header_key_b))
+}
+
+/// Open a frame and strip its padding
+fn open_padded(message_key: &[u8; 32], encrypted_data: &[u8], header_len: usize, associated_data: &[u8]) -> Result<Vec<u8>> {
+    let mut padded = open(message_key, encrypted_data, header_len, associated_data)?;
+    let plaintext = unpad(&padded).map_err(|_| EncryptionError::DecryptionFailed);
+    padded.zeroize();
+    plaintext
+}
+
+/// Encrypt a plain header into the HE prefix: [version][header_len:2][header_nonce:24][encrypted_header]
+fn encrypt_header(header_key: &HeaderKey, header_bytes: &[u8], associated_data: &[u8]) -> Result<Vec<u8>> {
+    let cipher = XChaCha20Poly1305::new_from_slice(&header_key.0)
+        .map_err(|_| EncryptionError::InvalidKeyLength)?;
+
+    let mut nonce_bytes = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce_bytes);
+
+    let aad = build_aad(&[RATCHET_HE_WIRE_VERSION], associated_data);
+    let encrypted_header = cipher
+        .encrypt(XNonce::from_slice(&nonce_bytes), Payload { msg: header_bytes, aad: &aad })
+        .map_err(|_| EncryptionError::EncryptionFailed)?;
+    let header_len = u16::try_from(encrypted_header.len())
+        .map_err(|_| EncryptionError::EncryptionFailed)?;
+
+    let mut bytes = Vec::with_capacity(HE_PREFIX_LEN + encrypted_header.len());
+    bytes.push(RATCHET_HE_WIRE_VERSION);
+    bytes.extend_from_slice(&header_len.to_be_bytes());
+    bytes.extend_from_slice(&nonce_bytes);
+    bytes.extend_from_slice(&encrypted_header);
+    Ok(bytes)
+}
+
+/// Decrypt the header of an HE frame
+///
+/// # Returns
+/// (plain header bytes, length of the encrypted header section in bytes)
+fn open_header(header_key: &HeaderKey, encrypted_data: &[u8], associated_data: &[u8]) -> Result<(Vec<u8>, usize)> {
+    if encrypted_data.len() < HE_PREFIX_LEN {
+        return Err(EncryptionError::DecryptionFailed);
+    }
+    let header_len = u16::from_be_bytes([encrypted_data[1], encrypted_data[2]]) as usize;
+    let section_len = HE_PREFIX_LEN + header_len;
+    if encrypted_data.len() < section_len {
+        return Err(EncryptionError::DecryptionFailed);
+    }
+
+    let cipher = XChaCha20Poly1305::new_from_slice(&header_key.0)
+        .map_err(|_| EncryptionError::InvalidKeyLength)?;
+
+    let aad = build_aad(&[RATCHET_HE_WIRE_VERSION], associated_data);
+    let header_bytes = cipher
+        .decrypt(
+            XNonce::from_slice(&encrypted_data[3..HE_PREFIX_LEN]),
+            Payload { msg: &encrypted_data[HE_PREFIX_LEN..section_len], aad: &aad },
+        )
+        .map_err(|_| EncryptionError::DecryptionFailed)?;
+
+    Ok((header_bytes, section_len))
+}
+
+fn x25519(our_secret: &X25519Secret, their_public: &[u8; 32]) -> Result<Zeroizing<[u8; 32]>> {
+    our_secret
+        .diffie_hellman(their_public)
+        .map_err(|_| EncryptionError::InvalidKeyLength)
+}
+
+/// AEAD associated data: session associated data || header
+fn build_aad(header_bytes: &[u8], associated_data: &[u8]) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(associated_data.len() + header_bytes.len());
+    aad.extend_from_slice(associated_data);
+    aad.extend_from_slice(header_bytes);
+    aad
+}
+
+fn seal(
+    message_key: &MessageKey,
+    header_bytes: &[u8],
+    associated_data: &[u8],
+    plaintext: &[u8],
+) -> Result<Vec<u8>> {
+    let cipher = XChaCha20Poly1305::new_from_slice(message_key.expose_secret())
+        .map_err(|_| EncryptionError::InvalidKeyLength)?;
+
+    let mut nonce_bytes = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = XNonce::from_slice(&nonce_bytes);
+
+    let aad = build_aad(header_bytes, associated_data);
+    let ciphertext = cipher
+        .encrypt(nonce, Payload { msg: plaintext, aad: &aad })
+        .map_err(|_| EncryptionError::EncryptionFailed)?;
+
+    let mut encrypted_message = Vec::with_capacity(header_bytes.len() + 24 + ciphertext.len());
+    encrypted_message.extend_from_slice(header_bytes);
+    encrypted_message.extend_from_slice(&nonce_bytes);
+    encrypted_message.extend_from_slice(&ciphertext);
+
+    Ok(encrypted_message)
+}
+
+fn open(
+    message_key: &[u8; 32],
+    encrypted_data: &[u8],
+    header_len: usize,
+    associated_data: &[u8],
+) -> Result<Vec<u8>> {
+    let header_bytes = &encrypted_data[..header_len];
+    let nonce_bytes = &encrypted_data[header_len..header_len + 24];
+    let ciphertext = &encrypted_data[header_len + 24..];
+
+    let cipher = XChaCha20Poly1305::new_from_slice(message_key)
+        .map_err(|_| EncryptionError::InvalidKeyLength)?;
+
+    let aad = build_aad(header_bytes, associated_data);
+    cipher
+        .decrypt(XNonce::from_slice(nonce_bytes), Payload { msg: ciphertext, aad: &aad })
+        .map_err(|_| EncryptionError::DecryptionFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::security_mode::SecurityTier;
+
+    fn session_pair() -> (RatchetSession, RatchetSession) {
+        let root_key = RootKey::from([7u8; 32]);
+        let bob_secret = X25519Secret::generate();
+        let bob_public = bob_secret.public_key();
+        let ad = b"alice-identity|bob-identity";
+
+        let alice = RatchetSession::new_initiator(&root_key, &bob_public, ad).unwrap();
+        let bob = RatchetSession::new_responder(&root_key, &bob_secret, ad).unwrap();
+        (alice, bob)
+    }
+
+    #[test]
+    fn test_ratchet_conversation() {
+        let (mut alice, mut bob) = session_pair();
+        assert!(!bob.can_send());
+
+        let m1 = alice.encrypt(b"hello bob").unwrap();
+        assert_eq!(bob.decrypt(&m1).unwrap(), b"hello bob");
+
+        let alice_key_before = alice.ratchet_public_key();
+        let reply = bob.encrypt(b"hello alice").unwrap();
+        assert_eq!(alice.decrypt(&reply).unwrap(), b"hello alice");
+
+        // Alice performed a DH ratchet step on receiving Bob's new key
+        assert_ne!(alice.ratchet_public_key(), alice_key_before);
+
+        let m2 = alice.encrypt(b"second turn").unwrap();
+        assert_eq!(bob.decrypt(&m2).unwrap(), b"second turn");
+    }
+
+    #[test]
+    fn test_out_of_order_across_ratchet_steps() {
+        let (mut alice, mut bob) = session_pair();
+
+        let m0 = alice.encrypt(b"m0").unwrap();
+        let m1 = alice.encrypt(b"m1").unwrap();
+        assert_eq!(bob.decrypt(&m1).unwrap(), b"m1");
+
+        let reply = bob.encrypt(b"reply").unwrap();
+        assert_eq!(alice.decrypt(&reply).unwrap(), b"reply");
+        let m2 = alice.encrypt(b"m2").unwrap();
+        assert_eq!(bob.decrypt(&m2).unwrap(), b"m2");
+
+        // m0 from the previous chain still decrypts via skipped keys
+        assert_eq!(bob.decrypt(&m0).unwrap(), b"m0");
+    }
+
+    #[test]
+    fn test_replay_rejected() {
+        let (mut alice, mut bob) = session_pair();
+
+        let m0 = alice.encrypt(b"once").unwrap();
+        assert!(bob.decrypt(&m0).is_ok());
+        assert!(bob.decrypt(&m0).is_err());
+    }
+
+    #[test]
+    fn test_tampered_header_rejected_without_state_change() {
+        let (mut alice, mut bob) = session_pair();
+
+        let mut m0 = alice.encrypt(b"payload").unwrap();
+        let original = m0.clone();
+        m0[45] ^= 0x01; // Flip a bit in the sequence number
+
+        assert!(bob.decrypt(&m0).is_err());
+        assert_eq!(bob.decrypt(&original).unwrap(), b"payload");
+    }
+
+    #[test]
+    fn test_forged_late_message_keeps_skipped_key() {
+        let (mut alice, mut bob) = session_pair();
+
+        let m0 = alice.encrypt(b"late").unwrap();
+        let m1 = alice.encrypt(b"first").unwrap();
+        assert_eq!(bob.decrypt(&m1).unwrap(), b"first");
+
+        // Same header, forged ciphertext: rejected without consuming the parked key
+        let mut forged = m0.clone();
+        let last = forged.len() - 1;
+        forged[last] ^= 0x01;
+        assert!(bob.decrypt(&forged).is_err());
+        assert_eq!(bob.decrypt(&m0).unwrap(), b"late");
+        assert!(bob.decrypt(&m0).is_err());
+    }
+
+    #[test]
+    fn test_session_serialization_roundtrip() {
+        let (mut alice, bob) = session_pair();
+
+        let bytes = bob.to_bytes().unwrap();
+        let mut restored = RatchetSession::from_bytes(&bytes).unwrap();
+
+        let m0 = alice.encrypt(b"after restore").unwrap();
+        assert_eq!(restored.decrypt(&m0).unwrap(), b"after restore");
+    }
+    #[test]
+    fn test_header_encryption_hides_header() {
+        let (alice, bob) = session_pair();
+        let negotiated = negotiate_capabilities(CAPABILITY_HEADER_ENCRYPTION | CAPABILITY_PQ_RATCHET, CAPABILITY_HEADER_ENCRYPTION);
+        let he = negotiated & CAPABILITY_HEADER_ENCRYPTION != 0;
+        let mut alice = alice.with_header_encryption(he);
+        let mut bob = bob.with_header_encryption(he);
+
+        let m0 = alice.encrypt(b"m0").unwrap();
+        let m1 = alice.encrypt(b"m1").unwrap();
+        assert_eq!(m0[0], RATCHET_HE_WIRE_VERSION);
+        let ratchet_public = alice.ratchet_public_key();
+        assert!(!m1.windows(32).any(|w| w == ratchet_public));
+
+        // Current-key and next-key trial decryption across several ratchet steps
+        assert_eq!(bob.decrypt(&m1).unwrap(), b"m1");
+        let reply = bob.encrypt(b"reply").unwrap();
+        assert_eq!(alice.decrypt(&reply).unwrap(), b"reply");
+        let m2 = alice.encrypt(b"m2").unwrap();
+        assert_eq!(bob.decrypt(&m2).unwrap(), b"m2");
+
+        // Late message from an earlier chain uses a previous header key
+        assert_eq!(bob.decrypt(&m0).unwrap(), b"m0");
+        assert!(bob.decrypt(&m0).is_err());
+    }
+
+    #[test]
+    fn test_header_encryption_is_per_sender() {
+        // Only Alice encrypts headers; Bob still decrypts them and replies in the clear
+        let (alice, mut bob) = session_pair();
+        let mut alice = alice.with_header_encryption(true);
+
+        let m0 = alice.encrypt(b"hidden").unwrap();
+        assert_eq!(bob.decrypt(&m0).unwrap(), b"hidden");
+
+        let reply = bob.encrypt(b"plain").unwrap();
+        assert_eq!(reply[0], RATCHET_WIRE_VERSION);
+        assert_eq!(alice.decrypt(&reply).unwrap(), b"plain");
+
+        let mut m1 = alice.encrypt(b"tampered").unwrap();
+        m1[10] ^= 0x01;
+        assert!(bob.decrypt(&m1).is_err());
+    }
+
+    #[test]
+    fn test_padding_per_tier() {
+        let (alice, mut bob) = session_pair();
+        let mut alice = alice.with_padding(PaddingScheme::for_tier(SecurityTier::HighRisk));
+
+        let short = alice.encrypt(b"hi").unwrap();
+        let longer = alice.encrypt(&[0x41; 200]).unwrap();
+        assert_eq!(short.len(), longer.len());
+        assert_eq!(bob.decrypt(&short).unwrap(), b"hi");
+        assert_eq!(bob.decrypt(&longer).unwrap(), vec![0x41; 200]);
+    }
+}
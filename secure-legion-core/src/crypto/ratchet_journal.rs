@@ -0,0 +1,472 @@
+//! Ratchet Write-Ahead Journal
+//! Encrypted append-only log of pending ratchet advancements
+//!
+//! Every pending advancement is appended (and fsynced) before its ciphertext leaves the
+//! device; commit and rollback append their own records. On startup the log is replayed
+//! to rebuild the set of uncommitted advancements, so a process kill between encrypt and
+//! commit no longer desynchronizes the chains.
+//!
+//! Each contact has a queue of advancements in sequence order. A commit record finalizes
+//! the queue up to and including its message; a rollback record drops its message and
+//! everything queued after it.
+//!
+//! File format: a sequence of frames [len u32 BE][nonce 24][XChaCha20-Poly1305 ciphertext].
+//! Each frame's AAD binds its index in the log, so frames cannot be reordered or dropped
+//! from the middle. A torn frame at the end (crash during append) is discarded.
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use zeroize::{Zeroize, Zeroizing};
+
+/// AAD prefix for journal frames
+const JOURNAL_AAD_PREFIX: &[u8] = b"SecureLegion-RatchetJournal-v1";
+
+/// Frame header: length prefix + nonce
+const FRAME_HEADER_LEN: usize = 4 + 24;
+
+/// Largest frame accepted on replay
+const MAX_FRAME_LEN: usize = 64 * 1024;
+
+/// Rewrite the log once it holds this many records
+const COMPACTION_THRESHOLD: usize = 1024;
+
+#[derive(Error, Debug)]
+pub enum RatchetJournalError {
+    #[error("Journal I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Journal record could not be decrypted (wrong key or tampered log)")]
+    DecryptionFailed,
+    #[error("Journal record is malformed")]
+    Corrupt,
+    #[error("Journal encryption failed")]
+    EncryptionFailed,
+}
+
+pub type Result<T> = std::result::Result<T, RatchetJournalError>;
+
+/// Uncommitted ratchet state recovered from the journal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournaledAdvancement {
+    pub contact_id: String,
+    pub message_id: String,
+    pub next_chain_key: [u8; 32],
+    pub next_sequence: u64,
+    /// Unix timestamp when the advancement was recorded
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum JournalRecord {
+    Pending(JournaledAdvancement),
+    /// Commit through `message_id`
+    Commit { contact_id: String, message_id: String },
+    /// Roll back `message_id` and everything after it
+    Rollback { contact_id: String, message_id: String },
+}
+
+/// Write-ahead journal for one device
+pub struct RatchetJournal {
+    path: PathBuf,
+    file: File,
+    key: Zeroizing<[u8; 32]>,
+    /// Number of records in the log (next frame index)
+    record_count: u64,
+    /// Per-contact queues in sequence order
+    pending: HashMap<String, Vec<JournaledAdvancement>>,
+}
+
+impl RatchetJournal {
+    /// Open (or create) the journal and replay it
+    ///
+    /// # Arguments
+    /// * `path` - Journal file
+    /// * `key` - 32-byte journal encryption key
+    pub fn open(path: impl AsRef<Path>, key: &[u8; 32]) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let key = Zeroizing::new(*key);
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let (records, valid_len) = replay_frames(&bytes, &key)?;
+        let mut pending = HashMap::new();
+        for record in &records {
+            apply_record(&mut pending, record);
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        if (valid_len as usize) < bytes.len() {
+            log::warn!("Discarding torn ratchet journal tail ({} bytes)", bytes.len() - valid_len as usize);
+            file.set_len(valid_len)?;
+            file.sync_all()?;
+        }
+
+        log::info!("Ratchet journal replayed: {} records, {} pending",
+            records.len(), pending.values().map(Vec::len).sum::<usize>());
+        Ok(Self { path, file, key, record_count: records.len() as u64, pending })
+    }
+
+    /// Record a pending advancement; must happen before the ciphertext is sent
+    pub fn record_pending(&mut self, advancement: JournaledAdvancement) -> Result<()> {
+        self.append(&JournalRecord::Pending(advancement.clone()))?;
+        self.pending.entry(advancement.contact_id.clone()).or_default().push(advancement);
+        Ok(())
+    }
+
+    /// Record a commit through `message_id`
+    ///
+    /// # Returns
+    /// The advancements it finalizes, oldest first (empty if the message is not pending)
+    pub fn commit(&mut self, contact_id: &str, message_id: &str) -> Result<Vec<JournaledAdvancement>> {
+        if !self.is_pending(contact_id, message_id) {
+            return Ok(Vec::new());
+        }
+        self.append(&JournalRecord::Commit {
+            contact_id: contact_id.to_string(),
+            message_id: message_id.to_string(),
+        })?;
+        let committed = commit_through(&mut self.pending, contact_id, message_id);
+        self.compact_if_needed()?;
+        Ok(committed)
+    }
+
+    /// Record a rollback of `message_id` and everything queued after it
+    ///
+    /// # Returns
+    /// The discarded advancements, oldest first (empty if the message is not pending)
+    pub fn rollback(&mut self, contact_id: &str, message_id: &str) -> Result<Vec<JournaledAdvancement>> {
+        if !self.is_pending(contact_id, message_id) {
+            return Ok(Vec::new());
+        }
+        self.append(&JournalRecord::Rollback {
+            contact_id: contact_id.to_string(),
+            message_id: message_id.to_string(),
+        })?;
+        let discarded = rollback_from(&mut self.pending, contact_id, message_id);
+        self.compact_if_needed()?;
+        Ok(discarded)
+    }
+
+    /// Uncommitted advancements (e.g. to re-send or resolve after a restart)
+    pub fn pending(&self) -> impl Iterator<Item = &JournaledAdvancement> {
+        self.pending.values().flatten()
+    }
+
+    /// A contact's queue, oldest first
+    pub fn pending_for(&self, contact_id: &str) -> &[JournaledAdvancement] {
+        self.pending.get(contact_id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    fn is_pending(&self, contact_id: &str, message_id: &str) -> bool {
+        self.pending_for(contact_id).iter().any(|a| a.message_id == message_id)
+    }
+
+    /// Rewrite the log with only the pending records (atomic: temp file, then rename)
+    pub fn compact(&mut self) -> Result<()> {
+        let tmp_path = self.path.with_extension("compact");
+        let mut bytes = Vec::new();
+        for (index, advancement) in self.pending().enumerate() {
+            bytes.extend(seal_frame(&self.key, index as u64, &JournalRecord::Pending(advancement.clone()))?);
+        }
+        {
+            let mut tmp = File::create(&tmp_path)?;
+            tmp.write_all(&bytes)?;
+            tmp.sync_all()?;
+        }
+        std::fs::rename(&tmp_path, &self.path)?;
+        // The rename itself is only durable once the directory entry is flushed
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            File::open(parent)?.sync_all()?;
+        }
+
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        self.record_count = self.pending().count() as u64;
+        Ok(())
+    }
+
+    /// Overwrite the log with zeros and delete it (duress wipe)
+    pub fn destroy(mut self) -> Result<()> {
+        for queue in self.pending.values_mut() {
+            for advancement in queue.iter_mut() {
+                advancement.next_chain_key.zeroize();
+            }
+        }
+
+        let len = self.file.metadata()?.len() as usize;
+        {
+            let mut file = OpenOptions::new().write(true).open(&self.path)?;
+            file.write_all(&vec![0u8; len])?;
+            file.sync_all()?;
+        }
+        std::fs::remove_file(&self.path)?;
+        Ok(())
+    }
+
+    fn compact_if_needed(&mut self) -> Result<()> {
+        let empty = self.pending().next().is_none();
+        if self.record_count as usize >= COMPACTION_THRESHOLD || (empty && self.record_count > 0) {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    fn append(&mut self, record: &JournalRecord) -> Result<()> {
+        let frame = seal_frame(&self.key, self.record_count, record)?;
+        self.file.write_all(&frame)?;
+        self.file.sync_data()?;
+        self.record_count += 1;
+        Ok(())
+    }
+}
+
+fn apply_record(pending: &mut HashMap<String, Vec<JournaledAdvancement>>, record: &JournalRecord) {
+    match record {
+        JournalRecord::Pending(advancement) => {
+            pending.entry(advancement.contact_id.clone()).or_default().push(advancement.clone());
+        }
+        JournalRecord::Commit { contact_id, message_id } => {
+            commit_through(pending, contact_id, message_id);
+        }
+        JournalRecord::Rollback { contact_id, message_id } => {
+            rollback_from(pending, contact_id, message_id);
+        }
+    }
+}
+
+/// Remove and return the queue prefix ending at `message_id`
+fn commit_through(
+    pending: &mut HashMap<String, Vec<JournaledAdvancement>>,
+    contact_id: &str,
+    message_id: &str,
+) -> Vec<JournaledAdvancement> {
+    let Some(queue) = pending.get_mut(contact_id) else { return Vec::new() };
+    let Some(position) = queue.iter().position(|a| a.message_id == message_id) else { return Vec::new() };
+    let committed: Vec<_> = queue.drain(..=position).collect();
+    if queue.is_empty() {
+        pending.remove(contact_id);
+    }
+    committed
+}
+
+/// Remove and return the queue suffix starting at `message_id`
+fn rollback_from(
+    pending: &mut HashMap<String, Vec<JournaledAdvancement>>,
+    contact_id: &str,
+    message_id: &str,
+) -> Vec<JournaledAdvancement> {
+    let Some(queue) = pending.get_mut(contact_id) else { return Vec::new() };
+    let Some(position) = queue.iter().position(|a| a.message_id == message_id) else { return Vec::new() };
+    let discarded = queue.split_off(position);
+    if queue.is_empty() {
+        pending.remove(contact_id);
+    }
+    discarded
+}
+
+fn frame_aad(index: u64) -> Vec<u8> {
+    let mut aad = JOURNAL_AAD_PREFIX.to_vec();
+    aad.extend_from_slice(&index.to_be_bytes());
+    aad
+}
+
+fn seal_frame(key: &[u8; 32], index: u64, record: &JournalRecord) -> Result<Vec<u8>> {
+    let mut plaintext = bincode::serialize(record).map_err(|_| RatchetJournalError::EncryptionFailed)?;
+    let cipher = XChaCha20Poly1305::new_from_slice(key).map_err(|_| RatchetJournalError::EncryptionFailed)?;
+    let mut nonce = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), Payload { msg: &plaintext, aad: &frame_aad(index) })
+        .map_err(|_| RatchetJournalError::EncryptionFailed);
+    plaintext.zeroize();
+    let ciphertext = ciphertext?;
+
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + ciphertext.len());
+    frame.extend_from_slice(&(ciphertext.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&nonce);
+    frame.extend_from_slice(&ciphertext);
+    Ok(frame)
+}
+
+/// Decrypt every complete frame
+///
+/// # Returns
+/// (records, length of the intact prefix of the log)
+fn replay_frames(bytes: &[u8], key: &[u8; 32]) -> Result<(Vec<JournalRecord>, u64)> {
+    let cipher = XChaCha20Poly1305::new_from_slice(key).map_err(|_| RatchetJournalError::DecryptionFailed)?;
+    let mut records = Vec::new();
+    let mut offset = 0usize;
+
+    while bytes.len() - offset >= FRAME_HEADER_LEN {
+        let len = u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap()) as usize;
+        if len > MAX_FRAME_LEN {
+            return Err(RatchetJournalError::Corrupt);
+        }
+        let end = offset + FRAME_HEADER_LEN + len;
+        if end > bytes.len() {
+            break; // torn append
+        }
+
+        let nonce = XNonce::from_slice(&bytes[offset + 4..offset + FRAME_HEADER_LEN]);
+        let aad = frame_aad(records.len() as u64);
+        let plaintext = Zeroizing::new(
+            cipher
+                .decrypt(nonce, Payload { msg: &bytes[offset + FRAME_HEADER_LEN..end], aad: &aad })
+                .map_err(|_| RatchetJournalError::DecryptionFailed)?,
+        );
+        records.push(bincode::deserialize(&plaintext).map_err(|_| RatchetJournalError::Corrupt)?);
+        offset = end;
+    }
+
+    Ok((records, offset as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn journal_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("ratchet_journal_{}_{}.log", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn advancement(contact_id: &str, message_id: &str, sequence: u64) -> JournaledAdvancement {
+        JournaledAdvancement {
+            contact_id: contact_id.to_string(),
+            message_id: message_id.to_string(),
+            next_chain_key: [sequence as u8; 32],
+            next_sequence: sequence,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_crash_at_each_step_recovers_state() {
+        let path = journal_path("steps");
+        let key = [7u8; 32];
+
+        // Crash after the pending record: advancement survives
+        {
+            let mut journal = RatchetJournal::open(&path, &key).unwrap();
+            journal.record_pending(advancement("alice", "m1", 1)).unwrap();
+            journal.record_pending(advancement("bob", "m2", 5)).unwrap();
+        }
+        let mut journal = RatchetJournal::open(&path, &key).unwrap();
+        assert_eq!(journal.pending_for("alice"), &[advancement("alice", "m1", 1)]);
+        assert_eq!(journal.pending().count(), 2);
+
+        // Crash after commit/rollback: they stay applied
+        assert_eq!(journal.commit("alice", "m1").unwrap(), vec![advancement("alice", "m1", 1)]);
+        drop(journal);
+        let mut journal = RatchetJournal::open(&path, &key).unwrap();
+        assert!(journal.pending_for("alice").is_empty());
+        assert_eq!(journal.pending_for("bob").len(), 1);
+
+        journal.rollback("bob", "m2").unwrap();
+        drop(journal);
+        let journal = RatchetJournal::open(&path, &key).unwrap();
+        assert_eq!(journal.pending().count(), 0);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_destroy_removes_log() {
+        let path = journal_path("destroy");
+        let key = [7u8; 32];
+        let mut journal = RatchetJournal::open(&path, &key).unwrap();
+        journal.record_pending(advancement("alice", "m1", 1)).unwrap();
+
+        journal.destroy().unwrap();
+        assert!(!path.exists());
+        assert_eq!(RatchetJournal::open(&path, &key).unwrap().pending().count(), 0);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_torn_append_is_discarded() {
+        let path = journal_path("torn");
+        let key = [7u8; 32];
+        {
+            let mut journal = RatchetJournal::open(&path, &key).unwrap();
+            journal.record_pending(advancement("alice", "m1", 1)).unwrap();
+            journal.record_pending(advancement("bob", "m2", 2)).unwrap();
+        }
+
+        // Crash in the middle of writing the second frame
+        let full = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &full[..full.len() - 10]).unwrap();
+
+        let mut journal = RatchetJournal::open(&path, &key).unwrap();
+        assert_eq!(journal.pending_for("alice").len(), 1);
+        assert!(journal.pending_for("bob").is_empty());
+
+        // Appends after recovery line up with the truncated log
+        journal.record_pending(advancement("carol", "m3", 3)).unwrap();
+        drop(journal);
+        let journal = RatchetJournal::open(&path, &key).unwrap();
+        assert_eq!(journal.pending().count(), 2);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_queue_commits_in_order_and_rolls_back_suffix() {
+        let path = journal_path("queue");
+        let key = [7u8; 32];
+        {
+            let mut journal = RatchetJournal::open(&path, &key).unwrap();
+            for (i, message_id) in ["m1", "m2", "m3", "m4"].iter().enumerate() {
+                journal.record_pending(advancement("alice", message_id, i as u64 + 1)).unwrap();
+            }
+
+            // ACK for m2 commits m1 and m2 only
+            let committed = journal.commit("alice", "m2").unwrap();
+            assert_eq!(committed.iter().map(|a| a.next_sequence).collect::<Vec<_>>(), vec![1, 2]);
+            assert!(journal.commit("alice", "m1").unwrap().is_empty());
+        }
+
+        // Rolling back m3 also discards m4, including after a replay
+        let mut journal = RatchetJournal::open(&path, &key).unwrap();
+        assert_eq!(journal.pending_for("alice").len(), 2);
+        let discarded = journal.rollback("alice", "m3").unwrap();
+        assert_eq!(discarded.iter().map(|a| a.message_id.as_str()).collect::<Vec<_>>(), vec!["m3", "m4"]);
+        drop(journal);
+        assert!(RatchetJournal::open(&path, &key).unwrap().pending_for("alice").is_empty());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_tampered_or_foreign_journal_is_rejected() {
+        let path = journal_path("tamper");
+        let key = [7u8; 32];
+        {
+            let mut journal = RatchetJournal::open(&path, &key).unwrap();
+            journal.record_pending(advancement("alice", "m1", 1)).unwrap();
+            journal.record_pending(advancement("bob", "m2", 2)).unwrap();
+        }
+
+        assert!(matches!(RatchetJournal::open(&path, &[8u8; 32]), Err(RatchetJournalError::DecryptionFailed)));
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(matches!(RatchetJournal::open(&path, &key), Err(RatchetJournalError::DecryptionFailed)));
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
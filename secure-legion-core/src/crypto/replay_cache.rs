@@ -1,47 +1,517 @@
-use lru::LruCache;
-use std::sync::Mutex;
+//! PING Replay Guard
+//! Time-windowed, per-sender replay protection that survives restarts
+//!
+//! A PING is only accepted if its signed timestamp lies inside the acceptance window
+//! (`window_secs` in the past, `max_future_skew_secs` ahead), so the guard only has to
+//! remember hashes for that window. Each sender has its own bounded set: once a sender
+//! exceeds `max_entries_per_sender`, its oldest entry is evicted and the sender's floor
+//! rises to that timestamp, so evicted PINGs stay rejected and other senders' entries
+//! are never touched.
+//!
+//! The sender table is bounded too, but a full table never refuses anyone: the sender
+//! whose newest PING is oldest is evicted and only its floor is kept. Kept floors are
+//! bounded separately; when they overflow, the lowest one is folded into a floor shared
+//! by every sender without state of its own.
+//!
+//! With a store attached, every accepted hash is appended (and fsynced) to an encrypted
+//! log before the PING is reported fresh. The log is replayed on open and rewritten once
+//! it is mostly expired. Framing matches the ratchet journal: a sequence of frames
+//! [len u32 BE][nonce 24][XChaCha20-Poly1305 ciphertext], with the frame index bound as AAD.
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
 use once_cell::sync::Lazy;
-use std::num::NonZeroUsize;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use thiserror::Error;
+use zeroize::{Zeroize, Zeroizing};
+
+/// Default acceptance window: PINGs older than this are rejected outright
+pub const DEFAULT_WINDOW_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Default tolerance for sender clocks running ahead of ours
+pub const DEFAULT_MAX_FUTURE_SKEW_SECS: i64 = 10 * 60;
+
+/// Default bound on remembered PINGs per sender
+pub const DEFAULT_MAX_ENTRIES_PER_SENDER: usize = 1_000;
+
+/// Default bound on tracked senders
+pub const DEFAULT_MAX_SENDERS: usize = 10_000;
+
+/// Default bound on floors kept for evicted senders
+pub const DEFAULT_MAX_EVICTED_FLOORS: usize = 50_000;
 
-/// Replay cache entry: (sender_pubkey, ping_hash) -> timestamp
-type CacheKey = ([u8; 32], [u8; 32]);
+/// AAD prefix for replay log frames
+const REPLAY_AAD_PREFIX: &[u8] = b"SecureLegion-PingReplayLog-v1";
+
+/// Frame header: length prefix + nonce
+const FRAME_HEADER_LEN: usize = 4 + 24;
+
+/// Largest frame accepted on replay
+const MAX_FRAME_LEN: usize = 1024;
+
+/// Only consider compaction once the log holds this many records
+const COMPACTION_THRESHOLD: u64 = 1024;
+
+#[derive(Error, Debug)]
+pub enum ReplayGuardError {
+    #[error("Replay log I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Replay log record could not be decrypted (wrong key or tampered log)")]
+    DecryptionFailed,
+    #[error("Replay log record is malformed")]
+    Corrupt,
+    #[error("Replay log encryption failed")]
+    EncryptionFailed,
+}
+
+pub type Result<T> = std::result::Result<T, ReplayGuardError>;
+
+/// Acceptance window and memory bounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayGuardConfig {
+    /// How far in the past a PING timestamp may lie (seconds)
+    pub window_secs: i64,
+    /// How far in the future a PING timestamp may lie (seconds)
+    pub max_future_skew_secs: i64,
+    /// Remembered PINGs per sender before its oldest entry is evicted
+    pub max_entries_per_sender: usize,
+    /// Tracked senders before the least recently seen one is evicted (its floor is kept)
+    pub max_senders: usize,
+    /// Floors kept for evicted senders before the lowest is folded into the shared floor
+    pub max_evicted_floors: usize,
+}
 
-/// Global LRU replay cache for PING deduplication
-/// Capacity: 10,000 entries (most recent PINGs)
-static REPLAY_CACHE: Lazy<Mutex<LruCache<CacheKey, i64>>> = Lazy::new(|| {
-    let capacity = NonZeroUsize::new(10_000).unwrap();
-    Mutex::new(LruCache::new(capacity))
-});
+impl Default for ReplayGuardConfig {
+    fn default() -> Self {
+        Self {
+            window_secs: DEFAULT_WINDOW_SECS,
+            max_future_skew_secs: DEFAULT_MAX_FUTURE_SKEW_SECS,
+            max_entries_per_sender: DEFAULT_MAX_ENTRIES_PER_SENDER,
+            max_senders: DEFAULT_MAX_SENDERS,
+            max_evicted_floors: DEFAULT_MAX_EVICTED_FLOORS,
+        }
+    }
+}
+
+/// Outcome of a replay check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayVerdict {
+    /// First time seen inside the window; process it
+    Fresh,
+    /// Already seen (or at/below the sender's eviction floor)
+    Replay,
+    /// Timestamp is older than the acceptance window
+    Expired,
+    /// Timestamp is further ahead than the allowed clock skew
+    FromFuture,
+}
+
+impl ReplayVerdict {
+    /// Whether the PING should be processed
+    pub fn is_fresh(self) -> bool {
+        self == ReplayVerdict::Fresh
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum ReplayRecord {
+    Seen { sender: [u8; 32], hash: [u8; 32], timestamp: i64 },
+    /// Carries a sender's eviction floor across compaction
+    Floor { sender: [u8; 32], timestamp: i64 },
+    /// Carries the shared floor of senders without state across compaction
+    SharedFloor { timestamp: i64 },
+}
+
+/// Remembered PINGs of one sender, ordered by timestamp
+#[derive(Debug, Default)]
+struct SenderWindow {
+    entries: BTreeSet<(i64, [u8; 32])>,
+    /// PINGs at or below this timestamp are rejected (set by eviction)
+    floor: i64,
+}
+
+struct ReplayStore {
+    path: PathBuf,
+    file: File,
+    key: Zeroizing<[u8; 32]>,
+    /// Number of records in the log (next frame index)
+    record_count: u64,
+}
+
+/// Replay guard for incoming PINGs
+pub struct ReplayGuard {
+    config: ReplayGuardConfig,
+    senders: HashMap<[u8; 32], SenderWindow>,
+    /// Floors of senders evicted from `senders`, so their old PINGs stay rejected
+    evicted_floors: HashMap<[u8; 32], i64>,
+    /// Floor for senders with no state of their own (raised when `evicted_floors` overflows)
+    shared_floor: i64,
+    store: Option<ReplayStore>,
+}
+
+impl ReplayGuard {
+    /// Create an in-memory guard (forgets everything on restart)
+    pub fn new(config: ReplayGuardConfig) -> Self {
+        Self {
+            config,
+            senders: HashMap::new(),
+            evicted_floors: HashMap::new(),
+            shared_floor: 0,
+            store: None,
+        }
+    }
+
+    /// Open (or create) a persistent guard and replay its log
+    ///
+    /// # Arguments
+    /// * `path` - Replay log file
+    /// * `key` - 32-byte log encryption key
+    /// * `config` - Acceptance window and bounds
+    /// * `now` - Current Unix time (entries outside the window are dropped)
+    pub fn open(path: impl AsRef<Path>, key: &[u8; 32], config: ReplayGuardConfig, now: i64) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let key = Zeroizing::new(*key);
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let (records, valid_len) = replay_frames(&bytes, &key)?;
+        let mut guard = Self::new(config);
+        for record in &records {
+            guard.apply(record);
+        }
+        guard.prune(now);
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        if (valid_len as usize) < bytes.len() {
+            log::warn!("Discarding torn replay log tail ({} bytes)", bytes.len() - valid_len as usize);
+            file.set_len(valid_len)?;
+            file.sync_all()?;
+        }
+
+        guard.store = Some(ReplayStore { path, file, key, record_count: records.len() as u64 });
+        guard.compact_if_needed()?;
+        log::info!("PING replay log replayed: {} records, {} live entries", records.len(), guard.len());
+        Ok(guard)
+    }
+
+    /// Check a PING and remember it if fresh
+    ///
+    /// With a store attached, a fresh PING is durably logged before this returns.
+    ///
+    /// # Arguments
+    /// * `sender_pubkey` - 32-byte Ed25519 public key of sender
+    /// * `ping_hash` - 32-byte hash of the PING wire bytes
+    /// * `timestamp` - Signed PingToken timestamp
+    /// * `now` - Current Unix time
+    pub fn check(&mut self, sender_pubkey: [u8; 32], ping_hash: [u8; 32], timestamp: i64, now: i64) -> Result<ReplayVerdict> {
+        if timestamp > now.saturating_add(self.config.max_future_skew_secs) {
+            return Ok(ReplayVerdict::FromFuture);
+        }
+        let cutoff = now.saturating_sub(self.config.window_secs);
+        if timestamp < cutoff {
+            return Ok(ReplayVerdict::Expired);
+        }
+
+        if let Some(window) = self.senders.get_mut(&sender_pubkey) {
+            window.prune(cutoff);
+            if timestamp <= window.floor || window.entries.contains(&(timestamp, ping_hash)) {
+                return Ok(ReplayVerdict::Replay);
+            }
+        } else {
+            let floor = self.evicted_floors.get(&sender_pubkey).copied().unwrap_or(0).max(self.shared_floor);
+            if timestamp <= floor {
+                return Ok(ReplayVerdict::Replay);
+            }
+            // Free slots before `apply` has to evict a sender that is still in the window
+            if self.senders.len() >= self.config.max_senders {
+                self.prune(now);
+            }
+        }
+
+        let record = ReplayRecord::Seen { sender: sender_pubkey, hash: ping_hash, timestamp };
+        if let Some(store) = self.store.as_mut() {
+            store.append(&record)?;
+        }
+        self.apply(&record);
+        self.compact_if_needed()?;
+        Ok(ReplayVerdict::Fresh)
+    }
+
+    /// Drop every entry that is outside the window at `now`
+    ///
+    /// Senders left with only a floor give up their slot and keep the floor.
+    pub fn prune(&mut self, now: i64) {
+        let cutoff = now.saturating_sub(self.config.window_secs);
+        let mut floor_only = Vec::new();
+        self.senders.retain(|sender, window| {
+            window.prune(cutoff);
+            if !window.entries.is_empty() {
+                return true;
+            }
+            if window.floor >= cutoff {
+                floor_only.push((*sender, window.floor));
+            }
+            false
+        });
+        self.evicted_floors.retain(|_, floor| *floor >= cutoff);
+        for (sender, floor) in floor_only {
+            self.keep_floor(sender, floor);
+        }
+    }
+
+    /// Number of remembered PINGs
+    pub fn len(&self) -> usize {
+        self.senders.values().map(|w| w.entries.len()).sum()
+    }
+
+    /// Whether no PINGs are remembered
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn apply(&mut self, record: &ReplayRecord) {
+        match record {
+            ReplayRecord::Seen { sender, hash, timestamp } => {
+                let evicted_floors = &mut self.evicted_floors;
+                let window = self.senders.entry(*sender).or_insert_with(|| SenderWindow {
+                    entries: BTreeSet::new(),
+                    floor: evicted_floors.remove(sender).unwrap_or(0),
+                });
+                window.entries.insert((*timestamp, *hash));
+                while window.entries.len() > self.config.max_entries_per_sender {
+                    if let Some((evicted, _)) = window.entries.pop_first() {
+                        window.floor = window.floor.max(evicted);
+                    }
+                }
+                if self.senders.len() > self.config.max_senders {
+                    self.evict_least_recent(sender);
+                }
+            }
+            ReplayRecord::Floor { sender, timestamp } => match self.senders.get_mut(sender) {
+                Some(window) => window.floor = window.floor.max(*timestamp),
+                None => self.keep_floor(*sender, *timestamp),
+            },
+            ReplayRecord::SharedFloor { timestamp } => {
+                self.shared_floor = self.shared_floor.max(*timestamp);
+            }
+        }
+    }
+
+    /// Evict the sender whose newest PING is oldest, keeping its floor
+    fn evict_least_recent(&mut self, keep: &[u8; 32]) {
+        let least_recent = self
+            .senders
+            .iter()
+            .filter(|(sender, _)| *sender != keep)
+            .min_by_key(|(_, window)| window.newest())
+            .map(|(sender, _)| *sender);
+        if let Some(sender) = least_recent {
+            if let Some(window) = self.senders.remove(&sender) {
+                self.keep_floor(sender, window.newest());
+            }
+        }
+    }
+
+    /// Remember the floor of a sender that has no window, folding the lowest into the shared floor on overflow
+    fn keep_floor(&mut self, sender: [u8; 32], floor: i64) {
+        let kept = self.evicted_floors.entry(sender).or_insert(floor);
+        *kept = (*kept).max(floor);
+
+        if self.evicted_floors.len() > self.config.max_evicted_floors {
+            let lowest = self.evicted_floors.iter().min_by_key(|(_, floor)| **floor).map(|(s, f)| (*s, *f));
+            if let Some((sender, floor)) = lowest {
+                self.evicted_floors.remove(&sender);
+                self.shared_floor = self.shared_floor.max(floor);
+            }
+        }
+    }
+
+    /// Rewrite the log with only live entries once most of it is dead weight
+    fn compact_if_needed(&mut self) -> Result<()> {
+        let live = self.len() as u64 + self.senders.len() as u64 + self.evicted_floors.len() as u64 + 1;
+        let Some(store) = self.store.as_mut() else { return Ok(()) };
+        if store.record_count < COMPACTION_THRESHOLD || store.record_count < live * 2 {
+            return Ok(());
+        }
+
+        let mut records = Vec::new();
+        if self.shared_floor > 0 {
+            records.push(ReplayRecord::SharedFloor { timestamp: self.shared_floor });
+        }
+        for (sender, floor) in &self.evicted_floors {
+            records.push(ReplayRecord::Floor { sender: *sender, timestamp: *floor });
+        }
+        for (sender, window) in &self.senders {
+            if window.floor > 0 {
+                records.push(ReplayRecord::Floor { sender: *sender, timestamp: window.floor });
+            }
+            for (timestamp, hash) in &window.entries {
+                records.push(ReplayRecord::Seen { sender: *sender, hash: *hash, timestamp: *timestamp });
+            }
+        }
+        store.rewrite(&records)
+    }
+}
+
+impl SenderWindow {
+    fn prune(&mut self, cutoff: i64) {
+        self.entries = self.entries.split_off(&(cutoff, [0u8; 32]));
+    }
 
-/// Check if PING is a replay, and insert if not
+    /// Timestamp of the newest remembered PING (the floor if none)
+    fn newest(&self) -> i64 {
+        self.entries.last().map_or(self.floor, |(timestamp, _)| (*timestamp).max(self.floor))
+    }
+}
+
+impl ReplayStore {
+    fn append(&mut self, record: &ReplayRecord) -> Result<()> {
+        let frame = seal_frame(&self.key, self.record_count, record)?;
+        self.file.write_all(&frame)?;
+        self.file.sync_data()?;
+        self.record_count += 1;
+        Ok(())
+    }
+
+    /// Atomically replace the log (temp file, then rename)
+    fn rewrite(&mut self, records: &[ReplayRecord]) -> Result<()> {
+        let tmp_path = self.path.with_extension("compact");
+        let mut bytes = Vec::new();
+        for (index, record) in records.iter().enumerate() {
+            bytes.extend(seal_frame(&self.key, index as u64, record)?);
+        }
+        {
+            let mut tmp = File::create(&tmp_path)?;
+            tmp.write_all(&bytes)?;
+            tmp.sync_all()?;
+        }
+        std::fs::rename(&tmp_path, &self.path)?;
+
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        self.record_count = records.len() as u64;
+        Ok(())
+    }
+}
+
+fn frame_aad(index: u64) -> Vec<u8> {
+    let mut aad = REPLAY_AAD_PREFIX.to_vec();
+    aad.extend_from_slice(&index.to_be_bytes());
+    aad
+}
+
+fn seal_frame(key: &[u8; 32], index: u64, record: &ReplayRecord) -> Result<Vec<u8>> {
+    let mut plaintext = bincode::serialize(record).map_err(|_| ReplayGuardError::EncryptionFailed)?;
+    let cipher = XChaCha20Poly1305::new_from_slice(key).map_err(|_| ReplayGuardError::EncryptionFailed)?;
+    let mut nonce = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), Payload { msg: &plaintext, aad: &frame_aad(index) })
+        .map_err(|_| ReplayGuardError::EncryptionFailed);
+    plaintext.zeroize();
+    let ciphertext = ciphertext?;
+
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + ciphertext.len());
+    frame.extend_from_slice(&(ciphertext.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&nonce);
+    frame.extend_from_slice(&ciphertext);
+    Ok(frame)
+}
+
+/// Decrypt every complete frame
+///
+/// # Returns
+/// (records, length of the intact prefix of the log)
+fn replay_frames(bytes: &[u8], key: &[u8; 32]) -> Result<(Vec<ReplayRecord>, u64)> {
+    let cipher = XChaCha20Poly1305::new_from_slice(key).map_err(|_| ReplayGuardError::DecryptionFailed)?;
+    let mut records = Vec::new();
+    let mut offset = 0usize;
+
+    while bytes.len() - offset >= FRAME_HEADER_LEN {
+        let len = u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap()) as usize;
+        if len > MAX_FRAME_LEN {
+            return Err(ReplayGuardError::Corrupt);
+        }
+        let end = offset + FRAME_HEADER_LEN + len;
+        if end > bytes.len() {
+            break; // torn append
+        }
+
+        let nonce = XNonce::from_slice(&bytes[offset + 4..offset + FRAME_HEADER_LEN]);
+        let aad = frame_aad(records.len() as u64);
+        let plaintext = cipher
+            .decrypt(nonce, Payload { msg: &bytes[offset + FRAME_HEADER_LEN..end], aad: &aad })
+            .map_err(|_| ReplayGuardError::DecryptionFailed)?;
+        records.push(bincode::deserialize(&plaintext).map_err(|_| ReplayGuardError::Corrupt)?);
+        offset = end;
+    }
+
+    Ok((records, offset as u64))
+}
+
+/// Global PING replay guard (in-memory until init_replay_guard)
+static REPLAY_GUARD: Lazy<Mutex<ReplayGuard>> =
+    Lazy::new(|| Mutex::new(ReplayGuard::new(ReplayGuardConfig::default())));
+
+/// Replace the global guard with a persistent one (call once at startup)
+///
+/// # Arguments
+/// * `path` - Replay log file in app-private storage
+/// * `key` - 32-byte log encryption key
+/// * `config` - Acceptance window and bounds
+///
+/// # Returns
+/// Number of PINGs still remembered from previous runs
+pub fn init_replay_guard(path: &Path, key: &[u8; 32], config: ReplayGuardConfig) -> Result<usize> {
+    let guard = ReplayGuard::open(path, key, config, unix_now())?;
+    let remembered = guard.len();
+    *REPLAY_GUARD.lock().unwrap() = guard;
+    Ok(remembered)
+}
+
+/// Check if PING is a replay, and remember it if not
 ///
 /// Returns true if PING is NEW (should be processed)
-/// Returns false if PING is a REPLAY (should be dropped)
+/// Returns false if PING is a REPLAY, outside the acceptance window, or cannot be
+/// recorded (should be dropped)
 ///
 /// # Arguments
 /// * `sender_pubkey` - 32-byte Ed25519 public key of sender
 /// * `ping_hash` - 32-byte Blake3 hash of PING ciphertext
-pub fn check_ping_replay(sender_pubkey: [u8; 32], ping_hash: [u8; 32]) -> bool {
-    let mut cache = REPLAY_CACHE.lock().unwrap();
-    let key = (sender_pubkey, ping_hash);
+/// * `ping_timestamp` - Signed PingToken timestamp
+pub fn check_ping_replay(sender_pubkey: [u8; 32], ping_hash: [u8; 32], ping_timestamp: i64) -> bool {
+    let mut guard = REPLAY_GUARD.lock().unwrap();
 
-    if cache.contains(&key) {
-        // Replay detected!
-        log::warn!("⚠️  REPLAY ATTACK: Duplicate PING detected from sender {}",
-            hex::encode(&sender_pubkey[..8]));
-        return false;
+    match guard.check(sender_pubkey, ping_hash, ping_timestamp, unix_now()) {
+        Ok(ReplayVerdict::Fresh) => {
+            log::debug!("✓ PING replay check passed (cache size: {})", guard.len());
+            true
+        }
+        Ok(ReplayVerdict::Replay) => {
+            log::warn!("⚠️  REPLAY ATTACK: Duplicate PING detected from sender {}",
+                hex::encode(&sender_pubkey[..8]));
+            false
+        }
+        Ok(verdict) => {
+            log::warn!("Dropping PING from sender {}: {:?} (timestamp {})",
+                hex::encode(&sender_pubkey[..8]), verdict, ping_timestamp);
+            false
+        }
+        Err(e) => {
+            log::error!("Failed to record PING in replay log: {}", e);
+            false
+        }
     }
-
-    // New PING - add to cache
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs() as i64;
-
-    cache.put(key, now);
-
-    log::debug!("✓ PING replay check passed (cache size: {})", cache.len());
-    true
 }
 
 /// Compute Blake3 hash of PING wire bytes
@@ -50,17 +520,25 @@ pub fn compute_ping_hash(ping_bytes: &[u8]) -> [u8; 32] {
     *hash.as_bytes()
 }
 
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
 /// Clear the replay cache (for testing)
 #[cfg(test)]
 pub fn clear_replay_cache() {
-    let mut cache = REPLAY_CACHE.lock().unwrap();
-    cache.clear();
+    *REPLAY_GUARD.lock().unwrap() = ReplayGuard::new(ReplayGuardConfig::default());
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const NOW: i64 = 1_700_000_000;
+
     #[test]
     fn test_replay_detection() {
         clear_replay_cache();
@@ -69,10 +547,10 @@ mod tests {
         let ping_hash = [2u8; 32];
 
         // First PING should pass
-        assert!(check_ping_replay(sender, ping_hash));
+        assert!(check_ping_replay(sender, ping_hash, unix_now()));
 
         // Duplicate PING should be rejected
-        assert!(!check_ping_replay(sender, ping_hash));
+        assert!(!check_ping_replay(sender, ping_hash, unix_now()));
     }
 
     #[test]
@@ -84,7 +562,90 @@ mod tests {
         let ping_hash_2 = [3u8; 32];
 
         // Different PINGs should both pass
-        assert!(check_ping_replay(sender, ping_hash_1));
-        assert!(check_ping_replay(sender, ping_hash_2));
+        assert!(check_ping_replay(sender, ping_hash_1, unix_now()));
+        assert!(check_ping_replay(sender, ping_hash_2, unix_now()));
+    }
+
+    #[test]
+    fn test_acceptance_window() {
+        let config = ReplayGuardConfig { window_secs: 3600, max_future_skew_secs: 60, ..Default::default() };
+        let mut guard = ReplayGuard::new(config);
+        let sender = [1u8; 32];
+
+        assert_eq!(guard.check(sender, [1u8; 32], NOW - 3601, NOW).unwrap(), ReplayVerdict::Expired);
+        assert_eq!(guard.check(sender, [2u8; 32], NOW + 61, NOW).unwrap(), ReplayVerdict::FromFuture);
+        assert_eq!(guard.check(sender, [3u8; 32], NOW - 3600, NOW).unwrap(), ReplayVerdict::Fresh);
+        assert_eq!(guard.check(sender, [3u8; 32], NOW - 3600, NOW).unwrap(), ReplayVerdict::Replay);
+
+        // Once the entry leaves the window it is forgotten, and the PING is expired anyway
+        guard.prune(NOW + 1);
+        assert!(guard.is_empty());
+        assert_eq!(guard.check(sender, [3u8; 32], NOW - 3600, NOW + 1).unwrap(), ReplayVerdict::Expired);
+    }
+
+    #[test]
+    fn test_noisy_sender_cannot_evict_others() {
+        let config = ReplayGuardConfig { max_entries_per_sender: 4, max_senders: 2, ..Default::default() };
+        let mut guard = ReplayGuard::new(config);
+        let (quiet, noisy) = ([1u8; 32], [2u8; 32]);
+
+        assert!(guard.check(quiet, [0xAA; 32], NOW - 50, NOW).unwrap().is_fresh());
+        for i in 0..100u8 {
+            assert!(guard.check(noisy, [i; 32], NOW - 40 + i as i64, NOW).unwrap().is_fresh());
+        }
+
+        // The quiet sender's entry survives; the noisy sender's evicted PINGs stay rejected
+        assert_eq!(guard.check(quiet, [0xAA; 32], NOW - 50, NOW).unwrap(), ReplayVerdict::Replay);
+        assert_eq!(guard.check(noisy, [0u8; 32], NOW - 40, NOW).unwrap(), ReplayVerdict::Replay);
+        assert_eq!(guard.len(), 1 + 4);
+
+        // Sender table is full: a third sender evicts the least recently seen one, whose floor is kept
+        assert!(guard.check([3u8; 32], [0xBB; 32], NOW, NOW).unwrap().is_fresh());
+        assert_eq!(guard.check(quiet, [0xAA; 32], NOW - 50, NOW).unwrap(), ReplayVerdict::Replay);
+        assert!(guard.check(quiet, [0xAC; 32], NOW - 5, NOW).unwrap().is_fresh());
+    }
+
+    #[test]
+    fn test_sender_flood_cannot_lock_out_new_senders() {
+        let config = ReplayGuardConfig { max_senders: 4, max_evicted_floors: 8, ..Default::default() };
+        let mut guard = ReplayGuard::new(config);
+        let contact = [0xC0; 32];
+        assert!(guard.check(contact, [1u8; 32], NOW - 2_000, NOW).unwrap().is_fresh());
+
+        for i in 0..1_000u32 {
+            let mut sender = [0u8; 32];
+            sender[..4].copy_from_slice(&i.to_be_bytes());
+            assert!(guard.check(sender, [2u8; 32], NOW - 1_000 + i as i64, NOW).unwrap().is_fresh());
+        }
+
+        // A new sender is still accepted, and the flooded-out PING stays rejected via the shared floor
+        assert!(guard.check([0xD0; 32], [3u8; 32], NOW, NOW).unwrap().is_fresh());
+        assert_eq!(guard.check(contact, [1u8; 32], NOW - 2_000, NOW).unwrap(), ReplayVerdict::Replay);
+        assert!(guard.senders.len() <= 4 && guard.evicted_floors.len() <= 8);
+    }
+
+    #[test]
+    fn test_persists_across_restart() {
+        let path = std::env::temp_dir().join(format!("ping_replay_{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let key = [7u8; 32];
+        let config = ReplayGuardConfig { window_secs: 3600, ..Default::default() };
+
+        {
+            let mut guard = ReplayGuard::open(&path, &key, config, NOW).unwrap();
+            assert!(guard.check([1u8; 32], [1u8; 32], NOW - 10, NOW).unwrap().is_fresh());
+            assert!(guard.check([1u8; 32], [2u8; 32], NOW - 3000, NOW).unwrap().is_fresh());
+        }
+
+        // After a restart the PING is still known; entries that left the window are dropped
+        let mut guard = ReplayGuard::open(&path, &key, config, NOW + 1000).unwrap();
+        assert_eq!(guard.len(), 1);
+        assert_eq!(guard.check([1u8; 32], [1u8; 32], NOW - 10, NOW + 1000).unwrap(), ReplayVerdict::Replay);
+
+        assert!(matches!(
+            ReplayGuard::open(&path, &[8u8; 32], config, NOW),
+            Err(ReplayGuardError::DecryptionFailed)
+        ));
+        let _ = std::fs::remove_file(&path);
     }
 }
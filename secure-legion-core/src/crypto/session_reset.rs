@@ -0,0 +1,575 @@
+//! Session Healing
+//! Signed SESSION_RESET exchange that re-keys a contact whose chains desynchronized
+//!
+//! Flow (either side may start it, e.g. after repeated `SequenceTooFar` / `ReplayAttack`):
+//! 1. Initiator generates a fresh hybrid X25519 + ML-KEM keypair and sends a signed
+//!    `SessionResetInit` for epoch N+1.
+//! 2. Responder verifies it against the pinned identity key, encapsulates to the KEM key,
+//!    derives the new root and switches to epoch N+1, answering with a signed `SessionResetAck`.
+//! 3. Initiator decapsulates, derives the same root and switches.
+//!
+//! root_key(N+1) = HKDF(salt = SHA-256(init message), ikm = hybrid secret, info = label || N+1)
+//!
+//! After switching, the previous epoch's root stays usable for receiving during a grace
+//! period so messages already in flight still decrypt. If both sides start a reset for the
+//! same epoch at once, the init from the lower Ed25519 key wins.
+//!
+//! Inits and ACKs are only accepted within `MAX_RESET_CLOCK_SKEW_SECS` of our clock, and an
+//! init can never move a session to an epoch it has already reached, so a recorded reset
+//! cannot be replayed. Chain messages of epoch > 0 carry their epoch in the header
+//! (`encryption::WIRE_FLAG_EPOCH`), which picks the root they were sent under.
+
+use super::encryption::RootKey;
+use super::pqc::pq_ratchet::generate_random_kem_keypair;
+use super::pqc::{hybrid_decapsulate, hybrid_encapsulate, HybridCiphertext, HybridKEMKeypair, HybridKEMPublicKey};
+use super::signing::{verify_signature, SigningSecret};
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use serde_big_array::BigArray;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use zeroize::{Zeroize, Zeroizing};
+
+/// SESSION_RESET message format version
+pub const SESSION_RESET_VERSION: u8 = 0x01;
+
+/// Message kinds following MSG_TYPE_SESSION_RESET on the wire
+pub const SESSION_RESET_KIND_INIT: u8 = 0x01;
+pub const SESSION_RESET_KIND_ACK: u8 = 0x02;
+
+/// How long the previous epoch keeps decrypting in-flight messages
+pub const DEFAULT_GRACE_PERIOD_SECS: i64 = 600;
+
+/// Maximum distance between a reset message's timestamp and our clock
+pub const MAX_RESET_CLOCK_SKEW_SECS: i64 = 300;
+
+const SESSION_RESET_KDF_INFO: &[u8] = b"SecureLegion-SessionReset-v1";
+const RESET_INIT_DOMAIN: &[u8] = b"SecureLegion-SessionResetInit-v1";
+const RESET_ACK_DOMAIN: &[u8] = b"SecureLegion-SessionResetAck-v1";
+
+#[derive(Error, Debug)]
+pub enum SessionResetError {
+    #[error("Invalid session reset signature")]
+    InvalidSignature,
+    #[error("Stale session reset epoch: received {received}, current {current}")]
+    StaleEpoch { received: u32, current: u32 },
+    #[error("Session reset ACK does not match a pending reset")]
+    UnexpectedAck,
+    #[error("Concurrent session reset: our reset for epoch {0} takes precedence")]
+    ConcurrentReset(u32),
+    #[error("Unsupported session reset version: {0}")]
+    UnsupportedVersion(u8),
+    #[error("Session reset timestamp {timestamp} outside the freshness window (now {now})")]
+    Expired { timestamp: i64, now: i64 },
+    #[error("Malformed session reset message")]
+    Malformed,
+    #[error("Key generation failed")]
+    KeyGenerationFailed,
+    #[error("Key agreement failed")]
+    KeyAgreementFailed,
+    #[error("Session epoch counter exhausted")]
+    EpochExhausted,
+}
+
+pub type Result<T> = std::result::Result<T, SessionResetError>;
+
+/// Signed request to move the session to a new epoch
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SessionResetInit {
+    pub version: u8,
+    pub epoch: u32,
+    /// Fresh hybrid KEM key for this reset only
+    pub kem_public: HybridKEMPublicKey,
+    pub timestamp: i64,
+    #[serde(with = "BigArray")]
+    pub signature: [u8; 64],
+}
+
+/// Signed answer carrying the KEM ciphertext for the new root
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SessionResetAck {
+    pub version: u8,
+    pub epoch: u32,
+    /// SHA-256 of the init being answered
+    pub init_hash: [u8; 32],
+    pub ciphertext: HybridCiphertext,
+    pub timestamp: i64,
+    #[serde(with = "BigArray")]
+    pub signature: [u8; 64],
+}
+
+/// SESSION_RESET payload (after the MSG_TYPE_SESSION_RESET byte)
+#[derive(Clone)]
+pub enum SessionResetMessage {
+    Init(SessionResetInit),
+    Ack(SessionResetAck),
+}
+
+/// Where the reset handshake stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionResetPhase {
+    /// Sending and receiving on the current epoch
+    Established,
+    /// Our init for `epoch` is waiting for an ACK
+    AwaitingAck { epoch: u32 },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct EpochRoot {
+    epoch: u32,
+    root_key: RootKey,
+    /// Unix timestamp after which a previous epoch stops decrypting
+    expires_at: Option<i64>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct PendingReset {
+    epoch: u32,
+    kem_keypair: HybridKEMKeypair,
+    init: SessionResetInit,
+}
+
+/// Per-contact session healing state machine
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SessionHealer {
+    current: EpochRoot,
+    previous: Option<EpochRoot>,
+    pending: Option<PendingReset>,
+    grace_period_secs: i64,
+}
+
+impl SessionResetInit {
+    fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = RESET_INIT_DOMAIN.to_vec();
+        bytes.push(self.version);
+        bytes.extend_from_slice(&self.epoch.to_be_bytes());
+        bytes.extend_from_slice(&self.kem_public.x25519_public);
+        bytes.extend_from_slice(&self.kem_public.kyber_public);
+        bytes.extend_from_slice(&self.timestamp.to_be_bytes());
+        bytes
+    }
+
+    /// Transcript hash bound into the ACK and the new root
+    pub fn hash(&self) -> [u8; 32] {
+        Sha256::new()
+            .chain_update(self.signing_bytes())
+            .chain_update(self.signature)
+            .finalize()
+            .into()
+    }
+
+    pub fn verify(&self, signer_ed25519: &[u8; 32]) -> Result<()> {
+        if self.version != SESSION_RESET_VERSION {
+            return Err(SessionResetError::UnsupportedVersion(self.version));
+        }
+        match verify_signature(&self.signing_bytes(), &self.signature, signer_ed25519) {
+            Ok(true) => Ok(()),
+            _ => Err(SessionResetError::InvalidSignature),
+        }
+    }
+}
+
+impl SessionResetAck {
+    fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = RESET_ACK_DOMAIN.to_vec();
+        bytes.push(self.version);
+        bytes.extend_from_slice(&self.epoch.to_be_bytes());
+        bytes.extend_from_slice(&self.init_hash);
+        bytes.extend_from_slice(&self.ciphertext.x25519_ephemeral);
+        bytes.extend_from_slice(&self.ciphertext.kyber_ciphertext);
+        bytes.extend_from_slice(&self.timestamp.to_be_bytes());
+        bytes
+    }
+
+    pub fn verify(&self, signer_ed25519: &[u8; 32]) -> Result<()> {
+        if self.version != SESSION_RESET_VERSION {
+            return Err(SessionResetError::UnsupportedVersion(self.version));
+        }
+        match verify_signature(&self.signing_bytes(), &self.signature, signer_ed25519) {
+            Ok(true) => Ok(()),
+            _ => Err(SessionResetError::InvalidSignature),
+        }
+    }
+}
+
+impl SessionResetMessage {
+    /// Serialize as [kind][bincode body]
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let (kind, body) = match self {
+            SessionResetMessage::Init(init) => (SESSION_RESET_KIND_INIT, bincode::serialize(init)),
+            SessionResetMessage::Ack(ack) => (SESSION_RESET_KIND_ACK, bincode::serialize(ack)),
+        };
+        let body = body.map_err(|_| SessionResetError::Malformed)?;
+        let mut bytes = Vec::with_capacity(1 + body.len());
+        bytes.push(kind);
+        bytes.extend_from_slice(&body);
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let (&kind, body) = bytes.split_first().ok_or(SessionResetError::Malformed)?;
+        match kind {
+            SESSION_RESET_KIND_INIT => bincode::deserialize(body)
+                .map(SessionResetMessage::Init)
+                .map_err(|_| SessionResetError::Malformed),
+            SESSION_RESET_KIND_ACK => bincode::deserialize(body)
+                .map(SessionResetMessage::Ack)
+                .map_err(|_| SessionResetError::Malformed),
+            _ => Err(SessionResetError::Malformed),
+        }
+    }
+}
+
+impl SessionHealer {
+    /// Start tracking a session at `epoch` (0 for a session from the initial key exchange)
+    ///
+    /// # Arguments
+    /// * `root_key` - Current root key
+    /// * `epoch` - Current epoch
+    pub fn new(root_key: RootKey, epoch: u32) -> Self {
+        Self {
+            current: EpochRoot { epoch, root_key, expires_at: None },
+            previous: None,
+            pending: None,
+            grace_period_secs: DEFAULT_GRACE_PERIOD_SECS,
+        }
+    }
+
+    pub fn with_grace_period(mut self, grace_period_secs: i64) -> Self {
+        self.grace_period_secs = grace_period_secs;
+        self
+    }
+
+    pub fn epoch(&self) -> u32 {
+        self.current.epoch
+    }
+
+    /// Root key for sending (current epoch)
+    pub fn root_key(&self) -> &RootKey {
+        &self.current.root_key
+    }
+
+    pub fn phase(&self) -> SessionResetPhase {
+        match &self.pending {
+            Some(pending) => SessionResetPhase::AwaitingAck { epoch: pending.epoch },
+            None => SessionResetPhase::Established,
+        }
+    }
+
+    /// Begin a reset (or return the same init again while it is unanswered and still fresh)
+    ///
+    /// # Arguments
+    /// * `our_signing_private` - Our Ed25519 private key
+    /// * `now` - Unix timestamp
+    pub fn initiate(&mut self, our_signing_private: &[u8], now: i64) -> Result<SessionResetInit> {
+        if let Some(pending) = &self.pending {
+            if check_fresh(pending.init.timestamp, now).is_ok() {
+                return Ok(pending.init.clone());
+            }
+            // The peer would refuse the old init by now; start over with a fresh KEM key
+        }
+
+        let epoch = self.current.epoch.checked_add(1).ok_or(SessionResetError::EpochExhausted)?;
+        let kem_keypair = generate_random_kem_keypair().map_err(|_| SessionResetError::KeyGenerationFailed)?;
+        let mut init = SessionResetInit {
+            version: SESSION_RESET_VERSION,
+            epoch,
+            kem_public: HybridKEMPublicKey {
+                x25519_public: kem_keypair.x25519_public,
+                kyber_public: kem_keypair.kyber_public,
+            },
+            timestamp: now,
+            signature: [0u8; 64],
+        };
+        init.signature = SigningSecret::from_bytes(our_signing_private)
+            .map_err(|_| SessionResetError::InvalidSignature)?
+            .sign(&init.signing_bytes());
+
+        self.pending = Some(PendingReset { epoch: init.epoch, kem_keypair, init: init.clone() });
+        log::info!("Session reset initiated for epoch {}", init.epoch);
+        Ok(init)
+    }
+
+    /// Answer a peer's reset and switch to the new epoch
+    ///
+    /// # Arguments
+    /// * `init` - Received init
+    /// * `their_ed25519` - Peer's pinned Ed25519 key
+    /// * `our_ed25519` - Our Ed25519 public key (breaks ties between concurrent resets)
+    /// * `our_signing_private` - Our Ed25519 private key
+    /// * `now` - Unix timestamp
+    pub fn handle_init(
+        &mut self,
+        init: &SessionResetInit,
+        their_ed25519: &[u8; 32],
+        our_ed25519: &[u8; 32],
+        our_signing_private: &[u8],
+        now: i64,
+    ) -> Result<SessionResetAck> {
+        init.verify(their_ed25519)?;
+        check_fresh(init.timestamp, now)?;
+        if init.epoch <= self.current.epoch {
+            return Err(SessionResetError::StaleEpoch { received: init.epoch, current: self.current.epoch });
+        }
+        if let Some(pending) = &self.pending {
+            if pending.epoch >= init.epoch && our_ed25519 < their_ed25519 {
+                return Err(SessionResetError::ConcurrentReset(pending.epoch));
+            }
+        }
+
+        let (mut secret, ciphertext) = hybrid_encapsulate(&init.kem_public.x25519_public, &init.kem_public.kyber_public)
+            .map_err(|_| SessionResetError::KeyAgreementFailed)?;
+        let init_hash = init.hash();
+        let root_key = derive_epoch_root(&secret, &init_hash, init.epoch);
+        secret.zeroize();
+
+        let mut ack = SessionResetAck {
+            version: SESSION_RESET_VERSION,
+            epoch: init.epoch,
+            init_hash,
+            ciphertext,
+            timestamp: now,
+            signature: [0u8; 64],
+        };
+        ack.signature = SigningSecret::from_bytes(our_signing_private)
+            .map_err(|_| SessionResetError::InvalidSignature)?
+            .sign(&ack.signing_bytes());
+
+        self.pending = None;
+        self.switch_epoch(init.epoch, root_key?, now);
+        Ok(ack)
+    }
+
+    /// Complete our reset with the peer's ACK and switch to the new epoch
+    pub fn handle_ack(&mut self, ack: &SessionResetAck, their_ed25519: &[u8; 32], now: i64) -> Result<()> {
+        let pending = self.pending.as_ref().ok_or(SessionResetError::UnexpectedAck)?;
+        if ack.epoch != pending.epoch || ack.init_hash != pending.init.hash() {
+            return Err(SessionResetError::UnexpectedAck);
+        }
+        ack.verify(their_ed25519)?;
+        check_fresh(ack.timestamp, now)?;
+
+        let mut secret = hybrid_decapsulate(
+            &pending.kem_keypair.x25519_secret,
+            &pending.kem_keypair.kyber_secret,
+            &ack.ciphertext,
+        )
+        .map_err(|_| SessionResetError::KeyAgreementFailed)?;
+        let root_key = derive_epoch_root(&secret, &ack.init_hash, ack.epoch);
+        secret.zeroize();
+
+        self.pending = None;
+        self.switch_epoch(ack.epoch, root_key?, now);
+        Ok(())
+    }
+
+    /// Root key for an epoch, if it still decrypts (current, or previous within the grace period)
+    pub fn root_key_for_epoch(&self, epoch: u32, now: i64) -> Option<&RootKey> {
+        self.receive_root_keys(now)
+            .into_iter()
+            .find(|(e, _)| *e == epoch)
+            .map(|(_, root_key)| root_key)
+    }
+
+    /// Roots to try when decrypting, newest first
+    pub fn receive_root_keys(&self, now: i64) -> Vec<(u32, &RootKey)> {
+        let mut roots = vec![(self.current.epoch, &self.current.root_key)];
+        if let Some(previous) = &self.previous {
+            if previous.expires_at.is_some_and(|expires_at| now < expires_at) {
+                roots.push((previous.epoch, &previous.root_key));
+            }
+        }
+        roots
+    }
+
+    /// When the previous epoch stops decrypting (None if there is none)
+    pub fn grace_expires_at(&self) -> Option<i64> {
+        self.previous.as_ref().and_then(|previous| previous.expires_at)
+    }
+
+    /// Forget the previous epoch once its grace period is over
+    pub fn expire_grace(&mut self, now: i64) {
+        if self
+            .previous
+            .as_ref()
+            .is_some_and(|previous| previous.expires_at.is_none_or(|expires_at| now >= expires_at))
+        {
+            self.previous = None;
+        }
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self).map_err(|_| SessionResetError::Malformed)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        bincode::deserialize(bytes).map_err(|_| SessionResetError::Malformed)
+    }
+
+    fn switch_epoch(&mut self, epoch: u32, root_key: RootKey, now: i64) {
+        let mut previous = std::mem::replace(&mut self.current, EpochRoot { epoch, root_key, expires_at: None });
+        previous.expires_at = Some(now + self.grace_period_secs);
+        self.previous = Some(previous);
+        log::info!("Session switched to epoch {}", epoch);
+    }
+}
+
+fn check_fresh(timestamp: i64, now: i64) -> Result<()> {
+    if timestamp.abs_diff(now) > MAX_RESET_CLOCK_SKEW_SECS as u64 {
+        return Err(SessionResetError::Expired { timestamp, now });
+    }
+    Ok(())
+}
+
+fn derive_epoch_root(secret: &[u8], init_hash: &[u8; 32], epoch: u32) -> Result<RootKey> {
+    let mut info = SESSION_RESET_KDF_INFO.to_vec();
+    info.extend_from_slice(&epoch.to_be_bytes());
+    let mut root_key = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(Some(init_hash), secret)
+        .expand(&info, &mut *root_key)
+        .map_err(|_| SessionResetError::KeyAgreementFailed)?;
+    RootKey::from_bytes(&*root_key).map_err(|_| SessionResetError::KeyAgreementFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::signing::generate_keypair;
+
+    struct Party {
+        public: [u8; 32],
+        private: Vec<u8>,
+        healer: SessionHealer,
+    }
+
+    fn party(root_key: [u8; 32]) -> Party {
+        let (public, private) = generate_keypair();
+        Party { public, private: private.to_vec(), healer: SessionHealer::new(RootKey::from(root_key), 0) }
+    }
+
+    #[test]
+    fn test_reset_converges_with_grace_period() {
+        let mut alice = party([1u8; 32]);
+        let mut bob = party([2u8; 32]); // desynchronized
+
+        let init = alice.healer.initiate(&alice.private, 1_000).unwrap();
+        assert_eq!(alice.healer.phase(), SessionResetPhase::AwaitingAck { epoch: 1 });
+
+        // Round-trip through the wire encoding
+        let wire = SessionResetMessage::Init(init).to_bytes().unwrap();
+        let SessionResetMessage::Init(init) = SessionResetMessage::from_bytes(&wire).unwrap() else { panic!() };
+        let ack = bob.healer.handle_init(&init, &alice.public, &bob.public, &bob.private, 1_000).unwrap();
+        alice.healer.handle_ack(&ack, &bob.public, 1_001).unwrap();
+
+        assert_eq!(alice.healer.epoch(), 1);
+        assert_eq!(alice.healer.phase(), SessionResetPhase::Established);
+        assert_eq!(alice.healer.root_key().expose_secret(), bob.healer.root_key().expose_secret());
+        assert_ne!(alice.healer.root_key().expose_secret(), &[1u8; 32]);
+
+        // Old epoch still decrypts during the grace period, then expires
+        assert_eq!(bob.healer.root_key_for_epoch(0, 1_000 + 60).map(RootKey::expose_secret), Some(&[2u8; 32]));
+        assert_eq!(bob.healer.receive_root_keys(1_000 + DEFAULT_GRACE_PERIOD_SECS).len(), 1);
+        bob.healer.expire_grace(1_000 + DEFAULT_GRACE_PERIOD_SECS);
+        assert!(bob.healer.root_key_for_epoch(0, 1_000).is_none());
+
+        // The healer state survives persistence
+        let restored = SessionHealer::from_bytes(&bob.healer.to_bytes().unwrap()).unwrap();
+        assert_eq!(restored.root_key().expose_secret(), bob.healer.root_key().expose_secret());
+    }
+
+    #[test]
+    fn test_reset_rejects_forged_or_stale_messages() {
+        let mut alice = party([1u8; 32]);
+        let mut bob = party([1u8; 32]);
+        let mallory = party([0u8; 32]);
+
+        let init = alice.healer.initiate(&alice.private, 1_000).unwrap();
+        assert!(matches!(
+            bob.healer.handle_init(&init, &mallory.public, &bob.public, &bob.private, 1_000),
+            Err(SessionResetError::InvalidSignature)
+        ));
+
+        let ack = bob.healer.handle_init(&init, &alice.public, &bob.public, &bob.private, 1_000).unwrap();
+        // Replaying the same init cannot roll the epoch again
+        assert!(matches!(
+            bob.healer.handle_init(&init, &alice.public, &bob.public, &bob.private, 1_001),
+            Err(SessionResetError::StaleEpoch { received: 1, current: 1 })
+        ));
+        // An ACK signed by someone else is refused and leaves the reset pending
+        assert!(alice.healer.handle_ack(&ack, &mallory.public, 1_001).is_err());
+        assert_eq!(alice.healer.phase(), SessionResetPhase::AwaitingAck { epoch: 1 });
+        alice.healer.handle_ack(&ack, &bob.public, 1_001).unwrap();
+        assert!(matches!(alice.healer.handle_ack(&ack, &bob.public, 1_002), Err(SessionResetError::UnexpectedAck)));
+    }
+
+    #[test]
+    fn test_reset_outside_freshness_window_is_refused() {
+        let mut alice = party([1u8; 32]);
+        let mut bob = party([1u8; 32]);
+
+        // A recorded init replayed later (or one from a badly skewed clock) does not re-key
+        let init = alice.healer.initiate(&alice.private, 1_000).unwrap();
+        let late = 1_000 + MAX_RESET_CLOCK_SKEW_SECS + 1;
+        assert!(matches!(
+            bob.healer.handle_init(&init, &alice.public, &bob.public, &bob.private, late),
+            Err(SessionResetError::Expired { timestamp: 1_000, .. })
+        ));
+        assert_eq!(bob.healer.epoch(), 0);
+
+        // Same for an ACK that arrives too late
+        let ack = bob.healer.handle_init(&init, &alice.public, &bob.public, &bob.private, 1_010).unwrap();
+        assert!(matches!(
+            alice.healer.handle_ack(&ack, &bob.public, 1_010 + MAX_RESET_CLOCK_SKEW_SECS + 1),
+            Err(SessionResetError::Expired { .. })
+        ));
+        assert_eq!(alice.healer.phase(), SessionResetPhase::AwaitingAck { epoch: 1 });
+        assert_eq!(bob.healer.grace_expires_at(), Some(1_010 + DEFAULT_GRACE_PERIOD_SECS));
+
+        // An unanswered init is repeated while fresh and replaced once it would be refused
+        let mut carol = party([1u8; 32]);
+        let first = carol.healer.initiate(&carol.private, 2_000).unwrap();
+        assert_eq!(carol.healer.initiate(&carol.private, 2_100).unwrap().hash(), first.hash());
+        let renewed = carol.healer.initiate(&carol.private, 2_000 + MAX_RESET_CLOCK_SKEW_SECS + 1).unwrap();
+        assert_ne!(renewed.hash(), first.hash());
+        assert_eq!(renewed.epoch, 1);
+    }
+
+    #[test]
+    fn test_initiate_at_last_epoch_fails() {
+        let (_, private) = generate_keypair();
+        let mut healer = SessionHealer::new(RootKey::from([1u8; 32]), u32::MAX);
+
+        assert!(matches!(healer.initiate(&private, 1_000), Err(SessionResetError::EpochExhausted)));
+        assert_eq!(healer.phase(), SessionResetPhase::Established);
+    }
+
+    #[test]
+    fn test_concurrent_resets_pick_one_winner() {
+        let mut alice = party([1u8; 32]);
+        let mut bob = party([1u8; 32]);
+
+        let alice_init = alice.healer.initiate(&alice.private, 1_000).unwrap();
+        let bob_init = bob.healer.initiate(&bob.private, 1_000).unwrap();
+
+        let alice_result = alice.healer.handle_init(&bob_init, &bob.public, &alice.public, &alice.private, 1_000);
+        let bob_result = bob.healer.handle_init(&alice_init, &alice.public, &bob.public, &bob.private, 1_000);
+
+        // Exactly one side answers (the lower key's reset wins); the other completes with that answer
+        let alice_wins = alice.public < bob.public;
+        match (alice_result, bob_result) {
+            (Err(SessionResetError::ConcurrentReset(1)), Ok(ack)) => {
+                assert!(alice_wins);
+                alice.healer.handle_ack(&ack, &bob.public, 1_001).unwrap();
+            }
+            (Ok(ack), Err(SessionResetError::ConcurrentReset(1))) => {
+                assert!(!alice_wins);
+                bob.healer.handle_ack(&ack, &alice.public, 1_001).unwrap();
+            }
+            _ => panic!("expected exactly one side to yield"),
+        }
+
+        assert_eq!(alice.healer.epoch(), 1);
+        assert_eq!(bob.healer.epoch(), 1);
+        assert_eq!(alice.healer.root_key().expose_secret(), bob.healer.root_key().expose_secret());
+    }
+}
@@ -1,6 +1,7 @@
 use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use rand::rngs::OsRng;
 use thiserror::Error;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 #[derive(Error, Debug)]
 pub enum SigningError {
@@ -16,6 +17,58 @@ pub enum SigningError {
 
 pub type Result<T> = std::result::Result<T, SigningError>;
 
+/// Ed25519 private key (32-byte seed), wiped on drop
+///
+/// Only signing and public key derivation are exposed; `Debug` never prints the key.
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub struct SigningSecret([u8; 32]);
+
+impl SigningSecret {
+    /// Generate a random signing key
+    pub fn generate() -> Self {
+        Self(SigningKey::generate(&mut OsRng).to_bytes())
+    }
+
+    /// Load a signing key from untrusted bytes
+    ///
+    /// # Arguments
+    /// * `bytes` - 32-byte Ed25519 private key
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let key: [u8; 32] = bytes.try_into().map_err(|_| SigningError::InvalidKeyLength)?;
+        Ok(Self(key))
+    }
+
+    /// Sign data
+    ///
+    /// # Returns
+    /// 64-byte signature
+    pub fn sign(&self, data: &[u8]) -> [u8; 64] {
+        SigningKey::from_bytes(&self.0).sign(data).to_bytes()
+    }
+
+    /// 32-byte Ed25519 public key
+    pub fn public_key(&self) -> [u8; 32] {
+        SigningKey::from_bytes(&self.0).verifying_key().to_bytes()
+    }
+
+    /// Raw key bytes, for handing back to KeyManager storage only
+    pub fn expose_secret(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl From<[u8; 32]> for SigningSecret {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl std::fmt::Debug for SigningSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SigningSecret([REDACTED])")
+    }
+}
+
 /// Generate an Ed25519 keypair
 ///
 /// # Returns
@@ -37,20 +90,9 @@ pub fn generate_keypair() -> ([u8; 32], [u8; 32]) {
 ///
 /// # Returns
 /// 64-byte signature
+#[deprecated(note = "use SigningSecret::sign")]
 pub fn sign_data(data: &[u8], private_key: &[u8]) -> Result<[u8; 64]> {
-    if private_key.len() != 32 {
-        return Err(SigningError::InvalidKeyLength);
-    }
-
-    // Create signing key from bytes
-    let mut key_bytes = [0u8; 32];
-    key_bytes.copy_from_slice(private_key);
-    let signing_key = SigningKey::from_bytes(&key_bytes);
-
-    // Sign
-    let signature = signing_key.sign(data);
-
-    Ok(signature.to_bytes())
+    Ok(SigningSecret::from_bytes(private_key)?.sign(data))
 }
 
 /// Verify Ed25519 signature
@@ -109,6 +151,7 @@ pub fn derive_public_key(private_key: &[u8]) -> Result<[u8; 32]> {
 }
 
 #[cfg(test)]
+#[allow(deprecated)]
 mod tests {
     use super::*;
 
@@ -148,4 +191,15 @@ mod tests {
 
         assert_eq!(expected_public, derived_public);
     }
+
+    #[test]
+    fn test_signing_secret() {
+        let secret = SigningSecret::generate();
+        let signature = secret.sign(b"typed");
+        assert!(verify_signature(b"typed", &signature, &secret.public_key()).unwrap());
+        assert_eq!(sign_data(b"typed", secret.expose_secret()).unwrap(), signature);
+
+        assert!(matches!(SigningSecret::from_bytes(&[0u8; 31]), Err(SigningError::InvalidKeyLength)));
+        assert_eq!(format!("{:?}", secret), "SigningSecret([REDACTED])");
+    }
 }
@@ -0,0 +1,260 @@
+//! Skipped Message Key Store
+//! Bounded cache of message keys for messages that have not arrived yet
+//!
+//! When a message with sequence N arrives while we expect M < N, the chain is advanced
+//! to N and the keys for M..N are parked here. A late message then decrypts with a
+//! single lookup instead of re-deriving the chain from the root.
+//!
+//! Keys are evicted oldest-first once the store is full, and dropped after a maximum
+//! age so a sender cannot make us hold old keys (and their plaintexts) forever.
+//! Evicted and consumed keys are zeroized.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Default maximum number of skipped keys held per session
+pub const DEFAULT_MAX_SKIPPED_KEYS: usize = 1000;
+
+/// Default maximum age of a skipped key (7 days)
+pub const DEFAULT_MAX_SKIPPED_KEY_AGE_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Chain identifier used by the linear (v1) chain, which only has one receiving chain
+pub const LINEAR_CHAIN_ID: [u8; 32] = [0u8; 32];
+
+/// Skipped key lookup: (chain identifier, sequence)
+type SkippedKeyId = ([u8; 32], u64);
+
+#[derive(Clone, Serialize, Deserialize)]
+struct SkippedKey {
+    message_key: [u8; 32],
+    stored_at: i64,
+}
+
+/// Bounded store of skipped message keys for one session
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SkippedKeyStore {
+    keys: HashMap<SkippedKeyId, SkippedKey>,
+    /// Insertion order, oldest first (may contain ids already taken)
+    order: VecDeque<SkippedKeyId>,
+    max_keys: usize,
+    max_age_secs: i64,
+}
+
+impl SkippedKeyStore {
+    /// Create a store with explicit bounds
+    ///
+    /// # Arguments
+    /// * `max_keys` - Maximum number of keys held before the oldest is evicted
+    /// * `max_age_secs` - Keys older than this are discarded
+    pub fn new(max_keys: usize, max_age_secs: i64) -> Self {
+        Self {
+            keys: HashMap::new(),
+            order: VecDeque::new(),
+            max_keys,
+            max_age_secs,
+        }
+    }
+
+    /// Number of keys currently held
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Whether the store holds no keys
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Whether a key for (chain_id, sequence) is held
+    pub fn contains(&self, chain_id: &[u8; 32], sequence: u64) -> bool {
+        self.keys.contains_key(&(*chain_id, sequence))
+    }
+
+    /// Store the message key for a skipped message
+    ///
+    /// Evicts expired keys, then the oldest keys until the store is within `max_keys`.
+    pub fn insert(&mut self, chain_id: [u8; 32], sequence: u64, message_key: &[u8; 32]) {
+        self.insert_at(chain_id, sequence, message_key, unix_now());
+    }
+
+    /// Remove and return the key for (chain_id, sequence), if held and not expired
+    pub fn take(&mut self, chain_id: &[u8; 32], sequence: u64) -> Option<Zeroizing<[u8; 32]>> {
+        self.take_at(chain_id, sequence, unix_now())
+    }
+
+    /// Return a copy of the key for (chain_id, sequence) without consuming it
+    ///
+    /// Use with `remove` once the message authenticated, so a forged message
+    /// neither consumes nor refreshes the parked key.
+    pub fn peek(&self, chain_id: &[u8; 32], sequence: u64) -> Option<Zeroizing<[u8; 32]>> {
+        self.peek_at(chain_id, sequence, unix_now())
+    }
+
+    /// Drop the key for (chain_id, sequence), if held
+    pub fn remove(&mut self, chain_id: &[u8; 32], sequence: u64) {
+        if let Some(mut entry) = self.keys.remove(&(*chain_id, sequence)) {
+            entry.message_key.zeroize();
+        }
+    }
+
+    /// Drop every key older than the maximum age
+    pub fn evict_expired(&mut self) {
+        self.evict_expired_at(unix_now());
+    }
+
+    fn insert_at(&mut self, chain_id: [u8; 32], sequence: u64, message_key: &[u8; 32], now: i64) {
+        self.evict_expired_at(now);
+
+        let id = (chain_id, sequence);
+        if let Some(mut old) = self.keys.insert(id, SkippedKey { message_key: *message_key, stored_at: now }) {
+            old.message_key.zeroize();
+            // Re-stored key is now the newest; keep order equal to age for eviction
+            if let Some(position) = self.order.iter().position(|queued| *queued == id) {
+                self.order.remove(position);
+            }
+        }
+        self.order.push_back(id);
+
+        while self.keys.len() > self.max_keys {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    if let Some(mut evicted) = self.keys.remove(&oldest) {
+                        evicted.message_key.zeroize();
+                    }
+                }
+                None => break,
+            }
+        }
+
+        // Taken keys leave stale ids behind; compact once they dominate
+        if self.order.len() > self.keys.len() * 2 + 16 {
+            let keys = &self.keys;
+            self.order.retain(|id| keys.contains_key(id));
+        }
+    }
+
+    fn take_at(&mut self, chain_id: &[u8; 32], sequence: u64, now: i64) -> Option<Zeroizing<[u8; 32]>> {
+        let mut entry = self.keys.remove(&(*chain_id, sequence))?;
+        let message_key = Zeroizing::new(entry.message_key);
+        entry.message_key.zeroize();
+        if now - entry.stored_at > self.max_age_secs {
+            return None;
+        }
+        Some(message_key)
+    }
+
+    fn peek_at(&self, chain_id: &[u8; 32], sequence: u64, now: i64) -> Option<Zeroizing<[u8; 32]>> {
+        let entry = self.keys.get(&(*chain_id, sequence))?;
+        if now - entry.stored_at > self.max_age_secs {
+            return None;
+        }
+        Some(Zeroizing::new(entry.message_key))
+    }
+
+    fn evict_expired_at(&mut self, now: i64) {
+        // Insertion order is also age order, so expired keys sit at the front
+        while let Some(&id) = self.order.front() {
+            match self.keys.get(&id) {
+                Some(entry) if now - entry.stored_at <= self.max_age_secs => break,
+                Some(_) => {
+                    if let Some(mut expired) = self.keys.remove(&id) {
+                        expired.message_key.zeroize();
+                    }
+                }
+                None => {}
+            }
+            self.order.pop_front();
+        }
+    }
+}
+
+impl Default for SkippedKeyStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_SKIPPED_KEYS, DEFAULT_MAX_SKIPPED_KEY_AGE_SECS)
+    }
+}
+
+impl Drop for SkippedKeyStore {
+    fn drop(&mut self) {
+        for entry in self.keys.values_mut() {
+            entry.message_key.zeroize();
+        }
+    }
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_is_single_use() {
+        let mut store = SkippedKeyStore::default();
+        store.insert(LINEAR_CHAIN_ID, 5, &[7u8; 32]);
+
+        assert_eq!(store.take(&LINEAR_CHAIN_ID, 5).as_deref(), Some(&[7u8; 32]));
+        assert_eq!(store.take(&LINEAR_CHAIN_ID, 5), None);
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_count_eviction_drops_oldest() {
+        let mut store = SkippedKeyStore::new(3, DEFAULT_MAX_SKIPPED_KEY_AGE_SECS);
+        for sequence in 0..5u64 {
+            store.insert(LINEAR_CHAIN_ID, sequence, &[sequence as u8; 32]);
+        }
+
+        assert_eq!(store.len(), 3);
+        assert!(!store.contains(&LINEAR_CHAIN_ID, 0));
+        assert!(!store.contains(&LINEAR_CHAIN_ID, 1));
+        assert!(store.contains(&LINEAR_CHAIN_ID, 4));
+    }
+
+    #[test]
+    fn test_age_eviction() {
+        let mut store = SkippedKeyStore::new(10, 60);
+        store.insert_at(LINEAR_CHAIN_ID, 1, &[1u8; 32], 1_000);
+        store.insert_at(LINEAR_CHAIN_ID, 2, &[2u8; 32], 1_050);
+
+        // Key 1 is too old to use even though it is still held
+        assert_eq!(store.take_at(&LINEAR_CHAIN_ID, 1, 1_061), None);
+
+        store.evict_expired_at(1_200);
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_reinsert_moves_key_to_back() {
+        let mut store = SkippedKeyStore::new(10, 60);
+        store.insert_at(LINEAR_CHAIN_ID, 1, &[1u8; 32], 1_000);
+        store.insert_at(LINEAR_CHAIN_ID, 2, &[2u8; 32], 1_010);
+        store.insert_at(LINEAR_CHAIN_ID, 1, &[1u8; 32], 1_050);
+
+        // The refreshed key no longer shields the expired one behind it
+        store.evict_expired_at(1_080);
+        assert!(store.contains(&LINEAR_CHAIN_ID, 1));
+        assert!(!store.contains(&LINEAR_CHAIN_ID, 2));
+    }
+
+    #[test]
+    fn test_peek_keeps_original_age() {
+        let mut store = SkippedKeyStore::new(10, 60);
+        store.insert_at(LINEAR_CHAIN_ID, 1, &[1u8; 32], 1_000);
+
+        // Repeated peeks (failed decrypt attempts) neither consume nor refresh the key
+        assert_eq!(store.peek_at(&LINEAR_CHAIN_ID, 1, 1_050).as_deref(), Some(&[1u8; 32]));
+        assert_eq!(store.peek_at(&LINEAR_CHAIN_ID, 1, 1_055).as_deref(), Some(&[1u8; 32]));
+        assert_eq!(store.peek_at(&LINEAR_CHAIN_ID, 1, 1_061), None);
+
+        store.remove(&LINEAR_CHAIN_ID, 1);
+        assert!(store.is_empty());
+    }
+}
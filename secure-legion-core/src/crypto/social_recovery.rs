@@ -0,0 +1,695 @@
+//! Social Recovery
+//! k-of-n Shamir secret sharing of the wallet seed phrase over GF(256)
+//!
+//! The shared secret is the 16-byte BIP39 entropy of KeyManager's 12-word seed phrase,
+//! the same identity an identity backup carries (see `crypto::backup`); the recovered
+//! phrase is restored with KeyManager.initializeFromSeed.
+//!
+//! Flow:
+//! 1. The owner splits the entropy into n shares and sends each guardian a `Deposit`,
+//!    sealed to the guardian's X25519 + Kyber key (`hybrid_encapsulate`).
+//! 2. After losing the device, the owner starts a `RecoverySession` on the new device,
+//!    which sends a `Request` carrying a fresh hybrid public key to each guardian.
+//! 3. A guardian who has confirmed the request out of band (call, in person) answers with
+//!    a `Release`: the share re-sealed to the requester's key.
+//! 4. Once k shares arrive the phrase is rebuilt and checked against the share set digest.
+//!
+//! All three travel as MSG_TYPE_RECOVERY_SHARE with a kind byte (see `RecoveryMessage`).
+//! Each share carries a digest of the secret bound to its set, so a corrupted or
+//! malicious share is detected. Shares are grouped by (set, threshold, digest) and every
+//! group and k-subset is tried, so a forged share, whether first or not, cannot block
+//! recovery while k honest shares are present. Groups are tried smallest first, each with
+//! its own attempt budget, and a session takes only a few releases per sender, so a large
+//! forged group neither starves the honest one nor costs unbounded work. A complete forged
+//! set still combines to the forger's phrase, so the app has the owner confirm the restored
+//! identity first.
+
+use super::backup::parse_seed_phrase;
+use super::pqc::pq_ratchet::generate_random_kem_keypair;
+use super::pqc::{
+    hybrid_decapsulate, hybrid_encapsulate, HybridCiphertext, HybridKEMKeypair, HybridKEMPublicKey,
+    KYBER_PUBLIC_KEY_BYTES, KYBER_SECRET_KEY_BYTES,
+};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, OsRng, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use hkdf::Hkdf;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_big_array::BigArray;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// Recovery share format version (v1 shared a 32-byte seed KeyManager never used)
+pub const RECOVERY_VERSION: u8 = 0x02;
+
+/// Length of the shared secret: BIP39 entropy of a 12-word seed phrase
+pub const SECRET_LEN: usize = 16;
+
+/// Message kinds following MSG_TYPE_RECOVERY_SHARE on the wire
+pub const RECOVERY_KIND_DEPOSIT: u8 = 0x01;
+pub const RECOVERY_KIND_REQUEST: u8 = 0x02;
+pub const RECOVERY_KIND_RELEASE: u8 = 0x03;
+
+/// Most k-subsets tried per share group when it holds more than k shares
+const MAX_GROUP_ATTEMPTS: usize = 10_000;
+
+/// Most distinct releases a recovery session keeps (one per possible share index)
+const MAX_SESSION_SHARES: usize = 255;
+
+/// Most releases a recovery session takes from one sender (a guardian may hold shares of
+/// several splits, but never many)
+const MAX_SHARES_PER_SENDER: usize = 4;
+
+const SHARE_KDF_INFO: &[u8] = b"SecureLegion-RecoveryShare-v1";
+const SEED_CHECK_DOMAIN: &[u8] = b"SecureLegion-RecoverySeedCheck-v1";
+
+#[derive(Error, Debug)]
+pub enum RecoveryError {
+    #[error("Invalid threshold {threshold} for {shares} shares")]
+    InvalidThreshold { threshold: u8, shares: u8 },
+    #[error("Need {needed} shares, have {have}")]
+    NotEnoughShares { needed: u8, have: usize },
+    #[error("Recovered secret failed the integrity check")]
+    ShareIntegrity,
+    #[error("Invalid seed phrase: {0}")]
+    InvalidSeedPhrase(String),
+    #[error("Too many shares received")]
+    TooManyShares,
+    #[error("Too many shares received from this sender")]
+    TooManySharesFromSender,
+    #[error("A share group has more combinations than can be checked")]
+    TooManyCandidates,
+    #[error("Unsupported recovery version: {0}")]
+    UnsupportedVersion(u8),
+    #[error("Malformed recovery message")]
+    Malformed,
+    #[error("Invalid key length")]
+    InvalidKeyLength,
+    #[error("Key agreement failed")]
+    KeyAgreementFailed,
+    #[error("Share decryption failed")]
+    DecryptionFailed,
+}
+
+pub type Result<T> = std::result::Result<T, RecoveryError>;
+
+/// One Shamir share of the seed phrase entropy
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub struct Share {
+    /// Random identifier shared by all shares of one split
+    pub set_id: [u8; 16],
+    pub threshold: u8,
+    /// x coordinate (1..=n)
+    pub index: u8,
+    /// y coordinates, one per secret byte
+    value: [u8; SECRET_LEN],
+    /// SHA-256(domain || set_id || threshold || secret), checked after combining
+    seed_check: [u8; 32],
+}
+
+/// A share encrypted to one hybrid public key
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SealedShare {
+    pub version: u8,
+    pub kem_ciphertext: HybridCiphertext,
+    pub nonce: [u8; 24],
+    pub ciphertext: Vec<u8>,
+}
+
+/// New device asking a guardian for its share
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RecoveryRequest {
+    pub version: u8,
+    pub x25519_public: [u8; 32],
+    #[serde(with = "BigArray")]
+    pub kyber_public: [u8; KYBER_PUBLIC_KEY_BYTES],
+}
+
+/// Body of a MSG_TYPE_RECOVERY_SHARE message
+pub enum RecoveryMessage {
+    /// Owner -> guardian: share to hold
+    Deposit(SealedShare),
+    /// New device -> guardian: please release the share
+    Request(RecoveryRequest),
+    /// Guardian -> new device: share re-sealed to the request key
+    Release(SealedShare),
+}
+
+/// Recovery state on the new device
+pub struct RecoverySession {
+    /// Ephemeral key guardians seal releases to (zeroized on drop)
+    keypair: HybridKEMKeypair,
+    shares: Vec<Share>,
+    /// Releases accepted per sender X25519 key
+    received_from: HashMap<[u8; 32], usize>,
+}
+
+impl Share {
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self).map_err(|_| RecoveryError::Malformed)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        bincode::deserialize(bytes).map_err(|_| RecoveryError::Malformed)
+    }
+}
+
+impl RecoveryMessage {
+    /// Serialize as [kind][bincode body]
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let (kind, body) = match self {
+            RecoveryMessage::Deposit(sealed) => (RECOVERY_KIND_DEPOSIT, bincode::serialize(sealed)),
+            RecoveryMessage::Request(request) => (RECOVERY_KIND_REQUEST, bincode::serialize(request)),
+            RecoveryMessage::Release(sealed) => (RECOVERY_KIND_RELEASE, bincode::serialize(sealed)),
+        };
+        let body = body.map_err(|_| RecoveryError::Malformed)?;
+        let mut bytes = Vec::with_capacity(1 + body.len());
+        bytes.push(kind);
+        bytes.extend_from_slice(&body);
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let (&kind, body) = bytes.split_first().ok_or(RecoveryError::Malformed)?;
+        match kind {
+            RECOVERY_KIND_DEPOSIT => bincode::deserialize(body)
+                .map(RecoveryMessage::Deposit)
+                .map_err(|_| RecoveryError::Malformed),
+            RECOVERY_KIND_REQUEST => bincode::deserialize(body)
+                .map(RecoveryMessage::Request)
+                .map_err(|_| RecoveryError::Malformed),
+            RECOVERY_KIND_RELEASE => bincode::deserialize(body)
+                .map(RecoveryMessage::Release)
+                .map_err(|_| RecoveryError::Malformed),
+            _ => Err(RecoveryError::Malformed),
+        }
+    }
+}
+
+impl RecoverySession {
+    /// Start recovery with a fresh hybrid keypair for guardians to seal to
+    pub fn new() -> Result<Self> {
+        let keypair = generate_random_kem_keypair().map_err(|_| RecoveryError::KeyAgreementFailed)?;
+        Ok(Self { keypair, shares: Vec::new(), received_from: HashMap::new() })
+    }
+
+    /// Request to send to every guardian
+    pub fn request(&self) -> RecoveryRequest {
+        RecoveryRequest {
+            version: RECOVERY_VERSION,
+            x25519_public: self.keypair.x25519_public,
+            kyber_public: self.keypair.kyber_public,
+        }
+    }
+
+    /// Add a guardian's release
+    ///
+    /// Releases from other sets or with conflicting values are kept as candidates
+    /// rather than rejected, so a forged release cannot lock out the honest ones.
+    ///
+    /// # Arguments
+    /// * `sender` - X25519 key of the contact the release came from
+    /// * `sealed` - The release
+    ///
+    /// # Returns
+    /// The seed phrase once enough valid shares have arrived, otherwise None.
+    /// `TooManyCandidates` means the share was kept but a group could not be fully checked.
+    pub fn add_release(&mut self, sender: &[u8; 32], sealed: &SealedShare) -> Result<Option<Zeroizing<String>>> {
+        let share = open_share(sealed, &self.keypair.x25519_secret, &self.keypair.kyber_secret)?;
+        if self.shares.contains(&share) {
+            return Ok(None);
+        }
+        if self.shares.len() >= MAX_SESSION_SHARES {
+            return Err(RecoveryError::TooManyShares);
+        }
+        let received = self.received_from.entry(*sender).or_insert(0);
+        if *received >= MAX_SHARES_PER_SENDER {
+            return Err(RecoveryError::TooManySharesFromSender);
+        }
+        *received += 1;
+        self.shares.push(share);
+
+        match recover_seed_phrase(&self.shares) {
+            Ok(phrase) => Ok(Some(phrase)),
+            Err(RecoveryError::NotEnoughShares { .. }) | Err(RecoveryError::ShareIntegrity) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn shares_received(&self) -> usize {
+        self.shares.len()
+    }
+}
+
+/// Split a seed phrase into `shares` shares, any `threshold` of which rebuild it
+///
+/// # Arguments
+/// * `seed_phrase` - KeyManager's 12-word wallet seed phrase
+/// * `threshold` - Shares needed to recover (k, at least 1)
+/// * `shares` - Shares to produce (n, at least k)
+pub fn split_seed_phrase(seed_phrase: &str, threshold: u8, shares: u8) -> Result<Vec<Share>> {
+    let mnemonic = parse_seed_phrase(seed_phrase).map_err(|e| RecoveryError::InvalidSeedPhrase(e.to_string()))?;
+    let (mut entropy, len) = mnemonic.to_entropy_array();
+    let secret: Result<[u8; SECRET_LEN]> =
+        entropy[..len].try_into().map_err(|_| RecoveryError::InvalidSeedPhrase("unexpected entropy length".into()));
+    entropy.zeroize();
+    let secret = Zeroizing::new(secret?);
+    split_secret(&secret, threshold, shares)
+}
+
+/// Rebuild the seed phrase from shares (see `combine_shares`)
+pub fn recover_seed_phrase(shares: &[Share]) -> Result<Zeroizing<String>> {
+    let secret = combine_shares(shares)?;
+    bip39::Mnemonic::from_entropy(secret.as_slice())
+        .map(|mnemonic| Zeroizing::new(mnemonic.to_string()))
+        .map_err(|e| RecoveryError::InvalidSeedPhrase(e.to_string()))
+}
+
+/// Split a secret into `shares` shares, any `threshold` of which rebuild it
+///
+/// # Arguments
+/// * `secret` - Seed phrase entropy
+/// * `threshold` - Shares needed to recover (k, at least 1)
+/// * `shares` - Shares to produce (n, at least k)
+pub fn split_secret(secret: &[u8; SECRET_LEN], threshold: u8, shares: u8) -> Result<Vec<Share>> {
+    if threshold == 0 || shares < threshold {
+        return Err(RecoveryError::InvalidThreshold { threshold, shares });
+    }
+
+    let mut set_id = [0u8; 16];
+    OsRng.fill_bytes(&mut set_id);
+    let seed_check = seed_check(&set_id, threshold, secret);
+
+    // One random polynomial of degree k-1 per secret byte, constant term = the byte
+    let mut coefficients = vec![[0u8; SECRET_LEN]; threshold as usize];
+    coefficients[0] = *secret;
+    for row in coefficients.iter_mut().skip(1) {
+        OsRng.fill_bytes(row);
+    }
+
+    let result = (1..=shares)
+        .map(|x| {
+            let mut value = [0u8; SECRET_LEN];
+            for (byte, y) in value.iter_mut().enumerate() {
+                // Horner's rule from the highest coefficient
+                *y = coefficients.iter().rev().fold(0u8, |acc, row| gf_mul(acc, x) ^ row[byte]);
+            }
+            Share { set_id, threshold, index: x, value, seed_check }
+        })
+        .collect();
+
+    for row in coefficients.iter_mut() {
+        row.zeroize();
+    }
+    Ok(result)
+}
+
+/// Rebuild the secret from shares, of which at least `threshold` of one set must be honest
+///
+/// Shares are grouped by (set_id, threshold, seed_check) and every group with enough
+/// distinct indices is tried, one k-subset at a time, until a subset passes the
+/// integrity check. Corrupted, forged or foreign shares therefore only cost attempts.
+/// Groups are tried in order of fewest k-subsets, each with `MAX_GROUP_ATTEMPTS`; if no
+/// group recovers and one ran out of attempts the result is `TooManyCandidates`.
+pub fn combine_shares(shares: &[Share]) -> Result<Zeroizing<[u8; SECRET_LEN]>> {
+    let mut groups: Vec<Vec<&Share>> = Vec::new();
+    for share in shares.iter().filter(|s| s.index != 0 && s.threshold != 0) {
+        match groups.iter_mut().find(|group| {
+            let first = group[0];
+            first.set_id == share.set_id && first.threshold == share.threshold && first.seed_check == share.seed_check
+        }) {
+            Some(group) if !group.contains(&share) => group.push(share),
+            Some(_) => {}
+            None => groups.push(vec![share]),
+        }
+    }
+
+    let mut closest: Option<(u8, usize)> = None;
+    let mut candidates: Vec<(usize, &Vec<&Share>)> = Vec::new();
+    for group in &groups {
+        let first = group[0];
+        let k = first.threshold as usize;
+        let mut indices: Vec<u8> = group.iter().map(|s| s.index).collect();
+        indices.sort_unstable();
+        indices.dedup();
+        if indices.len() < k {
+            if closest.is_none_or(|(needed, have)| k - indices.len() < needed as usize - have) {
+                closest = Some((first.threshold, indices.len()));
+            }
+            continue;
+        }
+        candidates.push((combinations(group.len(), k), group));
+    }
+    candidates.sort_by_key(|(count, _)| *count);
+
+    let mut exhausted = false;
+    for (_, group) in &candidates {
+        let first = group[0];
+        let k = first.threshold as usize;
+        let mut attempts = 0;
+        let mut subset: Vec<usize> = (0..k).collect();
+        loop {
+            if attempts >= MAX_GROUP_ATTEMPTS {
+                exhausted = true;
+                break;
+            }
+            attempts += 1;
+
+            let chosen: Vec<&Share> = subset.iter().map(|&i| group[i]).collect();
+            let distinct = chosen.iter().enumerate().all(|(i, s)| chosen[..i].iter().all(|o| o.index != s.index));
+            if distinct {
+                let secret = Zeroizing::new(interpolate_at_zero(&chosen));
+                if bool::from(seed_check(&first.set_id, first.threshold, &secret).ct_eq(&first.seed_check)) {
+                    return Ok(secret);
+                }
+            }
+            if !next_combination(&mut subset, group.len()) {
+                break;
+            }
+        }
+    }
+
+    if exhausted {
+        return Err(RecoveryError::TooManyCandidates);
+    }
+    if !candidates.is_empty() {
+        return Err(RecoveryError::ShareIntegrity);
+    }
+    let (needed, have) = closest.unwrap_or((1, 0));
+    Err(RecoveryError::NotEnoughShares { needed, have })
+}
+
+/// Seal a share to a recipient's hybrid public key
+///
+/// # Arguments
+/// * `share` - Share to seal
+/// * `recipient` - Guardian (deposit) or requesting device (release) public key
+pub fn seal_share(share: &Share, recipient: &HybridKEMPublicKey) -> Result<SealedShare> {
+    let (mut secret, kem_ciphertext) = hybrid_encapsulate(&recipient.x25519_public, &recipient.kyber_public)
+        .map_err(|_| RecoveryError::KeyAgreementFailed)?;
+    let key = share_key(&secret);
+    secret.zeroize();
+    let mut key = key?;
+
+    let mut nonce = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce);
+    let mut plaintext = share.to_bytes()?;
+    let aad = share_aad(&kem_ciphertext);
+    let ciphertext = XChaCha20Poly1305::new_from_slice(&key)
+        .map_err(|_| RecoveryError::InvalidKeyLength)
+        .and_then(|cipher| {
+            cipher
+                .encrypt(XNonce::from_slice(&nonce), Payload { msg: &plaintext, aad: &aad })
+                .map_err(|_| RecoveryError::KeyAgreementFailed)
+        });
+    key.zeroize();
+    plaintext.zeroize();
+
+    Ok(SealedShare { version: RECOVERY_VERSION, kem_ciphertext, nonce, ciphertext: ciphertext? })
+}
+
+/// Open a share sealed to our hybrid key
+///
+/// # Arguments
+/// * `sealed` - Sealed share from a deposit or release
+/// * `x25519_secret` - Our X25519 secret key
+/// * `kyber_secret` - Our Kyber secret key (3168 bytes)
+pub fn open_share(sealed: &SealedShare, x25519_secret: &[u8; 32], kyber_secret: &[u8]) -> Result<Share> {
+    if sealed.version != RECOVERY_VERSION {
+        return Err(RecoveryError::UnsupportedVersion(sealed.version));
+    }
+    let kyber_secret: &[u8; KYBER_SECRET_KEY_BYTES] =
+        kyber_secret.try_into().map_err(|_| RecoveryError::InvalidKeyLength)?;
+
+    let mut secret = hybrid_decapsulate(x25519_secret, kyber_secret, &sealed.kem_ciphertext)
+        .map_err(|_| RecoveryError::KeyAgreementFailed)?;
+    let key = share_key(&secret);
+    secret.zeroize();
+    let mut key = key?;
+
+    let aad = share_aad(&sealed.kem_ciphertext);
+    let plaintext = XChaCha20Poly1305::new_from_slice(&key)
+        .map_err(|_| RecoveryError::InvalidKeyLength)
+        .and_then(|cipher| {
+            cipher
+                .decrypt(XNonce::from_slice(&sealed.nonce), Payload { msg: &sealed.ciphertext, aad: &aad })
+                .map_err(|_| RecoveryError::DecryptionFailed)
+        });
+    key.zeroize();
+
+    let mut plaintext = plaintext?;
+    let share = Share::from_bytes(&plaintext);
+    plaintext.zeroize();
+    share
+}
+
+/// Guardian side: re-seal a held share for a recovery request
+pub fn release_share(share: &Share, request: &RecoveryRequest) -> Result<SealedShare> {
+    if request.version != RECOVERY_VERSION {
+        return Err(RecoveryError::UnsupportedVersion(request.version));
+    }
+    let recipient = HybridKEMPublicKey {
+        x25519_public: request.x25519_public,
+        kyber_public: request.kyber_public,
+    };
+    seal_share(share, &recipient)
+}
+
+fn seed_check(set_id: &[u8; 16], threshold: u8, secret: &[u8; SECRET_LEN]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(SEED_CHECK_DOMAIN)
+        .chain_update(set_id)
+        .chain_update([threshold])
+        .chain_update(secret)
+        .finalize()
+        .into()
+}
+
+fn share_key(hybrid_secret: &[u8]) -> Result<[u8; 32]> {
+    let hkdf = Hkdf::<Sha256>::new(None, hybrid_secret);
+    let mut key = [0u8; 32];
+    hkdf.expand(SHARE_KDF_INFO, &mut key).map_err(|_| RecoveryError::KeyAgreementFailed)?;
+    Ok(key)
+}
+
+fn share_aad(kem_ciphertext: &HybridCiphertext) -> Vec<u8> {
+    let mut aad = vec![RECOVERY_VERSION];
+    aad.extend_from_slice(&kem_ciphertext.to_bytes());
+    aad
+}
+
+/// Lagrange interpolation of every byte at x = 0
+fn interpolate_at_zero(shares: &[&Share]) -> [u8; SECRET_LEN] {
+    let mut seed = [0u8; SECRET_LEN];
+    for (i, share) in shares.iter().enumerate() {
+        // basis_i(0) = prod_{j != i} x_j / (x_j - x_i); subtraction is XOR in GF(256)
+        let mut basis = 1u8;
+        for (j, other) in shares.iter().enumerate() {
+            if i != j {
+                basis = gf_mul(basis, gf_mul(other.index, gf_inv(other.index ^ share.index)));
+            }
+        }
+        for (byte, y) in seed.iter_mut().zip(share.value.iter()) {
+            *byte ^= gf_mul(basis, *y);
+        }
+    }
+    seed
+}
+
+/// Number of k-subsets of n shares, saturating
+fn combinations(n: usize, k: usize) -> usize {
+    (0..k).fold(1usize, |count, i| count.saturating_mul(n - i) / (i + 1))
+}
+
+/// Advance `subset` (sorted indices into 0..n) to the next k-combination
+fn next_combination(subset: &mut [usize], n: usize) -> bool {
+    let k = subset.len();
+    for i in (0..k).rev() {
+        if subset[i] < n - k + i {
+            subset[i] += 1;
+            for j in i + 1..k {
+                subset[j] = subset[j - 1] + 1;
+            }
+            return true;
+        }
+    }
+    false
+}
+
+/// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x + 1, without secret-dependent branches
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = 0u8.wrapping_sub(a >> 7);
+        a = (a << 1) ^ (0x1b & carry);
+        b >>= 1;
+    }
+    product
+}
+
+/// Multiplicative inverse as a^254 (inverse of 0 is 0, never used: indices are distinct)
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1u8;
+    let mut base = a;
+    let mut exponent = 254u8;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = gf_mul(result, base);
+        }
+        base = gf_mul(base, base);
+        exponent >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHRASE: &str = "legal winner thank year wave sausage worth useful legal winner thank yellow";
+
+    #[test]
+    fn test_every_threshold_combination_recovers() {
+        let secret: [u8; SECRET_LEN] = core::array::from_fn(|i| (i as u8).wrapping_mul(37).wrapping_add(11));
+        for n in 1..=6u8 {
+            for k in 1..=n {
+                let shares = split_secret(&secret, k, n).unwrap();
+                for mask in 1u32..(1 << n) {
+                    let subset: Vec<Share> = (0..n as usize)
+                        .filter(|i| mask & (1 << i) != 0)
+                        .map(|i| shares[i].clone())
+                        .collect();
+                    let result = combine_shares(&subset);
+                    if subset.len() >= k as usize {
+                        assert_eq!(*result.unwrap(), secret, "k={} n={} mask={:b}", k, n, mask);
+                    } else {
+                        assert!(matches!(result, Err(RecoveryError::NotEnoughShares { .. })));
+                    }
+                }
+            }
+        }
+        assert!(split_secret(&secret, 0, 3).is_err());
+        assert!(split_secret(&secret, 4, 3).is_err());
+
+        // The phrase round-trips through its entropy
+        let shares = split_seed_phrase(PHRASE, 2, 3).unwrap();
+        assert_eq!(recover_seed_phrase(&shares[1..]).unwrap().as_str(), PHRASE);
+        assert!(split_seed_phrase("legal winner", 2, 3).is_err());
+    }
+
+    #[test]
+    fn test_corrupted_share_detected_and_skipped() {
+        let secret = [0xa5u8; SECRET_LEN];
+        let mut shares = split_secret(&secret, 3, 5).unwrap();
+        shares[0].value[4] ^= 0x40;
+
+        // Exactly k shares including the bad one: detected
+        assert!(matches!(combine_shares(&shares[..3]), Err(RecoveryError::ShareIntegrity)));
+        // More than k: another subset is used
+        assert_eq!(*combine_shares(&shares).unwrap(), secret);
+
+        // Shares of another set do not combine with this one
+        let other = split_secret(&secret, 3, 5).unwrap();
+        let mixed = vec![shares[1].clone(), shares[2].clone(), other[3].clone()];
+        assert!(matches!(combine_shares(&mixed), Err(RecoveryError::NotEnoughShares { needed: 3, have: 2 })));
+        let duplicate = vec![shares[1].clone(), shares[1].clone(), shares[2].clone()];
+        assert!(matches!(combine_shares(&duplicate), Err(RecoveryError::NotEnoughShares { needed: 3, have: 2 })));
+    }
+
+    #[test]
+    fn test_poisoned_first_share_does_not_block_recovery() {
+        let secret = [0x5au8; SECRET_LEN];
+        let honest = split_secret(&secret, 2, 3).unwrap();
+
+        // Forged share arriving first: foreign set and digest, at an honest index
+        let mut foreign = split_secret(&[0u8; SECRET_LEN], 2, 2).unwrap().remove(0);
+        foreign.index = honest[0].index;
+        // Forged share claiming the honest set, at an index an honest share also uses
+        let mut conflicting = honest[1].clone();
+        conflicting.value[0] ^= 0xff;
+
+        for poison in [foreign, conflicting] {
+            let shares = vec![poison.clone(), honest[0].clone(), honest[1].clone()];
+            assert_eq!(*combine_shares(&shares).unwrap(), secret);
+        }
+    }
+
+    #[test]
+    fn test_large_forged_group_first_does_not_starve_honest_group() {
+        let secret = [0x3cu8; SECRET_LEN];
+        let honest = split_secret(&secret, 2, 3).unwrap();
+
+        // 20 shares of a k=5 set whose digest matches no subset: C(20, 5) exceeds a group budget
+        let mut forged = split_secret(&[0x99u8; SECRET_LEN], 5, 20).unwrap();
+        for share in forged.iter_mut() {
+            share.seed_check[0] ^= 0x01;
+        }
+        assert!(combinations(forged.len(), 5) > MAX_GROUP_ATTEMPTS);
+        assert!(matches!(combine_shares(&forged), Err(RecoveryError::TooManyCandidates)));
+
+        let mut shares = forged.clone();
+        shares.extend(honest[..2].iter().cloned());
+        assert_eq!(*combine_shares(&shares).unwrap(), secret);
+    }
+
+    #[test]
+    fn test_releases_capped_per_sender() {
+        let mut session = RecoverySession::new().unwrap();
+        let request = session.request();
+        let forged = split_secret(&[0x11u8; SECRET_LEN], 5, 20).unwrap();
+        let mallory = [0xeeu8; 32];
+
+        for share in forged.iter().take(MAX_SHARES_PER_SENDER) {
+            assert!(session.add_release(&mallory, &release_share(share, &request).unwrap()).unwrap().is_none());
+        }
+        let extra = release_share(&forged[MAX_SHARES_PER_SENDER], &request).unwrap();
+        assert!(matches!(session.add_release(&mallory, &extra), Err(RecoveryError::TooManySharesFromSender)));
+        assert_eq!(session.shares_received(), MAX_SHARES_PER_SENDER);
+    }
+
+    #[test]
+    fn test_recovery_flow_through_guardians() {
+        let guardians: Vec<HybridKEMKeypair> = (0..3).map(|_| generate_random_kem_keypair().unwrap()).collect();
+        let shares = split_seed_phrase(PHRASE, 2, 3).unwrap();
+
+        // Owner deposits one sealed share per guardian
+        let deposits: Vec<Vec<u8>> = shares
+            .iter()
+            .zip(&guardians)
+            .map(|(share, guardian)| {
+                let public = HybridKEMPublicKey { x25519_public: guardian.x25519_public, kyber_public: guardian.kyber_public };
+                RecoveryMessage::Deposit(seal_share(share, &public).unwrap()).to_bytes().unwrap()
+            })
+            .collect();
+
+        let mut session = RecoverySession::new().unwrap();
+        let request_bytes = RecoveryMessage::Request(session.request()).to_bytes().unwrap();
+
+        // A forged release from another set arrives first
+        let forged = split_secret(&[7u8; SECRET_LEN], 2, 2).unwrap();
+        let RecoveryMessage::Request(request) = RecoveryMessage::from_bytes(&request_bytes).unwrap() else { panic!() };
+        assert!(session.add_release(&[0xeeu8; 32], &release_share(&forged[0], &request).unwrap()).unwrap().is_none());
+
+        let mut recovered = None;
+        for (deposit, guardian) in deposits.iter().zip(&guardians).take(2) {
+            let sender = guardian.x25519_public;
+            let RecoveryMessage::Deposit(sealed) = RecoveryMessage::from_bytes(deposit).unwrap() else { panic!() };
+            let held = open_share(&sealed, &guardian.x25519_secret, &guardian.kyber_secret).unwrap();
+            // Another guardian's key cannot open it
+            assert!(open_share(&sealed, &guardians[2].x25519_secret, &guardians[2].kyber_secret).is_err());
+
+            let RecoveryMessage::Request(request) = RecoveryMessage::from_bytes(&request_bytes).unwrap() else { panic!() };
+            let release = RecoveryMessage::Release(release_share(&held, &request).unwrap()).to_bytes().unwrap();
+            let RecoveryMessage::Release(sealed) = RecoveryMessage::from_bytes(&release).unwrap() else { panic!() };
+            recovered = session.add_release(&sender, &sealed).unwrap();
+        }
+        assert_eq!(session.shares_received(), 3);
+        assert_eq!(recovered.as_deref().map(String::as_str), Some(PHRASE));
+    }
+}
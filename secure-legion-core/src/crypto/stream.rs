@@ -0,0 +1,647 @@
+//! Streaming Chunked AEAD
+//! STREAM construction over XChaCha20-Poly1305 for attachments of any size
+//!
+//! The plaintext is split into fixed-size chunks, each sealed under the nonce
+//! [nonce prefix: 19][chunk index: 4][final flag: 1]. The index detects reordering and
+//! dropped chunks; the final flag is set only on the last chunk, so truncating the
+//! stream at a chunk boundary fails authentication. The header (version, chunk size,
+//! nonce prefix) is bound into every chunk as associated data.
+//!
+//! Stream format: [header: 24][chunk 0]...[final chunk], each chunk being
+//! [ciphertext: chunk_size (final chunk: 0..=chunk_size)][tag: 16].
+//!
+//! `StreamSealer`/`StreamOpener` work on one chunk at a time; `StreamEncryptor`/
+//! `StreamDecryptor` wrap them as `std::io::Read` adapters and `encrypt_stream_async`/
+//! `decrypt_stream_async` pump tokio `AsyncRead` into `AsyncWrite`. Only one chunk is
+//! ever held in memory.
+//!
+//! There is no transport for streams yet: an incoming stream must be announced by an
+//! authenticated chain message (stream ID and length) before the receiver spools it, and
+//! the attachment send/receive path does not exist in the app. Attachments are still
+//! sent as single frames, limited to 10 MB.
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, OsRng, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use rand::RngCore;
+use std::io::{self, Read};
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use zeroize::Zeroize;
+
+/// Stream format version
+pub const STREAM_VERSION: u8 = 0x01;
+
+/// Header: [version: 1][chunk size: 4][nonce prefix: 19]
+pub const STREAM_HEADER_LEN: usize = 1 + 4 + NONCE_PREFIX_LEN;
+
+/// Default plaintext bytes per chunk
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Largest chunk size accepted from a header (bounds decryptor memory)
+pub const MAX_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Poly1305 tag appended to every chunk
+pub const STREAM_TAG_LEN: usize = 16;
+
+const NONCE_PREFIX_LEN: usize = 19;
+
+#[derive(Error, Debug)]
+pub enum StreamError {
+    #[error("Invalid key length")]
+    InvalidKeyLength,
+    #[error("Malformed stream header")]
+    InvalidHeader,
+    #[error("Unsupported stream version: {0:#04x}")]
+    UnsupportedVersion(u8),
+    #[error("Invalid chunk size: {0}")]
+    InvalidChunkSize(usize),
+    #[error("Chunk {0} failed authentication")]
+    ChunkAuthentication(u32),
+    #[error("Stream truncated before the final chunk")]
+    Truncated,
+    #[error("Stream already finished")]
+    Finished,
+    #[error("Too many chunks in stream")]
+    TooManyChunks,
+    #[error("Encryption failed")]
+    EncryptionFailed,
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, StreamError>;
+
+/// Parsed stream header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamHeader {
+    pub chunk_size: usize,
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+}
+
+impl StreamHeader {
+    pub fn to_bytes(&self) -> [u8; STREAM_HEADER_LEN] {
+        let mut bytes = [0u8; STREAM_HEADER_LEN];
+        bytes[0] = STREAM_VERSION;
+        bytes[1..5].copy_from_slice(&(self.chunk_size as u32).to_be_bytes());
+        bytes[5..].copy_from_slice(&self.nonce_prefix);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != STREAM_HEADER_LEN {
+            return Err(StreamError::InvalidHeader);
+        }
+        if bytes[0] != STREAM_VERSION {
+            return Err(StreamError::UnsupportedVersion(bytes[0]));
+        }
+
+        let chunk_size = u32::from_be_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]) as usize;
+        validate_chunk_size(chunk_size)?;
+
+        let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+        nonce_prefix.copy_from_slice(&bytes[5..]);
+        Ok(Self { chunk_size, nonce_prefix })
+    }
+
+    /// Size of a non-final encrypted chunk
+    pub fn sealed_chunk_len(&self) -> usize {
+        self.chunk_size + STREAM_TAG_LEN
+    }
+}
+
+/// Chunk-at-a-time encryptor
+pub struct StreamSealer {
+    cipher: XChaCha20Poly1305,
+    header: StreamHeader,
+    next_index: u32,
+    finished: bool,
+}
+
+/// Chunk-at-a-time decryptor
+pub struct StreamOpener {
+    cipher: XChaCha20Poly1305,
+    header: StreamHeader,
+    next_index: u32,
+    finished: bool,
+}
+
+impl StreamSealer {
+    /// Start a new stream with a random nonce prefix
+    ///
+    /// # Arguments
+    /// * `key` - 32-byte encryption key
+    /// * `chunk_size` - Plaintext bytes per chunk (at most MAX_CHUNK_SIZE)
+    pub fn new(key: &[u8], chunk_size: usize) -> Result<Self> {
+        validate_chunk_size(chunk_size)?;
+        let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+        OsRng.fill_bytes(&mut nonce_prefix);
+
+        Ok(Self {
+            cipher: new_cipher(key)?,
+            header: StreamHeader { chunk_size, nonce_prefix },
+            next_index: 0,
+            finished: false,
+        })
+    }
+
+    /// Header to send before the first chunk
+    pub fn header(&self) -> StreamHeader {
+        self.header
+    }
+
+    /// Encrypt the next chunk
+    ///
+    /// # Arguments
+    /// * `chunk` - Exactly `chunk_size` bytes, or fewer for the final chunk
+    /// * `last` - Whether this is the final chunk (must be set exactly once)
+    ///
+    /// # Returns
+    /// Ciphertext with tag
+    pub fn seal_chunk(&mut self, chunk: &[u8], last: bool) -> Result<Vec<u8>> {
+        if self.finished {
+            return Err(StreamError::Finished);
+        }
+        if chunk.len() > self.header.chunk_size || (!last && chunk.len() != self.header.chunk_size) {
+            return Err(StreamError::InvalidChunkSize(chunk.len()));
+        }
+
+        let index = self.next_index;
+        let nonce = chunk_nonce(&self.header, index, last);
+        let aad = self.header.to_bytes();
+        let sealed = self
+            .cipher
+            .encrypt(XNonce::from_slice(&nonce), Payload { msg: chunk, aad: &aad })
+            .map_err(|_| StreamError::EncryptionFailed)?;
+
+        if last {
+            self.finished = true;
+        } else {
+            self.next_index = self.next_index.checked_add(1).ok_or(StreamError::TooManyChunks)?;
+        }
+        Ok(sealed)
+    }
+}
+
+impl StreamOpener {
+    /// Start decrypting a stream
+    ///
+    /// # Arguments
+    /// * `key` - 32-byte encryption key
+    /// * `header` - Header received before the first chunk
+    pub fn new(key: &[u8], header: StreamHeader) -> Result<Self> {
+        Ok(Self {
+            cipher: new_cipher(key)?,
+            header,
+            next_index: 0,
+            finished: false,
+        })
+    }
+
+    pub fn header(&self) -> StreamHeader {
+        self.header
+    }
+
+    /// Whether the final chunk has been opened
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Decrypt the next chunk
+    ///
+    /// # Arguments
+    /// * `sealed` - Ciphertext with tag
+    /// * `last` - Whether the transport says this is the final chunk
+    ///
+    /// # Returns
+    /// Plaintext of the chunk; fails if the chunk is forged, out of order, or `last` is wrong
+    pub fn open_chunk(&mut self, sealed: &[u8], last: bool) -> Result<Vec<u8>> {
+        if self.finished {
+            return Err(StreamError::Finished);
+        }
+        if sealed.len() < STREAM_TAG_LEN
+            || sealed.len() > self.header.sealed_chunk_len()
+            || (!last && sealed.len() != self.header.sealed_chunk_len())
+        {
+            return Err(StreamError::InvalidChunkSize(sealed.len()));
+        }
+
+        let index = self.next_index;
+        let nonce = chunk_nonce(&self.header, index, last);
+        let aad = self.header.to_bytes();
+        let plaintext = self
+            .cipher
+            .decrypt(XNonce::from_slice(&nonce), Payload { msg: sealed, aad: &aad })
+            .map_err(|_| StreamError::ChunkAuthentication(index))?;
+
+        if last {
+            self.finished = true;
+        } else {
+            self.next_index = self.next_index.checked_add(1).ok_or(StreamError::TooManyChunks)?;
+        }
+        Ok(plaintext)
+    }
+}
+
+/// `Read` adapter producing an encrypted stream (header, then chunks) from a plaintext reader
+pub struct StreamEncryptor<R: Read> {
+    inner: R,
+    sealer: StreamSealer,
+    /// Plaintext read ahead of the current chunk (to know whether it is the last)
+    lookahead: Vec<u8>,
+    inner_eof: bool,
+    output: Vec<u8>,
+    output_pos: usize,
+}
+
+impl<R: Read> StreamEncryptor<R> {
+    /// # Arguments
+    /// * `inner` - Plaintext source
+    /// * `key` - 32-byte encryption key
+    /// * `chunk_size` - Plaintext bytes per chunk (see DEFAULT_CHUNK_SIZE)
+    pub fn new(inner: R, key: &[u8], chunk_size: usize) -> Result<Self> {
+        let sealer = StreamSealer::new(key, chunk_size)?;
+        let output = sealer.header().to_bytes().to_vec();
+        Ok(Self {
+            inner,
+            sealer,
+            lookahead: Vec::with_capacity(chunk_size + 1),
+            inner_eof: false,
+            output,
+            output_pos: 0,
+        })
+    }
+
+    /// Seal the next chunk into the output buffer; false once the stream is complete
+    fn fill_output(&mut self) -> Result<bool> {
+        if self.sealer.finished {
+            return Ok(false);
+        }
+
+        let chunk_size = self.sealer.header.chunk_size;
+        // One byte past the chunk tells us whether more plaintext follows
+        fill_from(&mut self.inner, &mut self.lookahead, chunk_size + 1, &mut self.inner_eof)?;
+
+        let last = self.lookahead.len() <= chunk_size;
+        let take = self.lookahead.len().min(chunk_size);
+        self.output = self.sealer.seal_chunk(&self.lookahead[..take], last)?;
+        self.output_pos = 0;
+        self.lookahead[..take].zeroize();
+        self.lookahead.drain(..take);
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for StreamEncryptor<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.output_pos == self.output.len() && !self.fill_output().map_err(into_io_error)? {
+            return Ok(0);
+        }
+        let n = buf.len().min(self.output.len() - self.output_pos);
+        buf[..n].copy_from_slice(&self.output[self.output_pos..self.output_pos + n]);
+        self.output_pos += n;
+        Ok(n)
+    }
+}
+
+/// `Read` adapter producing plaintext from an encrypted stream
+///
+/// Errors with `InvalidData` if any chunk is forged, reordered, or the stream ends
+/// before its final chunk. Plaintext returned before the error must be discarded.
+pub struct StreamDecryptor<R: Read> {
+    inner: R,
+    key: [u8; 32],
+    opener: Option<StreamOpener>,
+    lookahead: Vec<u8>,
+    inner_eof: bool,
+    output: Vec<u8>,
+    output_pos: usize,
+}
+
+impl<R: Read> StreamDecryptor<R> {
+    /// # Arguments
+    /// * `inner` - Encrypted stream source (header first)
+    /// * `key` - 32-byte encryption key
+    pub fn new(inner: R, key: &[u8]) -> Result<Self> {
+        let key: [u8; 32] = key.try_into().map_err(|_| StreamError::InvalidKeyLength)?;
+        Ok(Self {
+            inner,
+            key,
+            opener: None,
+            lookahead: Vec::new(),
+            inner_eof: false,
+            output: Vec::new(),
+            output_pos: 0,
+        })
+    }
+
+    /// Open the next chunk into the output buffer; false once the final chunk was opened
+    fn fill_output(&mut self) -> Result<bool> {
+        if self.opener.is_none() {
+            fill_from(&mut self.inner, &mut self.lookahead, STREAM_HEADER_LEN, &mut self.inner_eof)?;
+            if self.lookahead.len() < STREAM_HEADER_LEN {
+                return Err(StreamError::Truncated);
+            }
+            let header = StreamHeader::from_bytes(&self.lookahead)?;
+            self.lookahead.clear();
+            self.opener = Some(StreamOpener::new(&self.key, header)?);
+        }
+        let opener = self.opener.as_mut().ok_or(StreamError::InvalidHeader)?;
+        if opener.is_finished() {
+            return Ok(false);
+        }
+
+        let sealed_len = opener.header().sealed_chunk_len();
+        fill_from(&mut self.inner, &mut self.lookahead, sealed_len + 1, &mut self.inner_eof)?;
+        if self.lookahead.len() < STREAM_TAG_LEN {
+            return Err(StreamError::Truncated);
+        }
+
+        let last = self.lookahead.len() <= sealed_len;
+        let take = self.lookahead.len().min(sealed_len);
+        self.output.zeroize();
+        self.output = opener.open_chunk(&self.lookahead[..take], last)?;
+        self.output_pos = 0;
+        self.lookahead.drain(..take);
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for StreamDecryptor<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.output_pos == self.output.len() {
+            if !self.fill_output().map_err(into_io_error)? {
+                return Ok(0);
+            }
+        }
+        let n = buf.len().min(self.output.len() - self.output_pos);
+        buf[..n].copy_from_slice(&self.output[self.output_pos..self.output_pos + n]);
+        self.output_pos += n;
+        Ok(n)
+    }
+}
+
+impl<R: Read> Drop for StreamDecryptor<R> {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.output.zeroize();
+    }
+}
+
+/// Encrypt everything `reader` yields into `writer` as one stream (header, then chunks)
+///
+/// # Arguments
+/// * `reader` - Plaintext source (file, pipe)
+/// * `writer` - Destination (e.g. a Tor socket)
+/// * `key` - 32-byte encryption key
+/// * `chunk_size` - Plaintext bytes per chunk (see DEFAULT_CHUNK_SIZE)
+///
+/// # Returns
+/// Number of encrypted bytes written
+pub async fn encrypt_stream_async<R, W>(reader: &mut R, writer: &mut W, key: &[u8], chunk_size: usize) -> Result<u64>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut sealer = StreamSealer::new(key, chunk_size)?;
+    let header = sealer.header().to_bytes();
+    writer.write_all(&header).await?;
+    let mut written = header.len() as u64;
+
+    let mut lookahead = Vec::with_capacity(chunk_size + 1);
+    let mut eof = false;
+    loop {
+        // One byte past the chunk tells us whether more plaintext follows
+        fill_from_async(reader, &mut lookahead, chunk_size + 1, &mut eof).await?;
+        let last = lookahead.len() <= chunk_size;
+        let take = lookahead.len().min(chunk_size);
+        let sealed = sealer.seal_chunk(&lookahead[..take], last)?;
+        lookahead[..take].zeroize();
+        lookahead.drain(..take);
+
+        writer.write_all(&sealed).await?;
+        written += sealed.len() as u64;
+        if last {
+            break;
+        }
+    }
+    writer.flush().await?;
+    Ok(written)
+}
+
+/// Decrypt a stream from `reader` into `writer`
+///
+/// Fails if any chunk is forged, reordered, or the stream ends before its final chunk.
+/// Plaintext written before the error must be discarded.
+///
+/// # Returns
+/// Number of plaintext bytes written
+pub async fn decrypt_stream_async<R, W>(reader: &mut R, writer: &mut W, key: &[u8]) -> Result<u64>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut buffer = Vec::new();
+    let mut eof = false;
+    fill_from_async(reader, &mut buffer, STREAM_HEADER_LEN, &mut eof).await?;
+    if buffer.len() < STREAM_HEADER_LEN {
+        return Err(StreamError::Truncated);
+    }
+    let header = StreamHeader::from_bytes(&buffer)?;
+    buffer.clear();
+
+    let mut opener = StreamOpener::new(key, header)?;
+    let sealed_len = header.sealed_chunk_len();
+    let mut written = 0u64;
+    while !opener.is_finished() {
+        fill_from_async(reader, &mut buffer, sealed_len + 1, &mut eof).await?;
+        if buffer.len() < STREAM_TAG_LEN {
+            return Err(StreamError::Truncated);
+        }
+        let last = buffer.len() <= sealed_len;
+        let take = buffer.len().min(sealed_len);
+        let mut plaintext = opener.open_chunk(&buffer[..take], last)?;
+        buffer.drain(..take);
+
+        let result = writer.write_all(&plaintext).await;
+        written += plaintext.len() as u64;
+        plaintext.zeroize();
+        result?;
+    }
+    writer.flush().await?;
+    Ok(written)
+}
+
+fn validate_chunk_size(chunk_size: usize) -> Result<()> {
+    if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+        return Err(StreamError::InvalidChunkSize(chunk_size));
+    }
+    Ok(())
+}
+
+fn new_cipher(key: &[u8]) -> Result<XChaCha20Poly1305> {
+    if key.len() != 32 {
+        return Err(StreamError::InvalidKeyLength);
+    }
+    XChaCha20Poly1305::new_from_slice(key).map_err(|_| StreamError::InvalidKeyLength)
+}
+
+/// [nonce prefix: 19][chunk index: 4][final flag: 1]
+fn chunk_nonce(header: &StreamHeader, index: u32, last: bool) -> [u8; 24] {
+    let mut nonce = [0u8; 24];
+    nonce[..NONCE_PREFIX_LEN].copy_from_slice(&header.nonce_prefix);
+    nonce[NONCE_PREFIX_LEN..23].copy_from_slice(&index.to_be_bytes());
+    nonce[23] = last as u8;
+    nonce
+}
+
+/// Read from `reader` until `buf` holds `target` bytes or the reader is exhausted
+fn fill_from<R: Read>(reader: &mut R, buf: &mut Vec<u8>, target: usize, eof: &mut bool) -> io::Result<()> {
+    let mut scratch = [0u8; 8192];
+    while !*eof && buf.len() < target {
+        let want = (target - buf.len()).min(scratch.len());
+        match reader.read(&mut scratch[..want]) {
+            Ok(0) => *eof = true,
+            Ok(n) => buf.extend_from_slice(&scratch[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    scratch.zeroize();
+    Ok(())
+}
+
+/// Async counterpart of `fill_from`
+async fn fill_from_async<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+    target: usize,
+    eof: &mut bool,
+) -> io::Result<()> {
+    let mut scratch = [0u8; 8192];
+    while !*eof && buf.len() < target {
+        let want = (target - buf.len()).min(scratch.len());
+        match reader.read(&mut scratch[..want]).await? {
+            0 => *eof = true,
+            n => buf.extend_from_slice(&scratch[..n]),
+        }
+    }
+    scratch.zeroize();
+    Ok(())
+}
+
+fn into_io_error(error: StreamError) -> io::Error {
+    match error {
+        StreamError::Io(e) => e,
+        other => io::Error::new(io::ErrorKind::InvalidData, other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encrypt_all(plaintext: &[u8], key: &[u8; 32], chunk_size: usize) -> Vec<u8> {
+        let mut encrypted = Vec::new();
+        StreamEncryptor::new(plaintext, key, chunk_size).unwrap().read_to_end(&mut encrypted).unwrap();
+        encrypted
+    }
+
+    fn decrypt_all(encrypted: &[u8], key: &[u8; 32]) -> io::Result<Vec<u8>> {
+        let mut plaintext = Vec::new();
+        StreamDecryptor::new(encrypted, key).unwrap().read_to_end(&mut plaintext)?;
+        Ok(plaintext)
+    }
+
+    #[test]
+    fn test_stream_roundtrip_across_chunk_boundaries() {
+        let key = [7u8; 32];
+        for len in [0usize, 1, 99, 100, 101, 1000] {
+            let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let encrypted = encrypt_all(&plaintext, &key, 100);
+
+            let chunks = len.div_ceil(100).max(1);
+            assert_eq!(encrypted.len(), STREAM_HEADER_LEN + len + chunks * STREAM_TAG_LEN);
+            assert_eq!(decrypt_all(&encrypted, &key).unwrap(), plaintext);
+        }
+        assert!(decrypt_all(&encrypt_all(b"secret", &key, 100), &[8u8; 32]).is_err());
+    }
+
+    #[test]
+    fn test_truncation_and_reordering_detected() {
+        let key = [7u8; 32];
+        let plaintext = vec![0x5a; 350];
+        let encrypted = encrypt_all(&plaintext, &key, 100);
+        let sealed = 100 + STREAM_TAG_LEN;
+
+        // Dropping the final chunk leaves a non-final chunk at the end
+        let truncated = &encrypted[..STREAM_HEADER_LEN + 3 * sealed];
+        assert!(decrypt_all(truncated, &key).is_err());
+        assert!(decrypt_all(&encrypted[..STREAM_HEADER_LEN], &key).is_err());
+        assert!(decrypt_all(&encrypted[..STREAM_HEADER_LEN - 1], &key).is_err());
+
+        // Swapping chunks 0 and 1
+        let mut reordered = encrypted.clone();
+        let (first, second) = (STREAM_HEADER_LEN, STREAM_HEADER_LEN + sealed);
+        reordered[first..first + sealed].copy_from_slice(&encrypted[second..second + sealed]);
+        reordered[second..second + sealed].copy_from_slice(&encrypted[first..first + sealed]);
+        assert!(decrypt_all(&reordered, &key).is_err());
+
+        // Changing the chunk size in the header breaks every chunk
+        let mut resized = encrypted.clone();
+        resized[4] = 50;
+        assert!(decrypt_all(&resized, &key).is_err());
+    }
+
+    #[test]
+    fn test_sealer_and_opener_enforce_final_flag() {
+        let key = [9u8; 32];
+        let mut sealer = StreamSealer::new(&key, 4).unwrap();
+        let c0 = sealer.seal_chunk(b"abcd", false).unwrap();
+        let c1 = sealer.seal_chunk(b"ef", true).unwrap();
+        assert!(matches!(sealer.seal_chunk(b"", true), Err(StreamError::Finished)));
+        assert!(StreamSealer::new(&key, 4).unwrap().seal_chunk(b"ab", false).is_err());
+
+        let header = StreamHeader::from_bytes(&sealer.header().to_bytes()).unwrap();
+
+        // A non-final chunk presented as final does not authenticate
+        let mut opener = StreamOpener::new(&key, header).unwrap();
+        assert!(matches!(opener.open_chunk(&c0, true), Err(StreamError::ChunkAuthentication(0))));
+
+        let mut opener = StreamOpener::new(&key, header).unwrap();
+        assert_eq!(opener.open_chunk(&c0, false).unwrap(), b"abcd");
+        assert_eq!(opener.open_chunk(&c1, true).unwrap(), b"ef");
+        assert!(opener.is_finished());
+        assert!(StreamHeader::from_bytes(&[0u8; STREAM_HEADER_LEN]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_async_stream_interoperates_with_read_adapters() {
+        let key = [3u8; 32];
+        let plaintext: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+
+        // Async encrypt over an in-memory pipe, sync decrypt
+        let (mut tx, mut rx) = tokio::io::duplex(64);
+        let source = plaintext.clone();
+        let sender = tokio::spawn(async move {
+            let written = encrypt_stream_async(&mut source.as_slice(), &mut tx, &key, 100).await.unwrap();
+            tx.shutdown().await.unwrap();
+            written
+        });
+        let mut encrypted = Vec::new();
+        rx.read_to_end(&mut encrypted).await.unwrap();
+        assert_eq!(sender.await.unwrap(), encrypted.len() as u64);
+        assert_eq!(decrypt_all(&encrypted, &key).unwrap(), plaintext);
+
+        // Sync encrypt, async decrypt
+        let encrypted = encrypt_all(&plaintext, &key, 100);
+        let mut decrypted = Vec::new();
+        decrypt_stream_async(&mut encrypted.as_slice(), &mut decrypted, &key).await.unwrap();
+        assert_eq!(decrypted, plaintext);
+
+        // Truncation is detected on the async path too
+        let truncated = &encrypted[..encrypted.len() - 300];
+        assert!(decrypt_stream_async(&mut &truncated[..], &mut Vec::new(), &key).await.is_err());
+    }
+}
@@ -5,15 +5,14 @@ use std::panic;
 use std::sync::{Arc, Mutex};
 use once_cell::sync::{OnceCell, Lazy};
 use std::collections::HashMap;
-use zeroize::Zeroize;
+use zeroize::{Zeroize, Zeroizing};
 
 use crate::crypto::{
-    decrypt_message, encrypt_message, generate_keypair, sign_data,
-    verify_signature, derive_root_key, evolve_chain_key, derive_message_key,
-    encrypt_message_with_evolution, decrypt_message_with_evolution,
-    derive_receive_key_at_sequence,
+    decrypt_message, encrypt_message, generate_keypair,
+    verify_signature, ChainKey, RootKey, SigningSecret,
 };
 use crate::network::{TorManager, PENDING_CONNECTIONS};
+use crate::protocol::key_registry::{ContactKeys, FileKeyStorage, KeyChangeEvent, KeyChangePolicy, KeyCheck, KeyRegistry};
 use crate::audio::voice_streaming::{VoiceStreamingListener, VoicePacket};
 use tokio::sync::mpsc;
 use tokio::io::AsyncReadExt;
@@ -88,6 +87,8 @@ static GLOBAL_ACK_RECEIVER: OnceCell<Arc<Mutex<mpsc::UnboundedReceiver<(u64, Vec
 static GLOBAL_MESSAGE_RECEIVER: OnceCell<Arc<Mutex<mpsc::UnboundedReceiver<(u64, Vec<u8>)>>>> = OnceCell::new();
 static GLOBAL_VOICE_RECEIVER: OnceCell<Arc<Mutex<mpsc::UnboundedReceiver<(u64, Vec<u8>)>>>> = OnceCell::new();
 static GLOBAL_FRIEND_REQUEST_RECEIVER: OnceCell<Arc<Mutex<mpsc::UnboundedReceiver<Vec<u8>>>>> = OnceCell::new();
+static GLOBAL_SESSION_RESET_RECEIVER: OnceCell<Arc<Mutex<mpsc::UnboundedReceiver<Vec<u8>>>>> = OnceCell::new();
+static GLOBAL_RECOVERY_RECEIVER: OnceCell<Arc<Mutex<mpsc::UnboundedReceiver<Vec<u8>>>>> = OnceCell::new();
 
 /// Global Voice Streaming Listener (v2.0)
 static GLOBAL_VOICE_LISTENER: OnceCell<Arc<tokio::sync::Mutex<VoiceStreamingListener>>> = OnceCell::new();
@@ -214,10 +215,8 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_encryptMessage(
         };
 
         // Derive shared secret using X25519 ECDH
-        let mut shared_secret = match crate::crypto::key_exchange::derive_shared_secret(
-            &our_x25519_private,
-            &recipient_x25519_bytes,
-        ) {
+        let mut shared_secret = match crate::crypto::key_exchange::X25519Secret::from_bytes(&our_x25519_private)
+            .and_then(|secret| secret.diffie_hellman(&recipient_x25519_bytes)) {
             Ok(secret) => secret,
             Err(e) => {
                 let _ = env.throw_new("java/lang/RuntimeException", format!("ECDH failed: {}", e));
@@ -226,7 +225,7 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_encryptMessage(
         };
 
         // Encrypt with shared secret
-        let encrypted = match encrypt_message(plaintext_str.as_bytes(), &shared_secret) {
+        let encrypted = match encrypt_message(plaintext_str.as_bytes(), shared_secret.as_slice()) {
             Ok(enc) => enc,
             Err(e) => {
                 shared_secret.zeroize();
@@ -325,10 +324,8 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptMessage(
         };
 
         // Derive shared secret using X25519 ECDH
-        let mut shared_secret = match crate::crypto::key_exchange::derive_shared_secret(
-            &our_x25519_private,
-            &sender_x25519_public,
-        ) {
+        let mut shared_secret = match crate::crypto::key_exchange::X25519Secret::from_bytes(&our_x25519_private)
+            .and_then(|secret| secret.diffie_hellman(&sender_x25519_public)) {
             Ok(secret) => secret,
             Err(e) => {
                 let _ = env.throw_new("java/lang/RuntimeException", format!("ECDH failed: {}", e));
@@ -337,7 +334,7 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptMessage(
         };
 
         // Decrypt with shared secret
-        let result = match decrypt_message(encrypted_data, &shared_secret) {
+        let result = match decrypt_message(encrypted_data, shared_secret.as_slice()) {
             Ok(mut plaintext) => {
                 let plaintext_str = String::from_utf8_lossy(&plaintext);
                 let result = match string_to_jstring(&mut env, &plaintext_str) {
@@ -389,7 +386,7 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_signData(
             }
         };
 
-        match sign_data(&data_vec, &priv_key) {
+        match SigningSecret::from_bytes(&priv_key).map(|secret| secret.sign(&data_vec)) {
             Ok(signature) => match vec_to_jbytearray(&mut env, &signature) {
                 Ok(arr) => arr.into_raw(),
                 Err(e) => {
@@ -521,6 +518,127 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_hashPassword(
     }, std::ptr::null_mut())
 }
 
+/// Derive a password key with explicit Argon2 parameters
+/// kdf_descriptor: 15-byte descriptor from calibrateKdf (stored with the hash)
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_hashPasswordWithKdf(
+    mut env: JNIEnv,
+    _class: JClass,
+    password: JString,
+    salt: JByteArray,
+    kdf_descriptor: JByteArray,
+) -> jbyteArray {
+    catch_panic!(env, {
+        let password_str = match jstring_to_string(&mut env, password) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        let salt_vec = match jbytearray_to_vec(&mut env, salt) {
+            Ok(v) => v,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        let params = match jbytearray_to_vec(&mut env, kdf_descriptor)
+            .and_then(|v| crate::crypto::hashing::KdfParams::from_bytes(&v).map_err(|e| e.to_string()))
+        {
+            Ok(p) => p,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        match crate::crypto::hashing::derive_key(&password_str, &salt_vec, &params) {
+            Ok(hash) => match vec_to_jbytearray(&mut env, &hash) {
+                Ok(arr) => arr.into_raw(),
+                Err(e) => {
+                    let _ = env.throw_new("java/lang/RuntimeException", e);
+                    std::ptr::null_mut()
+                }
+            },
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("{}", e));
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Calibrate Argon2id for this device
+/// Returns a 15-byte KDF descriptor whose derivation takes about target_millis
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_calibrateKdf(
+    mut env: JNIEnv,
+    _class: JClass,
+    target_millis: jint,
+    memory_kib: jint,
+) -> jbyteArray {
+    catch_panic!(env, {
+        let target = std::time::Duration::from_millis(target_millis.max(0) as u64);
+
+        match crate::crypto::hashing::calibrate_kdf(target, memory_kib.max(0) as u32) {
+            Ok(params) => match vec_to_jbytearray(&mut env, &params.to_bytes()) {
+                Ok(arr) => arr.into_raw(),
+                Err(e) => {
+                    let _ = env.throw_new("java/lang/RuntimeException", e);
+                    std::ptr::null_mut()
+                }
+            },
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("{}", e));
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Check whether a password key should be re-derived with the target parameters
+/// current_descriptor: stored descriptor, or null for keys from hashPassword (legacy)
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_kdfNeedsRehash(
+    mut env: JNIEnv,
+    _class: JClass,
+    current_descriptor: JByteArray,
+    target_descriptor: JByteArray,
+) -> jboolean {
+    catch_panic!(env, {
+        use crate::crypto::hashing::KdfParams;
+
+        let current = if current_descriptor.is_null() {
+            KdfParams::LEGACY
+        } else {
+            match jbytearray_to_vec(&mut env, current_descriptor)
+                .and_then(|v| KdfParams::from_bytes(&v).map_err(|e| e.to_string()))
+            {
+                Ok(p) => p,
+                Err(e) => {
+                    log::error!("Invalid stored KDF descriptor: {}", e);
+                    return 1;
+                }
+            }
+        };
+
+        let target = match jbytearray_to_vec(&mut env, target_descriptor)
+            .and_then(|v| KdfParams::from_bytes(&v).map_err(|e| e.to_string()))
+        {
+            Ok(p) => p,
+            Err(e) => {
+                log::error!("Invalid target KDF descriptor: {}", e);
+                return 0;
+            }
+        };
+
+        if current.needs_rehash(&target) { 1 } else { 0 }
+    }, 0)
+}
+
 // ==================== NETWORK (Tor Integration) ====================
 
 /// Initialize Tor client and bootstrap connection to Tor network
@@ -1474,29 +1592,8 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_sendPing(
             }
         };
 
-        // Get our X25519 encryption private key
-        let our_x25519_private = match crate::ffi::keystore::get_encryption_private_key(&mut env, &key_manager) {
-            Ok(k) => k,
-            Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get encryption key: {}", e));
-                return std::ptr::null_mut();
-            }
-        };
-
-        // Derive shared secret using X25519 ECDH
-        let shared_secret = match crate::crypto::key_exchange::derive_shared_secret(
-            &our_x25519_private,
-            &recipient_x25519_bytes,
-        ) {
-            Ok(secret) => secret,
-            Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("ECDH failed: {}", e));
-                return std::ptr::null_mut();
-            }
-        };
-
-        // Encrypt PingToken with shared secret
-        let encrypted_ping = match crate::crypto::encryption::encrypt_message(&ping_bytes, &shared_secret) {
+        // Encrypt PingToken to a fresh ephemeral X25519 key (forward secret)
+        let ping_envelope = match crate::network::encrypt_ping_envelope(&ping_bytes, &recipient_x25519_pubkey, None) {
             Ok(enc) => enc,
             Err(e) => {
                 let _ = env.throw_new("java/lang/RuntimeException", format!("Encryption failed: {}", e));
@@ -1504,11 +1601,10 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_sendPing(
             }
         };
 
-        // Wire format: [Type Byte 0x01][Our X25519 Public Key - 32 bytes][Encrypted Ping Token]
+        // Wire format: [Type Byte 0x01][Ping Envelope]
         let mut wire_message = Vec::new();
         wire_message.push(crate::network::tor::MSG_TYPE_PING); // Add type byte
-        wire_message.extend_from_slice(&sender_x25519_pubkey);
-        wire_message.extend_from_slice(&encrypted_ping);
+        wire_message.extend_from_slice(&ping_envelope);
 
         // Send encrypted Ping via Tor
         // HYBRID MODE: Try instant Pong (30s timeout), fall back to delayed mode
@@ -1832,10 +1928,8 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_sendTap(
         };
 
         // Derive shared secret using X25519 ECDH
-        let shared_secret = match crate::crypto::key_exchange::derive_shared_secret(
-            &our_x25519_private,
-            &recipient_x25519_bytes,
-        ) {
+        let shared_secret = match crate::crypto::key_exchange::X25519Secret::from_bytes(&our_x25519_private)
+            .and_then(|secret| secret.diffie_hellman(&recipient_x25519_bytes)) {
             Ok(secret) => secret,
             Err(e) => {
                 log::error!("ECDH failed: {}", e);
@@ -1852,7 +1946,7 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_sendTap(
         let tap_payload = format!("TAP:{}", timestamp);
 
         // Encrypt tap with shared secret
-        let encrypted_tap = match crate::crypto::encryption::encrypt_message(tap_payload.as_bytes(), &shared_secret) {
+        let encrypted_tap = match crate::crypto::encryption::encrypt_message(tap_payload.as_bytes(), shared_secret.as_slice()) {
             Ok(enc) => enc,
             Err(e) => {
                 log::error!("Tap encryption failed: {}", e);
@@ -2388,10 +2482,8 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptIncomingTap(
         };
 
         // Derive shared secret
-        let shared_secret = match crate::crypto::key_exchange::derive_shared_secret(
-            &our_x25519_private,
-            &sender_x25519_pubkey.to_vec(),
-        ) {
+        let shared_secret = match crate::crypto::key_exchange::X25519Secret::from_bytes(&our_x25519_private)
+            .and_then(|secret| secret.diffie_hellman(&sender_x25519_pubkey)) {
             Ok(secret) => secret,
             Err(e) => {
                 log::error!("ECDH failed: {}", e);
@@ -2400,7 +2492,7 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptIncomingTap(
         };
 
         // Decrypt tap
-        let decrypted = match crate::crypto::encryption::decrypt_message(encrypted_tap, &shared_secret) {
+        let decrypted = match crate::crypto::encryption::decrypt_message(encrypted_tap, shared_secret.as_slice()) {
             Ok(plain) => plain,
             Err(e) => {
                 log::error!("Tap decryption failed: {}", e);
@@ -2559,10 +2651,8 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptAndStorePongFro
         };
 
         // Derive shared secret
-        let shared_secret = match crate::crypto::key_exchange::derive_shared_secret(
-            &our_x25519_private,
-            &recipient_x25519_pubkey.to_vec(),
-        ) {
+        let shared_secret = match crate::crypto::key_exchange::X25519Secret::from_bytes(&our_x25519_private)
+            .and_then(|secret| secret.diffie_hellman(&recipient_x25519_pubkey)) {
             Ok(secret) => secret,
             Err(e) => {
                 log::error!("ECDH failed: {}", e);
@@ -2571,7 +2661,7 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptAndStorePongFro
         };
 
         // Decrypt the Pong token
-        let decrypted_pong = match crate::crypto::encryption::decrypt_message(encrypted_pong, &shared_secret) {
+        let decrypted_pong = match crate::crypto::encryption::decrypt_message(encrypted_pong, shared_secret.as_slice()) {
             Ok(plaintext) => plaintext,
             Err(e) => {
                 log::error!("Pong decryption failed: {}", e);
@@ -2580,7 +2670,7 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptAndStorePongFro
         };
 
         // Deserialize Pong token
-        let pong_token: crate::protocol::message::PongToken = match bincode::deserialize(&decrypted_pong) {
+        let pong_token = match crate::protocol::message::PongToken::deserialize(&decrypted_pong) {
             Ok(token) => token,
             Err(e) => {
                 log::error!("Failed to deserialize Pong token: {}", e);
@@ -2599,6 +2689,7 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptAndStorePongFro
             timestamp: pong_token.timestamp,
             authenticated: pong_token.authenticated,
             signature: pong_token.signature,
+            pq_signature: pong_token.pq_signature,
         };
 
         // Store in GLOBAL_PONG_SESSIONS
@@ -2903,17 +2994,91 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_sendHttpToVoiceOnion(
 static STORED_PINGS: Lazy<Arc<Mutex<HashMap<String, crate::network::PingToken>>>> =
     Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
 
+/// TOFU identity key registry (set up by initKeyRegistry)
+static KEY_REGISTRY: OnceCell<Mutex<KeyRegistry<FileKeyStorage>>> = OnceCell::new();
+
+/// Key changes detected on incoming traffic, drained by pollKeyChangeEvents
+static KEY_CHANGE_EVENTS: Lazy<Mutex<Vec<KeyChangeEvent>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+fn key_change_event_json(event: &KeyChangeEvent) -> serde_json::Value {
+    serde_json::json!({
+        "contactId": event.contact_id,
+        "previousEd25519": hex::encode(event.previous_keys.ed25519),
+        "previousX25519": hex::encode(event.previous_keys.x25519),
+        "presentedEd25519": hex::encode(event.presented_keys.ed25519),
+        "presentedX25519": hex::encode(event.presented_keys.x25519),
+        "ed25519Changed": event.ed25519_changed,
+        "x25519Changed": event.x25519_changed,
+        "wasVerified": event.was_verified,
+        "detectedAt": event.detected_at,
+    })
+}
+
+fn key_check_json(check: &KeyCheck) -> serde_json::Value {
+    match check {
+        KeyCheck::FirstSeen => serde_json::json!({ "result": "first_seen" }),
+        KeyCheck::Trusted { verified } => serde_json::json!({ "result": "trusted", "verified": verified }),
+        KeyCheck::Flagged(event) => serde_json::json!({ "result": "flagged", "event": key_change_event_json(event) }),
+        KeyCheck::Blocked(event) => serde_json::json!({ "result": "blocked", "event": key_change_event_json(event) }),
+    }
+}
+
+/// Check keys against the registry and queue any key change for the app
+fn check_registry_keys(
+    registry: &mut KeyRegistry<FileKeyStorage>,
+    contact_id: &str,
+    keys: &ContactKeys,
+) -> Result<KeyCheck, crate::protocol::key_registry::KeyRegistryError> {
+    let check = registry.check(contact_id, keys)?;
+    if let Some(event) = check.key_change() {
+        KEY_CHANGE_EVENTS.lock().unwrap().push(event.clone());
+    }
+    Ok(check)
+}
+
+/// Compare a Ping's sender keys with the keys pinned for `contact_id`
+///
+/// The contact must come from the caller's own state (ping inbox entry, contact record
+/// looked up by its stored identity key), never from the keys the Ping presents, so a
+/// sender cannot choose which record it is checked against.
+fn check_ping_sender(contact_id: &str, ping: &crate::network::PingToken) -> Result<(), String> {
+    let registry = KEY_REGISTRY.get().ok_or("Key registry not initialized")?;
+    let presented = ContactKeys { ed25519: ping.sender_pubkey, x25519: ping.sender_x25519_pubkey };
+    match check_registry_keys(&mut registry.lock().unwrap(), contact_id, &presented) {
+        Ok(check) if check.is_allowed() => Ok(()),
+        Ok(_) => Err(format!("Identity key changed for contact {}", contact_id)),
+        Err(e) => Err(format!("Key registry check failed: {}", e)),
+    }
+}
+
 /// Decrypt an incoming encrypted Ping token
 ///
-/// Wire format: [Sender X25519 Public Key - 32 bytes][Encrypted Ping Token]
+/// Wire format: [Ping Envelope] (see network::pingpong::encrypt_ping_envelope), or the
+/// legacy [Sender X25519 Public Key - 32 bytes][Encrypted Ping Token]
+/// contact_id: Contact the caller expects this Ping from (e.g. its ping inbox entry); its
+///             pinned keys must match the sender's. Null for Pings fresh off the listener,
+///             which the caller checks with verifyPingSender once it has resolved the contact
 /// Returns: Ping ID (String) that can be passed to respondToPing
 #[no_mangle]
 pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptIncomingPing(
     mut env: JNIEnv,
     _class: JClass,
     encrypted_ping_wire: JByteArray,
+    contact_id: JString,
 ) -> jstring {
     catch_panic!(env, {
+        let contact_id = if contact_id.is_null() {
+            None
+        } else {
+            match jstring_to_string(&mut env, contact_id) {
+                Ok(s) => Some(s),
+                Err(e) => {
+                    let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                    return std::ptr::null_mut();
+                }
+            }
+        };
+
         // Convert wire bytes
         let wire_bytes = match jbytearray_to_vec(&mut env, encrypted_ping_wire) {
             Ok(v) => v,
@@ -2923,19 +3088,7 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptIncomingPing(
             }
         };
 
-        // Wire format: [Sender X25519 Pubkey - 32 bytes][Encrypted Token]
-        if wire_bytes.len() < 32 {
-            let _ = env.throw_new("java/lang/IllegalArgumentException", "Wire message too short");
-            return std::ptr::null_mut();
-        }
-
-        // Extract sender's X25519 public key (first 32 bytes)
-        let sender_x25519_pubkey = &wire_bytes[0..32];
-
-        // Extract encrypted Ping token (rest of bytes)
-        let encrypted_ping = &wire_bytes[32..];
-
-        log::info!("Decrypting incoming Ping: {} bytes encrypted data", encrypted_ping.len());
+        log::info!("Decrypting incoming Ping: {} bytes", wire_bytes.len());
 
         // Get KeyManager to access our X25519 private key
         let context = match env.call_static_method(
@@ -2961,56 +3114,34 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptIncomingPing(
 
         // Get our X25519 private key
         let our_x25519_private = match crate::ffi::keystore::get_encryption_private_key(&mut env, &key_manager) {
-            Ok(k) => k,
+            Ok(k) => Zeroizing::new(k),
             Err(e) => {
                 let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get X25519 private key: {}", e));
                 return std::ptr::null_mut();
             }
         };
 
-        // Derive shared secret using X25519 ECDH
-        let shared_secret = match crate::crypto::key_exchange::derive_shared_secret(
-            &our_x25519_private,
-            sender_x25519_pubkey,
-        ) {
-            Ok(secret) => secret,
-            Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("ECDH failed: {}", e));
-                return std::ptr::null_mut();
-            }
-        };
-
-        log::info!("Derived shared secret for Ping decryption");
-
-        // Decrypt the Ping token
-        let decrypted_ping = match crate::crypto::encryption::decrypt_message(encrypted_ping, &shared_secret) {
-            Ok(plaintext) => plaintext,
-            Err(e) => {
-                let _ = env.throw_new("java/lang/SecurityException", format!("Ping decryption failed: {}", e));
+        let our_x25519_private = match crate::crypto::key_exchange::X25519Secret::from_bytes(&our_x25519_private) {
+            Ok(k) => k,
+            Err(_) => {
+                let _ = env.throw_new("java/lang/RuntimeException", "X25519 private key must be 32 bytes");
                 return std::ptr::null_mut();
             }
         };
 
-        log::info!("Ping decrypted successfully: {} bytes", decrypted_ping.len());
-
-        // Parse PingToken
-        let ping_token = match crate::network::PingToken::from_bytes(&decrypted_ping) {
+        // Decrypt (envelope or legacy format) and verify the Ping token
+        let ping_token = match crate::network::decrypt_incoming_ping(&wire_bytes, &our_x25519_private, None) {
             Ok(token) => token,
             Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to parse PingToken: {}", e));
+                let _ = env.throw_new("java/lang/SecurityException", format!("Ping decryption failed: {}", e));
                 return std::ptr::null_mut();
             }
         };
 
-        // Verify signature
-        match ping_token.verify() {
-            Ok(true) => {},
-            Ok(false) => {
-                let _ = env.throw_new("java/lang/SecurityException", "Invalid Ping signature");
-                return std::ptr::null_mut();
-            },
-            Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("Signature verification failed: {}", e));
+        // TOFU: the Ping must carry the keys pinned for the contact the caller expects
+        if let Some(contact_id) = contact_id.as_deref() {
+            if let Err(e) = check_ping_sender(contact_id, &ping_token) {
+                let _ = env.throw_new("java/lang/SecurityException", e);
                 return std::ptr::null_mut();
             }
         }
@@ -3037,122 +3168,329 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptIncomingPing(
     }, std::ptr::null_mut())
 }
 
-/// Get the sender's Ed25519 public key from a stored Ping token
+/// Check a decrypted Ping against the keys pinned for the contact the app resolved it to
+/// A rejected Ping is dropped, so respondToPing can no longer answer it
+/// Returns: true if the sender's keys match (or were pinned just now)
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_getPingSenderPublicKey(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_verifyPingSender(
     mut env: JNIEnv,
     _class: JClass,
     ping_id: JString,
-) -> jbyteArray {
+    contact_id: JString,
+) -> jboolean {
     catch_panic!(env, {
-        // Convert ping_id to String
-        let ping_id_str = match jstring_to_string(&mut env, ping_id) {
-            Ok(s) => s,
-            Err(e) => {
+        let (ping_id, contact_id) = match (jstring_to_string(&mut env, ping_id), jstring_to_string(&mut env, contact_id)) {
+            (Ok(ping_id), Ok(contact_id)) => (ping_id, contact_id),
+            (Err(e), _) | (_, Err(e)) => {
                 let _ = env.throw_new("java/lang/IllegalArgumentException", e);
-                return std::ptr::null_mut();
+                return 0 as jboolean;
             }
         };
 
-        // Look up stored PingToken (read-only, don't remove)
-        let sender_pubkey = {
-            let stored = STORED_PINGS.lock().unwrap();
-            match stored.get(&ping_id_str) {
-                Some(token) => token.sender_pubkey.clone(),
-                None => {
-                    log::warn!("Ping ID not found in storage: {}", ping_id_str);
-                    return std::ptr::null_mut();
-                }
-            }
+        let mut stored = STORED_PINGS.lock().unwrap();
+        let Some(ping_token) = stored.get(&ping_id) else {
+            log::warn!("verifyPingSender: no stored Ping {}", ping_id);
+            return 0 as jboolean;
         };
 
-        log::info!("Retrieved sender public key for Ping {}", ping_id_str);
-
-        // Convert sender_pubkey to Java byte array
-        match env.byte_array_from_slice(&sender_pubkey) {
-            Ok(arr) => arr.into_raw(),
+        match check_ping_sender(&contact_id, ping_token) {
+            Ok(()) => 1 as jboolean,
             Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to create byte array: {}", e));
-                std::ptr::null_mut()
+                log::warn!("Dropping Ping {}: {}", ping_id, e);
+                stored.remove(&ping_id);
+                0 as jboolean
             }
         }
-    }, std::ptr::null_mut())
+    }, 0 as jboolean)
 }
 
+/// Open the TOFU key registry stored at `path` (call once at startup)
+/// block_on_key_change: drop traffic from contacts whose keys changed (verified contacts are always blocked)
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_respondToPing(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_initKeyRegistry(
     mut env: JNIEnv,
     _class: JClass,
-    ping_id: JString,
-    authenticated: jboolean,
-) -> jbyteArray {
+    path: JString,
+    block_on_key_change: jboolean,
+) -> jboolean {
     catch_panic!(env, {
-        // If not authenticated, return null (no Pong)
-        if authenticated == 0 {
-            log::info!("Ping denied by user - not sending Pong");
-            return std::ptr::null_mut();
-        }
-
-        // Convert ping_id to String
-        let ping_id_str = match jstring_to_string(&mut env, ping_id) {
+        let path_str = match jstring_to_string(&mut env, path) {
             Ok(s) => s,
             Err(e) => {
                 let _ = env.throw_new("java/lang/IllegalArgumentException", e);
-                return std::ptr::null_mut();
+                return 0 as jboolean;
             }
         };
 
-        // Look up stored PingToken
-        let ping_token = {
-            let mut stored = STORED_PINGS.lock().unwrap();
-            match stored.remove(&ping_id_str) {
-                Some(token) => token,
-                None => {
-                    let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Ping ID not found: {}", ping_id_str));
-                    return std::ptr::null_mut();
+        let policy = if block_on_key_change != 0 { KeyChangePolicy::Block } else { KeyChangePolicy::Flag };
+        match KeyRegistry::open(FileKeyStorage::new(path_str), policy) {
+            Ok(registry) => {
+                if KEY_REGISTRY.set(Mutex::new(registry)).is_err() {
+                    log::warn!("Key registry already initialized");
                 }
+                1 as jboolean
             }
-        };
-
-        log::info!("Retrieved Ping {} from storage", ping_id_str);
-
-        // Get sender's X25519 public key from PingToken
-        // Note: We need to extract this from the sender's Ed25519 public key
-        // For now, we'll derive shared secret using the same approach as in sendPing
-
-        // Get KeyManager for our keys
-        let context = match env.call_static_method(
-            "android/app/ActivityThread",
-            "currentApplication",
-            "()Landroid/app/Application;",
-            &[],
-        ) {
-            Ok(ctx) => ctx.l().unwrap(),
             Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get context: {}", e));
-                return std::ptr::null_mut();
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to open key registry: {}", e));
+                0 as jboolean
             }
-        };
+        }
+    }, 0 as jboolean)
+}
 
-        let key_manager = match crate::ffi::keystore::get_key_manager(&mut env, &context) {
-            Ok(km) => km,
+/// Check a contact's identity keys against the TOFU registry, pinning them on first use
+/// Returns JSON: {"result": "first_seen" | "trusted" | "flagged" | "blocked", "verified"?, "event"?}
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_checkContactKeys(
+    mut env: JNIEnv,
+    _class: JClass,
+    contact_id: JString,
+    ed25519_public_key: JByteArray,
+    x25519_public_key: JByteArray,
+) -> jstring {
+    catch_panic!(env, {
+        let contact_id = match jstring_to_string(&mut env, contact_id) {
+            Ok(s) => s,
             Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get KeyManager: {}", e));
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
                 return std::ptr::null_mut();
             }
         };
+        let ed25519: Option<[u8; 32]> = jbytearray_to_vec(&mut env, ed25519_public_key)
+            .ok()
+            .and_then(|v| v.try_into().ok());
+        let x25519: Option<[u8; 32]> = jbytearray_to_vec(&mut env, x25519_public_key)
+            .ok()
+            .and_then(|v| v.try_into().ok());
+        let (Some(ed25519), Some(x25519)) = (ed25519, x25519) else {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", "Public keys must be 32 bytes");
+            return std::ptr::null_mut();
+        };
 
-        // Get our signing private key
-        let our_signing_private = match crate::ffi::keystore::get_signing_private_key(&mut env, &key_manager) {
-            Ok(k) => k,
+        let Some(registry) = KEY_REGISTRY.get() else {
+            let _ = env.throw_new("java/lang/IllegalStateException", "Key registry not initialized");
+            return std::ptr::null_mut();
+        };
+
+        let check = match check_registry_keys(&mut registry.lock().unwrap(), &contact_id, &ContactKeys { ed25519, x25519 }) {
+            Ok(check) => check,
             Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get signing key: {}", e));
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Key registry check failed: {}", e));
                 return std::ptr::null_mut();
             }
         };
 
-        // Create Ed25519 keypair
-        let recipient_keypair = match ed25519_dalek::SigningKey::from_bytes(&our_signing_private.as_slice().try_into().unwrap()) {
+        match env.new_string(key_check_json(&check).to_string()) {
+            Ok(s) => s.into_raw(),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to create JSON: {}", e));
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Accept the keys from a contact's last key change (pins them as Unverified)
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_acceptContactKeyChange(
+    mut env: JNIEnv,
+    _class: JClass,
+    contact_id: JString,
+) -> jboolean {
+    catch_panic!(env, {
+        let contact_id = match jstring_to_string(&mut env, contact_id) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return 0 as jboolean;
+            }
+        };
+
+        match KEY_REGISTRY.get().map(|registry| registry.lock().unwrap().accept_key_change(&contact_id)) {
+            Some(Ok(())) => 1 as jboolean,
+            Some(Err(e)) => {
+                log::warn!("Failed to accept key change: {}", e);
+                0 as jboolean
+            }
+            None => {
+                let _ = env.throw_new("java/lang/IllegalStateException", "Key registry not initialized");
+                0 as jboolean
+            }
+        }
+    }, 0 as jboolean)
+}
+
+/// Mark a contact's pinned keys as verified (after comparing safety numbers)
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_markContactKeysVerified(
+    mut env: JNIEnv,
+    _class: JClass,
+    contact_id: JString,
+) -> jboolean {
+    catch_panic!(env, {
+        let contact_id = match jstring_to_string(&mut env, contact_id) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return 0 as jboolean;
+            }
+        };
+
+        match KEY_REGISTRY.get().map(|registry| registry.lock().unwrap().mark_verified(&contact_id)) {
+            Some(Ok(())) => 1 as jboolean,
+            Some(Err(e)) => {
+                log::warn!("Failed to mark contact verified: {}", e);
+                0 as jboolean
+            }
+            None => {
+                let _ = env.throw_new("java/lang/IllegalStateException", "Key registry not initialized");
+                0 as jboolean
+            }
+        }
+    }, 0 as jboolean)
+}
+
+/// Drain key changes detected on incoming traffic
+/// Returns a JSON array of key change events (empty if none)
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_pollKeyChangeEvents(
+    mut env: JNIEnv,
+    _class: JClass,
+) -> jstring {
+    catch_panic!(env, {
+        let events: Vec<serde_json::Value> = KEY_CHANGE_EVENTS
+            .lock()
+            .unwrap()
+            .drain(..)
+            .map(|event| key_change_event_json(&event))
+            .collect();
+
+        match env.new_string(serde_json::Value::Array(events).to_string()) {
+            Ok(s) => s.into_raw(),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to create JSON: {}", e));
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Get the sender's Ed25519 public key from a stored Ping token
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_getPingSenderPublicKey(
+    mut env: JNIEnv,
+    _class: JClass,
+    ping_id: JString,
+) -> jbyteArray {
+    catch_panic!(env, {
+        // Convert ping_id to String
+        let ping_id_str = match jstring_to_string(&mut env, ping_id) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        // Look up stored PingToken (read-only, don't remove)
+        let sender_pubkey = {
+            let stored = STORED_PINGS.lock().unwrap();
+            match stored.get(&ping_id_str) {
+                Some(token) => token.sender_pubkey.clone(),
+                None => {
+                    log::warn!("Ping ID not found in storage: {}", ping_id_str);
+                    return std::ptr::null_mut();
+                }
+            }
+        };
+
+        log::info!("Retrieved sender public key for Ping {}", ping_id_str);
+
+        // Convert sender_pubkey to Java byte array
+        match env.byte_array_from_slice(&sender_pubkey) {
+            Ok(arr) => arr.into_raw(),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to create byte array: {}", e));
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
+}
+
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_respondToPing(
+    mut env: JNIEnv,
+    _class: JClass,
+    ping_id: JString,
+    authenticated: jboolean,
+) -> jbyteArray {
+    catch_panic!(env, {
+        // If not authenticated, return null (no Pong)
+        if authenticated == 0 {
+            log::info!("Ping denied by user - not sending Pong");
+            return std::ptr::null_mut();
+        }
+
+        // Convert ping_id to String
+        let ping_id_str = match jstring_to_string(&mut env, ping_id) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        // Look up stored PingToken
+        let ping_token = {
+            let mut stored = STORED_PINGS.lock().unwrap();
+            match stored.remove(&ping_id_str) {
+                Some(token) => token,
+                None => {
+                    let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Ping ID not found: {}", ping_id_str));
+                    return std::ptr::null_mut();
+                }
+            }
+        };
+
+        log::info!("Retrieved Ping {} from storage", ping_id_str);
+
+        // Get sender's X25519 public key from PingToken
+        // Note: We need to extract this from the sender's Ed25519 public key
+        // For now, we'll derive shared secret using the same approach as in sendPing
+
+        // Get KeyManager for our keys
+        let context = match env.call_static_method(
+            "android/app/ActivityThread",
+            "currentApplication",
+            "()Landroid/app/Application;",
+            &[],
+        ) {
+            Ok(ctx) => ctx.l().unwrap(),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get context: {}", e));
+                return std::ptr::null_mut();
+            }
+        };
+
+        let key_manager = match crate::ffi::keystore::get_key_manager(&mut env, &context) {
+            Ok(km) => km,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get KeyManager: {}", e));
+                return std::ptr::null_mut();
+            }
+        };
+
+        // Get our signing private key
+        let our_signing_private = match crate::ffi::keystore::get_signing_private_key(&mut env, &key_manager) {
+            Ok(k) => k,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get signing key: {}", e));
+                return std::ptr::null_mut();
+            }
+        };
+
+        // Create Ed25519 keypair
+        let recipient_keypair = match ed25519_dalek::SigningKey::from_bytes(&our_signing_private.as_slice().try_into().unwrap()) {
             signing_key => ed25519_dalek::SigningKey::from(signing_key)
         };
 
@@ -3189,10 +3527,8 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_respondToPing(
         let sender_x25519_pubkey = &ping_token.sender_x25519_pubkey;
 
         // Derive shared secret
-        let shared_secret = match crate::crypto::key_exchange::derive_shared_secret(
-            &our_x25519_private,
-            sender_x25519_pubkey,
-        ) {
+        let shared_secret = match crate::crypto::key_exchange::X25519Secret::from_bytes(&our_x25519_private)
+            .and_then(|secret| secret.diffie_hellman(sender_x25519_pubkey)) {
             Ok(secret) => secret,
             Err(e) => {
                 let _ = env.throw_new("java/lang/RuntimeException", format!("ECDH failed: {}", e));
@@ -3201,7 +3537,7 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_respondToPing(
         };
 
         // Encrypt Pong token
-        let encrypted_pong = match crate::crypto::encryption::encrypt_message(&pong_bytes, &shared_secret) {
+        let encrypted_pong = match crate::crypto::encryption::encrypt_message(&pong_bytes, shared_secret.as_slice()) {
             Ok(ciphertext) => ciphertext,
             Err(e) => {
                 let _ = env.throw_new("java/lang/RuntimeException", format!("Pong encryption failed: {}", e));
@@ -3299,10 +3635,8 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptIncomingPong(
         };
 
         // Derive shared secret
-        let shared_secret = match crate::crypto::key_exchange::derive_shared_secret(
-            &our_x25519_private,
-            &sender_x25519_bytes,
-        ) {
+        let shared_secret = match crate::crypto::key_exchange::X25519Secret::from_bytes(&our_x25519_private)
+            .and_then(|secret| secret.diffie_hellman(&sender_x25519_bytes)) {
             Ok(secret) => secret,
             Err(e) => {
                 log::error!("ECDH failed for Pong decryption: {}", e);
@@ -3312,7 +3646,7 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptIncomingPong(
         };
 
         // Decrypt Pong
-        let pong_bytes = match crate::crypto::encryption::decrypt_message(encrypted_pong, &shared_secret) {
+        let pong_bytes = match crate::crypto::encryption::decrypt_message(encrypted_pong, shared_secret.as_slice()) {
             Ok(bytes) => bytes,
             Err(e) => {
                 log::error!("Pong decryption failed: {}", e);
@@ -3511,11 +3845,9 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_sendDirectMessage(
             }
         };
 
-        // Derive shared secret for Ping encryption
-        let shared_secret = match crate::crypto::key_exchange::derive_shared_secret(
-            &our_x25519_private,
-            &recipient_x25519_bytes,
-        ) {
+        // Derive shared secret for Pong decryption
+        let shared_secret = match crate::crypto::key_exchange::X25519Secret::from_bytes(&our_x25519_private)
+            .and_then(|secret| secret.diffie_hellman(&recipient_x25519_bytes)) {
             Ok(secret) => secret,
             Err(e) => {
                 let _ = env.throw_new("java/lang/RuntimeException", format!("ECDH failed: {}", e));
@@ -3523,8 +3855,8 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_sendDirectMessage(
             }
         };
 
-        // Encrypt Ping
-        let encrypted_ping = match crate::crypto::encryption::encrypt_message(&ping_bytes, &shared_secret) {
+        // Encrypt Ping to a fresh ephemeral X25519 key (forward secret)
+        let ping_envelope = match crate::network::encrypt_ping_envelope(&ping_bytes, &recipient_x25519_pubkey, None) {
             Ok(enc) => enc,
             Err(e) => {
                 let _ = env.throw_new("java/lang/RuntimeException", format!("Ping encryption failed: {}", e));
@@ -3532,20 +3864,10 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_sendDirectMessage(
             }
         };
 
-        // Get our X25519 public key to prepend
-        let our_x25519_public = match crate::ffi::keystore::get_encryption_public_key(&mut env, &key_manager) {
-            Ok(k) => k,
-            Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get our X25519 pubkey: {}", e));
-                return 0;
-            }
-        };
-
-        // Wire format for Ping: [Type Byte 0x01][Our X25519 Public Key - 32 bytes][Encrypted Ping Token]
+        // Wire format for Ping: [Type Byte 0x01][Ping Envelope]
         let mut ping_wire_message = Vec::new();
         ping_wire_message.push(crate::network::tor::MSG_TYPE_PING); // Add type byte
-        ping_wire_message.extend_from_slice(&our_x25519_public);
-        ping_wire_message.extend_from_slice(&encrypted_ping);
+        ping_wire_message.extend_from_slice(&ping_envelope);
 
         // Step 2: Send Ping via Tor and wait for Pong
         let tor_manager = get_tor_manager();
@@ -3598,7 +3920,7 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_sendDirectMessage(
             let encrypted_pong = &pong_response[1..];
 
             // Decrypt Pong
-            let decrypted_pong = crate::crypto::encryption::decrypt_message(encrypted_pong, &shared_secret)?;
+            let decrypted_pong = crate::crypto::encryption::decrypt_message(encrypted_pong, shared_secret.as_slice())?;
 
             // Parse Pong token
             let pong_token = crate::network::PongToken::from_bytes(&decrypted_pong)?;
@@ -3754,7 +4076,8 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_getBootstrapStatus(
 // ==================== PING-PONG PROTOCOL (Socket.IO) ====================
 
 /// Create encrypted Ping token for socket.io wake notification
-/// Uses KeyStore keys via JNI callbacks + X25519 ECDH for encryption
+/// Uses KeyStore keys via JNI callbacks; the token is sealed in a PING envelope
+/// (ephemeral X25519 per Ping, see network::pingpong::encrypt_ping_envelope)
 #[no_mangle]
 pub extern "C" fn Java_com_securelegion_crypto_RustBridge_createPingToken(
     mut env: JNIEnv,
@@ -3892,29 +4215,8 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_createPingToken(
             }
         };
 
-        // 7. Get our X25519 encryption key
-        let our_x25519_private = match crate::ffi::keystore::get_encryption_private_key(&mut env, &key_manager) {
-            Ok(k) => k,
-            Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get encryption key: {}", e));
-                return std::ptr::null_mut();
-            }
-        };
-
-        // 8. Derive shared secret using X25519 ECDH
-        let shared_secret = match crate::crypto::key_exchange::derive_shared_secret(
-            &our_x25519_private,
-            &recipient_x25519_bytes,
-        ) {
-            Ok(secret) => secret,
-            Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("ECDH failed: {}", e));
-                return std::ptr::null_mut();
-            }
-        };
-
-        // 9. Encrypt PingToken with shared secret
-        let encrypted_ping = match crate::crypto::encryption::encrypt_message(&ping_bytes, &shared_secret) {
+        // 7. Encrypt PingToken to a fresh ephemeral X25519 key (forward secret)
+        let encrypted_ping = match crate::network::encrypt_ping_envelope(&ping_bytes, &recipient_x25519_pubkey, None) {
             Ok(enc) => enc,
             Err(e) => {
                 let _ = env.throw_new("java/lang/RuntimeException", format!("Encryption failed: {}", e));
@@ -3922,7 +4224,7 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_createPingToken(
             }
         };
 
-        // 10. Return encrypted bytes
+        // 8. Return encrypted bytes
         match vec_to_jbytearray(&mut env, &encrypted_ping) {
             Ok(arr) => arr.into_raw(),
             Err(e) => {
@@ -3984,56 +4286,36 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_nativeDecryptPingToken
 
         // 3. Get our X25519 private key
         let our_x25519_private = match crate::ffi::keystore::get_encryption_private_key(&mut env, &key_manager) {
-            Ok(k) => k,
+            Ok(k) => Zeroizing::new(k),
             Err(e) => {
                 let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get encryption key: {}", e));
                 return std::ptr::null_mut();
             }
         };
 
-        // 4. Derive shared secret
-        let shared_secret = match crate::crypto::key_exchange::derive_shared_secret(
-            &our_x25519_private,
-            &sender_x25519_bytes,
-        ) {
-            Ok(secret) => secret,
-            Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("ECDH failed: {}", e));
-                return std::ptr::null_mut();
-            }
-        };
-
-        // 5. Decrypt
-        let ping_bytes = match crate::crypto::encryption::decrypt_message(&encrypted_bytes, &shared_secret) {
-            Ok(bytes) => bytes,
-            Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("Decryption failed: {}", e));
+        let our_x25519_private = match crate::crypto::key_exchange::X25519Secret::from_bytes(&our_x25519_private) {
+            Ok(k) => k,
+            Err(_) => {
+                let _ = env.throw_new("java/lang/RuntimeException", "X25519 private key must be 32 bytes");
                 return std::ptr::null_mut();
             }
         };
 
-        // 6. Deserialize PingToken
-        let ping_token = match crate::network::PingToken::from_bytes(&ping_bytes) {
+        // 4-7. Decrypt and verify: PING envelope (createPingToken), or legacy static-ECDH token
+        let ping_result = crate::network::decrypt_ping_envelope(&encrypted_bytes, &our_x25519_private, None)
+            .or_else(|_| {
+                let mut legacy_wire = sender_x25519_bytes.clone();
+                legacy_wire.extend_from_slice(&encrypted_bytes);
+                crate::network::pingpong::decrypt_legacy_ping(&legacy_wire, &our_x25519_private)
+            });
+        let ping_token = match ping_result {
             Ok(token) => token,
             Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to parse Ping: {}", e));
+                let _ = env.throw_new("java/lang/SecurityException", format!("Ping decryption failed: {}", e));
                 return std::ptr::null_mut();
             }
         };
 
-        // 7. Verify signature
-        match ping_token.verify() {
-            Ok(true) => {}, // Valid
-            Ok(false) => {
-                let _ = env.throw_new("java/lang/SecurityException", "Invalid Ping signature");
-                return std::ptr::null_mut();
-            },
-            Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("Signature verification failed: {}", e));
-                return std::ptr::null_mut();
-            }
-        }
-
         // 8. Store in global session storage
         let ping_id = hex::encode(&ping_token.nonce);
         crate::network::store_ping_session(&ping_id, ping_token.clone());
@@ -4188,10 +4470,8 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_createPongToken(
         };
 
         // 9. Derive shared secret using X25519 ECDH (with sender's X25519 public key)
-        let shared_secret = match crate::crypto::key_exchange::derive_shared_secret(
-            &our_x25519_private,
-            &sender_x25519_bytes,
-        ) {
+        let shared_secret = match crate::crypto::key_exchange::X25519Secret::from_bytes(&our_x25519_private)
+            .and_then(|secret| secret.diffie_hellman(&sender_x25519_bytes)) {
             Ok(secret) => secret,
             Err(e) => {
                 let _ = env.throw_new("java/lang/RuntimeException", format!("ECDH failed: {}", e));
@@ -4200,7 +4480,7 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_createPongToken(
         };
 
         // 10. Encrypt PongToken with shared secret
-        let encrypted_pong = match crate::crypto::encryption::encrypt_message(&pong_bytes, &shared_secret) {
+        let encrypted_pong = match crate::crypto::encryption::encrypt_message(&pong_bytes, shared_secret.as_slice()) {
             Ok(enc) => enc,
             Err(e) => {
                 let _ = env.throw_new("java/lang/RuntimeException", format!("Encryption failed: {}", e));
@@ -4289,10 +4569,8 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_nativeDecryptPongToken
         };
 
         // 4. Derive shared secret
-        let shared_secret = match crate::crypto::key_exchange::derive_shared_secret(
-            &our_x25519_private,
-            &recipient_x25519_bytes,
-        ) {
+        let shared_secret = match crate::crypto::key_exchange::X25519Secret::from_bytes(&our_x25519_private)
+            .and_then(|secret| secret.diffie_hellman(&recipient_x25519_bytes)) {
             Ok(secret) => secret,
             Err(e) => {
                 let _ = env.throw_new("java/lang/RuntimeException", format!("ECDH failed: {}", e));
@@ -4301,7 +4579,7 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_nativeDecryptPongToken
         };
 
         // 5. Decrypt
-        let pong_bytes = match crate::crypto::encryption::decrypt_message(&encrypted_bytes, &shared_secret) {
+        let pong_bytes = match crate::crypto::encryption::decrypt_message(&encrypted_bytes, shared_secret.as_slice()) {
             Ok(bytes) => bytes,
             Err(e) => {
                 let _ = env.throw_new("java/lang/RuntimeException", format!("Decryption failed: {}", e));
@@ -4636,9 +4914,9 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_deriveSharedSecret(
         };
 
         // Derive shared secret
-        match crate::crypto::key_exchange::derive_shared_secret(&our_private, &their_public) {
+        match crate::crypto::key_exchange::X25519Secret::from_bytes(&our_private).and_then(|secret| secret.diffie_hellman(&their_public)) {
             Ok(shared_secret) => {
-                match vec_to_jbytearray(&mut env, &shared_secret) {
+                match vec_to_jbytearray(&mut env, shared_secret.as_slice()) {
                     Ok(arr) => arr.into_raw(),
                     Err(e) => {
                         let _ = env.throw_new("java/lang/RuntimeException", e);
@@ -4687,8 +4965,8 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_deriveRootKey(
             }
         };
 
-        match derive_root_key(&shared_secret_vec, info_str.as_bytes()) {
-            Ok(root_key) => match vec_to_jbytearray(&mut env, &root_key) {
+        match RootKey::derive(&shared_secret_vec, info_str.as_bytes()) {
+            Ok(root_key) => match vec_to_jbytearray(&mut env, root_key.expose_secret()) {
                 Ok(arr) => arr.into_raw(),
                 Err(e) => {
                     let _ = env.throw_new("java/lang/RuntimeException", e);
@@ -4727,18 +5005,19 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_evolveChainKey(
             return std::ptr::null_mut();
         }
 
-        let mut chain_key_array: [u8; 32] = chain_key_vec.try_into().unwrap();
+        let mut chain_key = match ChainKey::from_bytes(&chain_key_vec) {
+            Ok(k) => k,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", format!("{}", e));
+                return std::ptr::null_mut();
+            }
+        };
+        chain_key.advance();
 
-        match evolve_chain_key(&mut chain_key_array) {
-            Ok(new_chain_key) => match vec_to_jbytearray(&mut env, &new_chain_key) {
-                Ok(arr) => arr.into_raw(),
-                Err(e) => {
-                    let _ = env.throw_new("java/lang/RuntimeException", e);
-                    std::ptr::null_mut()
-                }
-            },
+        match vec_to_jbytearray(&mut env, chain_key.expose_secret()) {
+            Ok(arr) => arr.into_raw(),
             Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("{}", e));
+                let _ = env.throw_new("java/lang/RuntimeException", e);
                 std::ptr::null_mut()
             }
         }
@@ -4775,10 +5054,16 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_deriveReceiveKeyAtSequ
             return std::ptr::null_mut();
         }
 
-        let root_key_array: [u8; 32] = root_key_vec.try_into().unwrap();
-
-        // Parse onion addresses
-        let our_onion_str = match jstring_to_string(&mut env, our_onion) {
+        let root_key = match RootKey::from_bytes(&root_key_vec) {
+            Ok(k) => k,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", format!("{}", e));
+                return std::ptr::null_mut();
+            }
+        };
+
+        // Parse onion addresses
+        let our_onion_str = match jstring_to_string(&mut env, our_onion) {
             Ok(s) => s,
             Err(e) => {
                 let _ = env.throw_new("java/lang/IllegalArgumentException", e);
@@ -4795,13 +5080,8 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_deriveReceiveKeyAtSequ
         };
 
         // Derive key at sequence
-        match derive_receive_key_at_sequence(
-            &root_key_array,
-            sender_sequence as u64,
-            &our_onion_str,
-            &their_onion_str,
-        ) {
-            Ok(key) => match vec_to_jbytearray(&mut env, &key[..]) {
+        match root_key.receive_chain_key_at(sender_sequence as u64, &our_onion_str, &their_onion_str) {
+            Ok(key) => match vec_to_jbytearray(&mut env, key.expose_secret()) {
                 Ok(arr) => arr.into_raw(),
                 Err(e) => {
                     let _ = env.throw_new("java/lang/RuntimeException", e);
@@ -4839,18 +5119,18 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_deriveMessageKey(
             return std::ptr::null_mut();
         }
 
-        let chain_key_array: [u8; 32] = chain_key_vec.try_into().unwrap();
+        let message_key = match ChainKey::from_bytes(&chain_key_vec) {
+            Ok(k) => k.message_key(),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", format!("{}", e));
+                return std::ptr::null_mut();
+            }
+        };
 
-        match derive_message_key(&chain_key_array) {
-            Ok(message_key) => match vec_to_jbytearray(&mut env, &message_key) {
-                Ok(arr) => arr.into_raw(),
-                Err(e) => {
-                    let _ = env.throw_new("java/lang/RuntimeException", e);
-                    std::ptr::null_mut()
-                }
-            },
+        match vec_to_jbytearray(&mut env, message_key.expose_secret()) {
+            Ok(arr) => arr.into_raw(),
             Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("{}", e));
+                let _ = env.throw_new("java/lang/RuntimeException", e);
                 std::ptr::null_mut()
             }
         }
@@ -5086,6 +5366,140 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_hybridDecapsulate(
     }, std::ptr::null_mut())
 }
 
+/// Install our Ed25519 + ML-DSA-65 signing keypair derived from the identity seed
+/// Pings, Pongs and delivery ACKs we create afterwards carry an ML-DSA signature
+/// @param seed BIP39 wallet seed (64 bytes)
+/// @return Our ML-DSA-65 public key (1952 bytes), to publish in the contact card
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_initHybridSigning(
+    mut env: JNIEnv,
+    _class: JClass,
+    seed: JByteArray,
+) -> jbyteArray {
+    catch_panic!(env, {
+        let seed = match jbytearray_to_vec(&mut env, seed) {
+            Ok(v) => Zeroizing::new(v),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
+        if seed.len() != 64 {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", "Seed must be 64 bytes");
+            return std::ptr::null_mut();
+        }
+
+        let keypair = match crate::crypto::pqc::HybridSigningKeypair::from_seed(&seed) {
+            Ok(keypair) => keypair,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to derive hybrid keypair: {}", e));
+                return std::ptr::null_mut();
+            }
+        };
+        let ml_dsa_public = crate::crypto::pqc::hybrid_signature::install_local_keypair(keypair);
+
+        match vec_to_jbytearray(&mut env, &ml_dsa_public) {
+            Ok(arr) => arr.into_raw(),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Drop our hybrid signing keypair (call when the app locks)
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_clearHybridSigning(
+    mut env: JNIEnv,
+    _class: JClass,
+) {
+    catch_panic!(env, {
+        crate::crypto::pqc::hybrid_signature::clear_local_keypair();
+    }, ())
+}
+
+/// Our ML-DSA-65 public key, or null if initHybridSigning has not run
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_getHybridSigningPublicKey(
+    mut env: JNIEnv,
+    _class: JClass,
+) -> jbyteArray {
+    catch_panic!(env, {
+        let Some(ml_dsa_public) = crate::crypto::pqc::hybrid_signature::local_ml_dsa_public_key() else {
+            return std::ptr::null_mut();
+        };
+        match vec_to_jbytearray(&mut env, &ml_dsa_public) {
+            Ok(arr) => arr.into_raw(),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Register a contact's ML-DSA-65 key from their contact card
+/// From then on their Pings, Pongs and ACKs must carry a valid ML-DSA signature
+/// @return false if the key is malformed or differs from the one already registered
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_registerContactMlDsaKey(
+    mut env: JNIEnv,
+    _class: JClass,
+    ed25519_public_key: JByteArray,
+    ml_dsa_public_key: JByteArray,
+) -> jboolean {
+    catch_panic!(env, {
+        let ed25519: Option<[u8; 32]> = jbytearray_to_vec(&mut env, ed25519_public_key)
+            .ok()
+            .and_then(|v| v.try_into().ok());
+        let Some(ed25519) = ed25519 else {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", "Ed25519 public key must be 32 bytes");
+            return 0 as jboolean;
+        };
+        let ml_dsa_public = match jbytearray_to_vec(&mut env, ml_dsa_public_key) {
+            Ok(v) => v,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return 0 as jboolean;
+            }
+        };
+
+        match crate::crypto::pqc::hybrid_signature::register_peer_ml_dsa_key(ed25519, ml_dsa_public) {
+            Ok(()) => 1 as jboolean,
+            Err(e) => {
+                log::warn!("Rejected contact ML-DSA key: {}", e);
+                0 as jboolean
+            }
+        }
+    }, 0 as jboolean)
+}
+
+/// Register the capabilities from a contact's card
+/// A contact that negotiated canonical signing gets canonically signed tokens from then on,
+/// and their tokens must be signed canonically too
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_registerContactCapabilities(
+    mut env: JNIEnv,
+    _class: JClass,
+    ed25519_public_key: JByteArray,
+    peer_capabilities: jint,
+) {
+    catch_panic!(env, {
+        let ed25519: Option<[u8; 32]> = jbytearray_to_vec(&mut env, ed25519_public_key)
+            .ok()
+            .and_then(|v| v.try_into().ok());
+        let Some(ed25519) = ed25519 else {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", "Ed25519 public key must be 32 bytes");
+            return;
+        };
+
+        if negotiate_capabilities(LOCAL_CAPABILITIES, peer_capabilities as u8) & CAPABILITY_CANONICAL_SIGNING != 0 {
+            crate::crypto::pqc::hybrid_signature::register_peer_canonical_signing(ed25519);
+        }
+    }, ())
+}
+
 // ==================== END HYBRID POST-QUANTUM CRYPTOGRAPHY ====================
 
 /// Encrypt message with key evolution (for messaging)
@@ -5123,19 +5537,20 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_encryptMessageWithEvol
             }
         };
 
-        if chain_key_vec.len() != 32 {
-            let _ = env.throw_new("java/lang/IllegalArgumentException", "Chain key must be 32 bytes");
-            return std::ptr::null_mut();
-        }
-
-        let mut chain_key_array: [u8; 32] = chain_key_vec.try_into().unwrap();
+        let mut chain_key = match ChainKey::from_bytes(&chain_key_vec) {
+            Ok(key) => key,
+            Err(_) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "Chain key must be 32 bytes");
+                return std::ptr::null_mut();
+            }
+        };
 
-        match encrypt_message_with_evolution(plaintext_str.as_bytes(), &mut chain_key_array, sequence as u64) {
-            Ok(result) => {
+        match chain_key.encrypt_next(plaintext_str.as_bytes(), sequence as u64) {
+            Ok(ciphertext) => {
                 // Build result: [evolved_key:32][ciphertext]
-                let mut output = Vec::with_capacity(32 + result.ciphertext.len());
-                output.extend_from_slice(&result.evolved_chain_key);
-                output.extend_from_slice(&result.ciphertext);
+                let mut output = Vec::with_capacity(32 + ciphertext.len());
+                output.extend_from_slice(chain_key.expose_secret());
+                output.extend_from_slice(&ciphertext);
 
                 match vec_to_jbytearray(&mut env, &output) {
                     Ok(arr) => arr.into_raw(),
@@ -5188,22 +5603,23 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptMessageWithEvol
             }
         };
 
-        if chain_key_vec.len() != 32 {
-            let _ = env.throw_new("java/lang/IllegalArgumentException", "Chain key must be 32 bytes");
-            return std::ptr::null_mut();
-        }
-
-        let mut chain_key_array: [u8; 32] = chain_key_vec.try_into().unwrap();
+        let mut chain_key = match ChainKey::from_bytes(&chain_key_vec) {
+            Ok(key) => key,
+            Err(_) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "Chain key must be 32 bytes");
+                return std::ptr::null_mut();
+            }
+        };
 
-        match decrypt_message_with_evolution(&encrypted_vec, &mut chain_key_array, expected_sequence as u64) {
-            Ok(result) => {
+        match chain_key.decrypt_next(&encrypted_vec, expected_sequence as u64) {
+            Ok(plaintext) => {
                 // Convert plaintext bytes to UTF-8 string first
-                match String::from_utf8(result.plaintext.clone()) {
+                match String::from_utf8(plaintext) {
                     Ok(plaintext_str) => {
                         // Build result: [evolved_key:32][plaintext_utf8]
                         let plaintext_bytes = plaintext_str.as_bytes();
                         let mut output = Vec::with_capacity(32 + plaintext_bytes.len());
-                        output.extend_from_slice(&result.evolved_chain_key);
+                        output.extend_from_slice(chain_key.expose_secret());
                         output.extend_from_slice(plaintext_bytes);
 
                         match vec_to_jbytearray(&mut env, &output) {
@@ -5318,9 +5734,21 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptWithMessageKey(
 
 // ==================== PROTOCOL SECURITY FIXES (FIX #6, #7, #9) ====================
 
-use crate::crypto::encryption::{encrypt_message_deferred, store_pending_ratchet_advancement, commit_ratchet_advancement, rollback_ratchet_advancement};
-use crate::crypto::replay_cache::check_ping_replay;
-use crate::crypto::ack_state::{validate_and_record_ack, reset_ack_state};
+use crate::crypto::encryption::{encrypt_message_deferred, store_pending_ratchet_advancement, commit_ratchet_advancement, rollback_ratchet_advancement, init_ratchet_journal};
+use crate::crypto::encryption::{ack_ratchet_persisted, commit_ratchet_advancement_through, rollback_ratchet_advancement_from};
+use crate::crypto::encryption::{encrypt_message_deferred_v2, decrypt_message_with_evolution_v2, set_legacy_v1_accepted, MessageBinding};
+use crate::crypto::encryption::{encrypt_message_with_evolution_v2, decrypt_with_message_key_v2, decrypt_message_with_skipped_keys};
+use crate::crypto::skipped_keys::SkippedKeyStore;
+use crate::crypto::encryption::{encrypt_message_committing, decrypt_message_committing};
+use crate::crypto::ratchet::{negotiate_capabilities, CAPABILITY_CANONICAL_SIGNING, CAPABILITY_KEY_COMMITTING, LOCAL_CAPABILITIES};
+use crate::crypto::canonical::set_legacy_signing_accepted;
+use crate::crypto::padding::PaddingScheme;
+use crate::protocol::security_mode::SecurityTier;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use crate::crypto::replay_cache::{check_ping_replay, compute_ping_hash, init_replay_guard, ReplayGuardConfig};
+use crate::crypto::ack_state::{
+    begin_delivery, mark_delivery_failed, reset_ack_state, stuck_deliveries, validate_and_record_ack,
+};
 
 /// FIX #6: Encrypt message with deferred ratchet commitment (Phase 1)
 /// Returns JSON: {"ciphertext": "base64", "nextChainKey": "base64", "nextSequence": 123}
@@ -5381,702 +5809,912 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_encryptMessageDeferred
     }, std::ptr::null_mut())
 }
 
-/// FIX #6: Store pending ratchet advancement (after encryption but before PING_ACK)
+/// Build a v2 header binding from JNI identity keys, message type, session epoch and
+/// the contact's capabilities (v3 key-committing when both sides advertise it)
+fn jni_message_binding(
+    env: &mut JNIEnv,
+    sender_identity: JByteArray,
+    recipient_identity: JByteArray,
+    msg_type: jint,
+    epoch: jint,
+    capabilities: jint,
+) -> Result<MessageBinding, String> {
+    let sender_vec = jbytearray_to_vec(env, sender_identity)?;
+    let recipient_vec = jbytearray_to_vec(env, recipient_identity)?;
+
+    let sender: [u8; 32] = sender_vec.try_into()
+        .map_err(|_| "Sender identity key must be 32 bytes".to_string())?;
+    let recipient: [u8; 32] = recipient_vec.try_into()
+        .map_err(|_| "Recipient identity key must be 32 bytes".to_string())?;
+    let msg_type = u8::try_from(msg_type)
+        .map_err(|_| "Message type must be a single byte".to_string())?;
+    let epoch = u32::try_from(epoch)
+        .map_err(|_| "Session epoch must not be negative".to_string())?;
+
+    let key_committing = negotiate_capabilities(LOCAL_CAPABILITIES, capabilities as u8) & CAPABILITY_KEY_COMMITTING != 0;
+
+    Ok(MessageBinding::new(sender, recipient, msg_type)
+        .with_epoch(epoch)
+        .with_key_committing(key_committing))
+}
+
+/// Encrypt message with deferred ratchet commitment using the v2 (authenticated header) format
+/// Binds version, sequence, both identity keys and the MSG_TYPE byte as AEAD associated data
+/// Plaintext is padded per security tier ("HIGH_RISK", "NORMAL", "BULK") before encryption
+/// Returns JSON: {"ciphertext": "base64", "nextChainKey": "base64", "nextSequence": 123}
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_storePendingRatchetAdvancement(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_encryptMessageDeferredV2(
     mut env: JNIEnv,
     _class: JClass,
-    contact_id: JString,
-    message_id: JString,
-    next_chain_key: JByteArray,
-    next_sequence: jlong,
-) -> jboolean {
+    plaintext: JString,
+    chain_key_bytes: JByteArray,
+    sequence: jlong,
+    sender_identity: JByteArray,
+    recipient_identity: JByteArray,
+    msg_type: jint,
+    epoch: jint,
+    capabilities: jint,
+    security_tier: JString,
+) -> jstring {
     catch_panic!(env, {
-        let contact_id_str = match jstring_to_string(&mut env, contact_id) {
-            Ok(s) => s,
-            Err(_) => return 0
-        };
-
-        let message_id_str = match jstring_to_string(&mut env, message_id) {
+        let plaintext_str = match jstring_to_string(&mut env, plaintext) {
             Ok(s) => s,
-            Err(_) => return 0
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
         };
 
-        let chain_key_vec = match jbytearray_to_vec(&mut env, next_chain_key) {
+        let chain_key_vec = match jbytearray_to_vec(&mut env, chain_key_bytes) {
             Ok(v) => v,
-            Err(_) => return 0
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
         };
 
         if chain_key_vec.len() != 32 {
-            return 0;
+            let _ = env.throw_new("java/lang/IllegalArgumentException", "Chain key must be 32 bytes");
+            return std::ptr::null_mut();
         }
 
-        let mut chain_key_array = [0u8; 32];
-        chain_key_array.copy_from_slice(&chain_key_vec);
-
-        match store_pending_ratchet_advancement(&contact_id_str, &message_id_str, chain_key_array, next_sequence as u64) {
-            Ok(_) => 1,
-            Err(_) => 0
-        }
-    }, 0)
-}
+        let binding = match jni_message_binding(&mut env, sender_identity, recipient_identity, msg_type, epoch, capabilities) {
+            Ok(b) => b,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
 
-/// FIX #6: Commit ratchet advancement after PING_ACK received (Phase 2)
-/// Returns JSON: {"nextChainKey": "base64", "nextSequence": 123} or null if no pending
-#[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_commitRatchetAdvancement(
-    mut env: JNIEnv,
-    _class: JClass,
-    contact_id: JString,
-) -> jstring {
-    catch_panic!(env, {
-        let contact_id_str = match jstring_to_string(&mut env, contact_id) {
-            Ok(s) => s,
+        let padding = match jstring_to_string(&mut env, security_tier) {
+            Ok(tier) => PaddingScheme::for_tier(SecurityTier::from_string(&tier)),
             Err(e) => {
                 let _ = env.throw_new("java/lang/IllegalArgumentException", e);
                 return std::ptr::null_mut();
             }
         };
 
-        match commit_ratchet_advancement(&contact_id_str) {
-            Ok(Some((next_key, next_seq))) => {
+        let mut chain_key_array = [0u8; 32];
+        chain_key_array.copy_from_slice(&chain_key_vec);
+
+        match encrypt_message_deferred_v2(plaintext_str.as_bytes(), &chain_key_array, sequence as u64, &binding, padding) {
+            Ok(result) => {
                 let json = serde_json::json!({
-                    "nextChainKey": base64::encode(&next_key),
-                    "nextSequence": next_seq
+                    "ciphertext": BASE64.encode(&result.ciphertext),
+                    "nextChainKey": BASE64.encode(result.next_chain_key),
+                    "nextSequence": result.next_sequence
                 });
 
                 match env.new_string(json.to_string()) {
                     Ok(s) => s.into_raw(),
-                    Err(_) => std::ptr::null_mut()
+                    Err(e) => {
+                        let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to create JSON: {}", e));
+                        std::ptr::null_mut()
+                    }
                 }
             }
-            Ok(None) => {
-                std::ptr::null_mut()
-            }
             Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("Commit failed: {}", e));
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Encryption failed: {}", e));
                 std::ptr::null_mut()
             }
         }
     }, std::ptr::null_mut())
 }
 
-/// FIX #6: Rollback pending ratchet advancement (if send permanently fails)
+/// Encrypt message with key evolution using the v2 (padded, authenticated header) format
+/// Only for contacts that negotiated CAPABILITY_CHAIN_V2; others still need encryptMessageWithEvolutionJNI
+/// Return format: [evolved_key:32][ciphertext]
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_rollbackRatchetAdvancement(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_encryptMessageWithEvolutionV2JNI(
     mut env: JNIEnv,
     _class: JClass,
-    contact_id: JString,
-) -> jboolean {
+    plaintext: JString,
+    chain_key: JByteArray,
+    sequence: jlong,
+    sender_identity: JByteArray,
+    recipient_identity: JByteArray,
+    msg_type: jint,
+    epoch: jint,
+    capabilities: jint,
+    security_tier: JString,
+) -> jbyteArray {
     catch_panic!(env, {
-        let contact_id_str = match jstring_to_string(&mut env, contact_id) {
+        let plaintext_str = match jstring_to_string(&mut env, plaintext) {
             Ok(s) => s,
-            Err(_) => return 0
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
         };
 
-        match rollback_ratchet_advancement(&contact_id_str) {
-            Ok(_) => 1,
-            Err(_) => 0
-        }
-    }, 0)
-}
-
-/// FIX #9: Check if PING is a replay attack
-/// Returns true if PING should be processed (not a replay)
-/// Returns false if PING is a duplicate (should be dropped)
-#[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_checkPingReplay(
-    mut env: JNIEnv,
-    _class: JClass,
-    sender_pubkey: JByteArray,
-    ping_bytes: JByteArray,
-) -> jboolean {
-    catch_panic!(env, {
-        let pubkey_vec = match jbytearray_to_vec(&mut env, sender_pubkey) {
+        let chain_key_vec = Zeroizing::new(match jbytearray_to_vec(&mut env, chain_key) {
             Ok(v) => v,
-            Err(_) => return 0
-        };
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        });
 
-        let ping_vec = match jbytearray_to_vec(&mut env, ping_bytes) {
-            Ok(v) => v,
-            Err(_) => return 0
+        let mut chain_key_array: Zeroizing<[u8; 32]> = match chain_key_vec.as_slice().try_into() {
+            Ok(key) => Zeroizing::new(key),
+            Err(_) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "Chain key must be 32 bytes");
+                return std::ptr::null_mut();
+            }
         };
 
-        if pubkey_vec.len() != 32 {
-            return 0;
-        }
-
-        let mut pubkey = [0u8; 32];
-        pubkey.copy_from_slice(&pubkey_vec);
-
-        let ping_hash = blake3::hash(&ping_vec);
-        let ping_hash_array: [u8; 32] = *ping_hash.as_bytes();
+        let binding = match jni_message_binding(&mut env, sender_identity, recipient_identity, msg_type, epoch, capabilities) {
+            Ok(b) => b,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
 
-        if check_ping_replay(pubkey, ping_hash_array) {
-            1  // Not a replay - process it
-        } else {
-            0  // Replay detected - drop it
+        let padding = match jstring_to_string(&mut env, security_tier) {
+            Ok(tier) => PaddingScheme::for_tier(SecurityTier::from_string(&tier)),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        match encrypt_message_with_evolution_v2(plaintext_str.as_bytes(), &mut chain_key_array, sequence as u64, &binding, padding) {
+            Ok(result) => {
+                // Build result: [evolved_key:32][ciphertext]
+                let mut output = Zeroizing::new(Vec::with_capacity(32 + result.ciphertext.len()));
+                output.extend_from_slice(result.evolved_chain_key.expose_secret());
+                output.extend_from_slice(&result.ciphertext);
+
+                match vec_to_jbytearray(&mut env, &output) {
+                    Ok(arr) => arr.into_raw(),
+                    Err(e) => {
+                        let _ = env.throw_new("java/lang/RuntimeException", e);
+                        std::ptr::null_mut()
+                    }
+                }
+            }
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Encryption failed: {}", e));
+                std::ptr::null_mut()
+            }
         }
-    }, 0)
+    }, std::ptr::null_mut())
 }
 
-/// FIX #7: Validate ACK ordering according to protocol rules
-/// @param contactId Contact identifier
-/// @param ackType 0=PING_ACK, 1=PONG_ACK, 2=MESSAGE_ACK
-/// @return true if ACK is valid, false if it violates ordering
+/// Decrypt a v2/v3 chain message with a stored (skipped) message key
+/// Binding is from the receiver's view: sender = contact's identity key, recipient = ours
+/// Returns the plaintext, or null if decryption fails
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_validateAckOrdering(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptWithMessageKeyV2(
     mut env: JNIEnv,
     _class: JClass,
-    contact_id: JString,
-    ack_type: jint,
-) -> jboolean {
+    ciphertext: JByteArray,
+    message_key: JByteArray,
+    sender_identity: JByteArray,
+    recipient_identity: JByteArray,
+    msg_type: jint,
+    epoch: jint,
+    capabilities: jint,
+) -> jstring {
     catch_panic!(env, {
-        let contact_id_str = match jstring_to_string(&mut env, contact_id) {
-            Ok(s) => s,
-            Err(_) => return 0
+        let ciphertext_vec = match jbytearray_to_vec(&mut env, ciphertext) {
+            Ok(v) => v,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
         };
 
-        let ack_type_u8 = match ack_type {
-            0 | 1 | 2 => ack_type as u8,
-            _ => return 0
+        let message_key_vec = Zeroizing::new(match jbytearray_to_vec(&mut env, message_key) {
+            Ok(v) => v,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        });
+
+        let message_key_array: Zeroizing<[u8; 32]> = match message_key_vec.as_slice().try_into() {
+            Ok(key) => Zeroizing::new(key),
+            Err(_) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "Message key must be 32 bytes");
+                return std::ptr::null_mut();
+            }
         };
 
-        if validate_and_record_ack(&contact_id_str, ack_type_u8) {
-            1  // Valid
-        } else {
-            0  // Invalid
+        let binding = match jni_message_binding(&mut env, sender_identity, recipient_identity, msg_type, epoch, capabilities) {
+            Ok(b) => b,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        let plaintext = match decrypt_with_message_key_v2(&ciphertext_vec, &message_key_array, &binding) {
+            Ok(p) => p,
+            Err(e) => {
+                log::warn!("Skipped-key decryption failed: {}", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        match String::from_utf8(plaintext) {
+            Ok(plaintext_str) => match env.new_string(&plaintext_str) {
+                Ok(jstr) => jstr.into_raw(),
+                Err(e) => {
+                    let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to create Java string: {}", e));
+                    std::ptr::null_mut()
+                }
+            },
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Invalid UTF-8: {}", e));
+                std::ptr::null_mut()
+            }
         }
-    }, 0)
+    }, std::ptr::null_mut())
 }
 
-/// FIX #7: Reset ACK state for a contact (after completing message exchange)
+/// Decrypt message with key evolution, accepting v2 and (during migration) v1
+/// Binding is from the receiver's view: sender = contact's identity key, recipient = ours
+/// Return format: [evolved_key:32][plaintext_utf8], or null if decryption fails
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_resetAckState(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptMessageWithEvolutionV2JNI(
     mut env: JNIEnv,
     _class: JClass,
-    contact_id: JString,
-) {
+    encrypted_data: JByteArray,
+    chain_key: JByteArray,
+    expected_sequence: jlong,
+    sender_identity: JByteArray,
+    recipient_identity: JByteArray,
+    msg_type: jint,
+    epoch: jint,
+    capabilities: jint,
+) -> jbyteArray {
     catch_panic!(env, {
-        let contact_id_str = match jstring_to_string(&mut env, contact_id) {
-            Ok(s) => s,
-            Err(_) => return
+        let encrypted_vec = match jbytearray_to_vec(&mut env, encrypted_data) {
+            Ok(v) => v,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
         };
 
-        reset_ack_state(&contact_id_str);
-    }, ())
-}
+        let chain_key_vec = match jbytearray_to_vec(&mut env, chain_key) {
+            Ok(v) => v,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
 
-// ==================== END PROTOCOL SECURITY FIXES ====================
+        if chain_key_vec.len() != 32 {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", "Chain key must be 32 bytes");
+            return std::ptr::null_mut();
+        }
 
-// ==================== DELIVERY ACK (CONFIRMATION) ====================
+        let binding = match jni_message_binding(&mut env, sender_identity, recipient_identity, msg_type, epoch, capabilities) {
+            Ok(b) => b,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
 
-/// Send a delivery ACK (confirmation) to recipient
-/// ack_type: "PING_ACK" or "MESSAGE_ACK"
-/// item_id: ping_id or message_id being acknowledged
+        let mut chain_key_array = [0u8; 32];
+        chain_key_array.copy_from_slice(&chain_key_vec);
+
+        match decrypt_message_with_evolution_v2(&encrypted_vec, &mut chain_key_array, expected_sequence as u64, &binding) {
+            Ok(result) => {
+                match String::from_utf8(result.plaintext.clone()) {
+                    Ok(plaintext_str) => {
+                        // Build result: [evolved_key:32][plaintext_utf8]
+                        let plaintext_bytes = plaintext_str.as_bytes();
+                        let mut output = Vec::with_capacity(32 + plaintext_bytes.len());
+                        output.extend_from_slice(result.evolved_chain_key.expose_secret());
+                        output.extend_from_slice(plaintext_bytes);
+
+                        match vec_to_jbytearray(&mut env, &output) {
+                            Ok(arr) => arr.into_raw(),
+                            Err(e) => {
+                                let _ = env.throw_new("java/lang/RuntimeException", e);
+                                std::ptr::null_mut()
+                            }
+                        }
+                    },
+                    Err(e) => {
+                        let _ = env.throw_new("java/lang/RuntimeException", format!("Invalid UTF-8: {}", e));
+                        std::ptr::null_mut()
+                    }
+                }
+            },
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Decryption failed: {}", e));
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Decrypt a chain message that may arrive early or late, using the contact's skipped-key store
+/// Early messages park the keys for the gap; late ones decrypt with their parked key in O(1)
+/// skipped_key_store: bytes from a previous call (null or empty for a new store)
+/// Return format: [evolved_key:32][next_sequence:8 BE][store_len:4 BE][store][plaintext_utf8],
+/// or null if decryption fails (chain key and store are then unchanged)
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_sendDeliveryAck(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptMessageWithSkippedKeysJNI(
     mut env: JNIEnv,
     _class: JClass,
-    item_id: JString,
-    ack_type: JString,
-    recipient_ed25519_pubkey: JByteArray,
-    recipient_x25519_pubkey: JByteArray,
-    recipient_onion: JString,
-) -> jboolean {
+    encrypted_data: JByteArray,
+    chain_key: JByteArray,
+    expected_sequence: jlong,
+    skipped_key_store: JByteArray,
+    sender_identity: JByteArray,
+    recipient_identity: JByteArray,
+    msg_type: jint,
+    epoch: jint,
+    capabilities: jint,
+) -> jbyteArray {
     catch_panic!(env, {
-        // Convert inputs
-        let item_id_str = match jstring_to_string(&mut env, item_id) {
-            Ok(s) => s,
+        let encrypted_vec = match jbytearray_to_vec(&mut env, encrypted_data) {
+            Ok(v) => v,
             Err(e) => {
-                log::error!("Failed to convert item_id: {}", e);
-                return 0;
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
             }
         };
 
-        let ack_type_str = match jstring_to_string(&mut env, ack_type) {
-            Ok(s) => s,
+        let chain_key_vec = Zeroizing::new(match jbytearray_to_vec(&mut env, chain_key) {
+            Ok(v) => v,
             Err(e) => {
-                log::error!("Failed to convert ack_type: {}", e);
-                return 0;
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        });
+        let mut chain_key_array: Zeroizing<[u8; 32]> = match chain_key_vec.as_slice().try_into() {
+            Ok(key) => Zeroizing::new(key),
+            Err(_) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "Chain key must be 32 bytes");
+                return std::ptr::null_mut();
             }
         };
 
-        let recipient_ed25519_bytes = match jbytearray_to_vec(&mut env, recipient_ed25519_pubkey) {
-            Ok(v) => v,
-            Err(e) => {
-                log::error!("Failed to convert recipient Ed25519 pubkey: {}", e);
-                return 0;
+        let store_vec = if skipped_key_store.is_null() {
+            Zeroizing::new(Vec::new())
+        } else {
+            match jbytearray_to_vec(&mut env, skipped_key_store) {
+                Ok(v) => Zeroizing::new(v),
+                Err(e) => {
+                    let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                    return std::ptr::null_mut();
+                }
             }
         };
-
-        let recipient_x25519_bytes = match jbytearray_to_vec(&mut env, recipient_x25519_pubkey) {
-            Ok(v) => v,
-            Err(e) => {
-                log::error!("Failed to convert recipient X25519 pubkey: {}", e);
-                return 0;
+        let mut store = if store_vec.is_empty() {
+            SkippedKeyStore::default()
+        } else {
+            match bincode::deserialize::<SkippedKeyStore>(&store_vec) {
+                Ok(store) => store,
+                Err(e) => {
+                    let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid skipped-key store: {}", e));
+                    return std::ptr::null_mut();
+                }
             }
         };
 
-        let recipient_onion_str = match jstring_to_string(&mut env, recipient_onion) {
-            Ok(s) => s,
+        let binding = match jni_message_binding(&mut env, sender_identity, recipient_identity, msg_type, epoch, capabilities) {
+            Ok(b) => b,
             Err(e) => {
-                log::error!("Failed to convert recipient onion: {}", e);
-                return 0;
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
             }
         };
 
-        log::info!("╔════════════════════════════════════════");
-        log::info!("║ 📤 SENDING DELIVERY ACK");
-        log::info!("║ Item ID: {}", item_id_str);
-        log::info!("║ ACK Type: {}", ack_type_str);
-        log::info!("║ Recipient: {}", recipient_onion_str);
-        log::info!("╚════════════════════════════════════════");
-
-        // Get KeyManager for our keys
-        let context = match env.call_static_method(
-            "android/app/ActivityThread",
-            "currentApplication",
-            "()Landroid/app/Application;",
-            &[],
+        let result = match decrypt_message_with_skipped_keys(
+            &encrypted_vec,
+            &mut chain_key_array,
+            expected_sequence as u64,
+            &mut store,
+            Some(&binding),
         ) {
-            Ok(ctx) => ctx.l().unwrap(),
+            Ok(result) => result,
             Err(e) => {
-                log::error!("Failed to get context: {}", e);
-                return 0;
+                log::warn!("Windowed decryption failed: {}", e);
+                return std::ptr::null_mut();
             }
         };
 
-        let key_manager = match crate::ffi::keystore::get_key_manager(&mut env, &context) {
-            Ok(km) => km,
+        let store_bytes = match bincode::serialize(&store) {
+            Ok(bytes) => Zeroizing::new(bytes),
             Err(e) => {
-                log::error!("Failed to get KeyManager: {}", e);
-                return 0;
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to serialize skipped-key store: {}", e));
+                return std::ptr::null_mut();
             }
         };
 
-        // Get our signing private key (Ed25519)
-        let our_signing_private = match crate::ffi::keystore::get_signing_private_key(&mut env, &key_manager) {
-            Ok(k) => k,
+        if std::str::from_utf8(&result.plaintext).is_err() {
+            let _ = env.throw_new("java/lang/RuntimeException", "Invalid UTF-8 plaintext");
+            return std::ptr::null_mut();
+        }
+
+        // Build result: [evolved_key:32][next_sequence:8][store_len:4][store][plaintext_utf8]
+        let mut output = Zeroizing::new(Vec::with_capacity(44 + store_bytes.len() + result.plaintext.len()));
+        output.extend_from_slice(result.evolved_chain_key.expose_secret());
+        output.extend_from_slice(&result.next_expected_sequence.to_be_bytes());
+        output.extend_from_slice(&(store_bytes.len() as u32).to_be_bytes());
+        output.extend_from_slice(&store_bytes);
+        output.extend_from_slice(&result.plaintext);
+
+        match vec_to_jbytearray(&mut env, &output) {
+            Ok(arr) => arr.into_raw(),
             Err(e) => {
-                log::error!("Failed to get signing key: {}", e);
-                return 0;
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                std::ptr::null_mut()
             }
-        };
+        }
+    }, std::ptr::null_mut())
+}
 
-        // Create Ed25519 signing keypair
-        let sender_keypair = ed25519_dalek::SigningKey::from_bytes(&our_signing_private.as_slice().try_into().unwrap());
+/// Open or close the migration window for legacy v1 (unauthenticated header) messages
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_setLegacyV1MessagesAccepted(
+    mut env: JNIEnv,
+    _class: JClass,
+    accepted: jboolean,
+) {
+    catch_panic!(env, {
+        set_legacy_v1_accepted(accepted != 0);
+    }, ())
+}
 
-        // Create DeliveryAck token (signed)
-        let ack_token = match crate::network::pingpong::DeliveryAck::new(
-            &item_id_str,
-            &ack_type_str,
-            &sender_keypair,
-        ) {
-            Ok(token) => token,
-            Err(e) => {
-                log::error!("Failed to create ACK: {}", e);
-                return 0;
-            }
-        };
+/// Open or close the migration window for the legacy (pre-canonical) token signing encoding
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_setLegacySigningAccepted(
+    mut env: JNIEnv,
+    _class: JClass,
+    accepted: jboolean,
+) {
+    catch_panic!(env, {
+        set_legacy_signing_accepted(accepted != 0);
+    }, ())
+}
 
-        // Serialize ACK token
-        let ack_bytes = match ack_token.to_bytes() {
-            Ok(bytes) => bytes,
-            Err(e) => {
-                log::error!("Failed to serialize ACK: {}", e);
-                return 0;
-            }
-        };
+/// Capability flags this build advertises in its contact card
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_getLocalCapabilities(
+    mut env: JNIEnv,
+    _class: JClass,
+) -> jint {
+    catch_panic!(env, {
+        LOCAL_CAPABILITIES as jint
+    }, 0)
+}
 
-        // Get our X25519 encryption keys
-        let our_x25519_public = match crate::ffi::keystore::get_encryption_public_key(&mut env, &key_manager) {
-            Ok(k) => k,
-            Err(e) => {
-                log::error!("Failed to get our X25519 pubkey: {}", e);
-                return 0;
-            }
-        };
+/// Capabilities shared with a contact, from the flags in their contact card
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_negotiateCapabilities(
+    mut env: JNIEnv,
+    _class: JClass,
+    peer_capabilities: jint,
+) -> jint {
+    catch_panic!(env, {
+        negotiate_capabilities(LOCAL_CAPABILITIES, peer_capabilities as u8) as jint
+    }, 0)
+}
 
-        let our_x25519_private = match crate::ffi::keystore::get_encryption_private_key(&mut env, &key_manager) {
-            Ok(k) => k,
-            Err(e) => {
-                log::error!("Failed to get encryption key: {}", e);
-                return 0;
+/// Encrypt bytes (e.g. an attachment) with key-committing XChaCha20-Poly1305
+/// Returns [version][nonce][commitment][ciphertext]
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_encryptBytesCommitting(
+    mut env: JNIEnv,
+    _class: JClass,
+    data: JByteArray,
+    key: JByteArray,
+) -> jbyteArray {
+    catch_panic!(env, {
+        let (data_vec, mut key_vec) = match (jbytearray_to_vec(&mut env, data), jbytearray_to_vec(&mut env, key)) {
+            (Ok(d), Ok(k)) => (d, k),
+            _ => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid data or key");
+                return std::ptr::null_mut();
             }
         };
 
-        // Derive shared secret using X25519 ECDH
-        let shared_secret = match crate::crypto::key_exchange::derive_shared_secret(
-            &our_x25519_private,
-            &recipient_x25519_bytes,
-        ) {
-            Ok(secret) => secret,
-            Err(e) => {
-                log::error!("ECDH failed: {}", e);
-                return 0;
-            }
-        };
+        let result = encrypt_message_committing(&data_vec, &key_vec);
+        key_vec.zeroize();
 
-        // Encrypt ACK with shared secret
-        let encrypted_ack = match crate::crypto::encryption::encrypt_message(&ack_bytes, &shared_secret) {
-            Ok(enc) => enc,
+        match result {
+            Ok(encrypted) => match vec_to_jbytearray(&mut env, &encrypted) {
+                Ok(array) => array.into_raw(),
+                Err(e) => {
+                    let _ = env.throw_new("java/lang/RuntimeException", e);
+                    std::ptr::null_mut()
+                }
+            },
             Err(e) => {
-                log::error!("Encryption failed: {}", e);
-                return 0;
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Encryption failed: {}", e));
+                std::ptr::null_mut()
             }
-        };
-
-        // Wire format: [Type Byte 0x06][Our X25519 Public Key - 32 bytes][Encrypted ACK]
-        let mut wire_message = Vec::new();
-        wire_message.push(crate::network::tor::MSG_TYPE_DELIVERY_CONFIRMATION);
+        }
+    }, std::ptr::null_mut())
+}
 
-        let sender_x25519_pubkey: [u8; 32] = match our_x25519_public.as_slice().try_into() {
-            Ok(arr) => arr,
-            Err(_) => {
-                log::error!("Invalid sender X25519 pubkey size");
-                return 0;
+/// Decrypt bytes produced by encryptBytesCommitting
+/// Returns null if the key does not match the commitment or the ciphertext was modified
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptBytesCommitting(
+    mut env: JNIEnv,
+    _class: JClass,
+    encrypted: JByteArray,
+    key: JByteArray,
+) -> jbyteArray {
+    catch_panic!(env, {
+        let (encrypted_vec, mut key_vec) = match (jbytearray_to_vec(&mut env, encrypted), jbytearray_to_vec(&mut env, key)) {
+            (Ok(d), Ok(k)) => (d, k),
+            _ => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid data or key");
+                return std::ptr::null_mut();
             }
         };
-        wire_message.extend_from_slice(&sender_x25519_pubkey);
-        wire_message.extend_from_slice(&encrypted_ack);
-
-        // Send encrypted ACK via Tor to recipient's port 9153 (ACK listener)
-        let tor_manager = get_tor_manager();
-        let runtime = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
-
-        const ACK_PORT: u16 = 9153;
 
-        let result: Result<(), Box<dyn std::error::Error>> = runtime.block_on(async {
-            let manager = tor_manager.lock().unwrap();
-            let mut conn = manager.connect(&recipient_onion_str, ACK_PORT).await?;
-            manager.send(&mut conn, &wire_message).await?;
-            Ok(())
-        });
+        let result = decrypt_message_committing(&encrypted_vec, &key_vec);
+        key_vec.zeroize();
 
         match result {
-            Ok(_) => {
-                log::info!("✓ ACK sent successfully: {} for {}", ack_type_str, item_id_str);
-                1 // success
+            Ok(mut plaintext) => {
+                let array = vec_to_jbytearray(&mut env, &plaintext);
+                plaintext.zeroize();
+                match array {
+                    Ok(array) => array.into_raw(),
+                    Err(e) => {
+                        let _ = env.throw_new("java/lang/RuntimeException", e);
+                        std::ptr::null_mut()
+                    }
+                }
             }
             Err(e) => {
-                log::error!("Failed to send ACK: {}", e);
-                0 // failure
+                log::warn!("Committing decryption failed: {}", e);
+                std::ptr::null_mut()
             }
         }
-    }, 0)
+    }, std::ptr::null_mut())
 }
 
-/// Start ACK listener on port 9153
+/// Stream-encrypt a file (e.g. a large attachment) chunk by chunk
+/// Returns true on success; the output is written as [header][chunks]
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_startAckListener(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_encryptFileStream(
     mut env: JNIEnv,
     _class: JClass,
-    port: jint,
+    input_path: JString,
+    output_path: JString,
+    key: JByteArray,
 ) -> jboolean {
     catch_panic!(env, {
-        log::info!("Starting ACK listener on port {}...", port);
-
-        let tor_manager = get_tor_manager();
+        let (input_str, output_str, mut key_vec) = match (
+            jstring_to_string(&mut env, input_path),
+            jstring_to_string(&mut env, output_path),
+            jbytearray_to_vec(&mut env, key),
+        ) {
+            (Ok(i), Ok(o), Ok(k)) => (i, o, k),
+            _ => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid paths or key");
+                return 0;
+            }
+        };
 
-        // Run async listener start using the global runtime
-        let result = GLOBAL_RUNTIME.block_on(async {
-            let mut manager = tor_manager.lock().unwrap();
-            manager.start_listener(Some(port as u16)).await
-        });
+        let result = (|| -> Result<u64, String> {
+            let input = std::fs::File::open(&input_str).map_err(|e| e.to_string())?;
+            let mut encryptor = crate::crypto::stream::StreamEncryptor::new(
+                std::io::BufReader::new(input),
+                &key_vec,
+                crate::crypto::stream::DEFAULT_CHUNK_SIZE,
+            ).map_err(|e| e.to_string())?;
+            let mut output = std::fs::File::create(&output_str).map_err(|e| e.to_string())?;
+            let written = std::io::copy(&mut encryptor, &mut output).map_err(|e| e.to_string())?;
+            output.sync_all().map_err(|e| e.to_string())?;
+            Ok(written)
+        })();
+        key_vec.zeroize();
 
         match result {
-            Ok(mut receiver) => {
-                // Create shared channel for ACK messages
-                // This channel is accessible from both port 9153 (normal path) and port 8080 (error recovery)
-                let (tx, rx) = mpsc::unbounded_channel::<(u64, Vec<u8>)>();
-
-                // Store receiver globally for polling
-                let _ = GLOBAL_ACK_RECEIVER.set(Arc::new(Mutex::new(rx)));
-
-                // Store sender globally so port 8080 can route misrouted ACKs
-                let _ = crate::network::tor::ACK_TX.set(Arc::new(std::sync::Mutex::new(tx.clone())));
-
-                // Spawn task to receive from TorManager listener and forward to shared ACK channel
-                GLOBAL_RUNTIME.spawn(async move {
-                    while let Some((connection_id, ack_bytes)) = receiver.recv().await {
-                        log::info!("Received ACK via port 9153 listener: {} bytes", ack_bytes.len());
-
-                        // Forward to shared ACK channel
-                        if let Err(e) = tx.send((connection_id, ack_bytes)) {
-                            log::error!("Failed to send ACK to shared channel: {}", e);
-                            break;
-                        }
-                    }
-                    log::warn!("ACK listener receiver closed");
-                });
-
-                log::info!("ACK listener started successfully on port {} with shared channel", port);
-                1 // success
+            Ok(written) => {
+                log::info!("Stream-encrypted file ({} bytes)", written);
+                1
             }
             Err(e) => {
-                log::error!("Failed to start ACK listener: {}", e);
-                0 // failure
+                let _ = std::fs::remove_file(&output_str);
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Stream encryption failed: {}", e));
+                0
             }
         }
     }, 0)
 }
 
-/// Poll for an incoming ACK (non-blocking)
+/// Decrypt a file produced by encryptFileStream
+/// Returns false (and removes the partial output) if any chunk is forged, reordered or missing
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_pollIncomingAck(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptFileStream(
     mut env: JNIEnv,
     _class: JClass,
-) -> jbyteArray {
+    input_path: JString,
+    output_path: JString,
+    key: JByteArray,
+) -> jboolean {
     catch_panic!(env, {
-        if let Some(receiver) = GLOBAL_ACK_RECEIVER.get() {
-            let mut rx = receiver.lock().unwrap();
+        let (input_str, output_str, mut key_vec) = match (
+            jstring_to_string(&mut env, input_path),
+            jstring_to_string(&mut env, output_path),
+            jbytearray_to_vec(&mut env, key),
+        ) {
+            (Ok(i), Ok(o), Ok(k)) => (i, o, k),
+            _ => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid paths or key");
+                return 0;
+            }
+        };
 
-            // Try to receive without blocking
-            match rx.try_recv() {
-                Ok((_conn_id, ack_bytes)) => {
-                    log::info!("Polled ACK: {} bytes", ack_bytes.len());
-                    match vec_to_jbytearray(&mut env, &ack_bytes) {
-                        Ok(array) => array.into_raw(),
-                        Err(_) => std::ptr::null_mut(),
-                    }
-                }
-                Err(_) => {
-                    // No ACK available
-                    std::ptr::null_mut()
-                }
+        let result = (|| -> Result<u64, String> {
+            let input = std::fs::File::open(&input_str).map_err(|e| e.to_string())?;
+            let mut decryptor = crate::crypto::stream::StreamDecryptor::new(
+                std::io::BufReader::new(input),
+                &key_vec,
+            ).map_err(|e| e.to_string())?;
+            let mut output = std::fs::File::create(&output_str).map_err(|e| e.to_string())?;
+            let written = std::io::copy(&mut decryptor, &mut output).map_err(|e| e.to_string())?;
+            output.sync_all().map_err(|e| e.to_string())?;
+            Ok(written)
+        })();
+        key_vec.zeroize();
+
+        match result {
+            Ok(written) => {
+                log::info!("Stream-decrypted file ({} bytes)", written);
+                1
+            }
+            Err(e) => {
+                // Plaintext written before the failure is unauthenticated
+                let _ = std::fs::remove_file(&output_str);
+                log::warn!("Stream decryption failed: {}", e);
+                0
             }
-        } else {
-            // Listener not started
-            std::ptr::null_mut()
         }
-    }, std::ptr::null_mut())
+    }, 0)
 }
 
-/// Decrypt incoming ACK from listener and store in GLOBAL_ACK_SESSIONS
-/// Wire format: [Sender X25519 Public Key - 32 bytes][Encrypted ACK]
+/// FIX #6: Store pending ratchet advancement (after encryption but before PING_ACK)
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptAndStoreAckFromListener(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_storePendingRatchetAdvancement(
     mut env: JNIEnv,
     _class: JClass,
-    ack_wire: JByteArray,
-) -> jstring {
+    contact_id: JString,
+    message_id: JString,
+    next_chain_key: JByteArray,
+    next_sequence: jlong,
+) -> jboolean {
     catch_panic!(env, {
-        let wire_bytes = match jbytearray_to_vec(&mut env, ack_wire) {
+        let contact_id_str = match jstring_to_string(&mut env, contact_id) {
+            Ok(s) => s,
+            Err(_) => return 0
+        };
+
+        let message_id_str = match jstring_to_string(&mut env, message_id) {
+            Ok(s) => s,
+            Err(_) => return 0
+        };
+
+        let chain_key_vec = match jbytearray_to_vec(&mut env, next_chain_key) {
             Ok(v) => v,
-            Err(e) => {
-                log::error!("Failed to convert ACK wire bytes: {}", e);
-                return std::ptr::null_mut();
-            }
+            Err(_) => return 0
         };
 
-        if wire_bytes.len() < 32 {
-            log::error!("ACK wire too short: {} bytes", wire_bytes.len());
-            return std::ptr::null_mut();
+        if chain_key_vec.len() != 32 {
+            return 0;
         }
 
-        // Extract sender's X25519 public key (first 32 bytes)
-        let sender_x25519_pubkey: [u8; 32] = wire_bytes[0..32].try_into().unwrap();
-        let encrypted_ack = &wire_bytes[32..];
-
-        log::info!("Decrypting ACK from sender X25519: {}", hex::encode(&sender_x25519_pubkey));
+        let mut chain_key_array = [0u8; 32];
+        chain_key_array.copy_from_slice(&chain_key_vec);
 
-        // Get our X25519 private key from KeyManager
-        let context = match env.call_static_method(
-            "android/app/ActivityThread",
-            "currentApplication",
-            "()Landroid/app/Application;",
-            &[],
-        ) {
-            Ok(ctx) => ctx.l().unwrap(),
-            Err(e) => {
-                log::error!("Failed to get context: {}", e);
-                return std::ptr::null_mut();
-            }
-        };
+        match store_pending_ratchet_advancement(&contact_id_str, &message_id_str, chain_key_array, next_sequence as u64) {
+            Ok(_) => 1,
+            Err(_) => 0
+        }
+    }, 0)
+}
 
-        let key_manager = match crate::ffi::keystore::get_key_manager(&mut env, &context) {
-            Ok(km) => km,
+/// FIX #6: Commit ratchet advancement after PING_ACK received (Phase 2)
+/// Returns JSON: {"nextChainKey": "base64", "nextSequence": 123} or null if no pending
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_commitRatchetAdvancement(
+    mut env: JNIEnv,
+    _class: JClass,
+    contact_id: JString,
+) -> jstring {
+    catch_panic!(env, {
+        let contact_id_str = match jstring_to_string(&mut env, contact_id) {
+            Ok(s) => s,
             Err(e) => {
-                log::error!("Failed to get KeyManager: {}", e);
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
                 return std::ptr::null_mut();
             }
         };
 
-        let our_x25519_private = match crate::ffi::keystore::get_encryption_private_key(&mut env, &key_manager) {
-            Ok(k) => k,
-            Err(e) => {
-                log::error!("Failed to get encryption private key: {}", e);
-                return std::ptr::null_mut();
-            }
-        };
+        match commit_ratchet_advancement(&contact_id_str) {
+            Ok(Some((next_key, next_seq))) => {
+                let json = serde_json::json!({
+                    "nextChainKey": base64::encode(&next_key),
+                    "nextSequence": next_seq
+                });
 
-        // Derive shared secret
-        let shared_secret = match crate::crypto::key_exchange::derive_shared_secret(
-            &our_x25519_private,
-            &sender_x25519_pubkey.to_vec(),
-        ) {
-            Ok(secret) => secret,
-            Err(e) => {
-                log::error!("ECDH failed: {}", e);
-                return std::ptr::null_mut();
+                match env.new_string(json.to_string()) {
+                    Ok(s) => s.into_raw(),
+                    Err(_) => std::ptr::null_mut()
+                }
             }
-        };
-
-        // Decrypt ACK
-        let ack_bytes = match crate::crypto::encryption::decrypt_message(encrypted_ack, &shared_secret) {
-            Ok(decrypted) => decrypted,
-            Err(e) => {
-                log::error!("Decryption failed: {}", e);
-                return std::ptr::null_mut();
+            Ok(None) => {
+                std::ptr::null_mut()
             }
-        };
-
-        // Deserialize ACK
-        let ack_token = match crate::network::pingpong::DeliveryAck::from_bytes(&ack_bytes) {
-            Ok(token) => token,
             Err(e) => {
-                log::error!("Failed to deserialize ACK: {}", e);
-                return std::ptr::null_mut();
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Commit failed: {}", e));
+                std::ptr::null_mut()
             }
-        };
-
-        log::info!("╔════════════════════════════════════════");
-        log::info!("║ 📥 RECEIVED DELIVERY ACK");
-        log::info!("║ Item ID: {}", ack_token.item_id);
-        log::info!("║ ACK Type: {}", ack_token.ack_type);
-        log::info!("╚════════════════════════════════════════");
-
-        let item_id = ack_token.item_id.clone();
-        let ack_type = ack_token.ack_type.clone();
-
-        // Store in GLOBAL_ACK_SESSIONS
-        crate::network::pingpong::store_ack_session(&item_id, ack_token);
-        log::info!("Stored ACK in GLOBAL_ACK_SESSIONS");
-
-        // Return JSON with item_id and ack_type
-        let ack_json = format!(r#"{{"item_id":"{}","ack_type":"{}"}}"#, item_id, ack_type);
-        match string_to_jstring(&mut env, &ack_json) {
-            Ok(s) => s.into_raw(),
-            Err(_) => std::ptr::null_mut(),
         }
     }, std::ptr::null_mut())
 }
 
-// ==================== UTILITY ====================
-
+/// FIX #6: Rollback pending ratchet advancement (if send permanently fails)
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_getVersion(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_rollbackRatchetAdvancement(
     mut env: JNIEnv,
     _class: JClass,
-) -> jstring {
+    contact_id: JString,
+) -> jboolean {
     catch_panic!(env, {
-        match string_to_jstring(&mut env, crate::VERSION) {
-            Ok(s) => s.into_raw(),
-            Err(_) => std::ptr::null_mut(),
+        let contact_id_str = match jstring_to_string(&mut env, contact_id) {
+            Ok(s) => s,
+            Err(_) => return 0
+        };
+
+        match rollback_ratchet_advancement(&contact_id_str) {
+            Ok(_) => 1,
+            Err(_) => 0
         }
-    }, std::ptr::null_mut())
+    }, 0)
 }
 
-// ==================== SESSION CLEANUP ====================
-
-/// Remove Ping session after Pong is sent
-/// Call this immediately after successfully sending Pong to prevent memory leak
+/// Commit pending ratchet advancements up to and including the acknowledged message
+/// Returns JSON: {"nextChainKey": "base64", "nextSequence": 123} or null if the message is not pending
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_removePingSession(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_commitRatchetAdvancementForMessage(
     mut env: JNIEnv,
     _class: JClass,
-    ping_id: JString,
-) {
+    contact_id: JString,
+    message_id: JString,
+) -> jstring {
     catch_panic!(env, {
-        let ping_id_str = match jstring_to_string(&mut env, ping_id) {
+        let contact_id_str = match jstring_to_string(&mut env, contact_id) {
             Ok(s) => s,
             Err(e) => {
-                log::error!("Failed to convert ping_id: {}", e);
-                return;
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
             }
         };
 
-        log::debug!("Removing Ping session: {}", ping_id_str);
-        crate::network::remove_ping_session(&ping_id_str);
-    }, ())
+        let message_id_str = match jstring_to_string(&mut env, message_id) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        match commit_ratchet_advancement_through(&contact_id_str, &message_id_str) {
+            Ok(Some((next_key, next_seq))) => {
+                let json = serde_json::json!({
+                    "nextChainKey": BASE64.encode(next_key),
+                    "nextSequence": next_seq
+                });
+
+                match env.new_string(json.to_string()) {
+                    Ok(s) => s.into_raw(),
+                    Err(_) => std::ptr::null_mut()
+                }
+            }
+            Ok(None) => std::ptr::null_mut(),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Commit failed: {}", e));
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
 }
 
-/// Remove Pong session after message blob is sent
-/// Call this immediately after successfully sending message to prevent memory leak
+/// Roll back a failed message and every pending advancement queued after it
+/// Returns the number of advancements discarded, or -1 on error
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_removePongSession(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_rollbackRatchetAdvancementForMessage(
     mut env: JNIEnv,
     _class: JClass,
-    ping_id: JString,
-) {
+    contact_id: JString,
+    message_id: JString,
+) -> jint {
     catch_panic!(env, {
-        let ping_id_str = match jstring_to_string(&mut env, ping_id) {
+        let contact_id_str = match jstring_to_string(&mut env, contact_id) {
             Ok(s) => s,
-            Err(e) => {
-                log::error!("Failed to convert ping_id: {}", e);
-                return;
-            }
+            Err(_) => return -1
         };
 
-        log::debug!("Removing Pong session: {}", ping_id_str);
-        crate::network::remove_pong_session(&ping_id_str);
-    }, ())
+        let message_id_str = match jstring_to_string(&mut env, message_id) {
+            Ok(s) => s,
+            Err(_) => return -1
+        };
+
+        match rollback_ratchet_advancement_from(&contact_id_str, &message_id_str) {
+            Ok(discarded) => discarded as jint,
+            Err(_) => -1
+        }
+    }, -1)
 }
 
-/// Remove ACK session after processing
-/// Call this immediately after processing ACK to prevent memory leak
+/// Finalize committed ratchet advancements once the app has persisted the new chain key
+/// Only now is the commit written to the journal, so a crash before the DB write replays it
+/// Returns the number of advancements finalized, or -1 on error
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_removeAckSession(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_ackRatchetPersisted(
     mut env: JNIEnv,
     _class: JClass,
-    item_id: JString,
-) {
+    contact_id: JString,
+    message_id: JString,
+) -> jint {
     catch_panic!(env, {
-        let item_id_str = match jstring_to_string(&mut env, item_id) {
+        let contact_id_str = match jstring_to_string(&mut env, contact_id) {
             Ok(s) => s,
-            Err(e) => {
-                log::error!("Failed to convert item_id: {}", e);
-                return;
-            }
+            Err(_) => return -1
         };
 
-        log::debug!("Removing ACK session: {}", item_id_str);
-        crate::network::remove_ack_session(&item_id_str);
-    }, ())
-}
+        let message_id_str = match jstring_to_string(&mut env, message_id) {
+            Ok(s) => s,
+            Err(_) => return -1
+        };
 
-/// Clean up expired Ping/Pong/ACK sessions (older than 5 minutes)
-/// Call this periodically as a safety net for orphaned entries from crashes
-#[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_cleanupExpiredSessions(
-    _env: JNIEnv,
-    _class: JClass,
-) {
-    log::debug!("Cleaning up expired sessions...");
-    crate::network::cleanup_expired_pings();
-    crate::network::cleanup_expired_pongs();
-    crate::network::cleanup_expired_acks();
+        match ack_ratchet_persisted(&contact_id_str, &message_id_str) {
+            Ok(finalized) => finalized as jint,
+            Err(_) => -1
+        }
+    }, -1)
 }
 
-// ==================== NLx402 PAYMENT PROTOCOL ====================
-
-/// Create a payment quote for NLx402 protocol
-/// Returns JSON string containing the quote
+/// Open the ratchet write-ahead journal and restore uncommitted advancements
+/// The journal key is derived from the KeyManager X25519 secret, so it never crosses JNI
+/// Returns JSON array of recovered advancements: [{"contactId", "messageId", "nextSequence"}]
+/// Entries the app already persisted must be resolved with ackRatchetPersisted
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_createPaymentQuote(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_initRatchetJournal(
     mut env: JNIEnv,
     _class: JClass,
-    recipient: JString,
-    amount: jlong,
-    token: JString,
-    description: JString,
-    sender_handle: JString,
-    recipient_handle: JString,
-    expiry_secs: jlong,
+    path: JString,
 ) -> jstring {
     catch_panic!(env, {
-        let recipient_str = match jstring_to_string(&mut env, recipient) {
+        let path_str = match jstring_to_string(&mut env, path) {
             Ok(s) => s,
             Err(e) => {
                 let _ = env.throw_new("java/lang/IllegalArgumentException", e);
@@ -6084,1055 +6722,3211 @@ pub extern "C" fn Java_com_securelegion_crypto_RustBridge_createPaymentQuote(
             }
         };
 
-        let token_str = match jstring_to_string(&mut env, token) {
-            Ok(s) => s,
+        let context = match env.call_static_method(
+            "android/app/ActivityThread",
+            "currentApplication",
+            "()Landroid/app/Application;",
+            &[],
+        ) {
+            Ok(ctx) => ctx.l().unwrap(),
             Err(e) => {
-                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get context: {}", e));
                 return std::ptr::null_mut();
             }
         };
-
-        // Handle optional parameters (empty string = None)
-        let description_opt = match jstring_to_string(&mut env, description) {
-            Ok(s) if !s.is_empty() => Some(s),
-            _ => None,
+        let key_manager = match crate::ffi::keystore::get_key_manager(&mut env, &context) {
+            Ok(km) => km,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get KeyManager: {}", e));
+                return std::ptr::null_mut();
+            }
         };
-
-        let sender_handle_opt = match jstring_to_string(&mut env, sender_handle) {
-            Ok(s) if !s.is_empty() => Some(s),
-            _ => None,
-        };
-
-        let recipient_handle_opt = match jstring_to_string(&mut env, recipient_handle) {
-            Ok(s) if !s.is_empty() => Some(s),
-            _ => None,
-        };
-
-        // Create the quote with custom expiry
-        let quote = match crate::nlx402::create_quote_with_expiry(
-            &recipient_str,
-            amount as u64,
-            &token_str,
-            description_opt.as_deref(),
-            sender_handle_opt.as_deref(),
-            recipient_handle_opt.as_deref(),
-            expiry_secs as u64,
-        ) {
-            Ok(q) => q,
+        let mut x25519_private = match crate::ffi::keystore::get_encryption_private_key(&mut env, &key_manager) {
+            Ok(k) => k,
             Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to create quote: {}", e));
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get X25519 private key: {}", e));
                 return std::ptr::null_mut();
             }
         };
 
-        // Serialize to JSON
-        let json = match quote.to_json() {
-            Ok(j) => j,
+        let mut journal_key = [0u8; 32];
+        let hkdf = hkdf::Hkdf::<sha2::Sha256>::new(None, &x25519_private);
+        x25519_private.zeroize();
+        if hkdf.expand(b"SecureLegion-RatchetJournal-Key-v1", &mut journal_key).is_err() {
+            let _ = env.throw_new("java/lang/RuntimeException", "Failed to derive journal key");
+            return std::ptr::null_mut();
+        }
+
+        let recovered = init_ratchet_journal(std::path::Path::new(&path_str), &journal_key);
+        journal_key.zeroize();
+        let recovered = match recovered {
+            Ok(r) => r,
             Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to serialize quote: {}", e));
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to open ratchet journal: {}", e));
                 return std::ptr::null_mut();
             }
         };
 
-        log::info!("Created payment quote: {} for {} {}", quote.quote_id, amount, token_str);
+        let json: Vec<serde_json::Value> = recovered
+            .iter()
+            .map(|a| serde_json::json!({
+                "contactId": a.contact_id,
+                "messageId": a.message_id,
+                "nextSequence": a.next_sequence,
+            }))
+            .collect();
 
-        match string_to_jstring(&mut env, &json) {
+        match env.new_string(serde_json::Value::Array(json).to_string()) {
             Ok(s) => s.into_raw(),
-            Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", e);
-                std::ptr::null_mut()
-            }
+            Err(_) => std::ptr::null_mut()
         }
     }, std::ptr::null_mut())
 }
 
-/// Get the memo string for a payment quote (for embedding in transaction)
+/// Open the encrypted PING replay log (call once at startup)
+/// The log key is derived from the KeyManager X25519 secret, so it never crosses JNI
+/// window_seconds <= 0 keeps the default acceptance window
+/// Returns the number of PINGs still remembered from previous runs, or -1 on failure
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_getQuoteMemo(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_initPingReplayGuard(
     mut env: JNIEnv,
     _class: JClass,
-    quote_json: JString,
-) -> jstring {
+    path: JString,
+    window_seconds: jlong,
+) -> jint {
     catch_panic!(env, {
-        let json_str = match jstring_to_string(&mut env, quote_json) {
+        let path_str = match jstring_to_string(&mut env, path) {
             Ok(s) => s,
             Err(e) => {
                 let _ = env.throw_new("java/lang/IllegalArgumentException", e);
-                return std::ptr::null_mut();
+                return -1;
             }
         };
 
-        let quote = match crate::nlx402::PaymentQuote::from_json(&json_str) {
-            Ok(q) => q,
+        let context = match env.call_static_method(
+            "android/app/ActivityThread",
+            "currentApplication",
+            "()Landroid/app/Application;",
+            &[],
+        ) {
+            Ok(ctx) => ctx.l().unwrap(),
             Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to parse quote: {}", e));
-                return std::ptr::null_mut();
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get context: {}", e));
+                return -1;
+            }
+        };
+        let key_manager = match crate::ffi::keystore::get_key_manager(&mut env, &context) {
+            Ok(km) => km,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get KeyManager: {}", e));
+                return -1;
+            }
+        };
+        let mut x25519_private = match crate::ffi::keystore::get_encryption_private_key(&mut env, &key_manager) {
+            Ok(k) => k,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get X25519 private key: {}", e));
+                return -1;
             }
         };
 
-        let memo = quote.to_memo();
+        let mut log_key = [0u8; 32];
+        let hkdf = hkdf::Hkdf::<sha2::Sha256>::new(None, &x25519_private);
+        x25519_private.zeroize();
+        if hkdf.expand(b"SecureLegion-PingReplayLog-Key-v1", &mut log_key).is_err() {
+            let _ = env.throw_new("java/lang/RuntimeException", "Failed to derive replay log key");
+            return -1;
+        }
 
-        match string_to_jstring(&mut env, &memo) {
-            Ok(s) => s.into_raw(),
+        let mut config = ReplayGuardConfig::default();
+        if window_seconds > 0 {
+            config.window_secs = window_seconds;
+        }
+
+        let remembered = init_replay_guard(std::path::Path::new(&path_str), &log_key, config);
+        log_key.zeroize();
+        match remembered {
+            Ok(count) => {
+                log::info!("PING replay guard ready ({} remembered, window {}s)", count, config.window_secs);
+                count as jint
+            }
             Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", e);
-                std::ptr::null_mut()
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to open replay log: {}", e));
+                -1
             }
         }
-    }, std::ptr::null_mut())
+    }, -1)
 }
 
-/// Verify a payment against a quote
-/// Returns true if payment is valid (excluding replay check - that's done in Kotlin)
+/// FIX #9: Check if PING is a replay attack
+/// Uses the sender and signed timestamp of a Ping already stored by decryptIncomingPing
+/// Returns true if PING should be processed (not a replay)
+/// Returns false if PING is a duplicate, outside the acceptance window, or unknown (should be dropped)
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_verifyPayment(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_checkPingReplay(
     mut env: JNIEnv,
     _class: JClass,
-    quote_json: JString,
-    tx_memo: JString,
-    tx_amount: jlong,
-    tx_recipient: JString,
-    tx_token: JString,
+    ping_id: JString,
+    ping_bytes: JByteArray,
 ) -> jboolean {
     catch_panic!(env, {
-        let json_str = match jstring_to_string(&mut env, quote_json) {
+        let ping_id_str = match jstring_to_string(&mut env, ping_id) {
             Ok(s) => s,
-            Err(e) => {
-                log::error!("Failed to get quote_json: {}", e);
-                return 0;
-            }
+            Err(_) => return 0
         };
 
-        let memo_str = match jstring_to_string(&mut env, tx_memo) {
-            Ok(s) => s,
-            Err(e) => {
-                log::error!("Failed to get tx_memo: {}", e);
-                return 0;
-            }
+        let ping_vec = match jbytearray_to_vec(&mut env, ping_bytes) {
+            Ok(v) => v,
+            Err(_) => return 0
         };
 
-        let recipient_str = match jstring_to_string(&mut env, tx_recipient) {
-            Ok(s) => s,
-            Err(e) => {
-                log::error!("Failed to get tx_recipient: {}", e);
+        let (pubkey, timestamp) = match STORED_PINGS.lock().unwrap().get(&ping_id_str) {
+            Some(token) => (token.sender_pubkey, token.timestamp),
+            None => {
+                log::warn!("Replay check for unknown Ping ID: {}", ping_id_str);
                 return 0;
             }
         };
 
-        let token_str = match jstring_to_string(&mut env, tx_token) {
+        if check_ping_replay(pubkey, compute_ping_hash(&ping_vec), timestamp) {
+            1  // Not a replay - process it
+        } else {
+            0  // Replay detected - drop it
+        }
+    }, 0)
+}
+
+/// FIX #7: Start tracking a message delivery (call when the PING is sent)
+/// @param messageId Message being delivered
+/// @param contactId Recipient
+/// @return true if tracking started, false if the message is already tracked
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_beginAckTracking(
+    mut env: JNIEnv,
+    _class: JClass,
+    message_id: JString,
+    contact_id: JString,
+) -> jboolean {
+    catch_panic!(env, {
+        let message_id_str = match jstring_to_string(&mut env, message_id) {
             Ok(s) => s,
-            Err(e) => {
-                log::error!("Failed to get tx_token: {}", e);
-                return 0;
-            }
+            Err(_) => return 0
         };
-
-        let quote = match crate::nlx402::PaymentQuote::from_json(&json_str) {
-            Ok(q) => q,
-            Err(e) => {
-                log::error!("Failed to parse quote: {}", e);
-                return 0;
-            }
+        let contact_id_str = match jstring_to_string(&mut env, contact_id) {
+            Ok(s) => s,
+            Err(_) => return 0
         };
 
-        // Use simple verification (replay check is done in Kotlin with local DB)
-        match crate::nlx402::verify_payment_simple(
-            &quote,
-            &memo_str,
-            tx_amount as u64,
-            &recipient_str,
-            &token_str,
-        ) {
-            Ok(_) => {
-                log::info!("Payment verified successfully for quote {}", quote.quote_id);
-                1
-            }
-            Err(e) => {
-                log::warn!("Payment verification failed: {}", e);
-                0
-            }
+        if begin_delivery(&message_id_str, &contact_id_str) {
+            1
+        } else {
+            0
         }
     }, 0)
 }
 
-/// Check if a quote has expired
+/// FIX #7: Validate ACK ordering according to protocol rules
+/// @param messageId Delivery the ACK refers to
+/// @param ackType 0=PING_ACK, 1=PONG_ACK, 2=MESSAGE_ACK, 3=TAP_ACK
+/// @return true if ACK is valid, false if it violates ordering
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_isQuoteExpired(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_validateAckOrdering(
     mut env: JNIEnv,
     _class: JClass,
-    quote_json: JString,
+    message_id: JString,
+    ack_type: jint,
 ) -> jboolean {
     catch_panic!(env, {
-        let json_str = match jstring_to_string(&mut env, quote_json) {
+        let message_id_str = match jstring_to_string(&mut env, message_id) {
             Ok(s) => s,
-            Err(e) => {
-                log::error!("Failed to get quote_json: {}", e);
-                return 1; // Treat as expired if we can't parse
-            }
+            Err(_) => return 0
         };
 
-        let quote = match crate::nlx402::PaymentQuote::from_json(&json_str) {
-            Ok(q) => q,
-            Err(e) => {
-                log::error!("Failed to parse quote: {}", e);
-                return 1; // Treat as expired if we can't parse
-            }
+        let ack_type_u8 = match u8::try_from(ack_type) {
+            Ok(t) => t,
+            Err(_) => return 0
         };
 
-        if quote.is_expired() { 1 } else { 0 }
-    }, 1)
+        if validate_and_record_ack(&message_id_str, ack_type_u8) {
+            1  // Valid
+        } else {
+            0  // Invalid
+        }
+    }, 0)
 }
 
-/// Extract quote hash from a transaction memo
-/// Returns empty string if memo is not valid NLx402 format
+/// FIX #7: Mark a delivery as failed (e.g., retries exhausted)
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_extractQuoteHashFromMemo(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_markDeliveryFailed(
     mut env: JNIEnv,
     _class: JClass,
-    memo: JString,
-) -> jstring {
+    message_id: JString,
+) -> jboolean {
     catch_panic!(env, {
-        let memo_str = match jstring_to_string(&mut env, memo) {
+        let message_id_str = match jstring_to_string(&mut env, message_id) {
             Ok(s) => s,
-            Err(e) => {
-                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
-                return std::ptr::null_mut();
-            }
-        };
-
-        let hash = match crate::nlx402::extract_quote_hash_from_memo(&memo_str) {
-            Ok(h) => h,
-            Err(_) => String::new(), // Return empty string for invalid memos
+            Err(_) => return 0
         };
 
-        match string_to_jstring(&mut env, &hash) {
-            Ok(s) => s.into_raw(),
-            Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", e);
-                std::ptr::null_mut()
-            }
+        if mark_delivery_failed(&message_id_str) {
+            1
+        } else {
+            0
         }
-    }, std::ptr::null_mut())
+    }, 0)
 }
 
-/// Get the NLx402 protocol version
+/// FIX #7: List deliveries stuck in a stage past its timeout
+/// Overdue deliveries are expired first
+/// Returns a JSON array of {message_id, contact_id, state, sent_at, state_since}
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_getNLx402Version(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_getStuckDeliveries(
     mut env: JNIEnv,
     _class: JClass,
 ) -> jstring {
     catch_panic!(env, {
-        match string_to_jstring(&mut env, crate::nlx402::PROTOCOL_VERSION) {
+        let deliveries: Vec<serde_json::Value> = stuck_deliveries()
+            .iter()
+            .map(|d| serde_json::json!({
+                "message_id": d.message_id,
+                "contact_id": d.contact_id,
+                "state": d.state.as_str(),
+                "sent_at": d.sent_at,
+                "state_since": d.state_since,
+            }))
+            .collect();
+
+        match env.new_string(serde_json::Value::Array(deliveries).to_string()) {
             Ok(s) => s.into_raw(),
             Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", e);
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to create JSON: {}", e));
                 std::ptr::null_mut()
             }
         }
     }, std::ptr::null_mut())
 }
 
-// ==================== v2.0: Friend Request System (Two .onion + IPFS) ====================
-
-/// Create a friend request hidden service (.onion address)
-/// Returns the .onion address as a string
+/// FIX #7: Stop tracking a delivery (after completing message exchange)
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_createFriendRequestHiddenService(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_resetAckState(
     mut env: JNIEnv,
     _class: JClass,
-    service_port: jint,
-    local_port: jint,
-    directory: JString,
-) -> jstring {
+    message_id: JString,
+) {
     catch_panic!(env, {
-        let dir_str = match jstring_to_string(&mut env, directory) {
+        let message_id_str = match jstring_to_string(&mut env, message_id) {
             Ok(s) => s,
-            Err(e) => {
-                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
-                return std::ptr::null_mut();
-            }
+            Err(_) => return
         };
 
-        log::info!("Creating friend request hidden service on port {} -> {}, dir: {}",
-            service_port, local_port, dir_str);
+        reset_ack_state(&message_id_str);
+    }, ())
+}
 
-        // Get KeyManager to retrieve friend request key
-        let context = match env.call_static_method(
-            "android/app/ActivityThread",
-            "currentApplication",
-            "()Landroid/app/Application;",
-            &[],
-        ) {
-            Ok(ctx) => ctx.l().unwrap(),
+// ==================== END PROTOCOL SECURITY FIXES ====================
+
+// ==================== DELIVERY ACK (CONFIRMATION) ====================
+
+/// Send a delivery ACK (confirmation) to recipient
+/// ack_type: "PING_ACK" or "MESSAGE_ACK"
+/// item_id: ping_id or message_id being acknowledged
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_sendDeliveryAck(
+    mut env: JNIEnv,
+    _class: JClass,
+    item_id: JString,
+    ack_type: JString,
+    recipient_ed25519_pubkey: JByteArray,
+    recipient_x25519_pubkey: JByteArray,
+    recipient_onion: JString,
+) -> jboolean {
+    catch_panic!(env, {
+        // Convert inputs
+        let item_id_str = match jstring_to_string(&mut env, item_id) {
+            Ok(s) => s,
             Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get context: {}", e));
-                return std::ptr::null_mut();
+                log::error!("Failed to convert item_id: {}", e);
+                return 0;
+            }
+        };
+
+        let ack_type_str = match jstring_to_string(&mut env, ack_type) {
+            Ok(s) => s,
+            Err(e) => {
+                log::error!("Failed to convert ack_type: {}", e);
+                return 0;
+            }
+        };
+
+        let recipient_ed25519_bytes = match jbytearray_to_vec(&mut env, recipient_ed25519_pubkey) {
+            Ok(v) => v,
+            Err(e) => {
+                log::error!("Failed to convert recipient Ed25519 pubkey: {}", e);
+                return 0;
+            }
+        };
+
+        let recipient_x25519_bytes = match jbytearray_to_vec(&mut env, recipient_x25519_pubkey) {
+            Ok(v) => v,
+            Err(e) => {
+                log::error!("Failed to convert recipient X25519 pubkey: {}", e);
+                return 0;
+            }
+        };
+
+        let recipient_onion_str = match jstring_to_string(&mut env, recipient_onion) {
+            Ok(s) => s,
+            Err(e) => {
+                log::error!("Failed to convert recipient onion: {}", e);
+                return 0;
+            }
+        };
+
+        log::info!("╔════════════════════════════════════════");
+        log::info!("║ 📤 SENDING DELIVERY ACK");
+        log::info!("║ Item ID: {}", item_id_str);
+        log::info!("║ ACK Type: {}", ack_type_str);
+        log::info!("║ Recipient: {}", recipient_onion_str);
+        log::info!("╚════════════════════════════════════════");
+
+        // Get KeyManager for our keys
+        let context = match env.call_static_method(
+            "android/app/ActivityThread",
+            "currentApplication",
+            "()Landroid/app/Application;",
+            &[],
+        ) {
+            Ok(ctx) => ctx.l().unwrap(),
+            Err(e) => {
+                log::error!("Failed to get context: {}", e);
+                return 0;
             }
         };
 
         let key_manager = match crate::ffi::keystore::get_key_manager(&mut env, &context) {
             Ok(km) => km,
             Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get KeyManager: {}", e));
-                return std::ptr::null_mut();
+                log::error!("Failed to get KeyManager: {}", e);
+                return 0;
             }
         };
 
-        // Get friend request private key (derived from seed with domain separation)
-        let fr_private_key = match crate::ffi::keystore::get_friend_request_private_key(&mut env, &key_manager) {
+        // Get our signing private key (Ed25519)
+        let our_signing_private = match crate::ffi::keystore::get_signing_private_key(&mut env, &key_manager) {
             Ok(k) => k,
             Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get friend request key: {}", e));
-                return std::ptr::null_mut();
+                log::error!("Failed to get signing key: {}", e);
+                return 0;
+            }
+        };
+
+        // Create Ed25519 signing keypair
+        let sender_keypair = ed25519_dalek::SigningKey::from_bytes(&our_signing_private.as_slice().try_into().unwrap());
+
+        let recipient_ed25519_verifying = match recipient_ed25519_bytes.as_slice().try_into().ok()
+            .and_then(|bytes| ed25519_dalek::VerifyingKey::from_bytes(bytes).ok()) {
+            Some(pk) => pk,
+            None => {
+                log::error!("Invalid recipient Ed25519 pubkey");
+                return 0;
+            }
+        };
+
+        // Create DeliveryAck token (signed)
+        let ack_token = match crate::network::pingpong::DeliveryAck::new(
+            &item_id_str,
+            &ack_type_str,
+            &sender_keypair,
+            &recipient_ed25519_verifying,
+        ) {
+            Ok(token) => token,
+            Err(e) => {
+                log::error!("Failed to create ACK: {}", e);
+                return 0;
+            }
+        };
+
+        // Serialize ACK token
+        let ack_bytes = match ack_token.to_bytes() {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::error!("Failed to serialize ACK: {}", e);
+                return 0;
+            }
+        };
+
+        // Get our X25519 encryption keys
+        let our_x25519_public = match crate::ffi::keystore::get_encryption_public_key(&mut env, &key_manager) {
+            Ok(k) => k,
+            Err(e) => {
+                log::error!("Failed to get our X25519 pubkey: {}", e);
+                return 0;
+            }
+        };
+
+        let our_x25519_private = match crate::ffi::keystore::get_encryption_private_key(&mut env, &key_manager) {
+            Ok(k) => k,
+            Err(e) => {
+                log::error!("Failed to get encryption key: {}", e);
+                return 0;
+            }
+        };
+
+        // Derive shared secret using X25519 ECDH
+        let shared_secret = match crate::crypto::key_exchange::X25519Secret::from_bytes(&our_x25519_private)
+            .and_then(|secret| secret.diffie_hellman(&recipient_x25519_bytes)) {
+            Ok(secret) => secret,
+            Err(e) => {
+                log::error!("ECDH failed: {}", e);
+                return 0;
+            }
+        };
+
+        // Encrypt ACK with shared secret
+        let encrypted_ack = match crate::crypto::encryption::encrypt_message(&ack_bytes, shared_secret.as_slice()) {
+            Ok(enc) => enc,
+            Err(e) => {
+                log::error!("Encryption failed: {}", e);
+                return 0;
+            }
+        };
+
+        // Wire format: [Type Byte 0x06][Our X25519 Public Key - 32 bytes][Encrypted ACK]
+        let mut wire_message = Vec::new();
+        wire_message.push(crate::network::tor::MSG_TYPE_DELIVERY_CONFIRMATION);
+
+        let sender_x25519_pubkey: [u8; 32] = match our_x25519_public.as_slice().try_into() {
+            Ok(arr) => arr,
+            Err(_) => {
+                log::error!("Invalid sender X25519 pubkey size");
+                return 0;
             }
         };
+        wire_message.extend_from_slice(&sender_x25519_pubkey);
+        wire_message.extend_from_slice(&encrypted_ack);
 
+        // Send encrypted ACK via Tor to recipient's port 9153 (ACK listener)
         let tor_manager = get_tor_manager();
+        let runtime = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
 
-        // Run async hidden service creation with friend request key
-        let result = GLOBAL_RUNTIME.block_on(async {
-            let mut manager = tor_manager.lock().unwrap();
-            manager.create_hidden_service(service_port as u16, local_port as u16, &fr_private_key).await
+        const ACK_PORT: u16 = 9153;
+
+        let result: Result<(), Box<dyn std::error::Error>> = runtime.block_on(async {
+            let manager = tor_manager.lock().unwrap();
+            let mut conn = manager.connect(&recipient_onion_str, ACK_PORT).await?;
+            manager.send(&mut conn, &wire_message).await?;
+            Ok(())
         });
 
         match result {
-            Ok(onion_address) => {
-                log::info!("Friend request hidden service created successfully: {}", onion_address);
-
-                match string_to_jstring(&mut env, &onion_address) {
-                    Ok(s) => s.into_raw(),
-                    Err(e) => {
-                        let _ = env.throw_new("java/lang/RuntimeException", e);
-                        std::ptr::null_mut()
-                    }
-                }
-            },
+            Ok(_) => {
+                log::info!("✓ ACK sent successfully: {} for {}", ack_type_str, item_id_str);
+                1 // success
+            }
             Err(e) => {
-                let error_msg = format!("Failed to create friend request hidden service: {}", e);
-                log::error!("{}", error_msg);
-                let _ = env.throw_new("java/lang/RuntimeException", error_msg);
-                std::ptr::null_mut()
+                log::error!("Failed to send ACK: {}", e);
+                0 // failure
             }
         }
-    }, std::ptr::null_mut())
+    }, 0)
 }
 
-/// Start the contact exchange endpoint on the specified port
-/// Returns true if endpoint started successfully
+/// Start ACK listener on port 9153
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_startFriendRequestServer(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_startAckListener(
     mut env: JNIEnv,
     _class: JClass,
     port: jint,
 ) -> jboolean {
     catch_panic!(env, {
-        log::info!("Starting contact exchange endpoint on port {}", port);
+        log::info!("Starting ACK listener on port {}...", port);
 
-        // Start contact exchange endpoint in background tokio runtime
-        std::thread::spawn(move || {
-            let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
-            rt.block_on(async {
-                let endpoint = crate::network::friend_request_server::get_endpoint().await;
-                if let Err(e) = endpoint.start(port as u16).await {
-                    log::error!("Failed to start contact exchange endpoint: {}", e);
-                } else {
-                    log::info!("Contact exchange endpoint started successfully on port {}", port);
-                }
-            });
+        let tor_manager = get_tor_manager();
+
+        // Run async listener start using the global runtime
+        let result = GLOBAL_RUNTIME.block_on(async {
+            let mut manager = tor_manager.lock().unwrap();
+            manager.start_listener(Some(port as u16)).await
         });
 
-        1 // true
-    }, 0)
+        match result {
+            Ok(mut receiver) => {
+                // Create shared channel for ACK messages
+                // This channel is accessible from both port 9153 (normal path) and port 8080 (error recovery)
+                let (tx, rx) = mpsc::unbounded_channel::<(u64, Vec<u8>)>();
+
+                // Store receiver globally for polling
+                let _ = GLOBAL_ACK_RECEIVER.set(Arc::new(Mutex::new(rx)));
+
+                // Store sender globally so port 8080 can route misrouted ACKs
+                let _ = crate::network::tor::ACK_TX.set(Arc::new(std::sync::Mutex::new(tx.clone())));
+
+                // Spawn task to receive from TorManager listener and forward to shared ACK channel
+                GLOBAL_RUNTIME.spawn(async move {
+                    while let Some((connection_id, ack_bytes)) = receiver.recv().await {
+                        log::info!("Received ACK via port 9153 listener: {} bytes", ack_bytes.len());
+
+                        // Forward to shared ACK channel
+                        if let Err(e) = tx.send((connection_id, ack_bytes)) {
+                            log::error!("Failed to send ACK to shared channel: {}", e);
+                            break;
+                        }
+                    }
+                    log::warn!("ACK listener receiver closed");
+                });
+
+                log::info!("ACK listener started successfully on port {} with shared channel", port);
+                1 // success
+            }
+            Err(e) => {
+                log::error!("Failed to start ACK listener: {}", e);
+                0 // failure
+            }
+        }
+    }, 0)
+}
+
+/// Poll for an incoming ACK (non-blocking)
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_pollIncomingAck(
+    mut env: JNIEnv,
+    _class: JClass,
+) -> jbyteArray {
+    catch_panic!(env, {
+        if let Some(receiver) = GLOBAL_ACK_RECEIVER.get() {
+            let mut rx = receiver.lock().unwrap();
+
+            // Try to receive without blocking
+            match rx.try_recv() {
+                Ok((_conn_id, ack_bytes)) => {
+                    log::info!("Polled ACK: {} bytes", ack_bytes.len());
+                    match vec_to_jbytearray(&mut env, &ack_bytes) {
+                        Ok(array) => array.into_raw(),
+                        Err(_) => std::ptr::null_mut(),
+                    }
+                }
+                Err(_) => {
+                    // No ACK available
+                    std::ptr::null_mut()
+                }
+            }
+        } else {
+            // Listener not started
+            std::ptr::null_mut()
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Decrypt incoming ACK from listener and store in GLOBAL_ACK_SESSIONS
+/// Wire format: [Sender X25519 Public Key - 32 bytes][Encrypted ACK]
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_decryptAndStoreAckFromListener(
+    mut env: JNIEnv,
+    _class: JClass,
+    ack_wire: JByteArray,
+) -> jstring {
+    catch_panic!(env, {
+        let wire_bytes = match jbytearray_to_vec(&mut env, ack_wire) {
+            Ok(v) => v,
+            Err(e) => {
+                log::error!("Failed to convert ACK wire bytes: {}", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        if wire_bytes.len() < 32 {
+            log::error!("ACK wire too short: {} bytes", wire_bytes.len());
+            return std::ptr::null_mut();
+        }
+
+        // Extract sender's X25519 public key (first 32 bytes)
+        let sender_x25519_pubkey: [u8; 32] = wire_bytes[0..32].try_into().unwrap();
+        let encrypted_ack = &wire_bytes[32..];
+
+        log::info!("Decrypting ACK from sender X25519: {}", hex::encode(&sender_x25519_pubkey));
+
+        // Get our X25519 private key from KeyManager
+        let context = match env.call_static_method(
+            "android/app/ActivityThread",
+            "currentApplication",
+            "()Landroid/app/Application;",
+            &[],
+        ) {
+            Ok(ctx) => ctx.l().unwrap(),
+            Err(e) => {
+                log::error!("Failed to get context: {}", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        let key_manager = match crate::ffi::keystore::get_key_manager(&mut env, &context) {
+            Ok(km) => km,
+            Err(e) => {
+                log::error!("Failed to get KeyManager: {}", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        let our_x25519_private = match crate::ffi::keystore::get_encryption_private_key(&mut env, &key_manager) {
+            Ok(k) => k,
+            Err(e) => {
+                log::error!("Failed to get encryption private key: {}", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        // Derive shared secret
+        let shared_secret = match crate::crypto::key_exchange::X25519Secret::from_bytes(&our_x25519_private)
+            .and_then(|secret| secret.diffie_hellman(&sender_x25519_pubkey)) {
+            Ok(secret) => secret,
+            Err(e) => {
+                log::error!("ECDH failed: {}", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        // Decrypt ACK
+        let ack_bytes = match crate::crypto::encryption::decrypt_message(encrypted_ack, shared_secret.as_slice()) {
+            Ok(decrypted) => decrypted,
+            Err(e) => {
+                log::error!("Decryption failed: {}", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        // Deserialize ACK
+        let ack_token = match crate::network::pingpong::DeliveryAck::from_bytes(&ack_bytes) {
+            Ok(token) => token,
+            Err(e) => {
+                log::error!("Failed to deserialize ACK: {}", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        log::info!("╔════════════════════════════════════════");
+        log::info!("║ 📥 RECEIVED DELIVERY ACK");
+        log::info!("║ Item ID: {}", ack_token.item_id);
+        log::info!("║ ACK Type: {}", ack_token.ack_type);
+        log::info!("╚════════════════════════════════════════");
+
+        let item_id = ack_token.item_id.clone();
+        let ack_type = ack_token.ack_type.clone();
+
+        // Store in GLOBAL_ACK_SESSIONS
+        crate::network::pingpong::store_ack_session(&item_id, ack_token);
+        log::info!("Stored ACK in GLOBAL_ACK_SESSIONS");
+
+        // Return JSON with item_id and ack_type
+        let ack_json = format!(r#"{{"item_id":"{}","ack_type":"{}"}}"#, item_id, ack_type);
+        match string_to_jstring(&mut env, &ack_json) {
+            Ok(s) => s.into_raw(),
+            Err(_) => std::ptr::null_mut(),
+        }
+    }, std::ptr::null_mut())
+}
+
+// ==================== UTILITY ====================
+
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_getVersion(
+    mut env: JNIEnv,
+    _class: JClass,
+) -> jstring {
+    catch_panic!(env, {
+        match string_to_jstring(&mut env, crate::VERSION) {
+            Ok(s) => s.into_raw(),
+            Err(_) => std::ptr::null_mut(),
+        }
+    }, std::ptr::null_mut())
+}
+
+// ==================== SESSION CLEANUP ====================
+
+/// Remove Ping session after Pong is sent
+/// Call this immediately after successfully sending Pong to prevent memory leak
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_removePingSession(
+    mut env: JNIEnv,
+    _class: JClass,
+    ping_id: JString,
+) {
+    catch_panic!(env, {
+        let ping_id_str = match jstring_to_string(&mut env, ping_id) {
+            Ok(s) => s,
+            Err(e) => {
+                log::error!("Failed to convert ping_id: {}", e);
+                return;
+            }
+        };
+
+        log::debug!("Removing Ping session: {}", ping_id_str);
+        crate::network::remove_ping_session(&ping_id_str);
+    }, ())
+}
+
+/// Remove Pong session after message blob is sent
+/// Call this immediately after successfully sending message to prevent memory leak
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_removePongSession(
+    mut env: JNIEnv,
+    _class: JClass,
+    ping_id: JString,
+) {
+    catch_panic!(env, {
+        let ping_id_str = match jstring_to_string(&mut env, ping_id) {
+            Ok(s) => s,
+            Err(e) => {
+                log::error!("Failed to convert ping_id: {}", e);
+                return;
+            }
+        };
+
+        log::debug!("Removing Pong session: {}", ping_id_str);
+        crate::network::remove_pong_session(&ping_id_str);
+    }, ())
+}
+
+/// Remove ACK session after processing
+/// Call this immediately after processing ACK to prevent memory leak
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_removeAckSession(
+    mut env: JNIEnv,
+    _class: JClass,
+    item_id: JString,
+) {
+    catch_panic!(env, {
+        let item_id_str = match jstring_to_string(&mut env, item_id) {
+            Ok(s) => s,
+            Err(e) => {
+                log::error!("Failed to convert item_id: {}", e);
+                return;
+            }
+        };
+
+        log::debug!("Removing ACK session: {}", item_id_str);
+        crate::network::remove_ack_session(&item_id_str);
+    }, ())
+}
+
+/// Clean up expired Ping/Pong/ACK sessions (older than 5 minutes)
+/// Call this periodically as a safety net for orphaned entries from crashes
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_cleanupExpiredSessions(
+    _env: JNIEnv,
+    _class: JClass,
+) {
+    log::debug!("Cleaning up expired sessions...");
+    crate::network::cleanup_expired_pings();
+    crate::network::cleanup_expired_pongs();
+    crate::network::cleanup_expired_acks();
+}
+
+// ==================== NLx402 PAYMENT PROTOCOL ====================
+
+/// Create a payment quote for NLx402 protocol
+/// Returns JSON string containing the quote
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_createPaymentQuote(
+    mut env: JNIEnv,
+    _class: JClass,
+    recipient: JString,
+    amount: jlong,
+    token: JString,
+    description: JString,
+    sender_handle: JString,
+    recipient_handle: JString,
+    expiry_secs: jlong,
+) -> jstring {
+    catch_panic!(env, {
+        let recipient_str = match jstring_to_string(&mut env, recipient) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        let token_str = match jstring_to_string(&mut env, token) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        // Handle optional parameters (empty string = None)
+        let description_opt = match jstring_to_string(&mut env, description) {
+            Ok(s) if !s.is_empty() => Some(s),
+            _ => None,
+        };
+
+        let sender_handle_opt = match jstring_to_string(&mut env, sender_handle) {
+            Ok(s) if !s.is_empty() => Some(s),
+            _ => None,
+        };
+
+        let recipient_handle_opt = match jstring_to_string(&mut env, recipient_handle) {
+            Ok(s) if !s.is_empty() => Some(s),
+            _ => None,
+        };
+
+        // Create the quote with custom expiry
+        let quote = match crate::nlx402::create_quote_with_expiry(
+            &recipient_str,
+            amount as u64,
+            &token_str,
+            description_opt.as_deref(),
+            sender_handle_opt.as_deref(),
+            recipient_handle_opt.as_deref(),
+            expiry_secs as u64,
+        ) {
+            Ok(q) => q,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to create quote: {}", e));
+                return std::ptr::null_mut();
+            }
+        };
+
+        // Serialize to JSON
+        let json = match quote.to_json() {
+            Ok(j) => j,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to serialize quote: {}", e));
+                return std::ptr::null_mut();
+            }
+        };
+
+        log::info!("Created payment quote: {} for {} {}", quote.quote_id, amount, token_str);
+
+        match string_to_jstring(&mut env, &json) {
+            Ok(s) => s.into_raw(),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Get the memo string for a payment quote (for embedding in transaction)
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_getQuoteMemo(
+    mut env: JNIEnv,
+    _class: JClass,
+    quote_json: JString,
+) -> jstring {
+    catch_panic!(env, {
+        let json_str = match jstring_to_string(&mut env, quote_json) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        let quote = match crate::nlx402::PaymentQuote::from_json(&json_str) {
+            Ok(q) => q,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to parse quote: {}", e));
+                return std::ptr::null_mut();
+            }
+        };
+
+        let memo = quote.to_memo();
+
+        match string_to_jstring(&mut env, &memo) {
+            Ok(s) => s.into_raw(),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Verify a payment against a quote
+/// Returns true if payment is valid (excluding replay check - that's done in Kotlin)
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_verifyPayment(
+    mut env: JNIEnv,
+    _class: JClass,
+    quote_json: JString,
+    tx_memo: JString,
+    tx_amount: jlong,
+    tx_recipient: JString,
+    tx_token: JString,
+) -> jboolean {
+    catch_panic!(env, {
+        let json_str = match jstring_to_string(&mut env, quote_json) {
+            Ok(s) => s,
+            Err(e) => {
+                log::error!("Failed to get quote_json: {}", e);
+                return 0;
+            }
+        };
+
+        let memo_str = match jstring_to_string(&mut env, tx_memo) {
+            Ok(s) => s,
+            Err(e) => {
+                log::error!("Failed to get tx_memo: {}", e);
+                return 0;
+            }
+        };
+
+        let recipient_str = match jstring_to_string(&mut env, tx_recipient) {
+            Ok(s) => s,
+            Err(e) => {
+                log::error!("Failed to get tx_recipient: {}", e);
+                return 0;
+            }
+        };
+
+        let token_str = match jstring_to_string(&mut env, tx_token) {
+            Ok(s) => s,
+            Err(e) => {
+                log::error!("Failed to get tx_token: {}", e);
+                return 0;
+            }
+        };
+
+        let quote = match crate::nlx402::PaymentQuote::from_json(&json_str) {
+            Ok(q) => q,
+            Err(e) => {
+                log::error!("Failed to parse quote: {}", e);
+                return 0;
+            }
+        };
+
+        // Use simple verification (replay check is done in Kotlin with local DB)
+        match crate::nlx402::verify_payment_simple(
+            &quote,
+            &memo_str,
+            tx_amount as u64,
+            &recipient_str,
+            &token_str,
+        ) {
+            Ok(_) => {
+                log::info!("Payment verified successfully for quote {}", quote.quote_id);
+                1
+            }
+            Err(e) => {
+                log::warn!("Payment verification failed: {}", e);
+                0
+            }
+        }
+    }, 0)
+}
+
+/// Check if a quote has expired
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_isQuoteExpired(
+    mut env: JNIEnv,
+    _class: JClass,
+    quote_json: JString,
+) -> jboolean {
+    catch_panic!(env, {
+        let json_str = match jstring_to_string(&mut env, quote_json) {
+            Ok(s) => s,
+            Err(e) => {
+                log::error!("Failed to get quote_json: {}", e);
+                return 1; // Treat as expired if we can't parse
+            }
+        };
+
+        let quote = match crate::nlx402::PaymentQuote::from_json(&json_str) {
+            Ok(q) => q,
+            Err(e) => {
+                log::error!("Failed to parse quote: {}", e);
+                return 1; // Treat as expired if we can't parse
+            }
+        };
+
+        if quote.is_expired() { 1 } else { 0 }
+    }, 1)
+}
+
+/// Extract quote hash from a transaction memo
+/// Returns empty string if memo is not valid NLx402 format
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_extractQuoteHashFromMemo(
+    mut env: JNIEnv,
+    _class: JClass,
+    memo: JString,
+) -> jstring {
+    catch_panic!(env, {
+        let memo_str = match jstring_to_string(&mut env, memo) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        let hash = match crate::nlx402::extract_quote_hash_from_memo(&memo_str) {
+            Ok(h) => h,
+            Err(_) => String::new(), // Return empty string for invalid memos
+        };
+
+        match string_to_jstring(&mut env, &hash) {
+            Ok(s) => s.into_raw(),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Get the NLx402 protocol version
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_getNLx402Version(
+    mut env: JNIEnv,
+    _class: JClass,
+) -> jstring {
+    catch_panic!(env, {
+        match string_to_jstring(&mut env, crate::nlx402::PROTOCOL_VERSION) {
+            Ok(s) => s.into_raw(),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
+}
+
+// ==================== v2.0: Friend Request System (Two .onion + IPFS) ====================
+
+/// Create a friend request hidden service (.onion address)
+/// Returns the .onion address as a string
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_createFriendRequestHiddenService(
+    mut env: JNIEnv,
+    _class: JClass,
+    service_port: jint,
+    local_port: jint,
+    directory: JString,
+) -> jstring {
+    catch_panic!(env, {
+        let dir_str = match jstring_to_string(&mut env, directory) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        log::info!("Creating friend request hidden service on port {} -> {}, dir: {}",
+            service_port, local_port, dir_str);
+
+        // Get KeyManager to retrieve friend request key
+        let context = match env.call_static_method(
+            "android/app/ActivityThread",
+            "currentApplication",
+            "()Landroid/app/Application;",
+            &[],
+        ) {
+            Ok(ctx) => ctx.l().unwrap(),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get context: {}", e));
+                return std::ptr::null_mut();
+            }
+        };
+
+        let key_manager = match crate::ffi::keystore::get_key_manager(&mut env, &context) {
+            Ok(km) => km,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get KeyManager: {}", e));
+                return std::ptr::null_mut();
+            }
+        };
+
+        // Get friend request private key (derived from seed with domain separation)
+        let fr_private_key = match crate::ffi::keystore::get_friend_request_private_key(&mut env, &key_manager) {
+            Ok(k) => k,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to get friend request key: {}", e));
+                return std::ptr::null_mut();
+            }
+        };
+
+        let tor_manager = get_tor_manager();
+
+        // Run async hidden service creation with friend request key
+        let result = GLOBAL_RUNTIME.block_on(async {
+            let mut manager = tor_manager.lock().unwrap();
+            manager.create_hidden_service(service_port as u16, local_port as u16, &fr_private_key).await
+        });
+
+        match result {
+            Ok(onion_address) => {
+                log::info!("Friend request hidden service created successfully: {}", onion_address);
+
+                match string_to_jstring(&mut env, &onion_address) {
+                    Ok(s) => s.into_raw(),
+                    Err(e) => {
+                        let _ = env.throw_new("java/lang/RuntimeException", e);
+                        std::ptr::null_mut()
+                    }
+                }
+            },
+            Err(e) => {
+                let error_msg = format!("Failed to create friend request hidden service: {}", e);
+                log::error!("{}", error_msg);
+                let _ = env.throw_new("java/lang/RuntimeException", error_msg);
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Start the contact exchange endpoint on the specified port
+/// Returns true if endpoint started successfully
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_startFriendRequestServer(
+    mut env: JNIEnv,
+    _class: JClass,
+    port: jint,
+) -> jboolean {
+    catch_panic!(env, {
+        log::info!("Starting contact exchange endpoint on port {}", port);
+
+        // Start contact exchange endpoint in background tokio runtime
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
+            rt.block_on(async {
+                let endpoint = crate::network::friend_request_server::get_endpoint().await;
+                if let Err(e) = endpoint.start(port as u16).await {
+                    log::error!("Failed to start contact exchange endpoint: {}", e);
+                } else {
+                    log::info!("Contact exchange endpoint started successfully on port {}", port);
+                }
+            });
+        });
+
+        1 // true
+    }, 0)
+}
+
+/// Stop the contact exchange endpoint
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_stopFriendRequestServer(
+    mut env: JNIEnv,
+    _class: JClass,
+) {
+    catch_panic!(env, {
+        log::info!("Stopping contact exchange endpoint");
+
+        // Stop endpoint in background task
+        std::thread::spawn(|| {
+            let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
+            rt.block_on(async {
+                let endpoint = crate::network::friend_request_server::get_endpoint().await;
+                endpoint.stop().await;
+                log::info!("Contact exchange endpoint stopped");
+            });
+        });
+
+    }, ())
+}
+
+/// Store encrypted contact card to be served at GET /contact-card
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_serveContactCard(
+    mut env: JNIEnv,
+    _class: JClass,
+    encrypted_card: JByteArray,
+    card_length: jint,
+    cid: JString,
+) {
+    catch_panic!(env, {
+        let card_bytes = match jbytearray_to_vec(&mut env, encrypted_card) {
+            Ok(b) => b,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                return;
+            }
+        };
+
+        let cid_str = match jstring_to_string(&mut env, cid) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return;
+            }
+        };
+
+        log::info!("Storing encrypted contact card (CID: {}, length: {})", cid_str, card_length);
+
+        // Store card in endpoint state
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
+            rt.block_on(async {
+                let endpoint = crate::network::friend_request_server::get_endpoint().await;
+                endpoint.set_contact_card(card_bytes, cid_str).await;
+                log::info!("Contact card stored in contact exchange endpoint");
+            });
+        });
+
+    }, ())
+}
+
+/// Serve contact list on the contact exchange endpoint (v5 architecture)
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_serveContactList(
+    mut env: JNIEnv,
+    _class: JClass,
+    cid: JString,
+    encrypted_list: JByteArray,
+    list_length: jint,
+) {
+    catch_panic!(env, {
+        let cid_str = match jstring_to_string(&mut env, cid) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return;
+            }
+        };
+
+        let list_bytes = match jbytearray_to_vec(&mut env, encrypted_list) {
+            Ok(b) => b,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                return;
+            }
+        };
+
+        log::info!("Storing encrypted contact list (CID: {}, length: {})", cid_str, list_length);
+
+        // Store contact list in endpoint state
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
+            rt.block_on(async {
+                let endpoint = crate::network::friend_request_server::get_endpoint().await;
+                endpoint.set_contact_list(cid_str, list_bytes).await;
+                log::info!("Contact list stored in contact exchange endpoint");
+            });
+        });
+
+    }, ())
+}
+
+/// Publish a PQXDH prekey bundle at GET /prekey-bundle
+/// One-time bundles are handed out once each; the fallback bundle is served when none are left
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_servePrekeyBundle(
+    mut env: JNIEnv,
+    _class: JClass,
+    bundle: JByteArray,
+    one_time: jboolean,
+) {
+    catch_panic!(env, {
+        let bundle_bytes = match jbytearray_to_vec(&mut env, bundle) {
+            Ok(b) => b,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                return;
+            }
+        };
+
+        // Reject anything that is not a correctly signed bundle before serving it (our own
+        // bundle, so the key it names is the one to check against)
+        if let Err(e) = crate::crypto::PrekeyBundle::from_bytes(&bundle_bytes).and_then(|b| b.verify(&b.identity_signing_key)) {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid prekey bundle: {}", e));
+            return;
+        }
+
+        let one_time = one_time != 0;
+        log::info!("Storing prekey bundle (one-time: {}, length: {})", one_time, bundle_bytes.len());
+
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
+            rt.block_on(async {
+                let endpoint = crate::network::friend_request_server::get_endpoint().await;
+                if one_time {
+                    endpoint.add_prekey_bundle(bundle_bytes).await;
+                } else {
+                    endpoint.set_fallback_prekey_bundle(bundle_bytes).await;
+                }
+            });
+        });
+
+    }, ())
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Generate a new PQXDH prekey store (signed prekey, signed KEM prekey, one-time prekeys)
+/// Returns the serialized store (contains secrets; persist encrypted)
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_generatePrekeyStore(
+    mut env: JNIEnv,
+    _class: JClass,
+    identity_signing_private: JByteArray,
+    identity_dh_public: JByteArray,
+    one_time_count: jint,
+) -> jbyteArray {
+    catch_panic!(env, {
+        let signing_private = match jbytearray_to_vec(&mut env, identity_signing_private) {
+            Ok(k) if k.len() == 32 => Zeroizing::new(<[u8; 32]>::try_from(k.as_slice()).unwrap()),
+            _ => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "Signing key must be 32 bytes");
+                return std::ptr::null_mut();
+            }
+        };
+        let dh_public: [u8; 32] = match jbytearray_to_vec(&mut env, identity_dh_public) {
+            Ok(k) if k.len() == 32 => k.try_into().unwrap(),
+            _ => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "X25519 public key must be 32 bytes");
+                return std::ptr::null_mut();
+            }
+        };
+
+        let store = match crate::crypto::PrekeyStore::generate(&signing_private, &dh_public, one_time_count.max(0) as usize, unix_now())
+            .and_then(|store| store.to_bytes())
+        {
+            Ok(bytes) => Zeroizing::new(bytes),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Prekey generation failed: {}", e));
+                return std::ptr::null_mut();
+            }
+        };
+
+        match vec_to_jbytearray(&mut env, &store) {
+            Ok(arr) => arr.into_raw(),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Rotate the signed and KEM prekeys if they are due and top up one-time prekeys
+/// Returns the updated store, or null if nothing changed
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_maintainPrekeyStore(
+    mut env: JNIEnv,
+    _class: JClass,
+    store: JByteArray,
+    identity_signing_private: JByteArray,
+    one_time_target: jint,
+) -> jbyteArray {
+    catch_panic!(env, {
+        let store_bytes = match jbytearray_to_vec(&mut env, store) {
+            Ok(b) => Zeroizing::new(b),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                return std::ptr::null_mut();
+            }
+        };
+        let signing_private = match jbytearray_to_vec(&mut env, identity_signing_private) {
+            Ok(k) if k.len() == 32 => Zeroizing::new(<[u8; 32]>::try_from(k.as_slice()).unwrap()),
+            _ => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "Signing key must be 32 bytes");
+                return std::ptr::null_mut();
+            }
+        };
+
+        let mut prekeys = match crate::crypto::PrekeyStore::from_bytes(&store_bytes) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid prekey store: {}", e));
+                return std::ptr::null_mut();
+            }
+        };
+
+        let rotated = match prekeys.rotate_if_due(&signing_private, unix_now()) {
+            Ok(rotated) => rotated,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Prekey rotation failed: {}", e));
+                return std::ptr::null_mut();
+            }
+        };
+        let added = prekeys.replenish_one_time_prekeys(one_time_target.max(0) as usize);
+        if !rotated && added.is_empty() {
+            return std::ptr::null_mut();
+        }
+        log::info!("Prekey store updated (rotated: {}, new one-time prekeys: {})", rotated, added.len());
+
+        let updated = match prekeys.to_bytes() {
+            Ok(bytes) => Zeroizing::new(bytes),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Prekey serialization failed: {}", e));
+                return std::ptr::null_mut();
+            }
+        };
+        match vec_to_jbytearray(&mut env, &updated) {
+            Ok(arr) => arr.into_raw(),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Publish every bundle of a prekey store at GET /prekey-bundle
+/// Replaces previously published bundles (one per unused one-time prekey, plus the fallback)
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_publishPrekeyBundles(
+    mut env: JNIEnv,
+    _class: JClass,
+    store: JByteArray,
+) -> jboolean {
+    catch_panic!(env, {
+        let store_bytes = match jbytearray_to_vec(&mut env, store) {
+            Ok(b) => Zeroizing::new(b),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                return 0;
+            }
+        };
+
+        let bundles = crate::crypto::PrekeyStore::from_bytes(&store_bytes).and_then(|prekeys| {
+            let (one_time, fallback) = prekeys.publishable_bundles()?;
+            let one_time = one_time.iter().map(|b| b.to_bytes()).collect::<Result<Vec<_>, _>>()?;
+            Ok((one_time, fallback.to_bytes()?))
+        });
+        let (one_time, fallback) = match bundles {
+            Ok(bundles) => bundles,
+            Err(e) => {
+                log::error!("Failed to build prekey bundles: {}", e);
+                return 0;
+            }
+        };
+
+        log::info!("Publishing {} one-time prekey bundles plus fallback", one_time.len());
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
+            rt.block_on(async {
+                let endpoint = crate::network::friend_request_server::get_endpoint().await;
+                endpoint.replace_prekey_bundles(one_time, fallback).await;
+            });
+        });
+
+        1
+    }, 0)
+}
+
+/// Fetch a peer's prekey bundle via Tor and verify its signatures
+/// Returns the serialized bundle, or null if unavailable, forged, not for `expectedIdentityDhKey`,
+/// or not signed by the pinned `expectedIdentitySigningKey`
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_fetchPrekeyBundle(
+    mut env: JNIEnv,
+    _class: JClass,
+    url: JString,
+    expected_identity_dh_key: JByteArray,
+    expected_identity_signing_key: JByteArray,
+) -> jbyteArray {
+    catch_panic!(env, {
+        let url_str = match jstring_to_string(&mut env, url) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
+        let expected_key = match jbytearray_to_vec(&mut env, expected_identity_dh_key) {
+            Ok(k) => k,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                return std::ptr::null_mut();
+            }
+        };
+        let expected_signing_key: [u8; 32] = match jbytearray_to_vec(&mut env, expected_identity_signing_key)
+            .map(|k| k.try_into())
+        {
+            Ok(Ok(k)) => k,
+            _ => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "Ed25519 public key must be 32 bytes");
+                return std::ptr::null_mut();
+            }
+        };
+
+        let body = match crate::network::Socks5Client::tor_default().http_get_bytes(&url_str) {
+            Ok((200, body)) => body,
+            Ok((status, _)) => {
+                log::warn!("Prekey bundle not available (HTTP {})", status);
+                return std::ptr::null_mut();
+            }
+            Err(e) => {
+                log::warn!("Prekey bundle fetch failed: {}", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        match crate::crypto::PrekeyBundle::from_bytes(&body).and_then(|b| b.verify(&expected_signing_key).map(|_| b)) {
+            Ok(bundle) if bundle.identity_dh_key[..] == expected_key[..] => {}
+            Ok(_) => {
+                log::error!("Prekey bundle is for a different identity key - rejecting");
+                return std::ptr::null_mut();
+            }
+            Err(e) => {
+                log::error!("Invalid prekey bundle: {}", e);
+                return std::ptr::null_mut();
+            }
+        }
+
+        match vec_to_jbytearray(&mut env, &body) {
+            Ok(arr) => arr.into_raw(),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Start a PQXDH session from a peer's prekey bundle, which must be signed by the pinned
+/// `expectedIdentitySigningKey`
+/// Returns [root_key:32][init_message] (send the init message with the first payload), or null on failure
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_pqxdhInitiate(
+    mut env: JNIEnv,
+    _class: JClass,
+    our_identity_dh_private: JByteArray,
+    bundle: JByteArray,
+    expected_identity_signing_key: JByteArray,
+) -> jbyteArray {
+    catch_panic!(env, {
+        let our_private = match jbytearray_to_vec(&mut env, our_identity_dh_private)
+            .map(Zeroizing::new)
+            .map(|k| crate::crypto::X25519Secret::from_bytes(&k))
+        {
+            Ok(Ok(k)) => k,
+            _ => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "X25519 private key must be 32 bytes");
+                return std::ptr::null_mut();
+            }
+        };
+        let expected_signing_key: [u8; 32] = match jbytearray_to_vec(&mut env, expected_identity_signing_key)
+            .map(|k| k.try_into())
+        {
+            Ok(Ok(k)) => k,
+            _ => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "Ed25519 public key must be 32 bytes");
+                return std::ptr::null_mut();
+            }
+        };
+        let bundle_bytes = match jbytearray_to_vec(&mut env, bundle) {
+            Ok(b) => b,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        let result = crate::crypto::PrekeyBundle::from_bytes(&bundle_bytes)
+            .and_then(|bundle| crate::crypto::pqxdh::initiate(&our_private, &bundle, &expected_signing_key))
+            .and_then(|(output, message)| Ok((output, message.to_bytes()?)));
+        let (output, message) = match result {
+            Ok(r) => r,
+            Err(e) => {
+                log::error!("PQXDH initiate failed: {}", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        let mut out = Zeroizing::new(Vec::with_capacity(32 + message.len()));
+        out.extend_from_slice(output.root_key.expose_secret());
+        out.extend_from_slice(&message);
+        match vec_to_jbytearray(&mut env, &out) {
+            Ok(arr) => arr.into_raw(),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Complete a PQXDH session as the responder (consumes the named one-time prekey)
+/// Returns [root_key:32][updated_store] (persist the store before using the key), or null on failure
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_pqxdhRespond(
+    mut env: JNIEnv,
+    _class: JClass,
+    store: JByteArray,
+    our_identity_dh_private: JByteArray,
+    init_message: JByteArray,
+) -> jbyteArray {
+    catch_panic!(env, {
+        let store_bytes = match jbytearray_to_vec(&mut env, store) {
+            Ok(b) => Zeroizing::new(b),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                return std::ptr::null_mut();
+            }
+        };
+        let our_private = match jbytearray_to_vec(&mut env, our_identity_dh_private)
+            .map(Zeroizing::new)
+            .map(|k| crate::crypto::X25519Secret::from_bytes(&k))
+        {
+            Ok(Ok(k)) => k,
+            _ => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "X25519 private key must be 32 bytes");
+                return std::ptr::null_mut();
+            }
+        };
+        let message_bytes = match jbytearray_to_vec(&mut env, init_message) {
+            Ok(b) => b,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        let result = crate::crypto::PrekeyStore::from_bytes(&store_bytes).and_then(|mut prekeys| {
+            let message = crate::crypto::PqxdhInitMessage::from_bytes(&message_bytes)?;
+            let output = prekeys.respond(&our_private, &message)?;
+            Ok((output, Zeroizing::new(prekeys.to_bytes()?)))
+        });
+        let (output, updated) = match result {
+            Ok(r) => r,
+            Err(e) => {
+                log::error!("PQXDH respond failed: {}", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        let mut out = Zeroizing::new(Vec::with_capacity(32 + updated.len()));
+        out.extend_from_slice(output.root_key.expose_secret());
+        out.extend_from_slice(&updated);
+        match vec_to_jbytearray(&mut env, &out) {
+            Ok(arr) => arr.into_raw(),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Poll for incoming friend requests (non-blocking)
+/// Returns raw encrypted bytes (0x07 or 0x08 wire protocol messages)
+/// Kotlin will handle decryption based on message type
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_pollFriendRequest(
+    mut env: JNIEnv,
+    _class: JClass,
+) -> jbyteArray {
+    catch_panic!(env, {
+        if let Some(receiver) = GLOBAL_FRIEND_REQUEST_RECEIVER.get() {
+            let mut rx = receiver.lock().unwrap();
+
+            // Try to receive without blocking
+            match rx.try_recv() {
+                Ok(friend_request_bytes) => {
+                    log::info!("Polled friend request: {} bytes", friend_request_bytes.len());
+                    match vec_to_jbytearray(&mut env, &friend_request_bytes) {
+                        Ok(array) => array.into_raw(),
+                        Err(_) => std::ptr::null_mut(),
+                    }
+                }
+                Err(_) => {
+                    // No friend request available
+                    std::ptr::null_mut()
+                }
+            }
+        } else {
+            // Listener not started
+            std::ptr::null_mut()
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Poll for friend request responses (non-blocking)
+/// Returns JSON string with response data, or null if no responses
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_pollFriendResponse(
+    mut env: JNIEnv,
+    _class: JClass,
+) -> jstring {
+    catch_panic!(env, {
+        log::debug!("Polling for friend request responses");
+
+        // Poll for response in blocking fashion
+        let result = std::thread::spawn(|| {
+            let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
+            rt.block_on(async {
+                let endpoint = crate::network::friend_request_server::get_endpoint().await;
+                endpoint.poll_response().await
+            })
+        }).join();
+
+        match result {
+            Ok(Some(response)) => {
+                // Convert to JSON
+                let json = format!(
+                    r#"{{"approved":{},"encryptedCard":"{}","timestamp":{}}}"#,
+                    response.approved,
+                    base64::encode(&response.encrypted_card),
+                    response.timestamp
+                );
+
+                match string_to_jstring(&mut env, &json) {
+                    Ok(s) => s.into_raw(),
+                    Err(e) => {
+                        let _ = env.throw_new("java/lang/RuntimeException", e);
+                        std::ptr::null_mut()
+                    }
+                }
+            }
+            _ => std::ptr::null_mut()
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Make HTTP GET request via Tor SOCKS5 proxy
+/// Returns response body or null on error
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_httpGetViaTor(
+    mut env: JNIEnv,
+    _class: JClass,
+    url: JString,
+) -> jstring {
+    catch_panic!(env, {
+        let url_str = match jstring_to_string(&mut env, url) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        log::info!("HTTP GET via Tor: {}", url_str);
+
+        // Create SOCKS5 client and make request
+        let client = crate::network::Socks5Client::tor_default();
+
+        match client.http_get(&url_str) {
+            Ok(response) => {
+                // Extract body from response
+                if let Some(body) = crate::network::socks5_client::extract_http_body(&response) {
+                    match string_to_jstring(&mut env, &body) {
+                        Ok(s) => {
+                            log::info!("HTTP GET successful ({} bytes)", body.len());
+                            s.into_raw()
+                        }
+                        Err(e) => {
+                            let _ = env.throw_new("java/lang/RuntimeException", e);
+                            std::ptr::null_mut()
+                        }
+                    }
+                } else {
+                    // Return full response if can't extract body
+                    match string_to_jstring(&mut env, &response) {
+                        Ok(s) => s.into_raw(),
+                        Err(e) => {
+                            let _ = env.throw_new("java/lang/RuntimeException", e);
+                            std::ptr::null_mut()
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                log::error!("HTTP GET via Tor failed: {}", e);
+                let error_msg = format!("HTTP GET failed: {}", e);
+                let _ = env.throw_new("java/io/IOException", &error_msg);
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Make HTTP POST request via Tor SOCKS5 proxy
+/// Returns response body or null on error
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_httpPostViaTor(
+    mut env: JNIEnv,
+    _class: JClass,
+    url: JString,
+    body: JString,
+) -> jstring {
+    catch_panic!(env, {
+        let url_str = match jstring_to_string(&mut env, url) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        let body_str = match jstring_to_string(&mut env, body) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
+
+        log::info!("HTTP POST via Tor: {} (body length: {})", url_str, body_str.len());
+
+        // Create SOCKS5 client and make request
+        let client = crate::network::Socks5Client::tor_default();
+
+        // Use application/json as default content type
+        match client.http_post(&url_str, &body_str, "application/json") {
+            Ok(response) => {
+                // Extract body from response
+                if let Some(body) = crate::network::socks5_client::extract_http_body(&response) {
+                    match string_to_jstring(&mut env, &body) {
+                        Ok(s) => {
+                            log::info!("HTTP POST successful ({} bytes)", body.len());
+                            s.into_raw()
+                        }
+                        Err(e) => {
+                            let _ = env.throw_new("java/lang/RuntimeException", e);
+                            std::ptr::null_mut()
+                        }
+                    }
+                } else {
+                    // Return full response if can't extract body
+                    match string_to_jstring(&mut env, &response) {
+                        Ok(s) => s.into_raw(),
+                        Err(e) => {
+                            let _ = env.throw_new("java/lang/RuntimeException", e);
+                            std::ptr::null_mut()
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                log::error!("HTTP POST via Tor failed: {}", e);
+                let error_msg = format!("HTTP POST failed: {}", e);
+                let _ = env.throw_new("java/io/IOException", &error_msg);
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
+}
+
+// ==================== END v2.0: Friend Request System ====================
+
+// ==================== v2.0: Voice Streaming ====================
+
+/// Register voice packet callback handler (v2.0)
+/// Must be called before startVoiceStreamingServer
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_setVoicePacketCallback(
+    mut env: JNIEnv,
+    _class: JClass,
+    callback: JObject,
+) {
+    catch_panic!(env, {
+        // Create global reference to callback object
+        let global_callback = env.new_global_ref(callback)
+            .expect("Failed to create global reference to voice callback");
+
+        // Store in global
+        let callback_storage = GLOBAL_VOICE_CALLBACK.get_or_init(|| Mutex::new(None));
+        *callback_storage.lock().unwrap() = Some(global_callback);
+
+        log::info!("Voice packet callback registered");
+    }, ())
+}
+
+/// Register voice signaling callback handler (v2.0)
+/// Handles incoming signaling messages (CALL_OFFER, CALL_ANSWER, etc) over voice onion HTTP
+/// Must be called before startVoiceStreamingServer
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_setVoiceSignalingCallback(
+    mut env: JNIEnv,
+    _class: JClass,
+    callback: JObject,
+) {
+    catch_panic!(env, {
+        // Create global reference to callback object
+        let global_callback = env.new_global_ref(callback)
+            .expect("Failed to create global reference to voice signaling callback");
+
+        // Store in global
+        let callback_storage = GLOBAL_VOICE_SIGNALING_CALLBACK.get_or_init(|| Mutex::new(None));
+        *callback_storage.lock().unwrap() = Some(global_callback);
+
+        log::info!("Voice signaling callback registered");
+    }, ())
+}
+
+/// Start voice streaming listener on port 9152 (v2.0)
+/// Must be called before accepting or creating voice sessions
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_startVoiceStreamingServer(
+    mut env: JNIEnv,
+    _class: JClass,
+) {
+    catch_panic!(env, {
+        let voice_listener = get_voice_listener();
+
+        // Get JavaVM for callback invocations (get twice since JavaVM doesn't implement Clone)
+        let jvm_audio = env.get_java_vm().expect("Failed to get JavaVM");
+        let jvm_signaling = env.get_java_vm().expect("Failed to get JavaVM");
+
+        // Start listener in background task
+        GLOBAL_RUNTIME.spawn(async move {
+            // Set audio callback (must be done before starting listener)
+            {
+                let mut listener = voice_listener.lock().await;
+                listener.set_audio_callback(move |call_id: String, packet: crate::audio::voice_streaming::VoicePacket| {
+                // Get JNI environment (attach thread if needed)
+                let mut env = match jvm_audio.attach_current_thread() {
+                    Ok(env) => env,
+                    Err(e) => {
+                        log::error!("Failed to attach thread for voice callback: {}", e);
+                        return;
+                    }
+                };
+
+                // Get callback object
+                let callback_storage = GLOBAL_VOICE_CALLBACK.get().unwrap();
+                let callback_lock = callback_storage.lock().unwrap();
+
+                if let Some(ref callback_ref) = *callback_lock {
+                    let callback_obj = callback_ref.as_obj();
+
+                    // Convert call_id to JString
+                    let call_id_jstring: JString = match env.new_string(&call_id) {
+                        Ok(s) => s,
+                        Err(e) => {
+                            log::error!("Failed to create call_id JString: {}", e);
+                            return;
+                        }
+                    };
+
+                    // Convert audio_data to JByteArray
+                    let audio_data_array: JByteArray = match env.byte_array_from_slice(&packet.audio_data) {
+                        Ok(arr) => arr,
+                        Err(e) => {
+                            log::error!("Failed to create audio data byte array: {}", e);
+                            return;
+                        }
+                    };
+
+                    // Call onVoicePacket(callId: String, sequence: Int, timestamp: Long, circuitIndex: Byte, ptype: Byte, audioData: ByteArray)
+                    let result = env.call_method(
+                        callback_obj,
+                        "onVoicePacket",
+                        "(Ljava/lang/String;IJBB[B)V",
+                        &[
+                            (&call_id_jstring).into(),
+                            (packet.sequence as i32).into(),
+                            (packet.timestamp as i64).into(),
+                            (packet.circuit_index as i8).into(),
+                            (packet.ptype as i8).into(),
+                            (&audio_data_array).into(),
+                        ],
+                    );
+
+                    if let Err(e) = result {
+                        log::error!("Failed to invoke voice packet callback: {}", e);
+                    }
+                } else {
+                    log::warn!("Voice packet received but no callback registered");
+                }
+                });
+
+                // Set signaling callback for HTTP POST messages (CALL_OFFER, CALL_ANSWER, etc)
+                listener.set_signaling_callback(move |sender_pubkey: Vec<u8>, wire_message: Vec<u8>| {
+                    // Get JNI environment (attach thread if needed)
+                    let mut env = match jvm_signaling.attach_current_thread() {
+                        Ok(env) => env,
+                        Err(e) => {
+                            log::error!("Failed to attach thread for signaling callback: {}", e);
+                            return;
+                        }
+                    };
+
+                    // Get callback object
+                    let callback_storage = GLOBAL_VOICE_SIGNALING_CALLBACK.get().unwrap();
+                    let callback_lock = callback_storage.lock().unwrap();
+
+                    if let Some(ref callback_ref) = *callback_lock {
+                        let callback_obj = callback_ref.as_obj();
+
+                        // Convert sender_pubkey to JByteArray
+                        let sender_pubkey_array: JByteArray = match env.byte_array_from_slice(&sender_pubkey) {
+                            Ok(arr) => arr,
+                            Err(e) => {
+                                log::error!("Failed to create sender pubkey byte array: {}", e);
+                                return;
+                            }
+                        };
+
+                        // Convert wire_message to JByteArray
+                        let wire_message_array: JByteArray = match env.byte_array_from_slice(&wire_message) {
+                            Ok(arr) => arr,
+                            Err(e) => {
+                                log::error!("Failed to create wire message byte array: {}", e);
+                                return;
+                            }
+                        };
+
+                        // Call onSignalingMessage(senderPubkey: ByteArray, wireMessage: ByteArray)
+                        let result = env.call_method(
+                            callback_obj,
+                            "onSignalingMessage",
+                            "([B[B)V",
+                            &[
+                                (&sender_pubkey_array).into(),
+                                (&wire_message_array).into(),
+                            ],
+                        );
+
+                        if let Err(e) = result {
+                            log::error!("Failed to invoke voice signaling callback: {}", e);
+                        } else {
+                            log::info!("Voice signaling callback invoked successfully");
+                        }
+                    } else {
+                        log::warn!("Voice signaling message received but no callback registered");
+                    }
+                });
+            } // Drop MutexGuard here
+
+            // Start listener
+            if let Err(e) = voice_listener.lock().await.start().await {
+                log::error!("Failed to start voice streaming listener: {}", e);
+                return;
+            }
+
+            log::info!("Voice streaming listener started on port 9152");
+
+            // Note: start() now spawns its own accept task internally,
+            // so we don't need to do anything else here
+        });
+    }, ())
+}
+
+/// Create outgoing voice session to peer's voice .onion (v2.0)
+/// Connects to peer's voice hidden service on port 9152 with multiple circuits
+/// @param num_circuits Number of parallel Tor circuits to create (typically 3)
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_createVoiceSession(
+    mut env: JNIEnv,
+    _class: JClass,
+    call_id: JString,
+    peer_voice_onion: JString,
+    num_circuits: jint,
+) -> jboolean {
+    catch_panic!(env, {
+        let call_id_str = match jstring_to_string(&mut env, call_id) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return 0;
+            }
+        };
+
+        let peer_onion_str = match jstring_to_string(&mut env, peer_voice_onion) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return 0;
+            }
+        };
+
+        let num_circuits_usize = num_circuits as usize;
+        log::info!("Creating voice session with {} circuits for call: {} to {}",
+                   num_circuits_usize, call_id_str, peer_onion_str);
+
+        let voice_listener = get_voice_listener();
+
+        // Create session using global runtime
+        let result = GLOBAL_RUNTIME.block_on(async {
+            let mut listener = voice_listener.lock().await;
+            listener.create_session(call_id_str.clone(), &peer_onion_str, num_circuits_usize).await
+        });
+
+        match result {
+            Ok(_) => {
+                log::info!("Voice session with {} circuits created for call: {}", num_circuits_usize, call_id_str);
+                1
+            }
+            Err(e) => {
+                log::error!("Failed to create voice session: {}", e);
+                let error_msg = format!("Voice session creation failed: {}", e);
+                let _ = env.throw_new("java/io/IOException", &error_msg);
+                0
+            }
+        }
+    }, 0)
+}
+
+/// Send audio packet to peer in active voice session on specific circuit (v2.0)
+/// Audio data should be Opus-encoded
+/// @param circuit_index Which circuit to use (0 to num_circuits-1)
+/// @param ptype Packet type (0x01=AUDIO, 0x02=CONTROL)
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_sendAudioPacket(
+    mut env: JNIEnv,
+    _class: JClass,
+    call_id: JString,
+    sequence: jint,
+    timestamp: jlong,
+    audio_data: JByteArray,
+    circuit_index: jint,
+    ptype: jint,
+) -> jboolean {
+    catch_panic!(env, {
+        let call_id_str = match jstring_to_string(&mut env, call_id) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return 0;
+            }
+        };
+
+        let audio_bytes = match jbytearray_to_vec(&mut env, audio_data) {
+            Ok(v) => v,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return 0;
+            }
+        };
+
+        let packet = VoicePacket {
+            sequence: sequence as u32,
+            timestamp: timestamp as u64,
+            audio_data: audio_bytes,
+            circuit_index: circuit_index as u8,
+            ptype: ptype as u8,
+            redundant_audio_data: Vec::new(),  // Phase 3: Will be populated by sender task
+            redundant_sequence: 0,              // Phase 3: Will be populated by sender task
+        };
+
+        let voice_listener = get_voice_listener();
+        let listener = voice_listener.blocking_lock();
+
+        match listener.send_audio(&call_id_str, circuit_index as usize, packet) {
+            Ok(_) => 1,
+            Err(e) => {
+                log::warn!("Failed to send audio packet for call {} on circuit {}: {}", call_id_str, circuit_index, e);
+                0
+            }
+        }
+    }, 0)
+}
+
+/// End voice session and close connection (v2.0)
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_endVoiceSession(
+    mut env: JNIEnv,
+    _class: JClass,
+    call_id: JString,
+) {
+    catch_panic!(env, {
+        let call_id_str = match jstring_to_string(&mut env, call_id) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return;
+            }
+        };
+
+        log::info!("Ending voice session for call: {}", call_id_str);
+
+        let voice_listener = get_voice_listener();
+        let listener = voice_listener.blocking_lock();
+        listener.end_session(&call_id_str);
+    }, ())
+}
+
+/// Get number of active voice sessions (v2.0)
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_getActiveVoiceSessions(
+    _env: JNIEnv,
+    _class: JClass,
+) -> jint {
+    let voice_listener = get_voice_listener();
+    let listener = voice_listener.blocking_lock();
+    listener.active_sessions() as jint
+}
+
+/// Rebuild a specific voice circuit (close and reconnect with fresh Tor path)
+/// Tor daemon will automatically pick a different relay path via SOCKS5 isolation
+/// @param call_id Active call session ID
+/// @param circuit_index Circuit to rebuild (0-2)
+/// @param rebuild_epoch Incremented counter (forces fresh SOCKS5 isolation)
+/// @return true if rebuild succeeded, false if failed
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_rebuildVoiceCircuit(
+    mut env: JNIEnv,
+    _class: JClass,
+    call_id: JString,
+    circuit_index: jint,
+    rebuild_epoch: jint,
+) -> jboolean {
+    catch_panic!(env, {
+        let call_id_str = match jstring_to_string(&mut env, call_id) {
+            Ok(s) => s,
+            Err(e) => {
+                log::error!("Failed to convert call_id: {}", e);
+                return 0;
+            }
+        };
+
+        log::warn!("Rebuilding voice circuit {} for call {} (rebuild_epoch={})", circuit_index, call_id_str, rebuild_epoch);
+
+        // Rebuild circuit via async runtime
+        let result = GLOBAL_RUNTIME.block_on(async {
+            let voice_listener = get_voice_listener();
+            let mut listener = voice_listener.lock().await;
+
+            listener.rebuild_circuit(&call_id_str, circuit_index as usize, rebuild_epoch as u32).await
+        });
+
+        match result {
+            Ok(_) => {
+                log::info!("✓ Circuit {} rebuild SUCCESS for call {}", circuit_index, call_id_str);
+                1 // success
+            }
+            Err(e) => {
+                log::error!("✗ Circuit {} rebuild FAILED for call {}: {}", circuit_index, call_id_str, e);
+                0 // failure
+            }
+        }
+    }, 0)
+}
+
+// ==================== END v2.0: Voice Streaming ====================
+
+// ==================== IDENTITY BACKUP ====================
+
+/// Build backup contents from the JNI inputs
+///
+/// contacts_json: [{"contactId": "...", "card": {ContactCard JSON}}]
+/// ratchet_states_json: [{"contactId": "...", "state": "base64"}]
+/// Trust records come from the key registry when it is initialized.
+fn backup_contents_from_json(
+    seed_phrase: &str,
+    contacts_json: &str,
+    ratchet_states_json: &str,
+) -> Result<crate::crypto::backup::BackupContents, String> {
+    use crate::crypto::backup::{BackupContact, BackupContents, BackupRatchetState};
+
+    let mut contents = BackupContents::new(seed_phrase).map_err(|e| e.to_string())?;
+    let contacts: Vec<serde_json::Value> = serde_json::from_str(contacts_json).map_err(|e| e.to_string())?;
+    let registry = KEY_REGISTRY.get().map(|registry| registry.lock().unwrap());
+    for contact in contacts {
+        let contact_id = contact["contactId"].as_str().ok_or("contact missing contactId")?;
+        let card = crate::protocol::contact::ContactCard::from_json(&contact["card"].to_string()).map_err(|e| e.to_string())?;
+        let trust = registry.as_ref().and_then(|registry| registry.record(contact_id).cloned());
+        contents.contacts.push(BackupContact::new(contact_id, &card, trust).map_err(|e| e.to_string())?);
+    }
+
+    let ratchet_states: Vec<serde_json::Value> = serde_json::from_str(ratchet_states_json).map_err(|e| e.to_string())?;
+    for entry in ratchet_states {
+        let contact_id = entry["contactId"].as_str().ok_or("ratchet state missing contactId")?;
+        let state = BASE64.decode(entry["state"].as_str().ok_or("ratchet state missing state")?)
+            .map_err(|e| e.to_string())?;
+        contents.ratchet_states.push(BackupRatchetState { contact_id: contact_id.to_string(), state });
+    }
+    Ok(contents)
 }
 
-/// Stop the contact exchange endpoint
+/// Export an encrypted identity backup (seed phrase, contacts, ratchet state)
+/// seed_phrase: KeyManager's 12-word wallet seed phrase as UTF-8 (zeroed by the caller)
+/// Returns the backup blob (Argon2id + XChaCha20-Poly1305)
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_stopFriendRequestServer(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_exportIdentityBackup(
     mut env: JNIEnv,
     _class: JClass,
-) {
+    seed_phrase: JByteArray,
+    passphrase: JString,
+    contacts_json: JString,
+    ratchet_states_json: JString,
+) -> jbyteArray {
     catch_panic!(env, {
-        log::info!("Stopping contact exchange endpoint");
+        let (phrase_vec, passphrase_str, contacts_str, ratchets_str) = match (
+            jbytearray_to_vec(&mut env, seed_phrase),
+            jstring_to_string(&mut env, passphrase),
+            jstring_to_string(&mut env, contacts_json),
+            jstring_to_string(&mut env, ratchet_states_json),
+        ) {
+            (Ok(s), Ok(p), Ok(c), Ok(r)) => (Zeroizing::new(s), Zeroizing::new(p), c, r),
+            _ => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid backup arguments");
+                return std::ptr::null_mut();
+            }
+        };
+        let Ok(phrase) = std::str::from_utf8(&phrase_vec) else {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", "Seed phrase must be UTF-8");
+            return std::ptr::null_mut();
+        };
 
-        // Stop endpoint in background task
-        std::thread::spawn(|| {
-            let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
-            rt.block_on(async {
-                let endpoint = crate::network::friend_request_server::get_endpoint().await;
-                endpoint.stop().await;
-                log::info!("Contact exchange endpoint stopped");
-            });
+        let result = backup_contents_from_json(phrase, &contacts_str, &ratchets_str).and_then(|contents| {
+            crate::crypto::backup::create_backup(&contents, &passphrase_str).map_err(|e| e.to_string())
         });
 
-    }, ())
+        match result {
+            Ok(blob) => match vec_to_jbytearray(&mut env, &blob) {
+                Ok(array) => array.into_raw(),
+                Err(e) => {
+                    let _ = env.throw_new("java/lang/RuntimeException", e);
+                    std::ptr::null_mut()
+                }
+            },
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Backup export failed: {}", e));
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
 }
 
-/// Store encrypted contact card to be served at GET /contact-card
+/// Fixed slots of the importIdentityBackup result (mirrored in IdentityBackup.kt)
+const RESTORED_FIXED_SLOTS: usize = 15;
+
+/// Restore an identity backup and re-derive every key from its seed phrase
+/// Trust records are reinstated in the key registry when it is initialized.
+/// Returns byte[][] so every secret can be zeroed by the caller:
+/// [0] seed phrase (UTF-8), [1] BIP39 seed (64),
+/// [2..=3] signing private/public, [4..=5] X25519 private/public, [6..=7] Kyber public/secret,
+/// [8..=9] messaging .onion private/public, [10..=11] friend request .onion private/public,
+/// [12..=13] voice .onion private/public,
+/// [14] metadata JSON {"contacts": [{"contactId", "card"}], "ratchetStates": ["contactId", ...], "createdAt"},
+/// [15..] ratchet states in the order of "ratchetStates"
+/// Returns null on a wrong passphrase or corrupted backup
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_serveContactCard(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_importIdentityBackup(
     mut env: JNIEnv,
     _class: JClass,
-    encrypted_card: JByteArray,
-    card_length: jint,
-    cid: JString,
-) {
+    backup: JByteArray,
+    passphrase: JString,
+) -> jobjectArray {
     catch_panic!(env, {
-        let card_bytes = match jbytearray_to_vec(&mut env, encrypted_card) {
-            Ok(b) => b,
-            Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", e);
-                return;
+        let (blob, passphrase_str) = match (jbytearray_to_vec(&mut env, backup), jstring_to_string(&mut env, passphrase)) {
+            (Ok(b), Ok(p)) => (b, Zeroizing::new(p)),
+            _ => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid backup arguments");
+                return std::ptr::null_mut();
             }
         };
 
-        let cid_str = match jstring_to_string(&mut env, cid) {
-            Ok(s) => s,
+        let restored = match crate::crypto::backup::restore_backup(&blob, &passphrase_str) {
+            Ok(restored) => restored,
             Err(e) => {
-                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
-                return;
+                log::warn!("Backup import failed: {}", e);
+                return std::ptr::null_mut();
+            }
+        };
+        let (phrase, bip39_seed) = match (restored.contents.seed_phrase(), restored.contents.bip39_seed()) {
+            (Ok(phrase), Ok(seed)) => (phrase, seed),
+            _ => {
+                log::warn!("Backup import failed: invalid seed phrase");
+                return std::ptr::null_mut();
             }
         };
 
-        log::info!("Storing encrypted contact card (CID: {}, length: {})", cid_str, card_length);
+        let mut contacts = Vec::with_capacity(restored.contents.contacts.len());
+        for contact in &restored.contents.contacts {
+            let card = match contact.card().ok().and_then(|card| card.to_json().ok()) {
+                Some(card) => card,
+                None => continue,
+            };
+            if let (Some(trust), Some(registry)) = (contact.trust.clone(), KEY_REGISTRY.get()) {
+                if let Err(e) = registry.lock().unwrap().restore(trust) {
+                    log::warn!("Failed to restore trust record for {}: {}", contact.contact_id, e);
+                }
+            }
+            contacts.push(serde_json::json!({
+                "contactId": contact.contact_id,
+                "card": serde_json::from_str::<serde_json::Value>(&card).unwrap_or_default(),
+            }));
+        }
+        let ratchet_states = &restored.contents.ratchet_states;
+        let metadata = serde_json::json!({
+            "contacts": contacts,
+            "ratchetStates": ratchet_states.iter().map(|entry| entry.contact_id.as_str()).collect::<Vec<_>>(),
+            "createdAt": restored.contents.created_at,
+        })
+        .to_string();
+
+        let keys = &restored.keys;
+        let mut slots: Vec<&[u8]> = vec![
+            phrase.as_bytes(),
+            bip39_seed.as_slice(),
+            &keys.signing.private_key,
+            &keys.signing.public_key,
+            &keys.x25519.private_key,
+            &keys.x25519.public_key,
+            &keys.kyber_public,
+            &keys.kyber_secret,
+            &keys.hidden_service.private_key,
+            &keys.hidden_service.public_key,
+            &keys.friend_request.private_key,
+            &keys.friend_request.public_key,
+            &keys.voice_service.private_key,
+            &keys.voice_service.public_key,
+            metadata.as_bytes(),
+        ];
+        debug_assert_eq!(slots.len(), RESTORED_FIXED_SLOTS);
+        slots.extend(ratchet_states.iter().map(|entry| entry.state.as_slice()));
 
-        // Store card in endpoint state
-        std::thread::spawn(move || {
-            let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
-            rt.block_on(async {
-                let endpoint = crate::network::friend_request_server::get_endpoint().await;
-                endpoint.set_contact_card(card_bytes, cid_str).await;
-                log::info!("Contact card stored in contact exchange endpoint");
-            });
-        });
+        let byte_array_class = env.find_class("[B").unwrap();
+        let array = match env.new_object_array(slots.len() as i32, byte_array_class, JObject::null()) {
+            Ok(arr) => arr,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("{}", e));
+                return std::ptr::null_mut();
+            }
+        };
+        for (index, slot) in slots.iter().enumerate() {
+            let element = match vec_to_jbytearray(&mut env, slot) {
+                Ok(arr) => arr,
+                Err(e) => {
+                    let _ = env.throw_new("java/lang/RuntimeException", e);
+                    return std::ptr::null_mut();
+                }
+            };
+            if let Err(e) = env.set_object_array_element(&array, index as i32, &element) {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("{}", e));
+                return std::ptr::null_mut();
+            }
+            let _ = env.delete_local_ref(element);
+        }
 
-    }, ())
+        array.into_raw()
+    }, std::ptr::null_mut())
 }
 
-/// Serve contact list on the contact exchange endpoint (v5 architecture)
+// ==================== SOCIAL RECOVERY ====================
+
+/// Recovery in progress on this (new) device
+static RECOVERY_SESSION: Lazy<Mutex<Option<crate::crypto::social_recovery::RecoverySession>>> =
+    Lazy::new(|| Mutex::new(None));
+
+/// Start the RECOVERY_SHARE channel (separate from MESSAGE so shares are never decrypted as chain messages)
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_serveContactList(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_startRecoveryListener(
     mut env: JNIEnv,
     _class: JClass,
-    cid: JString,
-    encrypted_list: JByteArray,
-    list_length: jint,
-) {
+) -> jboolean {
     catch_panic!(env, {
-        let cid_str = match jstring_to_string(&mut env, cid) {
-            Ok(s) => s,
-            Err(e) => {
-                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
-                return;
-            }
-        };
-
-        let list_bytes = match jbytearray_to_vec(&mut env, encrypted_list) {
-            Ok(b) => b,
-            Err(e) => {
-                let _ = env.throw_new("java/lang/RuntimeException", e);
-                return;
-            }
-        };
-
-        log::info!("Storing encrypted contact list (CID: {}, length: {})", cid_str, list_length);
+        let (tx, rx) = mpsc::unbounded_channel::<Vec<u8>>();
+        let _ = GLOBAL_RECOVERY_RECEIVER.set(Arc::new(Mutex::new(rx)));
 
-        // Store contact list in endpoint state
-        std::thread::spawn(move || {
-            let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
-            rt.block_on(async {
-                let endpoint = crate::network::friend_request_server::get_endpoint().await;
-                endpoint.set_contact_list(cid_str, list_bytes).await;
-                log::info!("Contact list stored in contact exchange endpoint");
-            });
-        });
+        if crate::network::tor::RECOVERY_TX.set(Arc::new(std::sync::Mutex::new(tx))).is_err() {
+            log::error!("Recovery channel already initialized");
+            return 0;
+        }
 
-    }, ())
+        log::info!("Recovery listener channel initialized successfully");
+        1
+    }, 0)
 }
 
-/// Poll for incoming friend requests (non-blocking)
-/// Returns raw encrypted bytes (0x07 or 0x08 wire protocol messages)
-/// Kotlin will handle decryption based on message type
+/// Poll for an incoming RECOVERY_SHARE (non-blocking)
+/// Returns [sender_x25519:32][kind][body], or null if none is waiting
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_pollFriendRequest(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_pollRecoveryMessage(
     mut env: JNIEnv,
     _class: JClass,
 ) -> jbyteArray {
     catch_panic!(env, {
-        if let Some(receiver) = GLOBAL_FRIEND_REQUEST_RECEIVER.get() {
-            let mut rx = receiver.lock().unwrap();
-
-            // Try to receive without blocking
-            match rx.try_recv() {
-                Ok(friend_request_bytes) => {
-                    log::info!("Polled friend request: {} bytes", friend_request_bytes.len());
-                    match vec_to_jbytearray(&mut env, &friend_request_bytes) {
-                        Ok(array) => array.into_raw(),
-                        Err(_) => std::ptr::null_mut(),
-                    }
-                }
-                Err(_) => {
-                    // No friend request available
-                    std::ptr::null_mut()
-                }
-            }
-        } else {
-            // Listener not started
-            std::ptr::null_mut()
-        }
+        let Some(receiver) = GLOBAL_RECOVERY_RECEIVER.get() else {
+            return std::ptr::null_mut();
+        };
+        let message = match receiver.lock().unwrap().try_recv() {
+            Ok(message) => message,
+            Err(_) => return std::ptr::null_mut(),
+        };
+        vec_to_jbytearray(&mut env, &message)
+            .map(|a| a.into_raw())
+            .unwrap_or(std::ptr::null_mut())
     }, std::ptr::null_mut())
 }
 
-/// Poll for friend request responses (non-blocking)
-/// Returns JSON string with response data, or null if no responses
+/// Our X25519 and Kyber secret keys from the KeyManager
+fn local_hybrid_secrets(env: &mut JNIEnv) -> Result<([u8; 32], Vec<u8>), String> {
+    let context = env
+        .call_static_method("android/app/ActivityThread", "currentApplication", "()Landroid/app/Application;", &[])
+        .and_then(|ctx| ctx.l())
+        .map_err(|e| format!("Failed to get context: {}", e))?;
+    let key_manager = crate::ffi::keystore::get_key_manager(env, &context).map_err(|e| e.to_string())?;
+
+    let mut x25519_vec = crate::ffi::keystore::get_encryption_private_key(env, &key_manager).map_err(|e| e.to_string())?;
+    let x25519: Result<[u8; 32], _> = x25519_vec.as_slice().try_into();
+    x25519_vec.zeroize();
+    let x25519 = x25519.map_err(|_| "Invalid X25519 key length".to_string())?;
+    let kyber = crate::ffi::keystore::get_kyber_secret_key(env, &key_manager).map_err(|e| e.to_string())?;
+    Ok((x25519, kyber))
+}
+
+/// Split the wallet seed phrase into k-of-n shares, one sealed DEPOSIT per guardian
+/// seed_phrase: KeyManager's 12-word seed phrase as UTF-8 (zeroed by the caller)
+/// guardians_json: [{"contactId": "...", "x25519PublicKey": "base64", "kyberPublicKey": "base64"}]
+/// Returns JSON: [{"contactId": "...", "message": "base64"}] (send each as MSG_TYPE_RECOVERY_SHARE)
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_pollFriendResponse(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_createRecoveryShares(
     mut env: JNIEnv,
     _class: JClass,
+    seed_phrase: JByteArray,
+    threshold: jint,
+    guardians_json: JString,
 ) -> jstring {
     catch_panic!(env, {
-        log::debug!("Polling for friend request responses");
+        use crate::crypto::social_recovery::{seal_share, split_seed_phrase, RecoveryMessage};
 
-        // Poll for response in blocking fashion
-        let result = std::thread::spawn(|| {
-            let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
-            rt.block_on(async {
-                let endpoint = crate::network::friend_request_server::get_endpoint().await;
-                endpoint.poll_response().await
-            })
-        }).join();
+        let (phrase_vec, guardians_str) = match (jbytearray_to_vec(&mut env, seed_phrase), jstring_to_string(&mut env, guardians_json)) {
+            (Ok(s), Ok(g)) => (Zeroizing::new(s), g),
+            _ => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid recovery arguments");
+                return std::ptr::null_mut();
+            }
+        };
+        let Ok(phrase) = std::str::from_utf8(&phrase_vec) else {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", "Seed phrase must be UTF-8");
+            return std::ptr::null_mut();
+        };
 
-        match result {
-            Ok(Some(response)) => {
-                // Convert to JSON
-                let json = format!(
-                    r#"{{"approved":{},"encryptedCard":"{}","timestamp":{}}}"#,
-                    response.approved,
-                    base64::encode(&response.encrypted_card),
-                    response.timestamp
-                );
+        let result = (|| -> Result<serde_json::Value, String> {
+            let guardians: Vec<serde_json::Value> = serde_json::from_str(&guardians_str).map_err(|e| e.to_string())?;
+            let count = u8::try_from(guardians.len()).map_err(|_| "Too many guardians".to_string())?;
+            let threshold = u8::try_from(threshold).map_err(|_| "Invalid threshold".to_string())?;
+            let shares = split_seed_phrase(phrase, threshold, count).map_err(|e| e.to_string())?;
 
-                match string_to_jstring(&mut env, &json) {
-                    Ok(s) => s.into_raw(),
-                    Err(e) => {
-                        let _ = env.throw_new("java/lang/RuntimeException", e);
-                        std::ptr::null_mut()
-                    }
-                }
+            let mut messages = Vec::with_capacity(guardians.len());
+            for (guardian, share) in guardians.iter().zip(&shares) {
+                let contact_id = guardian["contactId"].as_str().ok_or("guardian missing contactId")?;
+                let decode = |field: &str| -> Result<Vec<u8>, String> {
+                    BASE64.decode(guardian[field].as_str().ok_or(format!("guardian missing {}", field))?)
+                        .map_err(|e| e.to_string())
+                };
+                let public = crate::crypto::pqc::HybridKEMPublicKey {
+                    x25519_public: decode("x25519PublicKey")?.try_into().map_err(|_| "Invalid X25519 key length")?,
+                    kyber_public: decode("kyberPublicKey")?.try_into().map_err(|_| "Invalid Kyber key length")?,
+                };
+                let sealed = seal_share(share, &public).map_err(|e| e.to_string())?;
+                let message = RecoveryMessage::Deposit(sealed).to_bytes().map_err(|e| e.to_string())?;
+                messages.push(serde_json::json!({ "contactId": contact_id, "message": BASE64.encode(message) }));
+            }
+            Ok(serde_json::Value::Array(messages))
+        })();
+
+        match result {
+            Ok(json) => match string_to_jstring(&mut env, &json.to_string()) {
+                Ok(s) => s.into_raw(),
+                Err(_) => std::ptr::null_mut(),
+            },
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to create recovery shares: {}", e));
+                std::ptr::null_mut()
             }
-            _ => std::ptr::null_mut()
         }
     }, std::ptr::null_mut())
 }
 
-/// Make HTTP GET request via Tor SOCKS5 proxy
-/// Returns response body or null on error
+/// Guardian side: open a DEPOSIT sealed to our keys
+/// Returns the share bytes to store (encrypted at rest by the app), or null if invalid
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_httpGetViaTor(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_openRecoveryDeposit(
     mut env: JNIEnv,
     _class: JClass,
-    url: JString,
-) -> jstring {
+    message: JByteArray,
+) -> jbyteArray {
     catch_panic!(env, {
-        let url_str = match jstring_to_string(&mut env, url) {
-            Ok(s) => s,
+        use crate::crypto::social_recovery::{open_share, RecoveryMessage};
+
+        let message_vec = match jbytearray_to_vec(&mut env, message) {
+            Ok(v) => v,
             Err(e) => {
                 let _ = env.throw_new("java/lang/IllegalArgumentException", e);
                 return std::ptr::null_mut();
             }
         };
+        let Ok(RecoveryMessage::Deposit(sealed)) = RecoveryMessage::from_bytes(&message_vec) else {
+            log::warn!("Not a recovery deposit");
+            return std::ptr::null_mut();
+        };
 
-        log::info!("HTTP GET via Tor: {}", url_str);
-
-        // Create SOCKS5 client and make request
-        let client = crate::network::Socks5Client::tor_default();
+        let (mut x25519, mut kyber) = match local_hybrid_secrets(&mut env) {
+            Ok(keys) => keys,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                return std::ptr::null_mut();
+            }
+        };
+        let share = open_share(&sealed, &x25519, &kyber).and_then(|share| share.to_bytes());
+        x25519.zeroize();
+        kyber.zeroize();
 
-        match client.http_get(&url_str) {
-            Ok(response) => {
-                // Extract body from response
-                if let Some(body) = crate::network::socks5_client::extract_http_body(&response) {
-                    match string_to_jstring(&mut env, &body) {
-                        Ok(s) => {
-                            log::info!("HTTP GET successful ({} bytes)", body.len());
-                            s.into_raw()
-                        }
-                        Err(e) => {
-                            let _ = env.throw_new("java/lang/RuntimeException", e);
-                            std::ptr::null_mut()
-                        }
-                    }
-                } else {
-                    // Return full response if can't extract body
-                    match string_to_jstring(&mut env, &response) {
-                        Ok(s) => s.into_raw(),
-                        Err(e) => {
-                            let _ = env.throw_new("java/lang/RuntimeException", e);
-                            std::ptr::null_mut()
-                        }
-                    }
-                }
+        match share {
+            Ok(mut bytes) => {
+                let array = vec_to_jbytearray(&mut env, &bytes);
+                bytes.zeroize();
+                array.map(|a| a.into_raw()).unwrap_or(std::ptr::null_mut())
             }
-            Err(e) => {
-                log::error!("HTTP GET via Tor failed: {}", e);
-                let error_msg = format!("HTTP GET failed: {}", e);
-                let _ = env.throw_new("java/io/IOException", &error_msg);
+            Err(e) => {
+                log::warn!("Failed to open recovery deposit: {}", e);
                 std::ptr::null_mut()
             }
         }
     }, std::ptr::null_mut())
 }
 
-/// Make HTTP POST request via Tor SOCKS5 proxy
-/// Returns response body or null on error
+/// Guardian side: answer a REQUEST with a RELEASE of the held share
+/// Only call after confirming the request with the owner out of band
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_httpPostViaTor(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_releaseRecoveryShare(
     mut env: JNIEnv,
     _class: JClass,
-    url: JString,
-    body: JString,
-) -> jstring {
+    share_bytes: JByteArray,
+    request_message: JByteArray,
+) -> jbyteArray {
     catch_panic!(env, {
-        let url_str = match jstring_to_string(&mut env, url) {
-            Ok(s) => s,
-            Err(e) => {
-                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+        use crate::crypto::social_recovery::{release_share, RecoveryMessage, Share};
+
+        let (mut share_vec, request_vec) = match (jbytearray_to_vec(&mut env, share_bytes), jbytearray_to_vec(&mut env, request_message)) {
+            (Ok(s), Ok(r)) => (s, r),
+            _ => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "Invalid recovery arguments");
                 return std::ptr::null_mut();
             }
         };
+        let share = Share::from_bytes(&share_vec);
+        share_vec.zeroize();
 
-        let body_str = match jstring_to_string(&mut env, body) {
-            Ok(s) => s,
-            Err(e) => {
-                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+        let release = match (share, RecoveryMessage::from_bytes(&request_vec)) {
+            (Ok(share), Ok(RecoveryMessage::Request(request))) => release_share(&share, &request)
+                .and_then(|sealed| RecoveryMessage::Release(sealed).to_bytes()),
+            _ => {
+                log::warn!("Invalid recovery share or request");
                 return std::ptr::null_mut();
             }
         };
 
-        log::info!("HTTP POST via Tor: {} (body length: {})", url_str, body_str.len());
-
-        // Create SOCKS5 client and make request
-        let client = crate::network::Socks5Client::tor_default();
-
-        // Use application/json as default content type
-        match client.http_post(&url_str, &body_str, "application/json") {
-            Ok(response) => {
-                // Extract body from response
-                if let Some(body) = crate::network::socks5_client::extract_http_body(&response) {
-                    match string_to_jstring(&mut env, &body) {
-                        Ok(s) => {
-                            log::info!("HTTP POST successful ({} bytes)", body.len());
-                            s.into_raw()
-                        }
-                        Err(e) => {
-                            let _ = env.throw_new("java/lang/RuntimeException", e);
-                            std::ptr::null_mut()
-                        }
-                    }
-                } else {
-                    // Return full response if can't extract body
-                    match string_to_jstring(&mut env, &response) {
-                        Ok(s) => s.into_raw(),
-                        Err(e) => {
-                            let _ = env.throw_new("java/lang/RuntimeException", e);
-                            std::ptr::null_mut()
-                        }
-                    }
-                }
-            }
+        match release {
+            Ok(bytes) => vec_to_jbytearray(&mut env, &bytes).map(|a| a.into_raw()).unwrap_or(std::ptr::null_mut()),
             Err(e) => {
-                log::error!("HTTP POST via Tor failed: {}", e);
-                let error_msg = format!("HTTP POST failed: {}", e);
-                let _ = env.throw_new("java/io/IOException", &error_msg);
+                log::warn!("Failed to release recovery share: {}", e);
                 std::ptr::null_mut()
             }
         }
     }, std::ptr::null_mut())
 }
 
-// ==================== END v2.0: Friend Request System ====================
-
-// ==================== v2.0: Voice Streaming ====================
-
-/// Register voice packet callback handler (v2.0)
-/// Must be called before startVoiceStreamingServer
+/// New device: start social recovery (replaces any recovery in progress)
+/// Returns the REQUEST message to send to every guardian
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_setVoicePacketCallback(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_startSocialRecovery(
     mut env: JNIEnv,
     _class: JClass,
-    callback: JObject,
-) {
+) -> jbyteArray {
     catch_panic!(env, {
-        // Create global reference to callback object
-        let global_callback = env.new_global_ref(callback)
-            .expect("Failed to create global reference to voice callback");
+        use crate::crypto::social_recovery::{RecoveryMessage, RecoverySession};
 
-        // Store in global
-        let callback_storage = GLOBAL_VOICE_CALLBACK.get_or_init(|| Mutex::new(None));
-        *callback_storage.lock().unwrap() = Some(global_callback);
+        let session = match RecoverySession::new() {
+            Ok(session) => session,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to start recovery: {}", e));
+                return std::ptr::null_mut();
+            }
+        };
+        let request = RecoveryMessage::Request(session.request()).to_bytes();
+        *RECOVERY_SESSION.lock().unwrap() = Some(session);
 
-        log::info!("Voice packet callback registered");
-    }, ())
+        match request {
+            Ok(bytes) => vec_to_jbytearray(&mut env, &bytes).map(|a| a.into_raw()).unwrap_or(std::ptr::null_mut()),
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Failed to build recovery request: {}", e));
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
 }
 
-/// Register voice signaling callback handler (v2.0)
-/// Handles incoming signaling messages (CALL_OFFER, CALL_ANSWER, etc) over voice onion HTTP
-/// Must be called before startVoiceStreamingServer
+/// New device: add a guardian's RELEASE
+/// Returns the seed phrase (UTF-8) once enough shares arrived (recovery ends), else null
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_setVoiceSignalingCallback(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_addRecoveryRelease(
     mut env: JNIEnv,
     _class: JClass,
-    callback: JObject,
-) {
+    sender_x25519: JByteArray,
+    message: JByteArray,
+) -> jbyteArray {
     catch_panic!(env, {
-        // Create global reference to callback object
-        let global_callback = env.new_global_ref(callback)
-            .expect("Failed to create global reference to voice signaling callback");
+        use crate::crypto::social_recovery::RecoveryMessage;
 
-        // Store in global
-        let callback_storage = GLOBAL_VOICE_SIGNALING_CALLBACK.get_or_init(|| Mutex::new(None));
-        *callback_storage.lock().unwrap() = Some(global_callback);
+        let sender: [u8; 32] = match jbytearray_to_vec(&mut env, sender_x25519).map(|s| s.try_into()) {
+            Ok(Ok(s)) => s,
+            _ => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "Sender X25519 key must be 32 bytes");
+                return std::ptr::null_mut();
+            }
+        };
+        let message_vec = match jbytearray_to_vec(&mut env, message) {
+            Ok(v) => v,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
+        let Ok(RecoveryMessage::Release(sealed)) = RecoveryMessage::from_bytes(&message_vec) else {
+            log::warn!("Not a recovery release");
+            return std::ptr::null_mut();
+        };
 
-        log::info!("Voice signaling callback registered");
-    }, ())
+        let mut guard = RECOVERY_SESSION.lock().unwrap();
+        let Some(session) = guard.as_mut() else {
+            let _ = env.throw_new("java/lang/IllegalStateException", "No social recovery in progress");
+            return std::ptr::null_mut();
+        };
+
+        match session.add_release(&sender, &sealed) {
+            Ok(Some(phrase)) => {
+                *guard = None;
+                log::info!("✓ Seed phrase recovered from guardian shares");
+                vec_to_jbytearray(&mut env, phrase.as_bytes())
+                    .map(|a| a.into_raw())
+                    .unwrap_or(std::ptr::null_mut())
+            }
+            Ok(None) => {
+                log::info!("Recovery share accepted ({} received)", session.shares_received());
+                std::ptr::null_mut()
+            }
+            Err(e) => {
+                log::warn!("Rejected recovery release: {}", e);
+                std::ptr::null_mut()
+            }
+        }
+    }, std::ptr::null_mut())
 }
 
-/// Start voice streaming listener on port 9152 (v2.0)
-/// Must be called before accepting or creating voice sessions
+// ==================== KEY HIERARCHY ====================
+
+/// Forget the session key hierarchy; the master seed is fetched again on next use
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_startVoiceStreamingServer(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_clearKeyHierarchy(
     mut env: JNIEnv,
     _class: JClass,
 ) {
     catch_panic!(env, {
-        let voice_listener = get_voice_listener();
-
-        // Get JavaVM for callback invocations (get twice since JavaVM doesn't implement Clone)
-        let jvm_audio = env.get_java_vm().expect("Failed to get JavaVM");
-        let jvm_signaling = env.get_java_vm().expect("Failed to get JavaVM");
-
-        // Start listener in background task
-        GLOBAL_RUNTIME.spawn(async move {
-            // Set audio callback (must be done before starting listener)
-            {
-                let mut listener = voice_listener.lock().await;
-                listener.set_audio_callback(move |call_id: String, packet: crate::audio::voice_streaming::VoicePacket| {
-                // Get JNI environment (attach thread if needed)
-                let mut env = match jvm_audio.attach_current_thread() {
-                    Ok(env) => env,
-                    Err(e) => {
-                        log::error!("Failed to attach thread for voice callback: {}", e);
-                        return;
-                    }
-                };
-
-                // Get callback object
-                let callback_storage = GLOBAL_VOICE_CALLBACK.get().unwrap();
-                let callback_lock = callback_storage.lock().unwrap();
-
-                if let Some(ref callback_ref) = *callback_lock {
-                    let callback_obj = callback_ref.as_obj();
-
-                    // Convert call_id to JString
-                    let call_id_jstring: JString = match env.new_string(&call_id) {
-                        Ok(s) => s,
-                        Err(e) => {
-                            log::error!("Failed to create call_id JString: {}", e);
-                            return;
-                        }
-                    };
-
-                    // Convert audio_data to JByteArray
-                    let audio_data_array: JByteArray = match env.byte_array_from_slice(&packet.audio_data) {
-                        Ok(arr) => arr,
-                        Err(e) => {
-                            log::error!("Failed to create audio data byte array: {}", e);
-                            return;
-                        }
-                    };
-
-                    // Call onVoicePacket(callId: String, sequence: Int, timestamp: Long, circuitIndex: Byte, ptype: Byte, audioData: ByteArray)
-                    let result = env.call_method(
-                        callback_obj,
-                        "onVoicePacket",
-                        "(Ljava/lang/String;IJBB[B)V",
-                        &[
-                            (&call_id_jstring).into(),
-                            (packet.sequence as i32).into(),
-                            (packet.timestamp as i64).into(),
-                            (packet.circuit_index as i8).into(),
-                            (packet.ptype as i8).into(),
-                            (&audio_data_array).into(),
-                        ],
-                    );
-
-                    if let Err(e) = result {
-                        log::error!("Failed to invoke voice packet callback: {}", e);
-                    }
-                } else {
-                    log::warn!("Voice packet received but no callback registered");
-                }
-                });
-
-                // Set signaling callback for HTTP POST messages (CALL_OFFER, CALL_ANSWER, etc)
-                listener.set_signaling_callback(move |sender_pubkey: Vec<u8>, wire_message: Vec<u8>| {
-                    // Get JNI environment (attach thread if needed)
-                    let mut env = match jvm_signaling.attach_current_thread() {
-                        Ok(env) => env,
-                        Err(e) => {
-                            log::error!("Failed to attach thread for signaling callback: {}", e);
-                            return;
-                        }
-                    };
-
-                    // Get callback object
-                    let callback_storage = GLOBAL_VOICE_SIGNALING_CALLBACK.get().unwrap();
-                    let callback_lock = callback_storage.lock().unwrap();
-
-                    if let Some(ref callback_ref) = *callback_lock {
-                        let callback_obj = callback_ref.as_obj();
-
-                        // Convert sender_pubkey to JByteArray
-                        let sender_pubkey_array: JByteArray = match env.byte_array_from_slice(&sender_pubkey) {
-                            Ok(arr) => arr,
-                            Err(e) => {
-                                log::error!("Failed to create sender pubkey byte array: {}", e);
-                                return;
-                            }
-                        };
-
-                        // Convert wire_message to JByteArray
-                        let wire_message_array: JByteArray = match env.byte_array_from_slice(&wire_message) {
-                            Ok(arr) => arr,
-                            Err(e) => {
-                                log::error!("Failed to create wire message byte array: {}", e);
-                                return;
-                            }
-                        };
-
-                        // Call onSignalingMessage(senderPubkey: ByteArray, wireMessage: ByteArray)
-                        let result = env.call_method(
-                            callback_obj,
-                            "onSignalingMessage",
-                            "([B[B)V",
-                            &[
-                                (&sender_pubkey_array).into(),
-                                (&wire_message_array).into(),
-                            ],
-                        );
-
-                        if let Err(e) = result {
-                            log::error!("Failed to invoke voice signaling callback: {}", e);
-                        } else {
-                            log::info!("Voice signaling callback invoked successfully");
-                        }
-                    } else {
-                        log::warn!("Voice signaling message received but no callback registered");
-                    }
-                });
-            } // Drop MutexGuard here
+        crate::ffi::keystore::clear_key_hierarchy();
+        log::info!("Session key hierarchy cleared");
+    }, ())
+}
 
-            // Start listener
-            if let Err(e) = voice_listener.lock().await.start().await {
-                log::error!("Failed to start voice streaming listener: {}", e);
-                return;
-            }
+// ==================== DURESS UNLOCK ====================
 
-            log::info!("Voice streaming listener started on port 9152");
+fn jbytearray_to_unlock_record(env: &mut JNIEnv, record: JByteArray) -> Option<crate::crypto::duress::UnlockRecord> {
+    let bytes = jbytearray_to_vec(env, record).ok()?;
+    match crate::crypto::duress::UnlockRecord::from_bytes(&bytes) {
+        Ok(record) => Some(record),
+        Err(e) => {
+            log::error!("Invalid unlock record: {}", e);
+            None
+        }
+    }
+}
 
-            // Note: start() now spawns its own accept task internally,
-            // so we don't need to do anything else here
-        });
-    }, ())
+/// Destroy the real profile's in-memory key material and ratchet state
+fn wipe_real_profile_state() {
+    crate::ffi::keystore::clear_key_hierarchy();
+    if let Err(e) = crate::crypto::encryption::wipe_ratchet_state() {
+        log::error!("Failed to wipe ratchet state: {}", e);
+    }
+    STORED_PINGS.lock().unwrap().clear();
+    *RECOVERY_SESSION.lock().unwrap() = None;
 }
 
-/// Create outgoing voice session to peer's voice .onion (v2.0)
-/// Connects to peer's voice hidden service on port 9152 with multiple circuits
-/// @param num_circuits Number of parallel Tor circuits to create (typically 3)
+/// Create an unlock record for the real password (no duress password)
+/// kdf_descriptor: 15-byte descriptor from calibrateKdf
+/// Returns the record bytes to store
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_createVoiceSession(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_createUnlockRecord(
     mut env: JNIEnv,
     _class: JClass,
-    call_id: JString,
-    peer_voice_onion: JString,
-    num_circuits: jint,
-) -> jboolean {
+    password: JString,
+    kdf_descriptor: JByteArray,
+) -> jbyteArray {
     catch_panic!(env, {
-        let call_id_str = match jstring_to_string(&mut env, call_id) {
-            Ok(s) => s,
+        let password_str = match jstring_to_string(&mut env, password) {
+            Ok(s) => Zeroizing::new(s),
             Err(e) => {
                 let _ = env.throw_new("java/lang/IllegalArgumentException", e);
-                return 0;
+                return std::ptr::null_mut();
             }
         };
-
-        let peer_onion_str = match jstring_to_string(&mut env, peer_voice_onion) {
-            Ok(s) => s,
+        let kdf = match jbytearray_to_vec(&mut env, kdf_descriptor)
+            .and_then(|v| crate::crypto::hashing::KdfParams::from_bytes(&v).map_err(|e| e.to_string()))
+        {
+            Ok(p) => p,
             Err(e) => {
                 let _ = env.throw_new("java/lang/IllegalArgumentException", e);
-                return 0;
+                return std::ptr::null_mut();
             }
         };
 
-        let num_circuits_usize = num_circuits as usize;
-        log::info!("Creating voice session with {} circuits for call: {} to {}",
-                   num_circuits_usize, call_id_str, peer_onion_str);
-
-        let voice_listener = get_voice_listener();
-
-        // Create session using global runtime
-        let result = GLOBAL_RUNTIME.block_on(async {
-            let mut listener = voice_listener.lock().await;
-            listener.create_session(call_id_str.clone(), &peer_onion_str, num_circuits_usize).await
-        });
-
-        match result {
-            Ok(_) => {
-                log::info!("Voice session with {} circuits created for call: {}", num_circuits_usize, call_id_str);
-                1
-            }
+        match crate::crypto::duress::UnlockRecord::new(&password_str, kdf) {
+            Ok(record) => vec_to_jbytearray(&mut env, &record.to_bytes())
+                .map(|a| a.into_raw())
+                .unwrap_or(std::ptr::null_mut()),
             Err(e) => {
-                log::error!("Failed to create voice session: {}", e);
-                let error_msg = format!("Voice session creation failed: {}", e);
-                let _ = env.throw_new("java/io/IOException", &error_msg);
-                0
+                let _ = env.throw_new("java/lang/RuntimeException", format!("{}", e));
+                std::ptr::null_mut()
             }
         }
-    }, 0)
+    }, std::ptr::null_mut())
 }
 
-/// Send audio packet to peer in active voice session on specific circuit (v2.0)
-/// Audio data should be Opus-encoded
-/// @param circuit_index Which circuit to use (0 to num_circuits-1)
-/// @param ptype Packet type (0x01=AUDIO, 0x02=CONTROL)
+/// Set or replace the duress password
+/// Returns the updated record, or null if the real password is wrong or both passwords match
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_sendAudioPacket(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_setDuressPassword(
     mut env: JNIEnv,
     _class: JClass,
-    call_id: JString,
-    sequence: jint,
-    timestamp: jlong,
-    audio_data: JByteArray,
-    circuit_index: jint,
-    ptype: jint,
-) -> jboolean {
+    record: JByteArray,
+    real_password: JString,
+    duress_password: JString,
+    wipe: jboolean,
+) -> jbyteArray {
     catch_panic!(env, {
-        let call_id_str = match jstring_to_string(&mut env, call_id) {
-            Ok(s) => s,
+        let Some(mut unlock_record) = jbytearray_to_unlock_record(&mut env, record) else {
+            return std::ptr::null_mut();
+        };
+        let (real_str, duress_str) = match (
+            jstring_to_string(&mut env, real_password),
+            jstring_to_string(&mut env, duress_password),
+        ) {
+            (Ok(r), Ok(d)) => (Zeroizing::new(r), Zeroizing::new(d)),
+            _ => return std::ptr::null_mut(),
+        };
+
+        match unlock_record.set_duress_password(&real_str, &duress_str, wipe != 0) {
+            Ok(()) => vec_to_jbytearray(&mut env, &unlock_record.to_bytes())
+                .map(|a| a.into_raw())
+                .unwrap_or(std::ptr::null_mut()),
             Err(e) => {
-                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
-                return 0;
+                log::warn!("Duress password not set: {}", e);
+                std::ptr::null_mut()
             }
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Remove the duress password
+/// Returns the updated record, or null if the real password is wrong
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_clearDuressPassword(
+    mut env: JNIEnv,
+    _class: JClass,
+    record: JByteArray,
+    real_password: JString,
+) -> jbyteArray {
+    catch_panic!(env, {
+        let Some(mut unlock_record) = jbytearray_to_unlock_record(&mut env, record) else {
+            return std::ptr::null_mut();
+        };
+        let real_str = match jstring_to_string(&mut env, real_password) {
+            Ok(s) => Zeroizing::new(s),
+            Err(_) => return std::ptr::null_mut(),
         };
 
-        let audio_bytes = match jbytearray_to_vec(&mut env, audio_data) {
-            Ok(v) => v,
+        match unlock_record.clear_duress_password(&real_str) {
+            Ok(()) => vec_to_jbytearray(&mut env, &unlock_record.to_bytes())
+                .map(|a| a.into_raw())
+                .unwrap_or(std::ptr::null_mut()),
             Err(e) => {
-                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
-                return 0;
+                log::warn!("Duress password not cleared: {}", e);
+                std::ptr::null_mut()
             }
+        }
+    }, std::ptr::null_mut())
+}
+
+/// Unlock with the real or duress password
+/// Takes the same time whichever password (or a wrong one) is entered.
+/// On Wiped, the real profile's in-memory keys and ratchet journal are destroyed here;
+/// the app must clear its key store and delete the real database.
+/// The decoy key opens the decoy database (zeros on Real: the real key is seed-derived).
+/// Returns [outcome: 1 (0=Real, 1=Decoy, 2=Wiped)][decoy_key: 32][record], or null if wrong
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_unlockWithPassword(
+    mut env: JNIEnv,
+    _class: JClass,
+    record: JByteArray,
+    password: JString,
+) -> jbyteArray {
+    catch_panic!(env, {
+        use crate::crypto::duress::UnlockOutcome;
+
+        let Some(mut unlock_record) = jbytearray_to_unlock_record(&mut env, record) else {
+            return std::ptr::null_mut();
+        };
+        let password_str = match jstring_to_string(&mut env, password) {
+            Ok(s) => Zeroizing::new(s),
+            Err(_) => return std::ptr::null_mut(),
         };
 
-        let packet = VoicePacket {
-            sequence: sequence as u32,
-            timestamp: timestamp as u64,
-            audio_data: audio_bytes,
-            circuit_index: circuit_index as u8,
-            ptype: ptype as u8,
-            redundant_audio_data: Vec::new(),  // Phase 3: Will be populated by sender task
-            redundant_sequence: 0,              // Phase 3: Will be populated by sender task
+        let unlocked = match unlock_record.unlock(&password_str) {
+            Ok(unlocked) => unlocked,
+            Err(_) => return std::ptr::null_mut(),
         };
 
-        let voice_listener = get_voice_listener();
-        let listener = voice_listener.blocking_lock();
+        if unlocked.outcome == UnlockOutcome::Wiped {
+            wipe_real_profile_state();
+        }
 
-        match listener.send_audio(&call_id_str, circuit_index as usize, packet) {
-            Ok(_) => 1,
-            Err(e) => {
-                log::warn!("Failed to send audio packet for call {} on circuit {}: {}", call_id_str, circuit_index, e);
-                0
-            }
+        let mut out = Zeroizing::new(Vec::with_capacity(1 + 32 + crate::crypto::duress::UNLOCK_RECORD_LEN));
+        out.push(unlocked.outcome.as_u8());
+        out.extend_from_slice(unlocked.decoy_key.as_slice());
+        out.extend_from_slice(&unlock_record.to_bytes());
+
+        vec_to_jbytearray(&mut env, &out)
+            .map(|a| a.into_raw())
+            .unwrap_or(std::ptr::null_mut())
+    }, std::ptr::null_mut())
+}
+
+// ==================== SESSION HEALING ====================
+
+/// Our Ed25519 public and private keys from the KeyManager
+fn local_signing_keys(env: &mut JNIEnv) -> Result<([u8; 32], Vec<u8>), String> {
+    let context = env
+        .call_static_method("android/app/ActivityThread", "currentApplication", "()Landroid/app/Application;", &[])
+        .and_then(|ctx| ctx.l())
+        .map_err(|e| format!("Failed to get context: {}", e))?;
+    let key_manager = crate::ffi::keystore::get_key_manager(env, &context).map_err(|e| e.to_string())?;
+
+    let public = crate::ffi::keystore::get_signing_public_key(env, &key_manager).map_err(|e| e.to_string())?;
+    let public: [u8; 32] = public.try_into().map_err(|_| "Invalid Ed25519 key length".to_string())?;
+    let private = crate::ffi::keystore::get_signing_private_key(env, &key_manager).map_err(|e| e.to_string())?;
+    Ok((public, private))
+}
+
+/// Restore a contact's healer, or start one from the current root and epoch
+fn jni_session_healer(
+    env: &mut JNIEnv,
+    healer_state: JByteArray,
+    root_key: JByteArray,
+    epoch: jint,
+) -> Result<crate::crypto::SessionHealer, String> {
+    if !healer_state.is_null() {
+        let mut state = jbytearray_to_vec(env, healer_state)?;
+        let healer = crate::crypto::SessionHealer::from_bytes(&state).map_err(|e| e.to_string());
+        state.zeroize();
+        return healer;
+    }
+    let mut root_vec = jbytearray_to_vec(env, root_key)?;
+    let root = crate::crypto::RootKey::from_bytes(&root_vec);
+    root_vec.zeroize();
+    let root = root.map_err(|_| "Root key must be 32 bytes".to_string())?;
+    let epoch = u32::try_from(epoch).map_err(|_| "Session epoch must not be negative".to_string())?;
+    Ok(crate::crypto::SessionHealer::new(root, epoch))
+}
+
+/// Start the SESSION_RESET channel (separate from MESSAGE so resets are never decrypted as chain messages)
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_startSessionResetListener(
+    mut env: JNIEnv,
+    _class: JClass,
+) -> jboolean {
+    catch_panic!(env, {
+        let (tx, rx) = mpsc::unbounded_channel::<Vec<u8>>();
+        let _ = GLOBAL_SESSION_RESET_RECEIVER.set(Arc::new(Mutex::new(rx)));
+
+        if crate::network::tor::SESSION_RESET_TX.set(Arc::new(std::sync::Mutex::new(tx))).is_err() {
+            log::error!("Session reset channel already initialized");
+            return 0;
         }
+
+        log::info!("Session reset listener channel initialized successfully");
+        1
     }, 0)
 }
 
-/// End voice session and close connection (v2.0)
+/// Poll for an incoming SESSION_RESET (non-blocking)
+/// Returns [sender_ed25519:32][kind][body], or null if none is waiting
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_endVoiceSession(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_pollSessionReset(
     mut env: JNIEnv,
     _class: JClass,
-    call_id: JString,
-) {
+) -> jbyteArray {
     catch_panic!(env, {
-        let call_id_str = match jstring_to_string(&mut env, call_id) {
-            Ok(s) => s,
-            Err(e) => {
-                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
-                return;
-            }
+        let Some(receiver) = GLOBAL_SESSION_RESET_RECEIVER.get() else {
+            return std::ptr::null_mut();
         };
-
-        log::info!("Ending voice session for call: {}", call_id_str);
-
-        let voice_listener = get_voice_listener();
-        let listener = voice_listener.blocking_lock();
-        listener.end_session(&call_id_str);
-    }, ())
+        let message = match receiver.lock().unwrap().try_recv() {
+            Ok(message) => message,
+            Err(_) => return std::ptr::null_mut(),
+        };
+        vec_to_jbytearray(&mut env, &message)
+            .map(|a| a.into_raw())
+            .unwrap_or(std::ptr::null_mut())
+    }, std::ptr::null_mut())
 }
 
-/// Get number of active voice sessions (v2.0)
+/// Session epoch of a chain message (0 for messages without an epoch header), or -1 if malformed
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_getActiveVoiceSessions(
-    _env: JNIEnv,
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_chainMessageEpoch(
+    mut env: JNIEnv,
     _class: JClass,
+    encrypted_data: JByteArray,
 ) -> jint {
-    let voice_listener = get_voice_listener();
-    let listener = voice_listener.blocking_lock();
-    listener.active_sessions() as jint
+    catch_panic!(env, {
+        let Ok(encrypted) = jbytearray_to_vec(&mut env, encrypted_data) else { return -1 };
+        crate::crypto::chain_message_epoch(&encrypted)
+            .ok()
+            .and_then(|epoch| jint::try_from(epoch).ok())
+            .unwrap_or(-1)
+    }, -1)
 }
 
-/// Rebuild a specific voice circuit (close and reconnect with fresh Tor path)
-/// Tor daemon will automatically pick a different relay path via SOCKS5 isolation
-/// @param call_id Active call session ID
-/// @param circuit_index Circuit to rebuild (0-2)
-/// @param rebuild_epoch Incremented counter (forces fresh SOCKS5 isolation)
-/// @return true if rebuild succeeded, false if failed
+/// Start (or repeat, while unanswered) a session reset with a contact
+/// healer_state: state returned by the last session reset call, or null to start from (root_key, epoch)
+/// Returns [state_len:4][healer_state][wire], wire = [our_ed25519:32][RESET_INIT] to send as MSG_TYPE_SESSION_RESET
 #[no_mangle]
-pub extern "C" fn Java_com_securelegion_crypto_RustBridge_rebuildVoiceCircuit(
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_sessionResetInitiate(
     mut env: JNIEnv,
     _class: JClass,
-    call_id: JString,
-    circuit_index: jint,
-    rebuild_epoch: jint,
-) -> jboolean {
+    healer_state: JByteArray,
+    root_key: JByteArray,
+    epoch: jint,
+) -> jbyteArray {
     catch_panic!(env, {
-        let call_id_str = match jstring_to_string(&mut env, call_id) {
-            Ok(s) => s,
+        use crate::crypto::SessionResetMessage;
+
+        let mut healer = match jni_session_healer(&mut env, healer_state, root_key, epoch) {
+            Ok(h) => h,
             Err(e) => {
-                log::error!("Failed to convert call_id: {}", e);
-                return 0;
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
+        let (our_public, mut our_private) = match local_signing_keys(&mut env) {
+            Ok(keys) => keys,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/RuntimeException", e);
+                return std::ptr::null_mut();
             }
         };
 
-        log::warn!("Rebuilding voice circuit {} for call {} (rebuild_epoch={})", circuit_index, call_id_str, rebuild_epoch);
+        let init = healer.initiate(&our_private, unix_now() as i64);
+        our_private.zeroize();
+        let wire = init.and_then(|init| SessionResetMessage::Init(init).to_bytes());
+        let state = healer.to_bytes();
+        let (wire, mut state) = match (wire, state) {
+            (Ok(w), Ok(s)) => (w, s),
+            (Err(e), _) | (_, Err(e)) => {
+                let _ = env.throw_new("java/lang/RuntimeException", format!("Session reset failed: {}", e));
+                return std::ptr::null_mut();
+            }
+        };
 
-        // Rebuild circuit via async runtime
-        let result = GLOBAL_RUNTIME.block_on(async {
-            let voice_listener = get_voice_listener();
-            let mut listener = voice_listener.lock().await;
+        let mut out = Vec::with_capacity(4 + state.len() + 32 + wire.len());
+        out.extend_from_slice(&(state.len() as u32).to_be_bytes());
+        out.extend_from_slice(&state);
+        out.extend_from_slice(&our_public);
+        out.extend_from_slice(&wire);
+        state.zeroize();
 
-            listener.rebuild_circuit(&call_id_str, circuit_index as usize, rebuild_epoch as u32).await
-        });
+        let result = vec_to_jbytearray(&mut env, &out)
+            .map(|a| a.into_raw())
+            .unwrap_or(std::ptr::null_mut());
+        out.zeroize();
+        result
+    }, std::ptr::null_mut())
+}
 
-        match result {
-            Ok(_) => {
-                log::info!("✓ Circuit {} rebuild SUCCESS for call {}", circuit_index, call_id_str);
-                1 // success
+/// Handle a SESSION_RESET init or ACK from a contact
+/// message: [kind][body] as polled (after the sender key); their_ed25519: the contact's pinned key
+/// Returns [switched:1][epoch:4][grace_expires_at:8][state_len:4][healer_state][root_key:32][reply]
+///   root_key is the new epoch's root (zeros unless switched), grace_expires_at the Unix time the
+///   previous epoch stops decrypting (0 if none), reply the wire to send back (empty for ACKs)
+/// Returns null if the message is forged, stale, outside the freshness window or loses a concurrent reset
+#[no_mangle]
+pub extern "C" fn Java_com_securelegion_crypto_RustBridge_sessionResetHandle(
+    mut env: JNIEnv,
+    _class: JClass,
+    healer_state: JByteArray,
+    root_key: JByteArray,
+    epoch: jint,
+    their_ed25519: JByteArray,
+    message: JByteArray,
+) -> jbyteArray {
+    catch_panic!(env, {
+        use crate::crypto::SessionResetMessage;
+
+        let mut healer = match jni_session_healer(&mut env, healer_state, root_key, epoch) {
+            Ok(h) => h,
+            Err(e) => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", e);
+                return std::ptr::null_mut();
+            }
+        };
+        let their_ed25519: [u8; 32] = match jbytearray_to_vec(&mut env, their_ed25519).ok().and_then(|v| v.try_into().ok()) {
+            Some(k) => k,
+            None => {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", "Contact Ed25519 key must be 32 bytes");
+                return std::ptr::null_mut();
+            }
+        };
+        let message = match jbytearray_to_vec(&mut env, message).map(|m| SessionResetMessage::from_bytes(&m)) {
+            Ok(Ok(m)) => m,
+            _ => {
+                log::warn!("Malformed session reset message");
+                return std::ptr::null_mut();
+            }
+        };
+
+        let epoch_before = healer.epoch();
+        let now = unix_now() as i64;
+        let handled = match message {
+            SessionResetMessage::Init(init) => {
+                let (our_public, mut our_private) = match local_signing_keys(&mut env) {
+                    Ok(keys) => keys,
+                    Err(e) => {
+                        let _ = env.throw_new("java/lang/RuntimeException", e);
+                        return std::ptr::null_mut();
+                    }
+                };
+                let ack = healer.handle_init(&init, &their_ed25519, &our_public, &our_private, now);
+                our_private.zeroize();
+                ack.and_then(|ack| SessionResetMessage::Ack(ack).to_bytes()).map(|ack| {
+                    let mut reply = our_public.to_vec();
+                    reply.extend_from_slice(&ack);
+                    reply
+                })
             }
+            SessionResetMessage::Ack(ack) => healer.handle_ack(&ack, &their_ed25519, now).map(|_| Vec::new()),
+        };
+        let reply = match handled {
+            Ok(reply) => reply,
             Err(e) => {
-                log::error!("✗ Circuit {} rebuild FAILED for call {}: {}", circuit_index, call_id_str, e);
-                0 // failure
+                log::warn!("Session reset rejected: {}", e);
+                return std::ptr::null_mut();
             }
-        }
-    }, 0)
-}
+        };
 
-// ==================== END v2.0: Voice Streaming ====================
+        let mut state = match healer.to_bytes() {
+            Ok(s) => s,
+            Err(_) => return std::ptr::null_mut(),
+        };
+        let switched = healer.epoch() != epoch_before;
+        let root: &[u8; 32] = if switched { healer.root_key().expose_secret() } else { &[0u8; 32] };
+
+        let mut out = Vec::with_capacity(1 + 4 + 8 + 4 + state.len() + 32 + reply.len());
+        out.push(switched as u8);
+        out.extend_from_slice(&healer.epoch().to_be_bytes());
+        out.extend_from_slice(&healer.grace_expires_at().unwrap_or(0).to_be_bytes());
+        out.extend_from_slice(&(state.len() as u32).to_be_bytes());
+        out.extend_from_slice(&state);
+        out.extend_from_slice(root);
+        out.extend_from_slice(&reply);
+        state.zeroize();
+
+        let result = vec_to_jbytearray(&mut env, &out)
+            .map(|a| a.into_raw())
+            .unwrap_or(std::ptr::null_mut());
+        out.zeroize();
+        result
+    }, std::ptr::null_mut())
+}
@@ -1,10 +1,16 @@
 /// FFI KeyStore Integration
 ///
-/// Provides secure access to Android KeyStore from Rust via JNI callbacks.
-/// This ensures private keys never leave the hardware-backed secure storage.
+/// Provides access to the identity keys from Rust via JNI callbacks.
+/// The master seed is fetched from KeyManager on first use and every purpose key is
+/// derived from it in Rust by `KeyHierarchy`; only the seed crosses the JNI boundary.
+/// The app drops the cached hierarchy on lock and whenever it leaves the foreground.
 
 use jni::JNIEnv;
 use jni::objects::{JObject, JByteArray};
+use once_cell::sync::Lazy;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use crate::crypto::key_hierarchy::{DerivationVersion, KeyHierarchy};
 
 /// KeyStore access errors
 #[derive(Debug)]
@@ -13,6 +19,7 @@ pub enum KeyStoreError {
     SigningFailed,
     EncryptionFailed,
     DecryptionFailed,
+    DerivationFailed(String),
     JniError(String),
 }
 
@@ -23,6 +30,7 @@ impl std::fmt::Display for KeyStoreError {
             Self::SigningFailed => write!(f, "Signing operation failed"),
             Self::EncryptionFailed => write!(f, "Encryption operation failed"),
             Self::DecryptionFailed => write!(f, "Decryption operation failed"),
+            Self::DerivationFailed(msg) => write!(f, "Key derivation failed: {}", msg),
             Self::JniError(msg) => write!(f, "JNI error: {}", msg),
         }
     }
@@ -30,272 +38,137 @@ impl std::fmt::Display for KeyStoreError {
 
 impl std::error::Error for KeyStoreError {}
 
-/// Get Ed25519 signing key from Android KeyStore
-///
-/// This calls back to KeyManager.getSigningKeyBytes() via JNI
-/// The key is never stored in Rust - only used for signing operations
-pub fn get_signing_private_key(env: &mut JNIEnv, key_manager: &JObject) -> Result<Vec<u8>, KeyStoreError> {
-    // Call KeyManager.getSigningKeyBytes()
-    let result = env
-        .call_method(
-            key_manager,
-            "getSigningKeyBytes",
-            "()[B",
-            &[],
-        )
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to call getSigningKeyBytes: {}", e)))?;
-
-    // Convert result to byte array
-    let byte_array = result.l()
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to get byte array: {}", e)))?;
+/// Key hierarchy for the current session, built from the master seed on first use
+static SESSION_HIERARCHY: Lazy<Mutex<Option<Arc<KeyHierarchy>>>> = Lazy::new(|| Mutex::new(None));
 
-    // Convert Java byte array to Rust Vec<u8>
-    let jbyte_array = JByteArray::from(byte_array);
-    let len = env.get_array_length(&jbyte_array)
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to get array length: {}", e)))?;
+/// Bumped by every clear, so a fetch that raced with a lock is used once but not cached
+static CLEAR_GENERATION: AtomicU64 = AtomicU64::new(0);
 
-    let mut buf = vec![0i8; len as usize];
-    env.get_byte_array_region(&jbyte_array, 0, &mut buf)
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to copy bytes: {}", e)))?;
-
-    let buf: Vec<u8> = buf.into_iter().map(|b| b as u8).collect();
+/// Lock the session cache; it only holds an `Option`, so a poisoned lock is still usable
+fn session_hierarchy() -> MutexGuard<'static, Option<Arc<KeyHierarchy>>> {
+    SESSION_HIERARCHY.lock().unwrap_or_else(PoisonError::into_inner)
+}
 
-    if buf.len() != 32 {
-        return Err(KeyStoreError::KeyNotFound);
+/// Get the session key hierarchy, fetching the master seed from KeyManager if needed
+///
+/// Calls KeyManager.getWalletSeed() and KeyManager.getKeyDerivationVersion() only when
+/// nothing is cached; later calls reuse the hierarchy until `clear_key_hierarchy`.
+/// The cache lock is never held across the JNI calls.
+pub fn get_key_hierarchy(env: &mut JNIEnv, key_manager: &JObject) -> Result<Arc<KeyHierarchy>, KeyStoreError> {
+    if let Some(hierarchy) = session_hierarchy().as_ref() {
+        return Ok(hierarchy.clone());
     }
-
-    Ok(buf)
+    let generation = CLEAR_GENERATION.load(Ordering::SeqCst);
+
+    let version = env
+        .call_method(key_manager, "getKeyDerivationVersion", "()I", &[])
+        .and_then(|v| v.i())
+        .map_err(|e| KeyStoreError::JniError(format!("Failed to call getKeyDerivationVersion: {}", e)))?;
+    let version = DerivationVersion::from_u8(version as u8)
+        .map_err(|e| KeyStoreError::DerivationFailed(e.to_string()))?;
+
+    let mut seed = call_byte_array_method(env, key_manager, "getWalletSeed")?;
+    let hierarchy = KeyHierarchy::new(&seed, version)
+        .map_err(|e| KeyStoreError::DerivationFailed(e.to_string()));
+    zeroize::Zeroize::zeroize(&mut seed);
+
+    let hierarchy = Arc::new(hierarchy?);
+    let mut cached = session_hierarchy();
+    if CLEAR_GENERATION.load(Ordering::SeqCst) != generation {
+        return Ok(hierarchy);
+    }
+    // Another thread may have filled the cache meanwhile; both were built from the same seed
+    Ok(cached.get_or_insert(hierarchy).clone())
 }
 
-/// Get Ed25519 public key from Android KeyStore
-pub fn get_signing_public_key(env: &mut JNIEnv, key_manager: &JObject) -> Result<Vec<u8>, KeyStoreError> {
-    let result = env
-        .call_method(
-            key_manager,
-            "getSigningPublicKey",
-            "()[B",
-            &[],
-        )
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to call getSigningPublicKey: {}", e)))?;
-
-    let byte_array = result.l()
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to get byte array: {}", e)))?;
-
-    let jbyte_array = JByteArray::from(byte_array);
-    let len = env.get_array_length(&jbyte_array)
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to get array length: {}", e)))?;
-
-    let mut buf = vec![0i8; len as usize];
-    env.get_byte_array_region(&jbyte_array, 0, &mut buf)
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to copy bytes: {}", e)))?;
-
-    let buf: Vec<u8> = buf.into_iter().map(|b| b as u8).collect();
+/// Drop the cached key hierarchy (on lock, backgrounding, logout or wipe)
+pub fn clear_key_hierarchy() {
+    let mut cached = session_hierarchy();
+    CLEAR_GENERATION.fetch_add(1, Ordering::SeqCst);
+    cached.take();
+}
 
-    if buf.len() != 32 {
-        return Err(KeyStoreError::KeyNotFound);
-    }
+/// Get Ed25519 signing key derived from the master seed
+pub fn get_signing_private_key(env: &mut JNIEnv, key_manager: &JObject) -> Result<Vec<u8>, KeyStoreError> {
+    let hierarchy = get_key_hierarchy(env, key_manager)?;
+    Ok(hierarchy.signing_key().to_bytes().to_vec())
+}
 
-    Ok(buf)
+/// Get Ed25519 public key derived from the master seed
+pub fn get_signing_public_key(env: &mut JNIEnv, key_manager: &JObject) -> Result<Vec<u8>, KeyStoreError> {
+    let hierarchy = get_key_hierarchy(env, key_manager)?;
+    Ok(hierarchy.signing_key().verifying_key().to_bytes().to_vec())
 }
 
-/// Get X25519 encryption private key from Android KeyStore
+/// Get X25519 encryption private key derived from the master seed
 pub fn get_encryption_private_key(env: &mut JNIEnv, key_manager: &JObject) -> Result<Vec<u8>, KeyStoreError> {
-    let result = env
-        .call_method(
-            key_manager,
-            "getEncryptionKeyBytes",
-            "()[B",
-            &[],
-        )
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to call getEncryptionKeyBytes: {}", e)))?;
-
-    let byte_array = result.l()
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to get byte array: {}", e)))?;
-
-    let jbyte_array = JByteArray::from(byte_array);
-    let len = env.get_array_length(&jbyte_array)
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to get array length: {}", e)))?;
-
-    let mut buf = vec![0i8; len as usize];
-    env.get_byte_array_region(&jbyte_array, 0, &mut buf)
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to copy bytes: {}", e)))?;
-
-    let buf: Vec<u8> = buf.into_iter().map(|b| b as u8).collect();
-
-    if buf.len() != 32 {
-        return Err(KeyStoreError::KeyNotFound);
-    }
-
-    Ok(buf)
+    let hierarchy = get_key_hierarchy(env, key_manager)?;
+    Ok(hierarchy.encryption_secret().to_bytes().to_vec())
 }
 
-/// Get X25519 encryption public key from Android KeyStore
+/// Get X25519 encryption public key derived from the master seed
 pub fn get_encryption_public_key(env: &mut JNIEnv, key_manager: &JObject) -> Result<Vec<u8>, KeyStoreError> {
-    let result = env
-        .call_method(
-            key_manager,
-            "getEncryptionPublicKey",
-            "()[B",
-            &[],
-        )
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to call getEncryptionPublicKey: {}", e)))?;
-
-    let byte_array = result.l()
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to get byte array: {}", e)))?;
-
-    let jbyte_array = JByteArray::from(byte_array);
-    let len = env.get_array_length(&jbyte_array)
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to get array length: {}", e)))?;
-
-    let mut buf = vec![0i8; len as usize];
-    env.get_byte_array_region(&jbyte_array, 0, &mut buf)
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to copy bytes: {}", e)))?;
-
-    let buf: Vec<u8> = buf.into_iter().map(|b| b as u8).collect();
-
-    if buf.len() != 32 {
-        return Err(KeyStoreError::KeyNotFound);
-    }
+    let hierarchy = get_key_hierarchy(env, key_manager)?;
+    Ok(x25519_dalek::PublicKey::from(&hierarchy.encryption_secret()).to_bytes().to_vec())
+}
 
-    Ok(buf)
+/// Get Kyber-1024 secret key derived from the master seed
+pub fn get_kyber_secret_key(env: &mut JNIEnv, key_manager: &JObject) -> Result<Vec<u8>, KeyStoreError> {
+    let hierarchy = get_key_hierarchy(env, key_manager)?;
+    let keypair = hierarchy.hybrid_keypair()
+        .map_err(|e| KeyStoreError::DerivationFailed(e.to_string()))?;
+    Ok(keypair.kyber_secret.to_vec())
 }
 
-/// Get hidden service Ed25519 private key from Android KeyStore
+/// Get hidden service Ed25519 private key derived from the master seed
 pub fn get_hidden_service_private_key(env: &mut JNIEnv, key_manager: &JObject) -> Result<Vec<u8>, KeyStoreError> {
-    let result = env
-        .call_method(
-            key_manager,
-            "getHiddenServiceKeyBytes",
-            "()[B",
-            &[],
-        )
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to call getHiddenServiceKeyBytes: {}", e)))?;
-
-    let byte_array = result.l()
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to get byte array: {}", e)))?;
-
-    let jbyte_array = JByteArray::from(byte_array);
-    let len = env.get_array_length(&jbyte_array)
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to get array length: {}", e)))?;
-
-    let mut buf = vec![0i8; len as usize];
-    env.get_byte_array_region(&jbyte_array, 0, &mut buf)
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to copy bytes: {}", e)))?;
-
-    let buf: Vec<u8> = buf.into_iter().map(|b| b as u8).collect();
-
-    if buf.len() != 32 {
-        return Err(KeyStoreError::KeyNotFound);
-    }
-
-    Ok(buf)
+    let hierarchy = get_key_hierarchy(env, key_manager)?;
+    Ok(hierarchy.hidden_service_key().to_bytes().to_vec())
 }
 
-/// Get friend request Ed25519 private key from Android KeyStore (v2.0)
-/// Derived from seed using domain separation ("friend_req")
+/// Get friend request Ed25519 private key derived from the master seed (v2.0)
+/// Legacy derivation uses domain separation ("friend_req")
 pub fn get_friend_request_private_key(env: &mut JNIEnv, key_manager: &JObject) -> Result<Vec<u8>, KeyStoreError> {
-    let result = env
-        .call_method(
-            key_manager,
-            "getFriendRequestKeyBytes",
-            "()[B",
-            &[],
-        )
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to call getFriendRequestKeyBytes: {}", e)))?;
-
-    let byte_array = result.l()
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to get byte array: {}", e)))?;
-
-    let jbyte_array = JByteArray::from(byte_array);
-    let len = env.get_array_length(&jbyte_array)
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to get array length: {}", e)))?;
-
-    let mut buf = vec![0i8; len as usize];
-    env.get_byte_array_region(&jbyte_array, 0, &mut buf)
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to copy bytes: {}", e)))?;
-
-    let buf: Vec<u8> = buf.into_iter().map(|b| b as u8).collect();
-
-    if buf.len() != 32 {
-        return Err(KeyStoreError::KeyNotFound);
-    }
-
-    Ok(buf)
+    let hierarchy = get_key_hierarchy(env, key_manager)?;
+    Ok(hierarchy.friend_request_key().to_bytes().to_vec())
 }
 
-/// Get voice service Ed25519 private key from Android KeyStore (v2.0)
-/// Derived from seed using domain separation ("tor_voice")
+/// Get voice service Ed25519 private key derived from the master seed (v2.0)
+/// Legacy derivation uses domain separation ("tor_voice")
 /// Used for voice calling .onion address creation
 pub fn get_voice_service_private_key(env: &mut JNIEnv, key_manager: &JObject) -> Result<Vec<u8>, KeyStoreError> {
-    let result = env
-        .call_method(
-            key_manager,
-            "getVoiceServicePrivateKey",
-            "()[B",
-            &[],
-        )
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to call getVoiceServicePrivateKey: {}", e)))?;
-
-    let byte_array = result.l()
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to get byte array: {}", e)))?;
-
-    let jbyte_array = JByteArray::from(byte_array);
-    let len = env.get_array_length(&jbyte_array)
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to get array length: {}", e)))?;
-
-    let mut buf = vec![0i8; len as usize];
-    env.get_byte_array_region(&jbyte_array, 0, &mut buf)
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to copy bytes: {}", e)))?;
-
-    let buf: Vec<u8> = buf.into_iter().map(|b| b as u8).collect();
-
-    if buf.len() != 32 {
-        return Err(KeyStoreError::KeyNotFound);
-    }
-
-    Ok(buf)
+    let hierarchy = get_key_hierarchy(env, key_manager)?;
+    Ok(hierarchy.voice_service_key().to_bytes().to_vec())
 }
 
-/// Sign data using Ed25519 key from Android KeyStore
+/// Sign data with the Ed25519 identity key
 ///
-/// Delegates the actual signing operation to Android KeyStore for maximum security
+/// The signing key is derived from the session hierarchy; no per-signature JNI call is made
 pub fn sign_with_keystore(
     env: &mut JNIEnv,
     key_manager: &JObject,
     data: &[u8],
 ) -> Result<Vec<u8>, KeyStoreError> {
-    // Convert data to Java byte array
-    let data_array = env.byte_array_from_slice(data)
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to create byte array: {}", e)))?;
+    use ed25519_dalek::Signer;
 
-    // Call KeyManager.signData(data)
-    let result = env
-        .call_method(
-            key_manager,
-            "signData",
-            "([B)[B",
-            &[(&data_array).into()],
-        )
-        .map_err(|_e| KeyStoreError::SigningFailed)?;
-
-    let signature_array = result.l()
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to get signature: {}", e)))?;
-
-    let jbyte_array = JByteArray::from(signature_array);
-    let len = env.get_array_length(&jbyte_array)
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to get signature length: {}", e)))?;
-
-    let mut buf = vec![0i8; len as usize];
-    env.get_byte_array_region(&jbyte_array, 0, &mut buf)
-        .map_err(|e| KeyStoreError::JniError(format!("Failed to copy signature: {}", e)))?;
+    let hierarchy = get_key_hierarchy(env, key_manager)?;
+    Ok(hierarchy.signing_key().sign(data).to_bytes().to_vec())
+}
 
-    let signature: Vec<u8> = buf.into_iter().map(|b| b as u8).collect();
+/// Call a no-argument KeyManager method returning byte[]
+fn call_byte_array_method(env: &mut JNIEnv, key_manager: &JObject, method: &str) -> Result<Vec<u8>, KeyStoreError> {
+    let result = env
+        .call_method(key_manager, method, "()[B", &[])
+        .map_err(|e| KeyStoreError::JniError(format!("Failed to call {}: {}", method, e)))?;
 
-    if signature.len() != 64 {
-        return Err(KeyStoreError::SigningFailed);
+    let byte_array = result.l()
+        .map_err(|e| KeyStoreError::JniError(format!("Failed to get byte array: {}", e)))?;
+    if byte_array.is_null() {
+        return Err(KeyStoreError::KeyNotFound);
     }
 
-    Ok(signature)
+    env.convert_byte_array(JByteArray::from(byte_array))
+        .map_err(|e| KeyStoreError::JniError(format!("Failed to copy bytes: {}", e)))
 }
 
 /// Get KeyManager instance from context
@@ -329,4 +202,12 @@ mod tests {
         let err = KeyStoreError::KeyNotFound;
         assert_eq!(err.to_string(), "Key not found in KeyStore");
     }
+
+    #[test]
+    fn test_clear_key_hierarchy_resets_session() {
+        *SESSION_HIERARCHY.lock().unwrap() =
+            Some(Arc::new(KeyHierarchy::new(&[1u8; 64], DerivationVersion::Legacy).unwrap()));
+        clear_key_hierarchy();
+        assert!(SESSION_HIERARCHY.lock().unwrap().is_none());
+    }
 }
@@ -8,9 +8,12 @@ pub mod ffi;
 // Re-export main types
 pub use crypto::{
     encrypt_message, decrypt_message,
-    sign_data, verify_signature, generate_keypair,
-    derive_shared_secret, hash_password, hash_handle,
+    verify_signature, generate_keypair,
+    hash_password, hash_handle,
+    SigningSecret, X25519Secret, RootKey, ChainKey, MessageKey,
 };
+#[allow(deprecated)]
+pub use crypto::{sign_data, derive_shared_secret};
 
 pub use protocol::{Message, ContactCard, SecurityMode, MessageType};
 pub use network::{PingToken, PongToken, PingPongManager, TorManager};
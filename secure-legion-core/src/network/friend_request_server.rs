@@ -3,12 +3,13 @@
 /// P2P endpoint that listens on localhost and serves contact data:
 /// - GET /contact-card - Returns encrypted contact card
 /// - GET /contact-list/{cid} - Returns encrypted contact list (v5 architecture)
+/// - GET /prekey-bundle - Returns a PQXDH prekey bundle (see crypto::pqxdh)
 ///
 /// This endpoint is accessible via the friend request .onion address.
 /// Friend requests are handled by the v1.0 wire protocol (0x07/0x08) on messaging .onion.
 
 use std::sync::Arc;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use tokio::sync::Mutex;
 use tokio::net::TcpListener;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
@@ -21,6 +22,10 @@ pub struct ContactExchangeEndpoint {
     cid: Arc<Mutex<Option<String>>>,
     /// Contact lists (CID → encrypted data) for v5 architecture
     contact_lists: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+    /// Prekey bundles, each carrying a distinct one-time prekey (handed out once)
+    prekey_bundles: Arc<Mutex<VecDeque<Vec<u8>>>>,
+    /// Bundle without a one-time prekey, served once the queue is empty
+    fallback_prekey_bundle: Arc<Mutex<Option<Vec<u8>>>>,
     /// Endpoint shutdown signal
     shutdown: Arc<Mutex<bool>>,
 }
@@ -31,6 +36,8 @@ impl ContactExchangeEndpoint {
             contact_card: Arc::new(Mutex::new(None)),
             cid: Arc::new(Mutex::new(None)),
             contact_lists: Arc::new(Mutex::new(HashMap::new())),
+            prekey_bundles: Arc::new(Mutex::new(VecDeque::new())),
+            fallback_prekey_bundle: Arc::new(Mutex::new(None)),
             shutdown: Arc::new(Mutex::new(false)),
         }
     }
@@ -58,6 +65,31 @@ impl ContactExchangeEndpoint {
         log::info!("Contact list stored for CID: {} ({} bytes)", cid, list_len);
     }
 
+    /// Queue a prekey bundle carrying a one-time prekey (served to a single requester)
+    pub async fn add_prekey_bundle(&self, bundle: Vec<u8>) {
+        let mut bundles_lock = self.prekey_bundles.lock().await;
+        bundles_lock.push_back(bundle);
+
+        log::info!("Prekey bundle queued ({} available)", bundles_lock.len());
+    }
+
+    /// Replace every published prekey bundle (after a rotation or when republishing at startup)
+    pub async fn replace_prekey_bundles(&self, one_time: Vec<Vec<u8>>, fallback: Vec<u8>) {
+        let mut bundles_lock = self.prekey_bundles.lock().await;
+        *bundles_lock = one_time.into();
+        *self.fallback_prekey_bundle.lock().await = Some(fallback);
+
+        log::info!("Prekey bundles replaced ({} one-time available)", bundles_lock.len());
+    }
+
+    /// Set the bundle served when no one-time prekey bundles are left
+    pub async fn set_fallback_prekey_bundle(&self, bundle: Vec<u8>) {
+        let mut fallback_lock = self.fallback_prekey_bundle.lock().await;
+        *fallback_lock = Some(bundle);
+
+        log::info!("Fallback prekey bundle set");
+    }
+
     /// Start the contact exchange listener on the specified port
     pub async fn start(&self, port: u16) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let addr = format!("127.0.0.1:{}", port);
@@ -69,6 +101,10 @@ impl ContactExchangeEndpoint {
         let contact_card = self.contact_card.clone();
         let cid = self.cid.clone();
         let contact_lists = self.contact_lists.clone();
+        let prekeys = PrekeyBundles {
+            queue: self.prekey_bundles.clone(),
+            fallback: self.fallback_prekey_bundle.clone(),
+        };
         let shutdown = self.shutdown.clone();
 
         tokio::spawn(async move {
@@ -97,10 +133,11 @@ impl ContactExchangeEndpoint {
                 let contact_card = contact_card.clone();
                 let cid = cid.clone();
                 let contact_lists = contact_lists.clone();
+                let prekeys = prekeys.clone();
 
                 // Spawn a task to handle this connection
                 tokio::spawn(async move {
-                    if let Err(e) = handle_connection(&mut socket, contact_card, cid, contact_lists).await {
+                    if let Err(e) = handle_connection(&mut socket, contact_card, cid, contact_lists, prekeys).await {
                         log::error!("Error handling connection: {}", e);
                     }
                 });
@@ -149,12 +186,20 @@ pub struct FriendResponse {
     pub approved: bool,
 }
 
+/// Prekey bundle state shared with connection handlers
+#[derive(Clone)]
+struct PrekeyBundles {
+    queue: Arc<Mutex<VecDeque<Vec<u8>>>>,
+    fallback: Arc<Mutex<Option<Vec<u8>>>>,
+}
+
 /// Handle incoming contact fetch request from peer
 async fn handle_connection(
     socket: &mut tokio::net::TcpStream,
     contact_card: Arc<Mutex<Option<Vec<u8>>>>,
     cid: Arc<Mutex<Option<String>>>,
     contact_lists: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+    prekeys: PrekeyBundles,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Read HTTP request
     let mut buffer = vec![0u8; 8192];
@@ -238,6 +283,33 @@ async fn handle_connection(
             }
         }
 
+        ("GET", "/prekey-bundle") => {
+            // Serve a one-time prekey bundle if any are left, otherwise the fallback
+            let bundle = match prekeys.queue.lock().await.pop_front() {
+                Some(bundle) => Some(bundle),
+                None => prekeys.fallback.lock().await.clone(),
+            };
+
+            if let Some(bundle) = bundle {
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\n\
+                     Content-Type: application/octet-stream\r\n\
+                     Content-Length: {}\r\n\
+                     \r\n",
+                    bundle.len()
+                );
+
+                socket.write_all(response.as_bytes()).await?;
+                socket.write_all(&bundle).await?;
+
+                log::info!("Served prekey bundle ({} bytes)", bundle.len());
+            } else {
+                let response = "HTTP/1.1 404 Not Found\r\n\r\nNo prekey bundle available";
+                socket.write_all(response.as_bytes()).await?;
+                log::warn!("Prekey bundle requested but not available");
+            }
+        }
+
         _ => {
             let response = "HTTP/1.1 404 Not Found\r\n\r\n";
             socket.write_all(response.as_bytes()).await?;
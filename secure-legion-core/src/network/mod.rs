@@ -15,6 +15,11 @@ pub use pingpong::{
     cleanup_expired_pongs,
     remove_ack_session,
     cleanup_expired_acks,
+    encrypt_ping_envelope,
+    decrypt_ping_envelope,
+    decrypt_incoming_ping,
+    PingEnvelopeError,
+    PING_ENVELOPE_VERSION,
 };
 pub use tor::{TorManager, PENDING_CONNECTIONS, PendingConnection};
 pub use friend_request_server::{ContactExchangeEndpoint, get_endpoint};
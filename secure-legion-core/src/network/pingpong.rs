@@ -1,11 +1,27 @@
 use serde::{Deserialize, Serialize};
-use ed25519_dalek::{SigningKey, VerifyingKey, Signature, Signer, Verifier};
+use ed25519_dalek::{SigningKey, VerifyingKey, Signature, Signer};
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::sync::OnceLock;
 use serde_big_array::BigArray;
 use super::tor::TorManager;
+use crate::crypto::canonical::CanonicalEncoder;
+use crate::crypto::encryption::decrypt_message;
+use crate::crypto::key_exchange::X25519Secret;
+use crate::crypto::pqc::{hybrid_decapsulate, hybrid_encapsulate, HybridCiphertext, HybridKEMKeypair, HybridKEMPublicKey};
+use crate::crypto::pqc::hybrid_signature::{
+    attach_local_pq_signature, deserialize_with_pq_trailer, impl_hybrid_signed_token, peer_ml_dsa_key,
+    serialize_with_pq_trailer, HybridSignedToken, PqSignature,
+};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use thiserror::Error;
+use zeroize::Zeroizing;
 
 /// Ping Token - sent from sender to recipient to initiate handshake
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -28,9 +44,13 @@ pub struct PingToken {
     /// Unix timestamp when Ping was created
     pub timestamp: i64,
 
-    /// Ed25519 signature of (sender_pubkey || recipient_pubkey || sender_x25519_pubkey || recipient_x25519_pubkey || nonce || timestamp)
+    /// Ed25519 signature over the canonical encoding of every field above (see `crypto::canonical`)
     #[serde(with = "BigArray")]
     pub signature: [u8; 64],
+
+    /// Optional ML-DSA-65 half of a hybrid signature (sent as a trailer after the bincode body)
+    #[serde(skip)]
+    pub pq_signature: Option<PqSignature>,
 }
 
 /// Pong Token - response from recipient confirming readiness
@@ -51,6 +71,10 @@ pub struct PongToken {
     /// Recipient's Ed25519 signature
     #[serde(with = "BigArray")]
     pub signature: [u8; 64],
+
+    /// Optional ML-DSA-65 half of a hybrid signature (sent as a trailer after the bincode body)
+    #[serde(skip)]
+    pub pq_signature: Option<PqSignature>,
 }
 
 /// Delivery Confirmation (ACK) Token - confirms receipt of protocol messages
@@ -69,6 +93,10 @@ pub struct DeliveryAck {
     /// Sender's Ed25519 signature (proves this ACK is from the expected party)
     #[serde(with = "BigArray")]
     pub signature: [u8; 64],
+
+    /// Optional ML-DSA-65 half of a hybrid signature (sent as a trailer after the bincode body)
+    #[serde(skip)]
+    pub pq_signature: Option<PqSignature>,
 }
 
 /// Ping-Pong Protocol Manager
@@ -361,11 +389,13 @@ impl PingToken {
             nonce,
             timestamp,
             signature: [0u8; 64],
+            pq_signature: None,
         };
 
-        // Sign the Ping
+        // Sign the Ping (in the encoding the recipient verifies)
         let signature = ping.sign(sender_keypair)?;
         ping.signature = signature.to_bytes();
+        attach_local_pq_signature(&mut ping, &sender_keypair.verifying_key(), &recipient_pubkey.to_bytes())?;
 
         Ok(ping)
     }
@@ -391,53 +421,284 @@ impl PingToken {
             nonce,
             timestamp,
             signature: [0u8; 64],
+            pq_signature: None,
         };
 
-        // Sign the Ping
+        // Sign the Ping (in the encoding the recipient verifies)
         let signature = ping.sign(sender_keypair)?;
         ping.signature = signature.to_bytes();
+        attach_local_pq_signature(&mut ping, &sender_keypair.verifying_key(), &recipient_pubkey.to_bytes())?;
 
         Ok(ping)
     }
 
     /// Sign the Ping token
     fn sign(&self, keypair: &SigningKey) -> Result<Signature, Box<dyn std::error::Error>> {
-        let message = self.serialize_for_signing();
+        let message = self.signing_bytes_for(&self.recipient_pubkey);
         Ok(keypair.sign(&message))
     }
 
-    /// Verify the Ping signature
+    /// Verify the Ping signature (and its ML-DSA half if the sender's ML-DSA key is registered)
     pub fn verify(&self) -> Result<bool, Box<dyn std::error::Error>> {
         let sender_pubkey = VerifyingKey::from_bytes(&self.sender_pubkey)?;
-        let signature = Signature::from_bytes(&self.signature);
-        let message = self.serialize_for_signing();
 
-        Ok(sender_pubkey.verify(&message, &signature).is_ok())
+        Ok(self.verify_ed25519(&sender_pubkey, &self.signature)
+            && self.verify_pq(&self.sender_pubkey, peer_ml_dsa_key(&self.sender_pubkey).as_deref()))
     }
 
     /// Serialize Ping for signing (everything except the signature field)
     fn serialize_for_signing(&self) -> Vec<u8> {
-        let mut bytes = Vec::new();
-        bytes.extend_from_slice(&self.sender_pubkey);
-        bytes.extend_from_slice(&self.recipient_pubkey);
-        bytes.extend_from_slice(&self.sender_x25519_pubkey);
-        bytes.extend_from_slice(&self.recipient_x25519_pubkey);
-        bytes.extend_from_slice(&self.nonce);
-        bytes.extend_from_slice(&self.timestamp.to_le_bytes());
-        bytes
+        CanonicalEncoder::new(<Self as HybridSignedToken>::PQ_CONTEXT)
+            .bytes(1, &self.sender_pubkey)
+            .bytes(2, &self.recipient_pubkey)
+            .bytes(3, &self.sender_x25519_pubkey)
+            .bytes(4, &self.recipient_x25519_pubkey)
+            .bytes(5, &self.nonce)
+            .i64(6, self.timestamp)
+            .finish()
     }
 
     /// Serialize to bytes for network transmission
     pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-        Ok(bincode::serialize(self)?)
+        Ok(serialize_with_pq_trailer(self, self.pq_signature.as_ref())?)
     }
 
-    /// Deserialize from bytes
+    /// Deserialize from bytes (an ML-DSA trailer, if present, is kept for `verify_pq`)
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
-        Ok(bincode::deserialize(bytes)?)
+        let (mut token, pq_signature): (Self, _) = deserialize_with_pq_trailer(bytes)?;
+        token.pq_signature = pq_signature;
+        Ok(token)
     }
 }
 
+/// PING envelope format version (ephemeral X25519 per PING)
+pub const PING_ENVELOPE_VERSION: u8 = 0x02;
+
+/// Envelope flag: key agreement also includes a hybrid X25519 + ML-KEM encapsulation
+pub const PING_ENVELOPE_FLAG_HYBRID: u8 = 0x01;
+
+/// HKDF info for the envelope key
+const PING_ENVELOPE_KDF_INFO: &[u8] = b"SecureLegion-PingEnvelope-v1";
+
+const PING_ENVELOPE_NONCE_LEN: usize = 24;
+
+/// Errors from PING envelope encryption/decryption
+#[derive(Error, Debug)]
+pub enum PingEnvelopeError {
+    #[error("Malformed PING envelope")]
+    Malformed,
+
+    #[error("Unsupported PING envelope version: {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("Hybrid PING envelope received but no KEM keypair is available")]
+    MissingKemKeypair,
+
+    #[error("Key agreement failed")]
+    KeyAgreementFailed,
+
+    #[error("Encryption failed")]
+    EncryptionFailed,
+
+    #[error("PING decryption failed")]
+    DecryptionFailed,
+
+    #[error("Invalid PingToken encoding")]
+    InvalidToken,
+
+    #[error("Invalid Ping signature")]
+    InvalidSignature,
+
+    #[error("Ping is addressed to a different X25519 key")]
+    WrongRecipient,
+}
+
+/// Encrypt a serialized PingToken into a forward-secret envelope
+///
+/// Every PING uses a fresh X25519 key, so a later compromise of either party's static
+/// X25519 key does not decrypt recorded PINGs. When the recipient's hybrid KEM key is
+/// known, the envelope key additionally depends on an ML-KEM-1024 encapsulation.
+///
+/// # Arguments
+/// * `ping_bytes` - Serialized PingToken (`PingToken::to_bytes`)
+/// * `recipient_x25519_pubkey` - Recipient's X25519 public key
+/// * `recipient_kem` - Recipient's hybrid KEM public key, if known
+///
+/// # Returns
+/// Plain: [version][flags=0][ephemeral X25519 pubkey:32][nonce:24][ciphertext]
+/// Hybrid: [version][flags=1][hybrid KEM ciphertext][nonce:24][ciphertext]
+pub fn encrypt_ping_envelope(
+    ping_bytes: &[u8],
+    recipient_x25519_pubkey: &[u8; 32],
+    recipient_kem: Option<&HybridKEMPublicKey>,
+) -> Result<Vec<u8>, PingEnvelopeError> {
+    let mut header = vec![PING_ENVELOPE_VERSION];
+
+    let key = match recipient_kem {
+        Some(kem) => {
+            let (secret, ciphertext) = hybrid_encapsulate(&kem.x25519_public, &kem.kyber_public)
+                .map_err(|_| PingEnvelopeError::KeyAgreementFailed)?;
+            let secret = Zeroizing::new(secret);
+            header.push(PING_ENVELOPE_FLAG_HYBRID);
+            header.extend_from_slice(&ciphertext.to_bytes());
+            derive_ping_envelope_key(&header, &*secret)?
+        }
+        None => {
+            let ephemeral_secret = X25519Secret::generate();
+            let shared = ephemeral_secret.diffie_hellman(recipient_x25519_pubkey)
+                .map_err(|_| PingEnvelopeError::KeyAgreementFailed)?;
+            header.push(0x00);
+            header.extend_from_slice(&ephemeral_secret.public_key());
+            derive_ping_envelope_key(&header, &*shared)?
+        }
+    };
+
+    let mut nonce_bytes = [0u8; PING_ENVELOPE_NONCE_LEN];
+    getrandom::getrandom(&mut nonce_bytes).map_err(|_| PingEnvelopeError::EncryptionFailed)?;
+
+    let cipher = XChaCha20Poly1305::new_from_slice(&*key)
+        .map_err(|_| PingEnvelopeError::EncryptionFailed)?;
+
+    // Header is authenticated so the ephemeral key / KEM ciphertext cannot be swapped
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce_bytes), Payload { msg: ping_bytes, aad: &header })
+        .map_err(|_| PingEnvelopeError::EncryptionFailed)?;
+
+    let mut envelope = header;
+    envelope.extend_from_slice(&nonce_bytes);
+    envelope.extend_from_slice(&ciphertext);
+    Ok(envelope)
+}
+
+/// Decrypt and verify a PING envelope produced by `encrypt_ping_envelope`
+///
+/// # Arguments
+/// * `envelope` - Envelope bytes (without the MSG_TYPE byte)
+/// * `our_x25519_private` - Our static X25519 private key
+/// * `our_kem` - Our hybrid KEM keypair (required only for hybrid envelopes)
+///
+/// # Returns
+/// The PingToken, after checking its signature and that it is addressed to our X25519 key
+pub fn decrypt_ping_envelope(
+    envelope: &[u8],
+    our_x25519_private: &X25519Secret,
+    our_kem: Option<&HybridKEMKeypair>,
+) -> Result<PingToken, PingEnvelopeError> {
+    if envelope.len() < 2 {
+        return Err(PingEnvelopeError::Malformed);
+    }
+    if envelope[0] != PING_ENVELOPE_VERSION {
+        return Err(PingEnvelopeError::UnsupportedVersion(envelope[0]));
+    }
+
+    let hybrid = match envelope[1] {
+        0x00 => false,
+        PING_ENVELOPE_FLAG_HYBRID => true,
+        _ => return Err(PingEnvelopeError::Malformed),
+    };
+
+    let header_len = 2 + if hybrid { HybridCiphertext::size() } else { 32 };
+    if envelope.len() < header_len + PING_ENVELOPE_NONCE_LEN + 16 {
+        return Err(PingEnvelopeError::Malformed);
+    }
+    let header = &envelope[..header_len];
+    let key_share = &envelope[2..header_len];
+    let nonce = &envelope[header_len..header_len + PING_ENVELOPE_NONCE_LEN];
+    let ciphertext = &envelope[header_len + PING_ENVELOPE_NONCE_LEN..];
+
+    let key = if hybrid {
+        let kem = our_kem.ok_or(PingEnvelopeError::MissingKemKeypair)?;
+        let kem_ciphertext = HybridCiphertext::from_bytes(key_share)
+            .map_err(|_| PingEnvelopeError::Malformed)?;
+        let secret = hybrid_decapsulate(&kem.x25519_secret, &kem.kyber_secret, &kem_ciphertext)
+            .map(Zeroizing::new)
+            .map_err(|_| PingEnvelopeError::KeyAgreementFailed)?;
+        derive_ping_envelope_key(header, &*secret)?
+    } else {
+        let shared = our_x25519_private
+            .diffie_hellman(key_share)
+            .map_err(|_| PingEnvelopeError::KeyAgreementFailed)?;
+        derive_ping_envelope_key(header, &*shared)?
+    };
+
+    let cipher = XChaCha20Poly1305::new_from_slice(&*key)
+        .map_err(|_| PingEnvelopeError::DecryptionFailed)?;
+
+    let ping_bytes = cipher
+        .decrypt(XNonce::from_slice(nonce), Payload { msg: ciphertext, aad: header })
+        .map_err(|_| PingEnvelopeError::DecryptionFailed)?;
+
+    verify_incoming_ping(&ping_bytes, our_x25519_private)
+}
+
+/// Decrypt a legacy PING: [sender X25519 pubkey:32][encrypt_message(token, static ECDH)]
+///
+/// Legacy PINGs are not forward secret; this is kept for peers on older builds.
+pub fn decrypt_legacy_ping(
+    wire: &[u8],
+    our_x25519_private: &X25519Secret,
+) -> Result<PingToken, PingEnvelopeError> {
+    if wire.len() < 32 {
+        return Err(PingEnvelopeError::Malformed);
+    }
+
+    let shared_secret = our_x25519_private
+        .diffie_hellman(&wire[..32])
+        .map_err(|_| PingEnvelopeError::KeyAgreementFailed)?;
+    let decrypted = decrypt_message(&wire[32..], shared_secret.as_slice());
+    let ping_bytes = decrypted.map_err(|_| PingEnvelopeError::DecryptionFailed)?;
+
+    verify_incoming_ping(&ping_bytes, our_x25519_private)
+}
+
+/// Decrypt an incoming PING in either the envelope or the legacy format
+///
+/// A legacy PING starts with the sender's X25519 key, whose first byte can equal the
+/// envelope version, so a failed envelope decryption falls back to the legacy format.
+///
+/// # Arguments
+/// * `wire` - PING bytes after the MSG_TYPE byte
+/// * `our_x25519_private` - Our static X25519 private key
+/// * `our_kem` - Our hybrid KEM keypair, if available
+pub fn decrypt_incoming_ping(
+    wire: &[u8],
+    our_x25519_private: &X25519Secret,
+    our_kem: Option<&HybridKEMKeypair>,
+) -> Result<PingToken, PingEnvelopeError> {
+    if wire.first() != Some(&PING_ENVELOPE_VERSION) {
+        return decrypt_legacy_ping(wire, our_x25519_private);
+    }
+
+    match decrypt_ping_envelope(wire, our_x25519_private, our_kem) {
+        Ok(ping) => Ok(ping),
+        Err(envelope_error) => decrypt_legacy_ping(wire, our_x25519_private).map_err(|_| envelope_error),
+    }
+}
+
+/// HKDF-SHA256 over the key agreement output, salted with the envelope header
+fn derive_ping_envelope_key(header: &[u8], key_material: &[u8]) -> Result<Zeroizing<[u8; 32]>, PingEnvelopeError> {
+    let hkdf = Hkdf::<Sha256>::new(Some(header), key_material);
+    let mut key = Zeroizing::new([0u8; 32]);
+    hkdf.expand(PING_ENVELOPE_KDF_INFO, &mut *key)
+        .map_err(|_| PingEnvelopeError::KeyAgreementFailed)?;
+    Ok(key)
+}
+
+/// Parse a decrypted PingToken and check its signature and recipient
+fn verify_incoming_ping(ping_bytes: &[u8], our_x25519_private: &X25519Secret) -> Result<PingToken, PingEnvelopeError> {
+    let ping = PingToken::from_bytes(ping_bytes).map_err(|_| PingEnvelopeError::InvalidToken)?;
+
+    if !ping.verify().map_err(|_| PingEnvelopeError::InvalidSignature)? {
+        return Err(PingEnvelopeError::InvalidSignature);
+    }
+
+    if ping.recipient_x25519_pubkey != our_x25519_private.public_key() {
+        return Err(PingEnvelopeError::WrongRecipient);
+    }
+
+    Ok(ping)
+}
+
 impl PongToken {
     /// Create a new Pong token in response to a Ping
     pub fn new(
@@ -462,47 +723,49 @@ impl PongToken {
             timestamp,
             authenticated,
             signature: [0u8; 64],
+            pq_signature: None,
         };
 
-        // Sign the Pong
-        let signature = pong.sign(recipient_keypair)?;
+        // Sign the Pong (in the encoding the Ping's sender verifies)
+        let signature = pong.sign(recipient_keypair, &ping.sender_pubkey)?;
         pong.signature = signature.to_bytes();
+        attach_local_pq_signature(&mut pong, &recipient_keypair.verifying_key(), &ping.sender_pubkey)?;
 
         Ok(pong)
     }
 
-    /// Sign the Pong token
-    fn sign(&self, keypair: &SigningKey) -> Result<Signature, Box<dyn std::error::Error>> {
-        let message = self.serialize_for_signing();
+    /// Sign the Pong token for the Ping's sender
+    fn sign(&self, keypair: &SigningKey, peer: &[u8; 32]) -> Result<Signature, Box<dyn std::error::Error>> {
+        let message = self.signing_bytes_for(peer);
         Ok(keypair.sign(&message))
     }
 
-    /// Verify the Pong signature
+    /// Verify the Pong signature (and its ML-DSA half if the signer's ML-DSA key is registered)
     pub fn verify(&self, signer_pubkey: &VerifyingKey) -> Result<bool, Box<dyn std::error::Error>> {
-        let signature = Signature::from_bytes(&self.signature);
-        let message = self.serialize_for_signing();
-
-        Ok(signer_pubkey.verify(&message, &signature).is_ok())
+        Ok(self.verify_ed25519(signer_pubkey, &self.signature)
+            && self.verify_pq(signer_pubkey.as_bytes(), peer_ml_dsa_key(signer_pubkey.as_bytes()).as_deref()))
     }
 
     /// Serialize Pong for signing
     fn serialize_for_signing(&self) -> Vec<u8> {
-        let mut bytes = Vec::new();
-        bytes.extend_from_slice(&self.ping_nonce);
-        bytes.extend_from_slice(&self.pong_nonce);
-        bytes.extend_from_slice(&self.timestamp.to_le_bytes());
-        bytes.push(if self.authenticated { 1 } else { 0 });
-        bytes
+        CanonicalEncoder::new(<Self as HybridSignedToken>::PQ_CONTEXT)
+            .bytes(1, &self.ping_nonce)
+            .bytes(2, &self.pong_nonce)
+            .i64(3, self.timestamp)
+            .bool(4, self.authenticated)
+            .finish()
     }
 
     /// Serialize to bytes for network transmission
     pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-        Ok(bincode::serialize(self)?)
+        Ok(serialize_with_pq_trailer(self, self.pq_signature.as_ref())?)
     }
 
-    /// Deserialize from bytes
+    /// Deserialize from bytes (an ML-DSA trailer, if present, is kept for `verify_pq`)
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
-        Ok(bincode::deserialize(bytes)?)
+        let (mut token, pq_signature): (Self, _) = deserialize_with_pq_trailer(bytes)?;
+        token.pq_signature = pq_signature;
+        Ok(token)
     }
 }
 
@@ -517,10 +780,12 @@ impl DeliveryAck {
     /// item_id: ping_id, message_id, tap_nonce, or pong_nonce being acknowledged
     /// ack_type: "PING_ACK", "MESSAGE_ACK", "TAP_ACK", or "PONG_ACK"
     /// keypair: Ed25519 signing key of the party sending the ACK
+    /// recipient_pubkey: Ed25519 key of the party receiving it (selects the signing encoding)
     pub fn new(
         item_id: &str,
         ack_type: &str,
         keypair: &SigningKey,
+        recipient_pubkey: &VerifyingKey,
     ) -> Result<Self, Box<dyn std::error::Error>> {
 
         // Get current timestamp
@@ -534,49 +799,62 @@ impl DeliveryAck {
             ack_type: ack_type.to_string(),
             timestamp,
             signature: [0u8; 64],
+            pq_signature: None,
         };
 
-        // Sign the ACK
-        let signature = ack.sign(keypair)?;
+        // Sign the ACK (in the encoding the recipient verifies)
+        let signature = ack.sign(keypair, recipient_pubkey.as_bytes())?;
         ack.signature = signature.to_bytes();
+        attach_local_pq_signature(&mut ack, &keypair.verifying_key(), recipient_pubkey.as_bytes())?;
 
         Ok(ack)
     }
 
-    /// Sign the ACK token
-    fn sign(&self, keypair: &SigningKey) -> Result<Signature, Box<dyn std::error::Error>> {
-        let message = self.serialize_for_signing();
+    /// Sign the ACK token for its recipient
+    fn sign(&self, keypair: &SigningKey, peer: &[u8; 32]) -> Result<Signature, Box<dyn std::error::Error>> {
+        let message = self.signing_bytes_for(peer);
         Ok(keypair.sign(&message))
     }
 
-    /// Verify the ACK signature
+    /// Verify the ACK signature (and its ML-DSA half if the signer's ML-DSA key is registered)
     pub fn verify(&self, signer_pubkey: &VerifyingKey) -> Result<bool, Box<dyn std::error::Error>> {
-        let signature = Signature::from_bytes(&self.signature);
-        let message = self.serialize_for_signing();
-
-        Ok(signer_pubkey.verify(&message, &signature).is_ok())
+        Ok(self.verify_ed25519(signer_pubkey, &self.signature)
+            && self.verify_pq(signer_pubkey.as_bytes(), peer_ml_dsa_key(signer_pubkey.as_bytes()).as_deref()))
     }
 
     /// Serialize ACK for signing
     fn serialize_for_signing(&self) -> Vec<u8> {
-        let mut bytes = Vec::new();
-        bytes.extend_from_slice(self.item_id.as_bytes());
-        bytes.extend_from_slice(self.ack_type.as_bytes());
-        bytes.extend_from_slice(&self.timestamp.to_le_bytes());
-        bytes
+        CanonicalEncoder::new(<Self as HybridSignedToken>::PQ_CONTEXT)
+            .str(1, &self.item_id)
+            .str(2, &self.ack_type)
+            .i64(3, self.timestamp)
+            .finish()
     }
 
     /// Serialize to bytes for network transmission
     pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-        Ok(bincode::serialize(self)?)
+        Ok(serialize_with_pq_trailer(self, self.pq_signature.as_ref())?)
     }
 
-    /// Deserialize from bytes
+    /// Deserialize from bytes (an ML-DSA trailer, if present, is kept for `verify_pq`)
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
-        Ok(bincode::deserialize(bytes)?)
+        let (mut token, pq_signature): (Self, _) = deserialize_with_pq_trailer(bytes)?;
+        token.pq_signature = pq_signature;
+        Ok(token)
     }
 }
 
+impl_hybrid_signed_token!(PingToken, b"SecureLegion-PingToken", legacy(t => [
+    t.sender_pubkey, t.recipient_pubkey, t.sender_x25519_pubkey, t.recipient_x25519_pubkey, t.nonce,
+    t.timestamp.to_le_bytes(),
+]));
+impl_hybrid_signed_token!(PongToken, b"SecureLegion-PongToken", legacy(t => [
+    t.ping_nonce, t.pong_nonce, t.timestamp.to_le_bytes(), [t.authenticated as u8],
+]));
+impl_hybrid_signed_token!(DeliveryAck, b"SecureLegion-DeliveryAck", legacy(t => [
+    t.item_id.as_bytes(), t.ack_type.as_bytes(), t.timestamp.to_le_bytes(),
+]));
+
 impl PingPongManager {
     /// Create a new PingPongManager with a keypair and Tor manager
     pub fn new(keypair: SigningKey, tor_manager: TorManager) -> Self {
@@ -763,14 +1041,25 @@ impl PingPongManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::crypto::key_exchange::generate_static_keypair;
+    use rand::rngs::OsRng;
+
+    fn test_x25519_keys() -> ([u8; 32], [u8; 32]) {
+        ([7u8; 32], [9u8; 32])
+    }
 
     #[test]
     fn test_ping_token_creation() {
-        let mut csprng = OsRng{};
-        let sender_keypair = Keypair::generate(&mut csprng);
-        let recipient_keypair = Keypair::generate(&mut csprng);
+        let sender_keypair = SigningKey::generate(&mut OsRng);
+        let recipient_keypair = SigningKey::generate(&mut OsRng);
+        let (sender_x25519, recipient_x25519) = test_x25519_keys();
 
-        let ping = PingToken::new(&sender_keypair, &recipient_keypair.public).unwrap();
+        let ping = PingToken::new(
+            &sender_keypair,
+            &recipient_keypair.verifying_key(),
+            &sender_x25519,
+            &recipient_x25519,
+        ).unwrap();
 
         // Verify signature
         assert!(ping.verify().unwrap());
@@ -778,30 +1067,210 @@ mod tests {
 
     #[test]
     fn test_pong_token_creation() {
-        let mut csprng = OsRng{};
-        let sender_keypair = Keypair::generate(&mut csprng);
-        let recipient_keypair = Keypair::generate(&mut csprng);
-
-        let ping = PingToken::new(&sender_keypair, &recipient_keypair.public).unwrap();
+        let sender_keypair = SigningKey::generate(&mut OsRng);
+        let recipient_keypair = SigningKey::generate(&mut OsRng);
+        let (sender_x25519, recipient_x25519) = test_x25519_keys();
+
+        let ping = PingToken::new(
+            &sender_keypair,
+            &recipient_keypair.verifying_key(),
+            &sender_x25519,
+            &recipient_x25519,
+        ).unwrap();
         let pong = PongToken::new(&ping, &recipient_keypair, true).unwrap();
 
         // Verify signature
-        assert!(pong.verify(&recipient_keypair.public).unwrap());
+        assert!(pong.verify(&recipient_keypair.verifying_key()).unwrap());
         assert_eq!(pong.ping_nonce, ping.nonce);
         assert!(pong.authenticated);
     }
 
     #[test]
     fn test_ping_pong_serialization() {
-        let mut csprng = OsRng{};
-        let sender_keypair = Keypair::generate(&mut csprng);
-        let recipient_keypair = Keypair::generate(&mut csprng);
-
-        let ping = PingToken::new(&sender_keypair, &recipient_keypair.public).unwrap();
+        let sender_keypair = SigningKey::generate(&mut OsRng);
+        let recipient_keypair = SigningKey::generate(&mut OsRng);
+        let (sender_x25519, recipient_x25519) = test_x25519_keys();
+
+        let ping = PingToken::new(
+            &sender_keypair,
+            &recipient_keypair.verifying_key(),
+            &sender_x25519,
+            &recipient_x25519,
+        ).unwrap();
         let ping_bytes = ping.to_bytes().unwrap();
         let ping_deserialized = PingToken::from_bytes(&ping_bytes).unwrap();
 
         assert_eq!(ping.nonce, ping_deserialized.nonce);
         assert_eq!(ping.timestamp, ping_deserialized.timestamp);
     }
+
+    fn signed_ping_for(recipient_x25519: &[u8; 32]) -> PingToken {
+        let sender_keypair = SigningKey::generate(&mut OsRng);
+        let recipient_keypair = SigningKey::generate(&mut OsRng);
+        let (sender_x25519, _) = generate_static_keypair();
+        PingToken::new(
+            &sender_keypair,
+            &recipient_keypair.verifying_key(),
+            &sender_x25519,
+            recipient_x25519,
+        ).unwrap()
+    }
+
+    #[test]
+    fn test_ping_envelope_roundtrip_uses_fresh_ephemeral_keys() {
+        let recipient_private = X25519Secret::generate();
+        let recipient_public = recipient_private.public_key();
+        let ping = signed_ping_for(&recipient_public);
+        let ping_bytes = ping.to_bytes().unwrap();
+
+        let first = encrypt_ping_envelope(&ping_bytes, &recipient_public, None).unwrap();
+        let second = encrypt_ping_envelope(&ping_bytes, &recipient_public, None).unwrap();
+        assert_eq!(first[0], PING_ENVELOPE_VERSION);
+        assert_ne!(first[2..34], second[2..34]);
+
+        let decrypted = decrypt_incoming_ping(&first, &recipient_private, None).unwrap();
+        assert_eq!(decrypted.nonce, ping.nonce);
+
+        // Legacy static-ECDH PINGs still decrypt
+        let sender = X25519Secret::generate();
+        let shared = sender.diffie_hellman(&recipient_public).unwrap();
+        let mut legacy = sender.public_key().to_vec();
+        legacy.extend_from_slice(&crate::crypto::encryption::encrypt_message(&ping_bytes, shared.as_slice()).unwrap());
+        assert_eq!(decrypt_incoming_ping(&legacy, &recipient_private, None).unwrap().nonce, ping.nonce);
+    }
+
+    #[test]
+    fn test_ping_envelope_rejects_tampering_and_wrong_recipient() {
+        let recipient_private = X25519Secret::generate();
+        let recipient_public = recipient_private.public_key();
+        let ping_bytes = signed_ping_for(&recipient_public).to_bytes().unwrap();
+        let mut envelope = encrypt_ping_envelope(&ping_bytes, &recipient_public, None).unwrap();
+
+        let other_private = X25519Secret::generate();
+        assert!(decrypt_ping_envelope(&envelope, &other_private, None).is_err());
+
+        envelope[5] ^= 0x01;
+        assert!(decrypt_ping_envelope(&envelope, &recipient_private, None).is_err());
+
+        // Correctly encrypted to us, but signed for someone else's X25519 key
+        let (other_public, _) = generate_static_keypair();
+        let misaddressed = signed_ping_for(&other_public).to_bytes().unwrap();
+        let envelope = encrypt_ping_envelope(&misaddressed, &recipient_public, None).unwrap();
+        assert!(matches!(
+            decrypt_ping_envelope(&envelope, &recipient_private, None),
+            Err(PingEnvelopeError::WrongRecipient)
+        ));
+    }
+
+    #[test]
+    fn test_hybrid_ping_envelope_roundtrip() {
+        let recipient_private = X25519Secret::generate();
+        let recipient_public = recipient_private.public_key();
+        let kem = crate::crypto::generate_hybrid_keypair_from_seed(&[3u8; 32]).unwrap();
+        let kem_public = HybridKEMPublicKey {
+            x25519_public: kem.x25519_public,
+            kyber_public: kem.kyber_public,
+        };
+        let ping = signed_ping_for(&recipient_public);
+
+        let envelope = encrypt_ping_envelope(&ping.to_bytes().unwrap(), &recipient_public, Some(&kem_public)).unwrap();
+        assert_eq!(envelope[1], PING_ENVELOPE_FLAG_HYBRID);
+
+        assert!(matches!(
+            decrypt_ping_envelope(&envelope, &recipient_private, None),
+            Err(PingEnvelopeError::MissingKemKeypair)
+        ));
+        let decrypted = decrypt_ping_envelope(&envelope, &recipient_private, Some(&kem)).unwrap();
+        assert_eq!(decrypted.nonce, ping.nonce);
+    }
+
+    #[test]
+    fn test_hybrid_signed_ping_keeps_legacy_wire_compatibility() {
+        use crate::crypto::pqc::{HybridSigningKeypair, SIGNATURE_VERSION_HYBRID};
+
+        let seed = [5u8; 64];
+        let signer = HybridSigningKeypair::from_seed(&seed).unwrap();
+        let recipient_keypair = SigningKey::generate(&mut OsRng);
+        let (sender_x25519, recipient_x25519) = test_x25519_keys();
+        let mut ping = PingToken::new(
+            &signer.ed25519_signing_key(),
+            &recipient_keypair.verifying_key(),
+            &sender_x25519,
+            &recipient_x25519,
+        ).unwrap();
+        let legacy_bytes = ping.to_bytes().unwrap();
+        ping.sign_pq(&signer, &recipient_keypair.verifying_key().to_bytes()).unwrap();
+        let ml_dsa_public = signer.public_key().ml_dsa;
+
+        // Trailer survives the round trip and both halves verify
+        let hybrid_bytes = ping.to_bytes().unwrap();
+        let parsed = PingToken::from_bytes(&hybrid_bytes).unwrap();
+        assert_eq!(parsed.signature_version(), SIGNATURE_VERSION_HYBRID);
+        assert!(parsed.verify().unwrap());
+        assert!(parsed.verify_pq(&signer.ed25519_public, Some(&ml_dsa_public)));
+
+        // Old parsers ignore the trailer
+        let old_parse: PingToken = bincode::deserialize(&hybrid_bytes).unwrap();
+        assert!(old_parse.verify().unwrap());
+
+        // Stripped trailer: fine for Ed25519-only peers, rejected once the ML-DSA key is known
+        let stripped = PingToken::from_bytes(&legacy_bytes).unwrap();
+        assert!(stripped.pq_signature.is_none());
+        assert!(stripped.verify_pq(&signer.ed25519_public, None));
+        assert!(!stripped.verify_pq(&signer.ed25519_public, Some(&ml_dsa_public)));
+    }
+
+    #[test]
+    fn test_signing_encoding_follows_negotiated_capability() {
+        use crate::crypto::pqc::hybrid_signature::register_peer_canonical_signing;
+
+        let alice = SigningKey::generate(&mut OsRng);
+        let bob = SigningKey::generate(&mut OsRng);
+        let (alice_x25519, bob_x25519) = test_x25519_keys();
+        let legacy_verifies = |ping: &PingToken| {
+            let legacy = ping.legacy_signing_bytes().unwrap();
+            alice.verifying_key().verify_strict(&legacy, &Signature::from_bytes(&ping.signature)).is_ok()
+        };
+
+        // Bob has not negotiated canonical signing: Alice signs the legacy encoding he verifies
+        let ping = PingToken::new(&alice, &bob.verifying_key(), &alice_x25519, &bob_x25519).unwrap();
+        assert!(legacy_verifies(&ping));
+        assert!(ping.verify().unwrap());
+
+        // Once both sides negotiated it, Alice signs canonically...
+        register_peer_canonical_signing(bob.verifying_key().to_bytes());
+        let ping = PingToken::new(&alice, &bob.verifying_key(), &alice_x25519, &bob_x25519).unwrap();
+        assert!(!legacy_verifies(&ping));
+        assert!(ping.verify().unwrap());
+
+        // ...and legacy-signed tokens from Bob no longer verify (no downgrade)
+        let legacy_ack = DeliveryAck::new("item", DeliveryAck::ACK_TYPE_PING, &bob, &alice.verifying_key()).unwrap();
+        assert!(!legacy_ack.verify(&bob.verifying_key()).unwrap());
+    }
+
+    #[test]
+    fn test_installed_keypair_signs_and_registered_key_is_required() {
+        use crate::crypto::pqc::hybrid_signature::{install_local_keypair, register_peer_ml_dsa_key};
+        use crate::crypto::pqc::{HybridSigningKeypair, ML_DSA_65_PUBLIC_KEY_BYTES};
+
+        let signer = HybridSigningKeypair::from_seed(&[0x5a; 64]).unwrap();
+        let signing_key = signer.ed25519_signing_key();
+        let ml_dsa_public = install_local_keypair(signer.clone());
+
+        // Tokens signed by the installed identity get the trailer, others don't
+        let recipient = SigningKey::generate(&mut OsRng).verifying_key();
+        let ack = DeliveryAck::new("item", DeliveryAck::ACK_TYPE_MESSAGE, &signing_key, &recipient).unwrap();
+        assert!(ack.pq_signature.is_some());
+        let other = DeliveryAck::new("item", DeliveryAck::ACK_TYPE_MESSAGE, &SigningKey::generate(&mut OsRng), &recipient).unwrap();
+        assert!(other.pq_signature.is_none());
+
+        // Once the signer's ML-DSA key is registered, stripping the trailer fails verification
+        register_peer_ml_dsa_key(signer.ed25519_public, ml_dsa_public).unwrap();
+        assert!(register_peer_ml_dsa_key(signer.ed25519_public, vec![0u8; ML_DSA_65_PUBLIC_KEY_BYTES]).is_err());
+        let parsed = DeliveryAck::from_bytes(&ack.to_bytes().unwrap()).unwrap();
+        assert!(parsed.verify(&signing_key.verifying_key()).unwrap());
+        let mut stripped = parsed.clone();
+        stripped.pq_signature = None;
+        assert!(!stripped.verify(&signing_key.verifying_key()).unwrap());
+    }
 }
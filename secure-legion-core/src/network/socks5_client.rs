@@ -68,6 +68,37 @@ impl Socks5Client {
         Ok(response)
     }
 
+    /// Perform HTTP GET request through SOCKS5 and return the raw body (binary-safe)
+    ///
+    /// # Returns
+    /// (status_code, body)
+    pub fn http_get_bytes(&self, url: &str) -> Result<(u16, Vec<u8>)> {
+        let (host, port, path) = parse_url(url)?;
+        let mut stream = self.connect_socks5(&host, port)?;
+
+        let request = format!(
+            "GET {} HTTP/1.1\r\n\
+             Host: {}\r\n\
+             User-Agent: SecureLegion/2.0\r\n\
+             Accept: */*\r\n\
+             Connection: close\r\n\
+             \r\n",
+            path, host
+        );
+
+        stream.write_all(request.as_bytes())?;
+        stream.flush()?;
+
+        let response = read_http_response_bytes(&mut stream)?;
+        let header_end = response
+            .windows(4)
+            .position(|w| w == b"\r\n\r\n")
+            .ok_or("Malformed HTTP response")?;
+        let status = extract_status_code(&String::from_utf8_lossy(&response[..header_end]))
+            .ok_or("Missing HTTP status")?;
+        Ok((status, response[header_end + 4..].to_vec()))
+    }
+
     /// Perform HTTP POST request through SOCKS5
     pub fn http_post(&self, url: &str, body: &str, content_type: &str) -> Result<String> {
         let (host, port, path) = parse_url(url)?;
@@ -246,6 +277,13 @@ fn parse_url(url: &str) -> Result<(String, u16, String)> {
 
 /// Read HTTP response from stream
 fn read_http_response(stream: &mut TcpStream) -> Result<String> {
+    let buffer = read_http_response_bytes(stream)?;
+    let response = String::from_utf8_lossy(&buffer).to_string();
+    Ok(response)
+}
+
+/// Read raw HTTP response bytes from stream
+fn read_http_response_bytes(stream: &mut TcpStream) -> Result<Vec<u8>> {
     let mut buffer = Vec::new();
     let mut chunk = [0u8; 4096];
 
@@ -263,8 +301,7 @@ fn read_http_response(stream: &mut TcpStream) -> Result<String> {
         }
     }
 
-    let response = String::from_utf8_lossy(&buffer).to_string();
-    Ok(response)
+    Ok(buffer)
 }
 
 /// Extract body from HTTP response
@@ -172,6 +172,8 @@ pub const MSG_TYPE_PAYMENT_REQUEST: u8 = 0x0A;
 pub const MSG_TYPE_PAYMENT_SENT: u8 = 0x0B;
 pub const MSG_TYPE_PAYMENT_ACCEPTED: u8 = 0x0C;
 pub const MSG_TYPE_CALL_SIGNALING: u8 = 0x0D;  // Voice call signaling (OFFER/ANSWER/REJECT/END/BUSY)
+pub const MSG_TYPE_SESSION_RESET: u8 = 0x0E;  // Session healing (RESET_INIT/RESET_ACK, see crypto::session_reset)
+pub const MSG_TYPE_RECOVERY_SHARE: u8 = 0x0F;  // Social recovery (DEPOSIT/REQUEST/RELEASE, see crypto::social_recovery)
 
 /// Structure representing a pending connection waiting for Pong response
 pub struct PendingConnection {
@@ -192,6 +194,16 @@ pub static CONNECTION_ID_COUNTER: Lazy<Arc<StdMutex<u64>>> =
 /// Initialized from JNI via startFriendRequestListener()
 pub static FRIEND_REQUEST_TX: once_cell::sync::OnceCell<Arc<StdMutex<tokio::sync::mpsc::UnboundedSender<Vec<u8>>>>> = once_cell::sync::OnceCell::new();
 
+/// Global SESSION_RESET channel sender
+/// Re-key handshakes must not be mistaken for chain messages by the MESSAGE handler
+/// Initialized from JNI via startSessionResetListener()
+pub static SESSION_RESET_TX: once_cell::sync::OnceCell<Arc<StdMutex<tokio::sync::mpsc::UnboundedSender<Vec<u8>>>>> = once_cell::sync::OnceCell::new();
+
+/// Global RECOVERY_SHARE channel sender
+/// Social recovery deposits/requests/releases are not chain messages
+/// Initialized from JNI via startRecoveryListener()
+pub static RECOVERY_TX: once_cell::sync::OnceCell<Arc<StdMutex<tokio::sync::mpsc::UnboundedSender<Vec<u8>>>>> = once_cell::sync::OnceCell::new();
+
 /// Global channel for MESSAGE types (TEXT/VOICE/IMAGE/PAYMENT)
 /// Separate from PING channel to enable direct routing without trial decryption
 /// Initialized when listener starts
@@ -922,6 +934,35 @@ impl TorManager {
                     log::warn!("MESSAGE channel not initialized - dropping message");
                 }
             }
+            MSG_TYPE_SESSION_RESET => {
+                log::info!("→ Routing to SESSION_RESET handler (separate channel)");
+                // Payload: [sender X25519:32][sender Ed25519:32][kind][body]; no reply on this connection
+                // The X25519 prefix added by sendMessageBlob is not used to identify resets
+                if data.len() <= 32 + 32 {
+                    log::warn!("Session reset too short ({} bytes) - dropping", data.len());
+                } else if let Some(reset_tx) = SESSION_RESET_TX.get() {
+                    let tx_lock = reset_tx.lock().unwrap();
+                    if let Err(e) = tx_lock.send(data[32..].to_vec()) {
+                        log::error!("Failed to send session reset to channel: {}", e);
+                    }
+                } else {
+                    log::warn!("Session reset channel not initialized - dropping message");
+                }
+            }
+            MSG_TYPE_RECOVERY_SHARE => {
+                log::info!("→ Routing to RECOVERY handler (separate channel)");
+                // Payload: [sender X25519:32][kind][body]; no reply on this connection
+                if data.len() <= 32 {
+                    log::warn!("Recovery message too short ({} bytes) - dropping", data.len());
+                } else if let Some(recovery_tx) = RECOVERY_TX.get() {
+                    let tx_lock = recovery_tx.lock().unwrap();
+                    if let Err(e) = tx_lock.send(data) {
+                        log::error!("Failed to send recovery message to channel: {}", e);
+                    }
+                } else {
+                    log::warn!("Recovery channel not initialized - dropping message");
+                }
+            }
             MSG_TYPE_CALL_SIGNALING => {
                 log::info!("→ Routing to VOICE handler (dedicated channel for call signaling)");
 
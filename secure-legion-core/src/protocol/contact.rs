@@ -1,4 +1,12 @@
 use serde::{Deserialize, Serialize};
+use crate::crypto::canonical::CanonicalEncoder;
+use crate::crypto::ratchet::LOCAL_CAPABILITIES;
+use crate::crypto::pqc::hybrid_signature::{
+    deserialize_with_pq_trailer, impl_hybrid_signed_token, serialize_with_pq_trailer, HybridSignedToken, PqSignature,
+};
+
+/// JSON key carrying the ML-DSA half of the card signature
+const PQ_SIGNATURE_JSON_KEY: &str = "pq_signature";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContactCard {
@@ -9,6 +17,12 @@ pub struct ContactCard {
     pub relay_preferences: RelayPreferences,
     pub timestamp: i64,
     pub signature: Vec<u8>,
+    /// Protocol capability flags (`crypto::ratchet::CAPABILITY_*`); 0 on cards from older builds
+    #[serde(default)]
+    pub capabilities: u8,
+    /// Optional ML-DSA-65 half of a hybrid signature (bincode trailer / extra JSON key)
+    #[serde(skip)]
+    pub pq_signature: Option<PqSignature>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,38 +51,58 @@ impl ContactCard {
             },
             timestamp: Utc::now().timestamp(),
             signature: Vec::new(),
+            capabilities: LOCAL_CAPABILITIES,
+            pq_signature: None,
         }
     }
 
     pub fn serialize(&self) -> Result<Vec<u8>, bincode::Error> {
-        bincode::serialize(self)
+        serialize_with_pq_trailer(self, self.pq_signature.as_ref())
     }
 
     pub fn deserialize(data: &[u8]) -> Result<Self, bincode::Error> {
-        bincode::deserialize(data)
+        let (mut card, pq_signature): (Self, _) = deserialize_with_pq_trailer(data)?;
+        card.pq_signature = pq_signature;
+        Ok(card)
     }
 
     pub fn to_json(&self) -> Result<String, serde_json::Error> {
-        serde_json::to_string(self)
+        let mut value = serde_json::to_value(self)?;
+        if let (Some(pq), serde_json::Value::Object(map)) = (&self.pq_signature, &mut value) {
+            map.insert(PQ_SIGNATURE_JSON_KEY.to_string(), serde_json::to_value(pq)?);
+        }
+        serde_json::to_string(&value)
     }
 
     pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
-        serde_json::from_str(json)
+        let mut value: serde_json::Value = serde_json::from_str(json)?;
+        let pq_signature = match value.as_object_mut().and_then(|map| map.remove(PQ_SIGNATURE_JSON_KEY)) {
+            Some(pq) => Some(serde_json::from_value(pq)?),
+            None => None,
+        };
+        let mut card: Self = serde_json::from_value(value)?;
+        card.pq_signature = pq_signature;
+        Ok(card)
     }
 
+    /// Canonical signing bytes (see `crypto::canonical`)
     pub fn serialize_for_signing(&self) -> Vec<u8> {
-        let mut data = Vec::new();
-        data.extend_from_slice(&self.public_key);
-        data.extend_from_slice(self.solana_address.as_bytes());
-        data.extend_from_slice(self.handle.as_bytes());
-        if let Some(ref onion) = self.onion_address {
-            data.extend_from_slice(onion.as_bytes());
-        }
-        data.extend_from_slice(&self.timestamp.to_le_bytes());
-        data
+        CanonicalEncoder::new(<Self as HybridSignedToken>::PQ_CONTEXT)
+            .bytes(1, &self.public_key)
+            .str(2, &self.solana_address)
+            .str(3, &self.handle)
+            .opt_str(4, self.onion_address.as_deref())
+            .i64(5, self.timestamp)
+            .bytes(6, &[self.capabilities])
+            .finish()
     }
 }
 
+impl_hybrid_signed_token!(ContactCard, b"SecureLegion-ContactCard", legacy(t => [
+    t.public_key, t.solana_address.as_bytes(), t.handle.as_bytes(),
+    t.onion_address.as_deref().unwrap_or_default().as_bytes(), t.timestamp.to_le_bytes(),
+]));
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -102,5 +136,32 @@ mod tests {
         let deserialized = ContactCard::from_json(&json).unwrap();
 
         assert_eq!(card.handle, deserialized.handle);
+        assert_eq!(deserialized.capabilities, LOCAL_CAPABILITIES);
+
+        // Cards from builds without capabilities negotiate nothing
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value.as_object_mut().unwrap().remove("capabilities");
+        assert_eq!(ContactCard::from_json(&value.to_string()).unwrap().capabilities, 0);
+    }
+
+    #[test]
+    fn test_canonical_signing_bytes() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        // Shifting a byte between handle and onion address no longer yields the same signed bytes
+        let a = ContactCard::new(vec![1; 32], "Sol".to_string(), "ab".to_string(), Some("c.onion".to_string()));
+        let mut b = a.clone();
+        b.handle = "abc".to_string();
+        b.onion_address = Some(".onion".to_string());
+        assert_eq!(a.legacy_signing_bytes().unwrap(), b.legacy_signing_bytes().unwrap());
+        assert_ne!(a.serialize_for_signing(), b.serialize_for_signing());
+
+        // Cards signed over the legacy encoding still verify during the transition
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+        let legacy_signature = signing_key.sign(&a.legacy_signing_bytes().unwrap()).to_bytes();
+        let canonical_signature = signing_key.sign(&a.serialize_for_signing()).to_bytes();
+        assert!(a.verify_ed25519(&signing_key.verifying_key(), &canonical_signature));
+        assert!(a.verify_ed25519(&signing_key.verifying_key(), &legacy_signature));
+        assert!(!b.verify_ed25519(&signing_key.verifying_key(), &canonical_signature));
     }
 }
@@ -0,0 +1,392 @@
+//! Trust-On-First-Use Key Registry
+//! Records the first identity keys seen for each contact and detects when they change
+//!
+//! The first Ed25519/X25519 pair presented for a contact is pinned. Later traffic with the
+//! same keys is trusted; different keys produce a `KeyChangeEvent` and are either blocked or
+//! flagged depending on the policy (always blocked once the contact has been verified).
+//! Records persist through `KeyRegistryStorage` so the registry runs without JNI.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum KeyRegistryError {
+    #[error("Storage error: {0}")]
+    Storage(String),
+    #[error("Unknown contact: {0}")]
+    UnknownContact(String),
+    #[error("No pending key change for contact: {0}")]
+    NoPendingKeyChange(String),
+    #[error("Contact has an unresolved key change: {0}")]
+    UnresolvedKeyChange(String),
+}
+
+pub type Result<T> = std::result::Result<T, KeyRegistryError>;
+
+/// Identity keys pinned for a contact
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContactKeys {
+    pub ed25519: [u8; 32],
+    pub x25519: [u8; 32],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VerificationStatus {
+    /// Pinned on first use, never compared out of band
+    Unverified,
+    /// Safety number confirmed by the user
+    Verified,
+    /// Different keys were presented and the user has not accepted them yet
+    KeyChanged,
+}
+
+/// What happens to traffic signed with an unexpected key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyChangePolicy {
+    /// Reject until the user accepts the new keys
+    #[default]
+    Block,
+    /// Deliver, but report the change (verified contacts are still blocked)
+    Flag,
+}
+
+/// Everything known about one contact's keys
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustRecord {
+    pub contact_id: String,
+    pub keys: ContactKeys,
+    pub status: VerificationStatus,
+    /// Unix timestamp when the keys were pinned
+    pub first_seen: i64,
+    /// Keys presented since the last change, awaiting `accept_key_change`
+    pub pending_keys: Option<ContactKeys>,
+    /// Status was Verified when the pending key change was detected
+    pub was_verified: bool,
+}
+
+/// Reported when a contact presents keys that differ from the pinned ones
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyChangeEvent {
+    pub contact_id: String,
+    pub previous_keys: ContactKeys,
+    pub presented_keys: ContactKeys,
+    pub ed25519_changed: bool,
+    pub x25519_changed: bool,
+    /// The contact had been verified before the change
+    pub was_verified: bool,
+    pub detected_at: i64,
+}
+
+/// Result of checking presented keys against the registry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyCheck {
+    /// No record yet; the keys are now pinned
+    FirstSeen,
+    /// Keys match the pinned ones
+    Trusted { verified: bool },
+    /// Keys changed; traffic may be delivered but the app must surface the event
+    Flagged(KeyChangeEvent),
+    /// Keys changed; traffic must be dropped
+    Blocked(KeyChangeEvent),
+}
+
+impl KeyCheck {
+    pub fn is_allowed(&self) -> bool {
+        !matches!(self, KeyCheck::Blocked(_))
+    }
+
+    pub fn key_change(&self) -> Option<&KeyChangeEvent> {
+        match self {
+            KeyCheck::Flagged(event) | KeyCheck::Blocked(event) => Some(event),
+            _ => None,
+        }
+    }
+}
+
+/// Persistence backend for trust records
+pub trait KeyRegistryStorage: Send {
+    fn load_all(&self) -> Result<Vec<TrustRecord>>;
+
+    fn save(&mut self, record: &TrustRecord) -> Result<()>;
+
+    fn delete(&mut self, contact_id: &str) -> Result<()>;
+}
+
+/// Non-persistent storage (tests, ephemeral sessions)
+#[derive(Debug, Default)]
+pub struct MemoryKeyStorage {
+    records: HashMap<String, TrustRecord>,
+}
+
+impl KeyRegistryStorage for MemoryKeyStorage {
+    fn load_all(&self) -> Result<Vec<TrustRecord>> {
+        Ok(self.records.values().cloned().collect())
+    }
+
+    fn save(&mut self, record: &TrustRecord) -> Result<()> {
+        self.records.insert(record.contact_id.clone(), record.clone());
+        Ok(())
+    }
+
+    fn delete(&mut self, contact_id: &str) -> Result<()> {
+        self.records.remove(contact_id);
+        Ok(())
+    }
+}
+
+/// Single-file storage; every write replaces the file atomically (write temp, then rename)
+#[derive(Debug, Clone)]
+pub struct FileKeyStorage {
+    path: PathBuf,
+}
+
+impl FileKeyStorage {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    fn write_all(&self, records: &[TrustRecord]) -> Result<()> {
+        let bytes = bincode::serialize(records).map_err(|e| KeyRegistryError::Storage(e.to_string()))?;
+        let tmp_path = self.path.with_extension("tmp");
+        std::fs::write(&tmp_path, bytes).map_err(|e| KeyRegistryError::Storage(e.to_string()))?;
+        std::fs::rename(&tmp_path, &self.path).map_err(|e| KeyRegistryError::Storage(e.to_string()))
+    }
+}
+
+impl KeyRegistryStorage for FileKeyStorage {
+    fn load_all(&self) -> Result<Vec<TrustRecord>> {
+        match std::fs::read(&self.path) {
+            Ok(bytes) => bincode::deserialize(&bytes).map_err(|e| KeyRegistryError::Storage(e.to_string())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(KeyRegistryError::Storage(e.to_string())),
+        }
+    }
+
+    fn save(&mut self, record: &TrustRecord) -> Result<()> {
+        let mut records = self.load_all()?;
+        records.retain(|r| r.contact_id != record.contact_id);
+        records.push(record.clone());
+        self.write_all(&records)
+    }
+
+    fn delete(&mut self, contact_id: &str) -> Result<()> {
+        let mut records = self.load_all()?;
+        records.retain(|r| r.contact_id != contact_id);
+        self.write_all(&records)
+    }
+}
+
+/// TOFU registry over a storage backend
+pub struct KeyRegistry<S: KeyRegistryStorage> {
+    storage: S,
+    records: HashMap<String, TrustRecord>,
+    policy: KeyChangePolicy,
+}
+
+impl<S: KeyRegistryStorage> KeyRegistry<S> {
+    /// Load all records from storage
+    pub fn open(storage: S, policy: KeyChangePolicy) -> Result<Self> {
+        let records = storage
+            .load_all()?
+            .into_iter()
+            .map(|record| (record.contact_id.clone(), record))
+            .collect();
+        Ok(Self { storage, records, policy })
+    }
+
+    pub fn policy(&self) -> KeyChangePolicy {
+        self.policy
+    }
+
+    pub fn record(&self, contact_id: &str) -> Option<&TrustRecord> {
+        self.records.get(contact_id)
+    }
+
+    /// Check the keys a contact presented, pinning them on first use
+    ///
+    /// # Arguments
+    /// * `contact_id` - App-level contact identifier
+    /// * `presented` - Keys the incoming traffic was signed/encrypted with
+    pub fn check(&mut self, contact_id: &str, presented: &ContactKeys) -> Result<KeyCheck> {
+        let now = unix_now();
+        let Some(record) = self.records.get(contact_id) else {
+            let record = TrustRecord {
+                contact_id: contact_id.to_string(),
+                keys: *presented,
+                status: VerificationStatus::Unverified,
+                first_seen: now,
+                pending_keys: None,
+                was_verified: false,
+            };
+            self.storage.save(&record)?;
+            self.records.insert(contact_id.to_string(), record);
+            return Ok(KeyCheck::FirstSeen);
+        };
+
+        if record.keys == *presented && record.status != VerificationStatus::KeyChanged {
+            return Ok(KeyCheck::Trusted { verified: record.status == VerificationStatus::Verified });
+        }
+
+        let was_verified = match record.status {
+            VerificationStatus::Verified => true,
+            VerificationStatus::KeyChanged => record.was_verified,
+            VerificationStatus::Unverified => false,
+        };
+        let event = KeyChangeEvent {
+            contact_id: contact_id.to_string(),
+            previous_keys: record.keys,
+            presented_keys: *presented,
+            ed25519_changed: record.keys.ed25519 != presented.ed25519,
+            x25519_changed: record.keys.x25519 != presented.x25519,
+            was_verified,
+            detected_at: now,
+        };
+
+        if record.keys != *presented {
+            let mut updated = record.clone();
+            updated.status = VerificationStatus::KeyChanged;
+            updated.pending_keys = Some(*presented);
+            updated.was_verified = was_verified;
+            self.storage.save(&updated)?;
+            self.records.insert(contact_id.to_string(), updated);
+        }
+
+        if self.policy == KeyChangePolicy::Block || was_verified {
+            log::warn!("Blocked traffic from {}: identity key changed", contact_id);
+            Ok(KeyCheck::Blocked(event))
+        } else {
+            log::warn!("Identity key changed for {}", contact_id);
+            Ok(KeyCheck::Flagged(event))
+        }
+    }
+
+    /// Pin the keys from the last key change (user accepted them)
+    pub fn accept_key_change(&mut self, contact_id: &str) -> Result<()> {
+        let record = self
+            .records
+            .get_mut(contact_id)
+            .ok_or_else(|| KeyRegistryError::UnknownContact(contact_id.to_string()))?;
+        let pending = record
+            .pending_keys
+            .ok_or_else(|| KeyRegistryError::NoPendingKeyChange(contact_id.to_string()))?;
+
+        let mut updated = record.clone();
+        updated.keys = pending;
+        updated.pending_keys = None;
+        updated.was_verified = false;
+        updated.status = VerificationStatus::Unverified;
+        updated.first_seen = unix_now();
+        self.storage.save(&updated)?;
+        *record = updated;
+        Ok(())
+    }
+
+    /// Mark the pinned keys as verified out of band (e.g. safety number comparison)
+    pub fn mark_verified(&mut self, contact_id: &str) -> Result<()> {
+        let record = self
+            .records
+            .get_mut(contact_id)
+            .ok_or_else(|| KeyRegistryError::UnknownContact(contact_id.to_string()))?;
+        if record.status == VerificationStatus::KeyChanged {
+            return Err(KeyRegistryError::UnresolvedKeyChange(contact_id.to_string()));
+        }
+
+        let mut updated = record.clone();
+        updated.status = VerificationStatus::Verified;
+        self.storage.save(&updated)?;
+        *record = updated;
+        Ok(())
+    }
+
+    /// Reinstate a record from a backup, replacing any existing one
+    pub fn restore(&mut self, record: TrustRecord) -> Result<()> {
+        self.storage.save(&record)?;
+        self.records.insert(record.contact_id.clone(), record);
+        Ok(())
+    }
+
+    /// Drop a contact's record (contact deleted)
+    pub fn forget(&mut self, contact_id: &str) -> Result<()> {
+        self.storage.delete(contact_id)?;
+        self.records.remove(contact_id);
+        Ok(())
+    }
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(tag: u8) -> ContactKeys {
+        ContactKeys { ed25519: [tag; 32], x25519: [tag.wrapping_add(100); 32] }
+    }
+
+    #[test]
+    fn test_first_use_pins_keys() {
+        let mut registry = KeyRegistry::open(MemoryKeyStorage::default(), KeyChangePolicy::Block).unwrap();
+
+        assert_eq!(registry.check("alice", &keys(1)).unwrap(), KeyCheck::FirstSeen);
+        assert_eq!(registry.check("alice", &keys(1)).unwrap(), KeyCheck::Trusted { verified: false });
+
+        registry.mark_verified("alice").unwrap();
+        assert_eq!(registry.check("alice", &keys(1)).unwrap(), KeyCheck::Trusted { verified: true });
+    }
+
+    #[test]
+    fn test_key_change_is_flagged_or_blocked() {
+        let mut registry = KeyRegistry::open(MemoryKeyStorage::default(), KeyChangePolicy::Flag).unwrap();
+        registry.check("alice", &keys(1)).unwrap();
+        registry.check("bob", &keys(2)).unwrap();
+        registry.mark_verified("bob").unwrap();
+
+        // Unverified contact: flagged under the Flag policy
+        let mut changed = keys(1);
+        changed.x25519[0] ^= 1;
+        let check = registry.check("alice", &changed).unwrap();
+        let KeyCheck::Flagged(event) = check else { panic!("expected Flagged, got {:?}", check) };
+        assert!(event.x25519_changed && !event.ed25519_changed);
+        assert_eq!(registry.record("alice").unwrap().status, VerificationStatus::KeyChanged);
+        // The old keys are no longer silently trusted either
+        assert!(registry.check("alice", &keys(1)).unwrap().key_change().is_some());
+        assert!(registry.mark_verified("alice").is_err());
+
+        registry.accept_key_change("alice").unwrap();
+        assert_eq!(registry.check("alice", &changed).unwrap(), KeyCheck::Trusted { verified: false });
+
+        // Verified contact: always blocked
+        let check = registry.check("bob", &keys(3)).unwrap();
+        assert!(!check.is_allowed());
+        assert!(check.key_change().unwrap().was_verified);
+    }
+
+    #[test]
+    fn test_file_storage_persists_records() {
+        let path = std::env::temp_dir().join(format!("key_registry_test_{}.bin", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        {
+            let mut registry = KeyRegistry::open(FileKeyStorage::new(&path), KeyChangePolicy::Block).unwrap();
+            registry.check("alice", &keys(1)).unwrap();
+            registry.check("bob", &keys(2)).unwrap();
+            registry.mark_verified("alice").unwrap();
+            registry.forget("bob").unwrap();
+        }
+
+        let mut registry = KeyRegistry::open(FileKeyStorage::new(&path), KeyChangePolicy::Block).unwrap();
+        assert_eq!(registry.record("alice").unwrap().status, VerificationStatus::Verified);
+        assert!(registry.record("bob").is_none());
+        assert!(matches!(registry.check("alice", &keys(4)).unwrap(), KeyCheck::Blocked(_)));
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
 use serde_big_array::BigArray;
+use crate::crypto::canonical::CanonicalEncoder;
+use crate::crypto::pqc::hybrid_signature::{
+    deserialize_with_pq_trailer, impl_hybrid_signed_token, serialize_with_pq_trailer, HybridSignedToken, PqSignature,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MessageType {
@@ -67,26 +71,32 @@ pub struct PingToken {
     pub timestamp: i64,
     #[serde(with = "BigArray")]
     pub signature: [u8; 64],
+    /// Optional ML-DSA-65 half of a hybrid signature (trailer after the bincode body)
+    #[serde(skip)]
+    pub pq_signature: Option<PqSignature>,
 }
 
 impl PingToken {
     pub fn serialize(&self) -> Result<Vec<u8>, bincode::Error> {
-        bincode::serialize(self)
+        serialize_with_pq_trailer(self, self.pq_signature.as_ref())
     }
 
     pub fn deserialize(data: &[u8]) -> Result<Self, bincode::Error> {
-        bincode::deserialize(data)
+        let (mut token, pq_signature): (Self, _) = deserialize_with_pq_trailer(data)?;
+        token.pq_signature = pq_signature;
+        Ok(token)
     }
 
+    /// Canonical signing bytes (see `crypto::canonical`)
     pub fn serialize_for_signing(&self) -> Vec<u8> {
-        let mut data = Vec::new();
-        data.extend_from_slice(&self.sender_pubkey);
-        data.extend_from_slice(&self.recipient_pubkey);
-        data.extend_from_slice(&self.sender_x25519_pubkey);
-        data.extend_from_slice(&self.recipient_x25519_pubkey);
-        data.extend_from_slice(&self.nonce);
-        data.extend_from_slice(&self.timestamp.to_le_bytes());
-        data
+        CanonicalEncoder::new(<Self as HybridSignedToken>::PQ_CONTEXT)
+            .bytes(1, &self.sender_pubkey)
+            .bytes(2, &self.recipient_pubkey)
+            .bytes(3, &self.sender_x25519_pubkey)
+            .bytes(4, &self.recipient_x25519_pubkey)
+            .bytes(5, &self.nonce)
+            .i64(6, self.timestamp)
+            .finish()
     }
 }
 
@@ -98,24 +108,30 @@ pub struct PongToken {
     pub authenticated: bool,
     #[serde(with = "BigArray")]
     pub signature: [u8; 64],
+    /// Optional ML-DSA-65 half of a hybrid signature (trailer after the bincode body)
+    #[serde(skip)]
+    pub pq_signature: Option<PqSignature>,
 }
 
 impl PongToken {
     pub fn serialize(&self) -> Result<Vec<u8>, bincode::Error> {
-        bincode::serialize(self)
+        serialize_with_pq_trailer(self, self.pq_signature.as_ref())
     }
 
     pub fn deserialize(data: &[u8]) -> Result<Self, bincode::Error> {
-        bincode::deserialize(data)
+        let (mut token, pq_signature): (Self, _) = deserialize_with_pq_trailer(data)?;
+        token.pq_signature = pq_signature;
+        Ok(token)
     }
 
+    /// Canonical signing bytes (see `crypto::canonical`)
     pub fn serialize_for_signing(&self) -> Vec<u8> {
-        let mut data = Vec::new();
-        data.extend_from_slice(&self.ping_nonce);
-        data.extend_from_slice(&self.pong_nonce);
-        data.extend_from_slice(&self.timestamp.to_le_bytes());
-        data.push(if self.authenticated { 1 } else { 0 });
-        data
+        CanonicalEncoder::new(<Self as HybridSignedToken>::PQ_CONTEXT)
+            .bytes(1, &self.ping_nonce)
+            .bytes(2, &self.pong_nonce)
+            .i64(3, self.timestamp)
+            .bool(4, self.authenticated)
+            .finish()
     }
 }
 
@@ -126,23 +142,29 @@ pub struct DeliveryConfirmationToken {
     pub timestamp: i64,
     #[serde(with = "BigArray")]
     pub signature: [u8; 64],          // Signature over message_id + timestamp
+    /// Optional ML-DSA-65 half of a hybrid signature (trailer after the bincode body)
+    #[serde(skip)]
+    pub pq_signature: Option<PqSignature>,
 }
 
 impl DeliveryConfirmationToken {
     pub fn serialize(&self) -> Result<Vec<u8>, bincode::Error> {
-        bincode::serialize(self)
+        serialize_with_pq_trailer(self, self.pq_signature.as_ref())
     }
 
     pub fn deserialize(data: &[u8]) -> Result<Self, bincode::Error> {
-        bincode::deserialize(data)
+        let (mut token, pq_signature): (Self, _) = deserialize_with_pq_trailer(data)?;
+        token.pq_signature = pq_signature;
+        Ok(token)
     }
 
+    /// Canonical signing bytes (see `crypto::canonical`)
     pub fn serialize_for_signing(&self) -> Vec<u8> {
-        let mut data = Vec::new();
-        data.extend_from_slice(self.message_id.as_bytes());
-        data.extend_from_slice(&self.recipient_pubkey);
-        data.extend_from_slice(&self.timestamp.to_le_bytes());
-        data
+        CanonicalEncoder::new(<Self as HybridSignedToken>::PQ_CONTEXT)
+            .str(1, &self.message_id)
+            .bytes(2, &self.recipient_pubkey)
+            .i64(3, self.timestamp)
+            .finish()
     }
 }
 
@@ -156,23 +178,29 @@ pub struct TapAckToken {
     pub timestamp: i64,
     #[serde(with = "BigArray")]
     pub signature: [u8; 64],          // Signature over tap_nonce + timestamp
+    /// Optional ML-DSA-65 half of a hybrid signature (trailer after the bincode body)
+    #[serde(skip)]
+    pub pq_signature: Option<PqSignature>,
 }
 
 impl TapAckToken {
     pub fn serialize(&self) -> Result<Vec<u8>, bincode::Error> {
-        bincode::serialize(self)
+        serialize_with_pq_trailer(self, self.pq_signature.as_ref())
     }
 
     pub fn deserialize(data: &[u8]) -> Result<Self, bincode::Error> {
-        bincode::deserialize(data)
+        let (mut token, pq_signature): (Self, _) = deserialize_with_pq_trailer(data)?;
+        token.pq_signature = pq_signature;
+        Ok(token)
     }
 
+    /// Canonical signing bytes (see `crypto::canonical`)
     pub fn serialize_for_signing(&self) -> Vec<u8> {
-        let mut data = Vec::new();
-        data.extend_from_slice(&self.tap_nonce);
-        data.extend_from_slice(&self.recipient_pubkey);
-        data.extend_from_slice(&self.timestamp.to_le_bytes());
-        data
+        CanonicalEncoder::new(<Self as HybridSignedToken>::PQ_CONTEXT)
+            .bytes(1, &self.tap_nonce)
+            .bytes(2, &self.recipient_pubkey)
+            .i64(3, self.timestamp)
+            .finish()
     }
 }
 
@@ -187,26 +215,49 @@ pub struct PongAckToken {
     pub timestamp: i64,
     #[serde(with = "BigArray")]
     pub signature: [u8; 64],          // Signature over pong_nonce + timestamp
+    /// Optional ML-DSA-65 half of a hybrid signature (trailer after the bincode body)
+    #[serde(skip)]
+    pub pq_signature: Option<PqSignature>,
 }
 
 impl PongAckToken {
     pub fn serialize(&self) -> Result<Vec<u8>, bincode::Error> {
-        bincode::serialize(self)
+        serialize_with_pq_trailer(self, self.pq_signature.as_ref())
     }
 
     pub fn deserialize(data: &[u8]) -> Result<Self, bincode::Error> {
-        bincode::deserialize(data)
+        let (mut token, pq_signature): (Self, _) = deserialize_with_pq_trailer(data)?;
+        token.pq_signature = pq_signature;
+        Ok(token)
     }
 
+    /// Canonical signing bytes (see `crypto::canonical`)
     pub fn serialize_for_signing(&self) -> Vec<u8> {
-        let mut data = Vec::new();
-        data.extend_from_slice(&self.pong_nonce);
-        data.extend_from_slice(&self.recipient_pubkey);
-        data.extend_from_slice(&self.timestamp.to_le_bytes());
-        data
+        CanonicalEncoder::new(<Self as HybridSignedToken>::PQ_CONTEXT)
+            .bytes(1, &self.pong_nonce)
+            .bytes(2, &self.recipient_pubkey)
+            .i64(3, self.timestamp)
+            .finish()
     }
 }
 
+impl_hybrid_signed_token!(PingToken, b"SecureLegion-PingToken", legacy(t => [
+    t.sender_pubkey, t.recipient_pubkey, t.sender_x25519_pubkey, t.recipient_x25519_pubkey, t.nonce,
+    t.timestamp.to_le_bytes(),
+]));
+impl_hybrid_signed_token!(PongToken, b"SecureLegion-PongToken", legacy(t => [
+    t.ping_nonce, t.pong_nonce, t.timestamp.to_le_bytes(), [t.authenticated as u8],
+]));
+impl_hybrid_signed_token!(DeliveryConfirmationToken, b"SecureLegion-DeliveryConfirmationToken", legacy(t => [
+    t.message_id.as_bytes(), t.recipient_pubkey, t.timestamp.to_le_bytes(),
+]));
+impl_hybrid_signed_token!(TapAckToken, b"SecureLegion-TapAckToken", legacy(t => [
+    t.tap_nonce, t.recipient_pubkey, t.timestamp.to_le_bytes(),
+]));
+impl_hybrid_signed_token!(PongAckToken, b"SecureLegion-PongAckToken", legacy(t => [
+    t.pong_nonce, t.recipient_pubkey, t.timestamp.to_le_bytes(),
+]));
+
 #[cfg(test)]
 mod tests {
     use super::*;
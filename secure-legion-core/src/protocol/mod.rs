@@ -1,7 +1,9 @@
 pub mod message;
 pub mod contact;
 pub mod security_mode;
+pub mod key_registry;
 
 pub use message::{Message, MessageType};
 pub use contact::ContactCard;
 pub use security_mode::SecurityMode;
+pub use key_registry::{KeyChangeEvent, KeyCheck, KeyRegistry, KeyRegistryStorage};